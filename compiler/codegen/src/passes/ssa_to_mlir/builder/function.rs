@@ -376,6 +376,7 @@ impl<'m> ModuleBuilder<'m> {
             }
             Opcode::Head => self.cir().build_head(loc, arg).base(),
             Opcode::Tail => self.cir().build_tail(loc, arg).base(),
+            Opcode::FunArity => self.cir().build_fun_arity(loc, arg).base(),
             Opcode::Neg => {
                 let neg1 = self.get_or_declare_builtin("erlang:-/1").unwrap();
                 let op = self.cir().build_call(loc, neg1, &[arg]).base();
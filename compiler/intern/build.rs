@@ -150,6 +150,9 @@ fn main() {
             next_id
         })
     });
+    // The interner is prefilled from `__SYMBOLS` in order, so the index of each symbol must be
+    // its id, even when the id of a symbol is fixed out of declaration order
+    symbols.sort_by_key(|symbol| symbol.id);
 
     generate_symbols_rs(symbols).unwrap();
 }
@@ -611,6 +611,23 @@ mod tests {
         assert_eq!(i.intern("foo").as_u32(), (i.names.len() - 1) as u32);
     }
 
+    #[test]
+    fn core_symbols_have_the_ids_fixed_by_the_runtime() {
+        let mut i = Interner::fresh();
+        let expected = [
+            (symbols::False, "false"),
+            (symbols::True, "true"),
+            (symbols::Ok, "ok"),
+            (symbols::Error, "error"),
+            (symbols::Undefined, "undefined"),
+        ];
+
+        for (id, (sym, s)) in expected.iter().enumerate() {
+            assert_eq!(sym.as_u32(), id as u32, "{}", s);
+            assert_eq!(i.intern(s), *sym);
+        }
+    }
+
     #[test]
     fn unquote_string() {
         let i = Ident::from_str("\"after\"");
@@ -278,6 +278,10 @@ MLIR_CAPI_EXPORTED MlirOperation mlirCirUnpackEnvOp(MlirOpBuilder builder,
                                                     MlirValue fun,
                                                     MlirAttribute index);
 
+MLIR_CAPI_EXPORTED MlirOperation mlirCirFunArityOp(MlirOpBuilder builder,
+                                                   MlirLocation location,
+                                                   MlirValue fun);
+
 MLIR_CAPI_EXPORTED MlirOperation mlirCirConsOp(MlirOpBuilder builder,
                                                MlirLocation location,
                                                MlirValue head, MlirValue value);
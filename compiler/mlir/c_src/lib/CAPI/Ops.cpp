@@ -254,6 +254,14 @@ MlirOperation mlirCirUnpackEnvOp(MlirOpBuilder bldr, MlirLocation location,
   return wrap(op);
 }
 
+MlirOperation mlirCirFunArityOp(MlirOpBuilder bldr, MlirLocation location,
+                                MlirValue fun) {
+  OpBuilder *builder = unwrap(bldr);
+  Operation *op =
+      builder->create<cir::FunArityOp>(unwrap(location), unwrap(fun));
+  return wrap(op);
+}
+
 MlirOperation mlirCirConsOp(MlirOpBuilder bldr, MlirLocation location,
                             MlirValue head, MlirValue tail) {
   OpBuilder *builder = unwrap(bldr);
@@ -16,6 +16,7 @@
 #include "mlir/Pass/PassRegistry.h"
 #include "mlir/Transforms/DialectConversion.h"
 #include "llvm/ADT/StringExtras.h"
+#include "llvm/ADT/StringMap.h"
 #include "llvm/ADT/TypeSwitch.h"
 #include "llvm/Support/SHA1.h"
 #include <algorithm>
@@ -830,8 +831,9 @@ protected:
   // linkonce_odr linkage, intended to be gathered together by the linker into
   // an array of cstrings from which the global atom table will be initialized.
   //
-  // A call to a special builtin that constructs an atom term from its value as
-  // a cstring is used to obtain the result value returned by this function.
+  // The address of the record is encoded as the result value returned by this
+  // function. Once the module is lowered, that address is replaced by a load
+  // from the module's atom table, see emitModuleAtomTable.
   Value createAtom(OpBuilder &builder, Location loc, StringRef name,
                    ModuleOp &module) const {
     if (name == "false")
@@ -2275,6 +2277,37 @@ struct UnpackEnvOpLowering
   }
 };
 
+//===---------===//
+// FunArityOp
+//===---------===//
+struct FunArityOpLowering : public ConvertCIROpToLLVMPattern<cir::FunArityOp> {
+  using ConvertCIROpToLLVMPattern<cir::FunArityOp>::ConvertCIROpToLLVMPattern;
+
+  LogicalResult
+  matchAndRewrite(cir::FunArityOp op, OpAdaptor adaptor,
+                  ConversionPatternRewriter &rewriter) const override {
+    auto loc = op.getLoc();
+    auto i32Ty = getI32Type();
+    auto termTy = getTermType();
+    auto module = op->getParentOfType<ModuleOp>();
+
+    // The arity stored in the closure header is that of the callee, which includes
+    // the closure argument when there is an environment, so the runtime adjusts it
+    Operation *callee = module.lookupSymbol("__firefly_builtin_fun_arity");
+    if (!callee) {
+      auto calleeType =
+          LLVM::LLVMFunctionType::get(i32Ty, ArrayRef<Type>{termTy});
+      insertFunctionDeclaration(rewriter, loc, module,
+                                "__firefly_builtin_fun_arity", calleeType);
+    }
+
+    rewriter.replaceOpWithNewOp<LLVM::CallOp>(op, TypeRange({i32Ty}),
+                                              "__firefly_builtin_fun_arity",
+                                              ValueRange({adaptor.fun()}));
+    return success();
+  }
+};
+
 //===---------===//
 // MakeFunOp
 //===---------===//
@@ -3248,6 +3281,157 @@ struct BinaryPushOpLowering
 
 } // namespace
 
+//===----------------------------------------------------------------------===//
+// Module Atom Table
+//
+// Once a module is lowered, the AtomData records of the literal atoms it
+// references are gathered in a table in the `__module_atoms` section, which
+// the runtime walks at startup to intern each atom and fill in the module's
+// mapping array. Literal atoms in functions are then materialized by loading
+// them from the mapping array, rather than by taking the address of their
+// record, so they are the interned atom even if the linker did not merge the
+// records of different modules.
+//
+// The table corresponds to ModuleAtoms in firefly_rt:
+//
+//   { len: usize, names: *const *const AtomData, atoms: *mut *const AtomData }
+//===----------------------------------------------------------------------===//
+static void emitModuleAtomTable(ModuleOp module,
+                                CIRTypeConverter &typeConverter) {
+  bool isMachO = typeConverter.isMachO();
+  StringRef atomsSection = isMachO ? "__DATA,__atoms" : "__atoms";
+
+  // Index the atom records referenced by the module, in the order they are
+  // defined in it
+  SmallVector<LLVM::GlobalOp, 16> records;
+  llvm::StringMap<unsigned> indices;
+  for (auto global : module.getOps<LLVM::GlobalOp>()) {
+    auto section = global.getSection();
+    if (section && *section == atomsSection) {
+      indices[global.getSymName()] = records.size();
+      records.push_back(global);
+    }
+  }
+  if (records.empty())
+    return;
+
+  MLIRContext *context = module.getContext();
+  auto loc = module.getLoc();
+  auto isizeTy = typeConverter.getIsizeType();
+  auto atomDataPtrTy =
+      LLVM::LLVMPointerType::get(typeConverter.getAtomDataType());
+  auto atomDataPtrPtrTy = LLVM::LLVMPointerType::get(atomDataPtrTy);
+  auto arrayTy = LLVM::LLVMArrayType::get(atomDataPtrTy, records.size());
+  auto tableTy = LLVM::LLVMStructType::getLiteral(
+      context, {isizeTy, atomDataPtrPtrTy, atomDataPtrPtrTy});
+
+  // The globals are named after the module, so that the tables of different
+  // modules do not collide
+  llvm::SHA1 hasher;
+  if (auto name = module.getName())
+    hasher.update(*name);
+  auto suffix = llvm::toHex(hasher.result(), true);
+
+  OpBuilder builder(context);
+  builder.setInsertionPointToEnd(module.getBody());
+
+  // The names of the atoms, as the addresses of their records
+  auto namesGlobal = builder.create<LLVM::GlobalOp>(
+      loc, arrayTy, /*isConstant=*/true, LLVM::Linkage::Internal,
+      LLVM::ThreadLocalMode::NotThreadLocal,
+      std::string("firefly_module_atom_names_") + suffix, Attribute());
+  {
+    OpBuilder::InsertionGuard insertGuard(builder);
+    builder.createBlock(&namesGlobal.getInitializerRegion());
+
+    Value names = builder.create<LLVM::UndefOp>(loc, arrayTy);
+    for (auto it : llvm::enumerate(records)) {
+      Value record = builder.create<LLVM::AddressOfOp>(loc, it.value());
+      names = builder.create<LLVM::InsertValueOp>(
+          loc, names, record, builder.getI64ArrayAttr(it.index()));
+    }
+    builder.create<LLVM::ReturnOp>(loc, names);
+  }
+
+  // The mapping array, which is filled in by the runtime at startup
+  auto atomsGlobal = builder.create<LLVM::GlobalOp>(
+      loc, arrayTy, /*isConstant=*/false, LLVM::Linkage::Internal,
+      LLVM::ThreadLocalMode::NotThreadLocal,
+      std::string("firefly_module_atoms_") + suffix, Attribute());
+  {
+    OpBuilder::InsertionGuard insertGuard(builder);
+    builder.createBlock(&atomsGlobal.getInitializerRegion());
+
+    Value atoms = builder.create<LLVM::UndefOp>(loc, arrayTy);
+    Value null = builder.create<LLVM::NullOp>(loc, atomDataPtrTy);
+    for (unsigned i = 0; i < records.size(); ++i)
+      atoms = builder.create<LLVM::InsertValueOp>(loc, atoms, null,
+                                                  builder.getI64ArrayAttr(i));
+    builder.create<LLVM::ReturnOp>(loc, atoms);
+  }
+
+  // The table itself, which is gathered by the linker with those of the other
+  // modules. It has external linkage so that it is never discarded, as nothing
+  // refers to it by name.
+  std::string sectionName =
+      isMachO ? std::string("__DATA,__module_atoms") : "__module_atoms";
+  auto sectionAttr =
+      builder.getNamedAttr("section", builder.getStringAttr(sectionName));
+  auto tableGlobal = builder.create<LLVM::GlobalOp>(
+      loc, tableTy, /*isConstant=*/true, LLVM::Linkage::External,
+      LLVM::ThreadLocalMode::NotThreadLocal,
+      std::string("firefly_module_atom_table_") + suffix, Attribute(),
+      /*alignment=*/8, /*addrspace=*/0, /*dso_local=*/false,
+      ArrayRef<NamedAttribute>{sectionAttr});
+  {
+    OpBuilder::InsertionGuard insertGuard(builder);
+    builder.createBlock(&tableGlobal.getInitializerRegion());
+
+    Value len = builder.create<LLVM::ConstantOp>(
+        loc, isizeTy, builder.getIntegerAttr(isizeTy, records.size()));
+    Value namesPtr = builder.create<LLVM::BitcastOp>(
+        loc, atomDataPtrPtrTy,
+        builder.create<LLVM::AddressOfOp>(loc, namesGlobal));
+    Value atomsPtr = builder.create<LLVM::BitcastOp>(
+        loc, atomDataPtrPtrTy,
+        builder.create<LLVM::AddressOfOp>(loc, atomsGlobal));
+
+    Value table = builder.create<LLVM::UndefOp>(loc, tableTy);
+    table = builder.create<LLVM::InsertValueOp>(loc, table, len,
+                                                builder.getI64ArrayAttr(0));
+    table = builder.create<LLVM::InsertValueOp>(loc, table, namesPtr,
+                                                builder.getI64ArrayAttr(1));
+    table = builder.create<LLVM::InsertValueOp>(loc, table, atomsPtr,
+                                                builder.getI64ArrayAttr(2));
+    builder.create<LLVM::ReturnOp>(loc, table);
+  }
+
+  // Materialize the literal atoms in functions from the mapping array. Global
+  // initializers keep referring to the records, as they cannot load.
+  SmallVector<LLVM::AddressOfOp, 16> literals;
+  module.walk([&](LLVM::AddressOfOp addressOf) {
+    if (addressOf->getParentOfType<LLVM::LLVMFuncOp>() &&
+        indices.count(addressOf.getGlobalName()))
+      literals.push_back(addressOf);
+  });
+  for (auto addressOf : literals) {
+    OpBuilder builder(addressOf.getOperation());
+    auto loc = addressOf.getLoc();
+    unsigned index = indices.lookup(addressOf.getGlobalName());
+
+    Value atoms = builder.create<LLVM::AddressOfOp>(loc, atomsGlobal);
+    Value zero = builder.create<LLVM::ConstantOp>(
+        loc, isizeTy, builder.getIntegerAttr(isizeTy, 0));
+    Value offset = builder.create<LLVM::ConstantOp>(
+        loc, isizeTy, builder.getIntegerAttr(isizeTy, index));
+    Value ptr = builder.create<LLVM::GEPOp>(loc, atomDataPtrPtrTy, atoms,
+                                            ValueRange({zero, offset}));
+    Value atom = builder.create<LLVM::LoadOp>(loc, ptr);
+    addressOf.getResult().replaceAllUsesWith(atom);
+    addressOf.erase();
+  }
+}
+
 //===----------------------------------------------------------------------===//
 // ConvertCIRToLLVMPass
 //
@@ -3349,6 +3533,7 @@ void ConvertCIRToLLVMPass::runOnOperation() {
   patterns.add<MallocOpLowering>(typeConverter);
   patterns.add<MakeFunOpLowering>(typeConverter);
   patterns.add<UnpackEnvOpLowering>(typeConverter);
+  patterns.add<FunArityOpLowering>(typeConverter);
   patterns.add<ConsOpLowering>(typeConverter);
   patterns.add<HeadOpLowering>(typeConverter);
   patterns.add<TailOpLowering>(typeConverter);
@@ -3375,8 +3560,12 @@ void ConvertCIRToLLVMPass::runOnOperation() {
   // completes, only legal LLVM dialect ops will remain. If we forget to lower
   // an op that is illegal in LLVM, the pass will fail with an error pointing to
   // the guilty op.
-  if (failed(applyFullConversion(module, target, std::move(patterns))))
+  if (failed(applyFullConversion(module, target, std::move(patterns)))) {
     signalPassFailure();
+    return;
+  }
+
+  emitModuleAtomTable(module, typeConverter);
 }
 
 /// Create the conversion passs
@@ -1571,6 +1571,27 @@ impl<'a, B: OpBuilder> CirBuilder<'a, B> {
     }
 }
 
+/// Represents reading the arity a fun/closure is called with
+#[repr(transparent)]
+#[derive(Copy, Clone)]
+pub struct FunArityOp(OperationBase);
+impl Operation for FunArityOp {
+    fn base(&self) -> OperationBase {
+        self.0
+    }
+}
+impl<'a, B: OpBuilder> CirBuilder<'a, B> {
+    #[inline]
+    pub fn build_fun_arity<V: Value>(&self, loc: Location, fun: V) -> FunArityOp {
+        extern "C" {
+            fn mlirCirFunArityOp(builder: OpBuilderBase, loc: Location, fun: ValueBase)
+                -> FunArityOp;
+        }
+
+        unsafe { mlirCirFunArityOp(self.base().into(), loc, fun.base()) }
+    }
+}
+
 /// Represents the construction of a cons cell
 #[repr(transparent)]
 #[derive(Copy, Clone)]
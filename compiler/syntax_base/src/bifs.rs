@@ -94,6 +94,9 @@ lazy_static! {
             bif!(pub erlang:float_to_list/1(float) -> list),
             bif!(pub erlang:float_to_list/2(float, list) -> list),
             guard_bif!(pub erlang:floor/1(number) -> integer),
+            bif!(pub erlang:fun_info/1(function) -> list),
+            bif!(pub erlang:fun_to_list/1(function) -> list),
+            bif!(pub erlang:function_exported/3(module, atom, arity) -> boolean),
             bif!(pub erlang:garbage_collect/0() -> boolean),
             bif!(pub erlang:garbage_collect/1(pid) -> boolean),
             bif!(pub erlang:garbage_collect/2(pid, list) -> term),
@@ -118,6 +121,7 @@ lazy_static! {
             guard_bif!(pub erlang:is_binary/1(any) -> boolean),
             guard_bif!(pub erlang:is_bitstring/1(any) -> boolean),
             guard_bif!(pub erlang:is_boolean/1(any) -> boolean),
+            bif!(pub erlang:is_builtin/3(module, atom, arity) -> boolean),
             guard_bif!(pub erlang:is_float/1(any) -> boolean),
             guard_bif!(pub erlang:is_function/1(any) -> boolean),
             guard_bif!(pub erlang:is_function/2(any, arity) -> boolean),
@@ -181,6 +185,7 @@ lazy_static! {
             bif!(pub erlang:ref_to_list/1(reference) -> string),
             bif!(pub erlang:register/2(atom, term) -> boolean),
             bif!(pub erlang:registered/0() -> list),
+            bif!(pub erlang:resume_process/1(pid) -> boolean),
             guard_bif!(pub erlang:round/1(number) -> integer),
             bif!(pub erlang:setelement/3(pos_integer, tuple, term) -> tuple),
             guard_bif!(pub erlang:self/0() -> pid),
@@ -210,6 +215,7 @@ lazy_static! {
             bif!(pub erlang:spawn_request_abandon/1(reference) -> boolean),
             bif!(pub erlang:split_binary/2(binary, non_neg_integer) -> binary_split),
             bif!(pub erlang:statistics/1(atom) -> term),
+            bif!(pub erlang:suspend_process/1(pid) -> boolean),
             bif!(pub erlang:term_to_binary/1(term) -> binary),
             bif!(pub erlang:term_to_binary/2(term, list) -> binary),
             bif!(pub erlang:term_to_iovec/1(term) -> list),
@@ -235,6 +241,10 @@ lazy_static! {
             Signature::new(Visibility::PUBLIC | Visibility::EXTERNAL, CallConv::C, symbols::Erlang, symbols::RecvNext, FunctionType::default()),
             // pub erlang:recv_peek_message/0() -> <peek_succeeded, message>
             Signature::new(Visibility::PUBLIC | Visibility::EXTERNAL, CallConv::C, symbols::Erlang, symbols::RecvPeekMessage, FunctionType::new(vec![], vec![Type::Term(TermType::Bool), Type::Term(TermType::Any)])),
+            // pub erlang:recv_mark/1(reference)
+            Signature::new(Visibility::PUBLIC | Visibility::EXTERNAL, CallConv::C, symbols::Erlang, symbols::RecvMark, FunctionType::new(vec![Type::Term(TermType::Reference)], vec![])),
+            // pub erlang:recv_set/1(reference)
+            Signature::new(Visibility::PUBLIC | Visibility::EXTERNAL, CallConv::C, symbols::Erlang, symbols::RecvSet, FunctionType::new(vec![Type::Term(TermType::Reference)], vec![])),
             // pub erlang:recv_wait_timeout/1(timeout) -> <is_err, timeout_expired | *exception>
             Signature::new(Visibility::PUBLIC | Visibility::EXTERNAL, CallConv::C, symbols::Erlang, symbols::RecvWaitTimeout, FunctionType::new(vec![Type::Term(TermType::Any)], vec![Type::Primitive(PrimitiveType::I1), Type::Term(TermType::Any)])),
         ]
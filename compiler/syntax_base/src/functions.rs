@@ -4,6 +4,11 @@ use firefly_intern::{symbols, Symbol};
 
 use super::types::*;
 
+/// The maximum number of arguments a function may have, as in BEAM
+///
+/// The runtime can call functions of any arity up to this limit, including via `apply`.
+pub const MAX_ARITY: usize = u8::MAX as usize;
+
 /// This struct represents either a fully-qualified Erlang function name (i.e. MFA),
 /// or a locally-qualified function name (i.e. FA). Fully-qualified function names
 /// are module-agnostic (i.e. they are valid in any context), whereas locally-qualified
@@ -158,6 +163,8 @@ impl FunctionName {
             | symbols::RemoveMessage
             | symbols::RecvNext
             | symbols::RecvPeekMessage
+            | symbols::RecvMark
+            | symbols::RecvSet
             | symbols::RecvWaitTimeout => true,
             _ => false,
         }
@@ -5,7 +5,7 @@ use std::hash::{Hash, Hasher};
 use firefly_diagnostics::{Diagnostic, Label, Reporter, SourceSpan, Span, Spanned};
 use firefly_intern::Symbol;
 use firefly_number::Integer;
-use firefly_syntax_base::FunctionName;
+use firefly_syntax_base::{FunctionName, MAX_ARITY};
 
 use super::{Arity, Clause, Expr, Ident, Literal, Name, TypeSpec, Var};
 use crate::ParserError;
@@ -63,7 +63,7 @@ impl Function {
         let head = &head_pair.1;
         let head_span = head.span.clone();
         let name = head_name.unwrap().atom();
-        let arity = head.patterns.len().try_into().unwrap();
+        let arity = check_arity(head_span, &name.to_string(), head.patterns.len())?;
 
         // Check clauses
         let mut last_clause = head_span.clone();
@@ -84,7 +84,6 @@ impl Function {
             }
 
             let clause_name = clause_name.clone().unwrap();
-            let clause_arity: u8 = clause.patterns.len().try_into().unwrap();
 
             if clause_name != Name::Atom(name) {
                 reporter.diagnostic(
@@ -101,7 +100,7 @@ impl Function {
                 );
                 continue;
             }
-            if clause_arity != arity {
+            if clause.patterns.len() != arity as usize {
                 reporter.diagnostic(
                     Diagnostic::error()
                         .with_message("unterminated function clause")
@@ -133,6 +132,24 @@ impl Function {
     }
 }
 
+/// Returns the arity of a function with `num_args` arguments, or an error if it has more than
+/// `MAX_ARITY`, which is the most the runtime can call
+fn check_arity(span: SourceSpan, name: &str, num_args: usize) -> Result<u8, ParserError> {
+    if num_args > MAX_ARITY {
+        return Err(ParserError::ShowDiagnostic {
+            diagnostic: Diagnostic::error()
+                .with_message("too many arguments")
+                .with_labels(vec![Label::primary(span.source_id(), span).with_message(
+                    format!(
+                        "{} has {} arguments, but functions may have at most {}",
+                        name, num_args, MAX_ARITY
+                    ),
+                )]),
+        });
+    }
+    Ok(num_args as u8)
+}
+
 #[derive(Debug, Clone, Spanned)]
 pub struct Guard {
     #[span]
@@ -213,7 +230,11 @@ impl Fun {
         let name = head_clause.0.clone();
         let head = &head_clause.1;
         let head_span = head.span.clone();
-        let arity = head.patterns.len().try_into().unwrap();
+        let fun_name = match name {
+            Some(ref name) => format!("fun {}", name.symbol()),
+            None => "fun".to_string(),
+        };
+        let arity = check_arity(head_span, &fun_name, head.patterns.len())?;
 
         // Check clauses
         let mut last_clause = head_span.clone();
@@ -265,8 +286,7 @@ impl Fun {
                 continue;
             }
 
-            let clause_arity: u8 = clause.patterns.len().try_into().unwrap();
-            if clause_arity != arity {
+            if clause.patterns.len() != arity as usize {
                 reporter.diagnostic(
                     Diagnostic::error()
                         .with_message("unterminated function clause")
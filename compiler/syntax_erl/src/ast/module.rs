@@ -192,14 +192,47 @@ impl Module {
             deprecations: HashSet::new(),
         };
 
+        // The grammar only accepts `-module` as the first form of a source file, but forms in Erlang
+        // Abstract Format may contain it anywhere, so it is validated here
+        let mut declaration_span: Option<SourceSpan> = None;
+        let mut first_form_span: Option<SourceSpan> = None;
         for form in forms.drain(0..) {
             match form {
-                TopLevel::Attribute(attr) => sema::analyze_attribute(reporter, &mut module, attr),
-                TopLevel::Record(record) => sema::analyze_record(reporter, &mut module, record),
-                TopLevel::Function(function) => {
-                    sema::analyze_function(reporter, &mut module, function)
+                TopLevel::Module(declared) => {
+                    if let Some(prev_span) = declaration_span {
+                        reporter.show_error(
+                            "module is already declared",
+                            &[
+                                (declared.span, "redeclaration occurs here"),
+                                (prev_span, "first declared here"),
+                            ],
+                        );
+                    } else if let Some(first_span) = first_form_span {
+                        reporter.show_error(
+                            "-module must be the first attribute of a module",
+                            &[
+                                (declared.span, "module is declared here"),
+                                (first_span, "but this appears before it"),
+                            ],
+                        );
+                    }
+                    declaration_span.get_or_insert(declared.span);
+                }
+                form => {
+                    first_form_span.get_or_insert(form.span());
+                    match form {
+                        TopLevel::Attribute(attr) => {
+                            sema::analyze_attribute(reporter, &mut module, attr)
+                        }
+                        TopLevel::Record(record) => {
+                            sema::analyze_record(reporter, &mut module, record)
+                        }
+                        TopLevel::Function(function) => {
+                            sema::analyze_function(reporter, &mut module, function)
+                        }
+                        TopLevel::Module(_) => unreachable!(),
+                    }
                 }
-                _ => panic!("unexpected top-level form: {:?}", &form),
             }
         }
 
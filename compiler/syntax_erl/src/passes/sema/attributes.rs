@@ -77,6 +77,16 @@ pub fn analyze_attribute(reporter: &Reporter, module: &mut Module, attr: Attribu
         Attribute::Import(span, from_module, mut imports) => {
             for local_import in imports.drain(..) {
                 let import = local_import.resolve(from_module.name);
+                if let Some(function) = module.functions.get(&local_import) {
+                    reporter.show_error(
+                        "imported function is also defined in this module",
+                        &[
+                            (span, "function is imported here"),
+                            (function.span, "but also defined here"),
+                        ],
+                    );
+                    continue;
+                }
                 match module.imports.get(&local_import) {
                     None => {
                         let sig = match bifs::get(&import) {
@@ -88,7 +98,7 @@ pub fn analyze_attribute(reporter: &Reporter, module: &mut Module, attr: Attribu
                         };
                         module.imports.insert(*local_import, Span::new(span, sig));
                     }
-                    Some(ref spanned) => {
+                    Some(ref spanned) if spanned.module == from_module.name => {
                         let prev_span = spanned.span();
                         reporter.show_warning(
                             "unused import",
@@ -98,6 +108,20 @@ pub fn analyze_attribute(reporter: &Reporter, module: &mut Module, attr: Attribu
                             ],
                         );
                     }
+                    Some(ref spanned) => {
+                        let prev_span = spanned.span();
+                        let message = format!(
+                            "{} is already imported from {}",
+                            *local_import, spanned.module
+                        );
+                        reporter.show_error(
+                            "conflicting imports",
+                            &[
+                                (span, message.as_str()),
+                                (prev_span, "function was first imported here"),
+                            ],
+                        );
+                    }
                 }
             }
         }
@@ -805,3 +829,241 @@ fn to_list(head: &Expr, tail: &Expr) -> Vec<Expr> {
 
     list
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use pretty_assertions::assert_eq;
+
+    use firefly_diagnostics::*;
+    use firefly_intern::{Ident, Symbol};
+
+    use crate::ast::{Attribute, Module, TopLevel};
+    use crate::parser::{ParseConfig, Parser};
+
+    /// A diagnostic as its severity, message, and the source text under each of its labels,
+    /// primary label first
+    type Reported = (Severity, String, Vec<String>);
+
+    fn parse(source: &str) -> (Option<Module>, Vec<Reported>) {
+        let codemap = Arc::new(CodeMap::new());
+        let reporter = Reporter::new();
+        let parser = Parser::new(ParseConfig::default(), codemap.clone());
+        let module = parser
+            .parse_string::<Module, _, _>(reporter.clone(), source)
+            .ok();
+
+        (module, reported(&codemap, &reporter))
+    }
+
+    fn reported(codemap: &CodeMap, reporter: &Reporter) -> Vec<Reported> {
+        reporter
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let labels = diagnostic
+                    .labels
+                    .iter()
+                    .map(|label| {
+                        let file = codemap.get(label.file_id).unwrap();
+                        file.source_slice(label.range.clone()).unwrap().to_string()
+                    })
+                    .collect();
+
+                (diagnostic.severity, diagnostic.message.clone(), labels)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn module_declared_after_another_form_errors() {
+        let codemap = Arc::new(CodeMap::new());
+        let source = "-behaviour(gen_server).\n-module(foo).\n";
+        let id = codemap.add("nofile", source.to_string());
+        let span = |start: u32, end: u32| {
+            SourceSpan::new(
+                SourceIndex::new(id, ByteIndex(start)),
+                SourceIndex::new(id, ByteIndex(end)),
+            )
+        };
+        let behaviour_span = span(0, 23);
+        let behaviour = Ident::new(Symbol::intern("gen_server"), span(11, 21));
+        let name = Ident::new(Symbol::intern("foo"), span(32, 35));
+        let forms = vec![
+            TopLevel::Attribute(Attribute::Behaviour(behaviour_span, behaviour)),
+            TopLevel::Module(name),
+        ];
+        let reporter = Reporter::new();
+
+        let module =
+            Module::new_from_pp(&reporter, codemap.clone(), span(0, 38), forms).unwrap();
+
+        assert_eq!(module.name, name);
+        assert_eq!(
+            reported(&codemap, &reporter),
+            vec![(
+                Severity::Error,
+                "-module must be the first attribute of a module".to_string(),
+                vec!["foo".to_string(), "-behaviour(gen_server).".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn duplicate_on_load_errors_with_both_spans() {
+        let (module, reported) = parse(
+            "-module(foo).
+-on_load(init/0).
+-on_load(reload/0).
+
+init() -> ok.
+reload() -> ok.
+",
+        );
+
+        assert!(module.is_none());
+        assert_eq!(reported.len(), 1);
+        let (severity, message, labels) = &reported[0];
+        assert_eq!(*severity, Severity::Error);
+        assert_eq!(message, "on_load can only be defined once");
+        assert!(labels[0].contains("reload/0"), "{:?}", labels);
+        assert!(labels[1].contains("init/0"), "{:?}", labels);
+    }
+
+    #[test]
+    fn duplicate_behaviour_warns_and_deduplicates() {
+        let (module, reported) = parse(
+            "-module(foo).
+-behaviour(gen_server).
+-behaviour(gen_server).
+",
+        );
+
+        assert_eq!(module.unwrap().behaviours.len(), 1);
+        assert_eq!(reported.len(), 1);
+        let (severity, message, labels) = &reported[0];
+        assert_eq!(*severity, Severity::Warning);
+        assert_eq!(message, "duplicate behavior declaration");
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[1], "gen_server");
+    }
+
+    #[test]
+    fn import_before_local_definition_errors_at_import() {
+        let (module, reported) = parse(
+            "-module(foo).
+-import(lists, [map/2]).
+
+map(F, L) -> [F(X) || X <- L].
+",
+        );
+
+        assert!(module.is_none());
+        assert_import_conflict(&reported);
+    }
+
+    #[test]
+    fn import_after_local_definition_errors_at_import() {
+        let (module, reported) = parse(
+            "-module(foo).
+
+map(F, L) -> [F(X) || X <- L].
+
+-import(lists, [map/2]).
+",
+        );
+
+        assert!(module.is_none());
+        assert_import_conflict(&reported);
+    }
+
+    fn assert_import_conflict(reported: &[Reported]) {
+        assert_eq!(reported.len(), 1);
+        let (severity, message, labels) = &reported[0];
+        assert_eq!(*severity, Severity::Error);
+        assert_eq!(message, "imported function is also defined in this module");
+        assert!(labels[0].contains("import(lists, [map/2])"), "{:?}", labels);
+        assert!(labels[1].starts_with("map(F, L)"), "{:?}", labels);
+    }
+
+    #[test]
+    fn conflicting_imports_error_with_both_spans() {
+        let (module, reported) = parse(
+            "-module(foo).
+-import(lists, [map/2]).
+-import(mylists, [map/2]).
+",
+        );
+
+        assert!(module.is_none());
+        assert_eq!(reported.len(), 1);
+        let (severity, message, labels) = &reported[0];
+        assert_eq!(*severity, Severity::Error);
+        assert_eq!(message, "conflicting imports");
+        assert!(labels[0].contains("import(mylists, [map/2])"), "{:?}", labels);
+        assert!(labels[1].contains("import(lists, [map/2])"), "{:?}", labels);
+    }
+
+    #[test]
+    fn duplicate_import_from_the_same_module_warns() {
+        let (module, reported) = parse(
+            "-module(foo).
+-import(lists, [map/2]).
+-import(lists, [map/2, reverse/1]).
+",
+        );
+
+        assert_eq!(module.unwrap().imports.len(), 2);
+        assert_eq!(reported.len(), 1);
+        let (severity, message, _) = &reported[0];
+        assert_eq!(*severity, Severity::Warning);
+        assert_eq!(message, "unused import");
+    }
+
+    #[test]
+    fn module_with_every_attribute_kind_is_unchanged() {
+        let (module, reported) = parse(
+            "-module(foo).
+-vsn(\"1.0\").
+-author(\"nobody\").
+-on_load(init/0).
+-nifs([native/1]).
+-import(lists, [reverse/1]).
+-export([init/0, native/1, run/1]).
+-export_type([t/0]).
+-type t() :: atom().
+-behaviour(gen_server).
+-callback handle(term()) -> ok.
+-spec run(list()) -> list().
+-removed([{old, 0, \"use run/1\"}]).
+-deprecated([{run, 1, eventually}]).
+-record(state, {value}).
+-custom(value).
+
+init() -> ok.
+native(_X) -> erlang:nif_error(undef).
+run(L) -> reverse(L).
+",
+        );
+
+        assert_eq!(reported, vec![]);
+        let module = module.unwrap();
+        assert_eq!(
+            module.on_load.as_ref().map(|on_load| on_load.function),
+            Some(Symbol::intern("init"))
+        );
+        assert_eq!(module.nifs.len(), 1);
+        assert_eq!(module.imports.len(), 1);
+        assert_eq!(module.exports.len(), 3);
+        assert_eq!(module.exported_types.len(), 1);
+        assert_eq!(module.behaviours.len(), 1);
+        assert_eq!(module.callbacks.len(), 1);
+        assert_eq!(module.specs.len(), 1);
+        assert_eq!(module.removed.len(), 1);
+        assert_eq!(module.deprecations.len(), 1);
+        assert_eq!(module.records.len(), 1);
+        assert_eq!(module.attributes.len(), 1);
+        assert_eq!(module.functions.len(), 3);
+    }
+}
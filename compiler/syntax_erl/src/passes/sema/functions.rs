@@ -39,9 +39,16 @@ pub fn analyze_function(reporter: &Reporter, module: &mut Module, mut function:
         }
     }
 
-    // If we have a local with the same name as an imported function, the import is shadowed
-    if module.imports.contains_key(&local_resolved_name) {
-        module.imports.remove(&local_resolved_name);
+    // Calls to a function which is both imported and defined locally would be ambiguous, so the
+    // import is rejected, leaving only the local definition
+    if let Some(import) = module.imports.remove(&local_resolved_name) {
+        reporter.show_error(
+            "imported function is also defined in this module",
+            &[
+                (import.span(), "function is imported here"),
+                (function.span, "but also defined here"),
+            ],
+        );
     }
 
     match module.functions.entry(local_resolved_name) {
@@ -7,30 +7,61 @@ use crate::ast::{self, *};
 
 /// Registers auto-imported BIFs in the given module
 ///
-/// This pass takes into account the compiler options of the module when deciding what to import
-pub struct AddAutoImports;
+/// This pass takes into account the compiler options of the module when deciding what to import.
+///
+/// As in OTP, a function defined in the module shadows an auto-imported BIF of the same name, while
+/// explicitly importing a BIF of the same name from another module is an error, unless auto-import
+/// of that BIF is disabled with `-compile({no_auto_import, [F/A]})`.
+pub struct AddAutoImports {
+    reporter: Reporter,
+}
+impl AddAutoImports {
+    pub fn new(reporter: Reporter) -> Self {
+        Self { reporter }
+    }
+}
 impl Pass for AddAutoImports {
     type Input<'a> = &'a mut Module;
     type Output<'a> = &'a mut Module;
 
     fn run<'a>(&mut self, module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
-        if let Some(compile) = module.compile.as_ref() {
-            if compile.no_auto_import {
-                return Ok(module);
-            }
+        let no_auto_imports = match module.compile.as_ref() {
+            Some(compile) if compile.no_auto_import => return Ok(module),
+            Some(compile) => compile.no_auto_imports.clone(),
+            None => Default::default(),
+        };
 
-            let span = module.name.span;
-            for sig in bifs::all().iter().map(|sig| Span::new(span, sig.clone())) {
-                let local_name = sig.mfa().to_local();
-                if !compile.no_auto_imports.contains(&local_name) {
+        let span = module.name.span;
+        for sig in bifs::all().iter().map(|sig| Span::new(span, sig.clone())) {
+            let local_name = sig.mfa().to_local();
+            if no_auto_imports.contains(&local_name) || module.functions.contains_key(&local_name) {
+                continue;
+            }
+            match module.imports.get(&local_name) {
+                None => {
                     module.imports.insert(local_name, sig);
                 }
-            }
-        } else {
-            let span = module.name.span;
-            for sig in bifs::all().iter().map(|sig| Span::new(span, sig.clone())) {
-                let local_name = sig.mfa().to_local();
-                module.imports.insert(local_name, sig);
+                Some(import) if import.module == sig.module => (),
+                Some(import) => {
+                    let message = format!(
+                        "this import of {} overrides the auto-imported BIF {}",
+                        &local_name,
+                        sig.mfa()
+                    );
+                    let hint = format!(
+                        "use -compile({{no_auto_import, [{}]}}) to resolve the name clash",
+                        &local_name
+                    );
+                    let span = import.span();
+                    self.reporter.diagnostic(
+                        Diagnostic::error()
+                            .with_message("import overrides auto-imported BIF")
+                            .with_labels(vec![
+                                Label::primary(span.source_id(), span).with_message(message)
+                            ])
+                            .with_notes(vec![hint]),
+                    );
+                }
             }
         }
 
@@ -45,7 +45,7 @@ impl<'app> Pass for SemanticAnalysis<'app> {
     type Output<'a> = ast::Module;
 
     fn run<'a>(&mut self, mut module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
-        let mut passes = inject::AddAutoImports
+        let mut passes = inject::AddAutoImports::new(self.reporter.clone())
             .chain(verify::VerifyExports::new(self.reporter.clone()))
             .chain(verify::VerifyOnLoadFunctions::new(self.reporter.clone()))
             .chain(verify::VerifyTypeSpecs::new(self.reporter.clone()))
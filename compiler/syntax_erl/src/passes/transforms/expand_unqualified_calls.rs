@@ -1,4 +1,5 @@
 use core::ops::ControlFlow;
+use std::collections::BTreeSet;
 
 use crate::ast::*;
 
@@ -16,8 +17,12 @@ use crate::visit::{self as visit, VisitMut};
 ///
 /// Once this pass has run, we don't need to concern ourselves with imports anymore, as
 /// the distinction is erased.
+///
+/// The set of functions defined in the module is given separately from the module, as the
+/// function being expanded has been taken out of the module while it is being transformed.
 pub struct ExpandUnqualifiedCalls<'m> {
     module: &'m Module,
+    locals: &'m BTreeSet<FunctionName>,
 }
 impl<'m> Pass for ExpandUnqualifiedCalls<'m> {
     type Input<'a> = &'a mut Function;
@@ -31,8 +36,16 @@ impl<'m> Pass for ExpandUnqualifiedCalls<'m> {
     }
 }
 impl<'m> ExpandUnqualifiedCalls<'m> {
-    pub fn new(module: &'m Module) -> Self {
-        Self { module }
+    pub fn new(module: &'m Module, locals: &'m BTreeSet<FunctionName>) -> Self {
+        Self { module, locals }
+    }
+
+    fn is_local(&self, name: &FunctionName) -> bool {
+        self.locals.contains(&name.to_local())
+    }
+
+    fn is_import(&self, name: &FunctionName) -> bool {
+        !self.is_local(name) && self.module.imports.contains_key(&name.to_local())
     }
 }
 impl<'m> VisitMut<anyhow::Error> for ExpandUnqualifiedCalls<'m> {
@@ -44,9 +57,9 @@ impl<'m> VisitMut<anyhow::Error> for ExpandUnqualifiedCalls<'m> {
         let name = match apply.callee.as_mut() {
             Expr::Literal(Literal::Atom(ident)) => {
                 let local = FunctionName::new_local(ident.name, arity);
-                if self.module.is_local(&local) {
+                if self.is_local(&local) {
                     FunctionVar::Resolved(Span::new(span, local.resolve(self.module.name())))
-                } else if self.module.is_import(&local) {
+                } else if self.is_import(&local) {
                     let resolved = self.module.imports.get(&local).unwrap();
                     FunctionVar::Resolved(Span::new(span, local.resolve(resolved.module)))
                 } else {
@@ -72,7 +85,7 @@ impl<'m> VisitMut<anyhow::Error> for ExpandUnqualifiedCalls<'m> {
     fn visit_mut_function_var(&mut self, name: &mut FunctionVar) -> ControlFlow<anyhow::Error> {
         // We only care about partially-resolved function names at this point
         if let Some(ref local) = name.partial_resolution() {
-            if self.module.is_local(local) {
+            if self.is_local(local) {
                 let span = local.span();
                 let function = local.function;
                 let arity = local.arity;
@@ -80,7 +93,7 @@ impl<'m> VisitMut<anyhow::Error> for ExpandUnqualifiedCalls<'m> {
                     span,
                     FunctionName::new(self.module.name(), function, arity),
                 ));
-            } else if self.module.is_import(local) {
+            } else if self.is_import(local) {
                 let span = local.span();
                 let function = local.function;
                 let arity = local.arity;
@@ -2,7 +2,7 @@ mod expand_records;
 mod expand_substitutions;
 mod expand_unqualified_calls;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::Arc;
 
 use firefly_diagnostics::*;
@@ -30,10 +30,11 @@ impl Pass for CanonicalizeSyntax {
 
     fn run<'a>(&mut self, mut module: Self::Input<'a>) -> anyhow::Result<Self::Output<'a>> {
         let mut functions = BTreeMap::new();
+        let locals = module.functions.keys().copied().collect::<BTreeSet<_>>();
         while let Some((key, mut function)) = module.functions.pop_first() {
             // Prepare function for translation to CST
             let mut pipeline = ExpandRecords::new(&module)
-                .chain(ExpandUnqualifiedCalls::new(&module))
+                .chain(ExpandUnqualifiedCalls::new(&module, &locals))
                 .chain(ExpandSubstitutions::new(module.name, &self.codemap));
             pipeline.run(&mut function)?;
 
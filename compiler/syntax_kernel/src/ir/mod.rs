@@ -380,6 +380,46 @@ impl Expr {
             _ => None,
         }
     }
+
+    /// Returns a short name for the kind of this expression, for use in diagnostics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Binary(_) => "binary",
+            Self::BinaryInt(_) => "binary integer segment",
+            Self::BinarySegment(_) => "binary segment",
+            Self::BinaryEnd(_) => "binary end",
+            Self::Cons(_) => "cons",
+            Self::Tuple(_) => "tuple",
+            Self::Map(_) => "map",
+            Self::Literal(_) => "literal",
+            Self::Var(_) => "variable",
+            Self::Local(_) => "local function",
+            Self::Remote(_) => "remote function",
+            Self::Alias(_) => "alias",
+            Self::Alt(_) => "alt",
+            Self::Bif(_) => "bif",
+            Self::Break(_) => "break",
+            Self::Call(_) => "call",
+            Self::Catch(_) => "catch",
+            Self::Enter(_) => "enter",
+            Self::Fun(_) => "fun",
+            Self::Goto(_) => "goto",
+            Self::Guard(_) => "guard",
+            Self::If(_) => "if",
+            Self::LetRec(_) => "letrec",
+            Self::LetRecGoto(_) => "letrec goto",
+            Self::Match(_) => "match",
+            Self::Put(_) => "put",
+            Self::Return(_) => "return",
+            Self::Select(_) => "select",
+            Self::Seq(_) => "seq",
+            Self::Set(_) => "set",
+            Self::Test(_) => "test",
+            Self::Try(_) => "try",
+            Self::TryEnter(_) => "try enter",
+            Self::Values(_) => "values",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Spanned, PartialEq, Eq, PartialOrd, Ord)]
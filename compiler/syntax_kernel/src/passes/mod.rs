@@ -21,6 +21,9 @@ pub struct FunctionContext {
     defined: RedBlackTreeSet<Ident>,
     free: RedBlackTreeMap<Name, Vec<Expr>>,
     labels: RedBlackTreeSet<Symbol>,
+    // Receive loops which only match messages containing a reference created in this function,
+    // and so can skip the messages received before the reference was created
+    recv_marks: RedBlackTreeMap<Symbol, Ident>,
     ignore_funs: bool,
 }
 impl FunctionContext {
@@ -35,6 +38,7 @@ impl FunctionContext {
             defined: rbt_set![],
             free: RedBlackTreeMap::new(),
             labels: rbt_set![],
+            recv_marks: RedBlackTreeMap::new(),
             ignore_funs: false,
         }
     }
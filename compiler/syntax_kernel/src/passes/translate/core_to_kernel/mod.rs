@@ -415,9 +415,22 @@ impl TranslateCore {
                 box arg,
                 box body,
             }) => {
-                let (arg, mut pre) = self.body(arg, sub.clone())?;
+                // A reference can't be in any message received before it was created, so a
+                // receive which only matches messages containing it can skip those messages
+                let mut marked = vec![];
+                if vars.len() == 1 && is_make_ref(&arg) {
+                    marked_receives(&body, &vars[0], &mut marked);
+                }
+                let (mut arg, mut pre) = self.body(arg, sub.clone())?;
                 let cvars = vars.drain(..).map(core::Expr::Var).collect();
                 let (mut vars, sub) = self.pattern_list(cvars, sub.clone(), sub)?;
+                if let (Expr::Bif(bif), false) = (&mut arg, marked.is_empty()) {
+                    let reference = vars[0].as_var().unwrap().name;
+                    bif.annotations.set(symbols::RecvMark);
+                    for label in marked.drain(..) {
+                        self.context.recv_marks.insert_mut(label, reference);
+                    }
+                }
                 // Break down multiple values into separate set expressions
                 match arg {
                     Expr::Values(IValues { mut values, .. }) => {
@@ -783,9 +796,13 @@ impl TranslateCore {
             (body, fail) => {
                 self.context.labels = labels0;
                 let then_body = pre_seq(fpre, fail);
+                let mut annotations = Annotations::default();
+                if let Some(reference) = self.context.recv_marks.get(&label) {
+                    annotations.insert_mut(symbols::RecvSet, rbt_set![*reference]);
+                }
                 let alt = Expr::LetRecGoto(LetRecGoto {
                     span,
-                    annotations: Annotations::default(),
+                    annotations,
                     label,
                     vars: kvars,
                     first: Box::new(body),
@@ -1674,6 +1691,156 @@ fn same_args(args: &[core::Expr], fargs: &[Var], sub: &BiMap) -> bool {
     true
 }
 
+fn is_make_ref(expr: &core::Expr) -> bool {
+    match expr {
+        core::Expr::Call(call) => call.is_static(symbols::Erlang, symbols::MakeRef, 0),
+        _ => false,
+    }
+}
+
+/// Finds the receives in `expr` which only match messages containing `reference`, and so only
+/// need to look at the messages received after it was created, and adds the labels of their
+/// loops to `marked`
+fn marked_receives(expr: &core::Expr, reference: &Var, marked: &mut Vec<Symbol>) {
+    match expr {
+        core::Expr::LetRec(core::LetRec {
+            annotations,
+            defs,
+            body,
+            ..
+        }) if annotations.contains(symbols::LetrecGoto) => {
+            for (var, def) in defs.iter() {
+                let core::Expr::Fun(fun) = def else { continue };
+                if receive_requires(fun.body.as_ref(), reference) {
+                    marked.push(var.name());
+                }
+                marked_receives(fun.body.as_ref(), reference, marked);
+            }
+            marked_receives(body.as_ref(), reference, marked);
+        }
+        core::Expr::Let(core::Let {
+            vars, arg, body, ..
+        }) => {
+            marked_receives(arg.as_ref(), reference, marked);
+            if !vars.iter().any(|v| v.name() == reference.name()) {
+                marked_receives(body.as_ref(), reference, marked);
+            }
+        }
+        core::Expr::Seq(core::Seq { arg, body, .. }) => {
+            marked_receives(arg.as_ref(), reference, marked);
+            marked_receives(body.as_ref(), reference, marked);
+        }
+        core::Expr::Case(core::Case { clauses, .. }) => {
+            for clause in clauses.iter() {
+                marked_receives(clause.body.as_ref(), reference, marked);
+            }
+        }
+        core::Expr::If(core::If {
+            then_body,
+            else_body,
+            ..
+        }) => {
+            marked_receives(then_body.as_ref(), reference, marked);
+            marked_receives(else_body.as_ref(), reference, marked);
+        }
+        _ => (),
+    }
+}
+
+/// Returns true if `body` is the loop of a receive, as lowered by the core receive rewrite, in
+/// which every clause compares some part of the message with `reference` in its guard
+fn receive_requires(body: &core::Expr, reference: &Var) -> bool {
+    let core::Expr::Let(core::Let { vars, arg, body, .. }) = body else { return false };
+    match arg.as_ref() {
+        core::Expr::PrimOp(peek) if peek.name == symbols::RecvPeekMessage && vars.len() == 2 => (),
+        _ => return false,
+    }
+    let core::Expr::If(core::If { then_body, .. }) = body.as_ref() else { return false };
+    let core::Expr::Case(case) = then_body.as_ref() else { return false };
+    match case.arg.as_ref() {
+        core::Expr::Var(msg) if msg.name() == vars[1].name() => (),
+        _ => return false,
+    }
+    let mut clauses = case
+        .clauses
+        .iter()
+        .filter(|clause| !is_recv_next(clause))
+        .peekable();
+    clauses.peek().is_some()
+        && clauses.all(|clause| match clause.guard.as_deref() {
+            Some(guard) => guard_requires(guard, reference, &mut vec![]),
+            None => false,
+        })
+}
+
+/// Returns true if `clause` is the clause added by the core receive rewrite which moves on to the
+/// next message
+fn is_recv_next(clause: &core::Clause) -> bool {
+    match clause.body.as_ref() {
+        core::Expr::Seq(core::Seq { arg, .. }) => match arg.as_ref() {
+            core::Expr::PrimOp(op) => op.name == symbols::RecvNext,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns true if `guard` can only succeed when a value is equal to `reference`
+fn guard_requires<'a>(
+    guard: &'a core::Expr,
+    reference: &Var,
+    bound: &mut Vec<(Symbol, &'a core::Expr)>,
+) -> bool {
+    match guard {
+        core::Expr::Call(call)
+            if call.is_static(symbols::Erlang, symbols::EqualStrict, 2)
+                || call.is_static(symbols::Erlang, symbols::Equal, 2) =>
+        {
+            call.args.iter().any(|arg| match arg {
+                core::Expr::Var(v) => v.name() == reference.name(),
+                _ => false,
+            })
+        }
+        core::Expr::Call(call) if call.is_static(symbols::Erlang, symbols::And, 2) => call
+            .args
+            .iter()
+            .any(|arg| guard_requires(arg, reference, bound)),
+        core::Expr::Var(v) => {
+            let value = bound
+                .iter()
+                .rev()
+                .find(|(name, _)| *name == v.name())
+                .map(|(_, value)| *value);
+            match value {
+                Some(value) => guard_requires(value, reference, bound),
+                None => false,
+            }
+        }
+        core::Expr::Let(core::Let {
+            vars, arg, body, ..
+        }) => {
+            if let [var] = vars.as_slice() {
+                bound.push((var.name(), arg.as_ref()));
+            }
+            guard_requires(body.as_ref(), reference, bound)
+        }
+        // A guard which fails with an exception is false
+        core::Expr::Try(core::Try {
+            arg,
+            vars,
+            body,
+            handler,
+            ..
+        }) if handler.is_atom_value(symbols::False) => match (vars.as_slice(), body.as_ref()) {
+            ([var], core::Expr::Var(result)) if var.name() == result.name() => {
+                guard_requires(arg.as_ref(), reference, bound)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 fn make_clist(mut items: Vec<core::Expr>) -> core::Expr {
     items.drain(..).rfold(
         core::Expr::Literal(Literal::nil(SourceSpan::default())),
@@ -3626,7 +3793,11 @@ impl TranslateCore {
                 let ns = vars.iter().map(|v| v.name).collect();
                 let (f1, fu) = self.ubody(first, brk.clone())?;
                 let (t1, tu) = self.ubody(then, brk)?;
-                let used = sets::subtract(sets::union(fu, tu), ns);
+                let mut used = sets::subtract(sets::union(fu, tu), ns);
+                // The reference whose marker the receive starts from is used on entry
+                if let Some(Annotation::Vars(marked)) = annotations.get(symbols::RecvSet) {
+                    used = sets::union(used, marked.clone());
+                }
                 Ok((
                     Expr::LetRecGoto(LetRecGoto {
                         span,
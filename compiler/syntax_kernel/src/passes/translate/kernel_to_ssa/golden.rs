@@ -0,0 +1,207 @@
+//! Golden-file tests for `KernelToSsa`
+//!
+//! Each fixture in `tests/fixtures/kernel_to_ssa` is an Erlang module, named for the part of the
+//! lowering it exercises (`select_*`, `bif_*`, `guard_*`, `try_*`, `match_fail_*`, etc.). It is
+//! lowered to Kernel IR by the same passes the compiler driver runs, then through `KernelToSsa`,
+//! and the output of `write_function` for each function is compared against the `.golden` file
+//! of the same name. Every lowered function is also checked by `verify_function`, so the goldens
+//! remain valid SSA as the verifier learns new rules.
+//!
+//! When a change to the lowering alters its output on purpose, rewrite the goldens with:
+//!
+//! ```text
+//! BLESS=1 cargo test -p firefly_syntax_kernel golden
+//! ```
+//!
+//! and review the diff of the `.golden` files as part of the change. A new fixture is added by
+//! writing its `.erl` file and blessing it; the test fails for fixtures without a golden.
+//!
+//! Each path through `lower_select`, `lower_bif` and `lower_internal` is marked with `covered!`,
+//! which records its name here in test builds. `fixtures_cover_every_lowering_path` fails if one
+//! of the `SITES` is not reached by any fixture, so a path added to the lowering needs both a
+//! new site and a fixture which reaches it.
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use firefly_diagnostics::{CodeMap, Reporter, ToDiagnostic};
+use firefly_intern::Symbol;
+use firefly_pass::Pass;
+use firefly_syntax_base::ApplicationMetadata;
+use firefly_syntax_erl::passes::{AstToCore, CanonicalizeSyntax, SemanticAnalysis};
+use firefly_syntax_erl::{Module, ParseConfig, Parser};
+use firefly_syntax_ssa::verify::verify_function;
+use firefly_syntax_ssa::write::write_function;
+use pretty_assertions::StrComparison;
+
+use crate::passes::CoreToKernel;
+
+use super::KernelToSsa;
+
+/// Every path marked with `covered!` in the lowering
+///
+/// The arms of `lower_bif` for safe bifs outside the Erlang calling convention, and for fallible
+/// bifs with two results, are not marked: every bif other than the primops handled by
+/// `lower_internal` is declared with the Erlang calling convention and a single visible result,
+/// so no Erlang source reaches them.
+const SITES: &[&str] = &[
+    "select/binary",
+    "select/binary_segment",
+    "select/binary_end",
+    "select/map",
+    "select/cons",
+    "select/nil",
+    "select/literal",
+    "select/tuple",
+    "select/atom",
+    "select/float",
+    "select/int",
+    "bif/is_record",
+    "bif/safe",
+    "bif/fallible_no_ret",
+    "bif/fallible_one_ret",
+    "internal/make_fun_local",
+    "internal/make_fun_mfa",
+    "internal/unpack_env",
+    "internal/mailbox",
+    "internal/recv_peek_message",
+    "internal/recv_wait_timeout",
+    "internal/recv_marker",
+    "internal/build_stacktrace",
+    "internal/nif_start",
+    "internal/match_fail_function_clause",
+    "internal/match_fail_inlined",
+    "internal/match_fail_case_clause",
+    "internal/match_fail",
+    "internal/exception_op",
+    "internal/primop",
+];
+
+thread_local! {
+    static HITS: RefCell<BTreeSet<&'static str>> = RefCell::new(BTreeSet::new());
+}
+
+/// Records that the lowering reached `site`
+pub(super) fn hit(site: &'static str) {
+    debug_assert!(SITES.contains(&site), "{} is missing from SITES", site);
+    HITS.with(|hits| hits.borrow_mut().insert(site));
+}
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/kernel_to_ssa")
+}
+
+/// Returns the path of every `.erl` fixture, in a stable order
+fn fixtures() -> Vec<PathBuf> {
+    let mut fixtures = fs::read_dir(fixtures_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map(|ext| ext == "erl").unwrap_or_default())
+        .collect::<Vec<_>>();
+    fixtures.sort();
+    fixtures
+}
+
+/// Lowers the fixture at `path` to SSA, verifies each function, and returns them as text
+fn lower(path: &Path) -> String {
+    let codemap = Arc::new(CodeMap::new());
+    let reporter = Reporter::new();
+    let parser = Parser::new(ParseConfig::default(), codemap.clone());
+    let app = ApplicationMetadata {
+        name: Symbol::intern("golden"),
+        modules: Default::default(),
+    };
+
+    let ast = match parser.parse_file::<Module, _, _>(reporter.clone(), path) {
+        Ok(ast) => ast,
+        Err(err) => {
+            reporter.diagnostic(err.to_diagnostic());
+            reporter.print(&codemap);
+            panic!("failed to parse {}", path.display());
+        }
+    };
+    let mut passes = SemanticAnalysis::new(reporter.clone(), &app)
+        .chain(CanonicalizeSyntax::new(reporter.clone(), codemap.clone()))
+        .chain(AstToCore::new(reporter.clone()))
+        .chain(CoreToKernel::new(reporter.clone()))
+        .chain(KernelToSsa::new(reporter.clone()));
+    let module = match passes.run(ast) {
+        Ok(module) if !reporter.is_failed() => module,
+        result => {
+            reporter.print(&codemap);
+            panic!("failed to lower {}: {:?}", path.display(), result.err());
+        }
+    };
+
+    let mut out = vec![];
+    for function in module.functions.iter() {
+        if let Err(err) = verify_function(function) {
+            panic!("{} produced invalid SSA: {}", path.display(), err);
+        }
+        write_function(&mut out, function).unwrap();
+    }
+
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn lowering_matches_goldens() {
+    let bless = std::env::var_os("BLESS")
+        .map(|v| v == "1")
+        .unwrap_or_default();
+    let fixtures = fixtures();
+    assert!(
+        fixtures.len() >= 25,
+        "expected at least 25 fixtures, found {}",
+        fixtures.len()
+    );
+
+    let mut mismatched = vec![];
+    for fixture in fixtures.iter() {
+        let actual = lower(fixture);
+        let golden = fixture.with_extension("golden");
+        if bless {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+
+        let Ok(expected) = fs::read_to_string(&golden) else { panic!("{} has no golden, run with BLESS=1 to create it", fixture.display()) };
+        if expected != actual {
+            println!(
+                "{} does not match {}:\n{}",
+                fixture.display(),
+                golden.display(),
+                StrComparison::new(&expected, &actual)
+            );
+            mismatched.push(golden);
+        }
+    }
+
+    assert!(
+        mismatched.is_empty(),
+        "lowering differs from {} golden(s), see the diffs above; if the change is intended, rerun with BLESS=1 and review the goldens:\n{:#?}",
+        mismatched.len(),
+        mismatched
+    );
+}
+
+#[test]
+fn fixtures_cover_every_lowering_path() {
+    for fixture in fixtures() {
+        lower(&fixture);
+    }
+
+    let hits = HITS.with(|hits| hits.borrow().clone());
+    let missed = SITES
+        .iter()
+        .copied()
+        .filter(|site| !hits.contains(site))
+        .collect::<Vec<_>>();
+    assert!(
+        missed.is_empty(),
+        "no fixture reaches these paths in KernelToSsa: {:?}",
+        missed
+    );
+}
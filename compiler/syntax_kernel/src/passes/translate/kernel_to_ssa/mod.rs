@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use anyhow::anyhow;
 use firefly_binary::BinaryEntrySpecifier;
 use firefly_diagnostics::*;
-use firefly_intern::{symbols, Symbol};
+use firefly_intern::{symbols, Ident, Symbol};
 use firefly_number::Integer;
 use firefly_pass::Pass;
 use firefly_syntax_base::*;
@@ -15,8 +15,21 @@ use rpds::Stack;
 use crate::ir::{self as k, Expr as KExpr};
 
 mod builder;
+#[cfg(test)]
+mod golden;
+#[cfg(test)]
+mod test;
+
 use self::builder::IrBuilder;
 
+/// Records that the lowering path named `site` was taken, see `golden::SITES`
+macro_rules! covered {
+    ($site:literal) => {
+        #[cfg(test)]
+        self::golden::hit($site);
+    };
+}
+
 /// This pass is responsible for transforming the processed Kernel IR to SSA IR for code generation
 pub struct KernelToSsa {
     reporter: Reporter,
@@ -77,6 +90,7 @@ impl Pass for KernelToSsa {
         }
 
         // For every function in the module, run a function-local pass which produces the function body
+        let mut error = None;
         for (i, function) in module.functions.drain(..).enumerate() {
             let (id, sig) = functions.get(i).unwrap();
             let mut pass = LowerFunctionToSsa {
@@ -91,8 +105,17 @@ impl Pass for KernelToSsa {
                 brk: vec![],
                 recv: Stack::new(),
             };
-            let ir_function = pass.run(function)?;
-            ir_module.define_function(ir_function);
+            // Errors are reported as they are found, so keep going in order to report the
+            // errors in the remaining functions as well
+            match pass.run(function) {
+                Ok(ir_function) => ir_module.define_function(ir_function),
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        if let Some(err) = error {
+            return Err(err);
         }
 
         debug!("successfully lowered kernel module to core ir module");
@@ -232,6 +255,13 @@ impl<'m> LowerFunctionToSsa<'m> {
             }
             KExpr::Call(call) => self.lower_call(builder, call),
             KExpr::Enter(enter) => self.lower_enter(builder, enter),
+            KExpr::Bif(bif) if bif.annotations.contains(symbols::RecvMark) => {
+                // This creates a reference which a later receive starts from
+                let span = bif.span();
+                let reference = bif.ret[0].as_var().map(|v| v.name).unwrap();
+                self.lower_bif(builder, bif)?;
+                self.lower_recv_marker(builder, symbols::RecvMark, reference, span)
+            }
             KExpr::Bif(bif) => self.lower_bif(builder, bif),
             KExpr::Try(expr) => self.lower_try(builder, expr),
             KExpr::TryEnter(expr) => self.lower_try_enter(builder, expr),
@@ -289,12 +319,13 @@ impl<'m> LowerFunctionToSsa<'m> {
                 }
             }
             KExpr::LetRecGoto(k::LetRecGoto {
+                span,
+                annotations,
                 label,
                 vars,
                 box first,
                 box then,
                 ret,
-                ..
             }) => {
                 let then_block = builder.create_block();
                 for v in vars.iter() {
@@ -311,6 +342,13 @@ impl<'m> LowerFunctionToSsa<'m> {
                     );
                     builder.define_var(v.name, value);
                 }
+                // This is a receive which only needs to look at messages received after the
+                // reference was created
+                if let Some(Annotation::Vars(marked)) = annotations.get(symbols::RecvSet) {
+                    for reference in marked.iter().copied() {
+                        self.lower_recv_marker(builder, symbols::RecvSet, reference, span)?;
+                    }
+                }
                 self.labels.insert(label, then_block);
                 self.brk.push(final_block);
                 self.lower(builder, first)?;
@@ -333,6 +371,23 @@ impl<'m> LowerFunctionToSsa<'m> {
         }
     }
 
+    /// Generate a call to `erlang:recv_mark/1` or `erlang:recv_set/1` with the given reference
+    fn lower_recv_marker<'a>(
+        &mut self,
+        builder: &'a mut IrBuilder,
+        op: Symbol,
+        reference: Ident,
+        span: SourceSpan,
+    ) -> anyhow::Result<()> {
+        covered!("internal/recv_marker");
+        let callee = self
+            .module
+            .get_or_register_builtin(FunctionName::new(symbols::Erlang, op, 1));
+        let reference = self.ssa_value(builder, KExpr::Var(Var::new(reference)))?;
+        builder.ins().call(callee, &[reference], span);
+        Ok(())
+    }
+
     fn lower_if<'a>(&mut self, builder: &'a mut IrBuilder, expr: k::If) -> anyhow::Result<()> {
         let span = expr.span();
         let cond = self.ssa_value(builder, *expr.cond)?;
@@ -427,31 +482,39 @@ impl<'m> LowerFunctionToSsa<'m> {
 
         match clause.ty {
             MatchType::Binary if clause.values.len() == 1 => {
+                covered!("select/binary");
                 let clause = clause.values.pop().unwrap();
                 self.select_binary(builder, span, var, clause, type_fail, value_fail)
             }
             MatchType::BinarySegment | MatchType::BinaryInt => {
+                covered!("select/binary_segment");
                 self.select_binary_segments(builder, span, var, clause.values, type_fail)
             }
             MatchType::BinaryEnd if clause.values.len() == 1 => {
+                covered!("select/binary_end");
                 let clause = clause.values.pop().unwrap();
                 self.select_binary_end(builder, span, var, clause, type_fail)
             }
             MatchType::Map => {
+                covered!("select/map");
                 self.select_map(builder, span, var, clause.values, type_fail, value_fail)
             }
             MatchType::Cons if clause.values.len() == 1 => {
+                covered!("select/cons");
                 let clause = clause.values.pop().unwrap();
                 self.select_cons(builder, span, var, clause, type_fail, value_fail)
             }
             MatchType::Nil if clause.values.len() == 1 => {
+                covered!("select/nil");
                 let clause = clause.values.pop().unwrap();
                 self.select_nil(builder, span, var, clause, type_fail, value_fail)
             }
             MatchType::Literal => {
+                covered!("select/literal");
                 self.select_literal(builder, span, var, clause.values, type_fail, value_fail)
             }
             MatchType::Tuple => {
+                covered!("select/tuple");
                 // Value clauses should have differing arity at this stage, clauses with same
                 // arity are necessarily shadowed by the first clause. Our job here is to verify
                 // this, and order the clauses by arity, then lower this match based on a type
@@ -539,13 +602,18 @@ impl<'m> LowerFunctionToSsa<'m> {
                 let current_block = builder.current_block();
                 // Generate type test
                 let is_type = match ty {
-                    MatchType::Atom => builder.ins().is_type(Type::Term(TermType::Atom), src, span),
+                    MatchType::Atom => {
+                        covered!("select/atom");
+                        builder.ins().is_type(Type::Term(TermType::Atom), src, span)
+                    }
                     MatchType::Float => {
+                        covered!("select/float");
                         builder
                             .ins()
                             .is_type(Type::Term(TermType::Float), src, span)
                     }
                     MatchType::Int => {
+                        covered!("select/int");
                         builder
                             .ins()
                             .is_type(Type::Term(TermType::Integer), src, span)
@@ -656,6 +724,17 @@ impl<'m> LowerFunctionToSsa<'m> {
                 let tuple = self.ssa_value(builder, args.remove(0))?;
                 self.lower_test_is_record(builder, span, tuple, tag, arity, fail)
             }
+            (
+                symbols::IsFunction,
+                [_fun, KExpr::Literal(Literal {
+                    value: Lit::Integer(arity),
+                    ..
+                })],
+            ) if matches!(arity.to_usize(), Some(a) if a <= u8::MAX as usize) => {
+                let arity = arity.to_usize().unwrap();
+                let fun = self.ssa_value(builder, args.remove(0))?;
+                self.lower_test_is_function(builder, span, fun, arity, fail)
+            }
             _ => {
                 let callee = self.module.get_or_register_builtin(op);
                 let args = self.ssa_values(builder, args)?;
@@ -695,6 +774,27 @@ impl<'m> LowerFunctionToSsa<'m> {
         Ok(())
     }
 
+    fn lower_test_is_function<'a>(
+        &mut self,
+        builder: &'a mut IrBuilder,
+        span: SourceSpan,
+        fun: Value,
+        arity: usize,
+        fail: Block,
+    ) -> anyhow::Result<()> {
+        // First, check that the given value is a fun
+        let ty = Type::Term(TermType::Fun(None));
+        let is_type = builder.ins().is_type(ty.clone(), fun, span);
+        builder.ins().br_unless(is_type, fail, &[], span);
+        // Cast the input to a fun now that we know it is one
+        let fun = builder.ins().cast(fun, ty, span);
+        // Read the arity from the closure header, and compare it to the expected arity
+        let fun_arity = builder.ins().fun_arity(fun, span);
+        let has_arity = builder.ins().icmp_eq_imm(fun_arity, arity as i32, span);
+        builder.ins().br_unless(has_arity, fail, &[], span);
+        Ok(())
+    }
+
     fn lower_call<'a>(&mut self, builder: &'a mut IrBuilder, call: k::Call) -> anyhow::Result<()> {
         let span = call.span();
         match self.fail_context() {
@@ -752,6 +852,11 @@ impl<'m> LowerFunctionToSsa<'m> {
                         args.push(fun);
                         builder.ins().call(func, args.as_slice(), span)
                     }
+                    KExpr::Remote(k::Remote::Static(name)) if self.is_unexported_local(&name) => {
+                        let (apply3, args) =
+                            self.lower_unexported_remote(builder, name.item, call.args, span)?;
+                        builder.ins().call(apply3, args.as_slice(), span)
+                    }
                     KExpr::Local(name) | KExpr::Remote(k::Remote::Static(name)) => {
                         assert!(
                             !self.module.is_closure(&name),
@@ -869,6 +974,11 @@ impl<'m> LowerFunctionToSsa<'m> {
                 args.push(fun);
                 builder.ins().enter(func, args.as_slice(), span)
             }
+            KExpr::Remote(k::Remote::Static(name)) if self.is_unexported_local(&name) => {
+                let (apply3, args) =
+                    self.lower_unexported_remote(builder, name.item, call.args, span)?;
+                builder.ins().enter(apply3, args.as_slice(), span)
+            }
             KExpr::Local(name) | KExpr::Remote(k::Remote::Static(name)) => {
                 // Static call to a regular function
                 assert!(
@@ -917,6 +1027,43 @@ impl<'m> LowerFunctionToSsa<'m> {
     }
 
     ///  Generate code for a guard BIF or primop.
+    /// Returns true if `name` is a function defined in this module which is not exported
+    ///
+    /// Remote calls to such functions, e.g. `?MODULE:private()`, must raise `undef` exactly
+    /// like calls to functions which don't exist, so they cannot be lowered to direct calls.
+    fn is_unexported_local(&self, name: &FunctionName) -> bool {
+        if name.module != Some(self.module.name()) {
+            return false;
+        }
+        match self.module.get_callee(*name) {
+            Some(callee) => {
+                let signature = self.module.call_signature(callee);
+                signature.visibility.is_locally_defined() && !signature.visibility.is_public()
+            }
+            None => false,
+        }
+    }
+
+    /// Lowers a remote call to an unexported function in this module as a call to
+    /// `erlang:apply/3`, which only dispatches to exported functions
+    fn lower_unexported_remote<'a>(
+        &mut self,
+        builder: &'a mut IrBuilder,
+        name: FunctionName,
+        args: Vec<KExpr>,
+        span: SourceSpan,
+    ) -> anyhow::Result<(FuncRef, Vec<Value>)> {
+        let module = builder.ins().atom(name.module.unwrap(), span);
+        let function = builder.ins().atom(name.function, span);
+        let mut args = self.ssa_values(builder, args)?;
+        let apply3 = FunctionName::new(symbols::Erlang, symbols::Apply, 3);
+        let apply3 = self.module.get_or_register_builtin(apply3);
+        let argv = args.drain(..).rfold(builder.ins().nil(span), |tail, hd| {
+            builder.ins().cons(hd, tail, span)
+        });
+        Ok((apply3, vec![module, function, argv]))
+    }
+
     fn lower_bif<'a>(&mut self, builder: &'a mut IrBuilder, bif: k::Bif) -> anyhow::Result<()> {
         let span = bif.span();
         assert_eq!(bif.op.module, Some(symbols::Erlang));
@@ -934,12 +1081,14 @@ impl<'m> LowerFunctionToSsa<'m> {
                     ..
                 })],
             ) => {
+                covered!("bif/is_record");
                 let tag = *tag;
                 let arity = arity.to_usize().unwrap();
                 self.lower_is_record_bif(builder, bif, tag, arity)
             }
             _ if bif.op.is_safe() => {
                 // This bif can never fail, and has no side effects
+                covered!("bif/safe");
                 let callee = self.module.get_or_register_builtin(bif.op);
                 let args = self.ssa_values(builder, bif.args)?;
                 let inst = builder.ins().call(callee, args.as_slice(), span);
@@ -1000,6 +1149,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                 };
                 // If there are no rets, handle the thrown error implicitly
                 if bif.ret.is_empty() {
+                    covered!("bif/fallible_no_ret");
                     let fail = self.fail_context();
                     builder.ins().br_if(is_err, fail.block(), &[result], span);
                 } else {
@@ -1007,6 +1157,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                     match bif.ret.len() {
                         1 => {
                             // The error flag is ignored, so we need to handle it ourselves
+                            covered!("bif/fallible_one_ret");
                             let fail = self.fail_context();
                             builder.ins().br_if(is_err, fail.block(), &[result], span);
                             builder
@@ -1074,6 +1225,7 @@ impl<'m> LowerFunctionToSsa<'m> {
         match (bif.op.function, bif.args.as_slice()) {
             (symbols::MakeFun, [KExpr::Local(local), ..]) => {
                 // make_fun/2 requires special handling to convert to its corresponding core instruction
+                covered!("internal/make_fun_local");
                 let callee = builder
                     .get_callee(local.item)
                     .expect("undefined local function reference");
@@ -1095,6 +1247,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                 Ok(())
             }
             (symbols::MakeFun, _) => {
+                covered!("internal/make_fun_mfa");
                 assert_eq!(
                     bif.args.len(),
                     3,
@@ -1117,6 +1270,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                 Ok(())
             }
             (symbols::UnpackEnv, _) => {
+                covered!("internal/unpack_env");
                 assert_eq!(
                     bif.args.len(),
                     2,
@@ -1136,6 +1290,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                 Ok(())
             }
             (symbols::RemoveMessage | symbols::RecvNext, _) => {
+                covered!("internal/mailbox");
                 let callee = self.module.get_or_register_builtin(bif.op);
                 // These ops have no arguments and no results, i.e. they are not fallible, but do have a side effect on the process mailbox
                 assert_eq!(bif.ret.len(), 0);
@@ -1144,6 +1299,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                 Ok(())
             }
             (symbols::RecvPeekMessage, _) => {
+                covered!("internal/recv_peek_message");
                 let callee = self.module.get_or_register_builtin(bif.op);
                 assert_eq!(bif.ret.len(), 2);
                 // This op has a multi-value result. The first is a boolean indicating whether a message was available,
@@ -1162,6 +1318,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                 Ok(())
             }
             (symbols::RecvWaitTimeout, _) => {
+                covered!("internal/recv_wait_timeout");
                 let callee = self.module.get_or_register_builtin(bif.op);
                 assert!(bif.args.len() <= 1);
                 assert_eq!(bif.ret.len(), 1);
@@ -1175,7 +1332,8 @@ impl<'m> LowerFunctionToSsa<'m> {
                 //
                 // If the timeout was invalid, then the second result is an exception, which should then be raised based on
                 // the current failure context
-                let inst = builder.ins().call(callee, &[], span);
+                let args = self.ssa_values(builder, bif.args)?;
+                let inst = builder.ins().call(callee, args.as_slice(), span);
                 let (is_err, result) = {
                     let results = builder.inst_results(inst);
                     (results[0], results[1])
@@ -1193,6 +1351,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                 Ok(())
             }
             (symbols::BuildStacktrace, _) => {
+                covered!("internal/build_stacktrace");
                 assert_eq!(
                     bif.args.len(),
                     1,
@@ -1218,6 +1377,7 @@ impl<'m> LowerFunctionToSsa<'m> {
             }
             // The nif_start instruction is simply a marker for now, we don't have any reason to emit it to SSA
             (symbols::NifStart, _) => {
+                covered!("internal/nif_start");
                 assert_eq!(
                     bif.args.len(),
                     0,
@@ -1251,6 +1411,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                         let (module, function) = match bif.annotations.get(symbols::Inlined) {
                             None => {
                                 // This error is for the current module/function
+                                covered!("internal/match_fail_function_clause");
                                 let module = builder.ins().atom(self.signature.module, span);
                                 let function = builder.ins().atom(self.signature.name, span);
                                 (module, function)
@@ -1262,6 +1423,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                                 let Literal { value: Lit::Atom(name), .. } = elements[0] else { panic!("expected literal atom, got: {:#?}", &elements[0]) };
                                 // This error was inlined from another function which we can
                                 // extract from the annotated {Name, Arity} tuple
+                                covered!("internal/match_fail_inlined");
                                 let module = builder.ins().atom(self.signature.module, span);
                                 let function = builder.ins().atom(name, span);
                                 (module, function)
@@ -1283,6 +1445,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                         (results[0], results[1])
                     }
                     Some(symbols::CaseClause) => {
+                        covered!("internal/match_fail_case_clause");
                         // The first argument will be the type of match error (case clause),
                         // the second will be a list of the arguments
                         let mut args = self.ssa_values(builder, bif.args)?;
@@ -1300,6 +1463,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                     }
                     _ => {
                         // This is a regular match error, in which there is a single argument
+                        covered!("internal/match_fail");
                         assert_eq!(bif.args.len(), 2);
                         let reason = self.ssa_value(builder, bif.args.pop().unwrap())?;
                         let ty = self.ssa_value(builder, bif.args.pop().unwrap())?;
@@ -1339,6 +1503,7 @@ impl<'m> LowerFunctionToSsa<'m> {
             }
             // Exception builtins return a result matching the standard Erlang calling convention
             (op, _) if bif.op.is_exception_op() => {
+                covered!("internal/exception_op");
                 assert!(
                     bif.ret.len() < 2,
                     "incorrect results for builtin {}",
@@ -1373,6 +1538,7 @@ impl<'m> LowerFunctionToSsa<'m> {
                 }
             }
             _ => {
+                covered!("internal/primop");
                 let callee = self.module.get_or_register_builtin(bif.op);
                 // All other primops behave like regular function calls
                 let args = self.ssa_values(builder, bif.args)?;
@@ -1820,7 +1986,63 @@ impl<'m> LowerFunctionToSsa<'m> {
                 }
             },
             KExpr::Literal(lit) => self.lower_literal(builder, lit),
-            expr => panic!("unexpected value expression: {:#?}", &expr),
+            // The remaining cases are left behind by earlier passes which failed to flatten a
+            // nested expression into a variable. Those which can be lowered in place are
+            // recovered here, rather than failing compilation
+            KExpr::Cons(k::Cons {
+                span,
+                box head,
+                box tail,
+                ..
+            }) if is_constant(&head) && is_constant(&tail) => {
+                let head = self.ssa_value(builder, head)?;
+                let tail = self.ssa_value(builder, tail)?;
+                Ok(builder.ins().cons(head, tail, span))
+            }
+            KExpr::Tuple(k::Tuple {
+                span, elements, ..
+            }) if elements.iter().all(is_constant) => {
+                let mut elements = self.ssa_values(builder, elements)?;
+                let tuple = builder.ins().tuple_imm(elements.len(), span);
+                for (i, element) in elements.drain(..).enumerate() {
+                    builder.ins().set_element_mut(tuple, i, element, span);
+                }
+                Ok(tuple)
+            }
+            KExpr::Bif(bif) if !bif.op.is_primop() && bif.op.is_safe() => {
+                self.lower_bif_value(builder, bif)
+            }
+            expr => {
+                let message = format!(
+                    "expected a variable or literal here, but found a {} expression",
+                    expr.kind()
+                );
+                self.reporter.show_error(
+                    &format!("invalid value expression in {}", self.signature.mfa()),
+                    &[(expr.span(), message.as_str())],
+                );
+                Err(anyhow!("invalid expression"))
+            }
+        }
+    }
+
+    /// Lowers a safe bif in value position, returning its result
+    fn lower_bif_value<'a>(
+        &mut self,
+        builder: &'a mut IrBuilder,
+        bif: k::Bif,
+    ) -> anyhow::Result<Value> {
+        let span = bif.span();
+        let callee = self.module.get_or_register_builtin(bif.op);
+        let args = self.ssa_values(builder, bif.args)?;
+        let inst = builder.ins().call(callee, args.as_slice(), span);
+        let results = builder.inst_results(inst);
+        let sig = bifs::get(&bif.op).unwrap();
+        if sig.cc == CallConv::Erlang {
+            // Skip the error flag, which is never set by a safe bif
+            Ok(results[1])
+        } else {
+            Ok(results[0])
         }
     }
 
@@ -1835,6 +2057,16 @@ impl<'m> LowerFunctionToSsa<'m> {
     }
 }
 
+/// Returns true if `expr` is a literal, or a list or tuple constructed only from literals
+fn is_constant(expr: &KExpr) -> bool {
+    match expr {
+        KExpr::Literal(_) => true,
+        KExpr::Cons(k::Cons { head, tail, .. }) => is_constant(head) && is_constant(tail),
+        KExpr::Tuple(k::Tuple { elements, .. }) => elements.iter().all(is_constant),
+        _ => false,
+    }
+}
+
 // Select
 impl<'m> LowerFunctionToSsa<'m> {
     fn select_binary<'a>(
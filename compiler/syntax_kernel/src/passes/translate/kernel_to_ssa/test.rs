@@ -0,0 +1,229 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use firefly_diagnostics::*;
+use firefly_intern::{symbols, Ident, Symbol};
+use firefly_pass::Pass;
+use firefly_syntax_base::*;
+use firefly_syntax_ssa::write::write_function;
+
+use crate::ir::{self as k, Expr as KExpr};
+
+use super::KernelToSsa;
+
+/// The source text the spans in each test point into
+const SOURCE: &str = "{X, a}\nelement(1, X)\n";
+
+struct Test {
+    codemap: Arc<CodeMap>,
+    id: SourceId,
+    reporter: Reporter,
+}
+impl Test {
+    fn new() -> Self {
+        let codemap = Arc::new(CodeMap::new());
+        let id = codemap.add("nofile", SOURCE.to_string());
+
+        Self {
+            codemap,
+            id,
+            reporter: Reporter::new(),
+        }
+    }
+
+    fn span(&self, start: u32, end: u32) -> SourceSpan {
+        SourceSpan::new(
+            SourceIndex::new(self.id, ByteIndex(start)),
+            SourceIndex::new(self.id, ByteIndex(end)),
+        )
+    }
+
+    fn x(&self) -> KExpr {
+        KExpr::Var(Var::new(Ident::new(Symbol::intern("X"), self.span(1, 2))))
+    }
+
+    /// Builds `Name(X) -> return Value` for each of `functions`, with `Value` in value position
+    fn module(&self, functions: Vec<(&str, KExpr)>) -> k::Module {
+        let span = self.span(0, SOURCE.len() as u32);
+        let name = Ident::new(Symbol::intern("test"), span);
+        let functions = functions
+            .into_iter()
+            .map(|(function, value)| k::Function {
+                span,
+                annotations: Annotations::default(),
+                name: FunctionName::new(name.name, Symbol::intern(function), 1),
+                vars: vec![Var::new(Ident::new(Symbol::intern("X"), span))],
+                body: Box::new(KExpr::Return(k::Return::new(span, vec![value]))),
+            })
+            .collect();
+
+        k::Module {
+            span,
+            annotations: Annotations::default(),
+            name,
+            compile: CompileOptions::default(),
+            on_load: None,
+            exports: HashSet::new(),
+            nifs: HashSet::new(),
+            functions,
+        }
+    }
+
+    /// Lowers `module`, returning the SSA of each function as text
+    fn lower(&self, module: k::Module) -> anyhow::Result<String> {
+        let module = KernelToSsa::new(self.reporter.clone()).run(module)?;
+        let mut out = vec![];
+        for function in module.functions.iter() {
+            write_function(&mut out, function).unwrap();
+        }
+
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    /// Returns the message of each reported diagnostic, with the source text under its labels
+    fn reported(&self) -> Vec<(String, Vec<String>)> {
+        self.reporter
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let labels = diagnostic
+                    .labels
+                    .iter()
+                    .map(|label| {
+                        let file = self.codemap.get(label.file_id).unwrap();
+                        file.source_slice(label.range.clone()).unwrap().to_string()
+                    })
+                    .collect();
+
+                (diagnostic.message.clone(), labels)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn literal_tuple_in_value_position_is_lowered_as_a_literal() {
+    let test = Test::new();
+    let span = test.span(0, 6);
+    // {a, [1]}
+    let value = KExpr::Tuple(k::Tuple::new(
+        span,
+        vec![
+            KExpr::Literal(Literal::atom(span, Symbol::intern("a"))),
+            KExpr::Cons(k::Cons::new(
+                span,
+                KExpr::Literal(Literal::integer(span, 1)),
+                KExpr::Literal(Literal::nil(span)),
+            )),
+        ],
+    ));
+
+    let ssa = test.lower(test.module(vec![("f", value)])).unwrap();
+
+    assert!(ssa.contains("= tuple 2"), "{}", ssa);
+    assert!(ssa.contains("= cons "), "{}", ssa);
+    assert_eq!(ssa.matches("tuple.set.mut").count(), 2, "{}", ssa);
+    assert!(test.reported().is_empty());
+}
+
+#[test]
+fn literal_cons_in_value_position_is_lowered_as_a_literal() {
+    let test = Test::new();
+    let span = test.span(0, 6);
+    // [1 | [{}]]
+    let value = KExpr::Cons(k::Cons::new(
+        span,
+        KExpr::Literal(Literal::integer(span, 1)),
+        KExpr::Cons(k::Cons::new(
+            span,
+            KExpr::Tuple(k::Tuple::new(span, vec![])),
+            KExpr::Literal(Literal::nil(span)),
+        )),
+    ));
+
+    let ssa = test.lower(test.module(vec![("f", value)])).unwrap();
+
+    assert_eq!(ssa.matches("= cons ").count(), 2, "{}", ssa);
+    assert!(ssa.contains("= tuple 0"), "{}", ssa);
+    assert!(test.reported().is_empty());
+}
+
+#[test]
+fn safe_bif_in_value_position_is_lowered_inline() {
+    let test = Test::new();
+    let span = test.span(7, 20);
+    let op = FunctionName::new(symbols::Erlang, symbols::IsAtom, 1);
+    let value = KExpr::Bif(k::Bif::new(span, op, vec![test.x()]));
+
+    let ssa = test.lower(test.module(vec![("f", value)])).unwrap();
+
+    assert!(ssa.contains("call erlang:is_atom/1("), "{}", ssa);
+    assert!(test.reported().is_empty());
+}
+
+#[test]
+fn non_literal_tuple_in_value_position_is_reported() {
+    let test = Test::new();
+    let span = test.span(0, 6);
+    let value = KExpr::Tuple(k::Tuple::new(
+        span,
+        vec![
+            test.x(),
+            KExpr::Literal(Literal::atom(span, Symbol::intern("a"))),
+        ],
+    ));
+
+    assert!(test.lower(test.module(vec![("f", value)])).is_err());
+    assert_eq!(
+        test.reported(),
+        vec![(
+            "invalid value expression in test:f/1".to_string(),
+            vec!["{X, a}".to_string()]
+        )]
+    );
+}
+
+#[test]
+fn fallible_bif_in_value_position_is_reported() {
+    let test = Test::new();
+    let span = test.span(7, 20);
+    let op = FunctionName::new(symbols::Erlang, symbols::Element, 2);
+    let index = KExpr::Literal(Literal::integer(span, 1));
+    let value = KExpr::Bif(k::Bif::new(span, op, vec![index, test.x()]));
+
+    assert!(test.lower(test.module(vec![("g", value)])).is_err());
+    let reported = test.reported();
+    assert_eq!(reported.len(), 1);
+    let (message, labels) = &reported[0];
+    assert_eq!(message, "invalid value expression in test:g/1");
+    assert_eq!(labels, &["element(1, X)".to_string()]);
+}
+
+#[test]
+fn functions_after_an_invalid_value_expression_are_still_lowered() {
+    let test = Test::new();
+    let bif = |span| {
+        let op = FunctionName::new(symbols::Erlang, symbols::Element, 2);
+        let index = KExpr::Literal(Literal::integer(span, 1));
+        KExpr::Bif(k::Bif::new(span, op, vec![index, test.x()]))
+    };
+    let module = test.module(vec![
+        ("f", bif(test.span(7, 20))),
+        ("g", test.x()),
+        ("h", bif(test.span(7, 20))),
+    ]);
+
+    assert!(test.lower(module).is_err());
+    let messages: Vec<String> = test
+        .reported()
+        .into_iter()
+        .map(|(message, _)| message)
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "invalid value expression in test:f/1",
+            "invalid value expression in test:h/1"
+        ]
+    );
+}
@@ -0,0 +1,8 @@
+-module(bif_fallible).
+-export([add/2, second/1]).
+
+add(X, Y) -> X + Y.
+
+second(T) ->
+    E = element(2, T),
+    {ok, E}.
@@ -0,0 +1,6 @@
+-module(bif_for_effect).
+-export([check_tuple/1]).
+
+check_tuple(T) ->
+    _ = tuple_size(T),
+    ok.
@@ -0,0 +1,6 @@
+-module(bif_is_record).
+-export([check/1]).
+
+check(X) ->
+    IsPoint = is_record(X, point, 3),
+    {checked, IsPoint}.
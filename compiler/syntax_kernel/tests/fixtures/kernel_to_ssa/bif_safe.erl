@@ -0,0 +1,10 @@
+-module(bif_safe).
+-export([compare/2, me/0]).
+
+compare(X, Y) ->
+    Equal = X =:= Y,
+    Less = X < Y,
+    {Equal, Less}.
+
+me() ->
+    {self(), node()}.
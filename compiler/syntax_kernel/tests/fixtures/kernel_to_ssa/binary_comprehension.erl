@@ -0,0 +1,5 @@
+-module(binary_comprehension).
+-export([double/1]).
+
+double(Bin) ->
+    << <<(B * 2):8>> || <<B:8>> <= Bin >>.
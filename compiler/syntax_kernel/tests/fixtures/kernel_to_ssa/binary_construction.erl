@@ -0,0 +1,6 @@
+-module(binary_construction).
+-export([encode/2]).
+
+encode(Tag, Payload) ->
+    Size = byte_size(Payload),
+    <<Tag:8, Size:16/big, Payload/binary, "end">>.
@@ -0,0 +1,12 @@
+-module(calls).
+-export([local/1, remote/1, dynamic/3, closure/2]).
+
+local(X) -> helper(X).
+
+remote(L) -> lists:reverse(L).
+
+dynamic(M, F, A) -> M:F(A).
+
+closure(F, X) -> {result, F(X)}.
+
+helper(X) -> {helper, X}.
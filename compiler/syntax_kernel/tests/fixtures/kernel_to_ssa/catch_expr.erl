@@ -0,0 +1,6 @@
+-module(catch_expr).
+-export([attempt/1]).
+
+attempt(F) ->
+    Result = (catch F()),
+    {caught, Result}.
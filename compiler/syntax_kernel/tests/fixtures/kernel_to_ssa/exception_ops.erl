@@ -0,0 +1,9 @@
+-module(exception_ops).
+-export([fail/1, raise/3]).
+
+fail(error) -> error(boom);
+fail(throw) -> throw(boom);
+fail(exit) -> exit(boom).
+
+raise(Class, Reason, Stacktrace) ->
+    erlang:raise(Class, Reason, Stacktrace).
@@ -0,0 +1,6 @@
+-module(guard_bif_failure).
+-export([first/1]).
+
+first(T) when element(1, T) =:= ok -> ok;
+first(T) when length(T) > 1 -> many;
+first(_) -> other.
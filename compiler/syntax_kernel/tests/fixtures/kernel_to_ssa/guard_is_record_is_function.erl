@@ -0,0 +1,8 @@
+-module(guard_is_record_is_function).
+-export([call/2]).
+
+-record(handler, {fun1}).
+
+call(H, X) when is_record(H, handler) -> (H#handler.fun1)(X);
+call(F, X) when is_function(F, 1) -> F(X);
+call(_, _) -> error.
@@ -0,0 +1,7 @@
+-module(guard_type_tests).
+-export([kind/1]).
+
+kind(X) when is_integer(X), X > 0 -> positive;
+kind(X) when is_integer(X); is_float(X) -> number;
+kind(X) when is_atom(X) andalso X =/= undefined -> atom;
+kind(_) -> other.
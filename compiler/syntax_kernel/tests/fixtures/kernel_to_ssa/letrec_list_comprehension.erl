@@ -0,0 +1,4 @@
+-module(letrec_list_comprehension).
+-export([squares/1]).
+
+squares(L) -> [X * X || X <- L, X > 0].
@@ -0,0 +1,8 @@
+-module(make_fun_local).
+-export([adder/1, apply_adder/2]).
+
+adder(N) -> fun(X) -> X + N end.
+
+apply_adder(N, X) ->
+    F = adder(N),
+    F(X).
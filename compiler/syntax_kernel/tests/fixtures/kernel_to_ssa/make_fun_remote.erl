@@ -0,0 +1,6 @@
+-module(make_fun_remote).
+-export([dynamic/2, static/0]).
+
+dynamic(M, F) -> fun M:F/1.
+
+static() -> fun lists:reverse/1.
@@ -0,0 +1,10 @@
+-module(map_ops).
+-export([new/1, put/3, update/2, get/2]).
+
+new(X) -> #{key => X, other => 1}.
+
+put(Map, K, V) -> Map#{K => V}.
+
+update(Map, V) -> Map#{key := V}.
+
+get(K, Map) -> maps:get(K, Map).
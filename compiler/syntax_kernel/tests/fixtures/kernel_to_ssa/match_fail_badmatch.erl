@@ -0,0 +1,6 @@
+-module(match_fail_badmatch).
+-export([unwrap/1]).
+
+unwrap(X) ->
+    {ok, Value} = X,
+    Value.
@@ -0,0 +1,7 @@
+-module(match_fail_case_clause).
+-export([unwrap/1]).
+
+unwrap(X) ->
+    case X of
+        {ok, Value} -> Value
+    end.
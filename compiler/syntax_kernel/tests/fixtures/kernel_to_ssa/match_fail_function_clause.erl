@@ -0,0 +1,4 @@
+-module(match_fail_function_clause).
+-export([positive/1]).
+
+positive(X) when X > 0 -> X.
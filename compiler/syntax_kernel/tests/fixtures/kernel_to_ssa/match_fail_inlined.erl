@@ -0,0 +1,5 @@
+-module(match_fail_inlined).
+-export([matcher/1]).
+
+matcher(Expected) ->
+    fun(ok) -> Expected end.
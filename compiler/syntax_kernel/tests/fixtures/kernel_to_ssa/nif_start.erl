@@ -0,0 +1,6 @@
+-module(nif_start).
+-export([native/1]).
+-nifs([native/1]).
+
+native(_X) ->
+    erlang:nif_error(not_loaded).
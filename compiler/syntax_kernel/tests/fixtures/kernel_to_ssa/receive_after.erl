@@ -0,0 +1,10 @@
+-module(receive_after).
+-export([wait/1]).
+
+wait(Timeout) ->
+    receive
+        {ok, Value} -> Value;
+        {error, _} = Error -> Error
+    after Timeout ->
+        timeout
+    end.
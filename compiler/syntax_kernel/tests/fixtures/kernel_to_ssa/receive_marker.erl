@@ -0,0 +1,20 @@
+-module(receive_marker).
+-export([call/2, call_or_any/2]).
+
+%% Every clause matches the new reference, so the receive starts from its marker
+call(Pid, Request) ->
+    Ref = make_ref(),
+    Pid ! {self(), Ref, Request},
+    receive
+        {Ref, Reply} -> Reply;
+        {'DOWN', Ref, _, _, Reason} -> {error, Reason}
+    end.
+
+%% A clause matches messages without the reference, so it looks at every message
+call_or_any(Pid, Request) ->
+    Ref = make_ref(),
+    Pid ! {self(), Ref, Request},
+    receive
+        {Ref, Reply} -> Reply;
+        Other -> Other
+    end.
@@ -0,0 +1,9 @@
+-module(receive_selective).
+-export([flush/0]).
+
+flush() ->
+    receive
+        _ -> flush()
+    after 0 ->
+        ok
+    end.
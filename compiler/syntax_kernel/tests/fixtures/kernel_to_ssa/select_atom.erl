@@ -0,0 +1,6 @@
+-module(select_atom).
+-export([to_int/1]).
+
+to_int(one) -> 1;
+to_int(two) -> 2;
+to_int(_) -> other.
@@ -0,0 +1,9 @@
+-module(select_binary).
+-export([header/1, last_byte/1]).
+
+header(<<Tag:8, Size:16/big, Rest/binary>>) -> {Tag, Size, Rest};
+header(<<>>) -> empty;
+header(_) -> invalid.
+
+last_byte(<<Byte:8>>) -> Byte;
+last_byte(<<_:8, Rest/binary>>) -> last_byte(Rest).
@@ -0,0 +1,6 @@
+-module(select_binary_int).
+-export([command/1]).
+
+command(<<1:8, Arg:32>>) -> {ping, Arg};
+command(<<2:8, Arg/binary>>) -> {echo, Arg};
+command(_) -> unknown.
@@ -0,0 +1,6 @@
+-module(select_float).
+-export([classify/1]).
+
+classify(0.0) -> zero;
+classify(1.5) -> one_and_a_half;
+classify(_) -> other.
@@ -0,0 +1,6 @@
+-module(select_int).
+-export([to_atom/1]).
+
+to_atom(1) -> one;
+to_atom(2) -> two;
+to_atom(_) -> other.
@@ -0,0 +1,5 @@
+-module(select_literal).
+-export([is_origin/1]).
+
+is_origin({point, [0, 0]}) -> true;
+is_origin(_) -> false.
@@ -0,0 +1,6 @@
+-module(select_map).
+-export([name/1]).
+
+name(#{name := Name, age := _}) -> {person, Name};
+name(#{name := Name}) -> Name;
+name(_) -> anonymous.
@@ -0,0 +1,5 @@
+-module(select_nil_cons).
+-export([len/1]).
+
+len([]) -> 0;
+len([_ | Tail]) -> 1 + len(Tail).
@@ -0,0 +1,7 @@
+-module(select_tuple).
+-export([swap/1]).
+
+swap({A}) -> {A};
+swap({A, B}) -> {B, A};
+swap({A, _, C}) -> {C, A};
+swap(_) -> none.
@@ -0,0 +1,11 @@
+-module(try_after).
+-export([with_cleanup/1]).
+
+with_cleanup(F) ->
+    try
+        F()
+    after
+        cleanup()
+    end.
+
+cleanup() -> ok.
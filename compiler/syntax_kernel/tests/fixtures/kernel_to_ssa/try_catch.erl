@@ -0,0 +1,12 @@
+-module(try_catch).
+-export([safe_div/2]).
+
+safe_div(X, Y) ->
+    Result =
+        try X div Y of
+            Z -> {ok, Z}
+        catch
+            error:badarith:Stacktrace -> {error, Stacktrace};
+            Class:Reason -> {Class, Reason}
+        end,
+    {done, Result}.
@@ -0,0 +1,9 @@
+-module(try_enter).
+-export([call/1]).
+
+call(F) ->
+    try
+        F()
+    catch
+        throw:Thrown -> {thrown, Thrown}
+    end.
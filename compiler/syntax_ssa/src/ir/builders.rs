@@ -565,6 +565,16 @@ pub trait InstBuilder<'f>: InstBuilderBase<'f> {
         dfg.first_result(inst)
     }
 
+    fn fun_arity(self, fun: Value, span: SourceSpan) -> Value {
+        let (inst, dfg) = self.Unary(
+            Opcode::FunArity,
+            Type::Primitive(PrimitiveType::I32),
+            fun,
+            span,
+        );
+        dfg.first_result(inst)
+    }
+
     fn br(mut self, block: Block, args: &[Value], span: SourceSpan) -> Inst {
         let mut vlist = ValueList::default();
         {
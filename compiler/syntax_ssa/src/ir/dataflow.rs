@@ -320,6 +320,10 @@ impl DataFlowGraph {
                     self.append_result(inst, ty);
                     1
                 }
+                Opcode::FunArity => {
+                    self.append_result(inst, Type::Primitive(PrimitiveType::I32));
+                    1
+                }
                 Opcode::RecvStart => {
                     // This primop returns a receive context
                     self.append_result(inst, Type::RecvContext);
@@ -311,6 +311,7 @@ pub enum Opcode {
     // Closures
     MakeFun,
     UnpackEnv,
+    FunArity,
     // Primops
     RecvStart,
     RecvNext,
@@ -414,6 +415,8 @@ impl Opcode {
             Self::MakeFun => 0,
             // Unpacking a closure environment requires the closure value
             Self::UnpackEnv => 1,
+            // Reading the arity of a closure requires the closure value
+            Self::FunArity => 1,
             // Calls are entirely variable
             Self::Call | Self::CallIndirect | Self::Enter | Self::EnterIndirect => 0,
             // Ifs have a single argument, the conditional
@@ -513,6 +516,7 @@ impl fmt::Display for Opcode {
             Self::SetElementMut => f.write_str("tuple.set.mut"),
             Self::MakeFun => f.write_str("fun.make"),
             Self::UnpackEnv => f.write_str("fun.env.get"),
+            Self::FunArity => f.write_str("fun.arity"),
             Self::RecvStart => f.write_str("recv.start"),
             Self::RecvNext => f.write_str("recv.next"),
             Self::RecvPeek => f.write_str("recv.peek"),
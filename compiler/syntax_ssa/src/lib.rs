@@ -1,5 +1,6 @@
 #![deny(warnings)]
 pub mod ir;
+pub mod verify;
 pub mod write;
 
 pub use self::ir::*;
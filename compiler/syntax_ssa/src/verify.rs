@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use anyhow::bail;
+
+use super::{Block, BranchInfo, Function, Inst, Value};
+
+/// Checks the structural invariants of `func` that later passes rely on:
+///
+/// * every block in the layout is non-empty, and ends with a terminator
+/// * every branch targets a block in the layout, passing one argument per block parameter
+/// * every value used by an instruction is defined in `func`, as a block parameter or a result
+pub fn verify_function(func: &Function) -> anyhow::Result<()> {
+    let dfg = &func.dfg;
+    let name = func.signature.mfa();
+
+    let mut defined = HashSet::<Value>::new();
+    for (block, _) in dfg.blocks() {
+        defined.extend(dfg.block_params(block).iter().copied());
+        for inst in dfg.block_insts(block) {
+            defined.extend(dfg.inst_results(inst).iter().copied());
+        }
+    }
+
+    for (block, block_data) in dfg.blocks() {
+        let Some(last) = block_data.last() else { bail!("{}: {} is empty", name, block) };
+        if !dfg[last].opcode().is_terminator() {
+            bail!(
+                "{}: {} does not end with a terminator, found {}",
+                name,
+                block,
+                dfg[last].opcode()
+            );
+        }
+
+        for inst in block_data.insts() {
+            for arg in dfg[inst].arguments(&dfg.value_lists) {
+                if !defined.contains(arg) {
+                    bail!(
+                        "{}: {} in {} uses undefined value {}",
+                        name,
+                        inst,
+                        block,
+                        arg
+                    );
+                }
+            }
+            match dfg.analyze_branch(inst) {
+                BranchInfo::NotABranch => (),
+                BranchInfo::SingleDest(dest, args) => {
+                    verify_branch(func, block, inst, dest, args)?;
+                }
+                BranchInfo::MultiDest(targets) => {
+                    for target in targets.iter() {
+                        verify_branch(func, block, inst, target.destination, target.args)?;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn verify_branch(
+    func: &Function,
+    block: Block,
+    inst: Inst,
+    dest: Block,
+    args: &[Value],
+) -> anyhow::Result<()> {
+    let name = func.signature.mfa();
+    if !func.dfg.is_block_inserted(dest) {
+        bail!(
+            "{}: {} in {} branches to {}, which is not in the function",
+            name,
+            inst,
+            block,
+            dest
+        );
+    }
+    let expected = func.dfg.num_block_params(dest);
+    if args.len() != expected {
+        bail!(
+            "{}: {} in {} passes {} arguments to {}, which expects {}",
+            name,
+            inst,
+            block,
+            args.len(),
+            dest,
+            expected
+        );
+    }
+
+    Ok(())
+}
@@ -42,6 +42,7 @@ use core::marker::PhantomData;
 use thiserror::Error;
 
 use super::term::prelude::*;
+use crate::erts::process::alloc::StackAllocError;
 use crate::erts::process::trace::Trace;
 use crate::erts::string::InvalidEncodingNameError;
 
@@ -87,9 +88,25 @@ impl From<TermEncodingError> for Exception {
 // Runtime exception type conversions
 impl From<anyhow::Error> for Exception {
     fn from(err: anyhow::Error) -> Self {
+        // Running out of process stacks is a system limit, not a bad argument
+        if let Some(StackAllocError::Exhausted { .. }) = err.downcast_ref::<StackAllocError>() {
+            return Self::Runtime(system_limit(Trace::capture(), Some(ArcError::new(err))));
+        }
+
         InternalException::from(ArcError::new(err)).into()
     }
 }
+impl From<StackAllocError> for Exception {
+    fn from(err: StackAllocError) -> Self {
+        match err {
+            StackAllocError::Exhausted { .. } => Self::Runtime(system_limit(
+                Trace::capture(),
+                Some(ArcError::from_err(err)),
+            )),
+            StackAllocError::Alloc => Alloc::new().into(),
+        }
+    }
+}
 impl From<InvalidEncodingNameError> for Exception {
     fn from(err: InvalidEncodingNameError) -> Self {
         InternalException::from(ArcError::from_err(err)).into()
@@ -53,6 +53,11 @@ pub fn badmap(
     self::error(reason, None, trace, source)
 }
 
+#[inline]
+pub fn system_limit(trace: Arc<Trace>, source: Option<ArcError>) -> RuntimeException {
+    self::error(atom!(system_limit), None, trace, source)
+}
+
 #[inline]
 pub fn undef(trace: Arc<Trace>, source: Option<ArcError>) -> Exception {
     Exception::Runtime(self::exit(atom!(undef), trace, source))
@@ -397,10 +397,14 @@ impl HeapFragment {
 impl Drop for HeapFragment {
     fn drop(&mut self) {
         assert!(!self.link.is_linked());
-        // Check if the contained value needs to have its destructor run
+        // Check if the contained value needs to have its destructor run, unless it
+        // was moved out of the fragment by a collection, in which case it has been
+        // replaced by a move marker, and the moved value is owned by the process heap
         let ptr = self.raw.base.as_ptr() as *mut Term;
         let term = unsafe { *ptr };
-        term.release();
+        if !term.is_boxed() {
+            term.release();
+        }
         // Actually deallocate the memory backing this fragment
         let (layout, _offset) = Layout::new::<Self>().extend(self.raw.layout()).unwrap();
         unsafe {
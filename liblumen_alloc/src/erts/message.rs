@@ -10,7 +10,7 @@ use intrusive_collections::{LinkedListLink, UnsafeRef};
 /// to remain stable. The `link` is two words, and `data` is three words. On 64-bit
 /// architectures, data is equivalent to `{i32, [5 x i32]}`, the first i32 is the
 /// enum discriminator, the second i32 is padding, and starting at the 3rd i32 is
-/// either a single Term, or the HeapFragment struct.
+/// either a single Term, or the HeapFragment struct, whose first field is the Term.
 #[derive(Clone)]
 #[repr(C)]
 pub struct Message {
@@ -29,12 +29,13 @@ impl Message {
         match self.data {
             MessageData::Process(data) => data,
             MessageData::HeapFragment(HeapFragment { data, .. }) => data,
+            MessageData::Mailbox(HeapFragment { data, .. }) => data,
         }
     }
 
     pub fn is_off_heap(&self) -> bool {
         match self.data {
-            MessageData::HeapFragment(_) => true,
+            MessageData::HeapFragment(_) | MessageData::Mailbox(_) => true,
             _ => false,
         }
     }
@@ -52,12 +53,17 @@ pub enum MessageData {
     /// A message whose `message` `Term` had to be allocated in `heap` outside of the receiving
     /// `Process` because the `Process`'s `Heap` was locked.
     HeapFragment(HeapFragment),
+    /// A message whose `message` `Term` is allocated in `heap` owned by the mailbox, because the
+    /// receiving `Process` keeps its message queue off heap.  The `heap` is only attached to the
+    /// `Process` once the message is received, so that garbage collection does not scan the queue.
+    Mailbox(HeapFragment),
 }
 
 /// This struct is used to represent data which is stored in a heap fragment
 ///
 /// The boxed term will have all its data on the linked heap fragment. The
-/// heap fragment will be attached to a processes' off_heap list
+/// heap fragment will be attached to a processes' off_heap list, or, for
+/// `MessageData::Mailbox`, owned by the mailbox until the message is received
 #[derive(Debug, Clone)]
 #[repr(C)]
 pub struct HeapFragment {
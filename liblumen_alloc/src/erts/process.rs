@@ -8,8 +8,10 @@ mod frames;
 pub mod gc;
 mod heap;
 mod mailbox;
+mod max_heap_size;
 mod monitor;
 pub mod priority;
+mod signal;
 pub mod trace;
 
 use std::cell::RefCell;
@@ -20,7 +22,7 @@ use std::mem;
 use std::ops::DerefMut;
 use std::ptr::{self, NonNull};
 use std::str::Chars;
-use std::sync::atomic::{AtomicU16, AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use anyhow::*;
@@ -30,6 +32,7 @@ use intrusive_collections::{LinkedList, UnsafeRef};
 
 use liblumen_core::alloc::Layout;
 use liblumen_core::locks::{Mutex, MutexGuard, RwLock, SpinLock};
+use liblumen_core::sys::sysconf;
 
 use crate::borrow::CloneToProcess;
 use crate::erts;
@@ -45,18 +48,21 @@ use super::*;
 
 use self::alloc::VirtualAllocator;
 use self::alloc::{Heap, HeapAlloc, TermAlloc};
-use self::alloc::{StackAlloc, StackPrimitives};
+use self::alloc::{StackAlloc, StackAllocError, StackAllocator, StackPrimitives};
 use self::ffi::{set_process_signal, ErlangResult, ProcessSignal};
 pub use self::frame::{Frame, Native};
 pub use self::frame_with_arguments::FrameWithArguments;
 pub use self::frames::{Frames, StackTrace};
 use self::gc::{GcError, RootSet};
+use self::signal::{Queued, SignalQueue};
 
 pub use self::flags::*;
-pub use self::heap::ProcessHeap;
+pub use self::heap::{Collections, ProcessHeap};
 pub use self::mailbox::*;
+pub use self::max_heap_size::MaxHeapSize;
 pub use self::monitor::Monitor;
 pub use self::priority::Priority;
+pub use self::signal::{Signal, SignalData};
 
 // 4000 in [BEAM](https://github.com/erlang/otp/blob/61ebe71042fce734a06382054690d240ab027409/erts/emulator/beam/erl_vm.h#L39)
 cfg_if::cfg_if! {
@@ -67,6 +73,20 @@ cfg_if::cfg_if! {
   }
 }
 
+/// The number of minor collections a process does before a full sweep, unless spawned with
+/// `fullsweep_after`
+pub const DEFAULT_FULLSWEEP_AFTER: usize = 65535;
+
+/// The reductions of all processes, including those that have exited, that have been added to
+/// their `total_reductions`
+static SYSTEM_REDUCTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of reductions executed by all processes since the system started, not
+/// counting the current run of each running process
+pub fn system_reductions() -> u64 {
+    SYSTEM_REDUCTIONS.load(Ordering::Relaxed)
+}
+
 #[derive(Clone, Debug, Default)]
 #[repr(C)]
 #[cfg(all(unix, target_arch = "x86_64"))]
@@ -115,20 +135,20 @@ unsafe impl Sync for CalleeSavedRegisters {}
 pub struct Process {
     /// ID of the scheduler that is running the process
     scheduler_id: Mutex<Option<scheduler::ID>>,
-    /// The priority of the process in `scheduler`.
-    pub priority: Priority,
+    /// The priority of the process in `scheduler`, which `process_flag(priority, Level)` changes
+    priority: Mutex<Priority>,
     /// Process flags, e.g. `Process.flag/1`
     flags: AtomicProcessFlags,
     /// Minimum size of the heap that this process will start with
     min_heap_size: usize,
-    /// The maximum size of the heap allowed for this process
-    max_heap_size: usize,
+    /// The maximum size of the heap allowed for this process, and what happens when it's exceeded
+    max_heap_size: Mutex<MaxHeapSize>,
     /// Minimum virtual heap size for this process
     min_vheap_size: usize,
     /// The percentage of used to unused space at which a collection is triggered
     gc_threshold: f64,
     /// The maximum number of minor collections before a full sweep occurs
-    max_gen_gcs: usize,
+    max_gen_gcs: AtomicUsize,
     /// off-heap allocations
     off_heap: SpinLock<LinkedList<HeapFragmentAdapter>>,
     off_heap_size: AtomicUsize,
@@ -143,9 +163,24 @@ pub struct Process {
     /// The number of reductions in the current `run`.  `code` MUST return when `run_reductions`
     /// exceeds `MAX_REDUCTIONS_PER_RUN`.
     run_reductions: AtomicU16,
+    /// The reductions by which the last run went over `MAX_REDUCTIONS_PER_RUN`, which come out of
+    /// the budget of the next run, as natives that can't yield may overrun their budget.
+    overrun_reductions: AtomicU16,
     pub total_reductions: AtomicU64,
+    /// The value of each kind of statistic when it was last returned to this process by
+    /// `erlang:statistics/1`, so that the amount since the last call can be returned
+    statistics_since_last: Mutex<HashMap<Atom, u64>>,
     pub frames: Mutex<Frames>,
     pub status: RwLock<Status>,
+    /// An exit signalled by another process while this process was running, which only takes
+    /// effect once it stops running, as only the process itself changes its status while running
+    pending_exit: Mutex<Option<RuntimeException>>,
+    /// How many times the process has been suspended by `erlang:suspend_process/1` and not yet
+    /// resumed.  Its scheduler doesn't run it while this isn't 0.
+    suspend_count: AtomicUsize,
+    /// The `[module, function, arguments]` the process must be restarted with, instead of
+    /// resuming its stack, after it hibernated
+    hibernation: Mutex<Option<[Term; 3]>>,
     pub registered_name: RwLock<Option<Atom>>,
     /// Pids of processes that are linked to this process and need to be exited when this process
     /// exits
@@ -156,6 +191,12 @@ pub struct Process {
     /// Maps monitor references to the PID of the process being monitored by this process.
     pub monitored_pid_by_reference: DashMap<Reference, Pid>,
     pub mailbox: Mutex<RefCell<Mailbox>>,
+    /// Signals from other processes, and messages sent behind them, which the scheduler of the
+    /// process handles before running it
+    signal_queue: Mutex<SignalQueue>,
+    /// Whether `signal_queue` is busy, which `wait` checks without locking it, as the mailbox may
+    /// be locked by then, while senders lock the mailbox with `signal_queue` locked
+    signals_pending: AtomicBool,
     pub registers: CalleeSavedRegisters,
     pub stack: Mutex<alloc::Stack>,
     // process heap, cache line aligned to avoid false sharing with rest of struct
@@ -187,27 +228,34 @@ impl Process {
         Self {
             flags: AtomicProcessFlags::new(ProcessFlags::Default),
             min_heap_size: heap_size,
-            max_heap_size: 0,
+            max_heap_size: Default::default(),
             min_vheap_size: 0,
             gc_threshold: 0.75,
-            max_gen_gcs: 65535,
+            max_gen_gcs: AtomicUsize::new(DEFAULT_FULLSWEEP_AFTER),
             off_heap,
             off_heap_size: AtomicUsize::new(0),
             dictionary: Default::default(),
             pid,
             status: Default::default(),
+            pending_exit: Default::default(),
+            suspend_count: AtomicUsize::new(0),
+            hibernation: Default::default(),
             mailbox: Default::default(),
+            signal_queue: Default::default(),
+            signals_pending: AtomicBool::new(false),
             heap: Mutex::new(heap),
             stack: Default::default(),
             registers: Default::default(),
             frames: Default::default(),
             scheduler_id: Mutex::new(None),
-            priority,
+            priority: Mutex::new(priority),
             parent_pid,
             group_leader_pid: Mutex::new(group_leader_pid),
             initial_module_function_arity,
             run_reductions: Default::default(),
+            overrun_reductions: Default::default(),
             total_reductions: Default::default(),
+            statistics_since_last: Default::default(),
             registered_name: Default::default(),
             linked_pid_set: Default::default(),
             monitor_by_reference: Default::default(),
@@ -215,13 +263,24 @@ impl Process {
         }
     }
 
+    /// The number of pages of native stack requested for processes created with `new_with_stack`
+    pub const STACK_PAGES: usize = 32;
+
+    /// The size of the native stack requested for processes created with `new_with_stack`
+    pub fn stack_size_hint() -> usize {
+        Self::STACK_PAGES * sysconf::pagesize()
+    }
+
+    /// Like `new`, but also allocates a native stack for the process from `stack_allocator`
     pub fn new_with_stack(
         priority: Priority,
         parent: Option<&Self>,
         initial_module_function_arity: ModuleFunctionArity,
         heap: *mut Term,
         heap_size: usize,
-    ) -> AllocResult<Self> {
+        stack_allocator: &dyn StackAllocator,
+    ) -> Result<Self, StackAllocError> {
+        // The process takes ownership of the heap, so that it is freed if there is no stack for it
         let mut p = Self::new(
             priority,
             parent,
@@ -229,7 +288,7 @@ impl Process {
             heap,
             heap_size,
         );
-        p.stack = Mutex::new(self::alloc::stack(32)?);
+        p.stack = Mutex::new(stack_allocator.allocate(Self::stack_size_hint())?);
         Ok(p)
     }
 
@@ -243,6 +302,19 @@ impl Process {
         *self.scheduler_id.lock() = Some(scheduler_id);
     }
 
+    /// Returns the priority the process is scheduled with
+    pub fn priority(&self) -> Priority {
+        *self.priority.lock()
+    }
+
+    /// Sets the priority of the process, returning its previous priority
+    ///
+    /// A process already in a run queue stays in the queue for its previous priority, so this
+    /// should be called through its scheduler, which moves it.
+    pub fn set_priority(&self, priority: Priority) -> Priority {
+        mem::replace(&mut self.priority.lock(), priority)
+    }
+
     // Flags
 
     pub fn are_flags_set(&self, flags: ProcessFlags) -> bool {
@@ -277,6 +349,23 @@ impl Process {
         self.are_flags_set(ProcessFlags::TrapExit)
     }
 
+    /// Sets whether messages are kept outside of the heap until received, returning the old value
+    pub fn message_queue_off_heap(&self, value: bool) -> bool {
+        let flag = ProcessFlags::OffHeapMessageQueue;
+
+        let old_flags = if value {
+            self.set_flags(flag)
+        } else {
+            self.clear_flags(flag)
+        };
+
+        old_flags.are_set(flag)
+    }
+
+    pub fn is_message_queue_off_heap(&self) -> bool {
+        self.are_flags_set(ProcessFlags::OffHeapMessageQueue)
+    }
+
     // Alloc
 
     /// Acquires exclusive access to the process heap, blocking the current thread until it is able
@@ -477,6 +566,23 @@ impl Process {
         }
     }
 
+    /// Links to `other`, unless `other` is already exiting, in which case returns `false`.
+    ///
+    /// `other`'s status is held while linking, so either the link is in place before `other`
+    /// starts exiting, and its exit is propagated over the link, or no link is made.
+    pub fn link_unless_exiting(&self, other: &Process) -> bool {
+        let other_status = other.status.read();
+
+        match *other_status {
+            Status::Exited | Status::RuntimeException(_) => false,
+            _ => {
+                self.link(other);
+
+                true
+            }
+        }
+    }
+
     pub fn unlink(&self, other: &Process) {
         // unlink in order so that locks are always taken in the same order to prevent deadlocks
         if self.pid < other.pid {
@@ -538,12 +644,38 @@ impl Process {
     // Send
 
     pub fn send_heap_message(&self, heap_fragment: NonNull<HeapFragment>, data: Term) {
+        // Held while delivering, so that a signal sent after the message can't overtake it
+        let mut signal_queue = self.signal_queue.lock();
+
+        if signal_queue.is_busy() {
+            let message = SignalData::from_fragment(data, heap_fragment);
+            signal_queue.push(Queued::Message(message));
+        } else {
+            self.deliver_heap_message(heap_fragment, data);
+        }
+    }
+
+    /// Delivers the message in `heap_fragment` straight to the mailbox, without waiting behind any
+    /// queued signals, which only the scheduler of the process may do, while handling them.
+    pub fn deliver_heap_message(&self, heap_fragment: NonNull<HeapFragment>, data: Term) {
         let heap_fragment_ptr = heap_fragment.as_ptr();
 
+        // The mailbox owns the fragment until the message is received
+        if self.is_message_queue_off_heap() {
+            self.send_message(MessageData::Mailbox(message::HeapFragment {
+                unsafe_ref_heap_fragment: unsafe { UnsafeRef::from_raw(heap_fragment_ptr) },
+                data,
+            }));
+
+            return;
+        }
+
         let off_heap_unsafe_ref_heap_fragment = unsafe { UnsafeRef::from_raw(heap_fragment_ptr) };
+        let size = off_heap_unsafe_ref_heap_fragment.heap_size();
         self.off_heap
             .lock()
             .push_back(off_heap_unsafe_ref_heap_fragment);
+        self.off_heap_size.fetch_add(size, Ordering::AcqRel);
 
         let message_unsafe_ref_heap_fragment = unsafe { UnsafeRef::from_raw(heap_fragment_ptr) };
 
@@ -559,6 +691,25 @@ impl Process {
 
     /// Returns `true` if the process should stop waiting and be rescheduled as runnable.
     pub fn send_from_other(&self, data: Term) {
+        // Held while delivering, so that a signal sent after the message can't overtake it
+        let mut signal_queue = self.signal_queue.lock();
+
+        // A message waiting behind signals can't be on the heap, as it isn't a root of collections
+        // until it is delivered
+        if signal_queue.is_busy() {
+            signal_queue.push(Queued::Message(SignalData::new(data).unwrap()));
+
+            return;
+        }
+
+        if self.is_message_queue_off_heap() {
+            let (heap_fragment_data, heap_fragment) = data.clone_to_fragment().unwrap();
+
+            self.deliver_heap_message(heap_fragment, heap_fragment_data);
+
+            return;
+        }
+
         match self.heap.try_lock() {
             Some(ref mut destination_heap) => match data.clone_to_heap(destination_heap) {
                 Ok(destination_data) => {
@@ -567,15 +718,61 @@ impl Process {
                 Err(_) => {
                     let (heap_fragment_data, heap_fragment) = data.clone_to_fragment().unwrap();
 
-                    self.send_heap_message(heap_fragment, heap_fragment_data);
+                    self.deliver_heap_message(heap_fragment, heap_fragment_data);
                 }
             },
             None => {
                 let (heap_fragment_data, heap_fragment) = data.clone_to_fragment().unwrap();
 
-                self.send_heap_message(heap_fragment, heap_fragment_data);
+                self.deliver_heap_message(heap_fragment, heap_fragment_data);
+            }
+        }
+    }
+
+    /// Queues `signal` for the scheduler of the process to handle before it next runs the process.
+    ///
+    /// The caller must wake the process afterwards, in case it is waiting.
+    pub fn send_signal(&self, signal: Signal) {
+        let mut signal_queue = self.signal_queue.lock();
+        signal_queue.push(Queued::Signal(signal));
+        self.signals_pending.store(true, Ordering::Release);
+    }
+
+    /// Handles the signals sent to the process in the order they were sent, which only the
+    /// scheduler of the process may do, before running it.
+    ///
+    /// Messages that were sent behind signals are delivered to the mailbox, and every signal is
+    /// passed to `handle`.  Signals and messages sent while they are handled are queued behind them
+    /// and handled in turn.  Returns whether anything was handled.
+    pub fn handle_signals<F>(&self, mut handle: F) -> bool
+    where
+        F: FnMut(Signal),
+    {
+        let mut handled = false;
+
+        loop {
+            let option_queued = {
+                let mut signal_queue = self.signal_queue.lock();
+                let option_queued = signal_queue.pop();
+                self.signals_pending
+                    .store(signal_queue.is_busy(), Ordering::Release);
+
+                option_queued
+            };
+
+            match option_queued {
+                Some(Queued::Message(message)) => {
+                    let (data, heap_fragment) = message.into_fragment();
+                    self.deliver_heap_message(heap_fragment, data);
+                }
+                Some(Queued::Signal(signal)) => handle(signal),
+                None => break,
             }
+
+            handled = true;
         }
+
+        handled
     }
 
     fn send_message(&self, message: MessageData) {
@@ -993,13 +1190,18 @@ impl Process {
     }
 
     /// Inserts roots from the process into the given root set.
-    /// This includes all process dictionary entries.
+    /// This includes all process dictionary entries, and the continuation of a hibernating process.
     #[inline]
     pub fn base_root_set(&self, rootset: &mut RootSet) {
         for entry in self.dictionary.iter() {
             rootset.push(entry.key() as *const _ as *mut _);
             rootset.push(entry.value() as *const _ as *mut _);
         }
+        if let Some(continuation) = self.hibernation.lock().as_mut() {
+            for term in continuation.iter_mut() {
+                rootset.push(term);
+            }
+        }
     }
 
     /// Performs a garbage collection, using the provided root set
@@ -1019,12 +1221,144 @@ impl Process {
         roots: impl Into<RootSet>,
     ) -> Result<usize, GcError> {
         let mut heap = self.heap.lock();
+        // The mailbox is locked for the duration of the collection, as senders which find the
+        // heap locked will allocate their message in a heap fragment and push it on to the
+        // mailbox, and that message must not be observed while its data is being swept
+        let mailbox_guard = self.mailbox.lock();
+        let mut mailbox = mailbox_guard.borrow_mut();
         // The roots passed in here are pointers to the native stack, all other roots
         // we are able to pick up from the current process context
         let mut rootset = roots.into();
         self.base_root_set(&mut rootset);
+        mailbox.push_roots(&mut rootset);
         // Initialize the collector with the given root set
-        heap.garbage_collect(self, need, rootset)
+        let result = heap.garbage_collect(self, need, rootset);
+        // Any messages that were in heap fragments have been swept on to the heap
+        if result.is_ok() {
+            mailbox.mark_on_heap();
+        }
+        drop(mailbox);
+        drop(mailbox_guard);
+        drop(heap);
+
+        if result.is_ok() {
+            self.check_max_heap_size();
+        }
+
+        result
+    }
+
+    /// Returns the `max_heap_size` process flag
+    pub fn max_heap_size(&self) -> MaxHeapSize {
+        *self.max_heap_size.lock()
+    }
+
+    /// Sets the `max_heap_size` process flag, returning its previous value
+    pub fn set_max_heap_size(&self, max_heap_size: MaxHeapSize) -> MaxHeapSize {
+        mem::replace(&mut self.max_heap_size.lock(), max_heap_size)
+    }
+
+    /// Returns the minimum size of the heap, in words
+    pub fn min_heap_size(&self) -> usize {
+        self.min_heap_size
+    }
+
+    /// Returns the minimum size of the virtual binary heap, in words
+    pub fn min_bin_vheap_size(&self) -> usize {
+        self.min_vheap_size
+    }
+
+    /// Returns the number of minor collections after which the next collection is a full sweep
+    pub fn fullsweep_after(&self) -> usize {
+        self.max_gen_gcs.load(Ordering::Relaxed)
+    }
+
+    /// Sets the number of minor collections after which the next collection is a full sweep, as
+    /// the `fullsweep_after` spawn option does, returning its previous value
+    pub fn set_fullsweep_after(&self, fullsweep_after: usize) -> usize {
+        self.max_gen_gcs.swap(fullsweep_after, Ordering::Relaxed)
+    }
+
+    /// Returns the number of minor collections since the last full sweep
+    pub fn minor_gcs(&self) -> usize {
+        self.heap.lock().gen_gc_count
+    }
+
+    /// Returns the collections of the heap since the process was spawned
+    pub fn collections(&self) -> Collections {
+        self.heap.lock().collections()
+    }
+
+    /// Checks the size of the heap, including heap fragments, against the `max_heap_size` process
+    /// flag, which is done after every collection.  If it is exceeded, the process is reported
+    /// and/or exits with reason `killed`, as the flag says.
+    ///
+    /// Returns `true` if the process was killed, in which case it must not run any further.
+    pub fn check_max_heap_size(&self) -> bool {
+        let max_heap_size = self.max_heap_size();
+        let total_heap_size = self.total_heap_size();
+
+        if !max_heap_size.is_exceeded_by(total_heap_size) {
+            return false;
+        }
+
+        if max_heap_size.logs() {
+            log::error!(
+                "Process: {} Context: maximum heap size reached Max Heap Size: {} \
+                 Total Heap Size: {} Kill: {} Error Logger: true",
+                self,
+                max_heap_size.size(),
+                total_heap_size,
+                max_heap_size.kills()
+            );
+        }
+
+        if max_heap_size.kills() && !self.is_exiting() {
+            self.exit(Atom::str_to_term("killed"), trace::Trace::capture(), None);
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Performs a full sweep garbage collection
+    ///
+    /// This is the collection done by `erlang:garbage_collect/0,1`: unlike `garbage_collect`,
+    /// the old generation is always collected along with the young, and any heap fragments are
+    /// swept, so afterwards the heap is sized to the live data. Only the process stack and
+    /// dictionary are roots, so it must not be called while terms on the heap are held elsewhere.
+    /// Any pending request for a collection from `request_full_sweep` is satisfied by it.
+    ///
+    /// The estimated cost of the collection is charged to the process as reductions.
+    pub fn full_sweep(&self) -> Result<usize, GcError> {
+        self.full_sweep_with_roots(RootSet::empty())
+    }
+
+    fn full_sweep_with_roots(&self, roots: impl Into<RootSet>) -> Result<usize, GcError> {
+        self.set_flags(ProcessFlags::NeedFullSweep);
+        let result = self.garbage_collect(0, roots);
+        self.clear_flags(ProcessFlags::ForceGC | ProcessFlags::NeedFullSweep);
+
+        if let Ok(reductions) = result {
+            self.add_reductions(reductions as u64);
+        }
+
+        result
+    }
+
+    /// Requests a full sweep garbage collection of this process from another process
+    ///
+    /// The heap of a process may only be collected by the scheduler running it, so this only sets
+    /// `ProcessFlags::ForceGC`, which the scheduler checks with `is_full_sweep_requested` before
+    /// it next runs the process.
+    pub fn request_full_sweep(&self) {
+        self.set_flags(ProcessFlags::ForceGC | ProcessFlags::NeedFullSweep);
+    }
+
+    /// Whether a full sweep was requested with `request_full_sweep` and has not been done yet
+    pub fn is_full_sweep_requested(&self) -> bool {
+        self.is_gc_forced()
     }
 
     /// Cleans up any linked HeapFragments which should have had any live
@@ -1044,6 +1378,8 @@ impl Process {
         let mut off_heap = self.off_heap.lock();
         let mut cursor = off_heap.front_mut();
         while let Some(fragment_ref) = cursor.remove() {
+            self.off_heap_size
+                .fetch_sub(fragment_ref.heap_size(), Ordering::AcqRel);
             let fragment_ptr = UnsafeRef::into_raw(fragment_ref);
             unsafe { ptr::drop_in_place(fragment_ptr) };
         }
@@ -1079,8 +1415,27 @@ impl Process {
         self.run_reductions.fetch_add(1, Ordering::SeqCst);
     }
 
+    /// Adds `reductions` to the current run, as natives do for work proportional to the size of
+    /// their arguments, returning whether the process has used up its budget, after which it
+    /// should yield as soon as it can.
+    pub fn consume_reductions(&self, reductions: Reductions) -> bool {
+        let _ = self.run_reductions.fetch_update(
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+            |run_reductions| Some(run_reductions.saturating_add(reductions)),
+        );
+
+        self.is_reduced()
+    }
+
     pub fn is_reduced(&self) -> bool {
-        MAX_REDUCTIONS_PER_RUN <= self.run_reductions.load(Ordering::SeqCst)
+        let overrun_reductions = self.overrun_reductions.load(Ordering::SeqCst);
+
+        MAX_REDUCTIONS_PER_RUN
+            <= self
+                .run_reductions
+                .load(Ordering::SeqCst)
+                .saturating_add(overrun_reductions)
     }
 
     pub fn runnable<F>(&self, before_runnable: F)
@@ -1117,36 +1472,133 @@ impl Process {
     }
 
     fn stop_running(&self) {
-        self.total_reductions.fetch_add(
-            self.run_reductions.load(Ordering::SeqCst) as u64,
+        let run_reductions = self.run_reductions.swap(0, Ordering::SeqCst);
+        self.add_reductions(run_reductions as u64);
+
+        // An overrun is only charged once, so a process skips at most the run after it
+        let overrun_reductions = self
+            .overrun_reductions
+            .load(Ordering::SeqCst)
+            .saturating_add(run_reductions)
+            .saturating_sub(MAX_REDUCTIONS_PER_RUN);
+        self.overrun_reductions.store(
+            overrun_reductions.min(MAX_REDUCTIONS_PER_RUN),
             Ordering::SeqCst,
         );
-        self.run_reductions.store(0, Ordering::SeqCst);
 
         let mut writable_status = self.status.write();
 
-        if *writable_status == Status::Running {
-            *writable_status = Status::Runnable
+        match *writable_status {
+            // An exiting process keeps its own exception
+            Status::Exited | Status::RuntimeException(_) => (),
+            _ => {
+                if let Some(exception) = self.pending_exit.lock().take() {
+                    *writable_status = Status::RuntimeException(exception);
+                } else {
+                    self.deschedule_status(&mut writable_status);
+                }
+            }
+        }
+    }
+
+    /// Called by the scheduler once the process has stopped running on it, so that a running
+    /// process is runnable again, and a wait the process began is published as `Waiting`.
+    pub fn descheduled(&self) {
+        self.deschedule_status(&mut self.status.write());
+    }
+
+    fn deschedule_status(&self, status: &mut Status) {
+        match *status {
+            Status::Running => *status = Status::Runnable,
+            // Signals sent since the process began waiting are checked again now the wait is
+            // published, as their senders may have seen the process still running.  Messages
+            // sent since cancelled the wait through `stop_waiting`.
+            Status::MaybeWaiting => {
+                *status = if self.signals_pending.load(Ordering::Acquire) {
+                    Status::Runnable
+                } else {
+                    Status::Waiting
+                }
+            }
+            _ => (),
         }
     }
 
-    /// Puts the process in the waiting status
+    /// Puts the process in the waiting status, unless signals are queued for it, which its
+    /// scheduler must handle first.
+    ///
+    /// A running process only begins waiting, as `MaybeWaiting`, until its scheduler publishes the
+    /// wait with `descheduled`.
     pub fn wait(&self) {
-        *self.status.write() = Status::Waiting;
+        let mut writable_status = self.status.write();
+        // Senders queue signals before waking the process, which takes its status, so either the
+        // signal is seen here, or the process is waiting by the time the sender wakes it
+        if !self.signals_pending.load(Ordering::Acquire) {
+            *writable_status = match *writable_status {
+                Status::Running => Status::MaybeWaiting,
+                _ => Status::Waiting,
+            };
+        }
+        drop(writable_status);
         self.run_reductions.fetch_add(1, Ordering::AcqRel);
     }
 
-    /// Puts the process in the runnable status if it was waiting
+    /// Puts the process in the runnable status if it was waiting, returning `true` if so, in which
+    /// case the scheduler must move it back to a run queue.
+    ///
+    /// A wait that the process began, but its scheduler has not published yet, is cancelled
+    /// instead, so the process runs on, and is requeued by its scheduler as any running process.
     pub fn stop_waiting(&self) -> bool {
         let mut writable_status = self.status.write();
 
-        if *writable_status == Status::Waiting {
-            *writable_status = Status::Runnable;
+        match *writable_status {
+            Status::Waiting => {
+                *writable_status = Status::Runnable;
 
-            true
-        } else {
-            false
+                true
+            }
+            Status::MaybeWaiting => {
+                *writable_status = Status::Running;
+
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Hibernates the process, as `erlang:hibernate/3`, until a message arrives
+    ///
+    /// The heap is shrunk to fit the live data with a full sweep, for which the only roots are
+    /// `module`, `function` and `arguments`, the dictionary and the mailbox: the runtime calling
+    /// this must discard the stack of the process, and never resume it. Instead, the next time the
+    /// process runs it must be restarted with `apply(module, function, arguments)`, which the
+    /// runtime gets with `take_hibernation`.
+    ///
+    /// The process is left waiting, unless there is already a message in its mailbox, in which case
+    /// it stays runnable, as in BEAM.
+    pub fn hibernate(&self, module: Term, function: Term, arguments: Term) -> Result<(), GcError> {
+        let mut continuation = [module, function, arguments];
+        self.full_sweep_with_roots(continuation.as_mut_slice())?;
+        // Even shrunk to its live data, the heap can be over `max_heap_size`
+        if self.is_exiting() {
+            return Ok(());
         }
+        *self.hibernation.lock() = Some(continuation);
+
+        // The mailbox is locked so that a message can't be sent between checking it and waiting,
+        // as the sender would not see the process waiting to wake it
+        let mailbox_guard = self.mailbox.lock();
+        if mailbox_guard.borrow().is_empty() {
+            self.wait();
+        }
+
+        Ok(())
+    }
+
+    /// Takes the `[module, function, arguments]` the process must be restarted with, if it
+    /// hibernated since it last ran
+    pub fn take_hibernation(&self) -> Option<[Term; 3]> {
+        self.hibernation.lock().take()
     }
 
     pub fn erlang_exit(&self, exception: Box<ErlangException>) {
@@ -1169,6 +1621,27 @@ impl Process {
         self.set_runtime_exception(exception);
     }
 
+    /// Exits the process with `exception` on behalf of another process.
+    ///
+    /// If the process is running, its exit is deferred until its scheduler stops running it, so
+    /// that its status isn't changed while it runs.  Otherwise, the caller must wake the process
+    /// so that its scheduler observes the exit.  An exiting process keeps its own exception.
+    pub fn exit_from_signal(&self, exception: RuntimeException) {
+        let mut writable_status = self.status.write();
+
+        match *writable_status {
+            Status::Exited | Status::RuntimeException(_) => (),
+            Status::Running | Status::MaybeWaiting => {
+                let mut pending_exit = self.pending_exit.lock();
+
+                if pending_exit.is_none() {
+                    *pending_exit = Some(exception);
+                }
+            }
+            _ => *writable_status = Status::RuntimeException(exception),
+        }
+    }
+
     pub fn exit_normal(&self) {
         *self.status.write() = Status::Exited;
     }
@@ -1180,6 +1653,32 @@ impl Process {
         }
     }
 
+    /// Suspends the process, as `erlang:suspend_process/1`, returning how many times it is now
+    /// suspended.
+    ///
+    /// A suspended process still receives messages and signals, including exit signals, which
+    /// its scheduler handles, but the scheduler doesn't run it until it is resumed as many times
+    /// as it was suspended.
+    pub fn suspend(&self) -> usize {
+        self.suspend_count.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    /// Undoes one `suspend`, returning how many times the process is still suspended, or `None`
+    /// if it isn't suspended.  The caller must tell the scheduler of the process once it is no
+    /// longer suspended, so that it runs again.
+    pub fn resume(&self) -> Option<usize> {
+        self.suspend_count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                count.checked_sub(1)
+            })
+            .ok()
+            .map(|count| count - 1)
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        0 < self.suspend_count.load(Ordering::Acquire)
+    }
+
     /// Set the current process status to RuntimeException with the given exception
     pub fn set_runtime_exception(&self, exception: RuntimeException) {
         *self.status.write() = Status::RuntimeException(exception);
@@ -1335,7 +1834,7 @@ impl Process {
                         // unlike with non-Term::NONE `returned`, don't push `returned`
                         CalledCurrentNative::Runnable
                     }
-                    Status::Waiting => {
+                    Status::MaybeWaiting | Status::Waiting => {
                         // remove completed frame now that it isn't needed for backtrace
                         self.frames.lock().pop().unwrap();
                         self.stack_popn(arity);
@@ -1420,6 +1919,62 @@ impl Process {
         }
     }
 
+    // Introspection
+
+    /// Returns the number of messages in the mailbox
+    pub fn message_queue_len(&self) -> usize {
+        self.mailbox.lock().borrow().len()
+    }
+
+    /// Returns the number of reductions executed by this process, including the current run
+    pub fn reductions(&self) -> u64 {
+        self.total_reductions.load(Ordering::SeqCst)
+            + self.run_reductions.load(Ordering::SeqCst) as u64
+    }
+
+    /// Returns the reductions in the current run, which aren't yet in `total_reductions`
+    pub fn run_reductions(&self) -> u64 {
+        self.run_reductions.load(Ordering::SeqCst) as u64
+    }
+
+    /// Adds `reductions` to `total_reductions`, and to the `system_reductions` of all processes
+    pub fn add_reductions(&self, reductions: u64) {
+        self.total_reductions
+            .fetch_add(reductions, Ordering::SeqCst);
+        SYSTEM_REDUCTIONS.fetch_add(reductions, Ordering::Relaxed);
+    }
+
+    /// Returns how much the `kind` of statistic has grown to `total` since it was last passed for
+    /// `kind`, or since the system started the first time.
+    ///
+    /// Like in `erlang:statistics/1`, each kind is tracked separately for each process.
+    pub fn statistics_since_last(&self, kind: Atom, total: u64) -> u64 {
+        let last = self
+            .statistics_since_last
+            .lock()
+            .insert(kind, total)
+            .unwrap_or(0);
+
+        total.saturating_sub(last)
+    }
+
+    /// Returns the size of the youngest heap generation, in words
+    pub fn heap_size(&self) -> usize {
+        self.heap.lock().heap_size()
+    }
+
+    /// Returns the size of all heap generations and heap fragments, in words
+    pub fn total_heap_size(&self) -> usize {
+        self.heap.lock().total_heap_size() + self.off_heap_size()
+    }
+
+    /// Returns the `total_heap_size`, unless the heap is locked, for when blocking could
+    /// deadlock, such as in crash dumps
+    pub fn try_total_heap_size(&self) -> Option<usize> {
+        self.try_acquire_heap()
+            .map(|heap| heap.total_heap_size() + self.off_heap_size())
+    }
+
     pub fn current_module_function_arity(&self) -> Option<ModuleFunctionArity> {
         self.frames
             .lock()
@@ -1470,6 +2025,44 @@ impl Process {
     }
 }
 
+/// Helpers for tests which need a process with messages already in its mailbox, without having
+/// to spawn and schedule real sender processes.
+#[cfg(any(test, feature = "test-utils"))]
+impl Process {
+    /// Delivers `data` to the mailbox exactly as a send from another process would, copying it
+    /// to this process's heap, or to a heap fragment if the heap is unavailable.
+    pub fn test_inject_message(&self, data: Term) {
+        self.send_from_other(data)
+    }
+
+    /// Returns the data of every message in the mailbox, oldest first, without receiving them
+    pub fn test_mailbox_snapshot(&self) -> Vec<Term> {
+        let mailbox_guard = self.mailbox.lock();
+        let mailbox = mailbox_guard.borrow();
+        // The mailbox list is ordered newest first
+        let mut snapshot: Vec<Term> = mailbox.iter().map(Message::data).collect();
+        snapshot.reverse();
+
+        snapshot
+    }
+
+    /// Removes every message from the mailbox, returning their data oldest first
+    pub fn test_drain_mailbox(&self) -> Vec<Term> {
+        let mailbox_guard = self.mailbox.lock();
+        let mut mailbox = mailbox_guard.borrow_mut();
+        let mut drained = Vec::with_capacity(mailbox.len());
+
+        while let Some(oldest) = mailbox.cursor().get().map(|m| m as *const Message) {
+            drained.push(unsafe { (*oldest).data() });
+            if let Some(fragment) = mailbox.remove(oldest) {
+                self.attach_fragment(unsafe { &mut *fragment.as_ptr() });
+            }
+        }
+
+        drained
+    }
+}
+
 impl fmt::Debug for Process {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self.pid)?;
@@ -1509,6 +2102,24 @@ impl PartialEq for Process {
     }
 }
 
+impl Drop for Process {
+    fn drop(&mut self) {
+        // Messages left in the heap fragments they were sent in hold references to binaries,
+        // which would outlive the fragments, as nothing refers to the data of these messages
+        let mailbox_guard = self.mailbox.lock();
+        for message in mailbox_guard.borrow().iter() {
+            if let MessageData::HeapFragment(_) = message.data {
+                message.data().release();
+            }
+        }
+        drop(mailbox_guard);
+
+        // Only the process owns its heap fragments, the binaries linked to its heap are released
+        // when the heap is dropped
+        self.sweep_off_heap();
+    }
+}
+
 unsafe impl Send for Process {}
 unsafe impl Sync for Process {}
 
@@ -1538,6 +2149,10 @@ pub enum Status {
     /// The process has had scheduler-specific initialization and can be run when it appears.
     Runnable,
     Running,
+    /// The process is running, but has begun waiting.  Its scheduler publishes the wait as
+    /// `Waiting` once the process has stopped running, unless `stop_waiting` cancels it first, so
+    /// that a wakeup can't come between the process beginning to wait and its scheduler parking it.
+    MaybeWaiting,
     Waiting,
     /// The process has exited normally
     Exited,
@@ -3,6 +3,7 @@ mod iter;
 mod process_heap_alloc;
 mod semispace;
 mod stack_alloc;
+mod stack_allocator;
 mod stack_primitives;
 mod term_alloc;
 mod virtual_alloc;
@@ -13,6 +14,10 @@ pub use self::iter::HeapIter;
 pub use self::process_heap_alloc::ProcessHeapAlloc;
 pub use self::semispace::{GenerationalHeap, SemispaceHeap};
 pub use self::stack_alloc::StackAlloc;
+pub use self::stack_allocator::{
+    DynamicStackAllocator, FixedPoolStackAllocator, StackAllocError, StackAllocator,
+    StackAllocatorStats,
+};
 pub use self::stack_primitives::StackPrimitives;
 pub use self::term_alloc::TermAlloc;
 pub use self::virtual_alloc::{VirtualAlloc, VirtualAllocator, VirtualHeap};
@@ -23,12 +28,16 @@ use core::ffi::c_void;
 use core::mem::transmute;
 use core::ptr::{self, NonNull};
 
+use std::sync::Arc;
+
 use lazy_static::lazy_static;
 
 use crate::erts::apply::DynamicCallee;
 use crate::erts::exception::AllocResult;
 use crate::erts::term::prelude::Term;
 
+use self::stack_allocator::StackOwner;
+
 use super::Frame;
 
 pub const DEFAULT_STACK_SIZE: usize = 1; // 1 page
@@ -44,8 +53,13 @@ pub struct Stack {
     pub top: *mut u8,
     pub size: usize,
     pub end: *mut u8,
+    // The allocator this stack is released to when dropped, if it came from one
+    owner: Option<Arc<dyn StackOwner>>,
 }
 impl Stack {
+    /// The size of the reserved region at the end of the usable stack
+    pub const RED_ZONE_SIZE: usize = 128;
+
     fn new(base: *mut u8, pages: usize) -> Self {
         use liblumen_core::alloc::utils::align_up_to;
         use liblumen_core::sys::sysconf;
@@ -57,7 +71,7 @@ impl Stack {
         let bottom = unsafe { base.offset(page_size as isize) };
         // We add some reserved space, called red zone, at the bottom of the stack.
         // The starting address of the red zone is also the "end" of the usable stack
-        let with_red_zone = unsafe { bottom.offset(128) };
+        let with_red_zone = unsafe { bottom.offset(Self::RED_ZONE_SIZE as isize) };
         let end = align_up_to(with_red_zone, STACK_ALIGNMENT);
         // The start, or top, of the stack is given by offsetting our base by the size
         // of the entire mapped region
@@ -68,6 +82,28 @@ impl Stack {
             top,
             size,
             end,
+            owner: None,
+        }
+    }
+
+    /// Creates a stack occupying the `size` bytes starting at `base`, without a guard page.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure the region is valid, aligned to `STACK_ALIGNMENT`, and
+    /// outlives the returned stack. The region is not freed when the stack is dropped.
+    unsafe fn from_raw_parts(base: *mut u8, size: usize) -> Self {
+        use liblumen_core::alloc::utils::align_up_to;
+
+        let end = align_up_to(base.add(Self::RED_ZONE_SIZE), STACK_ALIGNMENT);
+        let top = base.add(size);
+
+        Self {
+            base,
+            top,
+            size,
+            end,
+            owner: None,
         }
     }
 
@@ -94,6 +130,22 @@ impl Stack {
         use liblumen_core::util::pointer::in_area_inclusive;
         in_area_inclusive(addr, self.base, self.end)
     }
+
+    /// Unmaps a stack allocated by `stack`
+    unsafe fn unmap(&mut self) {
+        use liblumen_core::alloc::mmap;
+        use liblumen_core::sys::sysconf;
+
+        let page_size = sysconf::pagesize();
+        let pages = (self.size / page_size) - 1;
+
+        let (layout, _offset) = Layout::from_size_align(page_size, page_size)
+            .unwrap()
+            .repeat(pages)
+            .unwrap();
+
+        mmap::unmap(self.base, layout);
+    }
 }
 impl Default for Stack {
     fn default() -> Self {
@@ -102,6 +154,7 @@ impl Default for Stack {
             top: ptr::null_mut(),
             size: 0,
             end: ptr::null_mut(),
+            owner: None,
         }
     }
 }
@@ -111,23 +164,13 @@ impl Default for Stack {
 unsafe impl Sync for Stack {}
 impl Drop for Stack {
     fn drop(&mut self) {
-        use liblumen_core::alloc::mmap;
-        use liblumen_core::sys::sysconf;
-
         if self.base.is_null() {
             return;
         }
 
-        let page_size = sysconf::pagesize();
-        let pages = (self.size / page_size) - 1;
-
-        let (layout, _offset) = Layout::from_size_align(page_size, page_size)
-            .unwrap()
-            .repeat(pages)
-            .unwrap();
-
-        unsafe {
-            mmap::unmap(self.base, layout);
+        match self.owner.take() {
+            Some(owner) => unsafe { owner.release(self) },
+            None => unsafe { self.unmap() },
         }
     }
 }
@@ -296,14 +296,23 @@ where
     A: Heap + VirtualAlloc,
     B: Heap + VirtualAlloc,
 {
+    /// Links the binary to the virtual heap of the generation it was allocated in, which is
+    /// the old generation for binaries tenured by a minor collection
     #[inline]
     fn virtual_alloc(&mut self, value: Boxed<ProcBin>) {
-        self.young.virtual_alloc(value)
+        if self.old.contains(value.as_ptr()) {
+            self.old.virtual_alloc(value)
+        } else {
+            self.young.virtual_alloc(value)
+        }
     }
 
     #[inline]
     fn virtual_free(&mut self, value: Boxed<ProcBin>) {
         let ptr = value.as_ptr();
+        if self.old.virtual_contains(ptr) {
+            return self.old.virtual_free(value);
+        }
         assert!(
             self.young.virtual_contains(ptr),
             "can't free term not linked to this virtual heap"
@@ -314,6 +323,9 @@ where
     #[inline]
     fn virtual_unlink(&mut self, value: Boxed<ProcBin>) {
         let ptr = value.as_ptr();
+        if self.old.virtual_contains(ptr) {
+            return self.old.virtual_unlink(value);
+        }
         assert!(
             self.young.virtual_contains(ptr),
             "can't unlink term not linked to this virtual heap"
@@ -324,6 +336,9 @@ where
     #[inline]
     fn virtual_pop(&mut self, value: Boxed<ProcBin>) -> ProcBin {
         let ptr = value.as_ptr();
+        if self.old.virtual_contains(ptr) {
+            return self.old.virtual_pop(value);
+        }
         assert!(
             self.young.virtual_contains(ptr),
             "can't pop binary not linked to this virtual heap"
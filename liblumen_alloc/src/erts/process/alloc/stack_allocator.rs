@@ -0,0 +1,324 @@
+use core::alloc::Layout;
+use core::fmt::{self, Debug};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use std::alloc::{AllocError, Allocator, Global};
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use liblumen_core::locks::Mutex;
+use liblumen_core::sys::sysconf;
+
+use super::{Stack, STACK_ALIGNMENT};
+
+/// Produced by a `StackAllocator` when a stack cannot be handed out
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackAllocError {
+    /// The allocator has a fixed number of stacks, and all of them are in use
+    #[error("all {capacity} process stacks are in use")]
+    Exhausted { capacity: usize },
+    /// The underlying memory could not be allocated
+    #[error("unable to allocate memory for process stack")]
+    Alloc,
+}
+impl From<AllocError> for StackAllocError {
+    #[inline]
+    fn from(_: AllocError) -> Self {
+        Self::Alloc
+    }
+}
+
+/// A snapshot of the occupancy of a `StackAllocator`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackAllocatorStats {
+    /// The number of stacks currently handed out
+    pub in_use: usize,
+    /// The maximum number of stacks that can be handed out, or `None` if unbounded
+    pub capacity: Option<usize>,
+}
+
+/// The source of process stacks for a scheduler.
+///
+/// Each scheduler is given a `StackAllocator` when it is constructed, and all processes
+/// spawned by that scheduler obtain their stacks from it. Stacks are returned to the
+/// allocator they came from when dropped, i.e. when the owning process exits.
+pub trait StackAllocator: Debug + Send + Sync {
+    /// Allocates a new stack, using `size_hint` (in bytes) as the requested usable size.
+    ///
+    /// Allocators which hand out fixed-size stacks are free to ignore the hint.
+    fn allocate(&self, size_hint: usize) -> Result<Stack, StackAllocError>;
+
+    /// Returns a stack to this allocator.
+    ///
+    /// This is equivalent to dropping the stack, and is provided for symmetry.
+    fn deallocate(&self, stack: Stack) {
+        drop(stack)
+    }
+
+    /// The number of stacks which are currently in use
+    fn in_use(&self) -> usize;
+
+    /// The total number of stacks this allocator can hand out, or `None` if unbounded
+    fn capacity(&self) -> Option<usize>;
+
+    /// The number of stacks which can still be allocated, or `None` if unbounded
+    fn remaining(&self) -> Option<usize> {
+        self.capacity()
+            .map(|capacity| capacity.saturating_sub(self.in_use()))
+    }
+
+    /// Returns a snapshot of the occupancy of this allocator
+    fn stats(&self) -> StackAllocatorStats {
+        StackAllocatorStats {
+            in_use: self.in_use(),
+            capacity: self.capacity(),
+        }
+    }
+}
+
+/// Implemented by the owners of the memory backing a `Stack`, so that the
+/// stack can be released back to where it came from when dropped
+pub(super) trait StackOwner: Send + Sync {
+    /// Releases the memory backing `stack`
+    ///
+    /// # Safety
+    ///
+    /// Must only be called once, with a stack that was allocated by this owner
+    unsafe fn release(&self, stack: &mut Stack);
+}
+
+/// The default stack allocator, which maps a fresh region of memory (with a guard page)
+/// for each stack, and unmaps it when the stack is dropped
+#[derive(Clone, Default)]
+pub struct DynamicStackAllocator {
+    owner: Arc<DynamicStackOwner>,
+}
+impl DynamicStackAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl Debug for DynamicStackAllocator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DynamicStackAllocator")
+            .field("in_use", &self.in_use())
+            .finish()
+    }
+}
+impl StackAllocator for DynamicStackAllocator {
+    fn allocate(&self, size_hint: usize) -> Result<Stack, StackAllocError> {
+        let page_size = sysconf::pagesize();
+        let num_pages = (size_hint + page_size - 1) / page_size;
+        let mut stack = super::stack(num_pages.max(1)).map_err(|_| StackAllocError::Alloc)?;
+        stack.owner = Some(self.owner.clone());
+        self.owner.in_use.fetch_add(1, Ordering::SeqCst);
+        Ok(stack)
+    }
+
+    #[inline]
+    fn in_use(&self) -> usize {
+        self.owner.in_use.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+}
+
+#[derive(Default)]
+struct DynamicStackOwner {
+    in_use: AtomicUsize,
+}
+impl StackOwner for DynamicStackOwner {
+    unsafe fn release(&self, stack: &mut Stack) {
+        stack.unmap();
+        self.in_use.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A stack allocator which hands out stacks from a pool of `N` slots of `M` bytes each,
+/// allocated up front when the pool is created.
+///
+/// This is intended for targets where mapping memory on demand is unavailable or
+/// undesirable. Once all slots are in use, allocation fails with
+/// `StackAllocError::Exhausted` until a stack is returned to the pool.
+///
+/// NOTE: Stacks in the pool do not have guard pages, so overflowing one is not detected
+/// by the hardware; only the red zone at the end of each slot is reserved.
+#[derive(Clone)]
+pub struct FixedPoolStackAllocator {
+    pool: Arc<StackPool>,
+}
+impl FixedPoolStackAllocator {
+    /// Creates a new pool of `num_slots` stacks, each `slot_size` bytes in size.
+    ///
+    /// The slot size is rounded up to the stack alignment.
+    pub fn new(num_slots: usize, slot_size: usize) -> Result<Self, StackAllocError> {
+        assert!(num_slots > 0, "stack pool must have at least one slot");
+        assert!(
+            slot_size > Stack::RED_ZONE_SIZE,
+            "stack pool slot size must be larger than the red zone"
+        );
+
+        let slot_size = (slot_size + STACK_ALIGNMENT - 1) & !(STACK_ALIGNMENT - 1);
+        let layout = Layout::from_size_align(slot_size * num_slots, sysconf::pagesize())
+            .map_err(|_| StackAllocError::Alloc)?;
+        let region = Global.allocate(layout)?;
+
+        // Slots are handed out lowest-address first
+        let free = (0..num_slots).rev().collect();
+
+        Ok(Self {
+            pool: Arc::new(StackPool {
+                region: region.as_non_null_ptr(),
+                layout,
+                slot_size,
+                num_slots,
+                free: Mutex::new(free),
+            }),
+        })
+    }
+
+    /// The size in bytes of each slot in this pool
+    #[inline]
+    pub fn slot_size(&self) -> usize {
+        self.pool.slot_size
+    }
+}
+impl Debug for FixedPoolStackAllocator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FixedPoolStackAllocator")
+            .field("slot_size", &self.pool.slot_size)
+            .field("num_slots", &self.pool.num_slots)
+            .field("in_use", &self.in_use())
+            .finish()
+    }
+}
+impl StackAllocator for FixedPoolStackAllocator {
+    fn allocate(&self, _size_hint: usize) -> Result<Stack, StackAllocError> {
+        let slot = self
+            .pool
+            .free
+            .lock()
+            .pop()
+            .ok_or(StackAllocError::Exhausted {
+                capacity: self.pool.num_slots,
+            })?;
+        let base = unsafe { self.pool.region.as_ptr().add(slot * self.pool.slot_size) };
+        let mut stack = unsafe { Stack::from_raw_parts(base, self.pool.slot_size) };
+        stack.owner = Some(self.pool.clone());
+        Ok(stack)
+    }
+
+    #[inline]
+    fn in_use(&self) -> usize {
+        self.pool.num_slots - self.pool.free.lock().len()
+    }
+
+    #[inline]
+    fn capacity(&self) -> Option<usize> {
+        Some(self.pool.num_slots)
+    }
+}
+
+struct StackPool {
+    region: NonNull<u8>,
+    layout: Layout,
+    slot_size: usize,
+    num_slots: usize,
+    /// Indices of the slots which are available for allocation
+    free: Mutex<Vec<usize>>,
+}
+// The region is only ever accessed through the stacks handed out from it,
+// each of which has exclusive access to its own slot
+unsafe impl Send for StackPool {}
+unsafe impl Sync for StackPool {}
+impl StackOwner for StackPool {
+    unsafe fn release(&self, stack: &mut Stack) {
+        let offset = stack.base.offset_from(self.region.as_ptr()) as usize;
+        debug_assert_eq!(offset % self.slot_size, 0, "stack is not from this pool");
+        let slot = offset / self.slot_size;
+        debug_assert!(slot < self.num_slots, "stack is not from this pool");
+        self.free.lock().push(slot);
+    }
+}
+impl Drop for StackPool {
+    fn drop(&mut self) {
+        // Every stack holds a reference to the pool, so by the time we get
+        // here, all slots have been returned
+        unsafe { Global.deallocate(self.region, self.layout) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOT_SIZE: usize = 16 * 1024;
+
+    #[test]
+    fn fixed_pool_allocates_up_to_capacity() {
+        let allocator = FixedPoolStackAllocator::new(4, SLOT_SIZE).unwrap();
+
+        let stacks = (0..4)
+            .map(|_| allocator.allocate(SLOT_SIZE).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(allocator.in_use(), 4);
+        assert_eq!(allocator.remaining(), Some(0));
+        assert_eq!(
+            allocator.allocate(SLOT_SIZE).unwrap_err(),
+            StackAllocError::Exhausted { capacity: 4 }
+        );
+
+        // Each stack gets its own slot
+        for (i, stack) in stacks.iter().enumerate() {
+            for other in stacks.iter().skip(i + 1) {
+                assert_ne!(stack.base, other.base);
+            }
+            assert_eq!(stack.size, SLOT_SIZE);
+        }
+    }
+
+    #[test]
+    fn fixed_pool_reuses_released_slots() {
+        let allocator = FixedPoolStackAllocator::new(2, SLOT_SIZE).unwrap();
+
+        for _ in 0..6 {
+            let stack = allocator.allocate(SLOT_SIZE).unwrap();
+            assert_eq!(allocator.in_use(), 1);
+            allocator.deallocate(stack);
+            assert_eq!(allocator.in_use(), 0);
+        }
+
+        let first = allocator.allocate(SLOT_SIZE).unwrap();
+        let second = allocator.allocate(SLOT_SIZE).unwrap();
+        assert!(allocator.allocate(SLOT_SIZE).is_err());
+        drop(first);
+        let third = allocator.allocate(SLOT_SIZE).unwrap();
+        assert_eq!(
+            allocator.stats(),
+            StackAllocatorStats {
+                in_use: 2,
+                capacity: Some(2)
+            }
+        );
+        drop(second);
+        drop(third);
+        assert_eq!(allocator.in_use(), 0);
+    }
+
+    #[test]
+    fn dynamic_allocator_tracks_occupancy() {
+        let allocator = DynamicStackAllocator::new();
+
+        let stack = allocator.allocate(1).unwrap();
+        assert_eq!(allocator.in_use(), 1);
+        assert_eq!(allocator.remaining(), None);
+        drop(stack);
+        assert_eq!(allocator.in_use(), 0);
+    }
+}
@@ -121,6 +121,12 @@ impl VirtualBinaryHeap {
         }
     }
 
+    /// Returns an iterator over the binaries linked to this heap
+    #[cfg(test)]
+    pub(in crate::erts::process) fn iter(&self) -> impl Iterator<Item = &ProcBin> {
+        self.bins.iter()
+    }
+
     #[inline]
     unsafe fn unlink_raw(&mut self, raw: *mut ProcBin) {
         // Remove from the list
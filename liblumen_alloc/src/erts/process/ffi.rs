@@ -28,12 +28,19 @@ pub enum ProcessSignal {
 ///
 /// When a call fails, `exception` is always non-null pointer to the current process exception, and
 /// `value` is `Term::NONE`.
+///
+/// NOTE: This is the calling convention of the code generated for the runtimes built on this crate,
+/// which is not the same as that of `firefly_rt::function::ErlangResult`, where the error is
+/// indicated by a flag preceding a single payload word. Natives must use the type matching the
+/// runtime they are linked into.
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct ErlangResult {
     pub value: Term,
     pub exception: *mut ErlangException,
 }
+const_assert_eq!(core::mem::size_of::<ErlangResult>(), 2 * core::mem::size_of::<usize>());
+
 impl ErlangResult {
     #[inline]
     pub fn ok(value: Term) -> Self {
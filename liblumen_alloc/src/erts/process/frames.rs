@@ -27,6 +27,11 @@ impl Frames {
         self.stack.push(frame);
     }
 
+    /// Returns the frames that are currently executing, starting with the `current` one
+    pub fn iter(&self) -> impl Iterator<Item = &Frame> {
+        self.stack.iter()
+    }
+
     pub fn pop(&mut self) -> Option<Frame> {
         self.stack.pop()
     }
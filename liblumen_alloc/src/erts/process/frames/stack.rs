@@ -30,6 +30,10 @@ impl Stack {
         self.0.get(0)
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &Frame> {
+        self.0.iter()
+    }
+
     pub fn trace(&self) -> Trace {
         let mut stacktrace = Vec::with_capacity(self.len());
 
@@ -17,6 +17,8 @@ pub use self::rootset::RootSet;
 pub use self::sweep::{Sweep, Sweepable, Sweeper};
 pub use self::young_heap::YoungHeap;
 
+use core::sync::atomic::{AtomicU64, Ordering};
+
 use super::alloc::SemispaceHeap;
 use crate::erts::exception;
 use thiserror::Error;
@@ -77,3 +79,23 @@ pub fn estimate_cost(moved_live_words: usize, resize_moved_words: usize) -> usiz
         reds
     }
 }
+
+/// The number of collections of all processes since the system started
+static COLLECTIONS: AtomicU64 = AtomicU64::new(0);
+/// The words reclaimed by `COLLECTIONS`
+static WORDS_RECLAIMED: AtomicU64 = AtomicU64::new(0);
+
+/// Counts a collection of a process heap that reclaimed `words_reclaimed`
+pub fn record_collection(words_reclaimed: usize) {
+    COLLECTIONS.fetch_add(1, Ordering::Relaxed);
+    WORDS_RECLAIMED.fetch_add(words_reclaimed as u64, Ordering::Relaxed);
+}
+
+/// Returns the number of collections of all processes since the system started, and the number
+/// of words that they reclaimed
+pub fn statistics() -> (u64, u64) {
+    (
+        COLLECTIONS.load(Ordering::Relaxed),
+        WORDS_RECLAIMED.load(Ordering::Relaxed),
+    )
+}
@@ -65,6 +65,12 @@ impl OldHeap {
     pub fn active(&self) -> bool {
         !self.start.is_null()
     }
+
+    /// Returns the virtual heap which tracks the binaries referenced from this heap
+    #[cfg(test)]
+    pub(in crate::erts::process) fn virtual_binary_heap(&self) -> &VirtualBinaryHeap {
+        &self.vheap
+    }
 }
 impl Heap for OldHeap {
     fn is_corrupted(&self) -> bool {
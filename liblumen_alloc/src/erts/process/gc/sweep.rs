@@ -145,10 +145,11 @@ where
         use core::slice;
 
         let sub = self.as_ref();
+        let src = self.as_ptr();
         let size = mem::size_of::<SubBinary>();
 
         // Determine if we should convert this sub-binary to a heapbin
-        if let Ok((_bin_flags, bin_ptr, bin_size)) = sub.to_heapbin_parts() {
+        let (dst, size) = if let Ok((_bin_flags, bin_ptr, bin_size)) = sub.to_heapbin_parts() {
             // Allocate heap binary via copy
             let bytes = slice::from_raw_parts(bin_ptr, bin_size);
             let dst = HeapBin::from_slice(sweeper, bytes, Encoding::Raw).unwrap();
@@ -158,7 +159,6 @@ where
             (dst.cast::<Term>().as_ptr(), size)
         } else {
             // Move to new location
-            let src = self.as_ptr();
             let dst = sweeper
                 .alloc_layout(Layout::new::<SubBinary>())
                 .unwrap()
@@ -166,9 +166,62 @@ where
                 .as_ptr();
             src.copy_to_nonoverlapping(dst, 1);
 
-            (dst as *mut Term, size)
-        }
+            // The original binary is not visited when the new heap is scanned, as it is
+            // not an element of the sub-binary, so it must be moved along with it
+            let moved = &mut *dst;
+            let bytes_moved = sweep_original(sweeper, moved.original_mut());
+
+            (dst as *mut Term, size + bytes_moved)
+        };
+
+        // Write move marker to previous location
+        let marker: Term = dst.into();
+        (src as *mut Term).write(marker);
+
+        (dst, size)
+    }
+}
+
+// Moves the binary referenced by a moved sub-binary, unless it is a literal or has already
+// been moved, in which case the reference only needs to be updated to the new location
+unsafe fn sweep_original<G>(sweeper: &mut G, original: &mut Term) -> usize
+where
+    G: Sweeper,
+{
+    // No move required for literals
+    if original.is_literal() {
+        return 0;
     }
+
+    let original_ptr: *mut Term = original.dyn_cast();
+    let header = *original_ptr;
+
+    // No move required for move markers, just need to update our reference
+    if header.is_boxed() {
+        *original = header;
+        return 0;
+    }
+
+    // No move required if the original is already in the target heap
+    if sweeper.target().contains(original_ptr) {
+        return 0;
+    }
+
+    let (new_original_ptr, bytes_moved) = if header.is_heapbin() {
+        let bin = HeapBin::from_raw_term(original_ptr);
+        bin.sweep(sweeper)
+    } else if header.is_procbin() {
+        let bin: Boxed<ProcBin> = original_ptr.into();
+        bin.sweep(sweeper)
+    } else {
+        panic!(
+            "encountered invalid binary reference in sub-binary: {:?}",
+            header
+        );
+    };
+    *original = new_original_ptr.into();
+
+    bytes_moved
 }
 
 // Match contexts are similar to sub-binaries, except rather than promoting the
@@ -257,11 +310,14 @@ where
         let bin = self.as_ref();
         let bytes = bin.as_bytes();
         let copy = HeapBin::from_slice(sweeper, bytes, bin.encoding()).unwrap();
+        let dst = copy.cast::<Term>().as_ptr();
+        let size = mem::size_of_val(copy.as_ref());
+
+        // Write move marker to previous location
+        let marker: Term = dst.into();
+        self.cast::<Term>().as_ptr().write(marker);
 
-        (
-            copy.cast::<Term>().as_ptr(),
-            mem::size_of_val(copy.as_ref()),
-        )
+        (dst, size)
     }
 }
 
@@ -297,6 +353,11 @@ where
         let boxed = Boxed::new_unchecked(dst);
         sweeper.target_mut().virtual_alloc(boxed);
 
+        // Write move marker to previous location, so that other references to
+        // this binary are updated to share the moved copy, rather than copying it again
+        let marker: Term = (dst as *mut Term).into();
+        (src as *mut Term).write(marker);
+
         (dst as *mut Term, size)
     }
 }
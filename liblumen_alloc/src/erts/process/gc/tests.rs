@@ -9,6 +9,7 @@ use crate::erts::testing::DEFAULT_HEAP_SIZE;
 
 mod collector;
 mod simple_collector;
+mod snapshot;
 mod sweep;
 
 #[inline]
@@ -48,6 +48,59 @@ fn gc_fullsweep_after_tenuring_test() {
     tenuring_gc_test(process, true);
 }
 
+// This test ensures that once a large structure has been tenured, minor collections no longer
+// copy it, so they copy as much as the live young data, whatever the size of the structure
+#[test]
+fn gc_minor_after_tenuring_copies_only_young_data_test() {
+    let small = minor_words_copied_while_churning(100);
+    let large = minor_words_copied_while_churning(10_000);
+
+    assert!(0 < small);
+    assert_eq!(small, large);
+}
+
+// This test ensures that a process does a full sweep once it has done as many minor collections
+// as its `fullsweep_after`
+#[test]
+fn gc_fullsweep_after_minor_collections_test() {
+    let process = process();
+    process.set_fullsweep_after(2);
+    let mut roots = [process.list_from_slice(&[fixnum!(1); 8])];
+
+    for _ in 0..3 {
+        process.garbage_collect(0, &mut roots[..]).unwrap();
+    }
+
+    let collections = process.collections();
+    assert_eq!(collections.minor, 2);
+    assert_eq!(collections.full, 1);
+    assert_eq!(process.minor_gcs(), 0);
+}
+
+/// Collects a process holding a list of `stable_len` elements, while it replaces a short list of
+/// temporaries and makes garbage between collections, returning the words copied by the last
+/// minor collection
+fn minor_words_copied_while_churning(stable_len: usize) -> usize {
+    const COLLECTIONS: usize = 4;
+
+    let process = process();
+    let stable: Vec<Term> = (0..stable_len).map(|i| process.integer(i)).collect();
+    let mut roots = [process.list_from_slice(&stable), Term::NIL];
+
+    for _ in 0..COLLECTIONS {
+        roots[1] = process.list_from_slice(&[fixnum!(1); 8]);
+        process.list_from_slice(&[fixnum!(2); 8]);
+
+        process.garbage_collect(0, &mut roots[..]).unwrap();
+    }
+
+    let collections = process.collections();
+    assert_eq!(collections.minor, COLLECTIONS);
+    assert_eq!(collections.full, 0);
+
+    collections.minor_words_copied
+}
+
 fn simple_gc_test(process: Process) {
     // Allocate an `{:ok, "hello world"}` tuple
     // First, the `ok` atom, an immediate, is super easy
@@ -0,0 +1,317 @@
+use core::ptr::NonNull;
+
+use crate::erts::fragment::HeapFragment;
+use crate::erts::process::gc::RootSet;
+use crate::erts::process::heap::{check_invariants, snapshot, HeapRegions, HeapSnapshot};
+use crate::erts::process::test::process;
+use crate::erts::scheduler;
+use crate::erts::term::closure::Creator;
+use crate::erts::term::prelude::*;
+use crate::erts::*;
+use crate::{atom, fixnum};
+
+#[derive(Debug, Clone, Copy)]
+enum Collection {
+    Minor,
+    Full,
+}
+
+// Performs a collection of the process, and asserts that it left the heap consistent, and that
+// the terms reachable from the roots have the same values and sharing as before
+fn assert_collection_preserves_terms(process: &Process, collection: Collection) -> HeapSnapshot {
+    let before = snapshot(process);
+    let freed = HeapRegions::of(process);
+
+    if let Collection::Full = collection {
+        process.set_flags(ProcessFlags::NeedFullSweep);
+    }
+    process.garbage_collect(0, RootSet::empty()).unwrap();
+
+    let violations = check_invariants(process, &freed);
+    assert!(
+        violations.is_empty(),
+        "{:?} collection violated heap invariants: {:#?}",
+        collection,
+        violations
+    );
+
+    let after = snapshot(process);
+    assert!(
+        before.equivalent(&after),
+        "{:?} collection changed the live terms\nbefore: {:#?}\nafter: {:#?}",
+        collection,
+        before,
+        after
+    );
+
+    after
+}
+
+// The first minor collection moves the live terms to a new young generation, the second
+// tenures them into the old generation, and the full sweep moves them back to a new young
+// generation, so the terms are moved between each kind of heap
+fn assert_collections_preserve_terms(process: &Process) -> HeapSnapshot {
+    assert_collection_preserves_terms(process, Collection::Minor);
+    assert_collection_preserves_terms(process, Collection::Minor);
+    assert_collection_preserves_terms(process, Collection::Full)
+}
+
+#[test]
+fn shared_subterms_remain_shared() {
+    let process = process();
+    let shared = process.tuple_from_slice(&[atom!("shared"), process.binary_from_str("bin")]);
+    let list = process.list_from_slice(&[shared, shared]);
+    let outer = process.tuple_from_slice(&[shared, list, shared]);
+    process.stack_push(outer).unwrap();
+    process.stack_push(shared).unwrap();
+
+    let after = assert_collections_preserve_terms(&process);
+
+    // The outer tuple, the two cons cells, the shared tuple and its binary
+    assert_eq!(after.len(), 5);
+}
+
+#[test]
+fn cycles_are_preserved() {
+    let process = process();
+    let tuple = process.tuple_from_slice(&[atom!("self"), Term::NIL]);
+    let tuple_ptr: *mut Term = tuple.dyn_cast();
+    let mut tuple_ref = unsafe { Tuple::from_raw_term(tuple_ptr) };
+    tuple_ref.set_element(1, tuple).unwrap();
+    process.stack_push(tuple).unwrap();
+
+    let after = assert_collections_preserve_terms(&process);
+
+    assert_eq!(after.len(), 1);
+}
+
+#[test]
+fn long_lists_are_preserved() {
+    let process = process();
+    let elements: Vec<Term> = (0..1_000).map(|i| process.integer(i)).collect();
+    let list = process.list_from_slice(&elements);
+    process.stack_push(list).unwrap();
+
+    assert_collections_preserve_terms(&process);
+}
+
+#[test]
+fn nested_tuples_and_improper_lists_are_preserved() {
+    let process = process();
+    let mut nested = atom!("leaf");
+    for depth in 0..32 {
+        nested = process.tuple_from_slice(&[process.integer(depth), nested]);
+    }
+    let improper =
+        process.improper_list_from_slice(&[fixnum!(1), nested, fixnum!(2)], atom!("tail"));
+    process.stack_push(improper).unwrap();
+
+    assert_collections_preserve_terms(&process);
+}
+
+#[test]
+fn heap_binaries_are_preserved() {
+    let process = process();
+    let small = process.binary_from_bytes(&[1, 2, 3]);
+    let max = process.binary_from_bytes(&[0xff; 64]);
+    let tuple = process.tuple_from_slice(&[small, max, small]);
+    process.stack_push(tuple).unwrap();
+
+    assert_collections_preserve_terms(&process);
+}
+
+#[test]
+fn reference_counted_binaries_stay_linked() {
+    let process = process();
+    let first = process.binary_from_bytes(&[7; 100]);
+    let second = process.binary_from_bytes(&[8; 200]);
+    // A binary which is only garbage, so that it must be unlinked and released
+    let _garbage = process.binary_from_bytes(&[9; 300]);
+    let tuple = process.tuple_from_slice(&[first, second, first]);
+    process.stack_push(tuple).unwrap();
+    process.stack_push(second).unwrap();
+
+    let after = assert_collections_preserve_terms(&process);
+
+    // The tuple and the two binaries
+    assert_eq!(after.len(), 3);
+}
+
+#[test]
+fn sub_binaries_are_preserved() {
+    let process = process();
+    let heap_binary = process.binary_from_str("hello world");
+    let refc_binary = process.binary_from_bytes(&[42; 128]);
+    // Small enough to be converted to a heap binary
+    let aligned = process.subbinary_from_original(heap_binary, 6, 0, 5, 0);
+    // Too large to be converted, so the binary it references must be kept
+    let large = process.subbinary_from_original(refc_binary, 8, 0, 100, 0);
+    // Not aligned, so it can't be converted, and the heap binary it references must be kept
+    let unaligned = process.subbinary_from_original(heap_binary, 1, 3, 2, 5);
+    let tuple = process.tuple_from_slice(&[aligned, large, unaligned, large]);
+    process.stack_push(tuple).unwrap();
+
+    assert_collections_preserve_terms(&process);
+}
+
+#[test]
+fn closures_with_environments_are_preserved() {
+    let process = process();
+    let captured = process.tuple_from_slice(&[atom!("captured"), process.binary_from_str("env")]);
+    let closure = process.anonymous_closure_with_env_from_slice(
+        atom_from_str!("module"),
+        1,
+        2,
+        [0; 16],
+        1,
+        NonNull::new(native as _),
+        Creator::Local(process.pid()),
+        &[captured, fixnum!(3), captured],
+    );
+    process.stack_push(closure).unwrap();
+
+    assert_collections_preserve_terms(&process);
+}
+
+extern "C" fn native() -> Term {
+    Term::NONE
+}
+
+#[test]
+fn numbers_and_references_are_preserved() {
+    let process = process();
+    let big_integer = process.integer(u128::MAX);
+    let float = process.float(1.5);
+    let reference = process.reference_from_scheduler(scheduler::id::next(), 7);
+    let tuple = process.tuple_from_slice(&[big_integer, float, reference, big_integer]);
+    process.stack_push(tuple).unwrap();
+
+    assert_collections_preserve_terms(&process);
+}
+
+#[test]
+fn maps_are_preserved() {
+    let process = process();
+    // The collector does not trace the contents of maps, so only immediates are used
+    let map = process.map_from_slice(&[
+        (atom!("one"), fixnum!(1)),
+        (atom!("two"), atom!("two")),
+        (fixnum!(3), Term::NIL),
+    ]);
+    let tuple = process.tuple_from_slice(&[map, map]);
+    process.stack_push(tuple).unwrap();
+
+    assert_collections_preserve_terms(&process);
+}
+
+#[test]
+fn dictionary_entries_are_preserved() {
+    let process = process();
+    let list = process.list_from_slice(&[fixnum!(1), process.binary_from_str("two")]);
+    process.put(atom!("list"), list);
+    let tuple = process.tuple_from_slice(&[atom!("tuple"), process.binary_from_bytes(&[3; 8])]);
+    process.put(atom!("tuple"), tuple);
+    process.put(atom!("immediate"), fixnum!(4));
+
+    assert_collections_preserve_terms(&process);
+}
+
+#[test]
+fn terms_in_heap_fragments_are_moved_on_to_the_heap() {
+    let process = process();
+    let on_heap = process.tuple_from_slice(&[atom!("on_heap"), process.binary_from_str("bin")]);
+    let in_fragment: Term = process
+        .attach_fragment_or_panic(HeapFragment::new_tuple_from_slice(&[
+            atom!("in_fragment"),
+            on_heap,
+        ]))
+        .into();
+    let (binary, fragment) = HeapFragment::new_binary_from_str("in another fragment").unwrap();
+    process.attach_fragment(unsafe { &mut *fragment.as_ptr() });
+    process.stack_push(in_fragment).unwrap();
+    process.stack_push(binary).unwrap();
+    process.stack_push(on_heap).unwrap();
+    assert!(process.off_heap_size() > 0);
+
+    assert_collections_preserve_terms(&process);
+
+    assert_eq!(process.off_heap_size(), 0);
+}
+
+#[test]
+fn full_mailbox_is_preserved() {
+    let sender = process();
+    let process = process();
+
+    for i in 0..50 {
+        let data = sender.tuple_from_slice(&[
+            sender.integer(i),
+            sender.binary_from_str("on heap"),
+            sender.list_from_slice(&[atom!("message"), sender.integer(i)]),
+        ]);
+        if i % 2 == 0 {
+            process.test_inject_message(data);
+        } else {
+            // While the heap is locked, messages are delivered in heap fragments
+            let _heap = process.acquire_heap();
+            process.send_from_other(data);
+        }
+    }
+    assert!(process.off_heap_size() > 0);
+
+    assert_collections_preserve_terms(&process);
+
+    assert_eq!(process.off_heap_size(), 0);
+    assert_eq!(process.message_queue_len(), 50);
+    let mailbox_guard = process.mailbox.lock();
+    assert!(mailbox_guard
+        .borrow()
+        .iter()
+        .all(|message| !message.is_off_heap()));
+}
+
+#[test]
+fn off_heap_message_queue_is_left_in_the_mailbox() {
+    let sender = process();
+    let process = process();
+    process.message_queue_off_heap(true);
+
+    for i in 0..50 {
+        let data = sender.tuple_from_slice(&[
+            sender.integer(i),
+            sender.binary_from_str("off heap"),
+            sender.list_from_slice(&[atom!("message"), sender.integer(i)]),
+        ]);
+        process.send_from_other(data);
+    }
+    let queued = process.test_mailbox_snapshot();
+
+    assert_collections_preserve_terms(&process);
+
+    assert_eq!(process.off_heap_size(), 0);
+    assert_eq!(process.test_mailbox_snapshot(), queued);
+    let mailbox_guard = process.mailbox.lock();
+    assert!(mailbox_guard
+        .borrow()
+        .iter()
+        .all(|message| matches!(message.data, MessageData::Mailbox(_))));
+}
+
+#[test]
+fn garbage_between_live_terms_is_reclaimed() {
+    let process = process();
+    let mut live = Vec::new();
+    for i in 0..20 {
+        live.push(process.tuple_from_slice(&[process.integer(i), process.binary_from_str("live")]));
+        let garbage: Vec<Term> = (0..10).map(|j| process.integer(j)).collect();
+        process.list_from_slice(&garbage);
+    }
+    for term in live.iter().copied() {
+        process.stack_push(term).unwrap();
+    }
+    let used_before = process.young_heap_used();
+
+    assert_collection_preserves_terms(&process, Collection::Full);
+
+    assert!(process.young_heap_used() < used_before);
+}
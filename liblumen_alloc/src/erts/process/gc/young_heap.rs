@@ -120,6 +120,12 @@ impl YoungHeap {
         distance_absolute(self.high_water_mark, self.start)
     }
 
+    /// Returns the virtual heap which tracks the binaries referenced from this heap
+    #[cfg(test)]
+    pub(in crate::erts::process) fn virtual_binary_heap(&self) -> &VirtualBinaryHeap {
+        &self.vheap
+    }
+
     /// Sets the high water mark to the current top of the heap
     #[inline]
     pub fn set_high_water_mark(&mut self) {
@@ -1,3 +1,6 @@
+#[cfg(test)]
+mod snapshot;
+
 use core::alloc::Layout;
 use core::ptr::NonNull;
 
@@ -5,6 +8,7 @@ use log::trace;
 
 use liblumen_core::util::pointer::distance_absolute;
 
+use crate::erts;
 use crate::erts::exception::AllocResult;
 use crate::erts::term::prelude::{Boxed, ProcBin, Term};
 
@@ -12,14 +16,30 @@ use super::alloc::{self, *};
 use super::gc::{self, *};
 use super::{Process, ProcessFlags};
 
+#[cfg(test)]
+pub(super) use self::snapshot::{check_invariants, snapshot, HeapRegions, HeapSnapshot};
+
+/// The collections of a process heap, as counted since the process was spawned
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Collections {
+    /// The number of minor collections, which only copy the young generation
+    pub minor: usize,
+    /// The number of full sweeps, which copy both generations
+    pub full: usize,
+    /// The words copied by the last minor collection, including those tenured to the old
+    /// generation.  Data that has been tenured is not copied again until a full sweep.
+    pub minor_words_copied: usize,
+}
+
 /// This struct contains the actual semi-space heap that stack/heap allocations
 /// are delegated to, and provides coordination for garbage collection of the
 /// heap given the current process context.
 #[derive(Debug)]
 #[repr(C)]
 pub struct ProcessHeap {
-    // the number of minor collections
+    // the number of minor collections since the last full sweep
     pub(super) gen_gc_count: usize,
+    collections: Collections,
     // The semi-space generational heap
     heap: SemispaceProcessHeap,
 }
@@ -30,6 +50,7 @@ impl ProcessHeap {
         let heap = SemispaceHeap::new(young, old);
         Self {
             gen_gc_count: 0,
+            collections: Default::default(),
             heap,
         }
     }
@@ -40,6 +61,18 @@ impl ProcessHeap {
         self.heap.should_collect(gc_threshold)
     }
 
+    /// Returns the combined size of the young and old generations, in words
+    #[inline]
+    pub fn total_heap_size(&self) -> usize {
+        self.heap.young_generation().heap_size() + self.heap.old_generation().heap_size()
+    }
+
+    /// Returns the collections of this heap so far
+    #[inline]
+    pub fn collections(&self) -> Collections {
+        self.collections
+    }
+
     #[cfg(test)]
     pub(super) fn heap(&self) -> &SemispaceProcessHeap {
         &self.heap
@@ -78,13 +111,24 @@ impl ProcessHeap {
 
         // Initialize the collector
         // Determine if the current collection requires a full sweep or not
-        if process.needs_fullsweep() || self.gen_gc_count >= process.max_gen_gcs {
+        if process.needs_fullsweep()
+            || self.gen_gc_count >= process.fullsweep_after()
+            || self.old_generation_is_full()
+        {
             self.collect_full(process, needed, roots)
         } else {
             self.collect_minor(process, needed, roots)
         }
     }
 
+    /// Returns true if the mature data of the young generation, which a minor collection would
+    /// tenure, doesn't fit in what is left of the old generation, so only a full sweep can collect
+    fn old_generation_is_full(&self) -> bool {
+        let old = self.heap.old_generation();
+
+        old.active() && self.heap.young_generation().mature_size() > old.heap_available()
+    }
+
     /// Handles the specific details required to initialize and execute a full sweep garbage
     /// collection
     fn collect_full(
@@ -116,12 +160,6 @@ impl ProcessHeap {
                 baseline_size
             };
 
-        // Verify that our projected heap size is not going to blow the max heap size, if set
-        // NOTE: When this happens, we will be left with no choice but to kill the process
-        if process.max_heap_size > 0 && process.max_heap_size < new_heap_size {
-            return Err(GcError::MaxHeapSizeExceeded);
-        }
-
         // Unset heap_grow and need_fullsweep flags, because we are doing both
         process
             .flags
@@ -139,20 +177,21 @@ impl ProcessHeap {
             gc.garbage_collect()?
         };
 
-        // TODO: Move messages to be stored on-heap, on to the heap
-
         // Now that all live data has been swept on to the new heap, we can
-        // clean up all of the off heap fragments that we still have laying around
+        // clean up all of the off heap fragments that we still have laying around.
+        // This includes the fragments of messages, which were roots of the collection
         process.sweep_off_heap();
 
         // Reset the generational GC counter
         self.gen_gc_count = 0;
+        self.collections.full += 1;
 
         // Calculate reclamation for tracing
         let young = self.heap.young_generation();
         let stack_used = young.stack_used();
         let heap_used = young.heap_used();
         let size_after = stack_used + heap_used + process.off_heap_size();
+        gc::record_collection(size_before.saturating_sub(size_after));
         if size_before >= size_after {
             trace!(
                 "Full sweep reclaimed {} words of garbage",
@@ -244,36 +283,6 @@ impl ProcessHeap {
         // Calculate mature region
         let mature_size = young.mature_size();
 
-        // Verify that our projected heap size does not exceed
-        // the max heap size, if one was configured.
-        //
-        // If a max heap size is set, make sure we're not going to exceed it
-        if process.max_heap_size > 0 {
-            // First, check if we have exceeded the max heap size
-            let mut heap_size = size_before;
-            // In this estimate, our stack size includes unused area between stack and heap
-            let stack_size = stack_size + young.heap_available();
-            let old = self.heap.old_generation();
-            // Add potential old heap size
-            if !old.active() && mature_size > 0 {
-                heap_size += alloc::next_heap_size(size_before);
-            } else if old.active() {
-                heap_size += old.heap_used();
-            }
-
-            // Add potential new young heap size, conservatively estimating
-            // the worst case scenario where we free no memory and need to
-            // reclaim `needed` words. We grow the projected size until there
-            // is at least enough memory for the current heap + `needed`
-            let baseline_size = stack_size + size_before + needed;
-            heap_size += alloc::next_heap_size(baseline_size);
-
-            // When this error type is returned, a full sweep will be triggered
-            if heap_size > process.max_heap_size {
-                return Err(GcError::MaxHeapSizeExceeded);
-            }
-        }
-
         // Allocate an old heap if we don't have one and one is needed
         if !self.heap.old_generation().active() && mature_size > 0 {
             let size = alloc::next_heap_size(size_before);
@@ -304,7 +313,7 @@ impl ProcessHeap {
         // Swap it with the existing young generation heap
         let mut source = self.heap.swap_young(new_young);
 
-        let _moved = {
+        let moved = {
             // Initialize the collector to collect objects into the new semi-space heap
             let gc_type = MinorCollection::new(&mut source, &mut self.heap);
             let mut gc = ProcessCollector::new(roots, gc_type);
@@ -313,13 +322,14 @@ impl ProcessHeap {
         };
 
         // Now that all live data has been swept on to the new heap, we can
-        // clean up all of the off heap fragments that we still have laying around
+        // clean up all of the off heap fragments that we still have laying around.
+        // This includes the fragments of messages, which were roots of the collection
         process.sweep_off_heap();
 
         // Increment the generational GC counter
         self.gen_gc_count += 1;
-
-        // TODO: if using on-heap messages, move messages in the queue to the heap
+        self.collections.minor += 1;
+        self.collections.minor_words_copied = erts::to_word_size(moved);
 
         // Calculate memory usage after collection
         let old = self.heap.old_generation();
@@ -327,6 +337,7 @@ impl ProcessHeap {
         let new_mature_size = distance_absolute(old.heap_top(), prev_old_top);
         let heap_used = young.heap_used();
         let size_after = new_mature_size + heap_used; // TODO: add process.mbuf_size
+        gc::record_collection(size_before.saturating_sub(size_after));
         let needed_after = heap_used + needed + stack_size;
 
         // Excessively large heaps should be shrunk, but don't even bother on reasonable small heaps
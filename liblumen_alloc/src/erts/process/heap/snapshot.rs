@@ -0,0 +1,540 @@
+use std::collections::{HashMap, HashSet};
+
+use num_bigint::BigInt;
+
+use crate::erts::fragment::HeapFragment;
+use crate::erts::message::MessageData;
+use crate::erts::module_function_arity::Arity;
+use crate::erts::process::alloc::{GenerationalHeap, Heap, StackPrimitives, VirtualHeap};
+use crate::erts::process::gc::SemispaceProcessHeap;
+use crate::erts::process::Process;
+use crate::erts::term::closure::Definition;
+use crate::erts::term::prelude::*;
+
+/// The terms reachable from the roots of a process, captured as an owned graph
+///
+/// Every boxed term and cons cell is a node, identified by the order in which it is first
+/// reached from the roots, so a snapshot does not depend on where terms are allocated, but
+/// terms which are shared, or form cycles, are still distinguished from equal copies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapSnapshot {
+    stack: Vec<Value>,
+    dictionary: Vec<(Value, Value)>,
+    mailbox: Vec<Value>,
+    nodes: Vec<Node>,
+}
+impl HeapSnapshot {
+    /// Returns true if both snapshots have equal values with the same sharing structure
+    pub fn equivalent(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    /// Returns the number of distinct boxed terms and cons cells reachable from the roots
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+/// A term in a `HeapSnapshot`, either an immediate, or the id of a node
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Immediate(Term),
+    Node(usize),
+}
+
+/// A boxed term or cons cell in a `HeapSnapshot`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Cons {
+        head: Value,
+        tail: Value,
+    },
+    Tuple(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Closure {
+        module: Atom,
+        definition: Definition,
+        arity: Arity,
+        env: Vec<Value>,
+    },
+    BigInteger(BigInt),
+    #[cfg(not(target_arch = "x86_64"))]
+    Float(u64),
+    Reference(Reference),
+    /// A heap binary, binary literal or sub-binary, which are only compared by value, as
+    /// sub-binaries may be converted to heap binaries by a collection
+    Bitstring {
+        bytes: Vec<u8>,
+        partial_bits: Vec<u8>,
+    },
+    /// A reference-counted binary, along with the id of the underlying binary, so that
+    /// distinct `ProcBin`s referencing the same binary are distinguished from copies
+    ProcBin {
+        bytes: Vec<u8>,
+        binary: usize,
+    },
+}
+
+/// Captures a snapshot of every term reachable from the stack, dictionary and mailbox of
+/// `process`
+pub fn snapshot(process: &Process) -> HeapSnapshot {
+    let mut heap = process.heap.lock();
+    let mailbox_guard = process.mailbox.lock();
+    let queue = mailbox_guard.borrow();
+    let mut snapshotter = Snapshotter::default();
+
+    let young = heap.heap.young_generation_mut();
+    let sp = young.stack_pointer();
+    let stack = (0..young.stack_size())
+        .map(|slot| snapshotter.value(unsafe { *sp.add(slot) }))
+        .collect();
+
+    // The dictionary is unordered, so entries are visited in order of their keys
+    let mut entries: Vec<(Term, Term)> = process
+        .dictionary
+        .iter()
+        .map(|entry| (*entry.key(), *entry.value()))
+        .collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let dictionary = entries
+        .into_iter()
+        .map(|(key, value)| (snapshotter.value(key), snapshotter.value(value)))
+        .collect();
+
+    // The mailbox is ordered newest first, but messages are received oldest first
+    let mut messages: Vec<Term> = queue.iter().map(|message| message.data()).collect();
+    messages.reverse();
+    let mailbox = messages
+        .into_iter()
+        .map(|data| snapshotter.value(data))
+        .collect();
+
+    HeapSnapshot {
+        stack,
+        dictionary,
+        mailbox,
+        nodes: snapshotter
+            .nodes
+            .into_iter()
+            .map(|node| node.unwrap())
+            .collect(),
+    }
+}
+
+#[derive(Default)]
+struct Snapshotter {
+    ids: HashMap<usize, usize>,
+    binaries: HashMap<usize, usize>,
+    nodes: Vec<Option<Node>>,
+}
+impl Snapshotter {
+    fn value(&mut self, term: Term) -> Value {
+        let address = match pointee(term) {
+            Some(address) => address,
+            None => return Value::Immediate(term),
+        };
+        if let Some(id) = self.ids.get(&address) {
+            return Value::Node(*id);
+        }
+
+        // The id is assigned before visiting the children of the node, so that cycles refer
+        // back to it
+        let id = self.nodes.len();
+        self.ids.insert(address, id);
+        self.nodes.push(None);
+        let node = self.node(term);
+        self.nodes[id] = Some(node);
+
+        Value::Node(id)
+    }
+
+    fn node(&mut self, term: Term) -> Node {
+        assert!(
+            !is_move_marker(term),
+            "cannot snapshot a reference to a move marker"
+        );
+
+        match term.decode().unwrap() {
+            TypedTerm::List(cons) => {
+                let cons = cons.as_ref();
+                Node::Cons {
+                    head: self.value(cons.head),
+                    tail: self.value(cons.tail),
+                }
+            }
+            TypedTerm::Tuple(tuple) => Node::Tuple(
+                tuple
+                    .as_ref()
+                    .iter()
+                    .map(|element| self.value(*element))
+                    .collect(),
+            ),
+            TypedTerm::Map(map) => Node::Map(
+                map.as_ref()
+                    .iter()
+                    .map(|(key, value)| (self.value(*key), self.value(*value)))
+                    .collect(),
+            ),
+            TypedTerm::Closure(closure) => {
+                let closure = closure.as_ref();
+                Node::Closure {
+                    module: closure.module(),
+                    definition: closure.definition().clone(),
+                    arity: closure.arity(),
+                    env: closure
+                        .env_slice()
+                        .iter()
+                        .map(|element| self.value(*element))
+                        .collect(),
+                }
+            }
+            TypedTerm::BigInteger(big_integer) => {
+                Node::BigInteger(big_integer.as_ref().value.clone())
+            }
+            #[cfg(not(target_arch = "x86_64"))]
+            TypedTerm::Float(float) => Node::Float(float.as_ref().value().to_bits()),
+            TypedTerm::Reference(reference) => Node::Reference(*reference.as_ref()),
+            TypedTerm::HeapBinary(bin) => Node::Bitstring {
+                bytes: bin.as_ref().as_bytes().to_vec(),
+                partial_bits: Vec::new(),
+            },
+            TypedTerm::BinaryLiteral(bin) => Node::Bitstring {
+                bytes: bin.as_ref().as_bytes().to_vec(),
+                partial_bits: Vec::new(),
+            },
+            TypedTerm::SubBinary(bin) => {
+                let bin = bin.as_ref();
+                Node::Bitstring {
+                    bytes: bin.full_byte_iter().collect(),
+                    partial_bits: bin.partial_byte_bit_iter().collect(),
+                }
+            }
+            TypedTerm::ProcBin(bin) => {
+                let bin = bin.as_ref();
+                let address = unsafe { bin.as_byte_ptr() } as usize;
+                let next_id = self.binaries.len();
+                let binary = *self.binaries.entry(address).or_insert(next_id);
+                Node::ProcBin {
+                    bytes: bin.as_bytes().to_vec(),
+                    binary,
+                }
+            }
+            typed_term => panic!("snapshots of {:?} are not supported", typed_term),
+        }
+    }
+}
+
+/// The memory owned by a process at some point in time
+///
+/// These are captured before a collection, so that afterwards, pointers into memory which
+/// was freed by the collection can be told apart from pointers to memory which was never
+/// owned by the process.
+#[derive(Debug, Clone)]
+pub struct HeapRegions(Vec<(usize, usize)>);
+impl HeapRegions {
+    /// Captures the used portion of each generation of the heap of `process`, along with
+    /// each of its heap fragments
+    pub fn of(process: &Process) -> Self {
+        let heap = process.heap.lock();
+        Self::from_parts(process, &heap.heap)
+    }
+
+    fn from_parts(process: &Process, heap: &SemispaceProcessHeap) -> Self {
+        let mut regions = Vec::new();
+        let young = heap.young_generation();
+        regions.push((young.heap_start() as usize, young.heap_top() as usize));
+        let old = heap.old_generation();
+        if old.active() {
+            regions.push((old.heap_start() as usize, old.heap_top() as usize));
+        }
+        for fragment in process.off_heap.lock().iter() {
+            regions.push((fragment.heap_start() as usize, fragment.heap_top() as usize));
+        }
+
+        Self(regions)
+    }
+
+    fn contains(&self, address: usize) -> bool {
+        self.0
+            .iter()
+            .any(|(start, end)| *start <= address && address < *end)
+    }
+}
+
+/// A violation of an invariant which should hold after every collection
+///
+/// Addresses are used rather than terms, as terms involved in a violation may not be safe
+/// to decode.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// The word at `at` points into memory which was freed by the collection
+    FreedPointer { at: usize, to: usize },
+    /// The word at `at` points to memory which was never owned by the process, and isn't a
+    /// literal
+    ForeignPointer { at: usize, to: usize },
+    /// The word at `at` points to a move marker, rather than to where the term was moved
+    MoveMarker { at: usize, to: usize },
+    /// The sum of the sizes of the heap fragments of the process, in words, doesn't match the
+    /// size which is accounted for
+    OffHeapSize { fragments: usize, accounted: usize },
+    /// A message refers to a heap fragment which isn't attached to the process
+    DetachedFragment { fragment: usize },
+    /// A message refers to a heap fragment owned by the mailbox which is also attached to the
+    /// process, so it would be swept while the message is still queued
+    AttachedMailboxFragment { fragment: usize },
+    /// A reachable `ProcBin` isn't linked to the virtual heap of the generation it is in
+    UnlinkedBinary { bin: usize },
+    /// A `ProcBin` is linked to the virtual heap of a generation it isn't in
+    MislinkedBinary { bin: usize },
+    /// The bytes used by the virtual heap of a generation don't match the binaries linked to it
+    VirtualHeapUsed { linked: usize, accounted: usize },
+    /// A binary is referenced by more linked `ProcBin`s than its reference count
+    ReferenceCount {
+        binary: usize,
+        linked: usize,
+        refc: usize,
+    },
+}
+
+/// Checks the invariants which should hold after a collection of `process`, returning any
+/// violations found
+///
+/// `freed` are the regions of memory owned by `process` before the collection, from which
+/// every live term should have been moved.
+pub fn check_invariants(process: &Process, freed: &HeapRegions) -> Vec<Violation> {
+    let mut heap = process.heap.lock();
+    let mailbox_guard = process.mailbox.lock();
+    let mailbox = mailbox_guard.borrow();
+
+    let mut checker = Checker {
+        owned: HeapRegions::from_parts(process, &heap.heap),
+        freed,
+        visited: HashSet::new(),
+        procbins: Vec::new(),
+        violations: Vec::new(),
+    };
+
+    // Gather the locations of every root
+    let mut roots: Vec<*const Term> = Vec::new();
+    let young = heap.heap.young_generation_mut();
+    let sp = young.stack_pointer();
+    for slot in 0..young.stack_size() {
+        roots.push(unsafe { sp.add(slot) });
+    }
+    for entry in process.dictionary.iter() {
+        roots.push(entry.key());
+        roots.push(entry.value());
+    }
+    let is_attached = |fragment_ptr: *const HeapFragment| {
+        process
+            .off_heap
+            .lock()
+            .iter()
+            .any(|attached| attached as *const HeapFragment == fragment_ptr)
+    };
+    for message in mailbox.iter() {
+        match &message.data {
+            MessageData::Process(data) => roots.push(data),
+            MessageData::HeapFragment(fragment) => {
+                roots.push(&fragment.data);
+
+                let fragment_ptr: *const HeapFragment = &*fragment.unsafe_ref_heap_fragment;
+                if !is_attached(fragment_ptr) {
+                    checker.violations.push(Violation::DetachedFragment {
+                        fragment: fragment_ptr as usize,
+                    });
+                }
+            }
+            // Owned by the mailbox, so neither a root nor swept by the collection
+            MessageData::Mailbox(fragment) => {
+                let fragment_ptr: *const HeapFragment = &*fragment.unsafe_ref_heap_fragment;
+                if is_attached(fragment_ptr) {
+                    checker.violations.push(Violation::AttachedMailboxFragment {
+                        fragment: fragment_ptr as usize,
+                    });
+                }
+            }
+        }
+    }
+    checker.trace(roots);
+
+    // Heap fragments are accounted for as they are attached and swept
+    let fragments = process
+        .off_heap
+        .lock()
+        .iter()
+        .map(|fragment| fragment.heap_size())
+        .sum();
+    let accounted = process.off_heap_size();
+    if fragments != accounted {
+        checker.violations.push(Violation::OffHeapSize {
+            fragments,
+            accounted,
+        });
+    }
+
+    checker.check_binaries(&heap.heap);
+    checker.violations
+}
+
+struct Checker<'a> {
+    owned: HeapRegions,
+    freed: &'a HeapRegions,
+    visited: HashSet<usize>,
+    procbins: Vec<usize>,
+    violations: Vec<Violation>,
+}
+impl Checker<'_> {
+    fn trace(&mut self, mut locations: Vec<*const Term>) {
+        while let Some(at) = locations.pop() {
+            let term = unsafe { *at };
+            if term.is_literal() {
+                continue;
+            }
+            let to = match pointee(term) {
+                Some(to) => to,
+                None => continue,
+            };
+            let at = at as usize;
+
+            if !self.owned.contains(to) {
+                if self.freed.contains(to) {
+                    self.violations.push(Violation::FreedPointer { at, to });
+                } else {
+                    self.violations.push(Violation::ForeignPointer { at, to });
+                }
+                continue;
+            }
+            if is_move_marker(term) {
+                self.violations.push(Violation::MoveMarker { at, to });
+                continue;
+            }
+            if !self.visited.insert(to) {
+                continue;
+            }
+
+            match term.decode().unwrap() {
+                TypedTerm::List(cons) => {
+                    let cons = cons.as_ref();
+                    locations.push(&cons.head);
+                    locations.push(&cons.tail);
+                }
+                TypedTerm::Tuple(tuple) => {
+                    locations.extend(tuple.as_ref().iter().map(|element| element as *const _))
+                }
+                TypedTerm::Map(map) => {
+                    for (key, value) in map.as_ref().iter() {
+                        locations.push(key);
+                        locations.push(value);
+                    }
+                }
+                TypedTerm::Closure(closure) => locations.extend(
+                    closure
+                        .as_ref()
+                        .env_slice()
+                        .iter()
+                        .map(|element| element as *const _),
+                ),
+                TypedTerm::SubBinary(bin) => {
+                    locations.push(unsafe { (*bin.as_ptr()).original_mut() });
+                }
+                TypedTerm::MatchContext(context) => {
+                    locations.push(unsafe { (*context.as_ptr()).original_mut() });
+                }
+                TypedTerm::ProcBin(bin) => self.procbins.push(bin.as_ptr() as usize),
+                _ => (),
+            }
+        }
+    }
+
+    fn check_binaries(&mut self, heap: &SemispaceProcessHeap) {
+        let young = heap.young_generation();
+        let old = heap.old_generation();
+        let generations = [
+            (
+                young.heap_start() as usize,
+                young.heap_top() as usize,
+                young.virtual_binary_heap(),
+            ),
+            (
+                old.heap_start() as usize,
+                old.heap_top() as usize,
+                old.virtual_binary_heap(),
+            ),
+        ];
+
+        // Every reachable binary in a generation must be linked to its virtual heap, binaries
+        // in heap fragments are only linked once they are swept on to the heap
+        for bin in self.procbins.iter().copied() {
+            for (start, end, vheap) in generations.iter() {
+                if *start <= bin && bin < *end {
+                    let linked = vheap
+                        .iter()
+                        .any(|linked| linked as *const ProcBin as usize == bin);
+                    if !linked {
+                        self.violations.push(Violation::UnlinkedBinary { bin });
+                    }
+                }
+            }
+        }
+
+        let mut references: HashMap<usize, (usize, usize)> = HashMap::new();
+        for (start, end, vheap) in generations.iter() {
+            let mut linked = 0;
+            for bin in vheap.iter() {
+                let address = bin as *const ProcBin as usize;
+                if address < *start || *end <= address {
+                    self.violations
+                        .push(Violation::MislinkedBinary { bin: address });
+                }
+                linked += bin.full_byte_len();
+
+                let binary = unsafe { bin.as_byte_ptr() } as usize;
+                let (count, _) = references.entry(binary).or_insert((0, bin.ref_count()));
+                *count += 1;
+            }
+
+            let accounted = vheap.virtual_heap_used();
+            if linked != accounted {
+                self.violations
+                    .push(Violation::VirtualHeapUsed { linked, accounted });
+            }
+        }
+
+        for (binary, (linked, refc)) in references {
+            if refc < linked {
+                self.violations.push(Violation::ReferenceCount {
+                    binary,
+                    linked,
+                    refc,
+                });
+            }
+        }
+    }
+}
+
+/// Returns the address of the boxed term or cons cell `term` points to, if any
+fn pointee(term: Term) -> Option<usize> {
+    if term.is_boxed() {
+        let ptr: *mut Term = term.dyn_cast();
+        Some(ptr as usize)
+    } else if term.is_non_empty_list() {
+        let ptr: *mut Cons = term.dyn_cast();
+        Some(ptr as usize)
+    } else {
+        None
+    }
+}
+
+/// Returns true if `term` points to a move marker, rather than a term
+fn is_move_marker(term: Term) -> bool {
+    if term.is_boxed() {
+        let ptr: *mut Term = term.dyn_cast();
+        unsafe { (*ptr).is_boxed() }
+    } else if term.is_non_empty_list() {
+        let ptr: *mut Cons = term.dyn_cast();
+        unsafe { (*ptr).is_move_marker() }
+    } else {
+        false
+    }
+}
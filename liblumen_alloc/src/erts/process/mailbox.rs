@@ -1,14 +1,33 @@
+use core::ptr::{self, NonNull};
+
+use std::collections::VecDeque;
+
+use crate::erts::fragment::HeapFragment;
 use crate::erts::message::{Message, MessageAdapter, MessageData};
+use crate::erts::process::gc::RootSet;
+use crate::erts::term::prelude::{ReferenceNumber, Release, Term};
 
 use intrusive_collections::linked_list::Cursor;
 use intrusive_collections::{LinkedList, UnsafeRef};
 
 use liblumen_arena::TypedArena;
 
+/// The most markers a mailbox keeps, after which creating a marker drops the oldest
+pub const MAX_MARKERS: usize = 8;
+
+/// A position in the mailbox, which is the newest message before it, or `None` if it is before
+/// the oldest message
+type Position = Option<UnsafeRef<Message>>;
+
 pub struct Mailbox {
     len: usize,
     messages: LinkedList<MessageAdapter>,
     storage: TypedArena<Message>,
+    /// The position of the receive in progress, which has looked at the messages before it
+    save: Position,
+    /// The position of the mailbox when each reference was created, as it is only in messages
+    /// after it, newest last
+    markers: VecDeque<(ReferenceNumber, Position)>,
 }
 impl Mailbox {
     /// Create a new, empty mailbox
@@ -18,6 +37,8 @@ impl Mailbox {
             len: 0,
             messages: LinkedList::new(MessageAdapter::new()),
             storage: TypedArena::default(),
+            save: None,
+            markers: VecDeque::new(),
         }
     }
 
@@ -55,12 +76,113 @@ impl Mailbox {
         self.len += 1;
     }
 
-    /// Removes the given message from the mailbox
-    pub fn remove(&mut self, message: *const Message) {
+    /// Removes the given message from the mailbox, as it was received
+    ///
+    /// If the message data is in a heap fragment owned by the mailbox, the fragment is returned,
+    /// and must be attached to the receiving process, as the data is now in use by it.
+    #[must_use]
+    pub fn remove(&mut self, message: *const Message) -> Option<NonNull<HeapFragment>> {
+        self.forget_position(message);
         let mut cursor = unsafe { self.messages.cursor_mut_from_ptr(message) };
         debug_assert!(!cursor.is_null());
+        let fragment = cursor.get().and_then(owned_fragment);
         cursor.remove();
         self.len -= 1;
+
+        fragment
+    }
+
+    /// Returns the first message the receive in progress hasn't looked at, if any
+    pub fn peek(&self) -> Option<&Message> {
+        match &self.save {
+            None => self.messages.back().get(),
+            Some(saved) => {
+                let mut cursor = unsafe { self.messages.cursor_from_ptr(&**saved) };
+                cursor.move_prev();
+                cursor.get()
+            }
+        }
+    }
+
+    /// Moves the receive in progress past the message returned by `peek`, as it didn't match
+    pub fn next(&mut self) {
+        if let Some(message) = self.peek() {
+            let message = unsafe { UnsafeRef::from_raw(message) };
+            self.save = Some(message);
+        }
+    }
+
+    /// Removes the message returned by `peek`, as it was received, which ends the receive in
+    /// progress
+    ///
+    /// See `remove` for the meaning of the result.
+    #[must_use]
+    pub fn remove_peeked(&mut self) -> Option<NonNull<HeapFragment>> {
+        let peeked = self.peek().map(|message| message as *const Message);
+        let fragment = peeked.and_then(|message| self.remove(message));
+        self.save = None;
+
+        fragment
+    }
+
+    /// Ends the receive in progress without receiving a message, as it timed out
+    pub fn reset_save(&mut self) {
+        self.save = None;
+    }
+
+    /// Records the position of the mailbox as `reference` is created, so that a receive which
+    /// only matches messages containing it can start from there with `set_save_to_marker`
+    pub fn mark(&mut self, reference: ReferenceNumber) {
+        if self.markers.len() == MAX_MARKERS {
+            self.markers.pop_front();
+        }
+        let newest = self
+            .messages
+            .front()
+            .get()
+            .map(|message| unsafe { UnsafeRef::from_raw(message) });
+        self.markers.push_back((reference, newest));
+    }
+
+    /// Starts the receive in progress from the position recorded for `reference`, returning
+    /// `false` if there is none, in which case it starts from the oldest message, as usual
+    pub fn set_save_to_marker(&mut self, reference: ReferenceNumber) -> bool {
+        match self
+            .markers
+            .iter()
+            .position(|(marked, _)| *marked == reference)
+        {
+            Some(index) => {
+                let (_, position) = self.markers.remove(index).unwrap();
+                self.save = position;
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the positions at `message` to the message before it, as it is being removed
+    fn forget_position(&mut self, message: *const Message) {
+        let is_message = |position: &Position| match position {
+            Some(position) => ptr::eq(&**position, message),
+            None => false,
+        };
+        if !is_message(&self.save) && !self.markers.iter().any(|(_, p)| is_message(p)) {
+            return;
+        }
+        let before = unsafe { self.messages.cursor_from_ptr(message) }
+            .peek_next()
+            .get()
+            .map(|before| unsafe { UnsafeRef::from_raw(before) });
+        if is_message(&self.save) {
+            self.save = before.clone();
+        }
+        for (_, position) in self.markers.iter_mut() {
+            if is_message(position) {
+                *position = before.clone();
+            }
+        }
     }
 
     /// Removes the first matching message from the mailbox, traversing in receive order (oldest->newest)
@@ -75,8 +197,13 @@ impl Mailbox {
             }
             let found = current.get().map(|msg| predicate(msg)).unwrap_or(false);
             if found {
-                current.remove();
-                self.len -= 1;
+                let message = current.get().unwrap() as *const Message;
+                // The message is discarded, so nothing can refer to data the mailbox owns
+                let data = unsafe { (*message).data() };
+                if let Some(fragment) = self.remove(message) {
+                    data.release();
+                    unsafe { ptr::drop_in_place(fragment.as_ptr()) };
+                }
                 return found;
             }
             current.move_prev();
@@ -85,6 +212,41 @@ impl Mailbox {
         false
     }
 
+    /// Adds the data of every message in the mailbox to the given root set
+    ///
+    /// Messages are roots of every collection of the process heap, whether their data was
+    /// allocated on the heap, or in a heap fragment which has yet to be swept on to it.  Messages
+    /// in heap fragments owned by the mailbox are not, as they don't refer to the heap, and are
+    /// only swept on to it after they are received.
+    pub fn push_roots(&mut self, rootset: &mut RootSet) {
+        for message in self.messages.iter() {
+            let data = match &message.data {
+                MessageData::Process(data) => data,
+                MessageData::HeapFragment(fragment) => &fragment.data,
+                MessageData::Mailbox(_) => continue,
+            };
+            rootset.push(data as *const Term as *mut Term);
+        }
+    }
+
+    /// Marks every message in the mailbox as stored on the process heap
+    ///
+    /// This must be called once a collection which used the mailbox as roots has completed, as
+    /// the data of messages in heap fragments has been swept on to the heap, and the fragments
+    /// themselves have been freed.
+    pub fn mark_on_heap(&mut self) {
+        for message in self.messages.iter() {
+            if let MessageData::HeapFragment(fragment) = &message.data {
+                let data = fragment.data;
+                // Messages are only reachable through the list, which we have exclusive access to
+                let message = message as *const Message as *mut Message;
+                unsafe {
+                    (*message).data = MessageData::Process(data);
+                }
+            }
+        }
+    }
+
     /// This function garbage collects the storage arena to ensure it doesn't grow
     /// indefinitely. It uses the messages in the mailbox as roots.
     ///
@@ -101,7 +263,17 @@ impl Mailbox {
         // in the same order, only requiring a single traversal.
         let mut cursor = self.messages.back();
         while let Some(message) = cursor.get() {
-            let ptr = storage.alloc(message.clone());
+            let ptr = storage.alloc(message.clone()) as *const Message;
+            // Positions at the message move with it
+            let moved = |position: &mut Position| {
+                if let Some(true) = position.as_ref().map(|p| ptr::eq(&**p, message)) {
+                    *position = Some(unsafe { UnsafeRef::from_raw(ptr) });
+                }
+            };
+            moved(&mut self.save);
+            self.markers
+                .iter_mut()
+                .for_each(|(_, position)| moved(position));
             messages.push_front(unsafe { UnsafeRef::from_raw(ptr) });
             len += 1;
             cursor.move_prev();
@@ -123,3 +295,25 @@ impl Default for Mailbox {
         Self::new()
     }
 }
+impl Drop for Mailbox {
+    fn drop(&mut self) {
+        // Messages which were never received still own their heap fragments
+        for message in self.messages.iter() {
+            if let Some(fragment) = owned_fragment(message) {
+                message.data().release();
+                unsafe { ptr::drop_in_place(fragment.as_ptr()) };
+            }
+        }
+    }
+}
+
+/// Returns the heap fragment holding the data of `message` if it is owned by the mailbox
+fn owned_fragment(message: &Message) -> Option<NonNull<HeapFragment>> {
+    match &message.data {
+        MessageData::Mailbox(fragment) => {
+            let fragment_ptr: *const HeapFragment = &*fragment.unsafe_ref_heap_fragment;
+            NonNull::new(fragment_ptr as *mut HeapFragment)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,98 @@
+use core::convert::{TryFrom, TryInto};
+
+use anyhow::*;
+
+use crate::erts::term::prelude::*;
+
+use super::Process;
+
+/// The `max_heap_size` of a process, past which it is reported and/or killed.
+///
+/// A `size` of `0` means there is no limit.  Unless set, both `kill` and `error_logger` are
+/// `true`, as in OTP.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MaxHeapSize {
+    size: usize,
+    kill: Option<bool>,
+    error_logger: Option<bool>,
+}
+impl MaxHeapSize {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            kill: None,
+            error_logger: None,
+        }
+    }
+
+    pub fn kill(&mut self, kill: bool) {
+        self.kill = Some(kill);
+    }
+
+    pub fn error_logger(&mut self, error_logger: bool) {
+        self.error_logger = Some(error_logger);
+    }
+
+    /// The limit in words, or `0` if there is none
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether a process exceeding the limit is killed
+    pub fn kills(&self) -> bool {
+        self.kill.unwrap_or(true)
+    }
+
+    /// Whether a process exceeding the limit is reported
+    pub fn logs(&self) -> bool {
+        self.error_logger.unwrap_or(true)
+    }
+
+    /// Whether a process whose heap and heap fragments take `total_heap_size` words is over the
+    /// limit
+    pub fn is_exceeded_by(&self, total_heap_size: usize) -> bool {
+        0 < self.size && self.size < total_heap_size
+    }
+
+    /// The `#{size => Size, kill => Kill, error_logger => ErrorLogger}` map
+    /// `process_flag(max_heap_size, _)` returns
+    pub fn to_term(&self, process: &Process) -> Term {
+        process.map_from_slice(&[
+            (Atom::str_to_term("size"), process.integer(self.size)),
+            (Atom::str_to_term("kill"), self.kills().into()),
+            (Atom::str_to_term("error_logger"), self.logs().into()),
+        ])
+    }
+}
+
+impl TryFrom<Term> for MaxHeapSize {
+    type Error = anyhow::Error;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        let size: Result<usize, _> = term.try_into();
+        if let Ok(size) = size {
+            return Ok(Self::new(size));
+        }
+
+        let map: Boxed<Map> = term
+            .try_into()
+            .map_err(|_| anyhow!("{} is not a non-negative integer or a map", term))?;
+        let size = map
+            .get(Atom::str_to_term("size"))
+            .ok_or_else(|| anyhow!("missing size key in map"))
+            .and_then(|term| {
+                term.try_into()
+                    .map_err(|err: TryIntoIntegerError| anyhow!(err))
+            })
+            .context("size")?;
+        let mut max_heap_size = Self::new(size);
+        if let Some(kill) = map.get(Atom::str_to_term("kill")) {
+            max_heap_size.kill(kill.try_into().context("kill")?);
+        }
+        if let Some(error_logger) = map.get(Atom::str_to_term("error_logger")) {
+            max_heap_size.error_logger(error_logger.try_into().context("error_logger")?);
+        }
+
+        Ok(max_heap_size)
+    }
+}
@@ -0,0 +1,118 @@
+use core::mem;
+use core::ptr::{self, NonNull};
+
+use std::collections::vec_deque::VecDeque;
+use std::sync::Arc;
+
+use crate::borrow::CloneToProcess;
+use crate::erts::exception::{AllocResult, ArcError};
+use crate::erts::fragment::HeapFragment;
+use crate::erts::process::trace::Trace;
+use crate::erts::term::prelude::*;
+
+/// A signal sent to a process by another process, which is queued until the scheduler of the
+/// receiving process handles it, before next running the process.
+///
+/// As every signal to a process, and every message sent while signals are queued, goes through
+/// the same queue, a process observes the signals and messages from any other process in the
+/// order they were sent, as in BEAM.
+pub enum Signal {
+    /// An exit signal from `sender` with `reason`, from `exit/2`, or, if `linked`, from the exit
+    /// of `sender` over its link to the receiving process
+    Exit {
+        sender: Pid,
+        reason: SignalData,
+        linked: bool,
+        trace: Arc<Trace>,
+        source: Option<ArcError>,
+    },
+    /// The `DOWN` `message` of the monitor with `reference`, which is only delivered if the
+    /// receiving process didn't `demonitor` it before the signal is handled
+    Down {
+        reference: Reference,
+        message: SignalData,
+    },
+    /// `sender` removed its link to the receiving process with `unlink/1`.
+    ///
+    /// `sender` has already removed its side of the link, so any exit signal the receiving process
+    /// sends over the link before handling this is ignored, which makes an unlink acknowledgement
+    /// unnecessary.
+    Unlink { sender: Pid },
+    /// `group_leader/2` set the group leader of the receiving process
+    GroupLeader { group_leader: Pid },
+}
+
+/// A term copied to a heap fragment owned by a signal, as the receiving process's heap can't be
+/// used until its scheduler handles the signal.  The fragment is freed when this is dropped.
+pub struct SignalData {
+    data: Term,
+    fragment: NonNull<HeapFragment>,
+}
+impl SignalData {
+    pub fn new(term: Term) -> AllocResult<Self> {
+        let (data, fragment) = term.clone_to_fragment()?;
+
+        Ok(Self { data, fragment })
+    }
+
+    /// Takes ownership of `fragment`, which `data` is in
+    pub fn from_fragment(data: Term, fragment: NonNull<HeapFragment>) -> Self {
+        Self { data, fragment }
+    }
+
+    pub fn data(&self) -> Term {
+        self.data
+    }
+
+    /// Gives up ownership of the fragment, which must then be attached to the receiving process,
+    /// or owned by its mailbox
+    pub fn into_fragment(self) -> (Term, NonNull<HeapFragment>) {
+        let data_fragment = (self.data, self.fragment);
+        mem::forget(self);
+
+        data_fragment
+    }
+}
+impl Drop for SignalData {
+    fn drop(&mut self) {
+        // Nothing else refers to the data, so the references it holds can go with the fragment
+        self.data.release();
+        unsafe { ptr::drop_in_place(self.fragment.as_ptr()) };
+    }
+}
+unsafe impl Send for SignalData {}
+
+pub(super) enum Queued {
+    /// A message sent while signals were queued, or being handled, so it waits behind them
+    Message(SignalData),
+    Signal(Signal),
+}
+
+/// The signals, and messages behind them, waiting for the scheduler of a process to handle them
+#[derive(Default)]
+pub(super) struct SignalQueue {
+    queued: VecDeque<Queued>,
+    /// Whether a signal is being handled, in which case any message must wait behind it, even if
+    /// the queue is empty, as the signal may still deliver a message, such as an `EXIT` message
+    handling: bool,
+}
+impl SignalQueue {
+    /// Messages can only be delivered straight to the mailbox when no signal is queued or being
+    /// handled
+    pub(super) fn is_busy(&self) -> bool {
+        self.handling || !self.queued.is_empty()
+    }
+
+    pub(super) fn push(&mut self, queued: Queued) {
+        self.queued.push_back(queued);
+    }
+
+    /// Takes the oldest queued signal or message to handle, marking the queue as handling it, or
+    /// not handling anything if it is empty
+    pub(super) fn pop(&mut self) -> Option<Queued> {
+        let option_queued = self.queued.pop_front();
+        self.handling = option_queued.is_some();
+
+        option_queued
+    }
+}
@@ -49,6 +49,77 @@ mod traps_exit {
     }
 }
 
+mod exit_from_signal {
+    use super::*;
+
+    use crate::erts::exception;
+    use crate::erts::process::trace::Trace;
+
+    #[test]
+    fn exits_waiting_process_immediately() {
+        let process = process();
+        process.wait();
+
+        process.exit_from_signal(exit(atom!("shutdown")));
+
+        assert_eq!(exit_reason(&process), Some(atom!("shutdown")));
+    }
+
+    #[test]
+    fn exits_running_process_once_it_stops_running() {
+        let process = process();
+        process.start_running();
+
+        process.exit_from_signal(exit(atom!("shutdown")));
+        process.exit_from_signal(exit(atom!("later")));
+
+        assert_eq!(*process.status.read(), Status::Running);
+
+        process.stop_running();
+
+        assert_eq!(exit_reason(&process), Some(atom!("shutdown")));
+    }
+
+    #[test]
+    fn running_process_that_waits_still_exits_once_it_stops_running() {
+        let process = process();
+        process.start_running();
+
+        process.exit_from_signal(exit(atom!("shutdown")));
+        process.wait();
+        process.stop_running();
+
+        assert_eq!(exit_reason(&process), Some(atom!("shutdown")));
+    }
+
+    #[test]
+    fn exiting_process_keeps_its_own_reason() {
+        let process = process();
+        process.start_running();
+
+        process.exit_from_signal(exit(atom!("shutdown")));
+        process.exit(atom!("own"), Trace::capture(), None);
+        process.stop_running();
+
+        assert_eq!(exit_reason(&process), Some(atom!("own")));
+
+        process.exit_from_signal(exit(atom!("shutdown")));
+
+        assert_eq!(exit_reason(&process), Some(atom!("own")));
+    }
+
+    fn exit(reason: Term) -> exception::RuntimeException {
+        exception::exit(reason, Trace::capture(), None)
+    }
+
+    fn exit_reason(process: &Process) -> Option<Term> {
+        match *process.status.read() {
+            Status::RuntimeException(ref exception) => Some(exception.reason()),
+            _ => None,
+        }
+    }
+}
+
 mod integer {
     use super::*;
 
@@ -66,6 +137,573 @@ mod integer {
     }
 }
 
+mod test_mailbox {
+    use super::*;
+
+    #[test]
+    fn injected_messages_are_received_in_order() {
+        let sender = process();
+        let receiver = process();
+        let messages = messages(&sender);
+
+        for message in &messages {
+            receiver.test_inject_message(*message);
+        }
+
+        // Walk the mailbox the same way the receive state machine does
+        let mailbox_guard = receiver.mailbox.lock();
+        let mailbox = mailbox_guard.borrow();
+        let mut cursor = mailbox.cursor();
+        let mut received = Vec::new();
+
+        while let Some(message) = cursor.get() {
+            received.push(message.data());
+            cursor.move_prev();
+        }
+
+        assert_eq!(received, messages);
+    }
+
+    #[test]
+    fn snapshot_does_not_consume_messages() {
+        let sender = process();
+        let receiver = process();
+        let messages = messages(&sender);
+
+        for message in &messages {
+            receiver.test_inject_message(*message);
+        }
+
+        assert_eq!(receiver.test_mailbox_snapshot(), messages);
+        assert_eq!(receiver.test_mailbox_snapshot(), messages);
+        assert_eq!(receiver.mailbox.lock().borrow().len(), messages.len());
+    }
+
+    #[test]
+    fn drain_empties_mailbox() {
+        let sender = process();
+        let receiver = process();
+        let messages = messages(&sender);
+
+        for message in &messages {
+            receiver.test_inject_message(*message);
+        }
+
+        assert_eq!(receiver.test_drain_mailbox(), messages);
+        assert!(receiver.mailbox.lock().borrow().is_empty());
+        assert!(receiver.test_mailbox_snapshot().is_empty());
+    }
+
+    #[test]
+    fn injected_messages_interleave_with_sends_in_arrival_order() {
+        let sender = process();
+        let receiver = process();
+        let messages = messages(&sender);
+
+        receiver.test_inject_message(messages[0]);
+        receiver.send_from_other(messages[1]);
+        receiver.test_inject_message(messages[2]);
+
+        assert_eq!(receiver.test_mailbox_snapshot(), messages);
+    }
+
+    fn messages(sender: &Process) -> Vec<Term> {
+        vec![sender.integer(1), atom!("two"), atom!("three")]
+    }
+}
+
+mod message_queue_off_heap {
+    use super::*;
+
+    use core::convert::TryInto;
+
+    use std::time::{Duration, Instant};
+
+    use crate::erts::process::gc::RootSet;
+
+    const QUEUED_MESSAGE_COUNT: usize = 100_000;
+
+    #[test]
+    fn returns_old_value() {
+        let process = process();
+
+        assert_eq!(process.message_queue_off_heap(true), false);
+        assert_eq!(process.message_queue_off_heap(false), true);
+    }
+
+    #[test]
+    fn messages_are_kept_outside_the_heap_until_received() {
+        let sender = process();
+        let receiver = process();
+        receiver.message_queue_off_heap(true);
+        let total_heap_size = receiver.total_heap_size();
+
+        receiver.send_from_other(sender.tuple_from_slice(&[atom!("message"), sender.integer(1)]));
+
+        assert_eq!(receiver.total_heap_size(), total_heap_size);
+        assert!(receiver
+            .mailbox
+            .lock()
+            .borrow()
+            .iter()
+            .all(|message| matches!(message.data, MessageData::Mailbox(_))));
+
+        let received = receive(&receiver, |_| true).unwrap();
+
+        assert!(receiver.total_heap_size() > total_heap_size);
+
+        let mut roots = [received];
+        receiver.garbage_collect(0, &mut roots[..]).unwrap();
+
+        assert_eq!(
+            roots[0],
+            receiver.tuple_from_slice(&[atom!("message"), receiver.integer(1)])
+        );
+    }
+
+    #[test]
+    fn selective_receive_matches_messages_in_order() {
+        let sender = process();
+        let receiver = process();
+
+        // Messages queued on and off the heap are received in the order they were sent
+        for i in 0..10 {
+            if i == 5 {
+                receiver.message_queue_off_heap(true);
+            }
+
+            receiver.send_from_other(sender.tuple_from_slice(&[atom!("n"), sender.integer(i)]));
+        }
+
+        let even = |message: Term| {
+            let tuple: Boxed<Tuple> = message.try_into().unwrap();
+            let n: isize = tuple[1].try_into().unwrap();
+
+            n % 2 == 0
+        };
+        let mut received = Vec::new();
+        while let Some(message) = receive(&receiver, even) {
+            received.push(message);
+        }
+        // The queue survives a collection between receives, as do the received messages
+        receiver.garbage_collect(0, &mut received[..]).unwrap();
+        while let Some(message) = receive(&receiver, |_| true) {
+            received.push(message);
+        }
+
+        let expected: Vec<Term> = [0, 2, 4, 6, 8, 1, 3, 5, 7, 9]
+            .iter()
+            .map(|i| receiver.tuple_from_slice(&[atom!("n"), receiver.integer(*i)]))
+            .collect();
+        assert_eq!(received, expected);
+        assert_eq!(receiver.message_queue_len(), 0);
+    }
+
+    #[test]
+    fn garbage_collection_does_not_scan_queued_messages() {
+        let sender = process();
+        let message = sender.tuple_from_slice(&[atom!("message"), sender.integer(1)]);
+
+        let on_heap = process();
+        let off_heap = process();
+        off_heap.message_queue_off_heap(true);
+        full_sweep_duration(&off_heap);
+        let empty_heap_size = off_heap.total_heap_size();
+
+        for _ in 0..QUEUED_MESSAGE_COUNT {
+            on_heap.send_from_other(message);
+            off_heap.send_from_other(message);
+        }
+
+        let on_heap_duration = full_sweep_duration(&on_heap);
+        let off_heap_duration = full_sweep_duration(&off_heap);
+
+        assert_eq!(off_heap.total_heap_size(), empty_heap_size);
+        assert_eq!(off_heap.message_queue_len(), QUEUED_MESSAGE_COUNT);
+        assert!(
+            off_heap_duration < on_heap_duration,
+            "collecting with {} messages queued off heap took {:?}, but on heap only {:?}",
+            QUEUED_MESSAGE_COUNT,
+            off_heap_duration,
+            on_heap_duration
+        );
+        assert_eq!(receive(&off_heap, |_| true), Some(message));
+    }
+
+    fn full_sweep_duration(process: &Process) -> Duration {
+        process.set_flags(ProcessFlags::NeedFullSweep);
+        let start = Instant::now();
+        process.garbage_collect(0, RootSet::empty()).unwrap();
+
+        start.elapsed()
+    }
+
+    // Removes the oldest message matching `predicate` the same way the receive state machine does
+    fn receive<P>(process: &Process, predicate: P) -> Option<Term>
+    where
+        P: Fn(Term) -> bool,
+    {
+        let mailbox_guard = process.mailbox.lock();
+        let mut mailbox = mailbox_guard.borrow_mut();
+        let mut cursor = mailbox.cursor();
+        let mut matched = None;
+
+        while let Some(message) = cursor.get() {
+            if predicate(message.data()) {
+                matched = Some((message as *const Message, message.data()));
+                break;
+            }
+            cursor.move_prev();
+        }
+
+        matched.map(|(message, data)| {
+            if let Some(fragment) = mailbox.remove(message) {
+                process.attach_fragment(unsafe { &mut *fragment.as_ptr() });
+            }
+            data
+        })
+    }
+}
+
+mod send_binary {
+    use super::*;
+
+    use core::convert::TryInto;
+
+    use crate::erts::process::gc::RootSet;
+
+    const LEN: usize = 1024 * 1024;
+
+    #[test]
+    fn refc_binary_is_shared_with_the_receiver() {
+        let sender = process();
+        let receiver = process();
+        let sent = sender.binary_from_bytes(&[7; LEN]);
+
+        receiver.send_from_other(sent);
+
+        let received = receiver.test_mailbox_snapshot()[0];
+        let sent_bin = proc_bin(sent);
+        let received_bin = proc_bin(received);
+
+        assert_ne!(sent_bin.as_ptr(), received_bin.as_ptr());
+        assert_eq!(
+            sent_bin.as_bytes().as_ptr(),
+            received_bin.as_bytes().as_ptr()
+        );
+        assert_eq!(sent_bin.ref_count(), 2);
+
+        drop(receiver);
+
+        assert_eq!(sent_bin.ref_count(), 1);
+    }
+
+    #[test]
+    fn refc_binary_queued_off_heap_is_released_with_the_receiver() {
+        let sender = process();
+        let receiver = process();
+        receiver.message_queue_off_heap(true);
+        let sent = sender.binary_from_bytes(&[7; LEN]);
+
+        receiver.send_from_other(sent);
+
+        let sent_bin = proc_bin(sent);
+        assert_eq!(
+            sent_bin.as_bytes().as_ptr(),
+            proc_bin(receiver.test_mailbox_snapshot()[0])
+                .as_bytes()
+                .as_ptr()
+        );
+        assert_eq!(sent_bin.ref_count(), 2);
+
+        drop(receiver);
+
+        assert_eq!(sent_bin.ref_count(), 1);
+    }
+
+    #[test]
+    fn refc_binary_is_released_when_the_receiver_collects_it() {
+        let sender = process();
+        let receiver = process();
+        let sent = sender.binary_from_bytes(&[7; LEN]);
+
+        receiver.send_from_other(sent);
+
+        let mut received = receiver.test_drain_mailbox();
+        receiver.garbage_collect(0, &mut received[..]).unwrap();
+
+        let sent_bin = proc_bin(sent);
+        assert_eq!(
+            sent_bin.as_bytes().as_ptr(),
+            proc_bin(received[0]).as_bytes().as_ptr()
+        );
+        assert_eq!(sent_bin.ref_count(), 2);
+
+        receiver.set_flags(ProcessFlags::NeedFullSweep);
+        receiver.garbage_collect(0, RootSet::empty()).unwrap();
+
+        assert_eq!(sent_bin.ref_count(), 1);
+    }
+
+    #[test]
+    fn heap_binary_is_copied_to_the_receiver() {
+        let sender = process();
+        let receiver = process();
+        let sent = sender.binary_from_bytes(&[7; HeapBin::MAX_SIZE]);
+
+        receiver.send_from_other(sent);
+
+        let received = receiver.test_mailbox_snapshot()[0];
+        let sent_bin: Boxed<HeapBin> = sent.decode().unwrap().try_into().unwrap();
+        let received_bin: Boxed<HeapBin> = received.decode().unwrap().try_into().unwrap();
+
+        assert_ne!(
+            sent_bin.as_bytes().as_ptr(),
+            received_bin.as_bytes().as_ptr()
+        );
+        assert_eq!(received_bin.as_bytes(), sent_bin.as_bytes());
+    }
+
+    fn proc_bin(term: Term) -> Boxed<ProcBin> {
+        term.decode().unwrap().try_into().unwrap()
+    }
+}
+
+mod hibernate {
+    use super::*;
+
+    use crate::erts::process::gc::RootSet;
+
+    const LARGE_LIST_LENGTH: usize = 100_000;
+
+    #[test]
+    fn shrinks_heap_to_the_continuation() {
+        let process = process();
+        let heap_size_with_list = allocate_large_list(&process);
+        let arguments = process.list_from_slice(&[atom!("state")]);
+
+        process
+            .hibernate(atom!("module"), atom!("function"), arguments)
+            .unwrap();
+
+        let heap_size = process.heap_size();
+
+        assert!(
+            heap_size < heap_size_with_list,
+            "heap_size ({}) did not shrink from {} after hibernating",
+            heap_size,
+            heap_size_with_list
+        );
+        assert_eq!(*process.status.read(), Status::Waiting);
+    }
+
+    #[test]
+    fn keeps_continuation_until_taken() {
+        let process = process();
+        allocate_large_list(&process);
+        let arguments = process.list_from_slice(&[process.integer(1), atom!("state")]);
+
+        process
+            .hibernate(atom!("module"), atom!("function"), arguments)
+            .unwrap();
+
+        let [module, function, arguments] = process.take_hibernation().unwrap();
+
+        assert_eq!(module, atom!("module"));
+        assert_eq!(function, atom!("function"));
+        assert_eq!(
+            arguments,
+            process.list_from_slice(&[process.integer(1), atom!("state")])
+        );
+        assert_eq!(process.take_hibernation(), None);
+    }
+
+    #[test]
+    fn message_wakes_process_with_message_first_in_queue() {
+        let sender = process();
+        let process = process();
+        process
+            .hibernate(atom!("module"), atom!("function"), Term::NIL)
+            .unwrap();
+
+        let message = sender.tuple_from_slice(&[atom!("wake"), sender.integer(1)]);
+        process.send_from_other(message);
+
+        // As the scheduler does after sending
+        assert!(process.stop_waiting());
+        assert_eq!(*process.status.read(), Status::Runnable);
+        assert!(process.take_hibernation().is_some());
+        assert_eq!(process.test_mailbox_snapshot(), vec![message]);
+    }
+
+    #[test]
+    fn with_message_already_queued_does_not_wait() {
+        let process = process();
+        process.send_from_other(atom!("early"));
+        process.start_running();
+
+        process
+            .hibernate(atom!("module"), atom!("function"), Term::NIL)
+            .unwrap();
+
+        assert_eq!(*process.status.read(), Status::Running);
+        assert!(process.take_hibernation().is_some());
+    }
+
+    /// Allocates a list on `process`'s heap, growing the heap to fit it, and returns the heap size
+    /// with the list on it.  The list is not rooted, as if it were only on the discarded stack.
+    fn allocate_large_list(process: &Process) -> usize {
+        process.set_flags(ProcessFlags::NeedFullSweep);
+        process
+            .garbage_collect(2 * LARGE_LIST_LENGTH, RootSet::empty())
+            .unwrap();
+
+        let elements = vec![Term::NIL; LARGE_LIST_LENGTH];
+        process.list_from_slice(&elements);
+
+        process.heap_size()
+    }
+}
+
+mod consume_reductions {
+    use super::*;
+
+    #[test]
+    fn returns_true_once_budget_is_used_up() {
+        let process = process();
+        process.start_running();
+
+        assert!(!process.consume_reductions(MAX_REDUCTIONS_PER_RUN - 1));
+        assert!(process.consume_reductions(1));
+    }
+
+    #[test]
+    fn overrun_comes_out_of_budget_of_next_run() {
+        let process = process();
+        process.start_running();
+
+        assert!(process.consume_reductions(MAX_REDUCTIONS_PER_RUN + 100));
+
+        process.stop_running();
+
+        assert_eq!(process.reductions(), MAX_REDUCTIONS_PER_RUN as u64 + 100);
+
+        process.start_running();
+
+        assert!(!process.consume_reductions(MAX_REDUCTIONS_PER_RUN - 101));
+        assert!(process.consume_reductions(1));
+    }
+
+    #[test]
+    fn overrun_is_only_charged_to_the_next_run() {
+        let process = process();
+        process.start_running();
+
+        assert!(process.consume_reductions(u16::MAX));
+
+        process.stop_running();
+        process.start_running();
+
+        assert!(process.is_reduced());
+
+        process.stop_running();
+        process.start_running();
+
+        assert!(!process.is_reduced());
+    }
+}
+
+mod new_with_stack {
+    use super::*;
+
+    use crate::erts::process::alloc::{FixedPoolStackAllocator, StackAllocError, StackAllocator};
+
+    const POOL_SIZE: usize = 4;
+
+    #[test]
+    fn fails_with_exhausted_once_pool_is_full() {
+        let stack_allocator = stack_allocator();
+
+        let processes = (0..POOL_SIZE)
+            .map(|_| process_with_stack(&stack_allocator).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(stack_allocator.remaining(), Some(0));
+        assert_eq!(
+            process_with_stack(&stack_allocator).unwrap_err(),
+            StackAllocError::Exhausted {
+                capacity: POOL_SIZE
+            }
+        );
+
+        drop(processes);
+
+        assert_eq!(stack_allocator.remaining(), Some(POOL_SIZE));
+    }
+
+    #[test]
+    fn reuses_stacks_of_dropped_processes() {
+        let stack_allocator = stack_allocator();
+        let mut processes = Vec::with_capacity(POOL_SIZE);
+
+        for _ in 0..(3 * POOL_SIZE) {
+            if processes.len() == POOL_SIZE {
+                processes.remove(0);
+            }
+
+            processes.push(process_with_stack(&stack_allocator).unwrap());
+
+            assert_eq!(stack_allocator.in_use(), processes.len());
+        }
+    }
+
+    #[test]
+    fn exhausted_is_system_limit_in_erlang() {
+        use crate::erts::exception::{Class, Exception};
+
+        let stack_allocator = stack_allocator();
+        let _processes = (0..POOL_SIZE)
+            .map(|_| process_with_stack(&stack_allocator).unwrap())
+            .collect::<Vec<_>>();
+
+        // Spawning goes through `anyhow`, so check the conversion the natives use
+        let err = anyhow::Error::new(process_with_stack(&stack_allocator).unwrap_err());
+
+        match Exception::from(err) {
+            Exception::Runtime(exception) => {
+                assert_eq!(exception.class(), Class::Error { arguments: None });
+                assert_eq!(exception.reason(), atom!("system_limit"));
+            }
+            Exception::System(err) => panic!("expected system_limit, got {:?}", err),
+        }
+    }
+
+    fn stack_allocator() -> FixedPoolStackAllocator {
+        FixedPoolStackAllocator::new(POOL_SIZE, 16 * 1024).unwrap()
+    }
+
+    fn process_with_stack(
+        stack_allocator: &dyn StackAllocator,
+    ) -> Result<Process, StackAllocError> {
+        let init = atom_from_str!("init");
+        let initial_module_function_arity = ModuleFunctionArity {
+            module: init,
+            function: init,
+            arity: 0,
+        };
+        let (heap, heap_size) = alloc::default_heap().unwrap();
+
+        Process::new_with_stack(
+            Priority::Normal,
+            None,
+            initial_module_function_arity,
+            heap,
+            heap_size,
+            stack_allocator,
+        )
+    }
+}
+
 pub(super) fn process() -> Process {
     let init = atom_from_str!("init");
     let initial_module_function_arity = ModuleFunctionArity {
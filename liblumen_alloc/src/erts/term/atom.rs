@@ -79,6 +79,17 @@ pub fn dump_atoms() {
     table.dump();
 }
 
+/// Returns the number of atoms in the atom table
+pub fn atom_count() -> usize {
+    ATOMS.read().len()
+}
+
+/// Returns the number of atoms in the atom table, unless it is being written to, for when
+/// blocking could deadlock, such as in crash dumps
+pub fn try_atom_count() -> Option<usize> {
+    ATOMS.try_read().map(|table| table.len())
+}
+
 /// An interned string, represented in memory as a integer ID.
 ///
 /// This struct is simply a transparent wrapper around the ID.
@@ -489,6 +500,10 @@ impl AtomTable {
         Ok(id)
     }
 
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+
     fn dump(&self) {
         for (id, name) in self.names.iter() {
             println!("atom(id = {}, value = '{}')", *id, name);
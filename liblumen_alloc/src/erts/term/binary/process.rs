@@ -12,8 +12,7 @@ use liblumen_core::offset_of;
 
 use crate::borrow::CloneToProcess;
 use crate::erts::exception::AllocResult;
-use crate::erts::process::alloc::TermAlloc;
-use crate::erts::process::Process;
+use crate::erts::process::alloc::{TermAlloc, VirtualAllocator};
 use crate::erts::string::Encoding;
 use crate::erts::term::prelude::*;
 
@@ -162,6 +161,12 @@ impl ProcBin {
         unsafe { self.inner.as_ref() }
     }
 
+    /// Returns the number of references to the underlying binary
+    #[cfg(test)]
+    pub(crate) fn ref_count(&self) -> usize {
+        self.inner().refc.load(atomic::Ordering::Acquire)
+    }
+
     // Non-inlined part of `drop`.
     #[inline(never)]
     unsafe fn drop_slow(&self) {
@@ -218,18 +223,8 @@ impl Clone for ProcBin {
 }
 
 impl CloneToProcess for ProcBin {
-    fn clone_to_process(&self, process: &Process) -> Term {
-        let mut heap = process.acquire_heap();
-        let boxed = self.clone_to_heap(&mut heap).unwrap();
-        let ptr: *mut Self = boxed.dyn_cast();
-        self.inner().refc.fetch_add(1, atomic::Ordering::AcqRel);
-        // Reify a reference to the newly written clone, and push it
-        // on to the process virtual heap
-        let clone = unsafe { &*ptr };
-        process.virtual_alloc(clone);
-        boxed
-    }
-
+    /// Only the header is copied, the binary data is shared with the original, and the
+    /// copy holds its own reference to it
     fn clone_to_heap<A>(&self, heap: &mut A) -> AllocResult<Term>
     where
         A: ?Sized + TermAlloc,
@@ -238,15 +233,11 @@ impl CloneToProcess for ProcBin {
             // Allocate space for the header
             let layout = Layout::new::<Self>();
             let ptr = heap.alloc_layout(layout)?.as_ptr() as *mut Self;
-            // Write the binary header with an empty link
-            ptr::write(
-                ptr,
-                Self {
-                    header: self.header.clone(),
-                    inner: self.inner,
-                    link: LinkedListLink::new(),
-                },
-            );
+            // Write the binary header with an empty link, taking a new reference
+            ptr::write(ptr, self.clone());
+            // Process heaps release the reference when the header is collected, or the heap
+            // dropped, everything else does so when the header is released
+            heap.link_clone(Boxed::new_unchecked(ptr));
             // Reify result term
             Ok(ptr.into())
         }
@@ -257,6 +248,25 @@ impl CloneToProcess for ProcBin {
     }
 }
 
+/// Links headers cloned on to a heap to its virtual binary heap, if it has one
+trait LinkClone {
+    fn link_clone(&mut self, clone: Boxed<ProcBin>);
+}
+impl<A> LinkClone for A
+where
+    A: ?Sized + TermAlloc,
+{
+    default fn link_clone(&mut self, _clone: Boxed<ProcBin>) {}
+}
+impl<A> LinkClone for A
+where
+    A: TermAlloc + VirtualAllocator<ProcBin>,
+{
+    fn link_clone(&mut self, clone: Boxed<ProcBin>) {
+        self.virtual_alloc(clone);
+    }
+}
+
 impl Drop for ProcBin {
     fn drop(&mut self) {
         if self.inner().refc.fetch_sub(1, atomic::Ordering::Release) != 1 {
@@ -106,6 +106,13 @@ impl SubBinary {
         self.original
     }
 
+    /// Used by garbage collection to update the reference to the original
+    /// binary when it is moved along with this sub-binary
+    #[inline]
+    pub(in crate::erts) fn original_mut(&mut self) -> &mut Term {
+        &mut self.original
+    }
+
     /// During garbage collection, we sometimes want to convert sub-binary terms
     /// into full-fledged heap binaries, so that the original full-size binary can be freed.
     ///
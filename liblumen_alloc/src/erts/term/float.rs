@@ -18,7 +18,7 @@ use core::fmt::{self, Display};
 use core::hash::{self, Hash};
 use core::ops::*;
 
-use num_bigint::{BigInt, Sign};
+use num_bigint::BigInt;
 
 use super::prelude::*;
 
@@ -112,64 +112,55 @@ impl PartialOrd<f64> for Float {
 impl PartialOrd<SmallInteger> for Float {
     #[inline]
     fn partial_cmp(&self, other: &SmallInteger) -> Option<Ordering> {
-        use core::num::FpCategory;
-
-        let value = self.value();
-        let is_negative = value.is_sign_negative();
-        match value.classify() {
-            FpCategory::Nan => None,
-            FpCategory::Subnormal => {
-                // The float is less precise, so convert to isize
-                let f = value as isize;
-                Some(f.cmp(&other.0))
-            }
-            FpCategory::Infinite if is_negative => Some(Ordering::Less),
-            FpCategory::Infinite => Some(Ordering::Greater),
-            FpCategory::Zero => Some(0.cmp(&other.0)),
-            FpCategory::Normal => {
-                // Float is higher precision
-                let i = other.0 as f64;
-                value.partial_cmp(&i)
-            }
-        }
+        isize_cmp_f64(other.0, self.value()).map(|o| o.reverse())
     }
 }
 impl PartialOrd<BigInteger> for Float {
     #[inline]
     fn partial_cmp(&self, other: &BigInteger) -> Option<Ordering> {
-        use core::num::FpCategory;
-        use num_traits::Zero;
-
-        let value = self.value();
-        let is_negative = value.is_sign_negative();
-        match value.classify() {
-            FpCategory::Nan => None,
-            FpCategory::Subnormal => {
-                // The float is less precise, so convert to isize
-                let f = BigInt::from(value as isize);
-                Some(f.cmp(&other.value))
-            }
-            FpCategory::Infinite if is_negative => Some(Ordering::Less),
-            FpCategory::Infinite => Some(Ordering::Greater),
-            FpCategory::Zero => {
-                let f: BigInt = Zero::zero();
-                Some(f.cmp(&other.value))
-            }
-            FpCategory::Normal => {
-                use num_traits::ToPrimitive;
-                // Float is higher precision, try and convert to it,
-                // if we fail, then the bigint is larger in either direction,
-                // which we must determine based on its sign
-                if let Some(i) = other.value.to_isize() {
-                    return value.partial_cmp(&(i as f64));
-                }
-                if let Sign::Minus = other.value.sign() {
-                    return Some(Ordering::Greater);
-                }
-                Some(Ordering::Less)
-            }
-        }
+        big_int_cmp_f64(&other.value, self.value()).map(|o| o.reverse())
+    }
+}
+
+/// Compares `integer` with `float` exactly, as BEAM does.
+///
+/// Integers whose absolute value is at most 2^53 convert to `f64` without losing precision,
+/// so they are compared as floats.  Larger integers are compared against the float in the
+/// integer domain instead, so that no precision is lost and comparisons remain transitive.
+pub(crate) fn isize_cmp_f64(integer: isize, float: f64) -> Option<Ordering> {
+    if (integer as i64).unsigned_abs() <= (Float::INTEGRAL_MAX as u64) {
+        (integer as f64).partial_cmp(&float)
+    } else {
+        big_int_cmp_f64(&BigInt::from(integer), float)
+    }
+}
+
+/// Compares `integer` with `float` exactly, by comparing `integer` with the integral part of
+/// `float`, and only then with its fractional part.
+pub(crate) fn big_int_cmp_f64(integer: &BigInt, float: f64) -> Option<Ordering> {
+    use num_traits::FromPrimitive;
+
+    if float.is_nan() {
+        return None;
     }
+    if float.is_infinite() {
+        return Some(if float.is_sign_negative() {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        });
+    }
+
+    let integral = float.trunc();
+    // Finite integral floats are always exactly representable as a `BigInt`
+    let integral_big_int = BigInt::from_f64(integral).unwrap();
+    let fract = float - integral;
+
+    Some(
+        integer
+            .cmp(&integral_big_int)
+            .then_with(|| 0.0.partial_cmp(&fract).unwrap()),
+    )
 }
 
 impl Display for Float {
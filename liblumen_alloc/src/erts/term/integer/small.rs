@@ -6,6 +6,7 @@ use core::ops::*;
 use num_bigint::BigInt;
 
 use crate::erts::term::arch::{MAX_SMALLINT_VALUE, MIN_SMALLINT_VALUE};
+use crate::erts::term::float::isize_cmp_f64;
 use crate::erts::term::prelude::*;
 
 use super::*;
@@ -344,7 +345,7 @@ smallint_bitop_trait_impl!(BitXor, bitxor);
 impl PartialEq<Float> for SmallInteger {
     #[inline]
     fn eq(&self, other: &Float) -> bool {
-        (self.0 as f64).eq(&other.value())
+        self.partial_cmp(other) == Some(Ordering::Equal)
     }
 }
 impl PartialEq<BigInteger> for SmallInteger {
@@ -381,7 +382,7 @@ where
 impl PartialOrd<Float> for SmallInteger {
     #[inline]
     fn partial_cmp(&self, other: &Float) -> Option<Ordering> {
-        (self.0 as f64).partial_cmp(&other.value())
+        isize_cmp_f64(self.0, other.value())
     }
 }
 impl PartialOrd<BigInteger> for SmallInteger {
@@ -3,7 +3,7 @@ use thiserror::Error;
 use super::time::{Milliseconds, Monotonic};
 
 #[derive(Debug, Error)]
-#[error("invalid timeout value, must be `infinity` or a non-negative integer value")]
+#[error("invalid timeout value, must be `infinity` or a non-negative integer no greater than 4294967295")]
 pub struct InvalidTimeoutError;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,10 +13,15 @@ pub enum Timeout {
     Infinity,
 }
 impl Timeout {
+    /// The longest timeout, which as in BEAM, is the most milliseconds that fit in 32 bits
+    pub const MAX_MILLISECONDS: u64 = u32::MAX as u64;
+
     pub fn from_millis<T: Into<isize>>(to: T) -> Result<Self, InvalidTimeoutError> {
         match to.into() {
             0 => Ok(Self::Immediate),
-            ms if ms >= 0 => Ok(Self::Duration(Milliseconds(ms as u64))),
+            ms if ms > 0 && (ms as u64) <= Self::MAX_MILLISECONDS => {
+                Ok(Self::Duration(Milliseconds(ms as u64)))
+            }
             _ => Err(InvalidTimeoutError),
         }
     }
@@ -1,6 +1,6 @@
 use core::fmt;
 
-use crate::traits::{Aligned, Binary};
+use crate::traits::{Aligned, Binary, Bitstring};
 
 /// Creates a mask which can be used to extract `n` bits from a byte,
 /// starting from the least-significant bit.
@@ -140,7 +140,7 @@ pub fn display_binary<B: Binary + Aligned>(bin: B, f: &mut fmt::Formatter) -> fm
 pub fn display_bytes<I: Iterator<Item = u8>>(mut bytes: I, f: &mut fmt::Formatter) -> fmt::Result {
     f.write_str("<<")?;
 
-    let Some(byte) = bytes.next() else { return Ok(()); };
+    let Some(byte) = bytes.next() else { return f.write_str(">>"); };
     write!(f, "{}", byte)?;
 
     for byte in bytes {
@@ -150,6 +150,45 @@ pub fn display_bytes<I: Iterator<Item = u8>>(mut bytes: I, f: &mut fmt::Formatte
     f.write_str(">>")
 }
 
+/// Displays a bitstring as `erlang:display/1` does
+///
+/// A non-empty binary of printable ASCII characters is written as a string, e.g. `<<"abc">>`,
+/// and anything else as its byte values, with the value and size of any trailing partial byte
+/// written as `Value:Size`, e.g. `<<1,2:3>>`.
+pub fn display_bitstring<B: ?Sized + Bitstring>(bits: &B, f: &mut fmt::Formatter) -> fmt::Result {
+    use core::fmt::Write;
+
+    let trailing_bits = (bits.bit_size() % 8) as u8;
+    let printable = trailing_bits == 0
+        && bits.byte_size() > 0
+        && bits.bytes().all(|byte| (b' '..=b'~').contains(&byte));
+    if printable {
+        f.write_str("<<\"")?;
+        for byte in bits.bytes() {
+            if byte == b'"' || byte == b'\\' {
+                f.write_char('\\')?;
+            }
+            f.write_char(byte as char)?;
+        }
+        return f.write_str("\">>");
+    }
+
+    f.write_str("<<")?;
+    let len = bits.byte_size();
+    for (i, byte) in bits.bytes().enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        if i + 1 == len && trailing_bits > 0 {
+            // The unused low bits of a partial byte are zeroed
+            write!(f, "{}:{}", byte >> (8 - trailing_bits), trailing_bits)?;
+        } else {
+            write!(f, "{}", byte)?;
+        }
+    }
+    f.write_str(">>")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
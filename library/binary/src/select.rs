@@ -1095,17 +1095,7 @@ impl<'a> Hash for Selection<'a> {
 }
 impl<'a> fmt::Display for Selection<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use core::fmt::Write;
-
-        if let Some(s) = self.to_str() {
-            f.write_str("<<\"")?;
-            for c in s.escape_default() {
-                f.write_char(c)?;
-            }
-            f.write_str("\">>")
-        } else {
-            display_bytes(self.bytes(), f)
-        }
+        display_bitstring(self, f)
     }
 }
 
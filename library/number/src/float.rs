@@ -92,6 +92,48 @@ impl Float {
     pub fn is_finite(&self) -> bool {
         self.0.is_finite()
     }
+
+    /// Parses a float from `bytes` written the way `erlang:list_to_float/1` requires: an optional
+    /// `+` or `-`, at least one digit, a `.` followed by at least one digit, and an optional
+    /// exponent of `e` or `E`, an optional sign and at least one digit.
+    ///
+    /// Nothing else is accepted, such as whitespace, `_` separators, or a missing fraction, and
+    /// values too large to be finite are rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        fn digits(bytes: &[u8]) -> Option<&[u8]> {
+            let len = bytes
+                .iter()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count();
+            if len == 0 {
+                None
+            } else {
+                Some(&bytes[len..])
+            }
+        }
+
+        let rest = match bytes {
+            [b'-' | b'+', rest @ ..] => rest,
+            rest => rest,
+        };
+        let rest = match digits(rest)? {
+            [b'.', rest @ ..] => digits(rest)?,
+            _ => return None,
+        };
+        match rest {
+            [] => (),
+            [b'e' | b'E', b'-' | b'+', exponent @ ..] | [b'e' | b'E', exponent @ ..] => {
+                if !digits(exponent)?.is_empty() {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+
+        // Everything is ASCII and in a syntax Rust accepts too, which rounds correctly
+        let float: f64 = core::str::from_utf8(bytes).ok()?.parse().ok()?;
+        Self::new(float).ok()
+    }
 }
 impl fmt::Debug for Float {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -464,3 +506,85 @@ impl Rem<&Integer> for Float {
         self % rhs.to_efloat().map_err(|_| DivisionError)?
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use super::*;
+
+    #[test]
+    fn from_bytes_accepts_erlang_float_syntax() {
+        for (bytes, float) in [
+            (&b"1.0"[..], 1.0),
+            (b"-0.5", -0.5),
+            (b"+2.25", 2.25),
+            (b"0.1", 0.1),
+            (b"1.0e-5", 1.0e-5),
+            (b"1.5E10", 1.5e10),
+            (b"1.5e+10", 1.5e10),
+            (b"123456789.0", 123456789.0),
+            (b"007.000", 7.0),
+            (b"1.0e-400", 0.0),
+        ] {
+            assert_eq!(
+                Float::from_bytes(bytes).map(|f| f.inner()),
+                Some(float),
+                "{:?}",
+                core::str::from_utf8(bytes)
+            );
+        }
+    }
+
+    #[test]
+    fn from_bytes_rejects_anything_else() {
+        for bytes in [
+            &b""[..],
+            b"-",
+            b"1",
+            b"1.",
+            b".5",
+            b"1e10",
+            b"1.0e",
+            b"1.0e+",
+            b"1.0e1.0",
+            b" 1.0",
+            b"1.0 ",
+            b"1_000.0",
+            b"--1.0",
+            b"inf",
+            b"NaN",
+            b"0x1.0",
+            b"1.0e400",
+        ] {
+            assert!(
+                Float::from_bytes(bytes).is_none(),
+                "{:?}",
+                core::str::from_utf8(bytes)
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_through_scientific_notation() {
+        // xorshift, so the samples are the same on every run
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+
+        for _ in 0..10_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            let Ok(float) = Float::new(f64::from_bits(state)) else { continue; };
+            // 17 significant digits are always enough to round-trip
+            let string = format!("{:.16e}", float.inner());
+
+            assert_eq!(
+                Float::from_bytes(string.as_bytes()).map(|f| f.raw()),
+                Some(float.raw()),
+                "{}",
+                string
+            );
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::any::TypeId;
 use core::cmp::Ordering;
 use core::fmt;
@@ -7,7 +9,7 @@ use core::str::FromStr;
 pub use num_bigint::ToBigInt;
 pub use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
 
-use num_bigint::{BigInt, ParseBigIntError};
+use num_bigint::{BigInt, ParseBigIntError, Sign};
 
 use crate::{DivisionError, Float, FloatError, ShiftError};
 
@@ -73,6 +75,97 @@ impl Integer {
         Some(Self::Big(bi))
     }
 
+    /// Parses an integer written in `radix` from `bytes`, the way `erlang:binary_to_integer/2` does:
+    /// an optional `+` or `-` followed by at least one digit, with letters in either case.
+    ///
+    /// Unlike `from_string_radix`, nothing else is accepted, such as `_` separators, and values
+    /// too large for a small integer are promoted to a big integer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not in `2..=36`.
+    pub fn from_bytes_radix(bytes: &[u8], radix: u32) -> Option<Self> {
+        assert!((2..=36).contains(&radix), "radix must be in 2..=36");
+
+        let (sign, digits) = match bytes {
+            [b'-', digits @ ..] => (Sign::Minus, digits),
+            [b'+', digits @ ..] => (Sign::Plus, digits),
+            digits => (Sign::Plus, digits),
+        };
+        if digits.is_empty() {
+            return None;
+        }
+
+        // Accumulated as a negative number, as there is one more of those than positive numbers
+        let mut acc: i64 = 0;
+        for byte in digits {
+            let digit = (*byte as char).to_digit(radix)? as i64;
+            match acc
+                .checked_mul(radix as i64)
+                .and_then(|acc| acc.checked_sub(digit))
+            {
+                Some(next) => acc = next,
+                None => return Self::big_from_bytes_radix(sign, digits, radix),
+            }
+        }
+
+        match sign {
+            Sign::Minus => Some(Self::new(acc)),
+            _ => match acc.checked_neg() {
+                Some(i) => Some(Self::new(i)),
+                None => Self::big_from_bytes_radix(sign, digits, radix),
+            },
+        }
+    }
+
+    fn big_from_bytes_radix(sign: Sign, digits: &[u8], radix: u32) -> Option<Self> {
+        let digits = digits
+            .iter()
+            .map(|byte| (*byte as char).to_digit(radix).map(|digit| digit as u8))
+            .collect::<Option<Vec<u8>>>()?;
+
+        BigInt::from_radix_be(sign, &digits, radix).map(Self::Big)
+    }
+
+    /// Writes this integer in `radix`, with upper-case letters for digits above 9, the way
+    /// `erlang:integer_to_binary/2` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not in `2..=36`.
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        assert!((2..=36).contains(&radix), "radix must be in 2..=36");
+
+        match self {
+            Self::Small(i) => {
+                // Enough for a sign and the 64 digits of `i64::MIN` in base 2
+                let mut buf = [0u8; 65];
+                let mut start = buf.len();
+                let mut magnitude = i.unsigned_abs();
+                loop {
+                    let digit = (magnitude % radix as u64) as u32;
+                    start -= 1;
+                    buf[start] = char::from_digit(digit, radix).unwrap().to_ascii_uppercase() as u8;
+                    magnitude /= radix as u64;
+                    if magnitude == 0 {
+                        break;
+                    }
+                }
+                if *i < 0 {
+                    start -= 1;
+                    buf[start] = b'-';
+                }
+
+                String::from_utf8(buf[start..].to_vec()).unwrap()
+            }
+            Self::Big(i) => {
+                let mut string = i.to_str_radix(radix);
+                string.make_ascii_uppercase();
+                string
+            }
+        }
+    }
+
     pub fn to_arity(&self) -> u8 {
         match self {
             Self::Small(i) => (*i).try_into().unwrap(),
@@ -1039,3 +1132,146 @@ impl TryInto<usize> for Integer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn to_string_radix_uses_upper_case_letters() {
+        assert_eq!(Integer::new(255).to_string_radix(16), "FF");
+        assert_eq!(Integer::new(-255).to_string_radix(16), "-FF");
+        assert_eq!(Integer::new(0).to_string_radix(2), "0");
+        assert_eq!(Integer::new(i64::MIN).to_string_radix(2).len(), 65);
+        assert_eq!(
+            Integer::Big(BigInt::from(u64::MAX)).to_string_radix(36),
+            "3W5E11264SGSF"
+        );
+    }
+
+    #[test]
+    fn from_bytes_radix_accepts_sign_and_either_case() {
+        assert_eq!(
+            Integer::from_bytes_radix(b"ff", 16),
+            Some(Integer::new(255))
+        );
+        assert_eq!(
+            Integer::from_bytes_radix(b"+Ff", 16),
+            Some(Integer::new(255))
+        );
+        assert_eq!(
+            Integer::from_bytes_radix(b"-zz", 36),
+            Some(Integer::new(-1295))
+        );
+        assert_eq!(Integer::from_bytes_radix(b"007", 8), Some(Integer::new(7)));
+    }
+
+    #[test]
+    fn from_bytes_radix_rejects_invalid_digits() {
+        for (bytes, radix) in [
+            (&b""[..], 10),
+            (&b"-"[..], 10),
+            (&b"+"[..], 10),
+            (&b"--1"[..], 10),
+            (&b"1_000"[..], 10),
+            (&b" 1"[..], 10),
+            (&b"1 "[..], 10),
+            (&b"2"[..], 2),
+            (&b"z"[..], 35),
+            (&b"1.0"[..], 10),
+        ] {
+            assert_eq!(Integer::from_bytes_radix(bytes, radix), None, "{:?}", bytes);
+        }
+
+        // A digit invalid for the radix after the value no longer fits in an `i64`
+        let mut bytes = vec![b'1'; 40];
+        bytes.push(b'A');
+        assert_eq!(Integer::from_bytes_radix(&bytes, 10), None);
+    }
+
+    #[test]
+    fn from_bytes_radix_promotes_to_big_integer_past_small_integer_range() {
+        let max = Integer::from_bytes_radix(Integer::MAX_SMALL.to_string().as_bytes(), 10);
+        assert!(matches!(max, Some(Integer::Small(Integer::MAX_SMALL))));
+
+        let bytes = (Integer::MAX_SMALL as i128 + 1).to_string();
+        let past_max = Integer::from_bytes_radix(bytes.as_bytes(), 10).unwrap();
+        assert!(matches!(past_max, Integer::Big(_)));
+        assert_eq!(past_max.to_string(), bytes);
+
+        let bytes = (i64::MIN as i128 - 1).to_string();
+        let past_i64 = Integer::from_bytes_radix(bytes.as_bytes(), 10).unwrap();
+        assert_eq!(past_i64.to_string(), bytes);
+    }
+
+    #[test]
+    fn round_trips_across_radixes_and_magnitudes() {
+        for radix in 2..=36 {
+            for integer in sample_integers() {
+                let string = integer.to_string_radix(radix);
+                let parsed = Integer::from_bytes_radix(string.as_bytes(), radix);
+
+                assert_eq!(
+                    parsed.as_ref(),
+                    Some(&integer),
+                    "{} in radix {}",
+                    string,
+                    radix
+                );
+                assert_eq!(
+                    Integer::from_bytes_radix(string.to_ascii_lowercase().as_bytes(), radix),
+                    parsed
+                );
+            }
+        }
+    }
+
+    /// Integers at the boundaries between representations, and pseudo-random integers from one
+    /// to several hundred bits, positive and negative
+    fn sample_integers() -> Vec<Integer> {
+        let mut integers: Vec<Integer> = [
+            0,
+            1,
+            -1,
+            Integer::MIN_SMALL,
+            Integer::MIN_SMALL - 1,
+            Integer::MAX_SMALL,
+            Integer::MAX_SMALL + 1,
+            i64::MIN,
+            i64::MAX,
+        ]
+        .into_iter()
+        .map(Integer::new)
+        .collect();
+
+        // xorshift, so the samples are the same on every run
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for bits in (1..=512).step_by(7) {
+            let mut big = BigInt::from(0);
+            for _ in 0..=(bits / 64) {
+                big = (big << 64) + next();
+            }
+            big >>= (bits / 64 + 1) * 64 - bits;
+            if next() % 2 == 0 {
+                big = -big;
+            }
+            integers.push(match big.to_i64() {
+                Some(i) => Integer::new(i),
+                None => Integer::Big(big),
+            });
+        }
+
+        integers
+    }
+}
@@ -11,9 +11,13 @@ use std::path::PathBuf;
 use inflector::Inflector;
 use toml::Value;
 
+/// The compiler's signatures of every BIF, from which the runtime learns which functions are builtins
+const BIFS_RS: &str = "../../compiler/syntax_base/src/bifs.rs";
+
 #[derive(Debug, Default, Clone)]
 struct Symbol {
     key: String,
+    id: Option<i64>,
     value: String,
 }
 impl Eq for Symbol {}
@@ -36,6 +40,9 @@ impl Symbol {
     fn from_value<S: Into<String>>(name: S, value: &Value) -> Self {
         let name = name.into();
         let table = value.as_table().unwrap();
+        let id = table
+            .get("id")
+            .map(|id| id.as_integer().expect("id must be an integer"));
         let value = match table
             .get("value")
             .map(|v| v.as_str().expect("value must be a string"))
@@ -50,13 +57,14 @@ impl Symbol {
         } else {
             name.to_pascal_case()
         };
-        Self { key, value }
+        Self { key, id, value }
     }
 }
 
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=src/term/atom/atoms.toml");
+    println!("cargo:rerun-if-changed={}", BIFS_RS);
 
     let contents = fs::read_to_string("src/term/atom/atoms.toml").unwrap();
     let root = contents.parse::<Value>().unwrap();
@@ -72,7 +80,65 @@ fn main() {
         }
     }
 
+    // The atoms with fixed ids are pre-interned first, in order of id, followed by the rest in the
+    // order they are declared
+    symbols.sort_by_key(|sym| sym.id.unwrap_or(i64::MAX));
+    for (index, sym) in symbols
+        .iter()
+        .take_while(|sym| sym.id.is_some())
+        .enumerate()
+    {
+        assert_eq!(
+            sym.id,
+            Some(index as i64),
+            "fixed atom ids must be contiguous from 0, but {} has id {}",
+            &sym.value,
+            sym.id.unwrap()
+        );
+    }
+
     generate_symbols_rs(symbols).unwrap();
+    generate_builtins_rs().unwrap();
+}
+
+fn generate_builtins_rs() -> std::io::Result<()> {
+    let contents = fs::read_to_string(BIFS_RS)?;
+    let mut builtins: BTreeSet<(String, String, u8)> = BTreeSet::new();
+    for line in contents.lines().map(str::trim_start) {
+        // e.g. `guard_bif!(pub erlang:=</2(term, term) -> bool),`
+        let spec = match line
+            .strip_prefix("bif!(")
+            .or_else(|| line.strip_prefix("guard_bif!("))
+        {
+            Some(spec) => spec,
+            None => continue,
+        };
+        let spec = spec.strip_prefix("pub ").unwrap_or(spec);
+        let (module, rest) = spec.split_once(':').unwrap();
+        let (mfa, _) = rest.split_once('(').unwrap();
+        // Operators like `erlang://2` contain a slash, so the arity follows the last one
+        let (function, arity) = mfa.rsplit_once('/').unwrap();
+        let arity = arity.parse::<u8>().expect("invalid bif arity");
+        builtins.insert((module.to_string(), function.to_string(), arity));
+    }
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("builtins.rs");
+    let mut file = File::create(&out)?;
+    write!(
+        &mut file,
+        r#"/// The module, function and arity of every BIF known to the compiler, in sorted order
+pub(super) static BUILTINS: [(&str, &str, u8); {}] = [
+"#,
+        builtins.len()
+    )?;
+    for (module, function, arity) in builtins.iter() {
+        writeln!(&mut file, "    ({:?}, {:?}, {}),", module, function, arity)?;
+    }
+    file.write_all(b"];\n")?;
+
+    file.sync_data()?;
+
+    Ok(())
 }
 
 fn generate_symbols_rs(symbols: Vec<Symbol>) -> std::io::Result<()> {
@@ -92,7 +158,7 @@ fn generate_symbols_rs(symbols: Vec<Symbol>) -> std::io::Result<()> {
 pub const {0}_VALUE: &'static [u8] = b"{1}";
 
 #[cfg_attr(target_os = "macos", link_section = "__DATA,atoms")]
-#[cfg_attr(all(linux, not(target_os = "macos")), link_section = "__atoms")]
+#[cfg_attr(all(unix, not(target_os = "macos")), link_section = "__atoms")]
 #[export_name = "atom_{1}"]
 #[linkage = "linkonce_odr"]
 pub static {0}_ATOM: AtomData = AtomData {{
@@ -130,6 +196,50 @@ pub static {0}: Atom = Atom(&{0}_ATOM as *const AtomData);"#,
         }
     }
 
+    // Fixed atoms, indexed by id
+    let fixed: Vec<&Symbol> = symbols.iter().filter(|sym| sym.id.is_some()).collect();
+    write!(
+        &mut file,
+        r#"
+
+/// The atoms whose ids are fixed by convention with the compiler's symbol table, indexed by id
+pub static FIXED: [Atom; {}] = [
+"#,
+        fixed.len()
+    )?;
+    for symbol in fixed.iter() {
+        if symbol.key == "False" {
+            file.write_all(b"    Atom(core::ptr::null()),\n")?;
+        } else if symbol.key == "True" {
+            file.write_all(b"    Atom(1usize as *const AtomData),\n")?;
+        } else {
+            writeln!(
+                &mut file,
+                "    Atom(&{}_ATOM as *const AtomData),",
+                &symbol.key
+            )?;
+        }
+    }
+    file.write_all(b"];\n")?;
+
+    // Pre-interned atom data, which excludes the booleans, as they have no data
+    let preinterned: Vec<&Symbol> = symbols
+        .iter()
+        .filter(|sym| sym.key != "False" && sym.key != "True")
+        .collect();
+    write!(
+        &mut file,
+        r#"
+/// The data of the atoms the atom table is initialized with, in order
+pub(in crate::term::atom) static PREINTERNED: [&AtomData; {}] = [
+"#,
+        preinterned.len()
+    )?;
+    for symbol in preinterned.iter() {
+        writeln!(&mut file, "    &{}_ATOM,", &symbol.key)?;
+    }
+    file.write_all(b"];\n")?;
+
     file.sync_data()?;
 
     Ok(())
@@ -0,0 +1,39 @@
+//! Decodes arbitrary bit patterns as terms, with no heap registered.
+//!
+//! Run with `cargo fuzz run opaque_term_decode` from `library/rt`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use firefly_rt::term::{HeapRanges, InvalidTermError, OpaqueTerm, Term};
+
+fuzz_target!(|raw: u64| {
+    let heaps = HeapRanges::new();
+    let term = unsafe { OpaqueTerm::from_raw(raw) };
+
+    match term.try_decode(&heaps) {
+        Ok(decoded) => {
+            assert!(!term.is_box(), "{:#x} decoded as a boxed term", raw);
+            assert!(
+                matches!(
+                    decoded,
+                    Term::None
+                        | Term::Nil
+                        | Term::Bool(_)
+                        | Term::Atom(_)
+                        | Term::Int(_)
+                        | Term::Float(_)
+                ),
+                "{:#x} decoded as a boxed term",
+                raw
+            );
+            let reencoded: OpaqueTerm = decoded.into();
+            assert_eq!(reencoded.raw(), raw, "{:#x} did not round-trip", raw);
+        }
+        Err(InvalidTermError::UnknownAddress) => assert!(term.is_box()),
+        Err(InvalidTermError::InvalidEncoding) => assert!(!term.is_box()),
+        Err(InvalidTermError::UnknownType) => {
+            panic!("{:#x} was dereferenced without being registered", raw)
+        }
+    }
+});
@@ -359,7 +359,7 @@ where
         for arg in args.iter().rev().copied() {
             builder.push(arg.into())?;
         }
-        let arglist: OpaqueTerm = builder.finish().unwrap().into();
+        let arglist: OpaqueTerm = builder.finish().map(Term::Cons).unwrap_or(Term::Nil).into();
         Tuple::from_slice(&[module, function, arglist, locs], alloc)?
     } else {
         let arity: OpaqueTerm = Term::Int(mfa.arity as i64).into();
@@ -369,6 +369,8 @@ where
     Ok(frame.into())
 }
 
+/// Formats the location of a frame as `[{file, "<path>"}, {line, <line>}]`, leaving out the
+/// parts which are unknown, so the location of a frame with no location info is `[]`
 pub fn format_locations<H>(
     filename: Option<&str>,
     line: Option<u32>,
@@ -377,23 +379,19 @@ pub fn format_locations<H>(
 where
     H: Heap,
 {
-    // Each location is a pair of: {file, "<path>"}, {line, <line>}
-    let file_key = atoms::File.into();
-    let line_key = atoms::Line.into();
-    let file = if let Some(f) = filename {
-        let filename = to_trimmed_charlist(f, alloc).unwrap_or(OpaqueTerm::NIL);
-        Tuple::from_slice(&[file_key, filename], alloc)?
-    } else {
-        Tuple::from_slice(&[file_key, OpaqueTerm::NIL], alloc)?
-    };
-    let line = Term::Int(line.unwrap_or_default().try_into().unwrap());
-    let line = Tuple::from_slice(&[line_key, line.into()], alloc)?;
-
     let mut builder = ListBuilder::new(alloc);
-    builder.push(line.into())?;
-    builder.push(file.into())?;
+    if let Some(line) = line {
+        let line = Term::Int(line.into());
+        let line = Tuple::from_slice(&[atoms::Line.into(), line.into()], alloc)?;
+        builder.push(line.into())?;
+    }
+    if let Some(f) = filename {
+        let filename = to_trimmed_charlist(f, alloc).unwrap_or(OpaqueTerm::NIL);
+        let file = Tuple::from_slice(&[atoms::File.into(), filename], alloc)?;
+        builder.push(file.into())?;
+    }
 
-    Ok(builder.finish().unwrap().into())
+    Ok(builder.finish().map(Term::Cons).unwrap_or(Term::Nil))
 }
 
 pub fn to_trimmed_charlist<H, S>(filename: S, alloc: &H) -> Result<OpaqueTerm, AllocError>
@@ -9,7 +9,7 @@ use firefly_alloc::fragment::HeapFragment;
 use firefly_system::cell::ThreadLocalCell;
 
 use crate::function::ModuleFunctionArity;
-use crate::term::{Cons, OpaqueTerm, Term};
+use crate::term::{atoms, Atom, Cons, OpaqueTerm, Term};
 
 use super::{Frame, Symbolication, TraceFrame};
 
@@ -17,15 +17,28 @@ use super::{Frame, Symbolication, TraceFrame};
 /// either by an exception, or explicit request. It does not depend on any
 /// concrete representation of frames, but instead builds on the `Frame` trait
 /// to provide access to details needed to symbolicate and format traces.
+///
+/// Only the Erlang frames of a trace, i.e. those whose symbol is named `module:function/arity`,
+/// are part of its term form, most recent first, and the frames of the BIFs which raise an
+/// exception on behalf of their caller (e.g. `erlang:error/1`) are left out, so the trace begins
+/// with the function which raised the exception, as it does in BEAM.
 pub struct Trace {
     frames: Vec<TraceFrame>,
     fragment: ThreadLocalCell<Option<NonNull<HeapFragment>>>,
     term: ThreadLocalCell<Option<Term>>,
     top: ThreadLocalCell<Option<Term>>,
+    /// The index in `frames` of the frame which `top` stands in for, if any
+    replaced: ThreadLocalCell<Option<usize>>,
 }
 impl Trace {
+    /// The maximum number of Erlang frames in the term form of a trace
     pub const MAX_FRAMES: usize = 10;
 
+    /// The maximum number of native frames captured, of which only the Erlang frames are kept
+    /// when the trace is converted to a term. Runtime frames are interleaved with Erlang frames,
+    /// so this leaves room for a few of them per Erlang frame.
+    const MAX_NATIVE_FRAMES: usize = Self::MAX_FRAMES * 4;
+
     #[inline]
     pub fn new(frames: Vec<TraceFrame>) -> Arc<Self> {
         Arc::new(Self {
@@ -33,13 +46,14 @@ impl Trace {
             fragment: ThreadLocalCell::new(None),
             term: ThreadLocalCell::new(None),
             top: ThreadLocalCell::new(None),
+            replaced: ThreadLocalCell::new(None),
         })
     }
 
     #[cfg(feature = "std")]
     pub fn capture() -> Arc<Self> {
         // Allocates a new trace on the heap
-        let mut trace_arc = Self::new(Vec::with_capacity(Self::MAX_FRAMES));
+        let mut trace_arc = Self::new(Vec::with_capacity(Self::MAX_NATIVE_FRAMES));
         let trace = unsafe { Arc::get_mut_unchecked(&mut trace_arc) };
         //let stackmap = StackMap::get();
 
@@ -65,7 +79,7 @@ impl Trace {
             trace.push_frame(Box::new(frame.clone()));
             //}
 
-            depth < (Self::MAX_FRAMES + 2)
+            depth < (Self::MAX_NATIVE_FRAMES + 2)
         });
 
         trace_arc
@@ -73,7 +87,7 @@ impl Trace {
 
     #[cfg(not(feature = "std"))]
     pub fn capture() -> Arc<Self> {
-        Self::new()
+        Self::new(Vec::new())
     }
 
     /// Used by `erlang:raise/3` when the caller can specify a constrained format of `Term` for
@@ -86,6 +100,7 @@ impl Trace {
             fragment: ThreadLocalCell::new(Some(fragment)),
             term: ThreadLocalCell::new(Some(fragment_term)),
             top: Default::default(),
+            replaced: Default::default(),
         })
     }
 
@@ -97,31 +112,66 @@ impl Trace {
 
     #[inline]
     pub fn iter_symbols(&self) -> SymbolIter<'_> {
-        SymbolIter::new(self.frames.as_slice(), self.top.as_ref().clone())
+        SymbolIter::new(
+            self.frames.as_slice(),
+            self.top.as_ref().clone(),
+            self.replaced.as_ref().clone(),
+        )
     }
 
     /// Sets the top frame of the stacktrace to a specific module/function/arity,
     /// using the provided argument list in place of arity. This is a special case
-    /// added to support `undef` or `badarg` errors, which may display the arguments
-    /// used to call the function which raised the error.
+    /// added to support `undef`, `function_clause` or `badarg` errors, which display
+    /// the arguments used to call the function which raised the error.
     ///
-    /// NOTE: Support for this is optional, we are not required to display the arguments,
-    /// but may do so if available. At a minimum, we do need to support the ability to
-    /// add a frame to the trace for calls which we know will fail (e.g. apply/3 with
-    /// a function that doesn't exist). This still feels like a gross hack though.
+    /// If `mfa` is the most recent Erlang frame of the trace, i.e. the function raised the
+    /// error itself, the new frame replaces it and keeps its location. Otherwise, such as for
+    /// calls to functions which don't exist, it is added as the most recent frame.
     #[inline]
     pub fn set_top_frame(&self, mfa: &ModuleFunctionArity, arguments: &[OpaqueTerm]) {
+        let caller = self
+            .erlang_frames()
+            .next()
+            .filter(|(_, symbol)| symbol.mfa().as_ref() == Some(mfa));
+        self.set_top(mfa, arguments, caller);
+    }
+
+    /// Replaces the arity of the most recent Erlang frame of the trace with the provided
+    /// argument list, as `erlang:error/2` does for the function which called it.
+    ///
+    /// This does nothing if the trace has no Erlang frames.
+    #[inline]
+    pub fn set_top_arguments(&self, arguments: &[OpaqueTerm]) {
+        if let Some(caller) = self.erlang_frames().next() {
+            let mfa = caller.1.mfa().unwrap();
+            self.set_top(&mfa, arguments, Some(caller));
+        }
+    }
+
+    fn set_top(
+        &self,
+        mfa: &ModuleFunctionArity,
+        arguments: &[OpaqueTerm],
+        replaces: Option<(usize, &Symbolication)>,
+    ) {
         assert!(self.top.is_none(), "top of trace was already set");
 
         // Get heap to allocate the frame on
-        let heap_ptr = self.get_or_create_fragment(Some(arguments)).unwrap_or(None);
-        if let Some(mut heap) = heap_ptr {
-            let heap_mut = unsafe { heap.as_mut() };
+        let num_frames = self.erlang_frames().count() + replaces.is_none() as usize;
+        let heap_ptr = self
+            .get_or_create_fragment(num_frames, Some(arguments))
+            .unwrap_or(None);
+        if let Some(heap) = heap_ptr {
+            let heap = unsafe { heap.as_ref() };
+            let (filename, line) = replaces
+                .map(|(_, symbol)| (symbol.filename(), symbol.line()))
+                .unwrap_or_default();
             if let Ok(frame) =
-                super::symbolication::format_mfa(mfa, Some(arguments), None, None, heap_mut)
+                super::symbolication::format_mfa(mfa, Some(arguments), filename, line, heap)
             {
                 unsafe {
                     self.top.set(Some(frame));
+                    self.replaced.set(replaces.map(|(index, _)| index));
                 }
             }
         }
@@ -151,113 +201,104 @@ impl Trace {
     }
 }
 
+/// Iterates over the symbols of a trace, most recent first, beginning with its top frame if set.
+///
+/// Unlike the term form of a trace, this includes native frames.
 pub struct SymbolIter<'a> {
     frames: &'a [TraceFrame],
     top: Option<Term>,
-    pos: Option<usize>,
+    replaced: Option<usize>,
+    /// The positions not yet visited from either end, where position 0 is `top`, and the
+    /// position of each frame is one more than its index
+    front: usize,
+    back: usize,
 }
 impl<'a> SymbolIter<'a> {
-    fn new(frames: &'a [TraceFrame], top: Option<Term>) -> Self {
+    fn new(frames: &'a [TraceFrame], top: Option<Term>, replaced: Option<usize>) -> Self {
         Self {
             frames,
             top,
-            pos: None,
+            replaced,
+            front: 0,
+            back: frames.len() + 1,
         }
     }
-}
 
-impl Iterator for SymbolIter<'_> {
-    type Item = Symbolication;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.pos.is_none() {
-            self.pos = Some(0);
-        }
-
-        let mut pos = self.pos.unwrap();
+    fn symbol_at(&self, pos: usize) -> Option<Symbolication> {
         if pos == 0 {
-            if let Some(top) = self.top {
-                if let Ok(symbol) = top.try_into() {
-                    self.pos = Some(1);
-                    return Some(symbol);
-                }
-            }
-
-            // Skip the top
-            self.pos = Some(1);
+            return self.top.and_then(|top| top.try_into().ok());
         }
 
-        loop {
-            if pos >= self.frames.len() {
-                self.pos = Some(pos);
-                return None;
-            }
+        let index = pos - 1;
+        if self.replaced == Some(index) {
+            return None;
+        }
+        self.frames[index].symbolicate().cloned()
+    }
+}
 
-            let frame = &self.frames[pos];
-            pos += 1;
+impl Iterator for SymbolIter<'_> {
+    type Item = Symbolication;
 
-            if let Some(symbol) = frame.symbolicate() {
-                self.pos = Some(pos);
-                return Some(symbol.clone());
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.front < self.back {
+            let pos = self.front;
+            self.front += 1;
+            if let Some(symbol) = self.symbol_at(pos) {
+                return Some(symbol);
             }
         }
+        None
     }
 }
 impl FusedIterator for SymbolIter<'_> {}
 impl DoubleEndedIterator for SymbolIter<'_> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        if self.pos.is_none() {
-            self.pos = Some(self.frames.len());
-        }
-
-        let mut pos = self.pos.unwrap();
-
-        if pos == 0 {
-            return None;
-        }
-
-        loop {
-            if pos == 1 {
-                if let Some(top) = self.top {
-                    if let Ok(symbol) = top.try_into() {
-                        self.pos = Some(0);
-                        return Some(symbol);
-                    }
-                }
-
-                // Skip the top
-                self.pos = Some(0);
-                return None;
-            }
-
-            pos -= 1;
-            let frame = &self.frames[pos];
-            if let Some(symbol) = frame.symbolicate() {
-                self.pos = Some(pos);
-                return Some(symbol.clone());
+        while self.front < self.back {
+            self.back -= 1;
+            if let Some(symbol) = self.symbol_at(self.back) {
+                return Some(symbol);
             }
         }
+        None
     }
 }
 
 // Internals for term construction/allocation
 impl Trace {
+    /// Returns the Erlang frames which are part of the term form of this trace, along with
+    /// their index in `frames`, most recent first.
+    fn erlang_frames(&self) -> impl Iterator<Item = (usize, &Symbolication)> + '_ {
+        self.frames
+            .iter()
+            .enumerate()
+            // This implicitly ignores native frames, as symbol.mfa() returns None for those
+            .filter_map(|(index, frame)| {
+                frame
+                    .symbolicate()
+                    .filter(|symbol| symbol.mfa().is_some())
+                    .map(|symbol| (index, symbol))
+            })
+            .skip_while(|(_, symbol)| is_raise_bif(&symbol.mfa().unwrap()))
+            .take(Self::MAX_FRAMES)
+    }
+
     /// Retrieves the heap fragment allocated for this trace, or creates it,
     /// returning a mutable reference to that heap.
     ///
-    /// The allocated size of the fragment is sufficient to hold all of the frames
+    /// The allocated size of the fragment is sufficient to hold `num_frames` frames
     /// of the trace in Erlang Term form. The `extra` parameter is used to indicate
     /// that some amount of extra bytes is requested to fulfill auxillary requests,
     /// such as for `top`.
     fn get_or_create_fragment(
         &self,
+        num_frames: usize,
         extra: Option<&[OpaqueTerm]>,
     ) -> Result<Option<NonNull<HeapFragment>>, AllocError> {
         if let Some(fragment) = self.fragment.as_ref() {
             Ok(Some(fragment.clone()))
         } else {
-            if let Some(layout) =
-                super::symbolication::calculate_fragment_layout(self.frames.len(), extra)
+            if let Some(layout) = super::symbolication::calculate_fragment_layout(num_frames, extra)
             {
                 let heap_ptr = HeapFragment::new(layout, None)?;
                 unsafe {
@@ -277,20 +318,20 @@ impl Trace {
     fn construct(&self) -> Result<Term, AllocError> {
         assert!(self.term.is_none());
 
+        // If top was set without replacing a frame, we have an extra frame to append
+        let replaced = *self.replaced.as_ref();
+        let added = self.top.is_some() && replaced.is_none();
+        let num_frames = self.erlang_frames().count() + added as usize;
+
         // Either create a heap fragment for the terms, or use the one created already
-        let heap_ptr = self.get_or_create_fragment(None)?;
+        let heap_ptr = self.get_or_create_fragment(num_frames, None)?;
         if heap_ptr.is_none() {
             return Ok(Term::Nil);
         }
         let heap_ptr = heap_ptr.unwrap();
         let heap = unsafe { heap_ptr.as_ref() };
 
-        // If top was set, we have an extra frame to append
-        let mut erlang_frames = if self.top.is_some() {
-            Vec::with_capacity(1 + self.frames.len())
-        } else {
-            Vec::with_capacity(self.frames.len())
-        };
+        let mut erlang_frames = Vec::with_capacity(num_frames);
 
         // If top was set, add it as the most recent frame on the stack
         if let Some(top) = self.top.as_ref() {
@@ -298,20 +339,18 @@ impl Trace {
         }
 
         // Add all of the "real" stack frames
-        for frame in &self.frames[..] {
-            if let Some(symbol) = frame.symbolicate() {
-                // This implicitly ignores native frames, as symbol.mfa() returns None for those
-                if let Some(ref mfa) = symbol.mfa() {
-                    let erlang_frame = super::symbolication::format_mfa(
-                        mfa,
-                        None,
-                        symbol.filename(),
-                        symbol.line(),
-                        heap,
-                    )?;
-                    erlang_frames.push(erlang_frame);
-                }
+        for (index, symbol) in self.erlang_frames() {
+            if replaced == Some(index) {
+                continue;
             }
+            let erlang_frame = super::symbolication::format_mfa(
+                &symbol.mfa().unwrap(),
+                None,
+                symbol.filename(),
+                symbol.line(),
+                heap,
+            )?;
+            erlang_frames.push(erlang_frame);
         }
 
         // Then construct the stacktrace term from the frames we just built up
@@ -325,3 +364,175 @@ impl Trace {
         Ok(list)
     }
 }
+
+/// Returns true if `mfa` is one of the BIFs which raise an exception on behalf of their caller,
+/// and so are not part of the trace of the exception they raise.
+fn is_raise_bif(mfa: &ModuleFunctionArity) -> bool {
+    let raise_bifs: [(Atom, u8); 9] = [
+        (atoms::Error, 1),
+        (atoms::Error, 2),
+        (atoms::Error, 3),
+        (atoms::Exit, 1),
+        (atoms::Throw, 1),
+        (atoms::NifError, 1),
+        (atoms::NifError, 2),
+        (atoms::Raise, 2),
+        (atoms::Raise, 3),
+    ];
+
+    mfa.module == atoms::Erlang && raise_bifs.contains(&(mfa.function, mfa.arity))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hint::black_box;
+
+    use super::*;
+
+    #[export_name = "trace_test:outer/0"]
+    #[inline(never)]
+    fn outer() -> Arc<Trace> {
+        black_box(middle())
+    }
+
+    #[export_name = "trace_test:middle/1"]
+    #[inline(never)]
+    fn middle() -> Arc<Trace> {
+        black_box(inner())
+    }
+
+    #[export_name = "trace_test:inner/2"]
+    #[inline(never)]
+    fn inner() -> Arc<Trace> {
+        black_box(Trace::capture())
+    }
+
+    fn mfa(function: &str, arity: usize) -> ModuleFunctionArity {
+        let module = Atom::try_from("trace_test").unwrap();
+        let function = Atom::try_from(function).unwrap();
+        ModuleFunctionArity::new(module, function, arity)
+    }
+
+    /// Returns the frames of the term form of `trace`
+    fn frames(trace: &Trace) -> Vec<Term> {
+        match trace.as_term().unwrap() {
+            Term::Nil => vec![],
+            Term::Cons(ptr) => unsafe { ptr.as_ref() }
+                .iter()
+                .map(|frame| frame.unwrap())
+                .collect(),
+            other => panic!("expected a list of frames, but got {:?}", other),
+        }
+    }
+
+    fn elements(frame: Term) -> Vec<Term> {
+        let Term::Tuple(ptr) = frame else { panic!("expected a tuple, but got {:?}", frame); };
+        unsafe { ptr.as_ref() }
+            .as_slice()
+            .iter()
+            .copied()
+            .map(Term::from)
+            .collect()
+    }
+
+    fn symbolicate(frame: Term) -> Symbolication {
+        frame.try_into().unwrap()
+    }
+
+    #[test]
+    fn erlang_frames_are_most_recent_first_with_their_locations() {
+        let trace = outer();
+        let frames = frames(&trace);
+
+        assert!(frames.len() >= 3);
+        let symbols: Vec<Symbolication> = frames.iter().copied().map(symbolicate).collect();
+        assert_eq!(symbols[0].mfa(), Some(mfa("inner", 2)));
+        assert_eq!(symbols[1].mfa(), Some(mfa("middle", 1)));
+        assert_eq!(symbols[2].mfa(), Some(mfa("outer", 0)));
+        for symbol in &symbols[..3] {
+            assert!(symbol.filename().unwrap().ends_with("trace.rs"));
+            assert!(symbol.line().unwrap() > 0);
+        }
+
+        // The arity of frames without arguments is an integer
+        assert_eq!(elements(frames[0])[2], Term::Int(2));
+    }
+
+    #[test]
+    fn native_frames_are_not_part_of_the_term() {
+        let trace = outer();
+
+        assert!(trace.frames().len() > 3);
+        for frame in frames(&trace) {
+            assert!(symbolicate(frame).mfa().is_some());
+        }
+    }
+
+    #[test]
+    fn top_frame_for_a_function_not_in_the_trace_is_added() {
+        let trace = outer();
+        let undefined = mfa("undefined", 1);
+        trace.set_top_frame(&undefined, &[Term::Int(42).into()]);
+
+        let frames = frames(&trace);
+        let top = elements(frames[0]);
+        assert_eq!(top[1], Term::Atom(undefined.function));
+        assert_eq!(
+            top[2]
+                .as_cons()
+                .unwrap()
+                .iter()
+                .map(|arg| arg.unwrap())
+                .collect::<Vec<_>>(),
+            vec![Term::Int(42)]
+        );
+        // Nothing is known about the location of a function which isn't in the trace
+        assert_eq!(top[3], Term::Nil);
+        assert_eq!(symbolicate(frames[1]).mfa(), Some(mfa("inner", 2)));
+    }
+
+    #[test]
+    fn top_frame_for_the_most_recent_function_replaces_it() {
+        let trace = outer();
+        trace.set_top_frame(&mfa("inner", 2), &[Term::Int(1).into(), Term::Nil.into()]);
+
+        let frames = frames(&trace);
+        let top = symbolicate(frames[0]);
+        assert_eq!(top.mfa(), Some(mfa("inner", 2)));
+        assert!(top.filename().unwrap().ends_with("trace.rs"));
+        assert!(matches!(elements(frames[0])[2], Term::Cons(_)));
+        assert_eq!(symbolicate(frames[1]).mfa(), Some(mfa("middle", 1)));
+        assert_eq!(symbolicate(frames[2]).mfa(), Some(mfa("outer", 0)));
+    }
+
+    #[test]
+    fn top_arguments_replace_the_arity_of_the_most_recent_function() {
+        let trace = outer();
+        trace.set_top_arguments(&[]);
+
+        let frames = frames(&trace);
+        assert_eq!(symbolicate(frames[0]).mfa(), Some(mfa("inner", 0)));
+        assert_eq!(elements(frames[0])[2], Term::Nil);
+        assert_eq!(symbolicate(frames[1]).mfa(), Some(mfa("middle", 1)));
+
+        // The replaced frame is skipped when iterating symbols in either direction
+        let forward: Vec<_> = trace.iter_symbols().filter_map(|s| s.mfa()).collect();
+        let mut backward: Vec<_> = trace.iter_symbols().rev().filter_map(|s| s.mfa()).collect();
+        backward.reverse();
+        assert_eq!(
+            &forward[..3],
+            &[mfa("inner", 0), mfa("middle", 1), mfa("outer", 0)]
+        );
+        assert_eq!(forward, backward);
+    }
+
+    #[test]
+    fn term_is_constructed_once() {
+        let trace = outer();
+
+        assert_eq!(
+            OpaqueTerm::from(trace.as_term().unwrap()).raw(),
+            OpaqueTerm::from(trace.as_term().unwrap()).raw()
+        );
+    }
+}
@@ -98,3 +98,267 @@ impl<T: ExactEq, const N: usize> ExactEq for [T; N] {
         self[..].exact_eq(&other[..])
     }
 }
+
+#[cfg(test)]
+mod test {
+    use alloc::alloc::Layout;
+    use core::ptr::{self, NonNull};
+    use std::time::Instant;
+
+    use firefly_alloc::fragment::HeapFragment;
+
+    use super::*;
+    use crate::term::{Atom, Cons, ListBuilder, OpaqueTerm, Term, Tuple};
+
+    /// A heap fragment which is freed when dropped
+    struct Heap(NonNull<HeapFragment>);
+    impl Heap {
+        fn new(size: usize) -> Self {
+            let layout = Layout::from_size_align(size, 16).unwrap();
+            Self(HeapFragment::new(layout, None).unwrap())
+        }
+
+        fn fragment(&self) -> &HeapFragment {
+            unsafe { self.0.as_ref() }
+        }
+
+        fn list(&self, elements: &[Term], tail: Term) -> Term {
+            let mut builder = ListBuilder::new(self.fragment());
+            for element in elements.iter().rev() {
+                builder.push(*element).unwrap();
+            }
+            match builder.finish() {
+                None => tail,
+                Some(cons) => {
+                    // The builder always terminates the list with nil, so find the last cell
+                    let mut last = cons;
+                    while let Term::Cons(next) = unsafe { last.as_ref() }.tail() {
+                        last = next;
+                    }
+                    unsafe {
+                        last.as_mut().tail = tail.into();
+                    }
+                    Term::Cons(cons)
+                }
+            }
+        }
+
+        fn tuple(&self, elements: &[Term]) -> Term {
+            let elements: Vec<OpaqueTerm> = elements.iter().map(|e| (*e).into()).collect();
+            Term::Tuple(Tuple::from_slice(&elements, self.fragment()).unwrap())
+        }
+    }
+    impl Drop for Heap {
+        fn drop(&mut self) {
+            unsafe { ptr::drop_in_place(self.0.as_ptr()) }
+        }
+    }
+
+    /// A xorshift generator, so that failures can be reproduced from the seed
+    struct Rng(u64);
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    /// Draws terms from a small alphabet, so that independently generated terms are sometimes
+    /// equal, and equal numbers of different types (e.g. `0` and `0.0`) show up
+    fn arbitrary(rng: &mut Rng, heap: &Heap, depth: usize) -> Term {
+        let kinds = if depth == 0 { 4 } else { 6 };
+        match rng.below(kinds) {
+            0 => Term::Int(rng.below(2) as i64),
+            1 => Term::Float((rng.below(2) as f64).into()),
+            2 => Term::Atom(Atom::try_from(["a", "b"][rng.below(2) as usize]).unwrap()),
+            3 => Term::Nil,
+            4 => {
+                let elements: Vec<Term> = (0..rng.below(3))
+                    .map(|_| arbitrary(rng, heap, depth - 1))
+                    .collect();
+                let tail = if rng.below(4) == 0 {
+                    arbitrary(rng, heap, 0)
+                } else {
+                    Term::Nil
+                };
+                heap.list(&elements, tail)
+            }
+            _ => {
+                let elements: Vec<Term> = (0..rng.below(3))
+                    .map(|_| arbitrary(rng, heap, depth - 1))
+                    .collect();
+                heap.tuple(&elements)
+            }
+        }
+    }
+
+    /// Compares the terms `arbitrary` generates by walking them recursively, without any of the
+    /// shortcuts taken by `Term::exact_eq`
+    fn naive_exact_eq(lhs: Term, rhs: Term) -> bool {
+        match (lhs, rhs) {
+            (Term::Nil, Term::Nil) => true,
+            (Term::Int(x), Term::Int(y)) => x == y,
+            (Term::Float(x), Term::Float(y)) => x.inner() == y.inner(),
+            (Term::Atom(x), Term::Atom(y)) => x.as_str() == y.as_str(),
+            (Term::Cons(x), Term::Cons(y)) => {
+                let (x, y) = unsafe { (x.as_ref(), y.as_ref()) };
+                naive_exact_eq(x.head(), y.head()) && naive_exact_eq(x.tail(), y.tail())
+            }
+            (Term::Tuple(x), Term::Tuple(y)) => {
+                let (x, y) = unsafe { (x.as_ref(), y.as_ref()) };
+                x.len() == y.len()
+                    && x.as_slice()
+                        .iter()
+                        .zip(y.as_slice().iter())
+                        .all(|(x, y)| naive_exact_eq((*x).into(), (*y).into()))
+            }
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn exact_eq_agrees_with_naive_comparison() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..10_000 {
+            let heap = Heap::new(16 * 1024);
+            let seed = rng.below(u64::MAX) | 1;
+            let lhs = arbitrary(&mut Rng(seed), &heap, 3);
+            let rhs = match rng.below(4) {
+                // The same term, so only the shortcuts are taken
+                0 => lhs,
+                // An equal copy
+                1 => arbitrary(&mut Rng(seed), &heap, 3),
+                // Equal elements around the same, or an equal, term
+                2 => {
+                    let shared = if rng.below(2) == 0 {
+                        lhs
+                    } else {
+                        arbitrary(&mut Rng(seed), &heap, 3)
+                    };
+                    let lhs = heap.tuple(&[Term::Int(1), lhs]);
+                    let rhs = heap.tuple(&[Term::Int(1), shared]);
+                    assert_eq!(lhs.exact_eq(&rhs), naive_exact_eq(lhs, rhs));
+                    continue;
+                }
+                // Usually a different term
+                _ => arbitrary(&mut Rng(rng.below(u64::MAX) | 1), &heap, 3),
+            };
+
+            assert_eq!(
+                lhs.exact_eq(&rhs),
+                naive_exact_eq(lhs, rhs),
+                "{} =:= {}",
+                lhs,
+                rhs
+            );
+            assert_eq!(
+                rhs.exact_eq(&lhs),
+                naive_exact_eq(rhs, lhs),
+                "{} =:= {}",
+                rhs,
+                lhs
+            );
+        }
+    }
+
+    #[test]
+    fn exact_eq_distinguishes_integers_from_equal_floats_inside_containers() {
+        let heap = Heap::new(1024);
+        let ints = heap.list(&[Term::Int(1), heap.tuple(&[Term::Int(2)])], Term::Nil);
+        let floats = heap.list(
+            &[Term::Int(1), heap.tuple(&[Term::Float(2.0.into())])],
+            Term::Nil,
+        );
+
+        assert!(ints.exact_eq(&ints));
+        assert!(!ints.exact_eq(&floats));
+        assert!(ints == floats);
+    }
+
+    #[test]
+    fn exact_eq_of_tuples_with_different_arity_is_false() {
+        let heap = Heap::new(1024);
+        let lhs = heap.tuple(&[Term::Int(1), Term::Int(2)]);
+        let rhs = heap.tuple(&[Term::Int(1), Term::Int(2), Term::Int(3)]);
+
+        assert!(!lhs.exact_eq(&rhs));
+        assert!(!rhs.exact_eq(&lhs));
+    }
+
+    #[test]
+    fn exact_eq_of_long_lists_does_not_overflow_the_stack() {
+        let len = 1_000_000;
+        let (_heap, lhs, rhs) = long_lists(len, None);
+
+        assert!(lhs.exact_eq(&rhs));
+
+        let (_heap, lhs, rhs) = long_lists(len, Some(len - 1));
+
+        assert!(!lhs.exact_eq(&rhs));
+    }
+
+    #[test]
+    fn cons_and_tuple_exact_eq_agree_with_term_exact_eq() {
+        let heap = Heap::new(1024);
+        let lhs = heap.list(&[Term::Int(1), Term::Int(2)], Term::Nil);
+        let rhs = heap.list(&[Term::Int(1), Term::Float(2.0.into())], Term::Nil);
+        let (Term::Cons(lhs_cons), Term::Cons(rhs_cons)) = (lhs, rhs) else { unreachable!() };
+
+        unsafe {
+            assert!(lhs_cons.as_ref().exact_eq(lhs_cons.as_ref()));
+            assert!(!lhs_cons.as_ref().exact_eq(rhs_cons.as_ref()));
+        }
+
+        let lhs = heap.tuple(&[lhs]);
+        let rhs = heap.tuple(&[rhs]);
+        let (Term::Tuple(lhs_tuple), Term::Tuple(rhs_tuple)) = (lhs, rhs) else { unreachable!() };
+
+        unsafe {
+            assert!(lhs_tuple.as_ref().exact_eq(lhs_tuple.as_ref()));
+            assert!(!lhs_tuple.as_ref().exact_eq(rhs_tuple.as_ref()));
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_exact_eq_of_long_lists() {
+        let len = 100_000;
+        let iterations = 100;
+
+        for (name, differs_at) in [
+            ("differing at the first element", Some(0)),
+            ("differing at the middle element", Some(len / 2)),
+            ("identical", None),
+        ] {
+            let (_heap, lhs, rhs) = long_lists(len, differs_at);
+            let start = Instant::now();
+            for _ in 0..iterations {
+                assert_eq!(lhs.exact_eq(&rhs), differs_at.is_none());
+            }
+            std::println!(
+                "{} element lists {}: {:?} per comparison",
+                len,
+                name,
+                start.elapsed() / iterations
+            );
+        }
+    }
+
+    /// Builds two separately allocated lists of the integers `0..len`, where the element at
+    /// `differs_at`, if any, is negated in the second list
+    fn long_lists(len: usize, differs_at: Option<usize>) -> (Heap, Term, Term) {
+        let heap = Heap::new(2 * len * core::mem::size_of::<Cons>() + 1024);
+        let lhs: Vec<Term> = (0..len as i64).map(Term::Int).collect();
+        let mut rhs = lhs.clone();
+        if let Some(index) = differs_at {
+            rhs[index] = Term::Int(-(index as i64) - 1);
+        }
+        let lhs = heap.list(&lhs, Term::Nil);
+        let rhs = heap.list(&rhs, Term::Nil);
+
+        (heap, lhs, rhs)
+    }
+}
@@ -0,0 +1,256 @@
+    .section .text.__firefly_dynamic_apply,"ax",@progbits
+    .globl __firefly_dynamic_apply
+    .p2align 2
+    .type __firefly_dynamic_apply,@function
+    # The call site table and jump table below are offsets between labels in this function,
+    # which must be fixed at assembly time, so the linker may not relax any of it
+    .option push
+    .option norelax
+__firefly_dynamic_apply:
+.L_dyn_call_begin:
+    .cfi_startproc
+    .cfi_personality 155, DW.ref.rust_eh_personality
+    .cfi_lsda 27, .L_dyn_call_lsda
+    # At this point, the following registers are bound:
+    #
+    #   s0  <- frame pointer (optional)
+    #   ra  <- return address
+    #   a0  <- callee
+    #   a1  <- argv
+    #   a2  <- argc
+    #
+    # Save the parent frame pointer for when control returns to this call frame.
+    # CFA directives will inform the unwinder to expect s0 at the bottom of the
+    # stack for this frame, so this should be the last value on the stack in the caller
+    addi sp, sp, -16
+    sd   s0, 0(sp)
+    sd   ra, 8(sp)
+    # Set the frame pointer to the current stack pointer, we use this later to restore
+    # the original frame/stack pointers.
+    mv   s0, sp
+    # Define the call frame address relative to the frame pointer
+    .cfi_def_cfa s0, 16
+    .cfi_offset ra, -8
+    .cfi_offset s0, -16
+
+    # Save our callee and argv pointers, and argc, to scratch registers
+    mv   t0, a0
+    mv   t1, a1
+    mv   t2, a2
+
+    # Determine if spills are needed
+    # In the common case in which they are not, we perform a tail call
+    li   t3, 8
+    bgtu a2, t3, .L_dyn_call_spill
+
+.L_dyn_call_no_spill:
+    # We only reach this block if we had no arguments to spill, so
+    # we are not certain about which registers we need to assign. We
+    # simply check for each register whether this a corresponding argument,
+    # and if so, we assign it.
+
+    # Load the offset from the jump table to the block which handles the
+    # specific number of registers we have arguments for, then jump to that block
+    lla  t3, .L_dyn_call_jt
+    slli t4, a2, 2
+    add  t4, t3, t4
+    lw   t4, 0(t4)
+    add  t4, t3, t4
+    jr   t4
+
+    # All of these basic blocks perform a tail call. As such,
+    # the unwinder will skip over this frame should the callee
+    # throw an exception
+.L_dyn_call_regs0:
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    jr   t0
+
+.L_dyn_call_regs1:
+    ld   a0, 0(t1)
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    jr   t0
+
+.L_dyn_call_regs2:
+    ld   a0, 0(t1)
+    ld   a1, 8(t1)
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    jr   t0
+
+.L_dyn_call_regs3:
+    ld   a0, 0(t1)
+    ld   a1, 8(t1)
+    ld   a2, 16(t1)
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    jr   t0
+
+.L_dyn_call_regs4:
+    ld   a0, 0(t1)
+    ld   a1, 8(t1)
+    ld   a2, 16(t1)
+    ld   a3, 24(t1)
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    jr   t0
+
+.L_dyn_call_regs5:
+    ld   a0, 0(t1)
+    ld   a1, 8(t1)
+    ld   a2, 16(t1)
+    ld   a3, 24(t1)
+    ld   a4, 32(t1)
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    jr   t0
+
+.L_dyn_call_regs6:
+    ld   a0, 0(t1)
+    ld   a1, 8(t1)
+    ld   a2, 16(t1)
+    ld   a3, 24(t1)
+    ld   a4, 32(t1)
+    ld   a5, 40(t1)
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    jr   t0
+
+.L_dyn_call_regs7:
+    ld   a0, 0(t1)
+    ld   a1, 8(t1)
+    ld   a2, 16(t1)
+    ld   a3, 24(t1)
+    ld   a4, 32(t1)
+    ld   a5, 40(t1)
+    ld   a6, 48(t1)
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    jr   t0
+
+.L_dyn_call_regs8:
+    ld   a0, 0(t1)
+    ld   a1, 8(t1)
+    ld   a2, 16(t1)
+    ld   a3, 24(t1)
+    ld   a4, 32(t1)
+    ld   a5, 40(t1)
+    ld   a6, 48(t1)
+    ld   a7, 56(t1)
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    jr   t0
+
+.L_dyn_call_spill:
+    # If we hit this block, we have identified that there are
+    # arguments to spill. We perform some setup for the actual spilling
+    addi t3, t2, -8          # the number of arguments to spill, to be the loop bound
+
+    # Calculate spill space (i.e. new bottom of stack), ensure it is 16-byte aligned, and allocate it
+    #
+    # sp = sp - (t3 * 8), rounded down to 16 bytes
+    slli t4, t3, 3
+    sub  t4, sp, t4
+    andi sp, t4, -16
+
+    # Source pointer, starting at the first spilled element of argv
+    addi t4, t1, 64
+    # Destination pointer, starting at the bottom of the spill region
+    mv   t5, sp
+
+.L_dyn_call_spill_loop:
+    # if t3 == 0, we're done
+    beqz t3, .L_dyn_call_spill_loop_end
+    # Copy the argument through t6
+    ld   t6, 0(t4)
+    sd   t6, 0(t5)
+    addi t4, t4, 8
+    addi t5, t5, 8
+    # Decrement the loop bound
+    addi t3, t3, -1
+    # Next iteration
+    j    .L_dyn_call_spill_loop
+.L_dyn_call_spill_loop_end:
+    # We've spilled arguments, so we have at least 8 args
+    ld   a0, 0(t1)
+    ld   a1, 8(t1)
+    ld   a2, 16(t1)
+    ld   a3, 24(t1)
+    ld   a4, 32(t1)
+    ld   a5, 40(t1)
+    ld   a6, 48(t1)
+    ld   a7, 56(t1)
+
+.L_dyn_call_exec:
+    # If we spill arguments to the stack, we can't perform
+    # a tail call, so we do a normal call/ret sequence here
+    #
+    # At this point, the stack should be 16-byte aligned,
+    # all of the callee arguments should be in registers or
+    # spilled to the stack. All that remains is to actually
+    # execute the call!
+    jalr t0
+
+.L_dyn_call_ret:
+    # Non-tail call completed successfully
+    # Our frame pointer should have the same address as our original stack pointer
+    # so we restore it, and then reload the original frame pointer/return address
+    mv   sp, s0
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+    ret
+
+.L_dyn_call_end:
+    .size __firefly_dynamic_apply, .L_dyn_call_end-__firefly_dynamic_apply
+    .cfi_endproc
+
+    # The following is the jump table for setting up calls with
+    # a variable number of register-based arguments
+    .p2align 2
+.L_dyn_call_jt:
+    .word .L_dyn_call_regs0-.L_dyn_call_jt
+    .word .L_dyn_call_regs1-.L_dyn_call_jt
+    .word .L_dyn_call_regs2-.L_dyn_call_jt
+    .word .L_dyn_call_regs3-.L_dyn_call_jt
+    .word .L_dyn_call_regs4-.L_dyn_call_jt
+    .word .L_dyn_call_regs5-.L_dyn_call_jt
+    .word .L_dyn_call_regs6-.L_dyn_call_jt
+    .word .L_dyn_call_regs7-.L_dyn_call_jt
+    .word .L_dyn_call_regs8-.L_dyn_call_jt
+    .option pop
+
+    # The following is the LSDA metadata for exception handling
+    .section .gcc_except_table,"a",@progbits
+    .p2align 2
+.L_dyn_call_lsda:
+    # Landing pad encoding (DW_EH_PE_omit) = omit
+    .byte 255
+    # DWARF encoding of type entries in types table = no type entries
+    .byte 255
+.L_dyn_call_cst_header:
+    # Call site encoding = uleb128
+    .byte 1
+    # Size of call site table
+    .uleb128 .L_dyn_call_cst_end-.L_dyn_call_cst_begin
+.L_dyn_call_cst_begin:
+    # Call site entry for the dynamic callee (offset, size, pad, action)
+    #  call occurs between .L_dyn_call_exec and .L_dyn_call_ret
+    .uleb128 .L_dyn_call_exec-.L_dyn_call_begin
+    .uleb128 .L_dyn_call_ret-.L_dyn_call_exec
+    #  no landing pad, the unwinder will skip over us
+    .byte 0
+    #  offset into action table (0 is no action)
+    .byte 0
+.L_dyn_call_cst_end:
+    .p2align 2
@@ -25,9 +25,10 @@ L_dyn_call_begin:
     mov  r11, rsi
     mov  rax, rdx
 
-    # Determine if spills are needed
+    # Determine if spills are needed, i.e. if there are more than the 6 arguments
+    # passed in registers, which is also the last entry of the jump table below
     # In the common case in which they are not, we perform a tail call
-    cmp  rdx, 7
+    cmp  rdx, 6
     ja L_dyn_call_spill
 
 L_dyn_call_no_spill:
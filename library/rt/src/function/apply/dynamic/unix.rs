@@ -3,7 +3,8 @@
 ///! System-V ABI calling convention, or other equivalent platform-standard
 ///! convention, depending on target.
 ///!
-///! Currently, we have written the shim for x86_64 Linux and macOS.
+///! Currently, we have written the shim for x86_64 and aarch64 on Linux and macOS, and for
+///! riscv64 on Linux.
 ///!
 ///! See the assembly files in `dynamic_apply/*.s` for details on their
 ///! implementation.
@@ -29,6 +30,10 @@ cfg_if! {
         global_asm!(include_str!("asm/dynamic_apply_macos_aarch64.s"));
     } else if #[cfg(target_arch = "x86_64")] {
         global_asm!(include_str!("asm/dynamic_apply_linux.s"));
+    } else if #[cfg(all(target_os = "linux", target_arch = "aarch64"))] {
+        global_asm!(include_str!("asm/dynamic_apply_linux_aarch64.s"));
+    } else if #[cfg(all(target_os = "linux", target_arch = "riscv64"))] {
+        global_asm!(include_str!("asm/dynamic_apply_linux_riscv64.s"));
     } else {
         compile_error!("dynamic calls have not been implemented for this platform!");
     }
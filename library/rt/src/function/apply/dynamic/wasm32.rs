@@ -1,44 +1,28 @@
+///! WebAssembly has no way to construct a call with a variable number of arguments, as calls are
+///! checked against the signature of the callee, and the stack can't be manipulated directly.
+///!
+///! Instead, this module dispatches on the number of arguments to a trampoline which transmutes the
+///! callee to the function type of that arity, so there is one trampoline for every arity up to
+///! `MAX_ARITY`.
 use core::mem;
 
-use crate::term::{ErlangResult, Term};
+use seq_macro::seq;
 
-use super::DynamicCallee;
+use crate::function::{ErlangResult, MAX_ARITY};
+use crate::term::OpaqueTerm;
 
-type DynamicCallee1 = extern "C-unwind" fn(Term) -> ErlangResult;
-type DynamicCallee2 = extern "C-unwind" fn(Term, Term) -> ErlangResult;
-type DynamicCallee3 = extern "C-unwind" fn(Term, Term, Term) -> ErlangResult;
-type DynamicCallee4 = extern "C-unwind" fn(Term, Term, Term, Term) -> ErlangResult;
-type DynamicCallee5 = extern "C-unwind" fn(Term, Term, Term, Term, Term) -> ErlangResult;
+use super::DynamicCallee;
 
-pub unsafe fn apply(f: DynamicCallee, argv: *const Term, argc: usize) -> ErlangResult {
-    match argc {
-        0 => f(),
-        1 => {
-            let arity1 = mem::transmute::<_, DynamicCallee1>(f);
-            arity1(*argv)
-        }
-        2 => {
-            let arity2 = mem::transmute::<_, DynamicCallee2>(f);
-            arity2(*argv, *argv.offset(1))
-        }
-        3 => {
-            let arity3 = mem::transmute::<_, DynamicCallee3>(f);
-            arity3(*argv, *argv.offset(1), *argv.offset(2))
-        }
-        4 => {
-            let arity4 = mem::transmute::<_, DynamicCallee4>(f);
-            arity4(*argv, *argv.offset(1), *argv.offset(2), *argv.offset(3))
-        }
-        5 => {
-            let arity5 = mem::transmute::<_, DynamicCallee5>(f);
-            arity5(
-                *argv,
-                *argv.offset(1),
-                *argv.offset(2),
-                *argv.offset(3),
-                *argv.offset(4),
-            )
+pub unsafe fn apply(f: DynamicCallee, argv: *const OpaqueTerm, argc: usize) -> ErlangResult {
+    seq!(A in 0..256 {
+        match argc {
+            #(
+                A => seq!(N in 0..A {
+                    let callee = mem::transmute::<DynamicCallee, extern "C-unwind" fn(#(OpaqueTerm,)*) -> ErlangResult>(f);
+                    callee(#(*argv.add(N),)*)
+                }),
+            )*
+            _ => panic!("applying arity {} functions, but functions may have at most {} arguments", argc, MAX_ARITY),
         }
-        _ => unimplemented!("applying arity {} native functions", argc),
-    }
+    })
 }
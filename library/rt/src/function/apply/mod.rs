@@ -2,8 +2,11 @@ mod dynamic;
 
 pub use self::dynamic::DynamicCallee;
 
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::alloc::Layout;
 use core::mem;
+use core::ptr::NonNull;
 use core::slice;
 
 use hashbrown::{HashMap, HashSet};
@@ -12,7 +15,9 @@ use lazy_static::lazy_static;
 use firefly_arena::DroplessArena;
 use firefly_system::sync::RwLock;
 
-use crate::term::{Atom, OpaqueTerm};
+use crate::backtrace::Trace;
+use crate::error::ErlangException;
+use crate::term::{atoms, Atom, Closure, ExternalFun, OpaqueTerm};
 
 use super::{ErlangResult, FunctionSymbol, ModuleFunctionArity};
 
@@ -32,10 +37,14 @@ lazy_static! {
 ///   - Returns an immediate-sized term as a result
 ///
 /// This function returns `Err` if the called function returns the NONE value,
-/// or if the given symbol doesn't exist.
+/// if the given symbol doesn't exist, or if the number of arguments given does
+/// not match the arity of the symbol.
 ///
 /// This function will panic if the symbol table has not been initialized.
 pub fn apply(symbol: &ModuleFunctionArity, args: &[OpaqueTerm]) -> Result<ErlangResult, ()> {
+    if symbol.arity as usize != args.len() {
+        return Err(());
+    }
     if let Some(f) = find_symbol(symbol) {
         Ok(unsafe { dynamic::apply(f, args.as_ptr(), args.len()) })
     } else {
@@ -43,10 +52,67 @@ pub fn apply(symbol: &ModuleFunctionArity, args: &[OpaqueTerm]) -> Result<Erlang
     }
 }
 
+/// Invokes the exported function `mfa` with `args` via the dispatch table, as `erlang:apply/3` does
+///
+/// If there is no such export, this raises `undef` with `{Module, Function, Args, []}` as the top
+/// frame of the stacktrace, so that the failed call is reported the same way as in BEAM.
+pub fn apply_export(mfa: &ModuleFunctionArity, args: &[OpaqueTerm]) -> ErlangResult {
+    match apply(mfa, args) {
+        Ok(result) => result,
+        Err(_) => {
+            let trace = Trace::capture();
+            trace.set_top_frame(mfa, args);
+            let exception = ErlangException::new(atoms::Error, atoms::Undef.into(), trace);
+            ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(exception)) })
+        }
+    }
+}
+
+/// Invokes `fun` with `args` via the same dynamic call path as `apply`, as `erlang:apply/2` does
+///
+/// Returns `Err` if the number of arguments does not match the arity of `fun`, in which case the
+/// caller is expected to raise `badarity`.
+pub fn apply_closure(fun: &Closure, args: &[OpaqueTerm]) -> Result<ErlangResult, ()> {
+    if fun.effective_arity() != args.len() {
+        return Err(());
+    }
+
+    let callee = unsafe { mem::transmute::<*const (), DynamicCallee>(fun.callee()) };
+    if fun.is_thin() {
+        return Ok(unsafe { apply_callee(callee, args) });
+    }
+
+    // Closures with free variables receive the closure itself as an extra, final argument
+    let mut argv = Vec::with_capacity(args.len() + 1);
+    argv.extend_from_slice(args);
+    argv.push(unsafe { OpaqueTerm::from_gcbox_closure(fun) });
+    Ok(unsafe { apply_callee(callee, argv.as_slice()) })
+}
+
+/// Invokes the external fun `fun` with `args`, as `erlang:apply/2` does
+///
+/// The function `fun` refers to is looked up in the dispatch table now, rather than when `fun` was
+/// constructed, so if it doesn't exist this raises `undef` in the same way as `apply_export`.
+///
+/// Returns `Err` if the number of arguments does not match the arity of `fun`, in which case the
+/// caller is expected to raise `badarity`.
+pub fn apply_external(fun: &ExternalFun, args: &[OpaqueTerm]) -> Result<ErlangResult, ()> {
+    if fun.arity as usize != args.len() {
+        return Err(());
+    }
+
+    Ok(apply_export(&fun.mfa(), args))
+}
+
 pub unsafe fn apply_callee(callee: DynamicCallee, args: &[OpaqueTerm]) -> ErlangResult {
     dynamic::apply(callee, args.as_ptr(), args.len())
 }
 
+/// Looks up the function for `mfa` in the dispatch table
+///
+/// The compiler only registers exported functions in the dispatch table, so this returns `None`
+/// for private functions even though their symbols are linked into the executable. Callers which
+/// dispatch on a runtime MFA, such as `erlang:apply/3`, must treat `None` as `undef`.
 pub fn find_symbol(mfa: &ModuleFunctionArity) -> Option<DynamicCallee> {
     if let Some(f) = SYMBOLS.read().get_function(mfa) {
         Some(unsafe { mem::transmute::<*const (), DynamicCallee>(f) })
@@ -59,6 +125,14 @@ pub fn module_loaded(module: Atom) -> bool {
     SYMBOLS.read().contains_module(module)
 }
 
+/// Returns true if `mfa` is in the dispatch table, as `erlang:function_exported/3` does
+///
+/// Unlike `apply_export`, this is not an error for a module which does not exist, as such a module
+/// simply exports nothing.
+pub fn function_exported(mfa: &ModuleFunctionArity) -> bool {
+    SYMBOLS.read().get_function(mfa).is_some()
+}
+
 /// Performs one-time initialization of the atom table at program start, using the
 /// array of constant atom values present in the compiled program.
 ///
@@ -148,3 +222,201 @@ impl Default for SymbolTable {
 // These are safe to implement because the items in the symbol table are static
 unsafe impl Sync for SymbolTable {}
 unsafe impl Send for SymbolTable {}
+
+#[cfg(test)]
+mod tests {
+    use std::hint::black_box;
+    use std::sync::Once;
+
+    use seq_macro::seq;
+
+    use super::*;
+    use crate::function::MAX_ARITY;
+    use crate::term::Term;
+
+    extern "C-unwind" fn first(a: OpaqueTerm, _b: OpaqueTerm) -> ErlangResult {
+        ErlangResult::Ok(a)
+    }
+
+    /// Returns the number of arguments which arrived in order, i.e. the `i`th argument is `i`
+    fn count_in_order(args: &[OpaqueTerm]) -> ErlangResult {
+        let n = args
+            .iter()
+            .enumerate()
+            .filter(|(i, arg)| Term::from(**arg) == Term::Int(*i as i64))
+            .count();
+        ErlangResult::Ok(Term::Int(n as i64).into())
+    }
+
+    // Arities either side of the number of arguments passed in registers on every supported target
+    seq!(A in 0..=21 {
+        #(
+            seq!(N in 0..A {
+                extern "C-unwind" fn in_order~A(#(a~N: OpaqueTerm,)*) -> ErlangResult {
+                    count_in_order(&[#(a~N,)*])
+                }
+            });
+        )*
+    });
+
+    seq!(N in 0..255 {
+        extern "C-unwind" fn in_order255(#(a~N: OpaqueTerm,)*) -> ErlangResult {
+            count_in_order(&[#(a~N,)*])
+        }
+    });
+
+    /// Applies `callee` to `argc` arguments, checking they all arrive in order, and that the stack
+    /// of the caller is intact afterwards
+    fn assert_applies_in_order(callee: *const (), argc: usize) {
+        let args = (0..argc)
+            .map(|i| Term::Int(i as i64).into())
+            .collect::<Vec<OpaqueTerm>>();
+        let canary = black_box([0xdead_beef_u64; 8]);
+        let callee = unsafe { mem::transmute::<*const (), DynamicCallee>(callee) };
+        let result = unsafe { apply_callee(callee, args.as_slice()) };
+        assert_eq!(black_box(canary), [0xdead_beef_u64; 8], "arity {}", argc);
+        assert_eq!(
+            result,
+            ErlangResult::Ok(Term::Int(argc as i64).into()),
+            "arity {}",
+            argc
+        );
+    }
+
+    fn mfa(function: &str, arity: usize) -> ModuleFunctionArity {
+        let module = Atom::try_from("apply_test").unwrap();
+        let function = Atom::try_from(function).unwrap();
+        ModuleFunctionArity::new(module, function, arity)
+    }
+
+    fn init_symbols() {
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            let first = mfa("first", 2);
+            let in_order = mfa("in_order", 20);
+            let symbols = Box::leak(Box::new([
+                FunctionSymbol {
+                    module: first.module,
+                    function: first.function,
+                    arity: first.arity,
+                    ptr: self::first as *const (),
+                },
+                FunctionSymbol {
+                    module: in_order.module,
+                    function: in_order.function,
+                    arity: in_order.arity,
+                    ptr: in_order20 as *const (),
+                },
+            ]));
+            let range = symbols.as_ptr_range();
+            assert!(unsafe { init(range.start, range.end) });
+        });
+    }
+
+    #[test]
+    fn apply_export_calls_exported_function() {
+        init_symbols();
+
+        let args = [Term::Int(1).into(), Term::Int(2).into()];
+        assert_eq!(
+            apply_export(&mfa("first", 2), &args),
+            ErlangResult::Ok(Term::Int(1).into())
+        );
+    }
+
+    #[test]
+    fn apply_passes_arguments_in_order_at_every_arity() {
+        seq!(A in 0..=21 {
+            #(
+                assert_applies_in_order(in_order~A as *const (), A);
+            )*
+        });
+        assert_applies_in_order(in_order255 as *const (), MAX_ARITY);
+    }
+
+    #[test]
+    fn apply_export_calls_functions_with_spilled_arguments() {
+        init_symbols();
+
+        let args = (0..20)
+            .map(|i| Term::Int(i).into())
+            .collect::<Vec<OpaqueTerm>>();
+        assert_eq!(
+            apply_export(&mfa("in_order", 20), args.as_slice()),
+            ErlangResult::Ok(Term::Int(20).into())
+        );
+    }
+
+    #[test]
+    fn apply_rejects_mismatched_arity() {
+        init_symbols();
+
+        let args = [Term::Int(1).into()];
+        assert!(apply(&mfa("first", 1), &args).is_err());
+        assert!(apply(&mfa("first", 2), &args).is_err());
+    }
+
+    #[test]
+    fn function_exported_checks_the_dispatch_table() {
+        init_symbols();
+
+        assert!(function_exported(&mfa("first", 2)));
+        assert!(!function_exported(&mfa("first", 1)));
+        assert!(!function_exported(&mfa("missing", 2)));
+
+        let unknown_module = Atom::try_from("no_such_module").unwrap();
+        let first = Atom::try_from("first").unwrap();
+        assert!(!function_exported(&ModuleFunctionArity::new(
+            unknown_module,
+            first,
+            2
+        )));
+    }
+
+    #[test]
+    fn apply_external_resolves_the_function_when_called() {
+        init_symbols();
+
+        let args = [Term::Int(1).into(), Term::Int(2).into()];
+        let first = ExternalFun::from(mfa("first", 2));
+        assert_eq!(
+            apply_external(&first, &args),
+            Ok(ErlangResult::Ok(Term::Int(1).into()))
+        );
+        assert!(apply_external(&first, &args[..1]).is_err());
+
+        // Constructing a fun for a missing function is fine, calling it raises undef
+        let missing = ExternalFun::from(mfa("missing", 2));
+        let Ok(ErlangResult::Err(ptr)) = apply_external(&missing, &args) else { panic!("expected undef"); };
+        let exception = unsafe { Box::from_raw(ptr.as_ptr()) };
+        assert_eq!(exception.reason(), Term::Atom(atoms::Undef));
+    }
+
+    #[test]
+    fn apply_export_raises_undef_for_missing_function() {
+        init_symbols();
+
+        let missing = mfa("missing", 2);
+        let args = [Term::Int(1).into(), Term::Int(2).into()];
+        let ErlangResult::Err(ptr) = apply_export(&missing, &args) else { panic!("expected undef"); };
+        let exception = unsafe { Box::from_raw(ptr.as_ptr()) };
+        assert_eq!(exception.kind(), atoms::Error);
+        assert_eq!(exception.reason(), Term::Atom(atoms::Undef));
+
+        // The top of the stacktrace is `{apply_test, missing, [1, 2], []}`
+        let trace = exception.trace();
+        let Term::Cons(frames) = trace.as_term().unwrap() else { panic!("expected stacktrace"); };
+        let Term::Tuple(top) = unsafe { frames.as_ref() }.head() else { panic!("expected frame"); };
+        let top = unsafe { top.as_ref() };
+        assert_eq!(top.len(), 4);
+        assert_eq!(Term::from(top[0]), Term::Atom(missing.module));
+        assert_eq!(Term::from(top[1]), Term::Atom(missing.function));
+        let Term::Cons(arglist) = top[2].into() else { panic!("expected argument list"); };
+        let arglist = unsafe { arglist.as_ref() }
+            .iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(arglist, [Term::Int(1), Term::Int(2)]);
+    }
+}
@@ -0,0 +1,44 @@
+use super::ModuleFunctionArity;
+
+#[cfg_attr(rustfmt, rustfmt_skip)]
+mod generated {
+    // During the build step, `build.rs` extracts the BIFs from the signatures the compiler lowers
+    // them with, and outputs them to `OUT_DIR`, so the runtime and compiler agree on the set
+    include!(concat!(env!("OUT_DIR"), "/builtins.rs"));
+}
+
+/// Returns true if `mfa` is a BIF known to the compiler, as `erlang:is_builtin/3` does
+pub fn is_builtin(mfa: &ModuleFunctionArity) -> bool {
+    let key = (mfa.module.as_str(), mfa.function.as_str(), mfa.arity);
+    generated::BUILTINS.binary_search(&key).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_builtin(mfa: &str) -> bool {
+        super::is_builtin(&mfa.parse().unwrap())
+    }
+
+    #[test]
+    fn builtins_match_the_compiler() {
+        assert!(is_builtin("erlang:length/1"));
+        assert!(is_builtin("erlang:self/0"));
+        assert!(is_builtin("erlang:++/2"));
+        assert!(is_builtin("erlang:is_builtin/3"));
+        assert!(is_builtin("erlang:function_exported/3"));
+    }
+
+    #[test]
+    fn non_builtins_are_rejected() {
+        assert!(!is_builtin("erlang:length/2"));
+        assert!(!is_builtin("lists:reverse/1"));
+        assert!(!is_builtin("no_such_module:length/1"));
+    }
+
+    #[test]
+    fn builtins_are_sorted_for_lookup() {
+        assert!(generated::BUILTINS.windows(2).all(|w| w[0] < w[1]));
+    }
+}
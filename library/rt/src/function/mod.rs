@@ -1,24 +1,55 @@
 mod apply;
+mod builtins;
 mod mfa;
 
 pub use self::apply::*;
+pub use self::builtins::is_builtin;
 pub use self::mfa::ModuleFunctionArity;
 
 use core::convert::Infallible;
 use core::fmt;
+use core::mem;
 use core::ops::{self, ControlFlow};
 use core::ptr::NonNull;
 
+use static_assertions::const_assert_eq;
+
 use crate::error::ErlangException;
 use crate::term::{Atom, OpaqueTerm};
 
+/// The maximum number of arguments a function may have, as in BEAM
+///
+/// Functions of any arity up to this limit can be called dynamically, e.g. via `apply`, as the
+/// dynamic call shims pass whatever doesn't fit in registers on the stack, per the native calling
+/// convention of the target. The compiler rejects functions and calls with more arguments.
+pub const MAX_ARITY: usize = u8::MAX as usize;
+
 /// This type reflects the implicit return type expected by the Erlang calling convention
+///
+/// Generated code sees this type as the struct `{i1, T}`, i.e. a flag which is set if the call
+/// failed, followed by the payload at its natural alignment. For the default type parameters this is
+/// two words, which is returned in a pair of registers on all supported targets, and the assembly
+/// that calls `__firefly_builtin_exit` with the result of a process entry point relies on this.
+///
+/// * `Ok` has a flag of `0`, and the payload is the result of the call
+/// * `Err` has a flag of `1`, and the payload is a pointer to the exception that was raised, which
+/// was allocated via `Box` and is owned by whoever receives it, i.e. the handler which catches it,
+/// or the scheduler if it reaches the entry point of the process
+///
+/// Intrinsics whose compiler signature has a different payload type, e.g. `{i1, i32}`, use the
+/// corresponding type parameters, but the flag always comes first and has the same meaning.
+///
+/// NOTE: The legacy runtimes built on `liblumen_alloc` have a different calling convention, with
+/// their own `ErlangResult`, and the two must never be mixed.
 #[derive(Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
 pub enum ErlangResult<T = OpaqueTerm, E = NonNull<ErlangException>> {
     Ok(T) = 0,
     Err(E) = 1,
 }
+const_assert_eq!(mem::size_of::<ErlangResult>(), 2 * mem::size_of::<u64>());
+const_assert_eq!(mem::align_of::<ErlangResult>(), mem::align_of::<u64>());
+
 impl<T, E> const Clone for ErlangResult<T, E>
 where
     T: ~const Clone + ~const core::marker::Destruct,
@@ -228,3 +259,117 @@ unsafe impl Sync for FunctionSymbol {}
 
 /// Function symbols are read-only and pinned, and therefore Send
 unsafe impl Send for FunctionSymbol {}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::{self, MaybeUninit};
+    use core::ptr;
+
+    use super::*;
+    use crate::term::Term;
+
+    /// The layout of `ErlangResult` as seen by generated code, i.e. `{i1, term}`
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    struct RawErlangResult<T> {
+        is_err: u8,
+        value: T,
+    }
+
+    /// Returns the offsets of the flag and the payload of `result`
+    fn offsets<T, E>(result: &ErlangResult<T, E>) -> (usize, usize) {
+        let base = result as *const _ as usize;
+        let flag = result as *const _ as *const u8;
+        assert_eq!(unsafe { *flag }, result.is_err() as u8);
+        let payload = match result {
+            ErlangResult::Ok(value) => value as *const T as usize,
+            ErlangResult::Err(err) => err as *const E as usize,
+        };
+        (flag as usize - base, payload - base)
+    }
+
+    fn raw_offsets<T>() -> (usize, usize) {
+        let raw = MaybeUninit::<RawErlangResult<T>>::uninit();
+        let base = raw.as_ptr();
+        let flag = unsafe { ptr::addr_of!((*base).is_err) };
+        let value = unsafe { ptr::addr_of!((*base).value) };
+        (
+            flag as usize - base as usize,
+            value as usize - base as usize,
+        )
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    extern "C" fn identity(result: ErlangResult) -> ErlangResult {
+        result
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    extern "C" fn raise(exception: NonNull<ErlangException>) -> ErlangResult {
+        ErlangResult::Err(exception)
+    }
+
+    #[test]
+    fn erlang_result_is_laid_out_as_a_flag_followed_by_the_payload() {
+        assert_eq!(
+            mem::size_of::<ErlangResult>(),
+            mem::size_of::<RawErlangResult<OpaqueTerm>>()
+        );
+        assert_eq!(
+            mem::align_of::<ErlangResult>(),
+            mem::align_of::<RawErlangResult<OpaqueTerm>>()
+        );
+        let ok = ErlangResult::Ok(OpaqueTerm::NIL);
+        let err = ErlangResult::<OpaqueTerm>::Err(NonNull::dangling());
+        assert_eq!(offsets(&ok), raw_offsets::<OpaqueTerm>());
+        assert_eq!(offsets(&err), raw_offsets::<OpaqueTerm>());
+
+        // Intrinsics with narrower payloads have the same shape
+        assert_eq!(
+            mem::size_of::<ErlangResult<u32, u32>>(),
+            mem::size_of::<RawErlangResult<u32>>()
+        );
+        assert_eq!(
+            offsets(&ErlangResult::<u32, u32>::Ok(1)),
+            raw_offsets::<u32>()
+        );
+        assert_eq!(
+            offsets(&ErlangResult::<u32, u32>::Err(1)),
+            raw_offsets::<u32>()
+        );
+    }
+
+    #[test]
+    fn erlang_result_round_trips_through_the_c_abi() {
+        let f: extern "C" fn(ErlangResult) -> ErlangResult = identity;
+
+        let ok = ErlangResult::Ok(Term::Int(42).into());
+        assert_eq!(f(ok), ok);
+        let exception = NonNull::<ErlangException>::dangling();
+        assert_eq!(
+            f(ErlangResult::Err(exception)),
+            ErlangResult::Err(exception)
+        );
+
+        // Generated code reads the flag and payload of the same registers as a struct
+        let raw = unsafe {
+            mem::transmute::<
+                extern "C" fn(NonNull<ErlangException>) -> ErlangResult,
+                extern "C" fn(NonNull<ErlangException>) -> RawErlangResult<u64>,
+            >(raise)
+        };
+        let result = raw(exception);
+        assert_eq!(result.is_err, 1);
+        assert_eq!(result.value, exception.as_ptr() as u64);
+
+        let raw = unsafe {
+            mem::transmute::<
+                extern "C" fn(ErlangResult) -> ErlangResult,
+                extern "C" fn(ErlangResult) -> RawErlangResult<u64>,
+            >(identity)
+        };
+        let result = raw(ok);
+        assert_eq!(result.is_err, 0);
+        assert_eq!(result.value, OpaqueTerm::from(Term::Int(42)).raw());
+    }
+}
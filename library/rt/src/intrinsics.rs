@@ -35,6 +35,17 @@ pub extern "C" fn size(value: OpaqueTerm) -> usize {
     value.size()
 }
 
+/// This is an intrinsic expected by the compiler to be defined as part of the runtime, and is used to check the arity of funs in guards
+#[export_name = "__firefly_builtin_fun_arity"]
+pub extern "C" fn fun_arity(value: OpaqueTerm) -> u32 {
+    match value.into() {
+        Term::Closure(fun) => fun.effective_arity() as u32,
+        Term::ExternalFun(fun) => fun.arity as u32,
+        // The compiler only emits this after checking for a fun, but no fun can have this arity
+        _ => u32::MAX,
+    }
+}
+
 #[export_name = "erlang:is_atom/1"]
 pub extern "C" fn is_atom1(value: OpaqueTerm) -> ErlangResult {
     ErlangResult::Ok(value.is_atom().into())
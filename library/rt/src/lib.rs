@@ -56,5 +56,8 @@ pub mod cmp;
 pub mod error;
 pub mod function;
 pub mod intrinsics;
+pub mod memory;
 pub mod process;
 pub mod term;
+#[cfg(feature = "std")]
+pub mod time;
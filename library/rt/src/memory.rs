@@ -0,0 +1,79 @@
+//! Accounting of the memory allocated by the runtime system, as reported by `erlang:memory/0,1`
+//!
+//! Each category has a counter of the bytes currently allocated for it, which is updated by the
+//! allocation and deallocation paths of the memory in that category. Only memory allocated for
+//! one of the categories is counted, so the `total` is the sum of the categories, not all of the
+//! memory used by the OS process.
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::term::{atoms, Atom};
+
+/// The categories of memory, in the order they are reported by `erlang:memory/0`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Category {
+    /// Process heaps and stacks
+    Processes,
+    /// The names of atoms created while running, along with their atom table entries
+    Atom,
+    /// Reference-counted binaries
+    Binary,
+    /// The objects stored in ETS tables
+    Ets,
+}
+impl Category {
+    pub const ALL: [Self; 4] = [Self::Processes, Self::Atom, Self::Binary, Self::Ets];
+
+    /// Returns the category named by `name`, if there is one
+    pub fn from_atom(name: Atom) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|category| category.as_atom() == name)
+    }
+
+    /// Returns the name of this category, as used by `erlang:memory/0,1`
+    pub fn as_atom(self) -> Atom {
+        match self {
+            Self::Processes => atoms::Processes,
+            Self::Atom => atoms::AtomAtom,
+            Self::Binary => atoms::Binary,
+            Self::Ets => atoms::Ets,
+        }
+    }
+
+    /// Counts `bytes` as allocated in this category
+    #[inline]
+    pub fn allocated(self, bytes: usize) {
+        self.counter().fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Counts `bytes` previously counted by `allocated` as no longer allocated
+    #[inline]
+    pub fn deallocated(self, bytes: usize) {
+        self.counter().fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    /// Returns the bytes currently allocated in this category
+    pub fn bytes(self) -> usize {
+        self.counter().load(Ordering::Relaxed)
+    }
+
+    fn counter(self) -> &'static AtomicUsize {
+        match self {
+            Self::Processes => &PROCESSES,
+            Self::Atom => &ATOM,
+            Self::Binary => &BINARY,
+            Self::Ets => &ETS,
+        }
+    }
+}
+
+/// Returns the bytes currently allocated in all categories
+pub fn total() -> usize {
+    Category::ALL.iter().map(|category| category.bytes()).sum()
+}
+
+static PROCESSES: AtomicUsize = AtomicUsize::new(0);
+static ATOM: AtomicUsize = AtomicUsize::new(0);
+static BINARY: AtomicUsize = AtomicUsize::new(0);
+static ETS: AtomicUsize = AtomicUsize::new(0);
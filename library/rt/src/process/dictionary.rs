@@ -0,0 +1,255 @@
+use alloc::vec::Vec;
+use core::iter;
+use core::mem;
+
+use crate::cmp::ExactEq;
+use crate::term::{phash2, Term};
+
+/// The process dictionary, mapping keys to values which are matched exactly, i.e. `1` and `1.0`
+/// are different keys.
+///
+/// This is a hash table using open addressing with linear probing, where removals shift the
+/// entries after them back rather than leaving tombstones, so lookups never probe further than
+/// the entries which are actually present.
+///
+/// The table doesn't copy terms itself, the owning process must copy keys and values onto its
+/// heap before inserting them, see `Process::put`.
+#[derive(Default)]
+pub struct Dictionary {
+    /// The slots of the table, of which there are either none or a power of two
+    slots: Vec<Option<Entry>>,
+    len: usize,
+}
+
+#[derive(Copy, Clone)]
+struct Entry {
+    key: Term,
+    value: Term,
+    hash: u32,
+}
+
+impl Dictionary {
+    const MIN_CAPACITY: usize = 8;
+
+    pub const fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of entries in the dictionary
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the value of `key`, if present
+    pub fn get(&self, key: Term) -> Option<Term> {
+        self.find(key, phash2(key))
+            .map(|index| self.slots[index].unwrap().value)
+    }
+
+    /// Sets the value of `key`, returning its previous value, if present
+    ///
+    /// If `key` is already present, the existing key is kept and only the value is replaced.
+    pub fn insert(&mut self, key: Term, value: Term) -> Option<Term> {
+        let hash = phash2(key);
+        if let Some(index) = self.find(key, hash) {
+            let entry = self.slots[index].as_mut().unwrap();
+            return Some(mem::replace(&mut entry.value, value));
+        }
+
+        // Keep the load factor at or under 3/4, so there is always an empty slot to end a probe
+        if (self.len + 1) * 4 > self.slots.len() * 3 {
+            self.grow();
+        }
+        let mut index = self.home(hash);
+        while self.slots[index].is_some() {
+            index = self.next(index);
+        }
+        self.slots[index] = Some(Entry { key, value, hash });
+        self.len += 1;
+
+        None
+    }
+
+    /// Removes `key`, returning its value, if present
+    pub fn remove(&mut self, key: Term) -> Option<Term> {
+        let mut hole = self.find(key, phash2(key))?;
+        let removed = self.slots[hole].take().unwrap();
+        self.len -= 1;
+
+        // Shift back each entry in the rest of the probe sequence that may be moved into the hole,
+        // i.e. whose home slot is not cyclically between the hole and where the entry is
+        let mut index = self.next(hole);
+        while let Some(entry) = self.slots[index] {
+            let home = self.home(entry.hash);
+            let distance_to_home = index.wrapping_sub(home) & self.mask();
+            let distance_to_hole = index.wrapping_sub(hole) & self.mask();
+            if distance_to_home >= distance_to_hole {
+                self.slots[hole] = self.slots[index].take();
+                hole = index;
+            }
+            index = self.next(index);
+        }
+
+        Some(removed.value)
+    }
+
+    /// Removes all entries, returning them as `(key, value)` pairs
+    pub fn take_all(&mut self) -> Vec<(Term, Term)> {
+        let entries = self.iter().collect();
+        self.slots.clear();
+        self.len = 0;
+        entries
+    }
+
+    /// Returns an iterator over the `(key, value)` pairs of the dictionary, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Term, Term)> + '_ {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.map(|entry| (entry.key, entry.value)))
+    }
+
+    /// Returns the keys whose value is exactly `value`, in no particular order
+    pub fn keys_with_value(&self, value: Term) -> impl Iterator<Item = Term> + '_ {
+        self.iter()
+            .filter(move |(_, v)| v.exact_eq(&value))
+            .map(|(k, _)| k)
+    }
+
+    fn find(&self, key: Term, hash: u32) -> Option<usize> {
+        if self.slots.is_empty() {
+            return None;
+        }
+
+        let mut index = self.home(hash);
+        while let Some(entry) = &self.slots[index] {
+            if entry.hash == hash && entry.key.exact_eq(&key) {
+                return Some(index);
+            }
+            index = self.next(index);
+        }
+
+        None
+    }
+
+    fn grow(&mut self) {
+        let capacity = (self.slots.len() * 2).max(Self::MIN_CAPACITY);
+        let old = mem::replace(&mut self.slots, iter::repeat(None).take(capacity).collect());
+        for entry in old.into_iter().flatten() {
+            let mut index = self.home(entry.hash);
+            while self.slots[index].is_some() {
+                index = self.next(index);
+            }
+            self.slots[index] = Some(entry);
+        }
+    }
+
+    #[inline]
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    #[inline]
+    fn home(&self, hash: u32) -> usize {
+        hash as usize & self.mask()
+    }
+
+    #[inline]
+    fn next(&self, index: usize) -> usize {
+        (index + 1) & self.mask()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    fn int(i: i64) -> Term {
+        Term::Int(i)
+    }
+
+    #[test]
+    fn insert_returns_previous_value() {
+        let mut dictionary = Dictionary::new();
+
+        assert_eq!(dictionary.insert(int(1), int(10)), None);
+        assert_eq!(dictionary.insert(int(1), int(11)), Some(int(10)));
+        assert_eq!(dictionary.get(int(1)), Some(int(11)));
+        assert_eq!(dictionary.len(), 1);
+    }
+
+    #[test]
+    fn keys_are_matched_exactly() {
+        let mut dictionary = Dictionary::new();
+        dictionary.insert(int(1), int(10));
+
+        assert_eq!(dictionary.get(Term::from(1.0)), None);
+    }
+
+    #[test]
+    fn grows_and_keeps_every_entry() {
+        let mut dictionary = Dictionary::new();
+        for i in 0..1000 {
+            dictionary.insert(int(i), int(i * 2));
+        }
+
+        assert_eq!(dictionary.len(), 1000);
+        for i in 0..1000 {
+            assert_eq!(dictionary.get(int(i)), Some(int(i * 2)));
+        }
+    }
+
+    #[test]
+    fn remove_keeps_the_rest_reachable() {
+        let mut dictionary = Dictionary::new();
+        for i in 0..100 {
+            dictionary.insert(int(i), int(i));
+        }
+        for i in (0..100).step_by(3) {
+            assert_eq!(dictionary.remove(int(i)), Some(int(i)));
+        }
+
+        assert_eq!(dictionary.remove(int(0)), None);
+        for i in 0..100 {
+            let expected = if i % 3 == 0 { None } else { Some(int(i)) };
+            assert_eq!(dictionary.get(int(i)), expected);
+        }
+    }
+
+    #[test]
+    fn take_all_empties_the_dictionary() {
+        let mut dictionary = Dictionary::new();
+        dictionary.insert(int(1), int(10));
+        dictionary.insert(int(2), int(20));
+
+        let mut entries = dictionary.take_all();
+        entries.sort_by_key(|(key, _)| *key);
+
+        assert_eq!(entries, vec![(int(1), int(10)), (int(2), int(20))]);
+        assert!(dictionary.is_empty());
+        assert_eq!(dictionary.get(int(1)), None);
+    }
+
+    #[test]
+    fn keys_with_value_returns_only_matching_keys() {
+        let mut dictionary = Dictionary::new();
+        dictionary.insert(int(1), int(10));
+        dictionary.insert(int(2), int(20));
+        dictionary.insert(int(3), int(10));
+
+        let mut keys = dictionary.keys_with_value(int(10)).collect::<Vec<_>>();
+        keys.sort();
+
+        assert_eq!(keys, vec![int(1), int(3)]);
+    }
+}
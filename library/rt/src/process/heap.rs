@@ -5,6 +5,7 @@ use core::ptr::{self, NonNull};
 
 use firefly_alloc::heap::Heap;
 
+use crate::memory::Category;
 use crate::term::Term;
 
 pub struct ProcessHeap {
@@ -18,6 +19,7 @@ impl ProcessHeap {
         let layout =
             Layout::from_size_align(Self::DEFAULT_HEAP_SIZE, mem::align_of::<Term>()).unwrap();
         let nonnull = Global.allocate(layout).unwrap();
+        Category::Processes.allocated(Self::DEFAULT_HEAP_SIZE);
         Self {
             range: nonnull.as_ptr(),
             top: UnsafeCell::new(nonnull.as_non_null_ptr().as_ptr()),
@@ -29,6 +31,7 @@ impl Drop for ProcessHeap {
         let size = ptr::metadata(self.range) as usize;
         let layout = Layout::from_size_align(size, mem::align_of::<Term>()).unwrap();
         unsafe { Global.deallocate(NonNull::new_unchecked(self.range.cast()), layout) }
+        Category::Processes.deallocated(size);
     }
 }
 unsafe impl Allocator for ProcessHeap {
@@ -1,7 +1,9 @@
+mod dictionary;
 mod heap;
 mod stack;
 
 use alloc::alloc::{AllocError, Allocator, Layout};
+use alloc::vec::Vec;
 use core::cell::UnsafeCell;
 use core::ptr::NonNull;
 
@@ -9,8 +11,9 @@ use firefly_alloc::heap::Heap;
 
 use crate::error::ErlangException;
 use crate::function::ModuleFunctionArity;
-use crate::term::ProcessId;
+use crate::term::{ProcessId, Term};
 
+pub use self::dictionary::Dictionary;
 pub use self::heap::ProcessHeap;
 pub use self::stack::ProcessStack;
 
@@ -23,13 +26,18 @@ pub enum ProcessStatus {
     Errored(NonNull<ErlangException>),
 }
 
+/// The number of reductions a process may do each time it is scheduled, before it should yield
+pub const MAX_REDUCTIONS: usize = 4000;
+
 pub struct Process {
     parent: Option<ProcessId>,
     pid: ProcessId,
-    #[allow(dead_code)]
     mfa: ModuleFunctionArity,
     /// The process status is only ever manipulated/accessed by the owning scheduler
     status: UnsafeCell<ProcessStatus>,
+    /// The reductions done since the process was last scheduled, which like the status is only
+    /// ever accessed by the process itself or its owning scheduler
+    reductions: UnsafeCell<usize>,
     /// The process heap can be safely accessed directly via UnsafeCell because it
     /// is always the case that either:
     ///
@@ -41,6 +49,9 @@ pub struct Process {
     /// are properly updated so that the aliasing in that case is safe.
     heap: UnsafeCell<ProcessHeap>,
     stack: UnsafeCell<ProcessStack>,
+    /// The process dictionary is only ever accessed by the process itself, and its keys and values
+    /// are allocated on the process heap, so it is part of the root set of the process
+    dictionary: UnsafeCell<Dictionary>,
 }
 impl Process {
     pub fn new(parent: Option<ProcessId>, pid: ProcessId, mfa: ModuleFunctionArity) -> Self {
@@ -49,8 +60,10 @@ impl Process {
             pid,
             mfa,
             status: UnsafeCell::new(ProcessStatus::Waiting),
+            reductions: UnsafeCell::new(0),
             heap: UnsafeCell::new(ProcessHeap::new()),
             stack: UnsafeCell::new(ProcessStack::new(32).unwrap()),
+            dictionary: UnsafeCell::new(Dictionary::new()),
         }
     }
 
@@ -62,6 +75,11 @@ impl Process {
         self.pid
     }
 
+    /// Returns the function the process was spawned to run
+    pub fn mfa(&self) -> ModuleFunctionArity {
+        self.mfa
+    }
+
     pub fn status(&self) -> ProcessStatus {
         unsafe { self.status.get().read() }
     }
@@ -94,6 +112,72 @@ impl Process {
         self.status.get().write(status);
     }
 
+    /// Returns the reductions done since the process was last scheduled
+    pub fn reductions(&self) -> usize {
+        unsafe { self.reductions.get().read() }
+    }
+
+    /// Adds `reductions` to those done since the process was last scheduled, returning `true` if
+    /// it has used up its `MAX_REDUCTIONS` and should yield
+    ///
+    /// # Safety
+    ///
+    /// Like `set_status`, this must only be called by the process itself or its owning scheduler.
+    pub unsafe fn reduce(&self, reductions: usize) -> bool {
+        let total = self.reductions().saturating_add(reductions);
+        self.reductions.get().write(total);
+        total >= MAX_REDUCTIONS
+    }
+
+    /// Resets the reductions done, when the process is scheduled
+    ///
+    /// # Safety
+    ///
+    /// Like `set_status`, this must only be called by the owning scheduler.
+    pub unsafe fn reset_reductions(&self) {
+        self.reductions.get().write(0);
+    }
+
+    /// Returns the process dictionary
+    pub fn dictionary(&self) -> &Dictionary {
+        unsafe { &*self.dictionary.get() }
+    }
+
+    /// Sets `key` to `value` in the process dictionary, copying both onto the process heap, and
+    /// returns the previous value of `key`, if present
+    ///
+    /// # Safety
+    ///
+    /// This must only be called by the process itself, as it has exclusive access to its dictionary
+    pub unsafe fn put(&self, key: Term, value: Term) -> Result<Option<Term>, AllocError> {
+        let key = key.clone_to_heap(self)?;
+        let value = value.clone_to_heap(self)?;
+        Ok(self.dictionary_mut().insert(key, value))
+    }
+
+    /// Removes `key` from the process dictionary, returning its value, if present
+    ///
+    /// # Safety
+    ///
+    /// Like `put`, this must only be called by the process itself.
+    pub unsafe fn erase(&self, key: Term) -> Option<Term> {
+        self.dictionary_mut().remove(key)
+    }
+
+    /// Removes all entries from the process dictionary, returning them as `(key, value)` pairs
+    ///
+    /// # Safety
+    ///
+    /// Like `put`, this must only be called by the process itself.
+    pub unsafe fn erase_all(&self) -> Vec<(Term, Term)> {
+        self.dictionary_mut().take_all()
+    }
+
+    #[inline(always)]
+    unsafe fn dictionary_mut(&self) -> &mut Dictionary {
+        &mut *self.dictionary.get()
+    }
+
     #[inline(always)]
     fn heap(&self) -> &ProcessHeap {
         unsafe { &*self.heap.get() }
@@ -4,6 +4,8 @@ use core::ptr;
 use firefly_alloc::mmap;
 use firefly_system as system;
 
+use crate::memory::Category;
+
 const STACK_ALIGNMENT: usize = 16;
 
 #[derive(Debug)]
@@ -19,7 +21,9 @@ impl ProcessStack {
         debug_assert!(num_pages > 0, "stack size in pages must be greater than 0");
 
         let ptr = unsafe { mmap::map_stack(num_pages)? };
-        Ok(unsafe { Self::from_raw_parts(ptr.as_ptr(), num_pages) })
+        let stack = unsafe { Self::from_raw_parts(ptr.as_ptr(), num_pages) };
+        Category::Processes.allocated(stack.size);
+        Ok(stack)
     }
 
     unsafe fn from_raw_parts(base: *mut u8, pages: usize) -> Self {
@@ -81,5 +85,6 @@ impl Drop for ProcessStack {
         unsafe {
             mmap::unmap(self.base, layout);
         }
+        Category::Processes.deallocated(self.size);
     }
 }
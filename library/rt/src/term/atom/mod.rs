@@ -8,7 +8,10 @@ pub mod atoms {
 
 mod table;
 
-pub use self::table::AtomData;
+pub use self::table::{try_atom_count, AtomData, ModuleAtoms};
+
+use alloc::borrow::Cow;
+use alloc::string::String;
 
 use core::convert::AsRef;
 use core::fmt::{self, Debug, Display};
@@ -21,8 +24,8 @@ use firefly_binary::Encoding;
 
 use super::OpaqueTerm;
 
-/// The maximum length of an atom (255)
-pub const MAX_ATOM_LENGTH: usize = u16::max_value() as usize;
+/// The maximum length of an atom, in characters (255)
+pub const MAX_ATOM_LENGTH: usize = 255;
 
 /// Produced by operations which create atoms
 #[derive(Debug)]
@@ -75,11 +78,27 @@ pub struct Atom(*const AtomData);
 unsafe impl Send for Atom {}
 unsafe impl Sync for Atom {}
 impl Atom {
+    /// Creates a new atom from a slice of bytes interpreted as Latin-1.
+    ///
+    /// Returns `Err` if the atom name is invalid or the table overflows
+    #[inline]
+    pub fn try_from_latin1_bytes(name: &[u8]) -> Result<Self, AtomError> {
+        Self::try_from(latin1_to_str(name).as_ref())
+    }
+
     /// Creates a new atom from a slice of bytes interpreted as Latin-1.
     ///
     /// Returns `Err` if the atom does not exist
     #[inline]
     pub fn try_from_latin1_bytes_existing(name: &[u8]) -> Result<Self, AtomError> {
+        Self::try_from_str_existing(latin1_to_str(name))
+    }
+
+    /// Creates a new atom from a slice of bytes interpreted as UTF-8, but only if the atom already exists
+    ///
+    /// Returns `Err` if the bytes are not valid UTF-8, or the atom does not exist
+    #[inline]
+    pub fn try_from_utf8_bytes_existing(name: &[u8]) -> Result<Self, AtomError> {
         Self::try_from_str_existing(str::from_utf8(name)?)
     }
 
@@ -174,35 +193,163 @@ impl Atom {
 
     /// Returns true if this atom requires quotes when printing as an Erlang term
     pub fn needs_quotes(&self) -> bool {
-        // See https://github.com/erlang/otp/blob/ca83f680aab717fe65634247d16f18a8cbfc6d8d/erts/emulator/beam/erl_printf_term.c#L193-L212
-        let mut chars = self.as_str().chars();
-
-        match chars.next() {
-            Some(first_char) => {
-                // https://github.com/erlang/otp/blob/ca83f680aab717fe65634247d16f18a8cbfc6d8d/erts/emulator/beam/erl_printf_term.c#L198-L199
-                // -> https://github.com/erlang/otp/blob/ca83f680aab717fe65634247d16f18a8cbfc6d8d/erts/emulator/beam/erl_printf_term.c#L98
-                !first_char.is_ascii_lowercase() || {
-                    // https://github.com/erlang/otp/blob/ca83f680aab717fe65634247d16f18a8cbfc6d8d/erts/emulator/beam/erl_printf_term.c#L201-L200
-                    // -> https://github.com/erlang/otp/blob/ca83f680aab717fe65634247d16f18a8cbfc6d8d/erts/emulator/beam/erl_printf_term.c#L102
-                    //    -> https://github.com/erlang/otp/blob/ca83f680aab717fe65634247d16f18a8cbfc6d8d/erts/emulator/beam/erl_printf_term.c#L91
-                    //    -> https://github.com/erlang/otp/blob/ca83f680aab717fe65634247d16f18a8cbfc6d8d/erts/emulator/beam/erl_printf_term.c#L91
-                    //    -> https://github.com/erlang/otp/blob/ca83f680aab717fe65634247d16f18a8cbfc6d8d/erts/emulator/beam/erl_printf_term.c#L99
-                    chars.any(|c| (!c.is_ascii_alphanumeric() && c != '_'))
-                }
-            }
-            // https://github.com/erlang/otp/blob/ca83f680aab717fe65634247d16f18a8cbfc6d8d/erts/emulator/beam/erl_printf_term.c#L187-L190
-            None => true,
+        name_needs_quotes(&self.display_name())
+    }
+
+    /// Gets the name of this atom for printing.
+    ///
+    /// Unlike `as_str`, this never panics: if the name is not valid UTF-8, which can only happen
+    /// for atoms from the data of a compiled program, the invalid bytes are decoded as Latin-1.
+    fn display_name(&self) -> Cow<'static, str> {
+        match self {
+            &atoms::False | &atoms::True => Cow::Borrowed(self.as_str()),
+            _ => lossless_utf8_to_str(unsafe { (&*self.0).as_bytes() }),
         }
     }
 
     fn validate(name: &str) -> Result<(), AtomError> {
-        let len = name.len();
+        // A character is never encoded in less than a byte, so we only need to count them for long names
+        if name.len() <= MAX_ATOM_LENGTH {
+            return Ok(());
+        }
+        let len = name.chars().count();
         if len > MAX_ATOM_LENGTH {
             return Err(AtomError::InvalidLength(len));
         }
         Ok(())
     }
 }
+
+/// Returns true if an atom named `name` must be quoted to be read back as the same atom.
+///
+/// This matches `io_lib:quote_atom/2`, which is what `~p` uses: an atom is unquoted only if it
+/// starts with a lowercase Latin-1 letter, is made up of Latin-1 letters, digits, `_` and `@`, and
+/// is not a reserved word.
+///
+/// See `quote_atom/2` in https://github.com/erlang/otp/blob/master/lib/stdlib/src/io_lib.erl
+fn name_needs_quotes(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first_char) if is_lowercase_name_char(first_char) => {
+            !chars.all(is_name_char) || is_reserved_word(name)
+        }
+        _ => true,
+    }
+}
+
+fn is_lowercase_name_char(c: char) -> bool {
+    c.is_ascii_lowercase() || (('ß'..='ÿ').contains(&c) && c != '÷')
+}
+
+fn is_name_char(c: char) -> bool {
+    is_lowercase_name_char(c)
+        || c.is_ascii_uppercase()
+        || (('À'..='Þ').contains(&c) && c != '×')
+        || c.is_ascii_digit()
+        || c == '_'
+        || c == '@'
+}
+
+/// The reserved words of `erl_scan:reserved_word/1`, without those of optional language features
+fn is_reserved_word(name: &str) -> bool {
+    matches!(
+        name,
+        "after"
+            | "and"
+            | "andalso"
+            | "band"
+            | "begin"
+            | "bnot"
+            | "bor"
+            | "bsl"
+            | "bsr"
+            | "bxor"
+            | "case"
+            | "catch"
+            | "cond"
+            | "div"
+            | "end"
+            | "fun"
+            | "if"
+            | "let"
+            | "not"
+            | "of"
+            | "or"
+            | "orelse"
+            | "receive"
+            | "rem"
+            | "try"
+            | "when"
+            | "xor"
+    )
+}
+
+/// Writes `c` as it appears inside a quoted atom.
+///
+/// This matches `io_lib:write_string/2` when the encoding is `unicode`, except that `'` is the only
+/// quote character that needs escaping.
+///
+/// See `string_char/4` in https://github.com/erlang/otp/blob/master/lib/stdlib/src/io_lib.erl
+fn write_quoted_atom_char(f: &mut fmt::Formatter, c: char) -> fmt::Result {
+    use core::fmt::Write;
+
+    match c {
+        '\'' => f.write_str("\\'"),
+        '\\' => f.write_str("\\\\"),
+        ' '..='~' => f.write_char(c),
+        _ if '\u{A0}' <= c => f.write_char(c),
+        '\n' => f.write_str("\\n"),
+        '\r' => f.write_str("\\r"),
+        '\t' => f.write_str("\\t"),
+        '\u{B}' => f.write_str("\\v"),
+        '\u{8}' => f.write_str("\\b"),
+        '\u{C}' => f.write_str("\\f"),
+        '\u{1B}' => f.write_str("\\e"),
+        '\u{7F}' => f.write_str("\\d"),
+        // All the remaining characters are below 0o240, so fit in 3 octal digits
+        _ => write!(f, "\\{:03o}", c as u32),
+    }
+}
+
+/// Decodes `bytes` as UTF-8, decoding any invalid bytes as Latin-1 instead of rejecting them
+fn lossless_utf8_to_str(mut bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(name) = str::from_utf8(bytes) {
+        return Cow::Borrowed(name);
+    }
+
+    let mut name = String::with_capacity(bytes.len());
+
+    loop {
+        match str::from_utf8(bytes) {
+            Ok(valid) => {
+                name.push_str(valid);
+
+                break Cow::Owned(name);
+            }
+            Err(error) => {
+                let (valid, rest) = bytes.split_at(error.valid_up_to());
+                // SAFETY: `valid_up_to` is the length of the valid prefix
+                name.push_str(unsafe { str::from_utf8_unchecked(valid) });
+
+                let invalid_len = error.error_len().unwrap_or(rest.len());
+                let (invalid, rest) = rest.split_at(invalid_len);
+                name.extend(invalid.iter().map(|&b| b as char));
+                bytes = rest;
+            }
+        }
+    }
+}
+
+/// Decodes a Latin-1 byte slice, where every byte is the code point of the corresponding character
+fn latin1_to_str(bytes: &[u8]) -> Cow<'_, str> {
+    if bytes.is_ascii() {
+        // SAFETY: ASCII is a subset of UTF-8
+        Cow::Borrowed(unsafe { str::from_utf8_unchecked(bytes) })
+    } else {
+        Cow::Owned(bytes.iter().map(|&b| b as char).collect::<String>())
+    }
+}
 impl From<NonNull<AtomData>> for Atom {
     #[inline]
     fn from(ptr: NonNull<AtomData>) -> Self {
@@ -283,7 +430,7 @@ impl Ord for Atom {
 }
 impl Debug for Atom {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Atom({:p})", self.0)
+        write!(f, "Atom({})", self)
     }
 }
 impl fmt::Pointer for Atom {
@@ -292,35 +439,23 @@ impl fmt::Pointer for Atom {
     }
 }
 impl Display for Atom {
+    /// Writes the atom as `~p` would, quoting and escaping it only when necessary
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use core::fmt::Write;
-        let needs_quotes = self.needs_quotes();
 
-        if needs_quotes {
+        let name = self.display_name();
+
+        if name_needs_quotes(&name) {
             f.write_char('\'')?;
-        }
 
-        for c in self.as_str().chars() {
-            // https://github.com/erlang/otp/blob/dbf25321bdfdc3f4aae422b8ba2c0f31429eba61/erts/emulator/beam/erl_printf_term.c#L215-L232
-            match c {
-                '\'' => f.write_str("\\'")?,
-                '\\' => f.write_str("\\\\")?,
-                '\n' => f.write_str("\\n")?,
-                '\u{C}' => f.write_str("\\f")?,
-                '\t' => f.write_str("\\t")?,
-                '\r' => f.write_str("\\r")?,
-                '\u{8}' => f.write_str("\\b")?,
-                '\u{B}' => f.write_str("\\v")?,
-                _ if c.is_control() => write!(f, "\\{:o}", c as u8)?,
-                _ => f.write_char(c)?,
+            for c in name.chars() {
+                write_quoted_atom_char(f, c)?;
             }
-        }
 
-        if needs_quotes {
-            f.write_char('\'')?;
+            f.write_char('\'')
+        } else {
+            f.write_str(&name)
         }
-
-        Ok(())
     }
 }
 
@@ -336,3 +471,117 @@ pub unsafe extern "C-unwind" fn atom_from_cstr(ptr: *const core::ffi::c_char) ->
     let atom = Atom::from_raw_cstr(ptr);
     atom.into()
 }
+
+/// This is an intrinsic expected by the compiler to be defined as part of the runtime, and is used
+/// to materialize the literal atom at `index` in the atom table of a module, see `ModuleAtoms`.
+#[export_name = "__firefly_module_atom"]
+pub unsafe extern "C" fn module_atom(module_atoms: *const ModuleAtoms, index: usize) -> OpaqueTerm {
+    (*module_atoms).get(index).into()
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::boxed::Box;
+    use alloc::format;
+
+    use super::*;
+
+    #[test]
+    fn atoms_are_limited_to_255_characters() {
+        let longest = "a".repeat(MAX_ATOM_LENGTH);
+        assert!(Atom::try_from(longest.as_str()).is_ok());
+
+        let too_long = "a".repeat(MAX_ATOM_LENGTH + 1);
+        assert_eq!(
+            Atom::try_from(too_long.as_str()),
+            Err(AtomError::InvalidLength(MAX_ATOM_LENGTH + 1))
+        );
+    }
+
+    #[test]
+    fn atom_length_limit_counts_characters_not_bytes() {
+        // Each of these characters is 2 bytes in UTF-8
+        let name = "é".repeat(MAX_ATOM_LENGTH);
+        let atom = Atom::try_from(name.as_str()).unwrap();
+        assert_eq!(atom.as_str(), name);
+    }
+
+    #[test]
+    fn latin1_bytes_are_decoded_as_code_points() {
+        let atom = Atom::try_from_latin1_bytes(b"caf\xe9").unwrap();
+        assert_eq!(atom.as_str(), "café");
+        assert_eq!(Atom::try_from_latin1_bytes_existing(b"caf\xe9"), Ok(atom));
+    }
+
+    #[test]
+    fn existing_lookup_does_not_insert() {
+        let name = "atom_test_existing_lookup_does_not_insert";
+        assert_eq!(
+            Atom::try_from_str_existing(name),
+            Err(AtomError::NonExistent)
+        );
+        assert_eq!(
+            Atom::try_from_str_existing(name),
+            Err(AtomError::NonExistent)
+        );
+
+        let atom = Atom::try_from(name).unwrap();
+        assert_eq!(
+            Atom::try_from_utf8_bytes_existing(name.as_bytes()),
+            Ok(atom)
+        );
+    }
+
+    #[test]
+    fn display_matches_io_lib_p() {
+        // Each name with what `io:format("~p", [list_to_atom(Name)])` prints for it
+        let cases = [
+            ("ok", "ok"),
+            ("EXIT", "'EXIT'"),
+            ("hello world", "'hello world'"),
+            ("", "''"),
+            ("with'quote", "'with\\'quote'"),
+            ("back\\slash", "'back\\\\slash'"),
+            ("ólaf", "ólaf"),
+            ("Ólaf", "'Ólaf'"),
+            ("line\nfeed", "'line\\nfeed'"),
+            ("bell\u{7}", "'bell\\007'"),
+            ("delete\u{7F}", "'delete\\d'"),
+            ("node@host", "node@host"),
+            ("snake_case2", "snake_case2"),
+            ("end", "'end'"),
+            ("endless", "endless"),
+            ("true", "true"),
+        ];
+
+        for (name, expected) in cases {
+            let atom = Atom::try_from(name).unwrap();
+            assert_eq!(format!("{}", atom), expected, "atom named {:?}", name);
+        }
+    }
+
+    #[test]
+    fn display_prints_unicode_code_points_as_is() {
+        let atom = Atom::try_from("λx").unwrap();
+        assert_eq!(format!("{}", atom), "'λx'");
+    }
+
+    #[test]
+    fn display_decodes_invalid_utf8_as_latin1() {
+        let name: &'static [u8] = b"caf\xe9 au lait";
+        let data: &'static AtomData = Box::leak(Box::new(AtomData {
+            size: name.len(),
+            ptr: name.as_ptr(),
+        }));
+        let atom = Atom(data as *const AtomData);
+
+        assert_eq!(format!("{}", atom), "'café au lait'");
+        assert!(atom.needs_quotes());
+    }
+
+    #[test]
+    fn debug_includes_display() {
+        let atom = Atom::try_from("hello world").unwrap();
+        assert_eq!(format!("{:?}", atom), "Atom('hello world')");
+    }
+}
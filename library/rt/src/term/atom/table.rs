@@ -11,6 +11,9 @@ use lazy_static::lazy_static;
 use firefly_arena::DroplessArena;
 use firefly_system::sync::RwLock;
 
+use crate::memory::Category;
+
+use super::atoms::PREINTERNED;
 use super::{Atom, AtomError};
 
 lazy_static! {
@@ -53,6 +56,46 @@ impl AtomData {
     }
 }
 
+/// This struct matches the layout of the table of atoms the compiler generates for each module.
+///
+/// The tables of all modules are gathered by the linker in the `__module_atoms` section. Generated
+/// code materializes the literal atom at `index` by loading `atoms[index]`, which is filled in at
+/// startup with the interned atom for `names[index]`, so no lookup is needed at runtime.
+#[derive(Debug)]
+#[repr(C)]
+pub struct ModuleAtoms {
+    len: usize,
+    names: *const *const AtomData,
+    atoms: *mut *const AtomData,
+}
+// The names are read-only, and the atoms are only written while holding the atom table lock,
+// before any process runs
+unsafe impl Send for ModuleAtoms {}
+unsafe impl Sync for ModuleAtoms {}
+impl ModuleAtoms {
+    /// Returns the atom at `index` in this table
+    ///
+    /// # Safety
+    ///
+    /// The table must have been initialized by `__firefly_initialize_module_atom_tables`
+    #[inline]
+    pub unsafe fn get(&self, index: usize) -> Atom {
+        debug_assert!(index < self.len, "module atom index out of bounds");
+        Atom(*self.atoms.add(index))
+    }
+}
+
+// Ensures the `__module_atoms` section exists, so that its bounds are defined even when no
+// module in the program references an atom
+#[used]
+#[cfg_attr(target_os = "macos", link_section = "__DATA,__module_atoms")]
+#[cfg_attr(all(unix, not(target_os = "macos")), link_section = "__module_atoms")]
+static EMPTY_MODULE_ATOMS: ModuleAtoms = ModuleAtoms {
+    len: 0,
+    names: ptr::null(),
+    atoms: ptr::null_mut(),
+};
+
 /// Performs one-time initialization of the atom table at program start, using the
 /// array of constant atom values present in the compiled program.
 ///
@@ -81,6 +124,38 @@ pub unsafe extern "C-unwind" fn init(start: *const AtomData, end: *const AtomDat
     true
 }
 
+/// Interns the atoms of each of the module atom tables present in the compiled program, and fills
+/// in their mapping arrays, see `ModuleAtoms`.
+///
+/// The tables are walked in the order the linker laid them out, and the atoms of each table in
+/// order, so the first record for a given name becomes the interned atom, unless the atom was
+/// already interned by `__firefly_initialize_atom_table`, which must be called first.
+#[export_name = "__firefly_initialize_module_atom_tables"]
+pub unsafe extern "C-unwind" fn init_module_tables(
+    start: *const ModuleAtoms,
+    end: *const ModuleAtoms,
+) -> bool {
+    if start == end {
+        return true;
+    }
+    if start.is_null() || end.is_null() {
+        return false;
+    }
+
+    debug_assert_eq!(
+        ((end as usize) - (start as usize)) % mem::size_of::<ModuleAtoms>(),
+        0,
+        "invalid module atom table range"
+    );
+    let len = end.offset_from(start);
+    let tables = slice::from_raw_parts::<'static, _>(start, len as usize);
+
+    let mut default_table = ATOMS.write();
+    tables
+        .iter()
+        .all(|module_atoms| default_table.extend_module(module_atoms).is_ok())
+}
+
 /// Like `get_data_or_insert`, but optimized for the case where the given atom value has static lifetime,
 /// and thus doesn't require allocating space for and cloning the value. This is faster in that regard, but
 /// still has all of the downsides that come with acquiring a write lock on the atom table.
@@ -111,6 +186,12 @@ pub(super) fn get_data(name: &str) -> Option<NonNull<AtomData>> {
     ATOMS.read().get_data(name)
 }
 
+/// Returns the number of atoms in the global atom table, unless it is being written to, for when
+/// blocking could deadlock, such as in crash dumps
+pub fn try_atom_count() -> Option<usize> {
+    ATOMS.try_read().map(|table| table.ids.len())
+}
+
 /// This struct represents the atom table, of which a program will only ever have one at a time,
 /// with static lifetime. The atoms it contains are never collected.
 struct AtomTable {
@@ -127,10 +208,14 @@ unsafe impl Send for AtomTable {}
 unsafe impl Sync for AtomTable {}
 impl Default for AtomTable {
     fn default() -> Self {
-        Self {
+        let mut table = Self {
             ids: HashMap::with_capacity(100),
             arena: DroplessArena::default(),
-        }
+        };
+        // The atoms the runtime refers to statically, including those with fixed ids, are always
+        // interned, even when the program does not reference them
+        table.extend(PREINTERNED.iter().copied());
+        table
     }
 }
 impl fmt::Debug for AtomTable {
@@ -143,7 +228,7 @@ impl fmt::Debug for AtomTable {
     }
 }
 impl AtomTable {
-    fn extend(&mut self, data: &'static [AtomData]) {
+    fn extend<I: IntoIterator<Item = &'static AtomData>>(&mut self, data: I) {
         for atom in data {
             let ptr = unsafe { NonNull::new_unchecked(atom as *const AtomData as *mut AtomData) };
             let name = unsafe { atom.as_str().unwrap() };
@@ -151,6 +236,19 @@ impl AtomTable {
         }
     }
 
+    // SAFETY: `module_atoms` must be a table generated by the compiler, or have the same invariants
+    unsafe fn extend_module(&mut self, module_atoms: &ModuleAtoms) -> Result<(), AtomError> {
+        for index in 0..module_atoms.len {
+            let data = *module_atoms.names.add(index);
+            let name = str::from_utf8((*data).as_bytes())?;
+            let ptr = NonNull::new_unchecked(data as *mut AtomData);
+            let interned = *self.ids.entry(name).or_insert(ptr);
+            module_atoms.atoms.add(index).write(interned.as_ptr());
+        }
+
+        Ok(())
+    }
+
     fn get_data(&self, name: &str) -> Option<NonNull<AtomData>> {
         self.ids.get(name).copied()
     }
@@ -221,6 +319,7 @@ impl AtomTable {
             .unwrap();
         let layout = layout.pad_to_align();
         let ptr = self.arena.alloc_raw(layout);
+        Category::Atom.allocated(layout.size());
 
         let value_ptr = ptr.add(value_offset);
         let data_ptr: *mut AtomData = ptr.cast();
@@ -245,7 +344,129 @@ impl AtomTable {
 
         let ptr = self.arena.alloc_raw(layout) as *mut AtomData;
         ptr.write(data);
+        Category::Atom.allocated(layout.size());
 
         NonNull::new_unchecked(ptr)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use alloc::boxed::Box;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::term::atom::module_atom;
+    use crate::term::{atoms, OpaqueTerm};
+
+    #[test]
+    fn modules_with_overlapping_atoms_share_one_atom_per_name() {
+        let tables = leak_tables(vec![
+            module_atoms(&[b"module_atoms_shared", b"module_atoms_only_first"]),
+            module_atoms(&[b"module_atoms_only_second", b"module_atoms_shared"]),
+        ]);
+        assert!(init(tables));
+
+        let shared = Atom::try_from_str_existing("module_atoms_shared").unwrap();
+        unsafe {
+            assert_eq!(tables[0].get(0), shared);
+            assert_eq!(tables[1].get(1), shared);
+            // The first record for a name is the one interned
+            assert_eq!(shared.as_ptr(), *tables[0].names);
+
+            assert_eq!(
+                tables[0].get(1),
+                Atom::try_from("module_atoms_only_first").unwrap()
+            );
+            assert_eq!(
+                tables[1].get(0),
+                Atom::try_from("module_atoms_only_second").unwrap()
+            );
+            assert_ne!(tables[0].get(1), tables[1].get(0));
+
+            assert_eq!(module_atom(&tables[1], 1), OpaqueTerm::from(shared));
+        }
+    }
+
+    #[test]
+    fn literal_atoms_are_equal_to_runtime_atoms_of_the_same_name() {
+        let created_first = Atom::try_from("module_atoms_created_first").unwrap();
+        let tables = leak_tables(vec![module_atoms(&[
+            b"ok",
+            b"normal",
+            b"module_atoms_created_first",
+            b"module_atoms_created_later",
+        ])]);
+        assert!(init(tables));
+
+        unsafe {
+            assert_eq!(tables[0].get(0), atoms::Ok);
+            assert_eq!(tables[0].get(0), Atom::try_from("ok").unwrap());
+            assert_eq!(tables[0].get(1), atoms::Normal);
+            assert_eq!(tables[0].get(2), created_first);
+            assert_eq!(
+                tables[0].get(3),
+                Atom::try_from("module_atoms_created_later").unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn module_with_invalid_utf8_atom_fails_initialization() {
+        let tables = leak_tables(vec![module_atoms(&[b"module_atoms_\xff"])]);
+
+        assert!(!init(tables));
+    }
+
+    #[test]
+    fn core_atoms_have_the_ids_fixed_by_the_compiler() {
+        let expected = ["false", "true", "ok", "error", "undefined"];
+
+        assert_eq!(atoms::FIXED.len(), expected.len());
+        for (id, name) in expected.iter().enumerate() {
+            assert_eq!(
+                atoms::FIXED[id],
+                Atom::try_from(*name).unwrap(),
+                "id {}",
+                id
+            );
+            assert_eq!(atoms::FIXED[id].as_str(), *name);
+        }
+        assert_eq!(atoms::FIXED[2], atoms::Ok);
+        assert_eq!(atoms::FIXED[3], atoms::Error);
+        assert_eq!(atoms::FIXED[4], atoms::Undefined);
+    }
+
+    /// Builds a table as generated by the compiler, with its own record for each of `names`, as
+    /// when the linker has not merged the records of different modules
+    fn module_atoms(names: &[&'static [u8]]) -> ModuleAtoms {
+        let names: Vec<*const AtomData> = names
+            .iter()
+            .map(|name| {
+                let data: &'static AtomData = Box::leak(Box::new(AtomData {
+                    size: name.len(),
+                    ptr: name.as_ptr(),
+                }));
+                data as *const AtomData
+            })
+            .collect();
+        let atoms = vec![ptr::null(); names.len()];
+
+        ModuleAtoms {
+            len: names.len(),
+            names: Box::leak(names.into_boxed_slice()).as_ptr(),
+            atoms: Box::leak(atoms.into_boxed_slice()).as_mut_ptr(),
+        }
+    }
+
+    fn leak_tables(tables: Vec<ModuleAtoms>) -> &'static [ModuleAtoms] {
+        Box::leak(tables.into_boxed_slice())
+    }
+
+    fn init(tables: &'static [ModuleAtoms]) -> bool {
+        let range = tables.as_ptr_range();
+
+        unsafe { init_module_tables(range.start, range.end) }
+    }
+}
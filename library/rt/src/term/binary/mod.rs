@@ -4,12 +4,13 @@ mod slice;
 pub use self::matching::{MatchContext, MatchResult};
 pub use self::slice::BitSlice;
 
-use alloc::alloc::{AllocError, Allocator};
+use alloc::alloc::{AllocError, Allocator, Global};
 use alloc::borrow::Cow;
 use alloc::string::String;
 use core::any::TypeId;
 use core::fmt;
 use core::hash::{Hash, Hasher};
+use core::mem;
 use core::ops::{Index, IndexMut};
 use core::slice::SliceIndex;
 
@@ -17,6 +18,8 @@ use firefly_alloc::gc::GcBox;
 use firefly_alloc::rc::Rc;
 use firefly_binary::{Aligned, Binary, BinaryFlags, Bitstring, Encoding};
 
+use crate::memory::Category;
+
 /// This represents binary data, i.e. byte-aligned, with a number of bits
 /// divisible by 8 evenly.
 #[repr(C, align(16))]
@@ -77,7 +80,7 @@ impl BinaryData {
     /// NOTE: This function always allocates via Rc, even if the binary is smaller than 64 bytes.
     pub fn from_str(s: &str) -> Rc<BinaryData> {
         let bytes = s.as_bytes();
-        let mut rcbox = Rc::<BinaryData>::with_capacity(bytes.len());
+        let mut rcbox = Self::rc_with_capacity_in(bytes.len(), Global).unwrap();
         {
             let value = unsafe { Rc::get_mut_unchecked(&mut rcbox) };
             value.flags = BinaryFlags::new(bytes.len(), Encoding::Utf8);
@@ -103,7 +106,7 @@ impl BinaryData {
         alloc: A,
     ) -> Result<Rc<BinaryData>, AllocError> {
         assert!(cap > 64);
-        let mut rcbox = Rc::<BinaryData>::with_capacity_in(cap, alloc)?;
+        let mut rcbox = Self::rc_with_capacity_in(cap, alloc)?;
         {
             let value = unsafe { Rc::get_mut_unchecked(&mut rcbox) };
             value.flags = BinaryFlags::new(cap, Encoding::Raw);
@@ -111,6 +114,19 @@ impl BinaryData {
         Ok(rcbox)
     }
 
+    /// Allocates an Rc<BinaryData> with room for `cap` bytes, whose flags must be set by the caller
+    ///
+    /// All reference-counted binaries must be allocated by this function, so that they are counted
+    /// as binary memory until they are dropped.
+    pub(crate) fn rc_with_capacity_in<A: Allocator>(
+        cap: usize,
+        alloc: A,
+    ) -> Result<Rc<BinaryData>, AllocError> {
+        let rcbox = Rc::<BinaryData>::with_capacity_in(cap, alloc)?;
+        Category::Binary.allocated(mem::size_of_val::<BinaryData>(&rcbox));
+        Ok(rcbox)
+    }
+
     /// Constructs an Rc<BinaryData> from the given byte slice.
     ///
     /// The encoding of the given data is detected by examining the bytes. If you
@@ -132,7 +148,7 @@ impl BinaryData {
     /// the given bytes are valid for the specified encoding, preferably by having run validation
     /// checks in a previous step.
     pub unsafe fn from_bytes_with_encoding(bytes: &[u8], encoding: Encoding) -> Rc<BinaryData> {
-        let mut rcbox = Rc::<BinaryData>::with_capacity(bytes.len());
+        let mut rcbox = Self::rc_with_capacity_in(bytes.len(), Global).unwrap();
         {
             let value = Rc::get_mut_unchecked(&mut rcbox);
             value.flags = BinaryFlags::new(bytes.len(), encoding);
@@ -141,6 +157,13 @@ impl BinaryData {
         rcbox
     }
 }
+impl Drop for BinaryData {
+    fn drop(&mut self) {
+        // Binaries on process heaps and literals are never dropped, only those allocated by
+        // `rc_with_capacity_in` are, when their last reference is
+        Category::Binary.deallocated(mem::size_of_val(self));
+    }
+}
 impl fmt::Debug for BinaryData {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self)
@@ -149,7 +172,7 @@ impl fmt::Debug for BinaryData {
 impl fmt::Display for BinaryData {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        firefly_binary::helpers::display_binary(self, f)
+        firefly_binary::helpers::display_bitstring(self, f)
     }
 }
 impl Eq for BinaryData {}
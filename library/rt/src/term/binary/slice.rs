@@ -4,7 +4,7 @@ use core::hash::{Hash, Hasher};
 
 use firefly_binary::{Bitstring, Selection};
 
-use crate::term::OpaqueTerm;
+use crate::term::{OpaqueTerm, Term};
 
 /// A slice of another binary or bitstring value
 #[repr(C)]
@@ -50,6 +50,52 @@ impl BitSlice {
         Self { owner, selection }
     }
 
+    /// Create a BitSlice covering `length` bytes of the binary `term` starting at byte `start`
+    ///
+    /// A negative `length` covers the bytes preceding `start` instead, as in `binary_part/3`.
+    /// Returns `None` if `term` is not a binary, or the range is not contained in it.
+    ///
+    /// No bytes are copied; if `term` is itself a slice, the result borrows from its owner.
+    pub fn binary_part(term: OpaqueTerm, start: usize, length: isize) -> Option<Self> {
+        let t: Term = term.into();
+        let data = t.as_bitstring()?;
+        if !data.is_binary() {
+            return None;
+        }
+
+        let len = length.unsigned_abs();
+        let start = if length < 0 {
+            start.checked_sub(len)?
+        } else {
+            start
+        };
+        if start.checked_add(len)? > data.bit_size() / 8 {
+            return None;
+        }
+
+        let owner = match &t {
+            Term::RefBinary(slice) => slice.owner,
+            _ => term,
+        };
+        let bit_offset = data.bit_offset();
+        let bitsize = bit_offset as usize + data.bit_size();
+        let selection = unsafe {
+            let bytes = core::mem::transmute::<_, &'static [u8]>(data.as_bytes_unchecked());
+            Selection::new(bytes, start, bit_offset, Some(bitsize), len * 8).ok()?
+        };
+
+        // The new slice holds a reference to the owner, which is released on drop
+        owner.maybe_increment_refcount();
+
+        Some(Self { owner, selection })
+    }
+
+    /// Returns the term this slice borrows its data from
+    #[inline]
+    pub fn owner(&self) -> OpaqueTerm {
+        self.owner
+    }
+
     /// Returns the selection represented by this slice
     #[inline]
     pub fn as_selection(&self) -> Selection<'static> {
@@ -132,6 +178,104 @@ impl Hash for BitSlice {
 }
 impl fmt::Display for BitSlice {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", &self.selection)
+        firefly_binary::helpers::display_bitstring(self, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    use firefly_alloc::gc::GcBox;
+    use firefly_alloc::rc::Rc;
+
+    use crate::process::Process;
+    use crate::term::{BinaryData, ProcessId};
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn bytes(slice: &BitSlice) -> &[u8] {
+        unsafe { slice.as_bytes_unchecked() }
+    }
+
+    #[test]
+    fn binary_part_of_refc_binary_does_not_copy() {
+        let data: Vec<u8> = (0..100).collect();
+        let rc = BinaryData::from_bytes(&data);
+        let term: OpaqueTerm = Rc::clone(&rc).into();
+        assert_eq!(Rc::strong_count(&rc), 2);
+
+        let slice = BitSlice::binary_part(term, 10, 80).unwrap();
+        assert_eq!(slice.owner(), term);
+        assert_eq!(bytes(&slice), &data[10..90]);
+        assert_eq!(bytes(&slice).as_ptr(), unsafe {
+            rc.as_bytes_unchecked().as_ptr().add(10)
+        });
+        assert_eq!(Rc::strong_count(&rc), 3);
+
+        drop(slice);
+        assert_eq!(Rc::strong_count(&rc), 2);
+    }
+
+    #[test]
+    fn binary_part_with_negative_length_covers_preceding_bytes() {
+        let term: OpaqueTerm = BinaryData::from_bytes(&[1, 2, 3, 4, 5]).into();
+
+        let slice = BitSlice::binary_part(term, 4, -3).unwrap();
+        assert_eq!(bytes(&slice), &[2, 3, 4]);
+
+        let slice = BitSlice::binary_part(term, 5, -5).unwrap();
+        assert_eq!(bytes(&slice), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn binary_part_of_slice_borrows_from_original_owner() {
+        let process = process();
+        let term: OpaqueTerm = BinaryData::from_bytes(&[1, 2, 3, 4, 5]).into();
+        let outer = BitSlice::binary_part(term, 1, 4).unwrap();
+        let outer: OpaqueTerm = GcBox::new_in(outer, &process).unwrap().into();
+
+        let inner = BitSlice::binary_part(outer, 1, 2).unwrap();
+        assert_eq!(inner.owner(), term);
+        assert_eq!(bytes(&inner), &[3, 4]);
+    }
+
+    #[test]
+    fn binary_part_of_unaligned_binary() {
+        static DATA: [u8; 3] = [0x01, 0x23, 0x45];
+        let process = process();
+        // The 16 bits starting 4 bits into DATA, i.e. <<16#12, 16#34>>
+        let unaligned = unsafe { BitSlice::new(OpaqueTerm::NONE, &DATA, 4, 16) };
+        let unaligned: OpaqueTerm = GcBox::new_in(unaligned, &process).unwrap().into();
+
+        let slice = BitSlice::binary_part(unaligned, 1, 1).unwrap();
+        assert_eq!(slice.as_selection().to_bytes().as_ref(), &[0x34]);
+    }
+
+    #[test]
+    fn binary_part_out_of_range_is_none() {
+        let term: OpaqueTerm = BinaryData::from_bytes(&[1, 2, 3]).into();
+
+        assert!(BitSlice::binary_part(term, 0, 3).is_some());
+        assert!(BitSlice::binary_part(term, 3, 0).is_some());
+        assert!(BitSlice::binary_part(term, 0, 4).is_none());
+        assert!(BitSlice::binary_part(term, 4, 0).is_none());
+        assert!(BitSlice::binary_part(term, 2, -3).is_none());
+        assert!(BitSlice::binary_part(term, usize::MAX, 1).is_none());
+    }
+
+    #[test]
+    fn binary_part_of_non_binary_is_none() {
+        static DATA: [u8; 1] = [0xff];
+        let process = process();
+        let bits = unsafe { BitSlice::new(OpaqueTerm::NONE, &DATA, 0, 7) };
+        let bits: OpaqueTerm = GcBox::new_in(bits, &process).unwrap().into();
+
+        assert!(BitSlice::binary_part(bits, 0, 0).is_none());
+        assert!(BitSlice::binary_part(Term::Int(1).into(), 0, 0).is_none());
     }
 }
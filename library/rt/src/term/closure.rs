@@ -8,9 +8,9 @@ use seq_macro::seq;
 
 use firefly_alloc::gc::GcBox;
 
-use crate::function::ErlangResult;
+use crate::function::{self, ErlangResult};
 
-use super::{Atom, OpaqueTerm};
+use super::{phash2, Atom, OpaqueTerm, Term};
 
 /// This struct unifies function captures and closures under a single type.
 ///
@@ -43,8 +43,13 @@ impl fmt::Debug for Closure {
     }
 }
 impl fmt::Display for Closure {
+    /// Writes the closure as BEAM does, i.e. `#Fun<Module.Index.Uniq>`
+    ///
+    /// There is no hash of the code of the fun to use as its unique number, so the hash of its
+    /// name is used instead, which is just as stable between runs.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "#Fun<{}:{}/{}>", self.module, self.name, self.arity)
+        let uniq = phash2(Term::Atom(self.name));
+        write!(f, "#Fun<{}.{}.{}>", self.module, self.index(), uniq)
     }
 }
 impl Closure {
@@ -89,6 +94,19 @@ impl Closure {
         self.env.len() == 0
     }
 
+    /// Returns the number of arguments this closure is called with, as reported by `erlang:fun_info/2`
+    ///
+    /// This differs from `arity` for closures with a non-empty environment, as their callee
+    /// also receives the closure itself as an extra argument.
+    #[inline]
+    pub fn effective_arity(&self) -> usize {
+        if self.is_thin() {
+            self.arity
+        } else {
+            self.arity - 1
+        }
+    }
+
     /// Returns the size of the environment (in units of `OpaqueTerm`) bound to this closure
     #[inline]
     pub fn env_size(&self) -> usize {
@@ -99,6 +117,16 @@ impl Closure {
         &self.env
     }
 
+    /// Returns the index of this fun among those defined in the same function, as given by the
+    /// `-fun-N-` suffix of the names of lambdas, e.g. `-main/0-fun-1-`, or 0 for captures
+    pub fn index(&self) -> usize {
+        let name = self.name.as_str();
+        name.strip_suffix('-')
+            .and_then(|name| name.rsplit_once("-fun-"))
+            .and_then(|(_, index)| index.parse().ok())
+            .unwrap_or(0)
+    }
+
     pub fn callee(&self) -> *const () {
         self.fun
     }
@@ -116,8 +144,8 @@ impl Closure {
     /// This function will panic if the number of arguments given does not match
     /// the arity of the closure.
     ///
-    /// NOTE: Currently, a max arity of 10 is supported for dynamic apply via this function.
-    /// If the number of arguments exceeds this number, this function will panic.
+    /// NOTE: Closures of arity less than 10 are called directly, larger ones are called via
+    /// the same dynamic call path as `erlang:apply/2`, which supports any arity up to `MAX_ARITY`.
     #[inline]
     pub fn apply(&self, args: &[OpaqueTerm]) -> ErlangResult {
         seq!(N in 0..10 {
@@ -125,7 +153,7 @@ impl Closure {
                 #(
                     N => apply~N(self, args),
                 )*
-                n => panic!("apply failed: too many arguments, got {}, expected no more than 10", n),
+                _ => function::apply_closure(self, args).expect("mismatched arity"),
             }
         })
     }
@@ -252,3 +280,38 @@ impl Hash for Closure {
         ptr::hash(self.fun, state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::process::Process;
+    use crate::term::ProcessId;
+
+    extern "C" fn callee() {}
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    #[test]
+    fn effective_arity_of_capture_is_declared_arity() {
+        let process = process();
+        let module = Atom::try_from("closure_test").unwrap();
+        let fun = Closure::new_in(module, module, 2, callee as *const (), &[], &process).unwrap();
+
+        assert_eq!(fun.arity, 2);
+        assert_eq!(fun.effective_arity(), 2);
+    }
+
+    #[test]
+    fn effective_arity_of_closure_excludes_closure_argument() {
+        let process = process();
+        let module = Atom::try_from("closure_test").unwrap();
+        let env = [OpaqueTerm::NIL, OpaqueTerm::NIL];
+        // The callee of a 2-ary closure takes the closure as a third argument
+        let fun = Closure::new_in(module, module, 3, callee as *const (), &env, &process).unwrap();
+
+        assert_eq!(fun.effective_arity(), 2);
+    }
+}
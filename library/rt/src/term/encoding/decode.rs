@@ -0,0 +1,870 @@
+use alloc::alloc::AllocError;
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::str;
+
+use firefly_alloc::gc::GcBox;
+use firefly_alloc::heap::Heap;
+use firefly_binary::Bitstring;
+use firefly_number::{BigInt, Sign, ToPrimitive};
+
+use crate::term::{
+    atoms, Atom, AtomError, BinaryData, BitSlice, Cons, ListBuilder, Map, OpaqueTerm, Pid,
+    Reference, ReferenceId, Term, Tuple,
+};
+
+use super::{tag, VERSION_NUMBER};
+
+/// Produced when a binary cannot be decoded from the external term format
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input is not a valid encoding of a term, e.g. it is truncated, contains an
+    /// unknown tag, or has trailing bytes
+    Invalid,
+    /// Decoding would have created a new atom, which the `safe` option forbids
+    Unsafe,
+    /// The decoded term could not be allocated
+    AllocError,
+}
+impl From<AllocError> for DecodeError {
+    #[inline]
+    fn from(_: AllocError) -> Self {
+        Self::AllocError
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Invalid => f.write_str("invalid external term format"),
+            Self::Unsafe => f.write_str("decoding would create a new atom"),
+            Self::AllocError => f.write_str("unable to allocate memory for decoded term"),
+        }
+    }
+}
+
+/// The options accepted by `binary_to_term/2`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// When set, decoding fails rather than creating atoms which do not already exist
+    pub safe: bool,
+}
+
+/// Decodes a term in the external term format from `bytes`, allocating it on `heap`.
+///
+/// The input must consist of exactly one encoded term. Malformed input is always reported
+/// as an error, and terms of arbitrary depth can be decoded, as nested values are built using
+/// an explicit stack rather than by recursion.
+pub fn decode<H: Heap>(
+    bytes: &[u8],
+    options: DecodeOptions,
+    heap: &H,
+) -> Result<Term, DecodeError> {
+    let [VERSION_NUMBER, rest @ ..] = bytes else {
+        return Err(DecodeError::Invalid);
+    };
+
+    if let [tag::COMPRESSED, rest @ ..] = rest {
+        let mut reader = Reader { bytes: rest };
+        let size = reader.u32()? as usize;
+        let inflated = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(reader.bytes, size)
+            .map_err(|_| DecodeError::Invalid)?;
+        if inflated.len() != size {
+            return Err(DecodeError::Invalid);
+        }
+        return Decoder::new(&inflated, options, heap).decode();
+    }
+
+    Decoder::new(rest, options, heap).decode()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> Reader<'a> {
+    #[inline]
+    fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if len > self.bytes.len() {
+            return Err(DecodeError::Invalid);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Reads a length of `count` items, each of which is encoded using at least `min_size` bytes
+    ///
+    /// This rejects lengths which could not possibly be satisfied by the remaining input, so that
+    /// we never allocate based on a bogus length.
+    fn check_len(&self, count: usize, min_size: usize) -> Result<usize, DecodeError> {
+        match count.checked_mul(min_size) {
+            Some(size) if size <= self.remaining() => Ok(count),
+            _ => Err(DecodeError::Invalid),
+        }
+    }
+}
+
+/// A container which is waiting on some of its elements to be decoded
+enum Frame {
+    Tuple {
+        elements: Vec<OpaqueTerm>,
+        arity: usize,
+    },
+    /// The last value collected is the tail of the list
+    List { elements: Vec<Term>, len: usize },
+    /// Keys and values alternate
+    Map { items: Vec<Term>, arity: usize },
+}
+impl Frame {
+    /// Adds `value` to this frame, returning true if the frame is complete
+    fn push(&mut self, value: Term) -> bool {
+        match self {
+            Self::Tuple { elements, arity } => {
+                elements.push(value.into());
+                elements.len() == *arity
+            }
+            Self::List { elements, len } => {
+                elements.push(value);
+                elements.len() == *len + 1
+            }
+            Self::Map { items, arity } => {
+                items.push(value);
+                items.len() == *arity * 2
+            }
+        }
+    }
+
+    fn finish<H: Heap>(self, heap: &H) -> Result<Term, DecodeError> {
+        match self {
+            Self::Tuple { elements, .. } => Ok(Tuple::from_slice(&elements, heap)?.into()),
+            Self::List { mut elements, .. } => {
+                let mut tail = elements.pop().unwrap();
+                for element in elements.into_iter().rev() {
+                    let cons = Cons::new_in(heap)?;
+                    unsafe {
+                        cons.as_uninit_mut().write(Cons::cons(element, tail));
+                    }
+                    tail = Term::Cons(cons);
+                }
+                Ok(tail)
+            }
+            Self::Map { items, arity } => {
+                let pairs = items.chunks_exact(2).map(|pair| (pair[0], pair[1]));
+                let map = Map::new_from_iter(pairs);
+                // Duplicate keys are not permitted
+                if map.size() != arity {
+                    return Err(DecodeError::Invalid);
+                }
+                Ok(GcBox::new_in(map, heap)?.into())
+            }
+        }
+    }
+}
+
+/// The result of decoding the next tag in the input
+enum Next {
+    Value(Term),
+    Frame(Frame),
+}
+
+struct Decoder<'a, 'h, H: Heap> {
+    reader: Reader<'a>,
+    options: DecodeOptions,
+    heap: &'h H,
+}
+impl<'a, 'h, H: Heap> Decoder<'a, 'h, H> {
+    fn new(bytes: &'a [u8], options: DecodeOptions, heap: &'h H) -> Self {
+        Self {
+            reader: Reader { bytes },
+            options,
+            heap,
+        }
+    }
+
+    fn decode(mut self) -> Result<Term, DecodeError> {
+        let mut stack: Vec<Frame> = Vec::new();
+
+        loop {
+            let mut value = match self.next()? {
+                Next::Value(value) => value,
+                Next::Frame(frame) => {
+                    stack.push(frame);
+                    continue;
+                }
+            };
+
+            // Feed the value to the innermost container, completing as many as we can
+            loop {
+                let Some(frame) = stack.last_mut() else {
+                    // There must be nothing left over once the outermost term is decoded
+                    if self.reader.remaining() > 0 {
+                        return Err(DecodeError::Invalid);
+                    }
+                    return Ok(value);
+                };
+                if !frame.push(value) {
+                    break;
+                }
+                value = stack.pop().unwrap().finish(self.heap)?;
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<Next, DecodeError> {
+        let value = match self.reader.u8()? {
+            tag::SMALL_INTEGER_EXT => Term::Int(self.reader.u8()? as i64),
+            tag::INTEGER_EXT => Term::Int(self.reader.u32()? as i32 as i64),
+            tag::SMALL_BIG_EXT => {
+                let len = self.reader.u8()? as usize;
+                self.big_integer(len)?
+            }
+            tag::LARGE_BIG_EXT => {
+                let len = self.reader.u32()? as usize;
+                self.big_integer(len)?
+            }
+            tag::NEW_FLOAT_EXT => {
+                let bits = self.reader.u64()?;
+                self.float(f64::from_bits(bits))?
+            }
+            tag::FLOAT_EXT => {
+                let text = self.reader.take(31)?;
+                let len = text.iter().position(|b| *b == 0).unwrap_or(text.len());
+                let text = str::from_utf8(&text[..len]).map_err(|_| DecodeError::Invalid)?;
+                self.float(text.parse().map_err(|_| DecodeError::Invalid)?)?
+            }
+            tag::ATOM_EXT => {
+                let len = self.reader.u16()? as usize;
+                let name = self.reader.take(len)?;
+                self.atom(name, false)?
+            }
+            tag::SMALL_ATOM_EXT => {
+                let len = self.reader.u8()? as usize;
+                let name = self.reader.take(len)?;
+                self.atom(name, false)?
+            }
+            tag::ATOM_UTF8_EXT => {
+                let len = self.reader.u16()? as usize;
+                let name = self.reader.take(len)?;
+                self.atom(name, true)?
+            }
+            tag::SMALL_ATOM_UTF8_EXT => {
+                let len = self.reader.u8()? as usize;
+                let name = self.reader.take(len)?;
+                self.atom(name, true)?
+            }
+            tag::SMALL_TUPLE_EXT => {
+                let arity = self.reader.u8()? as usize;
+                return self.tuple(arity);
+            }
+            tag::LARGE_TUPLE_EXT => {
+                let arity = self.reader.u32()? as usize;
+                return self.tuple(arity);
+            }
+            tag::NIL_EXT => Term::Nil,
+            tag::STRING_EXT => {
+                let len = self.reader.u16()? as usize;
+                let bytes = self.reader.take(len)?;
+                let mut builder = ListBuilder::new(self.heap);
+                for byte in bytes.iter().rev() {
+                    builder.push(Term::Int(*byte as i64))?;
+                }
+                match builder.finish() {
+                    None => Term::Nil,
+                    Some(cons) => Term::Cons(cons),
+                }
+            }
+            tag::LIST_EXT => {
+                let len = self.reader.u32()? as usize;
+                // Each element and the tail take at least one byte
+                let len = self.reader.check_len(len, 1)?;
+                return Ok(Next::Frame(Frame::List {
+                    elements: Vec::with_capacity(len + 1),
+                    len,
+                }));
+            }
+            tag::MAP_EXT => {
+                let arity = self.reader.u32()? as usize;
+                let arity = self.reader.check_len(arity, 2)?;
+                if arity == 0 {
+                    Term::Map(GcBox::new_in(Map::new(), self.heap)?)
+                } else {
+                    return Ok(Next::Frame(Frame::Map {
+                        items: Vec::with_capacity(arity * 2),
+                        arity,
+                    }));
+                }
+            }
+            tag::BINARY_EXT => {
+                let len = self.reader.u32()? as usize;
+                let bytes = self.reader.take(len)?;
+                self.binary(bytes)?.into()
+            }
+            tag::BIT_BINARY_EXT => {
+                let len = self.reader.u32()? as usize;
+                let bits = self.reader.u8()?;
+                let bytes = self.reader.take(len)?;
+                self.bitstring(bytes, bits)?
+            }
+            tag::NEW_PID_EXT => {
+                self.local_node()?;
+                let number = self.reader.u32()? as usize;
+                let serial = self.reader.u32()? as usize;
+                let _creation = self.reader.u32()?;
+                let pid = Pid::new_local(number, serial).map_err(|_| DecodeError::Invalid)?;
+                Term::Pid(GcBox::new_in(pid, self.heap)?)
+            }
+            tag::NEWER_REFERENCE_EXT => {
+                let len = self.reader.u16()?;
+                self.local_node()?;
+                let _creation = self.reader.u32()?;
+                // We only produce references with two words, see the encoder
+                if len != 2 {
+                    return Err(DecodeError::Invalid);
+                }
+                let low = self.reader.u32()? as u64;
+                let high = self.reader.u32()? as u64;
+                let id = reference_id(low | (high << 32));
+                Term::Reference(GcBox::new_in(Reference::Local { id }, self.heap)?)
+            }
+            _ => return Err(DecodeError::Invalid),
+        };
+
+        Ok(Next::Value(value))
+    }
+
+    fn tuple(&mut self, arity: usize) -> Result<Next, DecodeError> {
+        if arity == 0 {
+            return Ok(Next::Value(Tuple::from_slice(&[], self.heap)?.into()));
+        }
+        let arity = self.reader.check_len(arity, 1)?;
+        Ok(Next::Frame(Frame::Tuple {
+            elements: Vec::with_capacity(arity),
+            arity,
+        }))
+    }
+
+    fn big_integer(&mut self, len: usize) -> Result<Term, DecodeError> {
+        let sign = match self.reader.u8()? {
+            0 => Sign::Plus,
+            1 => Sign::Minus,
+            _ => return Err(DecodeError::Invalid),
+        };
+        let digits = self.reader.take(len)?;
+        let i = BigInt::from_bytes_le(sign, digits);
+        if let Some(term) = i.to_i64().and_then(|i| Term::try_from(i).ok()) {
+            return Ok(term);
+        }
+        Ok(Term::BigInt(GcBox::new_in(i, self.heap)?))
+    }
+
+    fn float(&self, f: f64) -> Result<Term, DecodeError> {
+        // Erlang has no representation for NaN or the infinities
+        if f.is_finite() {
+            Ok(Term::Float(f.into()))
+        } else {
+            Err(DecodeError::Invalid)
+        }
+    }
+
+    fn atom(&self, name: &[u8], utf8: bool) -> Result<Term, DecodeError> {
+        let result = match (utf8, self.options.safe) {
+            (true, true) => Atom::try_from_utf8_bytes_existing(name),
+            (true, false) => Atom::try_from(name),
+            (false, true) => Atom::try_from_latin1_bytes_existing(name),
+            (false, false) => Atom::try_from_latin1_bytes(name),
+        };
+        match result {
+            Ok(atom) if atom == atoms::True => Ok(Term::Bool(true)),
+            Ok(atom) if atom == atoms::False => Ok(Term::Bool(false)),
+            Ok(atom) => Ok(Term::Atom(atom)),
+            Err(AtomError::NonExistent) => Err(DecodeError::Unsafe),
+            Err(_) => Err(DecodeError::Invalid),
+        }
+    }
+
+    fn binary(&self, bytes: &[u8]) -> Result<OpaqueTerm, DecodeError> {
+        if bytes.len() <= 64 {
+            let mut bin = BinaryData::with_capacity_small(bytes.len(), self.heap)?;
+            bin.copy_from_slice(bytes);
+            Ok(bin.into())
+        } else {
+            Ok(BinaryData::from_bytes(bytes).into())
+        }
+    }
+
+    fn bitstring(&self, bytes: &[u8], bits: u8) -> Result<Term, DecodeError> {
+        match bits {
+            8 => Ok(self.binary(bytes)?.into()),
+            1..=7 if !bytes.is_empty() => {
+                let owner = self.binary(bytes)?;
+                let owner_term: Term = owner.into();
+                let data = owner_term.as_bitstring().unwrap();
+                let num_bits = (bytes.len() - 1) * 8 + bits as usize;
+                let slice = unsafe { BitSlice::new(owner, data.as_bytes_unchecked(), 0, num_bits) };
+                Ok(Term::RefBinary(GcBox::new_in(slice, self.heap)?))
+            }
+            _ => Err(DecodeError::Invalid),
+        }
+    }
+
+    /// Reads a node name, which must be the local node, as we have no distribution
+    fn local_node(&mut self) -> Result<(), DecodeError> {
+        match self.next()? {
+            Next::Value(Term::Atom(node)) if node == atoms::NonodeAtNohost => Ok(()),
+            _ => Err(DecodeError::Invalid),
+        }
+    }
+}
+
+/// Reconstructs a reference id from the raw 64-bit value produced by `ReferenceId::as_u64`,
+/// which holds the scheduler id in the highest 16 bits
+fn reference_id(raw: u64) -> ReferenceId {
+    const ID_MASK: u64 = (1 << 48) - 1;
+    ReferenceId::new((raw >> 48) as u16, raw & ID_MASK)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::process::Process;
+    use crate::term::ProcessId;
+
+    use super::super::{encode, EncodeOptions};
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn decode_default(bytes: &[u8], process: &Process) -> Result<Term, DecodeError> {
+        decode(bytes, DecodeOptions::default(), process)
+    }
+
+    /// Decodes `bytes`, and checks that encoding the result reproduces them exactly
+    fn round_trip(bytes: &[u8], process: &Process) -> Term {
+        let term = decode_default(bytes, process).unwrap();
+        assert_eq!(encode(term, EncodeOptions::default()).unwrap(), bytes);
+        term
+    }
+
+    #[test]
+    fn integers() {
+        let process = process();
+        assert_eq!(round_trip(&[131, 97, 255], &process), Term::Int(255));
+        assert_eq!(round_trip(&[131, 98, 0, 0, 1, 0], &process), Term::Int(256));
+        assert_eq!(
+            round_trip(&[131, 98, 255, 255, 255, 255], &process),
+            Term::Int(-1)
+        );
+        assert_eq!(
+            round_trip(&[131, 110, 5, 0, 0, 0, 0, 0, 1], &process),
+            Term::Int(1 << 32)
+        );
+        assert_eq!(
+            round_trip(&[131, 110, 4, 1, 1, 0, 0, 128], &process),
+            Term::Int(-2147483649)
+        );
+    }
+
+    #[test]
+    fn big_integers() {
+        let process = process();
+        // 1 bsl 64
+        let bytes = [131, 110, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+        let Term::BigInt(i) = round_trip(&bytes, &process) else {
+            panic!("expected bigint");
+        };
+        assert_eq!(*i, BigInt::from(1u128 << 64));
+
+        // Non-canonical encodings of small values are accepted
+        let bytes = [131, 111, 0, 0, 0, 2, 0, 42, 0];
+        assert_eq!(decode_default(&bytes, &process), Ok(Term::Int(42)));
+    }
+
+    #[test]
+    fn floats() {
+        let process = process();
+        let bytes = [131, 70, 63, 248, 0, 0, 0, 0, 0, 0];
+        assert_eq!(round_trip(&bytes, &process), Term::Float(1.5.into()));
+
+        assert_eq!(
+            decode_default(&float_ext(b"-5.00000000000000000000e-01"), &process),
+            Ok(Term::Float((-0.5).into()))
+        );
+
+        // NaN cannot be represented
+        let bytes = [131, 70, 127, 248, 0, 0, 0, 0, 0, 0];
+        assert_eq!(decode_default(&bytes, &process), Err(DecodeError::Invalid));
+    }
+
+    #[test]
+    fn old_floats() {
+        let process = process();
+        assert_eq!(
+            decode_default(&float_ext(b"1.50000000000000000000e+00"), &process),
+            Ok(Term::Float(1.5.into()))
+        );
+        // The text may fill all 31 bytes, without a terminating NUL
+        assert_eq!(
+            decode_default(&float_ext(b"2.5000000000000000000000000e+02"), &process),
+            Ok(Term::Float(250.0.into()))
+        );
+        assert_eq!(
+            decode_default(&float_ext(b"not a float"), &process),
+            Err(DecodeError::Invalid)
+        );
+        // The text is always 31 bytes
+        assert_eq!(
+            decode_default(&[131, 99, b'1', b'.', b'5', 0], &process),
+            Err(DecodeError::Invalid)
+        );
+    }
+
+    /// Encodes `text` as a FLOAT_EXT, padded with NULs
+    fn float_ext(text: &[u8]) -> Vec<u8> {
+        let mut padded = [0u8; 31];
+        padded[..text.len()].copy_from_slice(text);
+
+        let mut bytes = vec![131, 99];
+        bytes.extend_from_slice(&padded);
+        bytes
+    }
+
+    #[test]
+    fn atoms() {
+        let process = process();
+        let hello = Atom::try_from("hello").unwrap();
+        assert_eq!(
+            round_trip(&[131, 119, 5, b'h', b'e', b'l', b'l', b'o'], &process),
+            Term::Atom(hello)
+        );
+        assert_eq!(
+            decode_default(&[131, 100, 0, 5, b'h', b'e', b'l', b'l', b'o'], &process),
+            Ok(Term::Atom(hello))
+        );
+        assert_eq!(
+            decode_default(&[131, 115, 5, b'h', b'e', b'l', b'l', b'o'], &process),
+            Ok(Term::Atom(hello))
+        );
+        assert_eq!(
+            decode_default(&[131, 118, 0, 5, b'h', b'e', b'l', b'l', b'o'], &process),
+            Ok(Term::Atom(hello))
+        );
+        assert_eq!(
+            round_trip(&[131, 119, 4, b't', b'r', b'u', b'e'], &process),
+            Term::Bool(true)
+        );
+        // Latin-1 atoms are converted to UTF-8
+        assert_eq!(
+            decode_default(&[131, 115, 1, 0xe5], &process),
+            Ok(Term::Atom(Atom::try_from("å").unwrap()))
+        );
+        // Invalid UTF-8
+        assert_eq!(
+            decode_default(&[131, 119, 1, 0xff], &process),
+            Err(DecodeError::Invalid)
+        );
+    }
+
+    #[test]
+    fn safe_option_rejects_new_atoms() {
+        let process = process();
+        let safe = DecodeOptions { safe: true };
+
+        let bytes = [131, 119, 5, b'h', b'e', b'l', b'l', b'o'];
+        Atom::try_from("hello").unwrap();
+        assert!(decode(&bytes, safe, &process).is_ok());
+
+        let name = b"binary_to_term_safe_never_seen_before";
+        let mut bytes = vec![131, 119, name.len() as u8];
+        bytes.extend_from_slice(name);
+        assert_eq!(decode(&bytes, safe, &process), Err(DecodeError::Unsafe));
+        let mut latin1 = vec![131, 115, name.len() as u8];
+        latin1.extend_from_slice(name);
+        assert_eq!(decode(&latin1, safe, &process), Err(DecodeError::Unsafe));
+
+        // The unsafe variant creates the atom, after which the safe variant succeeds
+        assert!(decode_default(&bytes, &process).is_ok());
+        assert!(decode(&bytes, safe, &process).is_ok());
+    }
+
+    #[test]
+    fn tuples() {
+        let process = process();
+        let term = round_trip(&[131, 104, 2, 97, 1, 97, 2], &process);
+        let Term::Tuple(tuple) = term else {
+            panic!("expected tuple");
+        };
+        let tuple = unsafe { tuple.as_ref() };
+        assert_eq!(tuple.len(), 2);
+        assert_eq!(tuple[0], OpaqueTerm::from(Term::Int(1)));
+        assert_eq!(tuple[1], OpaqueTerm::from(Term::Int(2)));
+
+        let Term::Tuple(empty) = round_trip(&[131, 104, 0], &process) else {
+            panic!("expected tuple");
+        };
+        assert_eq!(unsafe { empty.as_ref() }.len(), 0);
+
+        assert!(decode_default(&[131, 105, 0, 0, 0, 1, 106], &process).is_ok());
+    }
+
+    #[test]
+    fn lists() {
+        let process = process();
+        assert_eq!(round_trip(&[131, 106], &process), Term::Nil);
+
+        let term = round_trip(&[131, 107, 0, 3, 1, 2, 3], &process);
+        let Term::Cons(cons) = term else {
+            panic!("expected list");
+        };
+        let elements = unsafe { cons.as_ref() }
+            .iter()
+            .map(|t| t.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(elements, [Term::Int(1), Term::Int(2), Term::Int(3)]);
+
+        // [a | b]
+        let bytes = [131, 108, 0, 0, 0, 1, 119, 1, b'a', 119, 1, b'b'];
+        let Term::Cons(cons) = round_trip(&bytes, &process) else {
+            panic!("expected list");
+        };
+        let cons = unsafe { cons.as_ref() };
+        assert_eq!(cons.head(), Term::Atom(Atom::try_from("a").unwrap()));
+        assert_eq!(cons.tail(), Term::Atom(Atom::try_from("b").unwrap()));
+    }
+
+    #[test]
+    fn maps() {
+        let process = process();
+        let bytes = [131, 116, 0, 0, 0, 1, 119, 1, b'a', 97, 1];
+        let Term::Map(map) = round_trip(&bytes, &process) else {
+            panic!("expected map");
+        };
+        assert_eq!(map.size(), 1);
+        assert_eq!(
+            map.get(Term::Atom(Atom::try_from("a").unwrap())),
+            Some(Term::Int(1))
+        );
+
+        let Term::Map(empty) = round_trip(&[131, 116, 0, 0, 0, 0], &process) else {
+            panic!("expected map");
+        };
+        assert_eq!(empty.size(), 0);
+
+        // Duplicate keys are rejected
+        let bytes = [131, 116, 0, 0, 0, 2, 97, 1, 97, 2, 97, 1, 97, 3];
+        assert_eq!(decode_default(&bytes, &process), Err(DecodeError::Invalid));
+    }
+
+    #[test]
+    fn binaries() {
+        let process = process();
+        let term = round_trip(&[131, 109, 0, 0, 0, 3, b'a', b'b', b'c'], &process);
+        let bin = term.as_bitstring().unwrap();
+        assert!(bin.is_binary());
+        assert_eq!(unsafe { bin.as_bytes_unchecked() }, b"abc");
+
+        let mut bytes = vec![131, 109, 0, 0, 1, 0];
+        bytes.extend((0..=255u8).into_iter());
+        let term = round_trip(&bytes, &process);
+        assert_eq!(term.as_bitstring().unwrap().byte_size(), 256);
+
+        // <<1:1, 2:2>>
+        let term = round_trip(&[131, 77, 0, 0, 0, 1, 3, 0b1100_0000], &process);
+        assert_eq!(term.as_bitstring().unwrap().bit_size(), 3);
+
+        // Zero trailing bits are not permitted
+        assert_eq!(
+            decode_default(&[131, 77, 0, 0, 0, 1, 0, 0], &process),
+            Err(DecodeError::Invalid)
+        );
+    }
+
+    #[test]
+    fn pids_and_references() {
+        let process = process();
+        let pid = Pid::new_local(42, 1).unwrap();
+        let term = Term::Pid(GcBox::new_in(pid, &process).unwrap());
+        let bytes = encode(term, EncodeOptions::default()).unwrap();
+        assert_eq!(decode_default(&bytes, &process), Ok(term));
+
+        let id = ReferenceId::new(3, 12345);
+        let term = Term::Reference(GcBox::new_in(Reference::Local { id }, &process).unwrap());
+        let bytes = encode(term, EncodeOptions::default()).unwrap();
+        assert_eq!(decode_default(&bytes, &process), Ok(term));
+
+        // A pid on another node
+        let mut bytes = vec![131, 88, 119, 9];
+        bytes.extend_from_slice(b"a@b.local");
+        bytes.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(decode_default(&bytes, &process), Err(DecodeError::Invalid));
+    }
+
+    #[test]
+    fn compressed() {
+        let process = process();
+        let mut bytes = vec![131, 107, 0, 200];
+        bytes.extend(core::iter::repeat(b'x').take(200));
+        let term = decode_default(&bytes, &process).unwrap();
+
+        let options = EncodeOptions {
+            compression: 9,
+            ..Default::default()
+        };
+        let compressed = encode(term, options).unwrap();
+        assert_eq!(compressed[1], tag::COMPRESSED);
+        assert_eq!(decode_default(&compressed, &process), Ok(term));
+
+        // The uncompressed size must match
+        let mut wrong_size = compressed.clone();
+        wrong_size[5] += 1;
+        assert_eq!(
+            decode_default(&wrong_size, &process),
+            Err(DecodeError::Invalid)
+        );
+    }
+
+    #[test]
+    fn deeply_nested() {
+        const DEPTH: usize = 100_000;
+
+        let process = process();
+        // [[[...[]...]]]
+        let mut bytes = vec![131];
+        for _ in 0..DEPTH {
+            bytes.extend_from_slice(&[108, 0, 0, 0, 1]);
+        }
+        bytes.push(106);
+        for _ in 0..DEPTH {
+            bytes.push(106);
+        }
+        let term = decode_default(&bytes, &process).unwrap();
+        assert_eq!(encode(term, EncodeOptions::default()).unwrap(), bytes);
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let process = process();
+        let invalid: &[&[u8]] = &[
+            &[],
+            &[130, 97, 1],
+            &[131],
+            &[131, 1],
+            // Trailing bytes
+            &[131, 97, 1, 0],
+            &[131, 106, 106],
+            // Lengths which far exceed the input
+            &[131, 108, 255, 255, 255, 255, 106],
+            &[131, 105, 255, 255, 255, 255],
+            &[131, 116, 255, 255, 255, 255],
+            &[131, 109, 255, 255, 255, 255, 0],
+            &[131, 111, 255, 255, 255, 255, 0],
+            &[131, 80, 255, 255, 255, 255, 0],
+            // Invalid sign
+            &[131, 110, 1, 2, 1],
+            // Nested compression
+            &[131, 104, 1, 80, 0, 0, 0, 0],
+        ];
+        for bytes in invalid {
+            assert_eq!(
+                decode_default(bytes, &process),
+                Err(DecodeError::Invalid),
+                "{:?}",
+                bytes
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_input_is_rejected() {
+        let process = process();
+        let mut bytes = vec![131, 104, 4, 97, 1];
+        bytes.extend_from_slice(&[108, 0, 0, 0, 2, 119, 1, b'a', 98, 0, 0, 1, 0, 106]);
+        bytes.extend_from_slice(&[
+            116, 0, 0, 0, 1, 109, 0, 0, 0, 1, 0, 70, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+        bytes.extend_from_slice(&[107, 0, 2, 1, 2]);
+        assert!(decode_default(&bytes, &process).is_ok());
+
+        for len in 0..bytes.len() {
+            assert_eq!(
+                decode_default(&bytes[..len], &process),
+                Err(DecodeError::Invalid),
+                "{}",
+                len
+            );
+        }
+    }
+
+    /// A xorshift generator, so that the fuzz test is reproducible
+    struct Rng(u64);
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    #[test]
+    fn random_input_never_panics() {
+        let process = process();
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let safe = DecodeOptions { safe: true };
+
+        for _ in 0..20_000 {
+            let len = (rng.next() % 48) as usize;
+            let mut bytes = vec![131];
+            // Bias towards valid tags to reach deeper into the decoder
+            for _ in 0..len {
+                let byte = match rng.next() % 4 {
+                    0 => [
+                        70, 77, 88, 90, 97, 98, 104, 105, 106, 107, 108, 109, 110, 111, 116, 119,
+                    ][(rng.next() % 16) as usize],
+                    1 => (rng.next() % 4) as u8,
+                    _ => rng.next() as u8,
+                };
+                bytes.push(byte);
+            }
+            let _ = decode(&bytes, safe, &process);
+            let _ = decode(&bytes[1..], safe, &process);
+        }
+
+        // Mutations of a valid encoding
+        let mut valid = vec![131, 104, 3, 97, 1];
+        valid.extend_from_slice(&[108, 0, 0, 0, 1, 109, 0, 0, 0, 2, 1, 2, 106]);
+        valid.extend_from_slice(&[116, 0, 0, 0, 1, 119, 2, b'o', b'k', 77, 0, 0, 0, 1, 5, 0xf8]);
+        for _ in 0..20_000 {
+            let mut bytes = valid.clone();
+            for _ in 0..=(rng.next() % 3) {
+                let i = (rng.next() as usize) % bytes.len();
+                bytes[i] = rng.next() as u8;
+            }
+            let _ = decode(&bytes, safe, &process);
+        }
+    }
+}
@@ -0,0 +1,691 @@
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use firefly_binary::Bitstring;
+use firefly_number::{BigInt, Sign, ToPrimitive};
+
+use crate::term::{atoms, Atom, Cons, ImproperList, Node, Pid, Port, Reference, Term};
+
+use super::{tag, VERSION_NUMBER};
+
+/// The compression level used when `compressed` is given without an explicit level
+pub const DEFAULT_COMPRESSION_LEVEL: u8 = 6;
+
+/// The largest length of a list which may be encoded using `STRING_EXT`
+const MAX_STRING_EXT_LEN: usize = u16::MAX as usize;
+
+/// Produced when a term cannot be encoded in the external term format
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The term contains a value which has no external representation
+    Unsupported,
+    /// The term contains a value whose size exceeds what the format can represent
+    TooLarge,
+}
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unsupported => f.write_str("term contains a value which cannot be encoded"),
+            Self::TooLarge => f.write_str("term is too large to be encoded"),
+        }
+    }
+}
+
+/// The options accepted by `term_to_binary/2`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// The zlib compression level to use, from 0 to 9, where 0 disables compression
+    pub compression: u8,
+    /// Controls the encoding of floats and atoms.
+    ///
+    /// * `0` encodes floats as text, and atoms as Latin-1 where possible
+    /// * `1` encodes floats in their binary representation, and atoms as Latin-1 where possible
+    /// * `2` encodes floats in their binary representation, and atoms as UTF-8 (the default)
+    pub minor_version: u8,
+}
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            compression: 0,
+            minor_version: 2,
+        }
+    }
+}
+
+/// Encodes `term` in the external term format, returning the encoded bytes.
+///
+/// The encoding of maps is deterministic: pairs are always written in key order. Atoms are
+/// always encoded in full, as the atom cache only exists for distribution.
+///
+/// Terms of arbitrary depth can be encoded, as nested values are visited using an explicit
+/// work stack rather than by recursion.
+pub fn encode(term: Term, options: EncodeOptions) -> Result<Vec<u8>, EncodeError> {
+    let mut encoder = Encoder {
+        buffer: Vec::new(),
+        minor_version: options.minor_version,
+    };
+    encoder.buffer.push(VERSION_NUMBER);
+    encoder.encode(term)?;
+
+    let encoded = encoder.buffer;
+    if options.compression == 0 {
+        return Ok(encoded);
+    }
+
+    // Like BEAM, only use the compressed form if it is actually smaller
+    let uncompressed = &encoded[1..];
+    let uncompressed_size: u32 = uncompressed
+        .len()
+        .try_into()
+        .map_err(|_| EncodeError::TooLarge)?;
+    let level = options.compression.min(9);
+    let compressed = miniz_oxide::deflate::compress_to_vec_zlib(uncompressed, level);
+    if compressed.len() + 5 >= uncompressed.len() {
+        return Ok(encoded);
+    }
+
+    let mut buffer = Vec::with_capacity(compressed.len() + 6);
+    buffer.push(VERSION_NUMBER);
+    buffer.push(tag::COMPRESSED);
+    buffer.extend_from_slice(&uncompressed_size.to_be_bytes());
+    buffer.extend_from_slice(&compressed);
+    Ok(buffer)
+}
+
+struct Encoder {
+    buffer: Vec<u8>,
+    minor_version: u8,
+}
+impl Encoder {
+    fn encode(&mut self, term: Term) -> Result<(), EncodeError> {
+        // Values which still need to be written, in reverse order
+        let mut stack = Vec::with_capacity(16);
+        stack.push(term);
+
+        while let Some(term) = stack.pop() {
+            match term {
+                Term::None | Term::Closure(_) | Term::ExternalFun(_) => {
+                    return Err(EncodeError::Unsupported)
+                }
+                Term::Nil => self.buffer.push(tag::NIL_EXT),
+                Term::Bool(b) => self.atom(if b { atoms::True } else { atoms::False }),
+                Term::Atom(a) => self.atom(a),
+                Term::Int(i) => self.integer(i),
+                Term::BigInt(i) => self.big_integer(&i),
+                Term::Float(f) => self.float(f.inner()),
+                Term::Cons(ptr) => self.list(unsafe { ptr.as_ref() }, &mut stack)?,
+                Term::Tuple(ptr) => {
+                    let tuple = unsafe { ptr.as_ref() };
+                    let arity = tuple.len();
+                    if arity <= u8::MAX as usize {
+                        self.buffer.push(tag::SMALL_TUPLE_EXT);
+                        self.buffer.push(arity as u8);
+                    } else {
+                        self.buffer.push(tag::LARGE_TUPLE_EXT);
+                        self.length(arity)?;
+                    }
+                    stack.extend(tuple.as_slice().iter().rev().map(|t| Term::from(*t)));
+                }
+                Term::Map(map) => {
+                    self.buffer.push(tag::MAP_EXT);
+                    self.length(map.size())?;
+                    let mut pairs = map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+                    pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (key, value) in pairs.into_iter().rev() {
+                        stack.push(value);
+                        stack.push(key);
+                    }
+                }
+                Term::Pid(pid) => self.pid(&pid),
+                Term::Port(port) => self.port(&port),
+                Term::Reference(reference) => self.reference(&reference),
+                Term::HeapBinary(_)
+                | Term::RcBinary(_)
+                | Term::RefBinary(_)
+                | Term::ConstantBinary(_) => self.bitstring(term.as_bitstring().unwrap())?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn length(&mut self, len: usize) -> Result<(), EncodeError> {
+        let len: u32 = len.try_into().map_err(|_| EncodeError::TooLarge)?;
+        self.buffer.extend_from_slice(&len.to_be_bytes());
+        Ok(())
+    }
+
+    fn atom(&mut self, atom: Atom) {
+        let name = atom.as_str();
+
+        if self.minor_version < 2 {
+            if let Some(latin1) = to_latin1(name) {
+                self.buffer.push(tag::ATOM_EXT);
+                self.buffer
+                    .extend_from_slice(&(latin1.len() as u16).to_be_bytes());
+                self.buffer.extend_from_slice(&latin1);
+                return;
+            }
+        }
+
+        // Atoms are limited to 255 characters, but not to 255 bytes
+        if name.len() <= u8::MAX as usize {
+            self.buffer.push(tag::SMALL_ATOM_UTF8_EXT);
+            self.buffer.push(name.len() as u8);
+        } else {
+            self.buffer.push(tag::ATOM_UTF8_EXT);
+            self.buffer
+                .extend_from_slice(&(name.len() as u16).to_be_bytes());
+        }
+        self.buffer.extend_from_slice(name.as_bytes());
+    }
+
+    fn integer(&mut self, i: i64) {
+        match i {
+            0..=255 => {
+                self.buffer.push(tag::SMALL_INTEGER_EXT);
+                self.buffer.push(i as u8);
+            }
+            i if i >= i32::MIN as i64 && i <= i32::MAX as i64 => {
+                self.buffer.push(tag::INTEGER_EXT);
+                self.buffer.extend_from_slice(&(i as i32).to_be_bytes());
+            }
+            i => {
+                let digits = i.unsigned_abs().to_le_bytes();
+                let len = digits.iter().rposition(|d| *d != 0).unwrap() + 1;
+                self.big_digits(i < 0, &digits[..len]);
+            }
+        }
+    }
+
+    fn big_integer(&mut self, i: &BigInt) {
+        if let Some(i) = i.to_i64() {
+            return self.integer(i);
+        }
+        let (sign, digits) = i.to_bytes_le();
+        self.big_digits(sign == Sign::Minus, &digits);
+    }
+
+    fn big_digits(&mut self, negative: bool, digits: &[u8]) {
+        if digits.len() <= u8::MAX as usize {
+            self.buffer.push(tag::SMALL_BIG_EXT);
+            self.buffer.push(digits.len() as u8);
+        } else {
+            self.buffer.push(tag::LARGE_BIG_EXT);
+            self.buffer
+                .extend_from_slice(&(digits.len() as u32).to_be_bytes());
+        }
+        self.buffer.push(negative as u8);
+        self.buffer.extend_from_slice(digits);
+    }
+
+    fn float(&mut self, f: f64) {
+        if self.minor_version > 0 {
+            self.buffer.push(tag::NEW_FLOAT_EXT);
+            self.buffer.extend_from_slice(&f.to_bits().to_be_bytes());
+            return;
+        }
+
+        // FLOAT_EXT is the textual form produced by C's `%.20e`, zero-padded to 31 bytes
+        let formatted = format!("{:.20e}", f);
+        let (mantissa, exponent) = formatted.split_once('e').unwrap();
+        let exponent: i32 = exponent.parse().unwrap();
+        let sign = if exponent < 0 { '-' } else { '+' };
+        let text = format!("{}e{}{:02}", mantissa, sign, exponent.abs());
+
+        let mut bytes = [0u8; 31];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        self.buffer.push(tag::FLOAT_EXT);
+        self.buffer.extend_from_slice(&bytes);
+    }
+
+    fn list(&mut self, cons: &Cons, stack: &mut Vec<Term>) -> Result<(), EncodeError> {
+        let mut elements = Vec::new();
+        let mut tail = Term::Nil;
+        for element in cons.iter() {
+            match element {
+                Ok(element) => elements.push(element),
+                Err(ImproperList { tail: improper }) => tail = improper,
+            }
+        }
+
+        // Proper lists of bytes are encoded compactly
+        let is_string = tail == Term::Nil
+            && elements.len() <= MAX_STRING_EXT_LEN
+            && elements
+                .iter()
+                .all(|element| matches!(element, Term::Int(0..=255)));
+        if is_string {
+            self.buffer.push(tag::STRING_EXT);
+            self.buffer
+                .extend_from_slice(&(elements.len() as u16).to_be_bytes());
+            for element in elements.iter() {
+                if let Term::Int(byte) = element {
+                    self.buffer.push(*byte as u8);
+                }
+            }
+            return Ok(());
+        }
+
+        self.buffer.push(tag::LIST_EXT);
+        self.length(elements.len())?;
+        stack.push(tail);
+        stack.extend(elements.into_iter().rev());
+        Ok(())
+    }
+
+    fn bitstring(&mut self, bits: &dyn Bitstring) -> Result<(), EncodeError> {
+        let bytes = bits.select_all().to_bytes();
+        if bits.is_binary() {
+            self.buffer.push(tag::BINARY_EXT);
+            self.length(bytes.len())?;
+        } else {
+            self.buffer.push(tag::BIT_BINARY_EXT);
+            self.length(bytes.len())?;
+            self.buffer.push((bits.bit_size() % 8) as u8);
+        }
+        self.buffer.extend_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn pid(&mut self, pid: &Pid) {
+        let id = pid.id();
+        self.buffer.push(tag::NEW_PID_EXT);
+        let creation = self.node(pid.node().as_deref());
+        self.buffer.extend_from_slice(&id.number().to_be_bytes());
+        self.buffer.extend_from_slice(&id.serial().to_be_bytes());
+        self.buffer.extend_from_slice(&creation.to_be_bytes());
+    }
+
+    fn port(&mut self, port: &Port) {
+        let (id, node) = match port {
+            Port::Local { id } => (*id, None),
+            Port::External { id, node, .. } => (*id, Some(node.as_ref())),
+        };
+        self.buffer.push(tag::V4_PORT_EXT);
+        let creation = self.node(node);
+        self.buffer.extend_from_slice(&id.as_u64().to_be_bytes());
+        self.buffer.extend_from_slice(&creation.to_be_bytes());
+    }
+
+    fn reference(&mut self, reference: &Reference) {
+        // Reference ids are 64 bits, which we write as two 32-bit words, least significant first
+        let id = reference.id().as_u64();
+        self.buffer.push(tag::NEWER_REFERENCE_EXT);
+        self.buffer.extend_from_slice(&2u16.to_be_bytes());
+        let creation = self.node(reference.node().as_deref());
+        self.buffer.extend_from_slice(&creation.to_be_bytes());
+        self.buffer.extend_from_slice(&(id as u32).to_be_bytes());
+        self.buffer
+            .extend_from_slice(&((id >> 32) as u32).to_be_bytes());
+    }
+
+    /// Writes the name of `node`, returning its creation.
+    ///
+    /// Local identifiers are written as belonging to `nonode@nohost`, as BEAM does when
+    /// distribution is not started.
+    fn node(&mut self, node: Option<&Node>) -> u32 {
+        match node {
+            Some(node) => {
+                self.atom(node.name().unwrap_or(atoms::NonodeAtNohost));
+                node.creation()
+            }
+            None => {
+                self.atom(atoms::NonodeAtNohost);
+                0
+            }
+        }
+    }
+}
+
+/// Returns `s` encoded as Latin-1, if every character in it is representable
+fn to_latin1(s: &str) -> Option<Vec<u8>> {
+    s.chars().map(|c| u8::try_from(c).ok()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use core::ptr::NonNull;
+
+    use firefly_alloc::gc::GcBox;
+
+    use crate::process::Process;
+    use crate::term::{BinaryData, BitSlice, Map, OpaqueTerm, ProcessId, ReferenceId, Tuple};
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn encode_default(term: Term) -> Vec<u8> {
+        encode(term, EncodeOptions::default()).unwrap()
+    }
+
+    fn encode_minor(term: Term, minor_version: u8) -> Vec<u8> {
+        let options = EncodeOptions {
+            minor_version,
+            ..Default::default()
+        };
+        encode(term, options).unwrap()
+    }
+
+    #[test]
+    fn small_integer_ext() {
+        assert_eq!(encode_default(Term::Int(0)), [131, 97, 0]);
+        assert_eq!(encode_default(Term::Int(255)), [131, 97, 255]);
+    }
+
+    #[test]
+    fn integer_ext() {
+        assert_eq!(encode_default(Term::Int(256)), [131, 98, 0, 0, 1, 0]);
+        assert_eq!(encode_default(Term::Int(-1)), [131, 98, 255, 255, 255, 255]);
+        assert_eq!(
+            encode_default(Term::Int(i32::MIN as i64)),
+            [131, 98, 128, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn small_big_ext() {
+        // 1 bsl 32
+        assert_eq!(
+            encode_default(Term::Int(1 << 32)),
+            [131, 110, 5, 0, 0, 0, 0, 0, 1]
+        );
+        // -2147483649
+        assert_eq!(
+            encode_default(Term::Int(-2147483649)),
+            [131, 110, 4, 1, 1, 0, 0, 128]
+        );
+
+        let process = process();
+        // 1 bsl 64
+        let big = BigInt::from(1u128 << 64);
+        let big = GcBox::new_in(big, &process).unwrap();
+        assert_eq!(
+            encode_default(big.into()),
+            [131, 110, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn large_big_ext() {
+        let process = process();
+        // 1 bsl 2048
+        let big = BigInt::from(1u8) << 2048usize;
+        let big = GcBox::new_in(big, &process).unwrap();
+        let encoded = encode_default(big.into());
+
+        assert_eq!(&encoded[..7], &[131, 111, 0, 0, 1, 1, 0]);
+        assert_eq!(encoded.len(), 7 + 257);
+        assert!(encoded[7..263].iter().all(|d| *d == 0));
+        assert_eq!(encoded[263], 1);
+    }
+
+    #[test]
+    fn new_float_ext() {
+        assert_eq!(
+            encode_default(Term::from(1.5)),
+            [131, 70, 63, 248, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            encode_minor(Term::from(-0.25), 1),
+            [131, 70, 191, 208, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn float_ext() {
+        let mut expected = vec![131, 99];
+        expected.extend_from_slice(b"1.50000000000000000000e+00\0\0\0\0\0");
+        assert_eq!(encode_minor(Term::from(1.5), 0), expected);
+
+        let mut expected = vec![131, 99];
+        expected.extend_from_slice(b"-5.00000000000000000000e-01\0\0\0\0");
+        assert_eq!(encode_minor(Term::from(-0.5), 0), expected);
+    }
+
+    #[test]
+    fn small_atom_utf8_ext() {
+        let hello = Atom::try_from("hello").unwrap();
+        assert_eq!(
+            encode_default(hello.into()),
+            [131, 119, 5, b'h', b'e', b'l', b'l', b'o']
+        );
+        assert_eq!(
+            encode_default(Term::Bool(true)),
+            [131, 119, 4, b't', b'r', b'u', b'e']
+        );
+        // 'ö', which is written as UTF-8 unless an older minor version is requested
+        let oumlaut = Atom::try_from("ö").unwrap();
+        assert_eq!(encode_default(oumlaut.into()), [131, 119, 2, 0xc3, 0xb6]);
+    }
+
+    #[test]
+    fn atom_utf8_ext() {
+        let name = "λ".repeat(200);
+        let atom = Atom::try_from(name.as_str()).unwrap();
+        let encoded = encode_default(atom.into());
+
+        assert_eq!(&encoded[..4], &[131, 118, 1, 144]);
+        assert_eq!(&encoded[4..], name.as_bytes());
+    }
+
+    #[test]
+    fn atom_ext() {
+        let hello = Atom::try_from("hello").unwrap();
+        assert_eq!(
+            encode_minor(hello.into(), 1),
+            [131, 100, 0, 5, b'h', b'e', b'l', b'l', b'o']
+        );
+        let oumlaut = Atom::try_from("ö").unwrap();
+        assert_eq!(encode_minor(oumlaut.into(), 1), [131, 100, 0, 1, 0xf6]);
+        // Atoms which cannot be represented in Latin-1 are always UTF-8
+        let lambda = Atom::try_from("λ").unwrap();
+        assert_eq!(encode_minor(lambda.into(), 0), [131, 119, 2, 0xce, 0xbb]);
+    }
+
+    #[test]
+    fn small_tuple_ext() {
+        let process = process();
+        let empty = Tuple::from_slice(&[], &process).unwrap();
+        assert_eq!(encode_default(empty.into()), [131, 104, 0]);
+
+        let pair =
+            Tuple::from_slice(&[Term::Int(1).into(), Term::Int(2).into()], &process).unwrap();
+        assert_eq!(encode_default(pair.into()), [131, 104, 2, 97, 1, 97, 2]);
+    }
+
+    #[test]
+    fn large_tuple_ext() {
+        let process = process();
+        let elements = [OpaqueTerm::NIL; 256];
+        let tuple = Tuple::from_slice(&elements, &process).unwrap();
+        let encoded = encode_default(tuple.into());
+
+        assert_eq!(&encoded[..6], &[131, 105, 0, 0, 1, 0]);
+        assert_eq!(encoded.len(), 6 + 256);
+        assert!(encoded[6..].iter().all(|b| *b == 106));
+    }
+
+    #[test]
+    fn nil_ext() {
+        assert_eq!(encode_default(Term::Nil), [131, 106]);
+    }
+
+    #[test]
+    fn string_ext() {
+        let process = process();
+        let list = Cons::from_slice(&[Term::Int(1), Term::Int(2), Term::Int(3)], &process)
+            .unwrap()
+            .unwrap();
+        assert_eq!(encode_default(list.into()), [131, 107, 0, 3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn list_ext() {
+        let process = process();
+        // [256]
+        let list = Cons::from_slice(&[Term::Int(256)], &process)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            encode_default(list.into()),
+            [131, 108, 0, 0, 0, 1, 98, 0, 0, 1, 0, 106]
+        );
+
+        // [a | b]
+        let a = Atom::try_from("a").unwrap();
+        let b = Atom::try_from("b").unwrap();
+        let improper = Cons::new_in(&process).unwrap();
+        unsafe {
+            improper
+                .as_uninit_mut()
+                .write(Cons::cons(a.into(), b.into()));
+        }
+        assert_eq!(
+            encode_default(improper.into()),
+            [131, 108, 0, 0, 0, 1, 119, 1, b'a', 119, 1, b'b']
+        );
+    }
+
+    #[test]
+    fn map_ext() {
+        let process = process();
+        let empty = GcBox::new_in(Map::new(), &process).unwrap();
+        assert_eq!(encode_default(empty.into()), [131, 116, 0, 0, 0, 0]);
+
+        // #{b => 2, a => 1}, which is written in key order
+        let a = Atom::try_from("a").unwrap();
+        let b = Atom::try_from("b").unwrap();
+        let map =
+            Map::new_from_iter([(b.into(), Term::Int(2)), (a.into(), Term::Int(1))].into_iter());
+        let map = GcBox::new_in(map, &process).unwrap();
+        assert_eq!(
+            encode_default(map.into()),
+            [131, 116, 0, 0, 0, 2, 119, 1, b'a', 97, 1, 119, 1, b'b', 97, 2]
+        );
+    }
+
+    #[test]
+    fn binary_ext() {
+        let bin = BinaryData::from_bytes(b"abc");
+        let term: Term = OpaqueTerm::from(bin.clone()).into();
+        assert_eq!(
+            encode_default(term),
+            [131, 109, 0, 0, 0, 3, b'a', b'b', b'c']
+        );
+
+        let empty = BinaryData::from_bytes(&[]);
+        let term: Term = OpaqueTerm::from(empty.clone()).into();
+        assert_eq!(encode_default(term), [131, 109, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bit_binary_ext() {
+        let process = process();
+        // <<1:3>>
+        let bytes = [0b0010_0000];
+        let slice = unsafe { BitSlice::new(OpaqueTerm::NONE, &bytes, 0, 3) };
+        let slice = GcBox::new_in(slice, &process).unwrap();
+        assert_eq!(
+            encode_default(slice.into()),
+            [131, 77, 0, 0, 0, 1, 3, 0b0010_0000]
+        );
+    }
+
+    #[test]
+    fn new_pid_ext() {
+        let process = process();
+        // <0.80.0>
+        let pid = GcBox::new_in(Pid::new_local(80, 0).unwrap(), &process).unwrap();
+        let mut expected = vec![131, 88, 119, 13];
+        expected.extend_from_slice(b"nonode@nohost");
+        expected.extend_from_slice(&[0, 0, 0, 80, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(encode_default(pid.into()), expected);
+
+        let node = Arc::new(Node::new(1, Atom::try_from("a@b").unwrap(), 3));
+        let pid = GcBox::new_in(Pid::new_external(node, 1, 2).unwrap(), &process).unwrap();
+        assert_eq!(
+            encode_default(pid.into()),
+            [131, 88, 119, 3, b'a', b'@', b'b', 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3]
+        );
+    }
+
+    #[test]
+    fn newer_reference_ext() {
+        let process = process();
+        let id = ReferenceId::new(0, 0x0000_0001_0000_0002);
+        let reference = GcBox::new_in(Reference::Local { id }, &process).unwrap();
+        let mut expected = vec![131, 90, 0, 2, 119, 13];
+        expected.extend_from_slice(b"nonode@nohost");
+        expected.extend_from_slice(&[0, 0, 0, 0]);
+        expected.extend_from_slice(&(id.as_u64() as u32).to_be_bytes());
+        expected.extend_from_slice(&((id.as_u64() >> 32) as u32).to_be_bytes());
+        assert_eq!(encode_default(reference.into()), expected);
+    }
+
+    #[test]
+    fn none_is_unsupported() {
+        assert_eq!(
+            encode(Term::None, EncodeOptions::default()),
+            Err(EncodeError::Unsupported)
+        );
+    }
+
+    #[test]
+    fn compressed_round_trips() {
+        let process = process();
+        let elements = [OpaqueTerm::NIL; 1000];
+        let tuple = Tuple::from_slice(&elements, &process).unwrap();
+        let uncompressed = encode_default(tuple.into());
+        let options = EncodeOptions {
+            compression: DEFAULT_COMPRESSION_LEVEL,
+            ..Default::default()
+        };
+        let compressed = encode(tuple.into(), options).unwrap();
+
+        assert_eq!(&compressed[..2], &[131, 80]);
+        assert_eq!(
+            &compressed[2..6],
+            &((uncompressed.len() - 1) as u32).to_be_bytes()
+        );
+        assert!(compressed.len() < uncompressed.len());
+        let inflated = miniz_oxide::inflate::decompress_to_vec_zlib(&compressed[6..]).unwrap();
+        assert_eq!(inflated.as_slice(), &uncompressed[1..]);
+    }
+
+    #[test]
+    fn compression_is_skipped_when_it_does_not_help() {
+        let options = EncodeOptions {
+            compression: 9,
+            ..Default::default()
+        };
+        assert_eq!(encode(Term::Int(1), options).unwrap(), [131, 97, 1]);
+    }
+
+    #[test]
+    fn deeply_nested_lists_do_not_overflow() {
+        const DEPTH: usize = 100_000;
+
+        // [[[...[]...]]], allocated outside of a process heap to avoid growing it
+        let mut cells = Vec::with_capacity(DEPTH);
+        let mut term = OpaqueTerm::NIL;
+        for _ in 0..DEPTH {
+            let mut cell = Cons::new(term, OpaqueTerm::NIL);
+            term = NonNull::from(cell.as_mut()).into();
+            cells.push(cell);
+        }
+
+        let encoded = encode_default(term.into());
+        assert_eq!(encoded.len(), 1 + DEPTH * 6 + 1);
+        assert_eq!(&encoded[1..7], &[108, 0, 0, 0, 1, 108]);
+        assert_eq!(encoded[encoded.len() - 1], 106);
+    }
+}
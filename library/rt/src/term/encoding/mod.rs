@@ -0,0 +1,36 @@
+//! This module implements the Erlang [External Term Format](https://www.erlang.org/doc/apps/erts/erl_ext_dist.html),
+//! as used by `term_to_binary/1`, `binary_to_term/1` and friends.
+mod decode;
+mod encode;
+
+pub use self::decode::{decode, DecodeError, DecodeOptions};
+pub use self::encode::{encode, EncodeError, EncodeOptions, DEFAULT_COMPRESSION_LEVEL};
+
+/// The version byte which prefixes every term in the external term format
+pub const VERSION_NUMBER: u8 = 131;
+
+/// The tags used to identify each type of value in the external term format
+pub(crate) mod tag {
+    pub const NEW_FLOAT_EXT: u8 = 70;
+    pub const BIT_BINARY_EXT: u8 = 77;
+    pub const COMPRESSED: u8 = 80;
+    pub const NEW_PID_EXT: u8 = 88;
+    pub const NEWER_REFERENCE_EXT: u8 = 90;
+    pub const SMALL_INTEGER_EXT: u8 = 97;
+    pub const INTEGER_EXT: u8 = 98;
+    pub const FLOAT_EXT: u8 = 99;
+    pub const ATOM_EXT: u8 = 100;
+    pub const SMALL_TUPLE_EXT: u8 = 104;
+    pub const LARGE_TUPLE_EXT: u8 = 105;
+    pub const NIL_EXT: u8 = 106;
+    pub const STRING_EXT: u8 = 107;
+    pub const LIST_EXT: u8 = 108;
+    pub const BINARY_EXT: u8 = 109;
+    pub const SMALL_BIG_EXT: u8 = 110;
+    pub const LARGE_BIG_EXT: u8 = 111;
+    pub const SMALL_ATOM_EXT: u8 = 115;
+    pub const MAP_EXT: u8 = 116;
+    pub const ATOM_UTF8_EXT: u8 = 118;
+    pub const SMALL_ATOM_UTF8_EXT: u8 = 119;
+    pub const V4_PORT_EXT: u8 = 120;
+}
@@ -0,0 +1,104 @@
+use core::any::TypeId;
+use core::fmt;
+
+use crate::function::ModuleFunctionArity;
+
+use super::{Atom, Term};
+
+/// An external fun, i.e. `fun Module:Function/Arity`
+///
+/// Unlike a `Closure`, an external fun holds no function pointer and no environment, just the name
+/// of the function it refers to. The function is resolved via the dispatch table each time the fun
+/// is called, so an external fun can be constructed for a function which doesn't exist (yet), and
+/// calling it only raises `undef` if the function still doesn't exist at that point.
+///
+/// Two external funs are equal if they refer to the same function, regardless of where or when they
+/// were constructed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(C)]
+pub struct ExternalFun {
+    pub module: Atom,
+    pub function: Atom,
+    pub arity: u8,
+}
+impl ExternalFun {
+    pub const TYPE_ID: TypeId = TypeId::of::<ExternalFun>();
+
+    pub fn new(module: Atom, function: Atom, arity: u8) -> Self {
+        Self {
+            module,
+            function,
+            arity,
+        }
+    }
+
+    /// Returns the identity of the function this fun refers to
+    #[inline]
+    pub fn mfa(&self) -> ModuleFunctionArity {
+        ModuleFunctionArity {
+            module: self.module,
+            function: self.function,
+            arity: self.arity,
+        }
+    }
+}
+impl From<ModuleFunctionArity> for ExternalFun {
+    fn from(mfa: ModuleFunctionArity) -> Self {
+        Self::new(mfa.module, mfa.function, mfa.arity)
+    }
+}
+impl TryFrom<Term> for ExternalFun {
+    type Error = ();
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        match term {
+            Term::ExternalFun(fun) => Ok(*fun),
+            _ => Err(()),
+        }
+    }
+}
+impl fmt::Display for ExternalFun {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "fun {}:{}/{}", self.module, self.function, self.arity)
+    }
+}
+impl crate::cmp::ExactEq for ExternalFun {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::format;
+
+    fn fun(module: &str, function: &str, arity: u8) -> ExternalFun {
+        ExternalFun::new(
+            Atom::try_from(module).unwrap(),
+            Atom::try_from(function).unwrap(),
+            arity,
+        )
+    }
+
+    #[test]
+    fn external_funs_are_equal_by_mfa() {
+        assert_eq!(fun("erlang", "abs", 1), fun("erlang", "abs", 1));
+        assert_ne!(fun("erlang", "abs", 1), fun("erlang", "abs", 2));
+        assert_ne!(fun("erlang", "abs", 1), fun("erlang", "max", 1));
+        assert_ne!(fun("erlang", "abs", 1), fun("math", "abs", 1));
+    }
+
+    #[test]
+    fn external_funs_are_ordered_by_module_then_function_then_arity() {
+        assert!(fun("a", "z", 9) < fun("b", "a", 0));
+        assert!(fun("a", "a", 9) < fun("a", "b", 0));
+        assert!(fun("a", "a", 0) < fun("a", "a", 1));
+    }
+
+    #[test]
+    fn external_funs_display_as_fun_expressions() {
+        assert_eq!(format!("{}", fun("erlang", "abs", 1)), "fun erlang:abs/1");
+        assert_eq!(
+            format!("{}", fun("Elixir.Enum", "map", 2)),
+            "fun 'Elixir.Enum':map/2"
+        );
+    }
+}
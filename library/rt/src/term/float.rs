@@ -0,0 +1,156 @@
+use alloc::format;
+use alloc::string::String;
+
+/// The number of digits after the decimal point in the default format of `float_to_binary/1` and
+/// `float_to_list/1`
+pub const DEFAULT_SCIENTIFIC_DIGITS: u8 = 20;
+/// The largest number of digits allowed with the `{scientific, Digits}` option
+pub const MAX_SCIENTIFIC_DIGITS: u8 = 249;
+/// The largest number of digits allowed with the `{decimals, Digits}` option
+pub const MAX_DECIMALS: u8 = 253;
+
+/// BEAM formats floats with `{decimals, Digits}` into a static buffer of 256 bytes, including the
+/// terminating NUL, and raises `badarg` for any float which does not fit
+const MAX_DECIMALS_LEN: usize = 255;
+
+/// How a float is written out by `float_to_binary/2`, `float_to_list/2`, and the float directives
+/// of `io:format`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// One digit before the decimal point and `digits` after it, followed by a signed exponent
+    /// of at least two digits, i.e. `%.*e` in C
+    Scientific(u8),
+    /// `digits` after the decimal point, i.e. `%.*f` in C
+    ///
+    /// When `compact`, trailing zeros after the decimal point are trimmed, keeping at least one
+    Decimals { digits: u8, compact: bool },
+}
+impl Default for FloatFormat {
+    #[inline]
+    fn default() -> Self {
+        Self::Scientific(DEFAULT_SCIENTIFIC_DIGITS)
+    }
+}
+impl FloatFormat {
+    /// Writes out `value` in this format, correctly rounded from its exact binary value.
+    ///
+    /// Returns `None` if `value` is NaN or infinite, or if it has too many digits before the
+    /// decimal point to be written out with `Decimals`.
+    pub fn format(self, value: f64) -> Option<String> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        match self {
+            Self::Scientific(digits) => Some(scientific(value, digits as usize)),
+            Self::Decimals { digits, compact } => {
+                let mut formatted = format!("{:.*}", digits as usize, value);
+                if formatted.len() > MAX_DECIMALS_LEN {
+                    return None;
+                }
+                if compact {
+                    trim_trailing_zeros(&mut formatted);
+                }
+                Some(formatted)
+            }
+        }
+    }
+}
+
+fn scientific(value: f64, digits: usize) -> String {
+    // Rust writes the exponent as `e-5` or `e8`, where C writes `e-05` or `e+08`
+    let formatted = format!("{:.*e}", digits, value);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+    let (sign, exponent) = match exponent.strip_prefix('-') {
+        Some(exponent) => ('-', exponent),
+        None => ('+', exponent),
+    };
+
+    format!("{}e{}{:0>2}", mantissa, sign, exponent)
+}
+
+fn trim_trailing_zeros(formatted: &mut String) {
+    if let Some(point) = formatted.find('.') {
+        let len = formatted.trim_end_matches('0').len().max(point + 2);
+        formatted.truncate(len);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn format(value: f64, format: FloatFormat) -> String {
+        format.format(value).unwrap()
+    }
+
+    fn decimals(digits: u8) -> FloatFormat {
+        FloatFormat::Decimals {
+            digits,
+            compact: false,
+        }
+    }
+
+    fn compact(digits: u8) -> FloatFormat {
+        FloatFormat::Decimals {
+            digits,
+            compact: true,
+        }
+    }
+
+    #[test]
+    fn default_format_matches_otp() {
+        let default = FloatFormat::default();
+
+        assert_eq!(format(0.1, default), "1.00000000000000005551e-01");
+        assert_eq!(format(1.0e-5, default), "1.00000000000000008180e-05");
+        assert_eq!(format(123456789.0, default), "1.23456789000000000000e+08");
+        assert_eq!(format(1.0, default), "1.00000000000000000000e+00");
+        assert_eq!(format(-0.0, default), "-0.00000000000000000000e+00");
+        assert_eq!(format(1.0e300, default), "1.00000000000000005250e+300");
+    }
+
+    #[test]
+    fn scientific_matches_otp() {
+        assert_eq!(format(123456789.0, FloatFormat::Scientific(3)), "1.235e+08");
+        assert_eq!(format(0.1, FloatFormat::Scientific(0)), "1e-01");
+        assert_eq!(format(-2.5e-10, FloatFormat::Scientific(2)), "-2.50e-10");
+    }
+
+    #[test]
+    fn decimals_matches_otp() {
+        assert_eq!(format(0.1, decimals(4)), "0.1000");
+        assert_eq!(format(0.1, decimals(20)), "0.10000000000000000555");
+        assert_eq!(format(1.0e-5, decimals(3)), "0.000");
+        assert_eq!(format(123456789.0, decimals(2)), "123456789.00");
+        assert_eq!(format(123456789.0, decimals(0)), "123456789");
+        assert_eq!(format(-7.126, decimals(2)), "-7.13");
+    }
+
+    #[test]
+    fn compact_decimals_matches_otp() {
+        assert_eq!(format(0.1, compact(4)), "0.1");
+        assert_eq!(format(7.0, compact(4)), "7.0");
+        assert_eq!(format(7.12, compact(4)), "7.12");
+        assert_eq!(format(1.0e-5, compact(10)), "0.00001");
+        assert_eq!(format(123456789.0, compact(0)), "123456789");
+    }
+
+    #[test]
+    fn floats_which_do_not_fit_are_rejected() {
+        assert_eq!(decimals(0).format(1.0e300), None);
+        assert!(decimals(MAX_DECIMALS).format(1.0).is_some());
+        assert!(FloatFormat::Scientific(MAX_SCIENTIFIC_DIGITS)
+            .format(-f64::MAX)
+            .is_some());
+    }
+
+    #[test]
+    fn non_finite_floats_are_rejected() {
+        let default = FloatFormat::default();
+
+        assert_eq!(default.format(f64::NAN), None);
+        assert_eq!(default.format(f64::INFINITY), None);
+        assert_eq!(decimals(2).format(f64::NEG_INFINITY), None);
+    }
+}
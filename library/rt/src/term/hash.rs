@@ -0,0 +1,524 @@
+use alloc::vec::Vec;
+
+use firefly_binary::Bitstring;
+use firefly_number::Sign;
+
+use crate::term::{atoms, Atom, Port, Term};
+
+/// The golden ratio, an arbitrary value used to seed each step of the hash
+const HCONST: u32 = 0x9e3779b9;
+
+/// `HCONST * n mod 2^32`, used to distinguish the types of value being hashed
+const fn hconst(n: u32) -> u32 {
+    HCONST.wrapping_mul(n)
+}
+
+const HCONST_2: u32 = hconst(2);
+const HCONST_3: u32 = hconst(3);
+const HCONST_4: u32 = hconst(4);
+const HCONST_5: u32 = hconst(5);
+const HCONST_6: u32 = hconst(6);
+const HCONST_7: u32 = hconst(7);
+const HCONST_9: u32 = hconst(9);
+const HCONST_10: u32 = hconst(10);
+const HCONST_11: u32 = hconst(11);
+const HCONST_12: u32 = hconst(12);
+const HCONST_13: u32 = hconst(13);
+const HCONST_14: u32 = hconst(14);
+const HCONST_15: u32 = hconst(15);
+const HCONST_16: u32 = hconst(16);
+const HCONST_19: u32 = hconst(19);
+const HCONST_20: u32 = hconst(20);
+
+/// The value BEAM hashes in place of `[]`
+const NIL_DEF: u32 = 2;
+
+/// Computes the portable hash of `term`, as used by `erlang:phash2/1,2`.
+///
+/// This is a port of `make_hash2` from ERTS, and produces the same values as BEAM for atoms,
+/// numbers, binaries, lists, tuples and maps, independent of platform or word size. The full
+/// 32-bit hash is returned, it is up to the caller to reduce it to the requested range.
+///
+/// Pids, ports, references and funs have no portable identity across runtimes, so while
+/// they hash consistently, they do not match the values produced by BEAM.
+///
+/// The term is traversed using an explicit stack, so arbitrarily long or deep terms can
+/// be hashed without risk of overflowing the native stack.
+pub fn phash2(term: Term) -> u32 {
+    Hasher::default().hash(term)
+}
+
+/// Work remaining to be done by the hasher
+enum Work {
+    Term(Term),
+    /// A key/value pair has been hashed, and must be folded into the map hash
+    MapPair,
+    /// All pairs of a map have been hashed, restoring the given state
+    MapTail {
+        hash: u32,
+        xor_pairs: u32,
+    },
+}
+
+#[derive(Default)]
+struct Hasher {
+    hash: u32,
+    xor_pairs: u32,
+}
+impl Hasher {
+    fn hash(mut self, term: Term) -> u32 {
+        let mut stack = Vec::with_capacity(16);
+        stack.push(Work::Term(term));
+
+        while let Some(work) = stack.pop() {
+            match work {
+                Work::Term(term) => self.term(term, &mut stack),
+                Work::MapPair => {
+                    self.xor_pairs ^= self.hash;
+                    self.hash = 0;
+                }
+                Work::MapTail { hash, xor_pairs } => {
+                    self.hash = hash;
+                    self.mix_u32(self.xor_pairs, HCONST_19);
+                    self.xor_pairs = xor_pairs;
+                }
+            }
+        }
+
+        self.hash
+    }
+
+    fn term(&mut self, term: Term, stack: &mut Vec<Work>) {
+        match term {
+            Term::None => self.mix_u32(0, HCONST),
+            Term::Nil => self.mix_u32(NIL_DEF, HCONST_2),
+            Term::Bool(b) => self.atom(if b { atoms::True } else { atoms::False }),
+            Term::Atom(a) => self.atom(a),
+            Term::Int(i) => self.integer(i),
+            Term::BigInt(i) => {
+                let (sign, digits) = i.to_u32_digits();
+                self.big_digits(sign == Sign::Minus, &digits);
+            }
+            Term::Float(f) => {
+                // 0.0 and -0.0 compare equal, so must hash the same
+                let f = f.inner();
+                let bits = if f == 0.0 { 0 } else { f.to_bits() };
+                self.mix_u32_pair((bits >> 32) as u32, bits as u32, HCONST_12);
+            }
+            Term::Cons(ptr) => {
+                let mut cons = unsafe { ptr.as_ref() };
+                // Runs of bytes, as found in strings, are hashed four at a time
+                let mut packed = 0u32;
+                let mut count = 0;
+                loop {
+                    let Some(byte) = as_byte(cons.head()) else {
+                        if count > 0 {
+                            self.mix_u32(packed, HCONST_4);
+                        }
+                        stack.push(Work::Term(cons.tail()));
+                        stack.push(Work::Term(cons.head()));
+                        return;
+                    };
+                    packed = (packed << 8) + byte;
+                    if count == 3 {
+                        self.mix_u32(packed, HCONST_4);
+                        packed = 0;
+                        count = 0;
+                    } else {
+                        count += 1;
+                    }
+                    match cons.tail() {
+                        Term::Cons(next) => cons = unsafe { next.as_ref() },
+                        tail => {
+                            if count > 0 {
+                                self.mix_u32(packed, HCONST_4);
+                            }
+                            stack.push(Work::Term(tail));
+                            return;
+                        }
+                    }
+                }
+            }
+            Term::Tuple(ptr) => {
+                let tuple = unsafe { ptr.as_ref() };
+                self.mix_u32(tuple.len() as u32, HCONST_9);
+                stack.extend(
+                    tuple
+                        .as_slice()
+                        .iter()
+                        .rev()
+                        .map(|t| Work::Term((*t).into())),
+                );
+            }
+            Term::Map(map) => {
+                let size = map.size();
+                self.mix_u32(size as u32, HCONST_16);
+                if size == 0 {
+                    return;
+                }
+                // Each pair is hashed independently and the results combined with xor, so
+                // that the hash does not depend on the order in which pairs are visited
+                stack.push(Work::MapTail {
+                    hash: self.hash,
+                    xor_pairs: self.xor_pairs,
+                });
+                self.hash = 0;
+                self.xor_pairs = 0;
+                for (key, value) in map.iter() {
+                    stack.push(Work::MapPair);
+                    stack.push(Work::Term(*value));
+                    stack.push(Work::Term(*key));
+                }
+            }
+            Term::Closure(fun) => {
+                self.mix_u32_pair(atom_hash(fun.module), atom_hash(fun.name), HCONST_20);
+                self.mix_u32(fun.arity as u32, HCONST_20);
+                stack.extend(fun.env().iter().rev().map(|t| Work::Term((*t).into())));
+            }
+            Term::ExternalFun(fun) => {
+                self.mix_u32_pair(fun.arity as u32, atom_hash(fun.module), HCONST);
+                self.mix_u32(atom_hash(fun.function), HCONST_14);
+            }
+            Term::Pid(pid) => {
+                let id = pid.id();
+                self.mix_u32_pair(id.number(), id.serial(), HCONST_5);
+            }
+            Term::Port(port) => {
+                let id = match &*port {
+                    Port::Local { id } => *id,
+                    Port::External { id, .. } => *id,
+                };
+                let id = id.as_u64();
+                self.mix_u32_pair(id as u32, (id >> 32) as u32, HCONST_6);
+            }
+            Term::Reference(reference) => {
+                let id = reference.id().as_u64();
+                self.mix_u32_pair(id as u32, (id >> 32) as u32, HCONST_7);
+            }
+            Term::HeapBinary(_)
+            | Term::RcBinary(_)
+            | Term::RefBinary(_)
+            | Term::ConstantBinary(_) => self.bitstring(term.as_bitstring().unwrap()),
+        }
+    }
+
+    fn atom(&mut self, atom: Atom) {
+        let hash = atom_hash(atom);
+        // BEAM uses the atom hash as-is when it is the first value hashed
+        if self.hash == 0 {
+            self.hash = hash;
+        } else {
+            self.mix_u32(hash, HCONST_3);
+        }
+    }
+
+    fn integer(&mut self, i: i64) {
+        // Integers which would be bignums on a 32-bit system are hashed as such
+        if !(-(1 << 27)..(1 << 27)).contains(&i) {
+            let magnitude = i.unsigned_abs();
+            return self.big_digits(i < 0, &[magnitude as u32, (magnitude >> 32) as u32]);
+        }
+        let i = i as i32;
+        if i < 0 {
+            self.mix_u32(i.unsigned_abs(), HCONST);
+        }
+        self.mix_u32(i as u32, HCONST);
+    }
+
+    /// Hashes the magnitude of a big integer, given as little-endian 32-bit digits
+    fn big_digits(&mut self, negative: bool, digits: &[u32]) {
+        let con = if negative { HCONST_10 } else { HCONST_11 };
+        if digits.is_empty() {
+            return self.mix_u32_pair(0, 0, con);
+        }
+        for pair in digits.chunks(2) {
+            let low = pair[0];
+            let high = pair.get(1).copied().unwrap_or(0);
+            self.mix_u32_pair(low, high, con);
+        }
+    }
+
+    fn bitstring(&mut self, bits: &dyn Bitstring) {
+        let con = HCONST_13.wrapping_add(self.hash);
+        let bytes = bits.select_all().to_bytes();
+        let trailing_bits = bits.bit_size() % 8;
+        if bytes.is_empty() {
+            self.hash = con;
+            return;
+        }
+        let whole = if trailing_bits == 0 {
+            &bytes[..]
+        } else {
+            &bytes[..bytes.len() - 1]
+        };
+        self.hash = block_hash(whole, con);
+        if trailing_bits > 0 {
+            let last = bytes[bytes.len() - 1] >> (8 - trailing_bits);
+            self.mix_u32_pair(trailing_bits as u32, last as u32, HCONST_15);
+        }
+    }
+
+    #[inline]
+    fn mix_u32(&mut self, value: u32, con: u32) {
+        self.mix_u32_pair(value, 0, con)
+    }
+
+    #[inline]
+    fn mix_u32_pair(&mut self, x: u32, y: u32, con: u32) {
+        let mut a = con.wrapping_add(x);
+        let mut b = con.wrapping_add(y);
+        mix(&mut a, &mut b, &mut self.hash);
+    }
+}
+
+/// Returns the value of `term` if it is an integer in the range `0..=255`
+#[inline]
+fn as_byte(term: Term) -> Option<u32> {
+    match term {
+        Term::Int(i) if (0..=255).contains(&i) => Some(i as u32),
+        _ => None,
+    }
+}
+
+/// The hash of an atom name, as computed by the BEAM atom table.
+///
+/// This is `hashpjw` over the name as Latin-1 where possible, so that atoms hash the same
+/// regardless of whether the table stores them as Latin-1 or UTF-8.
+fn atom_hash(atom: Atom) -> u32 {
+    let mut bytes = atom.as_str().as_bytes();
+    let mut h = 0u32;
+    while let Some((&first, rest)) = bytes.split_first() {
+        let mut v = first as u32;
+        bytes = rest;
+        if let Some((&next, rest)) = bytes.split_first() {
+            if (first & 0xfe) == 0xc2 && (next & 0xc0) == 0x80 {
+                v = ((v << 6) | (next & 0x3f) as u32) & 0xff;
+                bytes = rest;
+            }
+        }
+        h = (h << 4).wrapping_add(v);
+        let g = h & 0xf0000000;
+        if g != 0 {
+            h ^= g >> 24;
+            h ^= g;
+        }
+    }
+    h
+}
+
+/// Bob Jenkins' lookup2 hash over `bytes`, seeded with `initval`
+fn block_hash(bytes: &[u8], initval: u32) -> u32 {
+    let mut a = HCONST;
+    let mut b = HCONST;
+    let mut c = initval;
+
+    let mut chunks = bytes.chunks_exact(12);
+    for chunk in &mut chunks {
+        a = a.wrapping_add(u32::from_le_bytes(chunk[0..4].try_into().unwrap()));
+        b = b.wrapping_add(u32::from_le_bytes(chunk[4..8].try_into().unwrap()));
+        c = c.wrapping_add(u32::from_le_bytes(chunk[8..12].try_into().unwrap()));
+        mix(&mut a, &mut b, &mut c);
+    }
+
+    // The low byte of `c` is reserved for the length, so the remainder starts at the second
+    let mut rest = [0u8; 12];
+    let remainder = chunks.remainder();
+    rest[..remainder.len()].copy_from_slice(remainder);
+    c = c.wrapping_add(bytes.len() as u32);
+    a = a.wrapping_add(u32::from_le_bytes(rest[0..4].try_into().unwrap()));
+    b = b.wrapping_add(u32::from_le_bytes(rest[4..8].try_into().unwrap()));
+    c = c.wrapping_add(u32::from_le_bytes(rest[8..12].try_into().unwrap()) << 8);
+    mix(&mut a, &mut b, &mut c);
+
+    c
+}
+
+#[inline]
+fn mix(a: &mut u32, b: &mut u32, c: &mut u32) {
+    *a = a.wrapping_sub(*b).wrapping_sub(*c) ^ (*c >> 13);
+    *b = b.wrapping_sub(*c).wrapping_sub(*a) ^ (*a << 8);
+    *c = c.wrapping_sub(*a).wrapping_sub(*b) ^ (*b >> 13);
+    *a = a.wrapping_sub(*b).wrapping_sub(*c) ^ (*c >> 12);
+    *b = b.wrapping_sub(*c).wrapping_sub(*a) ^ (*a << 16);
+    *c = c.wrapping_sub(*a).wrapping_sub(*b) ^ (*b >> 5);
+    *a = a.wrapping_sub(*b).wrapping_sub(*c) ^ (*c >> 3);
+    *b = b.wrapping_sub(*c).wrapping_sub(*a) ^ (*a << 10);
+    *c = c.wrapping_sub(*a).wrapping_sub(*b) ^ (*b >> 15);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use firefly_alloc::gc::GcBox;
+    use firefly_number::BigInt;
+
+    use crate::process::Process;
+    use crate::term::{BinaryData, BitSlice, Cons, ListBuilder, Map, OpaqueTerm, ProcessId, Tuple};
+
+    /// The range used by `erlang:phash2/1`
+    const RANGE: u32 = 1 << 27;
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(Atom::try_from(name).unwrap())
+    }
+
+    fn list(elements: &[Term], process: &Process) -> Term {
+        let mut builder = ListBuilder::new(process);
+        for element in elements.iter().rev() {
+            builder.push(*element).unwrap();
+        }
+        builder.finish().map(Term::Cons).unwrap_or(Term::Nil)
+    }
+
+    fn tuple(elements: &[Term], process: &Process) -> Term {
+        let elements = elements
+            .iter()
+            .map(|t| (*t).into())
+            .collect::<Vec<OpaqueTerm>>();
+        Tuple::from_slice(&elements, process).unwrap().into()
+    }
+
+    fn map(pairs: &[(Term, Term)], process: &Process) -> Term {
+        let map = Map::new_from_iter(pairs.iter().copied());
+        Term::Map(GcBox::new_in(map, process).unwrap())
+    }
+
+    fn binary(bytes: &[u8], process: &Process) -> Term {
+        let mut bin = BinaryData::with_capacity_small(bytes.len(), process).unwrap();
+        bin.copy_from_slice(bytes);
+        let bin: OpaqueTerm = bin.into();
+        bin.into()
+    }
+
+    fn big(i: i128, process: &Process) -> Term {
+        Term::BigInt(GcBox::new_in(BigInt::from(i), process).unwrap())
+    }
+
+    /// The values of `erlang:phash2/1` for each term, as computed by `make_hash2` in OTP 25
+    #[test]
+    fn matches_otp() {
+        let process = process();
+        let p = &process;
+
+        let abc = [Term::Int(97), Term::Int(98), Term::Int(99)];
+        let abcd = [abc[0], abc[1], abc[2], Term::Int(100)];
+        let improper = {
+            let cons = Cons::new_in(p).unwrap();
+            unsafe {
+                cons.as_uninit_mut().write(Cons::cons(atom("a"), atom("b")));
+            }
+            Term::Cons(cons)
+        };
+        let bits = {
+            let bytes = [0b0010_0000];
+            let slice = unsafe { BitSlice::new(OpaqueTerm::NONE, &bytes, 0, 3) };
+            let slice = GcBox::new_in(slice, p).unwrap();
+            // Hash the bits before `bytes` goes out of scope
+            phash2(Term::RefBinary(slice)) % RANGE
+        };
+        let nested = tuple(
+            &[
+                list(&[atom("a")], p),
+                map(&[(atom("k"), list(&[Term::Int(1), Term::Int(2)], p))], p),
+                binary(b"x", p),
+            ],
+            p,
+        );
+
+        let cases = [
+            (atom("a"), 97),
+            (atom("hello"), 7258927),
+            (Term::Bool(true), 506293),
+            (atom(""), 0),
+            (atom("é"), 233),
+            (atom("this_is_a_rather_long_atom_name"), 20874341),
+            (Term::Int(0), 88723725),
+            (Term::Int(1), 2614250),
+            (Term::Int(-1), 44071773),
+            (Term::Int(255), 44734653),
+            (Term::Int((1 << 27) - 1), 112602999),
+            (Term::Int(1 << 27), 12354923),
+            (Term::Int(-(1 << 27)), 69672967),
+            (Term::Int(-(1 << 27) - 1), 76739502),
+            (Term::Int(1 << 40), 13893919),
+            (big(1 << 64, p), 103122609),
+            (big(-(1 << 70), p), 20532417),
+            (Term::Float(0.0.into()), 20875736),
+            (Term::Float((-0.0).into()), 20875736),
+            (Term::Float(1.5.into()), 10380315),
+            (Term::Float((-3.25e10).into()), 75615883),
+            (binary(b"", p), 13708901),
+            (binary(b"abc", p), 98228475),
+            (binary(b"hello, world!", p), 88856418),
+            (Term::Nil, 113427502),
+            (list(&abc, p), 117343302),
+            (list(&abcd, p), 7922743),
+            (improper, 74710280),
+            (list(&[Term::Int(1000), atom("a")], p), 471936),
+            (tuple(&[], p), 87486268),
+            (tuple(&[atom("a"), Term::Int(1)], p), 72425156),
+            (map(&[], p), 39679005),
+            (
+                map(&[(atom("a"), Term::Int(1)), (atom("b"), Term::Int(2))], p),
+                103634663,
+            ),
+            (nested, 14875435),
+        ];
+        for (term, expected) in cases {
+            assert_eq!(phash2(term) % RANGE, expected, "phash2({})", term);
+        }
+        // <<1:3>>
+        assert_eq!(bits, 73037028);
+    }
+
+    #[test]
+    fn equal_terms_hash_equally() {
+        let process = process();
+        // Integers which are only bignums on some platforms
+        assert_eq!(phash2(Term::Int(1 << 40)), phash2(big(1 << 40, &process)));
+        assert_eq!(
+            phash2(Term::Int(-(1 << 50))),
+            phash2(big(-(1 << 50), &process))
+        );
+        // Map hashes do not depend on the order of insertion
+        let ab = map(
+            &[(atom("a"), Term::Int(1)), (atom("b"), Term::Int(2))],
+            &process,
+        );
+        let ba = map(
+            &[(atom("b"), Term::Int(2)), (atom("a"), Term::Int(1))],
+            &process,
+        );
+        assert_eq!(phash2(ab), phash2(ba));
+    }
+
+    #[test]
+    fn long_lists_do_not_overflow_the_stack() {
+        const LEN: usize = 1_000_000;
+
+        let process = process();
+        // A list of non-bytes, so that each element takes the slow path
+        let mut builder = ListBuilder::new(&process);
+        for _ in 0..LEN {
+            builder.push(atom("x")).unwrap();
+        }
+        let long = Term::Cons(builder.finish().unwrap());
+        phash2(long);
+
+        // Deeply nested lists, i.e. [[[...]]]
+        let mut nested = Term::Nil;
+        for _ in 0..LEN {
+            let cons = Cons::new_in(&process).unwrap();
+            unsafe {
+                cons.as_uninit_mut().write(Cons::cons(nested, Term::Nil));
+            }
+            nested = Term::Cons(cons);
+        }
+        phash2(nested);
+    }
+}
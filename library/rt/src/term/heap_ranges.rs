@@ -0,0 +1,36 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use firefly_alloc::heap::Heap;
+
+/// A registry of the ranges of memory in which boxed terms are allocated
+///
+/// This is used to validate the pointers in terms which may not be well-formed before they are
+/// dereferenced, see `OpaqueTerm::try_decode`. The registered ranges should only contain boxed
+/// terms, e.g. process heaps, heap fragments, or the memory holding literals.
+#[derive(Debug, Clone, Default)]
+pub struct HeapRanges {
+    ranges: Vec<Range<usize>>,
+}
+impl HeapRanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `range` as memory in which boxed terms are allocated
+    pub fn register(&mut self, range: Range<*const u8>) {
+        self.ranges
+            .push((range.start as usize)..(range.end as usize));
+    }
+
+    /// Registers the memory of `heap`
+    pub fn register_heap<H: Heap>(&mut self, heap: &H) {
+        self.register(heap.as_ptr_range());
+    }
+
+    /// Returns true if `ptr` points into one of the registered ranges
+    pub fn contains<T: ?Sized>(&self, ptr: *const T) -> bool {
+        let addr = ptr.cast::<u8>() as usize;
+        self.ranges.iter().any(|range| range.contains(&addr))
+    }
+}
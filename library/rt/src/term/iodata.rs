@@ -0,0 +1,573 @@
+use alloc::alloc::{AllocError, Allocator, Global};
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
+use firefly_alloc::heap::Heap;
+use firefly_alloc::rc::Rc;
+use firefly_binary::Bitstring;
+
+use super::{BinaryData, Cons, OpaqueTerm, Term};
+
+/// A piece of iodata, as visited by `Term::iodata_fold`
+#[derive(Copy, Clone)]
+pub enum IoChunk<'a> {
+    Byte(u8),
+    /// A binary, i.e. a bitstring with a number of bits divisible by 8
+    Binary(&'a dyn Bitstring),
+}
+impl IoChunk<'_> {
+    /// Returns the size of this chunk in bytes
+    pub fn byte_size(&self) -> usize {
+        match self {
+            Self::Byte(_) => 1,
+            Self::Binary(bits) => bits.byte_size(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IodataError {
+    /// The term is not iodata; this is the first element or tail found in it which is not a
+    /// byte, binary or list
+    Badarg(Term),
+    /// Could not allocate enough memory to store the binary
+    AllocError,
+}
+impl From<AllocError> for IodataError {
+    #[inline]
+    fn from(_: AllocError) -> Self {
+        Self::AllocError
+    }
+}
+
+/// A piece of chardata, as visited by `Term::chardata_fold`
+#[derive(Copy, Clone)]
+pub enum CharChunk<'a> {
+    /// An integer element of a list, which is only a character if it is a valid codepoint in the
+    /// encoding of the chardata
+    Char(i64),
+    /// A binary, i.e. a bitstring with a number of bits divisible by 8
+    Binary(&'a dyn Bitstring),
+}
+
+/// What remains of chardata when `Term::chardata_fold` is stopped, i.e. the element it was
+/// stopped at, followed by everything after it
+#[derive(Debug)]
+pub struct ChardataRest {
+    /// The element the fold was stopped at, or what remains of it
+    element: Term,
+    /// The rest of the list `element` is an element of, or `None` if it is the tail of a list,
+    /// or the term which was folded over
+    tail: Option<Term>,
+    /// The tails of the lists `element` is nested in, outermost first
+    pending: Vec<OpaqueTerm>,
+}
+impl ChardataRest {
+    /// Builds the rest of the chardata as a term, allocating the lists it needs in `alloc`.
+    ///
+    /// This is `element` itself when nothing follows it, and otherwise a deep list of `element`
+    /// and the rest of the lists it is nested in, which is the same chardata as what remained of
+    /// the original, though not necessarily of the same shape.
+    pub fn to_term<A: Allocator>(&self, alloc: A) -> Result<Term, AllocError> {
+        let mut rest = match self.tail {
+            None => self.element,
+            Some(tail) => cons(self.element, tail, &alloc)?,
+        };
+        let outer = self.pending.iter().map(|tail| Term::from(*tail));
+        let outer = outer.filter(|tail| !tail.is_nil()).collect::<Vec<_>>();
+        if !outer.is_empty() {
+            let mut list = Term::Nil;
+            for tail in outer {
+                list = cons(tail, list, &alloc)?;
+            }
+            rest = cons(rest, list, &alloc)?;
+        }
+        Ok(rest)
+    }
+}
+
+fn cons<A: Allocator>(head: Term, tail: Term, alloc: A) -> Result<Term, AllocError> {
+    let ptr = Cons::new_in(alloc)?;
+    unsafe {
+        ptr.as_ptr().write(Cons::cons(head, tail));
+    }
+    Ok(Term::Cons(ptr))
+}
+
+impl Term {
+    /// Visits the elements of this term as a possibly deep, possibly improper list, in order,
+    /// folding them into `init` with `f`, which is also told whether each is the tail of a list.
+    ///
+    /// Elements which are lists are visited in place, rather than passed to `f`, and so are tails
+    /// which are lists, while a term which is not a list is passed to `f` as if it were the tail
+    /// of one. Nested lists are walked iteratively, with an explicit stack of the tails still to
+    /// be visited, so deep nesting cannot overflow the native stack.
+    ///
+    /// `f` stops the fold by returning `ControlFlow::Break` with a result and what remains of the
+    /// element it was given, which is returned along with the rest of the list after it.
+    fn fold_elements<A, B, F>(self, init: A, mut f: F) -> Result<A, (B, ChardataRest)>
+    where
+        F: FnMut(A, Term, bool) -> ControlFlow<(B, Term), A>,
+    {
+        let mut pending: Vec<OpaqueTerm> = Vec::new();
+        let mut acc = init;
+        // Always either a list, or the tail of a list, which includes the top-level term
+        let mut list = self;
+
+        loop {
+            match list {
+                Term::Nil => (),
+                Term::Cons(ptr) => {
+                    let cons = unsafe { ptr.as_ref() };
+                    let head = cons.head();
+                    match head {
+                        Term::Nil | Term::Cons(_) => {
+                            // The tail is visited once the nested list is done
+                            pending.push(cons.tail);
+                            list = head;
+                            continue;
+                        }
+                        head => match f(acc, head, false) {
+                            ControlFlow::Continue(next) => acc = next,
+                            ControlFlow::Break((result, element)) => {
+                                let rest = ChardataRest {
+                                    element,
+                                    tail: Some(cons.tail()),
+                                    pending,
+                                };
+                                return Err((result, rest));
+                            }
+                        },
+                    }
+                    list = cons.tail();
+                    continue;
+                }
+                tail => match f(acc, tail, true) {
+                    ControlFlow::Continue(next) => acc = next,
+                    ControlFlow::Break((result, element)) => {
+                        let rest = ChardataRest {
+                            element,
+                            tail: None,
+                            pending,
+                        };
+                        return Err((result, rest));
+                    }
+                },
+            }
+
+            match pending.pop() {
+                None => return Ok(acc),
+                Some(tail) => list = tail.into(),
+            }
+        }
+    }
+
+    /// Visits each byte and binary of this term as iodata, in order, folding them into `init`
+    /// with `f`.
+    ///
+    /// iodata is a binary, or a possibly nested list of bytes and binaries, any of which may be
+    /// improper as long as the tail is a binary. Deep nesting cannot overflow the native stack.
+    ///
+    /// Returns `Err(IodataError::Badarg(term))` with the first `term` that is not allowed in
+    /// iodata, after `f` has been called for everything before it.
+    pub fn iodata_fold<A, F>(self, init: A, mut f: F) -> Result<A, IodataError>
+    where
+        F: FnMut(A, IoChunk<'_>) -> A,
+    {
+        self.fold_elements(init, |acc, element, is_tail| match element {
+            Term::Int(byte @ 0..=255) if !is_tail => {
+                ControlFlow::Continue(f(acc, IoChunk::Byte(byte as u8)))
+            }
+            element => match element.as_bitstring() {
+                Some(bits) if bits.is_binary() => {
+                    ControlFlow::Continue(f(acc, IoChunk::Binary(bits)))
+                }
+                _ => ControlFlow::Break(((), element)),
+            },
+        })
+        .map_err(|((), rest)| IodataError::Badarg(rest.element))
+    }
+
+    /// Visits each character and binary of this term as chardata, in order, folding them into
+    /// `init` with `f`.
+    ///
+    /// chardata is shaped like iodata, but every integer element of its lists is passed to `f`
+    /// as a character, since which characters are valid depends on the encoding of the chardata,
+    /// as does how its binaries are decoded.
+    ///
+    /// `f` stops the fold by returning `ControlFlow::Break` with the accumulator and what remains
+    /// of the chunk it was given, e.g. the part of a binary which could not be decoded. The fold
+    /// is stopped in the same way, with the accumulator unchanged, at the first term which is
+    /// neither a character nor a binary. Either way, the accumulator is returned with the rest
+    /// of the chardata, starting with what remained of the chunk or term it was stopped at.
+    pub fn chardata_fold<A, F>(self, init: A, mut f: F) -> Result<A, (A, ChardataRest)>
+    where
+        F: FnMut(A, CharChunk<'_>) -> ControlFlow<(A, Term), A>,
+    {
+        self.fold_elements(init, |acc, element, is_tail| match element {
+            Term::Int(c) if !is_tail => f(acc, CharChunk::Char(c)),
+            element => match element.as_bitstring() {
+                Some(bits) if bits.is_binary() => f(acc, CharChunk::Binary(bits)),
+                _ => ControlFlow::Break((acc, element)),
+            },
+        })
+    }
+
+    /// Returns the size in bytes of the binary this term would be as iodata, as
+    /// `erlang:iolist_size/1` does.
+    pub fn iodata_size(self) -> Result<usize, IodataError> {
+        self.iodata_fold(0, |size, chunk| size + chunk.byte_size())
+    }
+
+    /// Concatenates this term as iodata into a new binary, as `erlang:iolist_to_binary/1` does.
+    ///
+    /// The binary is allocated on `heap` if it is small enough, otherwise it is reference-counted.
+    pub fn iodata_to_binary<H: Heap>(self, heap: H) -> Result<Term, IodataError> {
+        let size = self.iodata_size()?;
+
+        if size <= BinaryData::MAX_HEAP_BYTES {
+            let mut gcbox = BinaryData::with_capacity_small(size, heap)?;
+            self.iodata_write(&mut gcbox)?;
+            Ok(gcbox.into())
+        } else {
+            let mut rc = BinaryData::with_capacity_large(size, Global)?;
+            self.iodata_write(unsafe { Rc::get_mut_unchecked(&mut rc) })?;
+            Ok(Rc::into_weak(rc).into())
+        }
+    }
+
+    /// Writes this term as iodata into `data`, which must be exactly its `iodata_size`
+    fn iodata_write(self, data: &mut BinaryData) -> Result<(), IodataError> {
+        let written = self.iodata_fold(0, |offset, chunk| match chunk {
+            IoChunk::Byte(byte) => {
+                data[offset] = byte;
+                offset + 1
+            }
+            IoChunk::Binary(bits) => {
+                let len = bits.byte_size();
+                let dest = &mut data[offset..(offset + len)];
+                if bits.is_aligned() {
+                    dest.copy_from_slice(unsafe { bits.as_bytes_unchecked() });
+                } else {
+                    for (dest, byte) in dest.iter_mut().zip(bits.bytes()) {
+                        *dest = byte;
+                    }
+                }
+                offset + len
+            }
+        })?;
+        assert_eq!(written, data.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::string::String;
+
+    use firefly_alloc::gc::GcBox;
+
+    use crate::process::Process;
+    use crate::term::{Atom, BitSlice, Cons, ListBuilder, ProcessId};
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn list(elements: &[Term], process: &Process) -> Term {
+        let mut builder = ListBuilder::new(process);
+        for element in elements.iter().rev() {
+            builder.push(*element).unwrap();
+        }
+        builder.finish().map(Term::Cons).unwrap_or(Term::Nil)
+    }
+
+    /// Allocates a single cell, so `tail` can make the list improper
+    fn cons(head: Term, tail: Term, process: &Process) -> Term {
+        let ptr = Cons::new_in(process).unwrap();
+        unsafe {
+            ptr.as_ptr().write(Cons::cons(head, tail));
+        }
+        Term::Cons(ptr)
+    }
+
+    fn binary(bytes: &[u8], process: &Process) -> Term {
+        let mut bin = BinaryData::with_capacity_small(bytes.len(), process).unwrap();
+        bin.copy_from_slice(bytes);
+        bin.into()
+    }
+
+    fn bytes(term: Term) -> Vec<u8> {
+        term.as_bitstring().unwrap().bytes().collect()
+    }
+
+    #[test]
+    fn binary_is_iodata() {
+        let process = process();
+        let bin = binary(b"hello", &process);
+
+        assert_eq!(bin.iodata_size(), Ok(5));
+        assert_eq!(bytes(bin.iodata_to_binary(&process).unwrap()), b"hello");
+    }
+
+    #[test]
+    fn nested_lists_of_bytes_and_binaries_are_concatenated_in_order() {
+        let process = process();
+        let inner = list(&[Term::Int(b'c' as i64), binary(b"de", &process)], &process);
+        let iodata = list(
+            &[
+                binary(b"ab", &process),
+                inner,
+                Term::Int(b'f' as i64),
+                list(&[list(&[Term::Int(b'g' as i64)], &process)], &process),
+            ],
+            &process,
+        );
+
+        assert_eq!(iodata.iodata_size(), Ok(7));
+        assert_eq!(
+            bytes(iodata.iodata_to_binary(&process).unwrap()),
+            b"abcdefg"
+        );
+    }
+
+    #[test]
+    fn binary_tails_are_iodata() {
+        let process = process();
+        // [1, 2 | <<3, 4>>]
+        let tail = binary(&[3, 4], &process);
+        let iodata = cons(Term::Int(1), cons(Term::Int(2), tail, &process), &process);
+
+        assert_eq!(iodata.iodata_size(), Ok(4));
+        assert_eq!(
+            bytes(iodata.iodata_to_binary(&process).unwrap()),
+            [1, 2, 3, 4]
+        );
+
+        // [[1 | <<2>>], 3 | <<4>>], where the nested list's tail comes before the element after it
+        let nested = cons(Term::Int(1), binary(&[2], &process), &process);
+        let tail = cons(Term::Int(3), binary(&[4], &process), &process);
+        let iodata = cons(nested, tail, &process);
+
+        assert_eq!(iodata.iodata_size(), Ok(4));
+        assert_eq!(
+            bytes(iodata.iodata_to_binary(&process).unwrap()),
+            [1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn nested_empty_lists_are_empty() {
+        let process = process();
+        let empty = list(
+            &[
+                Term::Nil,
+                list(&[Term::Nil], &process),
+                list(&[Term::Nil, list(&[Term::Nil], &process)], &process),
+            ],
+            &process,
+        );
+
+        assert_eq!(Term::Nil.iodata_size(), Ok(0));
+        assert_eq!(empty.iodata_size(), Ok(0));
+        assert_eq!(
+            bytes(empty.iodata_to_binary(&process).unwrap()),
+            Vec::<u8>::new()
+        );
+
+        let iodata = list(
+            &[
+                Term::Nil,
+                Term::Int(1),
+                empty,
+                list(&[Term::Int(2)], &process),
+                Term::Nil,
+            ],
+            &process,
+        );
+
+        assert_eq!(iodata.iodata_size(), Ok(2));
+        assert_eq!(bytes(iodata.iodata_to_binary(&process).unwrap()), [1, 2]);
+    }
+
+    #[test]
+    fn deeply_nested_lists_do_not_overflow_the_stack() {
+        let process = process();
+        let mut iodata = list(&[Term::Int(1)], &process);
+        for _ in 0..100_000 {
+            iodata = cons(iodata, Term::Nil, &process);
+        }
+
+        assert_eq!(iodata.iodata_size(), Ok(1));
+        assert_eq!(bytes(iodata.iodata_to_binary(&process).unwrap()), [1]);
+    }
+
+    #[test]
+    fn unaligned_binaries_are_copied_bytewise() {
+        static DATA: [u8; 3] = [0x01, 0x23, 0x45];
+        let process = process();
+        // The 16 bits starting 4 bits into DATA
+        let slice = unsafe { BitSlice::new(OpaqueTerm::NONE, &DATA, 4, 16) };
+        let slice = Term::RefBinary(GcBox::new_in(slice, &process).unwrap());
+        let iodata = list(&[Term::Int(0), slice], &process);
+
+        assert_eq!(iodata.iodata_size(), Ok(3));
+        assert_eq!(
+            bytes(iodata.iodata_to_binary(&process).unwrap()),
+            [0x00, 0x12, 0x34]
+        );
+    }
+
+    #[test]
+    fn small_outputs_are_allocated_on_the_heap() {
+        let process = process();
+        let iodata = list(
+            &[binary(&[1; 32], &process), binary(&[2; 32], &process)],
+            &process,
+        );
+
+        let bin = iodata.iodata_to_binary(&process).unwrap();
+
+        assert!(matches!(bin, Term::HeapBinary(_)));
+        assert_eq!(bytes(bin).len(), BinaryData::MAX_HEAP_BYTES);
+    }
+
+    #[test]
+    fn large_outputs_are_reference_counted() {
+        let process = process();
+        let chunk = binary(&[7; 60], &process);
+        let iodata = list(&[chunk, Term::Int(8), chunk], &process);
+
+        let bin = iodata.iodata_to_binary(&process).unwrap();
+
+        assert!(matches!(bin, Term::RcBinary(_)));
+
+        let bytes = bytes(bin);
+        assert_eq!(bytes.len(), 121);
+        assert!(bytes[..60].iter().all(|byte| *byte == 7));
+        assert_eq!(bytes[60], 8);
+        assert!(bytes[61..].iter().all(|byte| *byte == 7));
+    }
+
+    #[test]
+    fn badarg_identifies_the_first_non_iodata_term() {
+        let process = process();
+        let atom = Term::Atom(Atom::try_from("not_iodata").unwrap());
+
+        assert_eq!(atom.iodata_size(), Err(IodataError::Badarg(atom)));
+        assert_eq!(
+            Term::Int(1).iodata_size(),
+            Err(IodataError::Badarg(Term::Int(1)))
+        );
+
+        let iodata = list(&[Term::Int(1), Term::Int(256), Term::Int(-1)], &process);
+        assert_eq!(
+            iodata.iodata_size(),
+            Err(IodataError::Badarg(Term::Int(256)))
+        );
+
+        let iodata = list(&[list(&[Term::Int(-1)], &process), atom], &process);
+        assert_eq!(
+            iodata.iodata_to_binary(&process),
+            Err(IodataError::Badarg(Term::Int(-1)))
+        );
+
+        // Only binaries are allowed as tails
+        let iodata = cons(Term::Int(1), Term::Int(2), &process);
+        assert_eq!(iodata.iodata_size(), Err(IodataError::Badarg(Term::Int(2))));
+
+        // A bitstring which is not a binary is not iodata
+        static DATA: [u8; 1] = [0xff];
+        let bits = unsafe { BitSlice::new(OpaqueTerm::NONE, &DATA, 0, 7) };
+        let bits = Term::RefBinary(GcBox::new_in(bits, &process).unwrap());
+        let iodata = list(&[Term::Int(1), bits], &process);
+        assert_eq!(iodata.iodata_size(), Err(IodataError::Badarg(bits)));
+    }
+
+    /// Folds `chardata` into its characters, with the bytes of its binaries as latin1, stopping
+    /// at the first integer which is not a codepoint
+    fn chars(chardata: Term) -> Result<String, (String, ChardataRest)> {
+        chardata.chardata_fold(String::new(), |mut acc, chunk| match chunk {
+            CharChunk::Char(c) => match u32::try_from(c).ok().and_then(char::from_u32) {
+                Some(c) => {
+                    acc.push(c);
+                    ControlFlow::Continue(acc)
+                }
+                None => ControlFlow::Break((acc, Term::Int(c))),
+            },
+            CharChunk::Binary(bits) => {
+                acc.extend(bits.bytes().map(char::from));
+                ControlFlow::Continue(acc)
+            }
+        })
+    }
+
+    #[test]
+    fn chardata_characters_may_be_any_integer() {
+        let process = process();
+        let chardata = list(
+            &[
+                Term::Int('λ' as i64),
+                binary(b"ab", &process),
+                list(&[Term::Int(0x1F600)], &process),
+            ],
+            &process,
+        );
+
+        assert_eq!(chars(chardata).unwrap(), "λab\u{1F600}");
+    }
+
+    #[test]
+    fn chardata_rest_starts_where_the_fold_stopped() {
+        let process = process();
+        // [[$a, -1, $b], $c | <<"d">>]
+        let inner = list(
+            &[Term::Int('a' as i64), Term::Int(-1), Term::Int('b' as i64)],
+            &process,
+        );
+        let tail = cons(Term::Int('c' as i64), binary(b"d", &process), &process);
+        let chardata = cons(inner, tail, &process);
+
+        let (converted, rest) = chars(chardata).unwrap_err();
+
+        assert_eq!(converted, "a");
+        // [[-1, $b], [$c | <<"d">>]]
+        let expected = list(
+            &[
+                list(&[Term::Int(-1), Term::Int('b' as i64)], &process),
+                tail,
+            ],
+            &process,
+        );
+        assert_eq!(rest.to_term(&process).unwrap(), expected);
+    }
+
+    #[test]
+    fn chardata_fold_stops_at_terms_which_are_not_chardata() {
+        let process = process();
+        let atom = Term::Atom(Atom::try_from("not_chardata").unwrap());
+
+        let (converted, rest) = chars(atom).unwrap_err();
+        assert_eq!(converted, "");
+        assert_eq!(rest.to_term(&process).unwrap(), atom);
+
+        let chardata = list(
+            &[Term::Int('a' as i64), atom, Term::Int('b' as i64)],
+            &process,
+        );
+        let (converted, rest) = chars(chardata).unwrap_err();
+        assert_eq!(converted, "a");
+        assert_eq!(
+            rest.to_term(&process).unwrap(),
+            list(&[atom, Term::Int('b' as i64)], &process)
+        );
+
+        // Only binaries are allowed as tails
+        let chardata = cons(Term::Int('a' as i64), Term::Int('b' as i64), &process);
+        let (converted, rest) = chars(chardata).unwrap_err();
+        assert_eq!(converted, "a");
+        assert_eq!(rest.to_term(&process).unwrap(), Term::Int('b' as i64));
+    }
+}
@@ -1,4 +1,4 @@
-use alloc::alloc::{AllocError, Allocator, Layout};
+use alloc::alloc::{AllocError, Allocator, Global, Layout};
 use alloc::boxed::Box;
 use alloc::string::String;
 use core::any::TypeId;
@@ -10,11 +10,25 @@ use core::ptr::NonNull;
 use firefly_alloc::gc::GcBox;
 use firefly_alloc::heap::Heap;
 use firefly_alloc::rc::Rc;
-use firefly_binary::{BinaryFlags, BitVec, Bitstring, Encoding};
+use firefly_binary::{BinaryFlags, BitVec, Bitstring, Encoding, Selection};
 
 use crate::cmp::ExactEq;
 
-use super::{BinaryData, OpaqueTerm, Term, TupleIndex};
+use super::{BinaryData, OneBasedIndex, OpaqueTerm, Term, TupleIndex};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReverseError {
+    /// The list to reverse is an improper list
+    ImproperList,
+    /// Could not allocate enough memory to store the reversed list
+    AllocError,
+}
+impl From<AllocError> for ReverseError {
+    #[inline]
+    fn from(_: AllocError) -> Self {
+        Self::AllocError
+    }
+}
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum CharlistToBinaryError {
@@ -24,6 +38,20 @@ pub enum CharlistToBinaryError {
     AllocError,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BinaryToListError {
+    /// The input isn't a binary, or the requested range is invalid for it
+    Badarg,
+    /// Could not allocate enough memory to store the list
+    AllocError,
+}
+impl From<AllocError> for BinaryToListError {
+    #[inline]
+    fn from(_: AllocError) -> Self {
+        Self::AllocError
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct Cons {
@@ -114,20 +142,52 @@ impl Cons {
     ///
     /// If no key is found, returns 'badarg'
     pub fn keyfind<I, K: Into<Term>>(&self, index: I, key: K) -> Result<Option<Term>, ImproperList>
+    where
+        I: TupleIndex + Copy,
+    {
+        self.keysearch(index, key).map(|(_, found)| found)
+    }
+
+    /// Returns true if this keyword list has an element with a matching key at the given index.
+    ///
+    /// Like `keyfind`, this is only an error if the list is improper and no key is found first.
+    pub fn keymember<I, K: Into<Term>>(&self, index: I, key: K) -> Result<bool, ImproperList>
+    where
+        I: TupleIndex + Copy,
+    {
+        self.keysearch(index, key).map(|(_, found)| found.is_some())
+    }
+
+    /// Searches this keyword list for the first element which has a matching key
+    /// at the given index, returning the number of elements visited along with the match, if any.
+    ///
+    /// Elements which are not tuples, or are too small to have the index, are skipped. Keys are
+    /// compared with `==`, so `1` matches `1.0`, as in `lists:keyfind/3`.
+    ///
+    /// NOTE: When a match is found, the number of elements visited is its one-based position in
+    /// the list, otherwise it is the length of the list.
+    pub fn keysearch<I, K: Into<Term>>(
+        &self,
+        index: I,
+        key: K,
+    ) -> Result<(usize, Option<Term>), ImproperList>
     where
         I: TupleIndex + Copy,
     {
         let key = key.into();
+        let mut visited = 0;
         for result in self.iter() {
-            let Term::Tuple(ptr) = result? else { continue; };
+            let element = result?;
+            visited += 1;
+            let Term::Tuple(ptr) = element else { continue; };
             let tuple = unsafe { ptr.as_ref() };
             let Ok(candidate) = tuple.get_element(index) else { continue; };
             if candidate == key {
-                return Ok(Some(Term::Tuple(ptr)));
+                return Ok((visited, Some(Term::Tuple(ptr))));
             }
         }
 
-        Ok(None)
+        Ok((visited, None))
     }
 }
 
@@ -171,6 +231,64 @@ impl Cons {
         Ok(builder.finish())
     }
 
+    /// Constructs a list of the bytes contained in the given binary
+    ///
+    /// Returns `Err(BinaryToListError::Badarg)` if `bits` is not a binary, i.e. it has a
+    /// number of bits which is not evenly divisible by 8.
+    pub fn from_binary<H: Heap>(
+        bits: &dyn Bitstring,
+        heap: H,
+    ) -> Result<Option<NonNull<Cons>>, BinaryToListError> {
+        if !bits.is_binary() {
+            return Err(BinaryToListError::Badarg);
+        }
+        Ok(Self::from_selection(bits.select_all(), heap)?)
+    }
+
+    /// Constructs a list of the bytes contained in the given binary, in the one-based,
+    /// inclusive range `start..=stop`, with the same semantics as `binary_to_list/3`.
+    ///
+    /// Returns `Err(BinaryToListError::Badarg)` if `bits` is not a binary, if `start` is
+    /// greater than `stop`, or if `stop` is out of range.
+    ///
+    /// Only the bytes in the requested range are visited, so this is cheap to call on a
+    /// slice of a much larger binary.
+    pub fn from_binary_range<H: Heap>(
+        bits: &dyn Bitstring,
+        start: OneBasedIndex,
+        stop: OneBasedIndex,
+        heap: H,
+    ) -> Result<NonNull<Cons>, BinaryToListError> {
+        if !bits.is_binary() || start > stop {
+            return Err(BinaryToListError::Badarg);
+        }
+        let start: usize = start.into();
+        let stop: usize = stop.into();
+        if stop >= bits.byte_size() {
+            return Err(BinaryToListError::Badarg);
+        }
+        let len = stop - start + 1;
+        let selection = bits
+            .select_all()
+            .shrink_front(start * 8)
+            .take(len * 8)
+            .map_err(|_| BinaryToListError::Badarg)?;
+        // The range is non-empty, so we always get a cell back
+        Ok(Self::from_selection(selection, heap)?.unwrap())
+    }
+
+    /// Constructs a list of the bytes in the given selection
+    ///
+    /// The list is built back-to-front, so no reversal is required. If the selection is
+    /// aligned, the bytes are read in place; otherwise only the selected bytes are copied.
+    fn from_selection<H: Heap>(
+        selection: Selection<'_>,
+        heap: H,
+    ) -> Result<Option<NonNull<Cons>>, AllocError> {
+        let bytes = selection.to_bytes();
+        Self::from_bytes(&bytes, heap)
+    }
+
     /// Constructs a charlist from the given string
     pub fn charlist_from_str<H: Heap>(
         s: &str,
@@ -238,7 +356,7 @@ impl Cons {
         } else {
             self.write_raw_charlist_to_buffer(&mut buf)?;
         }
-        let mut rc = Rc::<BinaryData>::with_capacity(buf.byte_size());
+        let mut rc = BinaryData::rc_with_capacity_in(buf.byte_size(), Global).unwrap();
         {
             let value = unsafe { Rc::get_mut_unchecked(&mut rc) };
             unsafe {
@@ -341,8 +459,9 @@ impl Cons {
         Some((len, encoding))
     }
 
+    /// Returns true if this list is displayed as a string, as `~p` does for printable charlists
     // See https://github.com/erlang/otp/blob/b8e11b6abe73b5f6306e8833511fcffdb9d252b5/erts/emulator/beam/erl_printf_term.c#L117-L140
-    fn is_printable_string(&self) -> bool {
+    pub fn is_printable_string(&self) -> bool {
         self.iter().all(|result| match result {
             Ok(element) => {
                 // See https://github.com/erlang/otp/blob/b8e11b6abe73b5f6306e8833511fcffdb9d252b5/erts/emulator/beam/erl_printf_term.c#L128-L129
@@ -363,7 +482,7 @@ impl PartialEq for Cons {
 }
 impl ExactEq for Cons {
     fn exact_eq(&self, other: &Self) -> bool {
-        self.head().exact_eq(&other.head()) && self.tail().exact_eq(&other.tail())
+        Term::Cons(NonNull::from(self)).exact_eq(&Term::Cons(NonNull::from(other)))
     }
 }
 impl PartialOrd for Cons {
@@ -411,7 +530,11 @@ impl Display for Cons {
                 // `is_printable_string` guarantees all Ok
                 let element = result.unwrap();
                 match element.try_into().unwrap() {
-                    '\n' => f.write_str("\\\n")?,
+                    '\n' => f.write_str("\\n")?,
+                    '\r' => f.write_str("\\r")?,
+                    '\t' => f.write_str("\\t")?,
+                    '\x0c' => f.write_str("\\f")?,
+                    '\\' => f.write_str("\\\\")?,
                     '\"' => f.write_str("\\\"")?,
                     c => f.write_char(c)?,
                 }
@@ -423,9 +546,9 @@ impl Display for Cons {
 
             for (i, value) in self.iter().enumerate() {
                 match value {
-                    Ok(value) if i > 0 => write!(f, ", {}", value)?,
+                    Ok(value) if i > 0 => write!(f, ",{}", value)?,
                     Ok(value) => write!(f, "{}", value)?,
-                    Err(improper) if i > 0 => write!(f, " | {}", improper)?,
+                    Err(improper) if i > 0 => write!(f, "|{}", improper)?,
                     Err(improper) => write!(f, "{}", improper)?,
                 }
             }
@@ -514,10 +637,22 @@ impl Iterator for Iter<'_> {
 pub struct ListBuilder<'a, H: Heap> {
     heap: &'a H,
     tail: Option<NonNull<Cons>>,
+    /// The tail of the last cell, `[]` unless the builder was created `with_tail`
+    end: OpaqueTerm,
 }
 impl<'a, H: Heap> ListBuilder<'a, H> {
     pub fn new(heap: &'a H) -> Self {
-        Self { heap, tail: None }
+        Self::with_tail(heap, OpaqueTerm::NIL)
+    }
+
+    /// Creates a builder whose list ends in `tail` rather than `[]`, i.e. the values pushed are
+    /// consed on to `tail`
+    pub fn with_tail(heap: &'a H, tail: OpaqueTerm) -> Self {
+        Self {
+            heap,
+            tail: None,
+            end: tail,
+        }
     }
 
     pub fn push(&mut self, value: Term) -> Result<(), AllocError> {
@@ -529,7 +664,7 @@ impl<'a, H: Heap> ListBuilder<'a, H> {
                 unsafe {
                     cell.as_ptr().write(Cons {
                         head: value,
-                        tail: OpaqueTerm::NIL,
+                        tail: self.end,
                     });
                 }
                 self.tail = Some(cell.cast());
@@ -549,6 +684,20 @@ impl<'a, H: Heap> ListBuilder<'a, H> {
         Ok(())
     }
 
+    /// Pushes each element of `list` in turn, so that they end up in reverse order in front of
+    /// the values already pushed, as in `lists:reverse/2`, returning the number of elements pushed
+    ///
+    /// If `list` is improper, the builder is left with the elements before the improper tail.
+    pub fn reverse_onto(&mut self, list: &Cons) -> Result<usize, ReverseError> {
+        let mut len = 0;
+        for result in list.iter() {
+            let value = result.map_err(|_| ReverseError::ImproperList)?;
+            self.push(value)?;
+            len += 1;
+        }
+        Ok(len)
+    }
+
     pub fn finish(mut self) -> Option<NonNull<Cons>> {
         self.tail.take()
     }
@@ -576,7 +725,7 @@ mod test {
     use super::*;
 
     use crate::process::Process;
-    use crate::term::ProcessId;
+    use crate::term::{BitSlice, ProcessId, Tuple};
 
     #[test]
     fn list_builder_builds_proper_lists() {
@@ -597,4 +746,181 @@ mod test {
         assert_eq!(iter.next(), None);
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn keysearch_skips_non_tuples_and_tuples_smaller_than_index() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let small = Tuple::from_slice(&[Term::Int(1).into()], &process).unwrap();
+        let pair =
+            Tuple::from_slice(&[Term::Int(1).into(), Term::Int(2).into()], &process).unwrap();
+        let ptr = Cons::from_slice(
+            &[Term::Int(2), Term::Tuple(small), Term::Tuple(pair)],
+            &process,
+        )
+        .unwrap()
+        .unwrap();
+        let list = unsafe { ptr.as_ref() };
+
+        let two = OneBasedIndex::new(2).unwrap();
+        let three = OneBasedIndex::new(3).unwrap();
+        assert_eq!(
+            list.keysearch(two, Term::Int(2)),
+            Ok((3, Some(Term::Tuple(pair))))
+        );
+        assert_eq!(list.keyfind(two, Term::Int(2)), Ok(Some(Term::Tuple(pair))));
+        assert_eq!(list.keymember(two, Term::Int(2)), Ok(true));
+        assert_eq!(list.keysearch(three, Term::Int(2)), Ok((3, None)));
+        assert_eq!(list.keymember(three, Term::Int(2)), Ok(false));
+    }
+
+    #[test]
+    fn keysearch_compares_keys_with_equality() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let pair =
+            Tuple::from_slice(&[Term::Int(1).into(), Term::Int(2).into()], &process).unwrap();
+        let ptr = Cons::from_slice(&[Term::Tuple(pair)], &process)
+            .unwrap()
+            .unwrap();
+        let list = unsafe { ptr.as_ref() };
+
+        let one = OneBasedIndex::new(1).unwrap();
+        assert_eq!(
+            list.keyfind(one, Term::Float(1.0f64.into())),
+            Ok(Some(Term::Tuple(pair)))
+        );
+    }
+
+    #[test]
+    fn keysearch_of_improper_list_errors_only_if_key_not_found_first() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let pair =
+            Tuple::from_slice(&[Term::Int(1).into(), Term::Int(2).into()], &process).unwrap();
+        let mut builder = ListBuilder::with_tail(&process, Term::Int(3).into());
+        builder.push(Term::Tuple(pair)).unwrap();
+        let ptr = builder.finish().unwrap();
+        let list = unsafe { ptr.as_ref() };
+
+        let one = OneBasedIndex::new(1).unwrap();
+        let improper = ImproperList { tail: Term::Int(3) };
+        assert_eq!(list.keyfind(one, Term::Int(1)), Ok(Some(Term::Tuple(pair))));
+        assert_eq!(list.keyfind(one, Term::Int(2)), Err(improper.clone()));
+        assert_eq!(list.keymember(one, Term::Int(2)), Err(improper));
+    }
+
+    #[test]
+    fn reverse_onto_conses_elements_in_reverse_onto_tail() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let ptr = Cons::from_slice(&[Term::Int(1), Term::Int(2), Term::Int(3)], &process)
+            .unwrap()
+            .unwrap();
+        let list = unsafe { ptr.as_ref() };
+
+        let mut builder = ListBuilder::with_tail(&process, Term::Int(4).into());
+        assert_eq!(builder.reverse_onto(list), Ok(3));
+        let ptr = builder.finish().unwrap();
+        let reversed = unsafe { ptr.as_ref() };
+
+        let mut iter = reversed.iter();
+        assert_eq!(iter.next(), Some(Ok(Term::Int(3))));
+        assert_eq!(iter.next(), Some(Ok(Term::Int(2))));
+        assert_eq!(iter.next(), Some(Ok(Term::Int(1))));
+        assert_eq!(iter.next(), Some(Err(ImproperList { tail: Term::Int(4) })));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn reverse_onto_of_improper_list_errors() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let mut builder = ListBuilder::with_tail(&process, Term::Int(2).into());
+        builder.push(Term::Int(1)).unwrap();
+        let ptr = builder.finish().unwrap();
+        let list = unsafe { ptr.as_ref() };
+
+        let mut builder = ListBuilder::new(&process);
+        assert_eq!(builder.reverse_onto(list), Err(ReverseError::ImproperList));
+    }
+
+    #[test]
+    fn binary_to_list_of_empty_binary_is_nil() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let bin = BinaryData::from_bytes(&[]);
+
+        assert_eq!(Cons::from_binary(&*bin, &process), Ok(None));
+    }
+
+    #[test]
+    fn binary_to_list_of_single_byte_binary() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let bin = BinaryData::from_bytes(&[255]);
+
+        let ptr = Cons::from_binary(&*bin, &process).unwrap().unwrap();
+        let list = unsafe { ptr.as_ref() };
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(Ok(Term::Int(255))));
+        assert_eq!(iter.next(), None);
+
+        let start = OneBasedIndex::new(1).unwrap();
+        let ptr = Cons::from_binary_range(&*bin, start, start, &process).unwrap();
+        let list = unsafe { ptr.as_ref() };
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(Ok(Term::Int(255))));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn binary_to_list_range_is_inclusive() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let bin = BinaryData::from_bytes(b"hello");
+
+        let start = OneBasedIndex::new(2).unwrap();
+        let stop = OneBasedIndex::new(4).unwrap();
+        let ptr = Cons::from_binary_range(&*bin, start, stop, &process).unwrap();
+        let list = unsafe { ptr.as_ref() };
+        assert_eq!(list.to_string().as_deref(), Some("ell"));
+    }
+
+    #[test]
+    fn binary_to_list_range_rejects_invalid_ranges() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let bin = BinaryData::from_bytes(b"hello");
+        let empty = BinaryData::from_bytes(&[]);
+
+        let one = OneBasedIndex::new(1).unwrap();
+        let three = OneBasedIndex::new(3).unwrap();
+        let six = OneBasedIndex::new(6).unwrap();
+        assert_eq!(
+            Cons::from_binary_range(&*bin, three, one, &process),
+            Err(BinaryToListError::Badarg)
+        );
+        assert_eq!(
+            Cons::from_binary_range(&*bin, one, six, &process),
+            Err(BinaryToListError::Badarg)
+        );
+        assert_eq!(
+            Cons::from_binary_range(&*bin, six, six, &process),
+            Err(BinaryToListError::Badarg)
+        );
+        assert_eq!(
+            Cons::from_binary_range(&*empty, one, one, &process),
+            Err(BinaryToListError::Badarg)
+        );
+    }
+
+    #[test]
+    fn binary_to_list_range_of_sub_binary() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let bytes = b"abcdefgh";
+        // A sub-binary covering "cdef"
+        let slice = unsafe { BitSlice::new(OpaqueTerm::NONE, &bytes[2..6], 0, 32) };
+
+        let start = OneBasedIndex::new(2).unwrap();
+        let stop = OneBasedIndex::new(3).unwrap();
+        let ptr = Cons::from_binary_range(&slice, start, stop, &process).unwrap();
+        let list = unsafe { ptr.as_ref() };
+        assert_eq!(list.to_string().as_deref(), Some("de"));
+
+        let ptr = Cons::from_binary(&slice, &process).unwrap().unwrap();
+        let list = unsafe { ptr.as_ref() };
+        assert_eq!(list.to_string().as_deref(), Some("cdef"));
+    }
 }
@@ -214,6 +214,41 @@ impl Map {
         self.map.values()
     }
 
+    /// Returns the keys of this map in term order
+    pub fn sorted_keys(&self) -> Vec<Term> {
+        self.sorted_map_keys().drain(..).map(|k| k.0).collect()
+    }
+
+    /// Returns the values of this map, in the term order of their keys
+    pub fn sorted_values(&self) -> Vec<Term> {
+        self.sorted_map_keys()
+            .drain(..)
+            .map(|k| *self.map.get(&k).unwrap())
+            .collect()
+    }
+
+    /// Merges `other` into this map, returning a new map.
+    ///
+    /// Where a key is present in both maps, the value from `other` takes precedence.
+    pub fn merge(&self, other: &Self) -> Self {
+        // Insert the entries of the smaller map into the larger, to do as few insertions as we can
+        if self.size() < other.size() {
+            let mut map = other.map.clone();
+            for (k, v) in self.map.iter() {
+                if !map.contains_key(k) {
+                    map.insert_mut(*k, *v);
+                }
+            }
+            Self { map }
+        } else {
+            let mut map = self.map.clone();
+            for (k, v) in other.map.iter() {
+                map.insert_mut(*k, *v);
+            }
+            Self { map }
+        }
+    }
+
     fn sorted_map_keys(&self) -> Vec<MapKey> {
         let mut keys = self.map.keys().copied().collect::<Vec<_>>();
         keys.sort_unstable();
@@ -233,13 +268,14 @@ impl fmt::Debug for Map {
     }
 }
 impl fmt::Display for Map {
+    /// Writes the map with its keys in term order, as `~w` does
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str("#{")?;
-        for (i, (key, value)) in self.iter().enumerate() {
+        for (i, key) in self.sorted_keys().into_iter().enumerate() {
             if i > 0 {
-                f.write_str(", ")?;
+                f.write_str(",")?;
             }
-            write!(f, "{} => {}", key, value)?;
+            write!(f, "{} => {}", key, self.get(key).unwrap())?;
         }
         f.write_str("}")
     }
@@ -322,3 +358,132 @@ impl Ord for Map {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::term::Atom;
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(Atom::try_from(name).unwrap())
+    }
+
+    fn map(entries: &[(Term, Term)]) -> Map {
+        Map::new_from_iter(entries.iter().copied())
+    }
+
+    fn assert_terms_eq(actual: Vec<Term>, expected: &[Term]) {
+        assert_eq!(
+            actual.len(),
+            expected.len(),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(a.exact_eq(e), "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn sorted_keys_and_values_are_in_term_order() {
+        let entries = [
+            (atom("b"), Term::Int(1)),
+            (Term::Int(10), Term::Int(2)),
+            (atom("a"), Term::Int(3)),
+            (Term::Nil, Term::Int(4)),
+            (Term::Int(-1), Term::Int(5)),
+        ];
+        let expected_keys = [
+            Term::Int(-1),
+            Term::Int(10),
+            atom("a"),
+            atom("b"),
+            Term::Nil,
+        ];
+        let expected_values = [
+            Term::Int(5),
+            Term::Int(2),
+            Term::Int(3),
+            Term::Int(1),
+            Term::Int(4),
+        ];
+
+        // The order must not depend on the order the entries were inserted in
+        let forward = map(&entries);
+        let mut reversed = entries;
+        reversed.reverse();
+        let backward = map(&reversed);
+
+        for m in [forward, backward] {
+            assert_terms_eq(m.sorted_keys(), &expected_keys);
+            assert_terms_eq(m.sorted_values(), &expected_values);
+        }
+    }
+
+    #[test]
+    fn sorted_keys_orders_equal_floats_before_integers() {
+        let m = map(&[
+            (Term::Int(1), atom("int")),
+            (1.0f64.into(), atom("float")),
+            (0.5f64.into(), atom("half")),
+        ]);
+
+        assert_eq!(m.size(), 3);
+        assert_terms_eq(
+            m.sorted_keys(),
+            &[0.5f64.into(), 1.0f64.into(), Term::Int(1)],
+        );
+        assert_terms_eq(
+            m.sorted_values(),
+            &[atom("half"), atom("float"), atom("int")],
+        );
+    }
+
+    #[test]
+    fn merge_prefers_values_from_other() {
+        let small = map(&[(atom("a"), Term::Int(1)), (atom("b"), Term::Int(2))]);
+        let large = map(&[
+            (atom("b"), Term::Int(20)),
+            (atom("c"), Term::Int(30)),
+            (atom("d"), Term::Int(40)),
+        ]);
+
+        // Precedence must hold whichever of the maps is the larger
+        let merged = small.merge(&large);
+        assert_terms_eq(
+            merged.sorted_keys(),
+            &[atom("a"), atom("b"), atom("c"), atom("d")],
+        );
+        assert_terms_eq(
+            merged.sorted_values(),
+            &[Term::Int(1), Term::Int(20), Term::Int(30), Term::Int(40)],
+        );
+
+        let merged = large.merge(&small);
+        assert_terms_eq(
+            merged.sorted_keys(),
+            &[atom("a"), atom("b"), atom("c"), atom("d")],
+        );
+        assert_terms_eq(
+            merged.sorted_values(),
+            &[Term::Int(1), Term::Int(2), Term::Int(30), Term::Int(40)],
+        );
+
+        // Neither of the merged maps is modified
+        assert_eq!(small.size(), 2);
+        assert_eq!(large.get(atom("b")).unwrap(), Term::Int(20));
+    }
+
+    #[test]
+    fn merge_keeps_integer_and_float_keys_distinct() {
+        let ints = map(&[(Term::Int(1), atom("int"))]);
+        let floats = map(&[(1.0f64.into(), atom("float"))]);
+
+        let merged = ints.merge(&floats);
+        assert_eq!(merged.size(), 2);
+        assert_eq!(merged.get(Term::Int(1)).unwrap(), atom("int"));
+        assert_eq!(merged.get(Term::from(1.0f64)).unwrap(), atom("float"));
+    }
+}
@@ -1,7 +1,13 @@
 mod atom;
 mod binary;
 mod closure;
+pub mod encoding;
+mod external_fun;
+mod float;
+mod hash;
+mod heap_ranges;
 mod index;
+mod iodata;
 mod list;
 mod map;
 mod node;
@@ -11,23 +17,31 @@ mod port;
 mod reference;
 mod tuple;
 
-pub use self::atom::{atoms, Atom, AtomData};
+pub use self::atom::{atoms, try_atom_count, Atom, AtomData, AtomError, ModuleAtoms};
 pub use self::binary::*;
 pub use self::closure::Closure;
+pub use self::external_fun::ExternalFun;
+pub use self::float::{
+    FloatFormat, DEFAULT_SCIENTIFIC_DIGITS, MAX_DECIMALS, MAX_SCIENTIFIC_DIGITS,
+};
+pub use self::hash::phash2;
+pub use self::heap_ranges::HeapRanges;
 pub use self::index::{NonPrimitiveIndex, OneBasedIndex, TupleIndex, ZeroBasedIndex};
-pub use self::list::{Cons, ImproperList, ListBuilder};
+pub use self::iodata::{CharChunk, ChardataRest, IoChunk, IodataError};
+pub use self::list::{BinaryToListError, Cons, ImproperList, ListBuilder, ReverseError};
 pub use self::map::Map;
 pub use self::node::Node;
-pub use self::opaque::{OpaqueTerm, TermType};
+pub use self::opaque::{InvalidTermError, OpaqueTerm, TermType};
 pub use self::pid::{Pid, ProcessId};
 pub use self::port::{Port, PortId};
-pub use self::reference::{Reference, ReferenceId};
+pub use self::reference::{Reference, ReferenceId, ReferenceIdGenerator};
 pub use self::tuple::Tuple;
 
 pub use firefly_number::{BigInt, Float, Integer, Number};
 use firefly_number::{DivisionError, InvalidArithmeticError, Sign, ToPrimitive};
 
 use alloc::alloc::{AllocError, Layout};
+use alloc::vec::Vec;
 use core::convert::AsRef;
 use core::fmt;
 use core::ptr::NonNull;
@@ -78,6 +92,7 @@ pub enum Term {
     Tuple(NonNull<Tuple>),
     Map(GcBox<Map>),
     Closure(GcBox<Closure>),
+    ExternalFun(GcBox<ExternalFun>),
     Pid(GcBox<Pid>),
     Port(GcBox<Port>),
     Reference(GcBox<Reference>),
@@ -147,6 +162,13 @@ impl Term {
                     Self::Closure(cloned)
                 }
             }
+            Self::ExternalFun(boxed) => {
+                if heap.contains(GcBox::as_ptr(&boxed)) {
+                    Self::ExternalFun(boxed)
+                } else {
+                    Self::ExternalFun(GcBox::new_in(*boxed, heap)?)
+                }
+            }
             Self::Pid(boxed) => {
                 if heap.contains(GcBox::as_ptr(&boxed)) {
                     Self::Pid(boxed)
@@ -237,6 +259,14 @@ impl Term {
             _ => None,
         }
     }
+
+    pub fn as_external_fun(&self) -> Option<&ExternalFun> {
+        match self {
+            Self::ExternalFun(fun) => Some(fun.as_ref()),
+            _ => None,
+        }
+    }
+
     pub fn as_pid(&self) -> Option<&Pid> {
         match self {
             Self::Pid(pid) => Some(pid.as_ref()),
@@ -258,6 +288,20 @@ impl Term {
         }
     }
 
+    /// Returns the name of the node a pid, port or reference belongs to, as `erlang:node/1` does
+    pub fn node_name(&self) -> Option<Atom> {
+        let node = match self {
+            Self::Pid(pid) => pid.node(),
+            Self::Port(port) => port.node(),
+            Self::Reference(reference) => reference.node(),
+            _ => return None,
+        };
+        match node {
+            None => Some(Node::local_name()),
+            Some(node) => Some(node.name().unwrap_or(atoms::NonodeAtNohost)),
+        }
+    }
+
     pub fn as_bitstring(&self) -> Option<&dyn Bitstring> {
         match self {
             Self::HeapBinary(boxed) => Some(boxed),
@@ -341,6 +385,12 @@ impl Term {
                     extended.pad_to_align()
                 })
             }
+            Self::ExternalFun(_) => {
+                let (base, _) = Layout::new::<GcBox<ExternalFun>>()
+                    .extend(Layout::new::<ExternalFun>())
+                    .unwrap();
+                base.pad_to_align()
+            }
             Self::Pid(_) => {
                 let (base, _) = Layout::new::<GcBox<Pid>>()
                     .extend(Layout::new::<Pid>())
@@ -445,6 +495,11 @@ impl From<GcBox<Closure>> for Term {
         Self::Closure(term)
     }
 }
+impl From<GcBox<ExternalFun>> for Term {
+    fn from(term: GcBox<ExternalFun>) -> Self {
+        Self::ExternalFun(term)
+    }
+}
 impl From<GcBox<Pid>> for Term {
     fn from(term: GcBox<Pid>) -> Self {
         Self::Pid(term)
@@ -630,6 +685,7 @@ impl fmt::Display for Term {
             Self::Tuple(ptr) => write!(f, "{}", unsafe { ptr.as_ref() }),
             Self::Map(boxed) => write!(f, "{}", boxed),
             Self::Closure(boxed) => write!(f, "{}", boxed),
+            Self::ExternalFun(boxed) => write!(f, "{}", boxed),
             Self::Pid(boxed) => write!(f, "{}", boxed),
             Self::Port(boxed) => write!(f, "{}", boxed),
             Self::Reference(boxed) => write!(f, "{}", boxed),
@@ -694,6 +750,10 @@ impl PartialEq for Term {
                 Self::Closure(y) => x == y,
                 _ => false,
             },
+            Self::ExternalFun(x) => match other {
+                Self::ExternalFun(y) => x == y,
+                _ => false,
+            },
             Self::Pid(x) => match other {
                 Self::Pid(y) => x == y,
                 _ => false,
@@ -737,8 +797,11 @@ impl PartialEq for Term {
         }
     }
 }
-impl ExactEq for Term {
-    fn exact_eq(&self, other: &Self) -> bool {
+impl Term {
+    /// Compares two terms exactly, except that two lists, or two tuples, are never equal.
+    ///
+    /// This is the comparison of everything `exact_eq` does not walk itself.
+    fn exact_eq_unwalked(&self, other: &Self) -> bool {
         match self {
             Self::None => other.is_none(),
             Self::Nil => other.is_nil(),
@@ -770,14 +833,8 @@ impl ExactEq for Term {
                 Self::Float(y) => x == y,
                 _ => false,
             },
-            Self::Cons(x) => match other {
-                Self::Cons(y) => unsafe { x.as_ref().exact_eq(y.as_ref()) },
-                _ => false,
-            },
-            Self::Tuple(x) => match other {
-                Self::Tuple(y) => unsafe { x.as_ref().exact_eq(y.as_ref()) },
-                _ => false,
-            },
+            // Pairs of lists or tuples are walked by `exact_eq`, so these never match here
+            Self::Cons(_) | Self::Tuple(_) => false,
             Self::Map(x) => match other {
                 Self::Map(y) => x.as_ref().exact_eq(y.as_ref()),
                 _ => false,
@@ -786,6 +843,10 @@ impl ExactEq for Term {
                 Self::Closure(y) => x.as_ref().exact_eq(y.as_ref()),
                 _ => false,
             },
+            Self::ExternalFun(x) => match other {
+                Self::ExternalFun(y) => x.as_ref().exact_eq(y.as_ref()),
+                _ => false,
+            },
             Self::Pid(x) => match other {
                 Self::Pid(y) => x.as_ref().exact_eq(y.as_ref()),
                 _ => false,
@@ -828,6 +889,64 @@ impl ExactEq for Term {
             },
         }
     }
+}
+impl ExactEq for Term {
+    /// Lists and tuples are walked iteratively, with an explicit stack of the element pairs still
+    /// to be compared, so comparing a long list cannot overflow the native stack.
+    ///
+    /// The walk stops at the first difference, checks tuple arity before any elements, and skips
+    /// subterms which are the same term, e.g. shared tails or the same literal.
+    fn exact_eq(&self, other: &Self) -> bool {
+        let mut pending: Vec<(OpaqueTerm, OpaqueTerm)> = Vec::new();
+        let mut lhs = *self;
+        let mut rhs = *other;
+
+        loop {
+            let equal = match (lhs, rhs) {
+                (Self::Cons(x), Self::Cons(y)) => {
+                    if x != y {
+                        let (x, y) = unsafe { (x.as_ref(), y.as_ref()) };
+                        // The head is popped first, so the list is compared in order
+                        pending.push((x.tail, y.tail));
+                        pending.push((x.head, y.head));
+                    }
+                    true
+                }
+                (Self::Tuple(x), Self::Tuple(y)) => {
+                    if x == y {
+                        true
+                    } else {
+                        let (x, y) = unsafe { (x.as_ref(), y.as_ref()) };
+                        if x.len() == y.len() {
+                            let elements = x.as_slice().iter().zip(y.as_slice().iter());
+                            pending.extend(elements.rev().map(|(x, y)| (*x, *y)));
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                }
+                (lhs, rhs) => lhs.exact_eq_unwalked(&rhs),
+            };
+
+            if !equal {
+                return false;
+            }
+
+            loop {
+                match pending.pop() {
+                    None => return true,
+                    // The same encoded term is always exactly equal to itself
+                    Some((x, y)) if x == y => continue,
+                    Some((x, y)) => {
+                        lhs = x.into();
+                        rhs = y.into();
+                        break;
+                    }
+                }
+            }
+        }
+    }
 
     #[inline]
     fn exact_ne(&self, other: &Self) -> bool {
@@ -931,6 +1050,19 @@ impl Ord for Term {
                 | Self::Reference(_) => Ordering::Greater,
                 _ => Ordering::Less,
             },
+            // Local funs sort before external funs, as in BEAM
+            Self::ExternalFun(x) => match other {
+                Self::ExternalFun(y) => x.cmp(y),
+                Self::None
+                | Self::Int(_)
+                | Self::BigInt(_)
+                | Self::Float(_)
+                | Self::Bool(_)
+                | Self::Atom(_)
+                | Self::Reference(_)
+                | Self::Closure(_) => Ordering::Greater,
+                _ => Ordering::Less,
+            },
             Self::Port(x) => match other {
                 Self::Port(y) => x.cmp(y),
                 Self::None
@@ -940,7 +1072,8 @@ impl Ord for Term {
                 | Self::Bool(_)
                 | Self::Atom(_)
                 | Self::Reference(_)
-                | Self::Closure(_) => Ordering::Greater,
+                | Self::Closure(_)
+                | Self::ExternalFun(_) => Ordering::Greater,
                 _ => Ordering::Less,
             },
             Self::Pid(x) => match other {
@@ -953,6 +1086,7 @@ impl Ord for Term {
                 | Self::Atom(_)
                 | Self::Reference(_)
                 | Self::Closure(_)
+                | Self::ExternalFun(_)
                 | Self::Port(_) => Ordering::Greater,
                 _ => Ordering::Less,
             },
@@ -966,6 +1100,7 @@ impl Ord for Term {
                 | Self::Atom(_)
                 | Self::Reference(_)
                 | Self::Closure(_)
+                | Self::ExternalFun(_)
                 | Self::Port(_)
                 | Self::Pid(_) => Ordering::Greater,
                 _ => Ordering::Less,
@@ -980,6 +1115,7 @@ impl Ord for Term {
                 | Self::Atom(_)
                 | Self::Reference(_)
                 | Self::Closure(_)
+                | Self::ExternalFun(_)
                 | Self::Port(_)
                 | Self::Pid(_)
                 | Self::Tuple(_) => Ordering::Greater,
@@ -995,6 +1131,7 @@ impl Ord for Term {
                 | Self::Atom(_)
                 | Self::Reference(_)
                 | Self::Closure(_)
+                | Self::ExternalFun(_)
                 | Self::Port(_)
                 | Self::Pid(_)
                 | Self::Tuple(_)
@@ -1011,6 +1148,7 @@ impl Ord for Term {
                 | Self::Atom(_)
                 | Self::Reference(_)
                 | Self::Closure(_)
+                | Self::ExternalFun(_)
                 | Self::Port(_)
                 | Self::Pid(_)
                 | Self::Tuple(_)
@@ -1214,3 +1352,157 @@ mod test {
     }
 }
 */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::format;
+    use alloc::string::ToString;
+
+    use crate::process::Process;
+
+    extern "C" fn callee() {}
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(Atom::try_from(name).unwrap())
+    }
+
+    fn tuple(elements: &[Term], process: &Process) -> Term {
+        let elements = elements.iter().map(|t| (*t).into()).collect::<Vec<_>>();
+        Term::Tuple(Tuple::from_slice(&elements, process).unwrap())
+    }
+
+    fn list(elements: &[Term], process: &Process) -> Term {
+        let mut builder = ListBuilder::new(process);
+        for element in elements.iter().rev() {
+            builder.push(*element).unwrap();
+        }
+        builder.finish().map(Term::Cons).unwrap_or(Term::Nil)
+    }
+
+    /// Allocates a single cell, so `tail` can make the list improper
+    fn cons(head: Term, tail: Term, process: &Process) -> Term {
+        let ptr = Cons::new_in(process).unwrap();
+        unsafe {
+            ptr.as_ptr().write(Cons::cons(head, tail));
+        }
+        Term::Cons(ptr)
+    }
+
+    fn charlist(s: &str, process: &Process) -> Term {
+        Cons::charlist_from_str(s, process)
+            .unwrap()
+            .map(Term::Cons)
+            .unwrap_or(Term::Nil)
+    }
+
+    fn binary(bytes: &[u8], process: &Process) -> Term {
+        let mut bin = BinaryData::with_capacity_small(bytes.len(), process).unwrap();
+        bin.copy_from_slice(bytes);
+        bin.into()
+    }
+
+    fn map(entries: &[(Term, Term)], process: &Process) -> Term {
+        Term::Map(Map::new_from_iter_in(entries.iter().copied(), process).unwrap())
+    }
+
+    // The expected output is what `erlang:display/1` prints in OTP 25 for the same terms, except
+    // that map associations are written `K => V`, as `~w` does, rather than `K=>V`
+    #[test]
+    fn terms_are_displayed_as_beam_displays_them() {
+        let process = process();
+        let int = Term::Int;
+        let improper = cons(int(1), cons(int(2), atom("c"), &process), &process);
+        let nested = tuple(
+            &[
+                atom("a"),
+                atom("B"),
+                improper,
+                charlist("abc", &process),
+                Term::Nil,
+                tuple(&[], &process),
+            ],
+            &process,
+        );
+        let keyed = map(
+            &[
+                (atom("b"), list(&[atom("x")], &process)),
+                (atom("a"), tuple(&[int(1), int(2)], &process)),
+                (int(1), atom("nil")),
+            ],
+            &process,
+        );
+        static BITS: [u8; 2] = [1, 0b0100_0000];
+        let bits = unsafe { BitSlice::new(OpaqueTerm::NONE, &BITS, 0, 11) };
+        let bits = Term::RefBinary(GcBox::new_in(bits, &process).unwrap());
+        let binaries = list(
+            &[
+                binary(b"abc", &process),
+                binary(&[1, 2, 3], &process),
+                binary(b"", &process),
+                binary("é".as_bytes(), &process),
+                binary(b"say \"hi\"", &process),
+                bits,
+            ],
+            &process,
+        );
+        let strings = list(
+            &[
+                charlist("a\"b\n", &process),
+                list(&[int(1), int(2)], &process),
+            ],
+            &process,
+        );
+        let pid = Term::Pid(GcBox::new_in(Pid::new_local(5, 0).unwrap(), &process).unwrap());
+        let reference = Reference::Local {
+            id: ReferenceId::new(1, (2 << 32) | 3),
+        };
+        let reference = Term::Reference(GcBox::new_in(reference, &process).unwrap());
+        let ids = list(&[pid, reference], &process);
+        let empty = map(&[], &process);
+        let in_map = map(&[(nested, keyed), (ids, empty)], &process);
+
+        let corpus = [
+            (nested, "{a,'B',[1,2|c],\"abc\",[],{}}"),
+            (keyed, "#{1 => nil,a => {1,2},b => [x]}"),
+            (
+                binaries,
+                "[<<\"abc\">>,<<1,2,3>>,<<>>,<<195,169>>,<<\"say \\\"hi\\\"\">>,<<1,2:3>>]",
+            ),
+            (strings, "[\"a\\\"b\\n\",[1,2]]"),
+            (ids, "[<0.5.0>,#Ref<0.1.2.3>]"),
+            (empty, "#{}"),
+            (
+                in_map,
+                "#{{a,'B',[1,2|c],\"abc\",[],{}} => #{1 => nil,a => {1,2},b => [x]},\
+                 [<0.5.0>,#Ref<0.1.2.3>] => #{}}",
+            ),
+        ];
+        for (term, expected) in corpus {
+            assert_eq!(term.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn funs_are_displayed_with_their_module_index_and_uniq() {
+        let process = process();
+        let module = Atom::try_from("display_test").unwrap();
+        let name = Atom::try_from("-main/0-fun-1-").unwrap();
+        let lambda = Closure::new_in(module, name, 1, callee as *const (), &[], &process).unwrap();
+        let capture =
+            Closure::new_in(module, module, 1, callee as *const (), &[], &process).unwrap();
+
+        let uniq = phash2(Term::Atom(name));
+        assert_eq!(
+            Term::Closure(lambda).to_string(),
+            format!("#Fun<display_test.1.{}>", uniq)
+        );
+        assert!(Term::Closure(capture)
+            .to_string()
+            .starts_with("#Fun<display_test.0."));
+    }
+}
@@ -1,8 +1,11 @@
 use core::hash::{Hash, Hasher};
-use core::ptr::NonNull;
-use core::sync::atomic::AtomicPtr;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicPtr, Ordering};
 
-use super::{atom::AtomData, Atom};
+use super::{atom::AtomData, atoms, Atom};
+
+/// The name of the local node, or null until the node is named
+static LOCAL_NAME: AtomicPtr<AtomData> = AtomicPtr::new(ptr::null_mut());
 
 #[repr(C)]
 #[derive(Debug)]
@@ -27,10 +30,23 @@ impl Node {
         self.id
     }
 
+    /// Returns the name of the local node, which is `nonode@nohost` until it is named
+    pub fn local_name() -> Atom {
+        NonNull::new(LOCAL_NAME.load(Ordering::Relaxed))
+            .map(|ptr| ptr.into())
+            .unwrap_or(atoms::NonodeAtNohost)
+    }
+
+    /// Names the local node
+    ///
+    /// This is expected to be done once, while the runtime is initialized.
+    pub fn set_local_name(name: Atom) {
+        assert!(!name.is_boolean(), "a node cannot be named {}", name);
+        LOCAL_NAME.store(unsafe { name.as_ptr() as *mut AtomData }, Ordering::Relaxed);
+    }
+
     /// Returns the name of this node as an atom, if one was set
     pub fn name(&self) -> Option<Atom> {
-        use core::sync::atomic::Ordering;
-
         NonNull::new(self.name.load(Ordering::Relaxed)).map(|ptr| ptr.into())
     }
 
@@ -68,3 +84,34 @@ impl PartialOrd for Node {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use alloc::sync::Arc;
+
+    use firefly_alloc::gc::GcBox;
+
+    use super::*;
+    use crate::term::{Pid, Term};
+
+    #[test]
+    fn node_name_of_pids_is_the_name_of_their_node() {
+        let local = Term::Pid(GcBox::new(Pid::new_local(1, 1).unwrap()));
+        assert_eq!(local.node_name(), Some(atoms::NonodeAtNohost));
+
+        let name = Atom::try_from("local@host").unwrap();
+        Node::set_local_name(name);
+        assert_eq!(Node::local_name(), name);
+        assert_eq!(local.node_name(), Some(name));
+
+        let remote_name = Atom::try_from("remote@host").unwrap();
+        let node = Arc::new(Node::new(1, remote_name, 0));
+        let remote = Term::Pid(GcBox::new(Pid::new_external(node, 1, 1).unwrap()));
+        assert_eq!(remote.node_name(), Some(remote_name));
+    }
+
+    #[test]
+    fn node_name_of_integers_is_none() {
+        assert_eq!(Term::Int(1).node_name(), None);
+    }
+}
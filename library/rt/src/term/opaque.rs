@@ -41,7 +41,7 @@
 ///!
 ///! Furthermore, Erlang does not support NaN or the infinities, so those bit patterns can be used as well. In short, we have 52 bits to
 ///! work with when a float is NaN, which is large enough to store a pointer; and since our pointers must be 8-byte aligned, we have
-///! an additional 4 bits for a limited tagging scheme. The only requirement is that we don't permit overlapping bit patterns for two
+///! an additional 3 bits for a limited tagging scheme. The only requirement is that we don't permit overlapping bit patterns for two
 ///! different term types.
 ///!
 ///! Now that you understand the background, here is the encoding we use for different immediate values:
@@ -74,16 +74,28 @@
 ///!
 ///! All non-immediate terms are allocated/referenced via `GcBox<T>`.
 ///!
+///! The sign bit and the quiet bit partition the NaN space, so every bit pattern belongs to exactly one of these, or is invalid:
+///!
+///! * Sign bit set: an integer
+///! * Sign bit clear, quiet bit set: `None` (no payload), `false`/`true` (tag 0x02/0x03, no payload), or an atom (tag 0x02)
+///! * Sign bit clear, quiet bit clear: `Nil` (tag 0x00, no payload), or a pointer (any tag but 0x02, non-null payload)
+///!
+///! The payload of a pointer is bits 3 to 50, so pointers must fit in 51 bits. This holds for user-space pointers on x86_64 and
+///! aarch64, but on aarch64 any pointer authentication code must be stripped from a pointer before it is encoded.
+///!
 use core::fmt;
 use core::mem::{self, ManuallyDrop, MaybeUninit};
 use core::num::NonZeroU32;
 use core::ptr::{self, NonNull, Pointee};
 
-use super::{atoms, Atom, BinaryData, Closure, Cons, Float, Integer, Term, Tuple};
+use super::{
+    atoms, Atom, BinaryData, Closure, Cons, ExternalFun, Float, HeapRanges, Integer, Term, Tuple,
+};
 
 use firefly_alloc::gc::{self, GcBox};
 use firefly_alloc::rc::{self, Rc, Weak};
 use firefly_binary::BinaryFlags;
+use static_assertions::{const_assert, const_assert_eq};
 
 use crate::function::ErlangResult;
 
@@ -129,6 +141,41 @@ const INT_MASK: u64 = !INTEGER_TAG;
 // This mask when applied to a u64 will return a value which can be cast to pointer type and dereferenced
 const PTR_MASK: u64 = !(SIGN_BIT | NAN | TAG_MASK);
 
+// The layout documented above, checked at compile time so that changes which break it fail to build
+const_assert_eq!(NAN, 0x7ff8_0000_0000_0000);
+const_assert_eq!(INFINITY, 0x7ff0_0000_0000_0000);
+const_assert_eq!(INTEGER_TAG, 0xfff0_0000_0000_0000);
+const_assert_eq!(INT_MASK, (1 << 52) - 1);
+const_assert_eq!(PTR_MASK, 0x0007_ffff_ffff_fff8);
+// The tag bits are those which are always zero in pointers to terms, which are at least 8-byte aligned
+const_assert_eq!(TAG_MASK, 0b111);
+const_assert_eq!(PTR_MASK & TAG_MASK, 0);
+// The special values have no payload, and are distinct from each other
+const_assert_eq!((NONE | NIL | FALSE | TRUE) & PTR_MASK, 0);
+const_assert!(NONE != NIL && NONE != FALSE && NONE != TRUE && NIL != FALSE && NIL != TRUE);
+// Atoms use the canonical NaN space, and pointers the Infinity space
+const_assert_eq!(FALSE & (NAN | SIGN_BIT), NAN);
+const_assert_eq!(TRUE & (NAN | SIGN_BIT), NAN);
+const_assert_eq!(NIL & (NAN | SIGN_BIT), INFINITY);
+// The pointer tags are distinct, and none of them is the atom tag
+const_assert!(
+    LITERAL_TAG != RC_TAG
+        && CONS_TAG != CONS_LITERAL_TAG
+        && TUPLE_TAG != TUPLE_LITERAL_TAG
+        && CONS_TAG != TUPLE_TAG
+        && CONS_LITERAL_TAG != TUPLE_LITERAL_TAG
+        && RC_TAG != CONS_LITERAL_TAG
+        && RC_TAG != TUPLE_LITERAL_TAG
+);
+const_assert!(
+    ATOM_TAG != LITERAL_TAG
+        && ATOM_TAG != RC_TAG
+        && ATOM_TAG != CONS_TAG
+        && ATOM_TAG != CONS_LITERAL_TAG
+        && ATOM_TAG != TUPLE_TAG
+        && ATOM_TAG != TUPLE_LITERAL_TAG
+);
+
 // This tag indicates a negative integer (i.e. it has our designated sign bit set)
 #[cfg(test)]
 const NEG_INTEGER_TAG: u64 = INTEGER_TAG | QUIET_BIT;
@@ -142,6 +189,17 @@ const MAX_SMALL: i64 = (!NEG_INTEGER_TAG) as i64;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ImmediateOutOfRangeError;
 
+/// The reason `OpaqueTerm::try_decode` could not decode a term
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidTermError {
+    /// The bit pattern is not the encoding of any term
+    InvalidEncoding,
+    /// The term is boxed, but does not point into any of the registered heap ranges
+    UnknownAddress,
+    /// The term is boxed, but the pointee is not of any term type
+    UnknownType,
+}
+
 /// Represents the primary term types that exist in Erlang
 ///
 /// Some types are compositions of these (e.g. list), and some
@@ -187,6 +245,10 @@ pub enum TermType {
 pub struct OpaqueTerm(u64);
 impl crate::cmp::ExactEq for OpaqueTerm {
     fn exact_eq(&self, other: &Self) -> bool {
+        // The same encoded term is always exactly equal to itself, so there's no need to decode it
+        if self.0 == other.0 {
+            return true;
+        }
         let lhs: Term = (*self).into();
         let rhs: Term = (*other).into();
         lhs.exact_eq(&rhs)
@@ -207,6 +269,17 @@ impl OpaqueTerm {
         self.0
     }
 
+    /// Reinterprets a raw u64 as an opaque term
+    ///
+    /// # Safety
+    ///
+    /// Unless the resulting term is only decoded with `try_decode`, `raw` must be the encoding of a
+    /// valid term, e.g. a value previously returned by `raw`.
+    #[inline(always)]
+    pub unsafe fn from_raw(raw: u64) -> Self {
+        Self(raw)
+    }
+
     /// This is a low-level decoding function written in this specific way in order to
     /// maximize the optimizations the compiler can perform from higher-level conversions
     ///
@@ -220,9 +293,17 @@ impl OpaqueTerm {
             FALSE => term.write(Term::Bool(false)),
             TRUE => term.write(Term::Bool(true)),
             i if i & INTEGER_TAG == INTEGER_TAG => term.write(Term::Int(value.as_integer())),
+            // Other than the special values matched above, the canonical NaN space only holds atoms
+            other if other & NAN == NAN => match other & TAG_MASK {
+                ATOM_TAG => term.write(Term::Atom(value.as_atom())),
+                _ => return false,
+            },
+            // Other than nil, the Infinity space only holds non-null pointers
             other if value.is_nan() => {
+                if other & PTR_MASK == 0 {
+                    return false;
+                }
                 match other & TAG_MASK {
-                    ATOM_TAG => term.write(Term::Atom(value.as_atom())),
                     CONS_TAG | CONS_LITERAL_TAG => {
                         term.write(Term::Cons(NonNull::new_unchecked(
                             value.as_ptr() as *mut Cons
@@ -266,6 +347,9 @@ impl OpaqueTerm {
                             super::Closure::TYPE_ID => {
                                 term.write(Term::Closure(GcBox::from_raw_unchecked(ptr)));
                             }
+                            ExternalFun::TYPE_ID => {
+                                term.write(Term::ExternalFun(GcBox::from_raw_unchecked(ptr)));
+                            }
                             super::Pid::TYPE_ID => {
                                 term.write(Term::Pid(GcBox::from_raw_unchecked(ptr)));
                             }
@@ -293,6 +377,28 @@ impl OpaqueTerm {
         true
     }
 
+    /// Decodes this term without assuming that it is well-formed
+    ///
+    /// Unlike the conversion to `Term`, this never reads memory outside of `heaps`, so it can be used
+    /// on arbitrary bit patterns, e.g. to inspect memory when debugging. Boxed terms which point into
+    /// `heaps` are trusted to point to a valid box, and atoms are trusted to point to valid atom
+    /// data, as atom data is never freed.
+    pub fn try_decode(self, heaps: &HeapRanges) -> Result<Term, InvalidTermError> {
+        let is_box = self.is_box();
+        if is_box && !heaps.contains(unsafe { self.as_ptr() }) {
+            return Err(InvalidTermError::UnknownAddress);
+        }
+
+        let mut term = MaybeUninit::uninit();
+        if unsafe { Self::decode(self, term.as_mut_ptr()) } {
+            Ok(unsafe { term.assume_init() })
+        } else if is_box {
+            Err(InvalidTermError::UnknownType)
+        } else {
+            Err(InvalidTermError::InvalidEncoding)
+        }
+    }
+
     /// Follows the same rules as `decode`, but simply returns the detected term type
     #[inline]
     pub fn r#typeof(self) -> TermType {
@@ -302,9 +408,13 @@ impl OpaqueTerm {
             FALSE | TRUE => TermType::Bool,
             i if i & INTEGER_TAG == INTEGER_TAG => TermType::Int,
             _other if !self.is_nan() => TermType::Float,
+            other if other & NAN == NAN => match other & TAG_MASK {
+                ATOM_TAG => TermType::Atom,
+                _ => TermType::Invalid,
+            },
+            other if other & PTR_MASK == 0 => TermType::Invalid,
             other => {
                 match other & TAG_MASK {
-                    ATOM_TAG => TermType::Atom,
                     CONS_TAG | CONS_LITERAL_TAG => TermType::Cons,
                     TUPLE_TAG | TUPLE_LITERAL_TAG => TermType::Tuple,
                     RC_TAG => {
@@ -324,7 +434,8 @@ impl OpaqueTerm {
                         match unsafe { GcBox::<()>::type_id(ptr) } {
                             Integer::BIGINT_TYPE_ID => TermType::Int,
                             super::Map::TYPE_ID => TermType::Map,
-                            super::Closure::TYPE_ID => TermType::Closure,
+                            // External funs are funs as far as type checks are concerned
+                            super::Closure::TYPE_ID | ExternalFun::TYPE_ID => TermType::Closure,
                             super::Pid::TYPE_ID => TermType::Pid,
                             super::Port::TYPE_ID => TermType::Port,
                             super::Reference::TYPE_ID => TermType::Reference,
@@ -368,14 +479,10 @@ impl OpaqueTerm {
     pub fn is_box(self) -> bool {
         // The tag bits uniquely identify all boxed types, but only in conjunction with the hi tag
         match self.0 & TAG_MASK {
-            // The only special case is the tag for GcBox overlaps with Nil, but GcBox pointers cannot
-            // be null, so that's how we distinguish the two
-            0 => self.0 != NIL && self.0 & (NAN | SIGN_BIT) == INFINITY,
-            LITERAL_TAG | RC_TAG | CONS_TAG | CONS_LITERAL_TAG | TUPLE_TAG | TUPLE_LITERAL_TAG => {
-                // All pointer types use Infinity for their hi tag
-                self.0 & (NAN | SIGN_BIT) == INFINITY
-            }
-            _ => false,
+            ATOM_TAG => false,
+            // All pointer types use Infinity for their hi tag, and pointers cannot be null, which
+            // is also how a GcBox is distinguished from Nil, as their tags overlap
+            _ => self.0 & (NAN | SIGN_BIT) == INFINITY && self.0 & PTR_MASK != 0,
         }
     }
 
@@ -388,13 +495,13 @@ impl OpaqueTerm {
     /// Returns true if this term is a non-null pointer to a Rc<T> term
     #[inline]
     pub fn is_rc(self) -> bool {
-        self.0 & (NAN | SIGN_BIT | TAG_MASK) == (INFINITY | RC_TAG)
+        self.0 & (NAN | SIGN_BIT | TAG_MASK) == (INFINITY | RC_TAG) && self.0 & PTR_MASK != 0
     }
 
     /// Returns true if this term is a non-null pointer to a literal term
     #[inline]
     pub fn is_literal(self) -> bool {
-        self.0 & (NAN | SIGN_BIT | TAG_MASK) == (INFINITY | LITERAL_TAG)
+        self.0 & (NAN | SIGN_BIT | TAG_MASK) == (INFINITY | LITERAL_TAG) && self.0 & PTR_MASK != 0
     }
 
     /// Returns true if this term is the None value
@@ -413,11 +520,8 @@ impl OpaqueTerm {
     #[inline]
     pub fn is_atom(self) -> bool {
         const IS_ATOM: u64 = NAN | ATOM_TAG;
-        const IS_TRUE: u64 = TRUE;
-        match self.0 & (NAN | SIGN_BIT | TAG_MASK) {
-            IS_ATOM | IS_TRUE => true,
-            _ => false,
-        }
+        // The tag of `true` is only valid without a payload
+        self.0 & (NAN | SIGN_BIT | TAG_MASK) == IS_ATOM || self.0 == TRUE
     }
 
     /// Returns true only if this term is an immediate integer
@@ -450,7 +554,7 @@ impl OpaqueTerm {
         const IS_CONS_LITERAL: u64 = INFINITY | CONS_LITERAL_TAG;
 
         match self.0 & (NAN | SIGN_BIT | TAG_MASK) {
-            IS_CONS | IS_CONS_LITERAL => true,
+            IS_CONS | IS_CONS_LITERAL => self.0 & PTR_MASK != 0,
             _ => false,
         }
     }
@@ -462,7 +566,7 @@ impl OpaqueTerm {
         const IS_CONS_LITERAL: u64 = INFINITY | CONS_LITERAL_TAG;
 
         match self.0 & (NAN | SIGN_BIT | TAG_MASK) {
-            IS_CONS | IS_CONS_LITERAL => true,
+            IS_CONS | IS_CONS_LITERAL => self.0 & PTR_MASK != 0,
             _ => self.0 == NIL,
         }
     }
@@ -486,7 +590,7 @@ impl OpaqueTerm {
         const IS_TUPLE_LITERAL: u64 = INFINITY | TUPLE_LITERAL_TAG;
 
         match self.0 & (NAN | SIGN_BIT | TAG_MASK) {
-            IS_TUPLE | IS_TUPLE_LITERAL => unsafe {
+            IS_TUPLE | IS_TUPLE_LITERAL if self.0 & PTR_MASK != 0 => unsafe {
                 let ptr = self.as_ptr();
                 let meta_ptr: *const usize = ptr.cast();
                 ErlangResult::Ok((*meta_ptr) as u32)
@@ -629,16 +733,25 @@ impl OpaqueTerm {
     pub unsafe fn from_gcbox_closure(closure: &Closure) -> Self {
         let closure = closure as *const Closure;
         let (raw, _) = closure.to_raw_parts();
-        let raw = raw as u64;
+        Self::from_ptr(raw as u64, 0)
+    }
+
+    /// Encodes a pointer to a boxed term with the given tag
+    ///
+    /// The pointer must be non-null, at least 8-byte aligned, and fit in the payload bits, see the
+    /// module documentation.
+    #[inline(always)]
+    fn from_ptr(raw: u64, tag: u64) -> Self {
+        debug_assert_ne!(raw, 0, "expected pointer to be non-null");
         debug_assert!(
-            raw & INFINITY == 0,
+            raw & !(PTR_MASK | TAG_MASK) == 0,
             "expected nan bits to be unused in pointers"
         );
         debug_assert!(
             raw & TAG_MASK == 0,
             "expected pointer to have at least 8-byte alignment"
         );
-        Self(raw | INFINITY)
+        Self(raw | INFINITY | tag)
     }
 }
 impl fmt::Binary for OpaqueTerm {
@@ -678,6 +791,11 @@ impl From<f64> for OpaqueTerm {
     #[inline]
     fn from(f: f64) -> Self {
         assert!(!f.is_infinite());
+        // Any NaN is None, rather than only canonical NaN, as a NaN with the sign bit set would
+        // otherwise be an integer
+        if f.is_nan() {
+            return Self::NONE;
+        }
         Self(f.to_bits())
     }
 }
@@ -690,7 +808,12 @@ impl From<Float> for OpaqueTerm {
 impl From<Atom> for OpaqueTerm {
     #[inline]
     fn from(a: Atom) -> Self {
-        Self(unsafe { a.as_ptr() as u64 | FALSE })
+        let raw = unsafe { a.as_ptr() as u64 };
+        debug_assert!(
+            raw & !(PTR_MASK | TAG_MASK) == 0,
+            "expected nan bits to be unused in atom pointers"
+        );
+        Self(raw | FALSE)
     }
 }
 impl<T: ?Sized> From<GcBox<T>> for OpaqueTerm
@@ -699,15 +822,7 @@ where
 {
     fn from(boxed: GcBox<T>) -> Self {
         let raw = GcBox::into_raw(boxed) as *const () as u64;
-        debug_assert!(
-            raw & INFINITY == 0,
-            "expected nan bits to be unused in pointers"
-        );
-        debug_assert!(
-            raw & TAG_MASK == 0,
-            "expected pointer to have at least 8-byte alignment"
-        );
-        Self(raw | INFINITY)
+        Self::from_ptr(raw, 0)
     }
 }
 impl<T: ?Sized> From<Rc<T>> for OpaqueTerm
@@ -716,15 +831,7 @@ where
 {
     fn from(boxed: Rc<T>) -> Self {
         let raw = Rc::into_raw(boxed) as *const () as u64;
-        debug_assert!(
-            raw & INFINITY == 0,
-            "expected nan bits to be unused in pointers"
-        );
-        debug_assert!(
-            raw & TAG_MASK == 0,
-            "expected pointer to have at least 8-byte alignment"
-        );
-        Self(raw | INFINITY | RC_TAG)
+        Self::from_ptr(raw, RC_TAG)
     }
 }
 impl<T: ?Sized> From<Weak<T>> for OpaqueTerm
@@ -733,58 +840,26 @@ where
 {
     fn from(weak: Weak<T>) -> Self {
         let raw = Weak::into_raw(weak) as *const () as u64;
-        debug_assert!(
-            raw & INFINITY == 0,
-            "expected nan bits to be unused in pointers"
-        );
-        debug_assert!(
-            raw & TAG_MASK == 0,
-            "expected pointer to have at least 8-byte alignment"
-        );
-        Self(raw | INFINITY | RC_TAG)
+        Self::from_ptr(raw, RC_TAG)
     }
 }
 impl From<NonNull<Cons>> for OpaqueTerm {
     fn from(ptr: NonNull<Cons>) -> Self {
         let raw = ptr.as_ptr() as u64;
-        debug_assert!(
-            raw & INFINITY == 0,
-            "expected nan bits to be unused in pointers"
-        );
-        debug_assert!(
-            raw & TAG_MASK == 0,
-            "expected pointer to have at least 8-byte alignment"
-        );
-        Self(raw | INFINITY | CONS_TAG)
+        Self::from_ptr(raw, CONS_TAG)
     }
 }
 impl From<NonNull<Tuple>> for OpaqueTerm {
     fn from(ptr: NonNull<Tuple>) -> Self {
         let (raw, _meta) = ptr.to_raw_parts();
         let raw = raw.as_ptr() as u64;
-        debug_assert!(
-            raw & INFINITY == 0,
-            "expected nan bits to be unused in pointers"
-        );
-        debug_assert!(
-            raw & TAG_MASK == 0,
-            "expected pointer to have at least 8-byte alignment"
-        );
-        Self(raw | INFINITY | TUPLE_TAG)
+        Self::from_ptr(raw, TUPLE_TAG)
     }
 }
 impl From<&'static BinaryData> for OpaqueTerm {
     fn from(data: &'static BinaryData) -> Self {
         let raw = data as *const _ as *const () as u64;
-        debug_assert!(
-            raw & INFINITY == 0,
-            "expected nan bits to be unused in pointers"
-        );
-        debug_assert!(
-            raw & TAG_MASK == 0,
-            "expected pointer to have at least 8-byte alignment"
-        );
-        Self(raw | INFINITY | LITERAL_TAG)
+        Self::from_ptr(raw, LITERAL_TAG)
     }
 }
 impl From<Term> for OpaqueTerm {
@@ -801,6 +876,7 @@ impl From<Term> for OpaqueTerm {
             Term::Tuple(ptr) => ptr.into(),
             Term::Map(boxed) => boxed.into(),
             Term::Closure(boxed) => boxed.into(),
+            Term::ExternalFun(boxed) => boxed.into(),
             Term::Pid(boxed) => boxed.into(),
             Term::Port(boxed) => boxed.into(),
             Term::Reference(boxed) => boxed.into(),
@@ -830,6 +906,7 @@ mod tests {
     use alloc::alloc::Global;
     use alloc::alloc::Layout;
     use alloc::boxed::Box;
+    use alloc::vec;
     use alloc::vec::Vec;
     use core::mem::MaybeUninit;
     use core::num::NonZeroU32;
@@ -838,6 +915,7 @@ mod tests {
     use firefly_arena::DroplessArena;
     use firefly_binary::{BinaryFlags, Bitstring, Encoding, Selection};
 
+    use crate::process::Process;
     use crate::term::*;
 
     use super::*;
@@ -1112,6 +1190,11 @@ mod tests {
         let term: OpaqueTerm = closure.into();
         assert_eq!(term.r#typeof(), TermType::Closure);
 
+        // External fun, which is a fun as far as type checks are concerned
+        let fun = GcBox::new(ExternalFun::new(atoms::Erlang, atoms::Error, 1));
+        let term: OpaqueTerm = fun.into();
+        assert_eq!(term.r#typeof(), TermType::Closure);
+
         // Pid
         let pid = GcBox::new(Pid::new_local(1, 1).unwrap());
         let term: OpaqueTerm = pid.into();
@@ -1457,6 +1540,232 @@ mod tests {
         let _ = unsafe { Rc::from_raw(rc_ptr) };
     }
 
+    #[test]
+    fn opaque_term_negative_nan_is_none() {
+        let nan: OpaqueTerm = (-f64::NAN).into();
+        assert_eq!(nan, OpaqueTerm::NONE);
+    }
+
+    /// How a bit pattern is expected to be classified, without dereferencing it
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    enum Class {
+        None,
+        Nil,
+        Bool,
+        Atom,
+        Int,
+        Float,
+        Box,
+        Invalid,
+    }
+
+    #[test]
+    fn opaque_term_tag_space_is_exhaustively_classified() {
+        // A non-null, aligned payload which is not a registered address
+        const CANARY: u64 = 0x0000_5a5a_5a5a_5a58 & PTR_MASK;
+        let his = [
+            (0, Class::Float),
+            (SIGN_BIT, Class::Float),
+            (INFINITY & !(1 << 52), Class::Float),
+            ((INFINITY | SIGN_BIT) & !(1 << 52), Class::Float),
+            (INTEGER_TAG, Class::Int),
+            (INTEGER_TAG | QUIET_BIT, Class::Int),
+            (NAN, Class::Invalid),
+            (INFINITY, Class::Invalid),
+        ];
+        let heaps = HeapRanges::new();
+
+        for (hi, hi_class) in his {
+            for tag in 0..=TAG_MASK {
+                for payload in [0, CANARY] {
+                    let raw = hi | tag | payload;
+                    let class = match (hi, tag, payload) {
+                        (NAN, 0, 0) => Class::None,
+                        (NAN, ATOM_TAG, 0) | (NAN, RC_TAG, 0) => Class::Bool,
+                        (NAN, ATOM_TAG, _) => Class::Atom,
+                        (INFINITY, 0, 0) => Class::Nil,
+                        (INFINITY, ATOM_TAG, _) | (INFINITY, _, 0) => Class::Invalid,
+                        (INFINITY, _, _) => Class::Box,
+                        _ => hi_class,
+                    };
+                    let term = OpaqueTerm(raw);
+
+                    let decoded = term.try_decode(&heaps);
+                    match class {
+                        Class::None => assert_matches!(decoded, Ok(Term::None), "{:#x}", raw),
+                        Class::Nil => assert_matches!(decoded, Ok(Term::Nil), "{:#x}", raw),
+                        Class::Bool => assert_matches!(decoded, Ok(Term::Bool(_)), "{:#x}", raw),
+                        Class::Atom => assert_matches!(decoded, Ok(Term::Atom(_)), "{:#x}", raw),
+                        Class::Int => assert_matches!(decoded, Ok(Term::Int(_)), "{:#x}", raw),
+                        Class::Float => assert_matches!(decoded, Ok(Term::Float(_)), "{:#x}", raw),
+                        Class::Box => assert_eq!(
+                            decoded.err(),
+                            Some(InvalidTermError::UnknownAddress),
+                            "{:#x}",
+                            raw
+                        ),
+                        Class::Invalid => assert_eq!(
+                            decoded.err(),
+                            Some(InvalidTermError::InvalidEncoding),
+                            "{:#x}",
+                            raw
+                        ),
+                    }
+
+                    // The predicates must agree with decoding, and none of them may dereference
+                    // the canary
+                    assert_eq!(term.is_none(), class == Class::None, "{:#x}", raw);
+                    assert_eq!(term.is_nil(), class == Class::Nil, "{:#x}", raw);
+                    assert_eq!(
+                        term.is_atom(),
+                        class == Class::Bool || class == Class::Atom,
+                        "{:#x}",
+                        raw
+                    );
+                    assert_eq!(term.is_integer(), class == Class::Int, "{:#x}", raw);
+                    assert_eq!(term.is_float(), class == Class::Float, "{:#x}", raw);
+                    assert_eq!(term.is_box(), class == Class::Box, "{:#x}", raw);
+                    assert_eq!(
+                        term.is_gcbox(),
+                        class == Class::Box && tag == 0,
+                        "{:#x}",
+                        raw
+                    );
+                    assert_eq!(
+                        term.is_rc(),
+                        class == Class::Box && tag == RC_TAG,
+                        "{:#x}",
+                        raw
+                    );
+                    assert_eq!(
+                        term.is_literal(),
+                        class == Class::Box && tag == LITERAL_TAG,
+                        "{:#x}",
+                        raw
+                    );
+                    let is_cons = class == Class::Box && tag & !LITERAL_TAG == CONS_TAG;
+                    assert_eq!(term.is_nonempty_list(), is_cons, "{:#x}", raw);
+                    assert_eq!(term.is_list(), is_cons || class == Class::Nil, "{:#x}", raw);
+                    if class != Class::Box {
+                        assert_eq!(term.tuple_size(), ErlangResult::Err(()), "{:#x}", raw);
+                        let expected = match class {
+                            Class::None => TermType::None,
+                            Class::Nil => TermType::Nil,
+                            Class::Bool => TermType::Bool,
+                            Class::Atom => TermType::Atom,
+                            Class::Int => TermType::Int,
+                            Class::Float => TermType::Float,
+                            Class::Box | Class::Invalid => TermType::Invalid,
+                        };
+                        assert_eq!(term.r#typeof(), expected, "{:#x}", raw);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn opaque_term_round_trip() {
+        let process = Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap());
+        let mut heaps = HeapRanges::new();
+        heaps.register_heap(&process);
+
+        let mut terms = vec![
+            Term::None,
+            Term::Nil,
+            Term::Bool(false),
+            Term::Bool(true),
+            Term::Atom(atoms::Error),
+            Term::Atom(Atom::try_from("opaque_term_round_trip").unwrap()),
+            Term::Int(0),
+            Term::Int(1),
+            Term::Int(-1),
+            Term::Int(MIN_SMALL),
+            Term::Int(MAX_SMALL),
+            Term::Float(0.0.into()),
+            Term::Float((-0.0).into()),
+            Term::Float(f64::MAX.into()),
+            Term::Float(f64::MIN.into()),
+            Term::Float(f64::MIN_POSITIVE.into()),
+            Term::Float(f64::from_bits(1).into()),
+            Term::Float((-f64::from_bits(1)).into()),
+        ];
+
+        // Immediates generated from a fixed seed, so that failures are reproducible
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..1000 {
+            let int = (next() as i64) >> 12;
+            assert!(OpaqueTerm::is_small_integer(int));
+            terms.push(Term::Int(int));
+
+            let float = f64::from_bits(next());
+            if float.is_finite() {
+                terms.push(Term::Float(float.into()));
+            }
+        }
+
+        // Every type of boxed term, allocated on the registered process heap
+        let big = GcBox::new_in(BigInt::from(MAX_SMALL) + 1, &process).unwrap();
+        terms.push(Term::BigInt(big));
+        let mut builder = ListBuilder::new(&process);
+        builder.push(Term::Int(1)).unwrap();
+        builder.push(Term::Nil).unwrap();
+        terms.push(Term::Cons(builder.finish().unwrap()));
+        let tuple = Tuple::from_slice(&[atoms::Ok.into(), OpaqueTerm::NIL], &process).unwrap();
+        terms.push(Term::Tuple(tuple));
+        let mut map = Map::new_in(&process).unwrap();
+        map.insert_mut(Term::Int(1), Term::Atom(atoms::True));
+        terms.push(Term::Map(map));
+        let fun = erlang_error_1 as *const ();
+        let closure = Closure::new_in(atoms::Erlang, atoms::Error, 1, fun, &[], &process).unwrap();
+        terms.push(Term::Closure(closure));
+        let external = ExternalFun::new(atoms::Erlang, atoms::Error, 1);
+        let external = GcBox::new_in(external, &process).unwrap();
+        terms.push(Term::ExternalFun(external));
+        let pid = GcBox::new_in(Pid::new_local(1, 1).unwrap(), &process).unwrap();
+        terms.push(Term::Pid(pid));
+        let port = Port::Local {
+            id: unsafe { PortId::from_raw(1) },
+        };
+        terms.push(Term::Port(GcBox::new_in(port, &process).unwrap()));
+        let reference = Reference::Local {
+            id: ReferenceId::new(1, 1),
+        };
+        terms.push(Term::Reference(GcBox::new_in(reference, &process).unwrap()));
+        let mut bin = BinaryData::with_capacity_small(3, &process).unwrap();
+        bin.copy_from_slice(b"abc");
+        let bits =
+            unsafe { core::mem::transmute::<_, &'static dyn Bitstring>(&*bin as &dyn Bitstring) };
+        let selection = Selection::from_bitstring(bits);
+        let slice = BitSlice::from_selection(bin.into(), selection);
+        terms.push(Term::HeapBinary(bin));
+        terms.push(Term::RefBinary(GcBox::new_in(slice, &process).unwrap()));
+
+        // Binaries which live outside of the process heap have their allocations registered
+        let rc = BinaryData::from_str("testing 1 2 3");
+        terms.push(Term::RcBinary(Rc::into_weak(rc.clone())));
+        let mut constants = ConstantPool::default();
+        terms.push(Term::ConstantBinary(constants.insert(b"testing 1 2 3")));
+        for term in &terms[terms.len() - 2..] {
+            let ptr = unsafe { OpaqueTerm::from(*term).as_ptr() } as *const u8;
+            heaps.register(ptr..ptr.wrapping_add(1));
+        }
+
+        for term in terms {
+            let opaque: OpaqueTerm = term.into();
+            let decoded = opaque.try_decode(&heaps);
+            assert_eq!(decoded, Ok(term), "{:#x}", opaque.raw());
+            let reencoded: OpaqueTerm = decoded.unwrap().into();
+            assert_eq!(reencoded.raw(), opaque.raw());
+        }
+    }
+
     // Used for closure construction
     fn erlang_error_1(a: OpaqueTerm) -> ErlangResult {
         ErlangResult::Ok(a)
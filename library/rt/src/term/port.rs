@@ -20,6 +20,14 @@ pub enum Port {
 }
 impl Port {
     pub const TYPE_ID: TypeId = TypeId::of::<Port>();
+
+    /// Returns the node associated with this port, if applicable
+    pub fn node(&self) -> Option<Arc<Node>> {
+        match self {
+            Self::External { node, .. } => Some(node.clone()),
+            _ => None,
+        }
+    }
 }
 impl TryFrom<Term> for Port {
     type Error = ();
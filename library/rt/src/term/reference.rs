@@ -3,6 +3,7 @@ use core::any::{Any, TypeId};
 use core::fmt::{self, Display};
 use core::hash::{Hash, Hasher};
 use core::ptr;
+use core::sync::atomic::{AtomicU64, Ordering};
 
 use firefly_alloc::gc::GcBox;
 
@@ -162,34 +163,131 @@ impl Hash for Reference {
     }
 }
 
+/// The identifier of a reference, which is unique for the lifetime of the node
+///
+/// It holds the id of the scheduler that created the reference in its highest 16 bits, and the
+/// number the scheduler assigned to the reference in the rest, so ids compare by scheduler id,
+/// then by number.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct ReferenceId([u16; 4]);
+pub struct ReferenceId(u64);
 impl ReferenceId {
+    /// The largest number a scheduler can assign to a reference
+    pub const MAX_NUMBER: u64 = (1 << 48) - 1;
+
     /// Create a new reference id from raw components
     ///
-    /// NOTE: The highest 16 bits will always be zero
+    /// NOTE: The highest 16 bits of `id` must be zero
     pub fn new(scheduler_id: u16, id: u64) -> Self {
-        const MASK: u64 = 0xFFFF << 48;
-        assert_eq!(id & MASK, 0, "invalid reference id, value is too large");
-        let id = id | ((scheduler_id as u64) << 48);
-        Self(unsafe { core::mem::transmute::<u64, [u16; 4]>(id) })
+        assert!(
+            id <= Self::MAX_NUMBER,
+            "invalid reference id, value is too large"
+        );
+        Self(id | ((scheduler_id as u64) << 48))
     }
 
     /// Return the scheduler id contained in this reference
     pub fn scheduler_id(&self) -> u16 {
-        self.0[0]
+        (self.0 >> 48) as u16
+    }
+
+    /// Return the number the scheduler assigned to this reference
+    pub fn number(&self) -> u64 {
+        self.0 & Self::MAX_NUMBER
     }
 
     /// Get this reference id as a raw 64-bit integer value
     pub fn as_u64(&self) -> u64 {
-        unsafe { core::mem::transmute::<[u16; 4], u64>(self.0) }
+        self.0
     }
 }
 impl Display for ReferenceId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let r0 = self.0[1];
-        let r1 = self.0[2];
-        let r2 = self.0[3];
-        write!(f, "{}.{}.{}", r0, r1, r2)
+        let number = self.number();
+        write!(
+            f,
+            "{}.{}.{}",
+            self.scheduler_id(),
+            number >> 32,
+            number as u32
+        )
+    }
+}
+
+/// Generates the ids of the references created by one scheduler, in increasing order
+#[derive(Debug)]
+pub struct ReferenceIdGenerator {
+    scheduler_id: u16,
+    next_number: AtomicU64,
+}
+impl ReferenceIdGenerator {
+    pub const fn new(scheduler_id: u16) -> Self {
+        Self {
+            scheduler_id,
+            next_number: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the id of the scheduler the ids are generated for
+    pub fn scheduler_id(&self) -> u16 {
+        self.scheduler_id
+    }
+
+    /// Generates the next reference id
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scheduler has used up all `ReferenceId::MAX_NUMBER` numbers
+    pub fn next(&self) -> ReferenceId {
+        let number = self.next_number.fetch_add(1, Ordering::Relaxed);
+        ReferenceId::new(self.scheduler_id, number)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use alloc::vec::Vec;
+    use std::collections::HashSet;
+    use std::thread;
+
+    const REFERENCES_PER_SCHEDULER: usize = 5_000;
+
+    #[test]
+    fn reference_id_orders_by_scheduler_id_then_number() {
+        let id = ReferenceId::new(1, ReferenceId::MAX_NUMBER);
+        assert_eq!(id.scheduler_id(), 1);
+        assert_eq!(id.number(), ReferenceId::MAX_NUMBER);
+
+        assert!(ReferenceId::new(1, 2) < ReferenceId::new(1, 3));
+        assert!(ReferenceId::new(1, ReferenceId::MAX_NUMBER) < ReferenceId::new(2, 0));
+    }
+
+    #[test]
+    fn references_from_two_schedulers_are_distinct_and_increasing_per_scheduler() {
+        static FIRST: ReferenceIdGenerator = ReferenceIdGenerator::new(1);
+        static SECOND: ReferenceIdGenerator = ReferenceIdGenerator::new(2);
+
+        let handles = [&FIRST, &SECOND].map(|generator| {
+            thread::spawn(move || {
+                (0..REFERENCES_PER_SCHEDULER)
+                    .map(|_| Reference::Local {
+                        id: generator.next(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+        });
+        let [first, second] = handles.map(|handle| handle.join().unwrap());
+
+        for (references, generator) in [(&first, &FIRST), (&second, &SECOND)] {
+            assert!(references
+                .iter()
+                .all(|reference| reference.id().scheduler_id() == generator.scheduler_id()));
+            assert!(references.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        assert!(first.last().unwrap() < second.first().unwrap());
+
+        let distinct = first.iter().chain(second.iter()).collect::<HashSet<_>>();
+        assert_eq!(distinct.len(), 2 * REFERENCES_PER_SCHEDULER);
     }
 }
@@ -20,6 +20,9 @@ pub struct Tuple {
 impl Tuple {
     pub const TYPE_ID: TypeId = TypeId::of::<Tuple>();
 
+    /// The maximum number of elements in a tuple, as in ERTS
+    pub const MAX_LEN: usize = (1 << 24) - 1;
+
     /// Creates a new tuple in the given allocator, with room for `capacity` elements
     ///
     /// # Safety
@@ -196,7 +199,7 @@ impl fmt::Display for Tuple {
         f.write_str("{")?;
         for (i, element) in self.iter().enumerate() {
             if i > 0 {
-                write!(f, ", {}", element)?;
+                write!(f, ",{}", element)?;
             } else {
                 write!(f, "{}", element)?;
             }
@@ -247,10 +250,7 @@ impl PartialEq for Tuple {
 }
 impl ExactEq for Tuple {
     fn exact_eq(&self, other: &Self) -> bool {
-        if self.len() != other.len() {
-            return false;
-        }
-        self.iter().zip(other.iter()).all(|(x, y)| x.exact_eq(&y))
+        Term::Tuple(NonNull::from(self)).exact_eq(&Term::Tuple(NonNull::from(other)))
     }
 }
 
@@ -0,0 +1,203 @@
+//! Erlang monotonic and system time, as described in the ERTS
+//! [Time and Time Correction](https://www.erlang.org/doc/apps/erts/time_correction.html) guide.
+//!
+//! Monotonic time is measured from an arbitrary point fixed when the runtime starts. Erlang
+//! system time is not read from the OS clock directly, but derived from monotonic time by adding
+//! the time offset, so that `monotonic_time + time_offset == system_time` always holds.
+use core::sync::atomic::{AtomicI64, Ordering};
+
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+
+use crate::term::{atoms, Term};
+
+/// The resolution of the `native` time unit, in parts per second
+pub const NATIVE_HERTZ: u64 = 1_000_000_000;
+
+lazy_static! {
+    static ref START: Instant = Instant::now();
+}
+
+/// The difference between Erlang system time and monotonic time, in native units
+///
+/// This is atomic, so that a time warp mode can adjust it while the system is running
+static TIME_OFFSET: AtomicI64 = AtomicI64::new(0);
+
+/// Represents the time units accepted by functions such as `erlang:monotonic_time/1`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimeUnit {
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+    Native,
+    PerfCounter,
+    /// An integer unit, expressed as a number of parts per second
+    PartsPerSecond(u64),
+}
+impl TimeUnit {
+    /// Returns the number of parts per second in this unit
+    pub fn hertz(self) -> u64 {
+        match self {
+            Self::Second => 1,
+            Self::Millisecond => 1_000,
+            Self::Microsecond => 1_000_000,
+            Self::Nanosecond => 1_000_000_000,
+            Self::Native | Self::PerfCounter => NATIVE_HERTZ,
+            Self::PartsPerSecond(hertz) => hertz,
+        }
+    }
+}
+impl TryFrom<Term> for TimeUnit {
+    type Error = ();
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> {
+        match term {
+            Term::Int(hertz) if hertz > 0 => Ok(Self::PartsPerSecond(hertz as u64)),
+            Term::Atom(unit) if unit == atoms::Second => Ok(Self::Second),
+            Term::Atom(unit) if unit == atoms::Millisecond => Ok(Self::Millisecond),
+            Term::Atom(unit) if unit == atoms::Microsecond => Ok(Self::Microsecond),
+            Term::Atom(unit) if unit == atoms::Nanosecond => Ok(Self::Nanosecond),
+            Term::Atom(unit) if unit == atoms::Native => Ok(Self::Native),
+            Term::Atom(unit) if unit == atoms::PerfCounter => Ok(Self::PerfCounter),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Converts `time` from one unit to another
+///
+/// Like `erlang:convert_time_unit/3`, conversion to a coarser unit rounds towards negative infinity.
+pub fn convert(time: i64, from: TimeUnit, to: TimeUnit) -> i64 {
+    let from = from.hertz() as i128;
+    let to = to.hertz() as i128;
+    let scaled = time as i128 * to;
+    scaled.div_euclid(from) as i64
+}
+
+/// Fixes the start of monotonic time, and computes the time offset from the OS system clock
+///
+/// This should be called once when the runtime starts, before any process runs.
+pub fn init() {
+    let monotonic = monotonic_native();
+    let system = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_nanos() as i64)
+        .unwrap_or_default();
+    TIME_OFFSET.store(system - monotonic, Ordering::Relaxed);
+}
+
+#[inline]
+fn monotonic_native() -> i64 {
+    START.elapsed().as_nanos() as i64
+}
+
+/// Returns the current Erlang monotonic time in the given unit
+pub fn monotonic_time(unit: TimeUnit) -> i64 {
+    convert(monotonic_native(), TimeUnit::Native, unit)
+}
+
+/// Returns the current time offset in the given unit
+pub fn time_offset(unit: TimeUnit) -> i64 {
+    convert(TIME_OFFSET.load(Ordering::Relaxed), TimeUnit::Native, unit)
+}
+
+/// Returns the current Erlang system time, i.e. time since the Unix epoch, in the given unit
+pub fn system_time(unit: TimeUnit) -> i64 {
+    let system = monotonic_native() + TIME_OFFSET.load(Ordering::Relaxed);
+    convert(system, TimeUnit::Native, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::term::Atom;
+
+    #[test]
+    fn unit_from_term() {
+        assert_eq!(
+            TimeUnit::try_from(Term::Atom(atoms::Second)),
+            Ok(TimeUnit::Second)
+        );
+        assert_eq!(
+            TimeUnit::try_from(Term::Atom(atoms::PerfCounter)),
+            Ok(TimeUnit::PerfCounter)
+        );
+        assert_eq!(
+            TimeUnit::try_from(Term::Int(10)),
+            Ok(TimeUnit::PartsPerSecond(10))
+        );
+        assert_eq!(TimeUnit::try_from(Term::Int(0)), Err(()));
+        let invalid = Atom::try_from("fortnight").unwrap();
+        assert_eq!(TimeUnit::try_from(Term::Atom(invalid)), Err(()));
+    }
+
+    #[test]
+    fn convert_to_finer_unit_multiplies() {
+        assert_eq!(
+            convert(3, TimeUnit::Second, TimeUnit::Microsecond),
+            3_000_000
+        );
+        assert_eq!(
+            convert(5, TimeUnit::Second, TimeUnit::PartsPerSecond(10)),
+            50
+        );
+        assert_eq!(
+            convert(1, TimeUnit::Millisecond, TimeUnit::Native),
+            1_000_000
+        );
+    }
+
+    #[test]
+    fn convert_to_coarser_unit_rounds_towards_negative_infinity() {
+        assert_eq!(convert(1_999, TimeUnit::Millisecond, TimeUnit::Second), 1);
+        assert_eq!(convert(-1, TimeUnit::Millisecond, TimeUnit::Second), -1);
+        assert_eq!(convert(-1_001, TimeUnit::Millisecond, TimeUnit::Second), -2);
+        assert_eq!(
+            convert(7, TimeUnit::PartsPerSecond(3), TimeUnit::Second),
+            2
+        );
+    }
+
+    #[test]
+    fn system_time_is_monotonic_time_plus_offset() {
+        init();
+
+        let before = system_time(TimeUnit::Native);
+        let monotonic = monotonic_time(TimeUnit::Native);
+        let offset = time_offset(TimeUnit::Native);
+        let after = system_time(TimeUnit::Native);
+
+        assert!(before <= monotonic + offset);
+        assert!(monotonic + offset <= after);
+
+        // In coarser units, each of the three values is rounded separately, so the sum may be
+        // one unit behind, but never ahead
+        for unit in [
+            TimeUnit::Second,
+            TimeUnit::Millisecond,
+            TimeUnit::Microsecond,
+        ] {
+            let before = system_time(unit);
+            let sum = monotonic_time(unit) + time_offset(unit);
+            let after = system_time(unit);
+            assert!(before - 1 <= sum, "{:?}", unit);
+            assert!(sum <= after, "{:?}", unit);
+        }
+    }
+
+    #[test]
+    fn system_time_is_near_os_clock() {
+        init();
+
+        let os = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let system = system_time(TimeUnit::Second);
+
+        assert!((system - os).abs() <= 1);
+    }
+}
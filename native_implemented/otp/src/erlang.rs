@@ -53,6 +53,7 @@ pub mod erase_1;
 pub mod error_1;
 pub mod error_2;
 pub mod exit_1;
+pub mod exit_2;
 pub mod float_1;
 pub mod float_to_binary_1;
 pub mod float_to_binary_2;
@@ -61,6 +62,8 @@ pub mod float_to_list_2;
 mod float_to_string;
 pub mod floor_1;
 pub mod function_exported_3;
+pub mod garbage_collect_0;
+pub mod garbage_collect_1;
 pub mod get_0;
 pub mod get_1;
 pub mod get_keys_0;
@@ -111,7 +114,7 @@ pub mod list_to_float_1;
 pub mod list_to_integer_1;
 pub mod list_to_integer_2;
 pub mod list_to_pid_1;
-mod list_to_string;
+pub(crate) mod list_to_string;
 pub mod list_to_tuple_1;
 pub mod load_nif_2;
 pub mod localtime_0;
@@ -130,6 +133,7 @@ pub mod multiply_2;
 pub mod negate_1;
 pub mod nif_error_1;
 pub mod node_0;
+pub mod nodes_1;
 pub mod not_1;
 pub mod now_0;
 pub mod number_or_badarith_1;
@@ -137,7 +141,10 @@ mod number_to_integer;
 pub mod or_2;
 pub mod orelse_2;
 pub mod process_flag_2;
+mod process_info;
+pub mod process_info_1;
 pub mod process_info_2;
+pub mod processes_0;
 pub mod put_2;
 pub mod raise_3;
 pub mod read_timer_1;
@@ -145,6 +152,7 @@ pub mod read_timer_2;
 pub mod register_2;
 pub mod registered_0;
 pub mod rem_2;
+pub mod resume_process_1;
 pub mod round_1;
 pub mod self_0;
 pub mod send_2;
@@ -171,10 +179,12 @@ pub mod spawn_opt_4;
 pub mod split_binary_2;
 pub mod start_timer_3;
 pub mod start_timer_4;
+pub mod statistics_1;
 mod string_to_float;
 mod string_to_integer;
 pub mod subtract_2;
 pub mod subtract_list_2;
+pub mod suspend_process_1;
 pub mod system_flag_2;
 pub mod system_info_1;
 pub mod system_time_0;
@@ -219,7 +229,7 @@ use crate::runtime::scheduler::SchedulerDependentAlloc;
 use crate::runtime::timer::{Destination, Format, SourceEvent};
 use crate::timer;
 use crate::timer::start::ReferenceFrame;
-use lumen_rt_core::context::term_is_not_non_negative_integer;
+use firefly_rt_core::context::term_is_not_non_negative_integer;
 
 pub const MAX_SHIFT: usize = std::mem::size_of::<isize>() * 8 - 1;
 
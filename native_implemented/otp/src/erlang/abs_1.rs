@@ -1,5 +1,3 @@
-use std::cmp::Ordering;
-
 use anyhow::*;
 use num_bigint::BigInt;
 use num_traits::Zero;
@@ -14,6 +12,7 @@ fn result(process: &Process, number: Term) -> exception::Result<Term> {
         TypedTerm::SmallInteger(small_integer) => {
             let i: isize = small_integer.into();
 
+            // The negation of the smallest small integer is a big integer
             let abs_number = if i < 0 {
                 let positive = -i;
                 process.integer(positive)
@@ -40,13 +39,11 @@ fn result(process: &Process, number: Term) -> exception::Result<Term> {
         TypedTerm::Float(float) => {
             let f: f64 = float.into();
 
-            let abs_number = match f.partial_cmp(&0.0).unwrap() {
-                Ordering::Less => {
-                    let positive_f = f.abs();
-
-                    process.float(positive_f)
-                }
-                _ => number,
+            // `-0.0` compares equal to `0.0`, but must still become positive
+            let abs_number = if f.is_sign_negative() {
+                process.float(f.abs())
+            } else {
+                number
             };
 
             Ok(abs_number)
@@ -0,0 +1,77 @@
+use liblumen_alloc::atom;
+
+use crate::erlang::convert_time_unit_3::result;
+use crate::test::with_process;
+
+#[test]
+fn with_invalid_from_unit_errors_badarg() {
+    with_process(|process| {
+        let time = process.integer(1);
+
+        assert_badarg!(
+            result(process, time, atom!("invalid"), atom!("second")),
+            "from_unit"
+        );
+    });
+}
+
+#[test]
+fn with_invalid_to_unit_errors_badarg() {
+    with_process(|process| {
+        let time = process.integer(1);
+
+        assert_badarg!(
+            result(process, time, atom!("second"), atom!("invalid")),
+            "to_unit"
+        );
+    });
+}
+
+#[test]
+fn with_coarser_unit_rounds_towards_negative_infinity() {
+    with_process(|process| {
+        assert_eq!(
+            result(
+                process,
+                process.integer(1_999),
+                atom!("millisecond"),
+                atom!("second")
+            ),
+            Ok(process.integer(1))
+        );
+        assert_eq!(
+            result(
+                process,
+                process.integer(-1_001),
+                atom!("millisecond"),
+                atom!("second")
+            ),
+            Ok(process.integer(-2))
+        );
+    });
+}
+
+#[test]
+fn with_finer_unit_multiplies() {
+    with_process(|process| {
+        assert_eq!(
+            result(process, process.integer(3), atom!("second"), atom!("microsecond")),
+            Ok(process.integer(3_000_000))
+        );
+    });
+}
+
+#[test]
+fn with_parts_per_second_converts() {
+    with_process(|process| {
+        assert_eq!(
+            result(
+                process,
+                process.integer(5),
+                atom!("second"),
+                process.integer(10)
+            ),
+            Ok(process.integer(50))
+        );
+    });
+}
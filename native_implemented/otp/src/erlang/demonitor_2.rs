@@ -1,3 +1,6 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
 mod options;
 
 use std::convert::TryInto;
@@ -26,35 +29,29 @@ pub(in crate::erlang) fn demonitor(
     reference: &Reference,
     Options { flush, info }: Options,
 ) -> exception::Result<Term> {
-    match monitoring_process.demonitor(reference) {
+    let removed = match monitoring_process.demonitor(reference) {
         Some(monitored_pid) => {
-            match pid_to_process(&monitored_pid) {
-                Some(monitored_arc_proces) => match monitored_arc_proces.demonitored(reference) {
-                    Some(monitoring_pid) => assert_eq!(monitoring_process.pid(), monitoring_pid),
-                    None => (),
-                },
-                None => (),
-            }
-
-            if flush {
-                let flushed = self::flush(monitoring_process, reference);
-
-                if info && flushed {
-                    Ok(false.into())
-                } else {
-                    Ok(true.into())
+            // If the monitored process is exiting, this waits until its `DOWN` message for this
+            // monitor, if any, has been sent, so that it can't arrive after we return
+            if let Some(monitored_arc_process) = pid_to_process(&monitored_pid) {
+                if let Some(monitoring_pid) = monitored_arc_process.demonitored(reference) {
+                    assert_eq!(monitoring_process.pid(), monitoring_pid);
                 }
-            } else {
-                Ok(true.into())
-            }
-        }
-        None => {
-            if info {
-                Ok(false.into())
-            } else {
-                Ok(true.into())
             }
+
+            true
         }
+        // The monitor already fired, or never existed
+        None => false,
+    };
+
+    // A `DOWN` message may have been delivered whether or not the monitor was still found
+    let flushed = flush && self::flush(monitoring_process, reference);
+
+    if info {
+        Ok((removed && !flushed).into())
+    } else {
+        Ok(true.into())
     }
 }
 
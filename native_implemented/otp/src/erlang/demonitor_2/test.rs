@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::runtime::process::monitor::propagate_exit;
+use crate::runtime::scheduler;
+
+use crate::erlang::demonitor_2::result;
+use crate::erlang::monitor_2;
+use crate::test::{self, *};
+
+#[test]
+fn with_monitor_returns_true_and_removes_monitor() {
+    with_process_arc(|monitoring_arc_process| {
+        let monitored_arc_process = test::process::child(&monitoring_arc_process);
+        let monitor_reference = monitor(&monitoring_arc_process, &monitored_arc_process);
+
+        assert_eq!(
+            result(
+                &monitoring_arc_process,
+                monitor_reference,
+                options(&monitoring_arc_process, &["info"])
+            ),
+            Ok(true.into())
+        );
+        assert_eq!(monitored_count(&monitoring_arc_process), 0);
+        assert_eq!(monitor_count(&monitored_arc_process), 0);
+    });
+}
+
+#[test]
+fn with_flush_after_monitored_process_exits_removes_down_message() {
+    with_process_arc(|monitoring_arc_process| {
+        let monitored_arc_process = test::process::child(&monitoring_arc_process);
+        let monitor_reference = monitor(&monitoring_arc_process, &monitored_arc_process);
+        let down = exit(
+            &monitoring_arc_process,
+            &monitored_arc_process,
+            monitor_reference,
+        );
+
+        assert_has_message!(&monitoring_arc_process, down);
+
+        assert_eq!(
+            result(
+                &monitoring_arc_process,
+                monitor_reference,
+                options(&monitoring_arc_process, &["flush"])
+            ),
+            Ok(true.into())
+        );
+
+        assert!(!has_message(&monitoring_arc_process, down));
+    });
+}
+
+#[test]
+fn with_info_after_monitored_process_exits_returns_false() {
+    with_process_arc(|monitoring_arc_process| {
+        let monitored_arc_process = test::process::child(&monitoring_arc_process);
+        let monitor_reference = monitor(&monitoring_arc_process, &monitored_arc_process);
+        let down = exit(
+            &monitoring_arc_process,
+            &monitored_arc_process,
+            monitor_reference,
+        );
+
+        assert_eq!(
+            result(
+                &monitoring_arc_process,
+                monitor_reference,
+                options(&monitoring_arc_process, &["info"])
+            ),
+            Ok(false.into())
+        );
+
+        // Without `flush`, the `DOWN` message is left for the caller to receive
+        assert_has_message!(&monitoring_arc_process, down);
+    });
+}
+
+#[test]
+fn with_flush_and_info_after_monitored_process_exits_returns_false_and_removes_down_message() {
+    with_process_arc(|monitoring_arc_process| {
+        let monitored_arc_process = test::process::child(&monitoring_arc_process);
+        let monitor_reference = monitor(&monitoring_arc_process, &monitored_arc_process);
+        let down = exit(
+            &monitoring_arc_process,
+            &monitored_arc_process,
+            monitor_reference,
+        );
+
+        assert_eq!(
+            result(
+                &monitoring_arc_process,
+                monitor_reference,
+                options(&monitoring_arc_process, &["flush", "info"])
+            ),
+            Ok(false.into())
+        );
+
+        assert!(!has_message(&monitoring_arc_process, down));
+    });
+}
+
+#[test]
+fn with_flush_racing_monitored_process_exit_never_leaves_down_message() {
+    for _ in 0..100 {
+        with_process_arc(|monitoring_arc_process| {
+            let monitored_arc_process = test::process::child(&monitoring_arc_process);
+            let monitor_reference = monitor(&monitoring_arc_process, &monitored_arc_process);
+            let reason = Atom::str_to_term("normal");
+
+            let exiting_arc_process = monitored_arc_process.clone();
+            let exiting = std::thread::spawn(move || propagate_exit(&exiting_arc_process, None));
+
+            assert_eq!(
+                result(
+                    &monitoring_arc_process,
+                    monitor_reference,
+                    options(&monitoring_arc_process, &["flush"])
+                ),
+                Ok(true.into())
+            );
+
+            exiting.join().unwrap();
+
+            let down = down(
+                &monitoring_arc_process,
+                &monitored_arc_process,
+                monitor_reference,
+                reason,
+            );
+
+            assert!(!has_message(&monitoring_arc_process, down));
+            assert_eq!(monitored_count(&monitoring_arc_process), 0);
+        });
+    }
+}
+
+fn down(
+    monitoring_process: &Process,
+    monitored_process: &Process,
+    monitor_reference: Term,
+    reason: Term,
+) -> Term {
+    monitoring_process.tuple_from_slice(&[
+        Atom::str_to_term("DOWN"),
+        monitor_reference,
+        Atom::str_to_term("process"),
+        monitored_process.pid_term(),
+        reason,
+    ])
+}
+
+fn exit(
+    monitoring_arc_process: &Arc<Process>,
+    monitored_arc_process: &Arc<Process>,
+    monitor_reference: Term,
+) -> Term {
+    let reason = Atom::str_to_term("normal");
+    exit_when_run(monitored_arc_process, reason);
+
+    assert!(scheduler::run_through(monitored_arc_process));
+    assert!(monitored_arc_process.is_exiting());
+
+    down(
+        monitoring_arc_process,
+        monitored_arc_process,
+        monitor_reference,
+        reason,
+    )
+}
+
+fn monitor(monitoring_arc_process: &Arc<Process>, monitored_arc_process: &Arc<Process>) -> Term {
+    monitor_2::result(
+        monitoring_arc_process,
+        Atom::str_to_term("process"),
+        monitored_arc_process.pid_term(),
+    )
+    .unwrap()
+}
+
+fn options(process: &Process, names: &[&str]) -> Term {
+    let vec: Vec<Term> = names.iter().map(|name| Atom::str_to_term(name)).collect();
+
+    process.list_from_slice(&vec)
+}
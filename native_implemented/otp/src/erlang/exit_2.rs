@@ -0,0 +1,66 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::trace::Trace;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, exit};
+
+use crate::runtime::process::exit_signal;
+use crate::runtime::registry::pid_to_process;
+
+#[native_implemented::function(erlang:exit/2)]
+pub fn result(process: &Process, pid: Term, reason: Term) -> exception::Result<Term> {
+    match pid.decode()? {
+        TypedTerm::Pid(pid) => {
+            if pid == process.pid() {
+                exit_self(process, reason)
+            } else {
+                // Exit signals to processes that are no longer alive are dropped
+                if let Some(pid_arc_process) = pid_to_process(&pid) {
+                    exit_signal(process, &pid_arc_process, reason);
+                }
+
+                Ok(true.into())
+            }
+        }
+        TypedTerm::Port(_) => unimplemented!(),
+        TypedTerm::ExternalPid(_) => unimplemented!(),
+        TypedTerm::ExternalPort(_) => unimplemented!(),
+        _ => Err(TypeError)
+            .context(format!("pid ({}) is neither a pid nor a port", pid))
+            .map_err(From::from),
+    }
+}
+
+// Private
+
+/// Unlike other processes, the calling process exits with `normal` too, like `exit/1`
+fn exit_self(process: &Process, reason: Term) -> exception::Result<Term> {
+    let reason_atom: Option<Atom> = reason.try_into().ok();
+
+    match reason_atom {
+        Some(atom) if atom == "kill" => Err(exit!(
+            atom!("killed"),
+            Trace::capture(),
+            anyhow!("kill exit signal to self").into()
+        )
+        .into()),
+        _ if process.traps_exit() => {
+            let exit_message =
+                process.tuple_from_slice(&[atom!("EXIT"), process.pid_term(), reason]);
+            process.send_from_self(exit_message);
+
+            Ok(true.into())
+        }
+        _ => Err(exit!(
+            reason,
+            Trace::capture(),
+            anyhow!("exit signal to self").into()
+        )
+        .into()),
+    }
+}
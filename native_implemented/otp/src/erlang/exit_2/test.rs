@@ -0,0 +1,47 @@
+mod with_local_pid;
+
+use proptest::strategy::{Just, Strategy};
+
+use liblumen_alloc::erts::process::trace::Trace;
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{atom, exit};
+
+use crate::erlang::exit_2::result;
+use crate::runtime::process::handle_signals;
+use crate::runtime::scheduler;
+use crate::test::{self, has_message, strategy, with_process, with_process_arc};
+
+#[test]
+fn without_pid_or_port_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term(arc_process.clone()).prop_filter("Cannot be pid or port", |pid| {
+                    !(pid.is_pid() || pid.is_port())
+                }),
+                strategy::term(arc_process.clone()),
+            )
+        },
+        |(arc_process, pid, reason)| {
+            prop_assert_badarg!(
+                result(&arc_process, pid, reason),
+                format!("pid ({}) is neither a pid nor a port", pid)
+            );
+
+            Ok(())
+        },
+    );
+}
+
+/// The exit signal takes effect when `process` handles it, which its scheduler would do before
+/// running it
+fn exit_reason(process: &Process) -> Option<Term> {
+    handle_signals(process);
+
+    match *process.status.read() {
+        Status::RuntimeException(ref exception) => Some(exception.reason()),
+        _ => None,
+    }
+}
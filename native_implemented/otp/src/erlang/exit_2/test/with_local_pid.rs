@@ -0,0 +1,134 @@
+use super::*;
+
+#[test]
+fn with_self_exits_with_reason() {
+    with_process(|process| {
+        for reason in &["normal", "shutdown"] {
+            let reason = Atom::str_to_term(reason);
+
+            assert_eq!(
+                result(process, process.pid_term(), reason),
+                Err(exit!(reason, Trace::capture()).into())
+            );
+        }
+    });
+}
+
+#[test]
+fn with_self_when_trapping_exits_sends_exit_message() {
+    with_process(|process| {
+        process.trap_exit(true);
+
+        let reason = Atom::str_to_term("normal");
+
+        assert_eq!(result(process, process.pid_term(), reason), Ok(true.into()));
+        assert!(has_message(
+            process,
+            process.tuple_from_slice(&[atom!("EXIT"), process.pid_term(), reason])
+        ));
+    });
+}
+
+#[test]
+fn with_self_and_kill_when_trapping_exits_exits_with_killed() {
+    with_process(|process| {
+        process.trap_exit(true);
+
+        assert_eq!(
+            result(process, process.pid_term(), atom!("kill")),
+            Err(exit!(atom!("killed"), Trace::capture()).into())
+        );
+        assert_eq!(process.message_queue_len(), 0);
+    });
+}
+
+#[test]
+fn with_non_existent_pid_returns_true() {
+    with_process(|process| {
+        assert_eq!(
+            result(process, Pid::next_term(), Atom::str_to_term("shutdown")),
+            Ok(true.into())
+        );
+        assert!(!process.is_exiting());
+    });
+}
+
+#[test]
+fn with_exited_pid_returns_true() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = test::process::child(&arc_process);
+        let pid = other_arc_process.pid_term();
+        other_arc_process.exit_normal();
+
+        assert_eq!(
+            result(&arc_process, pid, Atom::str_to_term("shutdown")),
+            Ok(true.into())
+        );
+        assert!(!arc_process.is_exiting());
+        assert_eq!(*other_arc_process.status.read(), Status::Exited);
+    });
+}
+
+#[test]
+fn with_waiting_pid_exits_it() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = test::process::child(&arc_process);
+        assert!(scheduler::run_through(&other_arc_process));
+        other_arc_process.wait();
+
+        let reason = Atom::str_to_term("shutdown");
+
+        assert_eq!(
+            result(&arc_process, other_arc_process.pid_term(), reason),
+            Ok(true.into())
+        );
+        assert_eq!(exit_reason(&other_arc_process), Some(reason));
+        assert!(!arc_process.is_exiting());
+    });
+}
+
+#[test]
+fn with_waiting_pid_and_normal_does_not_exit_it() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = test::process::child(&arc_process);
+        other_arc_process.wait();
+
+        assert_eq!(
+            result(
+                &arc_process,
+                other_arc_process.pid_term(),
+                Atom::str_to_term("normal")
+            ),
+            Ok(true.into())
+        );
+        handle_signals(&other_arc_process);
+        assert!(!other_arc_process.is_exiting());
+    });
+}
+
+#[test]
+fn with_waiting_pid_trapping_exits_sends_exit_message() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = test::process::child(&arc_process);
+        other_arc_process.trap_exit(true);
+        other_arc_process.wait();
+
+        let reason = Atom::str_to_term("shutdown");
+
+        assert_eq!(
+            result(&arc_process, other_arc_process.pid_term(), reason),
+            Ok(true.into())
+        );
+        assert!(!other_arc_process.is_exiting());
+        assert!(has_message(
+            &other_arc_process,
+            other_arc_process.tuple_from_slice(&[atom!("EXIT"), arc_process.pid_term(), reason])
+        ));
+
+        assert_eq!(
+            result(&arc_process, other_arc_process.pid_term(), atom!("kill")),
+            Ok(true.into())
+        );
+        assert_eq!(exit_reason(&other_arc_process), Some(atom!("killed")));
+    });
+}
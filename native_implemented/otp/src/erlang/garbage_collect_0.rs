@@ -0,0 +1,15 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+/// Forces an immediate full sweep of the calling process, shrinking its heap to fit the live data
+#[native_implemented::function(erlang:garbage_collect/0)]
+pub fn result(process: &Process) -> Term {
+    if let Err(gc_err) = process.full_sweep() {
+        panic!("garbage collection of {} failed: {}", process, gc_err);
+    }
+
+    true.into()
+}
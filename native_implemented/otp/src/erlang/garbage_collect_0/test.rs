@@ -0,0 +1,65 @@
+use liblumen_alloc::erts::process::gc::RootSet;
+use liblumen_alloc::erts::process::{Process, ProcessFlags};
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::garbage_collect_0::result;
+use crate::erlang::process_info_2;
+use crate::test::with_process;
+
+const TRANSIENT_LIST_LENGTH: usize = 100_000;
+
+#[test]
+fn with_transient_list_dropped_shrinks_heap() {
+    with_process(|process| {
+        let heap_size_with_list = allocate_transient_list(process);
+
+        assert_eq!(result(process), true.into());
+
+        let heap_size = process.heap_size();
+
+        assert!(
+            heap_size < heap_size_with_list,
+            "heap_size ({}) did not shrink from {} after the list was dropped",
+            heap_size,
+            heap_size_with_list
+        );
+        assert_eq!(
+            process_info_2::result(process, process.pid_term(), Atom::str_to_term("heap_size")),
+            Ok(process
+                .tuple_from_slice(&[Atom::str_to_term("heap_size"), process.integer(heap_size)]))
+        );
+    });
+}
+
+#[test]
+fn with_transient_list_dropped_sweeps_heap_fragments() {
+    with_process(|process| {
+        // Larger than the heap, so it is allocated in a heap fragment
+        let elements = vec![Term::NIL; TRANSIENT_LIST_LENGTH];
+        process.list_from_slice(&elements);
+        let total_heap_size_with_list = process.total_heap_size();
+
+        assert!(process.heap_size() < total_heap_size_with_list);
+
+        assert_eq!(result(process), true.into());
+
+        assert!(process.total_heap_size() < total_heap_size_with_list);
+    });
+}
+
+/// Allocates a list on `process`'s heap, growing the heap to fit it as the collection triggered by
+/// the allocation would, and returns the heap size with the list on it.  The list is not rooted,
+/// so it is garbage as soon as this returns.
+fn allocate_transient_list(process: &Process) -> usize {
+    process.set_flags(ProcessFlags::NeedFullSweep);
+    process
+        .garbage_collect(2 * TRANSIENT_LIST_LENGTH, RootSet::empty())
+        .unwrap();
+
+    let elements = vec![Term::NIL; TRANSIENT_LIST_LENGTH];
+    let list = process.list_from_slice(&elements);
+
+    assert!(list.is_non_empty_list());
+
+    process.heap_size()
+}
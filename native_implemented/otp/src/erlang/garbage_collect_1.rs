@@ -0,0 +1,31 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::garbage_collect_0;
+use crate::runtime::registry::pid_to_process;
+
+/// Collects the calling process immediately, like `garbage_collect/0`.  Any other process is only
+/// marked for a full sweep, which its scheduler does before running it again.
+///
+/// Returns `false` if `pid` is not alive.
+#[native_implemented::function(erlang:garbage_collect/1)]
+pub fn result(process: &Process, pid: Term) -> exception::Result<Term> {
+    if pid == process.pid_term() {
+        Ok(garbage_collect_0::result(process))
+    } else {
+        let pid_pid = term_try_into_local_pid!(pid)?;
+
+        match pid_to_process(&pid_pid) {
+            Some(arc_process) if !arc_process.is_exiting() => {
+                arc_process.request_full_sweep();
+
+                Ok(true.into())
+            }
+            _ => Ok(false.into()),
+        }
+    }
+}
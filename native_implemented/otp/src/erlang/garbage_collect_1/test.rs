@@ -0,0 +1,80 @@
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::garbage_collect_1::result;
+use crate::runtime::scheduler;
+use crate::test;
+use crate::test::with_process_arc;
+
+#[test]
+fn without_pid_errors_badarg() {
+    with_process_arc(|arc_process| {
+        let pid = Atom::str_to_term("not_a_pid");
+
+        assert_badarg!(result(&arc_process, pid), "pid");
+    });
+}
+
+#[test]
+fn with_self_collects_immediately() {
+    with_process_arc(|arc_process| {
+        let elements = vec![Term::NIL; 100_000];
+        arc_process.list_from_slice(&elements);
+        let total_heap_size_with_list = arc_process.total_heap_size();
+
+        assert_eq!(
+            result(&arc_process, arc_process.pid_term()),
+            Ok(true.into())
+        );
+
+        assert!(!arc_process.is_full_sweep_requested());
+        assert!(arc_process.total_heap_size() < total_heap_size_with_list);
+    });
+}
+
+#[test]
+fn with_other_process_requests_collection_before_it_next_runs() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = test::process::child(&arc_process);
+        let elements = vec![Term::NIL; 100_000];
+        other_arc_process.list_from_slice(&elements);
+        let total_heap_size_with_list = other_arc_process.total_heap_size();
+
+        assert_eq!(
+            result(&arc_process, other_arc_process.pid_term()),
+            Ok(true.into())
+        );
+
+        // Only marked, as it is collected by its scheduler
+        assert!(other_arc_process.is_full_sweep_requested());
+        assert_eq!(
+            other_arc_process.total_heap_size(),
+            total_heap_size_with_list
+        );
+
+        assert!(scheduler::run_through(&other_arc_process));
+
+        assert!(!other_arc_process.is_full_sweep_requested());
+        assert!(other_arc_process.total_heap_size() < total_heap_size_with_list);
+    });
+}
+
+#[test]
+fn with_exiting_process_returns_false() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = test::process::child(&arc_process);
+        other_arc_process.exit_normal();
+
+        assert_eq!(
+            result(&arc_process, other_arc_process.pid_term()),
+            Ok(false.into())
+        );
+        assert!(!other_arc_process.is_full_sweep_requested());
+    });
+}
+
+#[test]
+fn without_process_returns_false() {
+    with_process_arc(|arc_process| {
+        assert_eq!(result(&arc_process, Pid::next_term()), Ok(false.into()));
+    });
+}
@@ -7,6 +7,7 @@ use liblumen_alloc::erts::exception;
 use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 
+use crate::runtime::process::group_leader_signal;
 use crate::runtime::registry::pid_to_process;
 
 macro_rules! is_not_alive {
@@ -29,7 +30,7 @@ pub fn result(process: &Process, group_leader: Term, pid: Term) -> exception::Re
         } else {
             match pid_to_process(&pid_pid) {
                 Some(pid_arc_process) => {
-                    pid_arc_process.set_group_leader_pid(group_leader_pid);
+                    group_leader_signal(&pid_arc_process, group_leader_pid);
 
                     Ok(true.into())
                 }
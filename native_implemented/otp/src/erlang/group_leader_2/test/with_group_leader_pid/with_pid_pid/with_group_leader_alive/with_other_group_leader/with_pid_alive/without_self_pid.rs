@@ -1,5 +1,7 @@
 use super::*;
 
+use crate::runtime::process::handle_signals;
+
 #[test]
 fn with_different_group_leader_and_pid_sets_group_leader() {
     run!(
@@ -15,6 +17,7 @@ fn with_different_group_leader_and_pid_sets_group_leader() {
             let pid = pid_arc_process.pid_term();
 
             prop_assert_eq!(result(&arc_process, group_leader, pid), Ok(true.into()));
+            handle_signals(&pid_arc_process);
             prop_assert_eq!(group_leader_0::result(&pid_arc_process), group_leader);
 
             Ok(())
@@ -31,6 +34,7 @@ fn with_same_group_leader_and_pid_sets_group_leader() {
             let pid = group_leader;
 
             prop_assert_eq!(result(&arc_process, group_leader, pid), Ok(true.into()));
+            handle_signals(&group_leader_and_pid_arc_process);
             prop_assert_eq!(
                 group_leader_0::result(&group_leader_and_pid_arc_process),
                 group_leader
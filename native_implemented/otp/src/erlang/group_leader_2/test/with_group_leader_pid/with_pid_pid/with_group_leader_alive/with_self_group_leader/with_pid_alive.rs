@@ -1,5 +1,7 @@
 use super::*;
 
+use crate::runtime::process::handle_signals;
+
 #[test]
 fn with_self_pid_sets_group_leader() {
     with_process(|process| {
@@ -25,6 +27,7 @@ fn without_self_pid_sets_group_leader() {
             let pid = pid_arc_process.pid_term();
 
             prop_assert_eq!(result(&arc_process, group_leader, pid), Ok(true.into()));
+            handle_signals(&pid_arc_process);
             prop_assert_eq!(group_leader_0::result(&pid_arc_process), group_leader);
 
             Ok(())
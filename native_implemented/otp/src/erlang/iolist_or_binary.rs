@@ -7,6 +7,9 @@ use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 
 use crate::runtime::context::{r#type, term_is_not_type};
+use crate::runtime::process::{
+    consume_reductions, BYTES_PER_CONSUME, CELLS_PER_CONSUME, REDUCTIONS_PER_CONSUME,
+};
 
 pub fn element_not_a_binary_context(iolist_or_binary: Term, element: Term) -> String {
     format!(
@@ -50,6 +53,8 @@ pub fn result(
 pub fn to_binary(process: &Process, name: &'static str, value: Term) -> exception::Result<Term> {
     let mut byte_vec: Vec<u8> = Vec::new();
     let mut stack: Vec<Term> = vec![value];
+    let mut cells = 0;
+    let mut consumed_len = 0;
 
     while let Some(top) = stack.pop() {
         match top.decode()? {
@@ -82,6 +87,13 @@ pub fn to_binary(process: &Process, name: &'static str, value: Term) -> exceptio
                 };
 
                 stack.push(boxed_cons.head);
+
+                cells += 1;
+
+                if cells == CELLS_PER_CONSUME {
+                    cells = 0;
+                    consume_reductions(REDUCTIONS_PER_CONSUME);
+                }
             }
             TypedTerm::HeapBinary(heap_binary) => {
                 byte_vec.extend_from_slice(heap_binary.as_bytes());
@@ -108,6 +120,13 @@ pub fn to_binary(process: &Process, name: &'static str, value: Term) -> exceptio
                     .map_err(From::from)
             }
         }
+
+        // The partial binary can't be kept across a yield, so a long conversion overruns the
+        // budget of the process instead
+        while consumed_len + BYTES_PER_CONSUME <= byte_vec.len() {
+            consumed_len += BYTES_PER_CONSUME;
+            consume_reductions(REDUCTIONS_PER_CONSUME);
+        }
     }
 
     Ok(process.binary_from_bytes(byte_vec.as_slice()))
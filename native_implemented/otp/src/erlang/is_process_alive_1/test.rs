@@ -5,6 +5,7 @@ use proptest::{prop_assert, prop_assert_eq};
 
 use crate::erlang::is_process_alive_1::result;
 use crate::test::strategy;
-use crate::test::with_process_arc;
+use crate::runtime::registry;
+use crate::test::{self, with_process_arc};
 
 // `without_pid_errors_badarg` in integration tests
@@ -20,3 +20,15 @@ fn with_exiting_returns_false() {
             .unwrap();
     });
 }
+
+#[test]
+fn with_exited_process_returns_false() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = test::process::child(&arc_process);
+        let pid = other_arc_process.pid_term();
+        other_arc_process.exit_normal();
+        registry::remove_pid_to_process(&other_arc_process);
+
+        assert_eq!(result(&arc_process, pid), Ok(false.into()));
+    });
+}
@@ -17,21 +17,15 @@ fn result(process: &Process, pid_or_port: Term) -> exception::Result<Term> {
             if pid == process.pid() {
                 Ok(true.into())
             } else {
-                match pid_to_process(&pid) {
-                    Some(pid_arc_process) => {
-                        process.link(&pid_arc_process);
-
-                        Ok(true.into())
-                    }
-                    None => Err(error(
-                        Atom::str_to_term("noproc"),
-                        None,
-                        Trace::capture(),
-                        Some(
-                            anyhow!("pid ({}) doesn't refer to an alive local process", pid).into(),
-                        ),
-                    )
-                    .into()),
+                let linked = match pid_to_process(&pid) {
+                    Some(pid_arc_process) => process.link_unless_exiting(&pid_arc_process),
+                    None => false,
+                };
+
+                if linked {
+                    Ok(true.into())
+                } else {
+                    noproc(process, pid_or_port)
                 }
             }
         }
@@ -46,3 +40,25 @@ fn result(process: &Process, pid_or_port: Term) -> exception::Result<Term> {
             .map_err(From::from),
     }
 }
+
+// Private
+
+fn noproc(process: &Process, pid: Term) -> exception::Result<Term> {
+    let noproc = Atom::str_to_term("noproc");
+
+    // A process trapping exits receives the `noproc` as an exit signal from `pid` instead
+    if process.traps_exit() {
+        let exit_message = process.tuple_from_slice(&[Atom::str_to_term("EXIT"), pid, noproc]);
+        process.send_from_self(exit_message);
+
+        Ok(true.into())
+    } else {
+        Err(error(
+            noproc,
+            None,
+            Trace::capture(),
+            Some(anyhow!("pid ({}) doesn't refer to an alive local process", pid).into()),
+        )
+        .into())
+    }
+}
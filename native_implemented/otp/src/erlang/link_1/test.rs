@@ -6,11 +6,12 @@ use proptest::strategy::{Just, Strategy};
 
 use liblumen_alloc::erts::exception::error;
 use liblumen_alloc::erts::process::trace::Trace;
-use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::process::{Process, Status};
 use liblumen_alloc::erts::term::prelude::*;
 
 use crate::erlang::link_1::result;
-use crate::test::{strategy, with_process};
+use crate::runtime::process::propagate_exit;
+use crate::test::{self, has_message, strategy, with_process, with_process_arc};
 
 #[test]
 fn without_pid_or_port_errors_badarg() {
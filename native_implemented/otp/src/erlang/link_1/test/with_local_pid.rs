@@ -25,3 +25,88 @@ fn with_non_existent_pid_errors_noproc() {
 // `when_a_linked_process_exits_normal_the_process_does_not_exit` in integration tests
 // `when_a_linked_process_exits_unexpected_the_process_does_not_exit` in integration tests
 // `when_the_process_exits_unexpected_linked_processes_exit_too` in integration tests
+
+#[test]
+fn with_non_existent_pid_when_trapping_exits_sends_noproc_exit_message() {
+    with_process(|process| {
+        process.trap_exit(true);
+
+        let pid = Pid::next_term();
+
+        assert_eq!(result(process, pid), Ok(true.into()));
+        assert!(has_message(
+            process,
+            process.tuple_from_slice(&[
+                Atom::str_to_term("EXIT"),
+                pid,
+                Atom::str_to_term("noproc")
+            ])
+        ));
+    });
+}
+
+#[test]
+fn with_exiting_pid_errors_noproc_and_does_not_link() {
+    with_process_arc(|arc_process| {
+        let exiting_arc_process = test::process::child(&arc_process);
+        exiting_arc_process.exit(Atom::str_to_term("abnormal"), Trace::capture(), None);
+
+        let link_count_before = link_count(&arc_process);
+
+        assert_eq!(
+            result(&arc_process, exiting_arc_process.pid_term()),
+            Err(error(
+                Atom::str_to_term("noproc"),
+                None,
+                Trace::capture(),
+                Some(anyhow!("Test").into())
+            )
+            .into())
+        );
+
+        assert_eq!(link_count(&arc_process), link_count_before);
+        assert_eq!(link_count(&exiting_arc_process), 0);
+    });
+}
+
+#[test]
+fn when_pid_exits_while_linking_exit_signal_is_not_lost() {
+    let tag = Atom::str_to_term("EXIT");
+    let reason = Atom::str_to_term("abnormal");
+    let noproc = Atom::str_to_term("noproc");
+
+    for _ in 0..100 {
+        with_process_arc(|arc_process| {
+            arc_process.trap_exit(true);
+
+            let exiting_arc_process = test::process::child(&arc_process);
+            let exiting_pid = exiting_arc_process.pid_term();
+
+            let exiting = std::thread::spawn(move || {
+                exiting_arc_process.exit(reason, Trace::capture(), None);
+
+                let exception = match *exiting_arc_process.status.read() {
+                    Status::RuntimeException(ref exception) => exception.clone(),
+                    ref status => panic!("process is not exiting ({:?})", status),
+                };
+
+                propagate_exit(&exiting_arc_process, Some(&exception));
+            });
+
+            assert_eq!(result(&arc_process, exiting_pid), Ok(true.into()));
+
+            exiting.join().unwrap();
+
+            // Linking either beat the exit, so the exit signal was sent over the link, or lost to
+            // it, so that `noproc` was sent instead, but never neither or both
+            let exit_message = arc_process.tuple_from_slice(&[tag, exiting_pid, reason]);
+            let noproc_message = arc_process.tuple_from_slice(&[tag, exiting_pid, noproc]);
+
+            assert_ne!(
+                has_message(&arc_process, exit_message),
+                has_message(&arc_process, noproc_message)
+            );
+            assert_eq!(link_count(&arc_process), 0);
+        });
+    }
+}
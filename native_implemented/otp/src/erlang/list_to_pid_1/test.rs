@@ -1,9 +1,6 @@
-use std::sync::Arc;
-
 use proptest::strategy::Just;
 
 use liblumen_alloc::erts::term::prelude::*;
-use liblumen_alloc::erts::Node;
 
 use crate::runtime::distribution::nodes;
 
@@ -118,23 +115,27 @@ fn with_list_encoding_external_pid_without_known_node_errors_badarg() {
             format!("string ({}) is missing 'serial>", string)
         );
 
-        // MUST be a different `id` than other tests that insert the node.
-        let arc_node = Arc::new(Node::new(2, Atom::try_from_str("2@external").unwrap(), 0));
+        // The node table never gives out this id
+        let unknown_id = usize::MAX;
 
         assert_badarg!(
-            result(&process, process.charlist_from_str("<2.3.4>")),
-            "No node with id (2)"
+            result(
+                &process,
+                process.charlist_from_str(&format!("<{}.3.4>", unknown_id))
+            ),
+            format!("No node with id ({})", unknown_id)
         );
 
-        nodes::insert(arc_node.clone());
+        let arc_node = nodes::get_or_insert(Atom::try_from_str("list_to_pid@external").unwrap(), 0);
+        let string = format!("<{}.3.4>", arc_node.id());
 
         assert_eq!(
-            result(&process, process.charlist_from_str("<2.3.4>")),
+            result(&process, process.charlist_from_str(&string)),
             Ok(process.external_pid(arc_node, 3, 4).unwrap())
         );
 
         assert_badarg!(
-            result(&process, process.charlist_from_str("<2.3.4>?")),
+            result(&process, process.charlist_from_str(&format!("{}?", string))),
             "extra characters (\"?\") beyond end of formatted pid"
         );
     });
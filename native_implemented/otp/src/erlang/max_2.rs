@@ -1,6 +1,8 @@
 #[cfg(all(not(target_arch = "wasm32"), test))]
 mod test;
 
+use std::cmp::Ordering;
+
 use liblumen_alloc::erts::term::prelude::Term;
 
 /// `max/2`
@@ -8,7 +10,10 @@ use liblumen_alloc::erts::term::prelude::Term;
 /// Returns the largest of `Term1` and `Term2`. If the terms are equal, `Term1` is returned.
 #[native_implemented::function(erlang:max/2)]
 pub fn result(term1: Term, term2: Term) -> Term {
-    // Flip the order because for Rust `max` returns the second argument when equal, but Erlang
-    // returns the first
-    term2.max(term1)
+    // Numbers of different types can compare equal, such as `1` and `1.0`, in which case the
+    // first must be returned
+    match term1.cmp(&term2) {
+        Ordering::Less => term2,
+        Ordering::Greater | Ordering::Equal => term1,
+    }
 }
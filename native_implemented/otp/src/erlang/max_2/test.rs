@@ -12,8 +12,10 @@ mod with_small_integer_first;
 mod with_subbinary_first;
 mod with_tuple_first;
 
-use proptest::prop_assert_eq;
-use proptest::strategy::Strategy;
+use std::cmp::Ordering;
+
+use proptest::{prop_assert, prop_assert_eq};
+use proptest::strategy::{Just, Strategy};
 use proptest::test_runner::{Config, TestRunner};
 
 use liblumen_alloc::erts::process::Process;
@@ -63,6 +65,41 @@ fn max_is_second_if_first_is_less_than_second() {
         .unwrap();
 }
 
+#[test]
+fn max_compares_integers_and_floats_near_2_pow_53_exactly() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                -2048_isize..=2048,
+                proptest::bool::ANY,
+            )
+        },
+        |(arc_process, offset, negative)| {
+            let magnitude = (1_isize << 53) + offset;
+            let i = if negative { -magnitude } else { magnitude };
+            // Rounds to the nearest float, which is integral at this magnitude
+            let f = i as f64;
+            let integer = arc_process.integer(i);
+            let float = arc_process.float(f);
+
+            let expected = match (i as i128).cmp(&(f as i128)) {
+                Ordering::Greater | Ordering::Equal => integer,
+                Ordering::Less => float,
+            };
+            prop_assert!(result(integer, float).exact_eq(&expected));
+
+            let expected = match (f as i128).cmp(&(i as i128)) {
+                Ordering::Greater | Ordering::Equal => float,
+                Ordering::Less => integer,
+            };
+            prop_assert!(result(float, integer).exact_eq(&expected));
+
+            Ok(())
+        },
+    );
+}
+
 fn max<F, S>(first: F, second: S, which: FirstSecond)
 where
     F: FnOnce(&Process) -> Term,
@@ -81,5 +118,7 @@ where
 
         // expected value
         assert_eq!(max, expected);
+        // expected type, as numbers of different types can compare equal
+        assert!(max.exact_eq(&expected));
     });
 }
@@ -53,6 +53,11 @@ fn without_number_returns_second() {
     );
 }
 
+#[test]
+fn with_same_value_small_integer_second_returns_first() {
+    max(|_, process| process.integer(1), First)
+}
+
 fn max<R>(second: R, which: FirstSecond)
 where
     R: FnOnce(Term, &Process) -> Term,
@@ -1,6 +1,8 @@
 #[cfg(all(not(target_arch = "wasm32"), test))]
 mod test;
 
+use std::cmp::Ordering;
+
 use liblumen_alloc::erts::term::prelude::Term;
 
 /// `min/2`
@@ -8,5 +10,10 @@ use liblumen_alloc::erts::term::prelude::Term;
 /// Returns the smallest of `Term1` and `Term2`. If the terms are equal, `Term1` is returned.
 #[native_implemented::function(erlang:min/2)]
 pub fn result(term1: Term, term2: Term) -> Term {
-    term1.min(term2)
+    // Numbers of different types can compare equal, such as `1` and `1.0`, in which case the
+    // first must be returned
+    match term1.cmp(&term2) {
+        Ordering::Greater => term2,
+        Ordering::Less | Ordering::Equal => term1,
+    }
 }
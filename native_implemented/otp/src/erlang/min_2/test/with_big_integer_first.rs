@@ -74,6 +74,25 @@ fn without_second_number_returns_first() {
     );
 }
 
+#[test]
+fn with_same_value_float_second_returns_first() {
+    super::min(
+        |process| process.integer(1_i128 << 64),
+        |_, process| process.float(18446744073709551616.0),
+        First,
+    );
+}
+
+#[test]
+fn with_float_rounded_from_first_second_returns_second() {
+    // 2^64 + 1 cannot be represented as a float, and rounds to 2^64
+    super::min(
+        |process| process.integer((1_i128 << 64) + 1),
+        |_, process| process.float(((1_i128 << 64) + 1) as f64),
+        Second,
+    );
+}
+
 fn min<R>(second: R, which: FirstSecond)
 where
     R: FnOnce(Term, &Process) -> Term,
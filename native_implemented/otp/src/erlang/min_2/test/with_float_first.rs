@@ -53,6 +53,11 @@ fn without_number_returns_first() {
     );
 }
 
+#[test]
+fn with_same_value_small_integer_second_returns_first() {
+    min(|_, process| process.integer(1), First)
+}
+
 fn min<R>(second: R, which: FirstSecond)
 where
     R: FnOnce(Term, &Process) -> Term,
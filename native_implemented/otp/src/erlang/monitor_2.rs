@@ -62,8 +62,12 @@ fn monitor_process_identifier_noproc(process: &Process, identifier: Term) -> Ter
 
 fn monitor_process_pid(process: &Process, process_identifier: Term, pid: Pid) -> Term {
     match registry::pid_to_process(&pid) {
-        Some(monitored_arc_process) => process::monitor(process, &monitored_arc_process),
-        None => monitor_process_identifier_noproc(process, process_identifier),
+        // An exiting process may have already sent its `DOWN` messages, so a new monitor on it
+        // would never fire
+        Some(monitored_arc_process) if !monitored_arc_process.is_exiting() => {
+            process::monitor(process, &monitored_arc_process)
+        }
+        _ => monitor_process_identifier_noproc(process, process_identifier),
     }
 }
 
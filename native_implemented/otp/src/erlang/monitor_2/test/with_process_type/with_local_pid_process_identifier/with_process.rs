@@ -124,3 +124,75 @@ fn when_monitored_process_exits_it_sends_message_for_each_monitor_reference() {
         );
     });
 }
+
+#[test]
+fn when_monitored_process_exits_monitor_is_removed_from_monitoring_process() {
+    with_process_arc(|monitoring_arc_process| {
+        let monitored_arc_process = test::process::child(&monitoring_arc_process);
+
+        let monitoring_monitored_count_before = monitored_count(&monitoring_arc_process);
+
+        result(
+            &monitoring_arc_process,
+            r#type(),
+            monitored_arc_process.pid_term(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            monitored_count(&monitoring_arc_process),
+            monitoring_monitored_count_before + 1
+        );
+
+        exit_when_run(&monitored_arc_process, Atom::str_to_term("normal"));
+
+        assert!(scheduler::run_through(&monitored_arc_process));
+
+        assert!(monitored_arc_process.is_exiting());
+        assert_eq!(
+            monitored_count(&monitoring_arc_process),
+            monitoring_monitored_count_before
+        );
+    });
+}
+
+#[test]
+fn when_monitored_process_is_exiting_returns_reference_but_immediately_sends_noproc_message() {
+    with_process_arc(|monitoring_arc_process| {
+        let monitored_arc_process = test::process::child(&monitoring_arc_process);
+
+        exit_when_run(&monitored_arc_process, Atom::str_to_term("normal"));
+
+        assert!(scheduler::run_through(&monitored_arc_process));
+
+        assert!(monitored_arc_process.is_exiting());
+
+        let monitored_monitor_count_before = monitor_count(&monitored_arc_process);
+
+        let monitor_reference = result(
+            &monitoring_arc_process,
+            r#type(),
+            monitored_arc_process.pid_term(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            monitor_count(&monitored_arc_process),
+            monitored_monitor_count_before
+        );
+
+        let tag = Atom::str_to_term("DOWN");
+        let reason = Atom::str_to_term("noproc");
+
+        assert_has_message!(
+            &monitoring_arc_process,
+            monitoring_arc_process.tuple_from_slice(&[
+                tag,
+                monitor_reference,
+                r#type(),
+                monitored_arc_process.pid_term(),
+                reason
+            ])
+        );
+    });
+}
@@ -1,8 +1,13 @@
+use std::convert::TryInto;
+
+use num_bigint::BigInt;
+
 use crate::erlang::monotonic_time_0::result;
 use crate::test::with_process;
 
 use liblumen_alloc::erts::time::Milliseconds;
 
+use crate::runtime::scheduler;
 use crate::runtime::time::monotonic;
 
 #[test]
@@ -19,3 +24,53 @@ fn increases_after_2_native_time_units() {
         assert!(first < second);
     });
 }
+
+#[test]
+fn never_decreases_across_scheduler_cycles() {
+    with_process(|process| {
+        let scheduler = scheduler::current();
+        let mut previous: BigInt = result(process).try_into().unwrap();
+
+        for _ in 0..1_000 {
+            scheduler.run_once();
+
+            let current: BigInt = result(process).try_into().unwrap();
+
+            assert!(previous <= current);
+
+            previous = current;
+        }
+    });
+}
+
+// `receive after` timeouts are driven by the timer wheel, so it must read the same clock
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn agrees_with_timer_wheel() {
+    use crate::runtime::time::{self, Unit};
+    use crate::test::{
+        freeze_at_timeout, freeze_timeout, has_message, timeout_message,
+        with_timer_in_same_thread,
+    };
+
+    with_timer_in_same_thread(|milliseconds, message, timer_reference, process| {
+        let start_monotonic = freeze_timeout();
+        let start: BigInt = result(process).try_into().unwrap();
+        let timeout_message = timeout_message(timer_reference, message, process);
+
+        assert!(!has_message(process, timeout_message));
+
+        freeze_at_timeout(start_monotonic + milliseconds + Milliseconds(1));
+        let end: BigInt = result(process).try_into().unwrap();
+
+        assert!(has_message(process, timeout_message));
+        assert_eq!(
+            end - start,
+            time::convert(
+                (milliseconds + Milliseconds(1)).into(),
+                Unit::Millisecond,
+                Unit::Native
+            )
+        );
+    });
+}
@@ -0,0 +1,85 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::sync::Arc;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception::{self, InternalResult};
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::Node;
+
+use crate::runtime::distribution::nodes::{self, node};
+use crate::runtime::distribution::transport;
+
+const SUPPORTED_NODE_TYPES: &str = "visible, hidden, connected, this, or known";
+
+/// Hidden connections are not supported, so all connected nodes are visible.
+#[native_implemented::function(erlang:nodes/1)]
+pub fn result(process: &Process, arg: Term) -> exception::Result<Term> {
+    let mut arc_node_vec: Vec<Arc<Node>> = Vec::new();
+
+    for node_type in node_type_vec(arg)? {
+        for arc_node in node_type_arc_node_vec(node_type)? {
+            if !arc_node_vec.contains(&arc_node) {
+                arc_node_vec.push(arc_node);
+            }
+        }
+    }
+
+    let name_vec: Vec<Term> = arc_node_vec
+        .iter()
+        .map(|arc_node| arc_node.name().encode().unwrap())
+        .collect();
+
+    Ok(process.list_from_slice(&name_vec))
+}
+
+// Private
+
+fn node_type_vec(arg: Term) -> InternalResult<Vec<Term>> {
+    match arg.decode()? {
+        TypedTerm::Atom(_) => Ok(vec![arg]),
+        TypedTerm::Nil => Ok(Vec::new()),
+        TypedTerm::List(cons) => {
+            let mut node_type_vec = Vec::new();
+
+            for result in cons.into_iter() {
+                match result {
+                    Ok(element) => node_type_vec.push(element),
+                    Err(_) => {
+                        return Err(ImproperListError)
+                            .context(format!("arg ({}) is improper", arg))
+                            .map_err(From::from)
+                    }
+                }
+            }
+
+            Ok(node_type_vec)
+        }
+        _ => Err(TypeError)
+            .context(format!(
+                "arg ({}) is neither a node type ({}) nor a list of node types",
+                arg, SUPPORTED_NODE_TYPES
+            ))
+            .map_err(From::from),
+    }
+}
+
+fn node_type_arc_node_vec(node_type: Term) -> InternalResult<Vec<Arc<Node>>> {
+    let node_type_atom = term_try_into_atom!(node_type)?;
+
+    match node_type_atom.name() {
+        "visible" | "connected" => Ok(transport::connected()),
+        "hidden" => Ok(Vec::new()),
+        "this" => Ok(vec![node::arc_node()]),
+        "known" => Ok(nodes::known()),
+        _ => Err(anyhow!(
+            "node_type ({}) is not a supported atom ({})",
+            node_type,
+            SUPPORTED_NODE_TYPES
+        )
+        .into()),
+    }
+}
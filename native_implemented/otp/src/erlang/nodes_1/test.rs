@@ -0,0 +1,110 @@
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::nodes_1::result;
+use crate::net_kernel::connect_node_1;
+use crate::test::distribution::{with_transport, MockTransport};
+use crate::test::{external_arc_node, with_process};
+
+#[test]
+fn without_atom_or_list_errors_badarg() {
+    with_process(|process| {
+        let arg = process.integer(0);
+
+        assert_badarg!(
+            result(process, arg),
+            format!(
+                "arg ({}) is neither a node type (visible, hidden, connected, this, or known) nor a list of node types",
+                arg
+            )
+        );
+    });
+}
+
+#[test]
+fn with_unsupported_atom_errors_badarg() {
+    with_process(|process| {
+        let arg = Atom::str_to_term("unsupported");
+
+        assert_badarg!(
+            result(process, arg),
+            format!(
+                "node_type ({}) is not a supported atom (visible, hidden, connected, this, or known)",
+                arg
+            )
+        );
+    });
+}
+
+#[test]
+fn without_transport_connected_is_empty() {
+    with_transport(None, || {
+        with_process(|process| {
+            for node_type in ["visible", "hidden", "connected"] {
+                assert_eq!(result(process, Atom::str_to_term(node_type)), Ok(Term::NIL));
+            }
+        });
+    });
+}
+
+#[test]
+fn with_this_returns_local_node() {
+    with_process(|process| {
+        assert_eq!(
+            result(process, Atom::str_to_term("this")),
+            Ok(process.list_from_slice(&[Atom::str_to_term("nonode@nohost")]))
+        );
+    });
+}
+
+#[test]
+fn with_known_includes_local_node_and_nodes_known_from_terms() {
+    with_process(|process| {
+        let external_node = external_arc_node().name().encode().unwrap();
+        let known = result(process, Atom::str_to_term("known")).unwrap();
+
+        assert!(known.is_list());
+
+        let known_cons: Boxed<Cons> = known.try_into().unwrap();
+        let known_vec: Vec<Term> = known_cons.into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(known_vec[0], Atom::str_to_term("nonode@nohost"));
+        assert!(known_vec.contains(&external_node));
+    });
+}
+
+#[test]
+fn with_transport_connected_and_visible_are_nodes_connected_to() {
+    let arc_node = external_arc_node();
+    let node = arc_node.name().encode().unwrap();
+
+    with_transport(Some(MockTransport::new(Some(arc_node.creation()))), || {
+        with_process(|process| {
+            assert_eq!(
+                result(process, Atom::str_to_term("connected")),
+                Ok(Term::NIL)
+            );
+
+            assert_eq!(connect_node_1::result(node), Ok(true.into()));
+
+            let node_list = process.list_from_slice(&[node]);
+
+            assert_eq!(
+                result(process, Atom::str_to_term("connected")),
+                Ok(node_list)
+            );
+            assert_eq!(result(process, Atom::str_to_term("visible")), Ok(node_list));
+            assert_eq!(result(process, Atom::str_to_term("hidden")), Ok(Term::NIL));
+
+            let node_type_list = process.list_from_slice(&[
+                Atom::str_to_term("this"),
+                Atom::str_to_term("visible"),
+                Atom::str_to_term("connected"),
+            ]);
+
+            assert_eq!(
+                result(process, node_type_list),
+                Ok(process.list_from_slice(&[Atom::str_to_term("nonode@nohost"), node]))
+            );
+        });
+    });
+}
@@ -4,10 +4,12 @@ mod test;
 use anyhow::*;
 
 use liblumen_alloc::erts::exception;
-use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::process::{MaxHeapSize, Priority, Process};
 use liblumen_alloc::erts::term::prelude::*;
 
+use crate::erlang::process_info;
 use crate::runtime::context::*;
+use crate::runtime::scheduler::Scheduled;
 
 #[native_implemented::function(erlang:process_flag/2)]
 pub fn result(process: &Process, flag: Term, value: Term) -> exception::Result<Term> {
@@ -15,11 +17,43 @@ pub fn result(process: &Process, flag: Term, value: Term) -> exception::Result<T
 
     match flag_atom.name() {
         "error_handler" => unimplemented!(),
-        "max_heap_size" => unimplemented!(),
-        "message_queue_data" => unimplemented!(),
+        "max_heap_size" => {
+            let max_heap_size: MaxHeapSize = value.try_into().context("max_heap_size value")?;
+
+            Ok(process.set_max_heap_size(max_heap_size).to_term(process))
+        }
+        "message_queue_data" => {
+            let value_atom: Atom = term_try_into_atom("message_queue_data value", value)?;
+            let off_heap = match value_atom.name() {
+                "off_heap" => true,
+                "on_heap" => false,
+                name => {
+                    return Err(TryAtomFromTermError(name))
+                        .context("supported message_queue_data are off_heap or on_heap")
+                        .map_err(From::from)
+                }
+            };
+            let old_value = process_info::message_queue_data(process);
+            process.message_queue_off_heap(off_heap);
+
+            Ok(old_value)
+        }
         "min_bin_vheap_size" => unimplemented!(),
         "min_heap_size" => unimplemented!(),
-        "priority" => unimplemented!(),
+        "priority" => {
+            let priority: Priority = value.try_into().context("priority value")?;
+            let old_value = process_info::priority(process);
+
+            // The scheduler moves the process if it is in a run queue, otherwise the new priority
+            // takes effect when it is next enqueued, which for the calling process is when it
+            // yields
+            match process.scheduler() {
+                Some(scheduler) => scheduler.set_priority(process, priority),
+                None => process.set_priority(priority),
+            };
+
+            Ok(old_value)
+        }
         "save_calls" => unimplemented!(),
         "sensitive" => unimplemented!(),
         "trap_exit" => {
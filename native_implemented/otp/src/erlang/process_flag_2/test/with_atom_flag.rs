@@ -1,3 +1,6 @@
+mod with_max_heap_size_flag;
+mod with_message_queue_data_flag;
+mod with_priority_flag;
 mod with_trap_exit_flag;
 
 use super::*;
@@ -26,7 +29,7 @@ fn unsupported_flag_atom() -> BoxedStrategy<Term> {
             let atom_atom: Atom = (*atom).try_into().unwrap();
 
             match atom_atom.name() {
-                "trap_exit" => false,
+                "max_heap_size" | "message_queue_data" | "priority" | "trap_exit" => false,
                 _ => true,
             }
         })
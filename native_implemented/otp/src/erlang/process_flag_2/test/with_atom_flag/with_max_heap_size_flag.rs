@@ -0,0 +1,58 @@
+use super::*;
+
+use liblumen_alloc::erts::process::MaxHeapSize;
+
+#[test]
+fn without_non_negative_integer_or_map_errors_badarg() {
+    with_process(|process| {
+        assert!(result(process, flag(), process.integer(-1)).is_err());
+        assert!(result(process, flag(), Atom::str_to_term("infinity")).is_err());
+        assert_eq!(process.max_heap_size(), MaxHeapSize::default());
+    });
+}
+
+#[test]
+fn with_size_returns_old_value() {
+    with_process(|process| {
+        let old_value = result(process, flag(), process.integer(1_000)).unwrap();
+
+        assert_eq!(fields(old_value), (0, true, true));
+        assert_eq!(
+            fields(process.max_heap_size().to_term(process)),
+            (1_000, true, true)
+        );
+    });
+}
+
+#[test]
+fn with_map_sets_kill_and_error_logger() {
+    with_process(|process| {
+        let value = process.map_from_slice(&[
+            (Atom::str_to_term("size"), process.integer(2_000)),
+            (Atom::str_to_term("kill"), false.into()),
+            (Atom::str_to_term("error_logger"), false.into()),
+        ]);
+
+        assert!(result(process, flag(), value).is_ok());
+
+        let max_heap_size = process.max_heap_size();
+
+        assert_eq!(max_heap_size.size(), 2_000);
+        assert!(!max_heap_size.kills());
+        assert!(!max_heap_size.logs());
+    });
+}
+
+fn fields(term: Term) -> (usize, bool, bool) {
+    let max_heap_size: MaxHeapSize = term.try_into().unwrap();
+
+    (
+        max_heap_size.size(),
+        max_heap_size.kills(),
+        max_heap_size.logs(),
+    )
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("max_heap_size")
+}
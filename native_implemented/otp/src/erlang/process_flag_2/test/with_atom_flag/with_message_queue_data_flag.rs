@@ -0,0 +1,46 @@
+use super::*;
+
+#[test]
+fn without_supported_value_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term(arc_process.clone()).prop_filter(
+                    "Value cannot be off_heap or on_heap",
+                    |value| match value.decode().unwrap() {
+                        TypedTerm::Atom(atom) => !matches!(atom.name(), "off_heap" | "on_heap"),
+                        _ => true,
+                    },
+                ),
+            )
+        },
+        |(arc_process, value)| {
+            prop_assert!(result(&arc_process, flag(), value).is_err());
+            prop_assert!(!arc_process.is_message_queue_off_heap());
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn with_supported_value_returns_old_value() {
+    with_process(|process| {
+        assert_eq!(
+            result(process, flag(), Atom::str_to_term("off_heap")),
+            Ok(Atom::str_to_term("on_heap"))
+        );
+        assert!(process.is_message_queue_off_heap());
+
+        assert_eq!(
+            result(process, flag(), Atom::str_to_term("on_heap")),
+            Ok(Atom::str_to_term("off_heap"))
+        );
+        assert!(!process.is_message_queue_off_heap());
+    });
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("message_queue_data")
+}
@@ -0,0 +1,59 @@
+use super::*;
+
+use liblumen_alloc::erts::process::Priority;
+
+#[test]
+fn without_supported_value_errors_badarg() {
+    run!(
+        |arc_process| {
+            (
+                Just(arc_process.clone()),
+                strategy::term(arc_process.clone()).prop_filter(
+                    "Value cannot be a priority",
+                    |value| match value.decode().unwrap() {
+                        TypedTerm::Atom(atom) => {
+                            !matches!(atom.name(), "low" | "normal" | "high" | "max")
+                        }
+                        _ => true,
+                    },
+                ),
+            )
+        },
+        |(arc_process, value)| {
+            prop_assert!(result(&arc_process, flag(), value).is_err());
+
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn with_supported_value_returns_old_value() {
+    with_process(|process| {
+        let mut old_priority = "normal";
+
+        for priority in &["low", "normal", "high", "max"] {
+            assert_eq!(
+                result(process, flag(), Atom::str_to_term(priority)),
+                Ok(Atom::str_to_term(old_priority))
+            );
+
+            old_priority = priority;
+        }
+    });
+}
+
+#[test]
+fn with_supported_value_sets_priority() {
+    with_process(|process| {
+        assert_eq!(
+            result(process, flag(), Atom::str_to_term("high")),
+            Ok(Atom::str_to_term("normal"))
+        );
+        assert_eq!(process.priority(), Priority::High);
+    });
+}
+
+fn flag() -> Term {
+    Atom::str_to_term("priority")
+}
@@ -0,0 +1,245 @@
+//! Items shared by `erlang:process_info/1` and `erlang:process_info/2`
+//!
+//! The process being inspected may be different from the calling process, so every term
+//! returned is allocated on the calling process's heap.
+
+use anyhow::*;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::borrow::clone_to_process::CloneToProcess;
+use liblumen_alloc::erts::exception::{self, InternalResult};
+use liblumen_alloc::erts::message::{self, MessageData};
+use liblumen_alloc::erts::process::{Priority, Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::ModuleFunctionArity;
+
+use crate::runtime::registry::pid_to_process;
+
+/// The items returned by `erlang:process_info/1`, in order.
+///
+/// `registered_name` is only included when the process is registered.
+pub const DEFAULT_ITEMS: &[&str] = &[
+    "registered_name",
+    "current_function",
+    "initial_call",
+    "status",
+    "message_queue_len",
+    "links",
+    "monitors",
+    "trap_exit",
+    "priority",
+    "group_leader",
+    "total_heap_size",
+    "heap_size",
+    "reductions",
+];
+
+/// Calls `f` with the live process for `pid`, or returns `undefined` if it is not alive
+pub fn with_process<F>(process: &Process, pid: Term, f: F) -> exception::Result<Term>
+where
+    F: FnOnce(&Process) -> exception::Result<Term>,
+{
+    let pid_pid = term_try_into_local_pid!(pid)?;
+
+    if process.pid() == pid_pid {
+        f(process)
+    } else {
+        match pid_to_process(&pid_pid) {
+            Some(pid_arc_process) if !pid_arc_process.is_exiting() => f(&pid_arc_process),
+            _ => Ok(atom!("undefined")),
+        }
+    }
+}
+
+/// Returns `{item, value}` for `item` of `target`
+pub fn item(process: &Process, target: &Process, item: Atom) -> InternalResult<Term> {
+    let tag = item.encode().unwrap();
+    let value = value(process, target, item)?;
+
+    Ok(process.tuple_from_slice(&[tag, value]))
+}
+
+/// Returns whether `target` has a registered name
+pub fn is_registered(target: &Process) -> bool {
+    target.registered_name.read().is_some()
+}
+
+fn value(process: &Process, target: &Process, item: Atom) -> InternalResult<Term> {
+    match item.name() {
+        "backtrace" => unimplemented!(),
+        "binary" => unimplemented!(),
+        "catchlevel" => unimplemented!(),
+        "current_function" => Ok(current_function(process, target)),
+        "current_location" => unimplemented!(),
+        "current_stacktrace" => unimplemented!(),
+        "dictionary" => unimplemented!(),
+        "error_handler" => unimplemented!(),
+        "garbage_collection" => Ok(garbage_collection(process, target)),
+        "garbage_collection_info" => unimplemented!(),
+        "group_leader" => Ok(target.get_group_leader_pid_term()),
+        "heap_size" => Ok(process.integer(target.heap_size())),
+        "initial_call" => Ok(module_function_arity_tuple(
+            process,
+            &target.initial_module_function_arity,
+        )),
+        "links" => Ok(links(process, target)),
+        "last_calls" => unimplemented!(),
+        "memory" => unimplemented!(),
+        "message_queue_len" => Ok(process.integer(target.message_queue_len())),
+        "messages" => Ok(messages(process, target)),
+        "min_heap_size" => unimplemented!(),
+        "min_bin_vheap_size" => unimplemented!(),
+        "monitored_by" => Ok(monitored_by(process, target)),
+        "monitors" => Ok(monitors(process, target)),
+        "message_queue_data" => Ok(message_queue_data(target)),
+        "priority" => Ok(priority(target)),
+        "reductions" => Ok(process.integer(target.reductions())),
+        "registered_name" => Ok(registered_name(target)),
+        "sequential_trace_token" => unimplemented!(),
+        "stack_size" => unimplemented!(),
+        "status" => Ok(status(target)),
+        "suspending" => unimplemented!(),
+        "total_heap_size" => Ok(process.integer(target.total_heap_size())),
+        "trace" => unimplemented!(),
+        "trap_exit" => Ok(target.traps_exit().into()),
+        name => Err(TryAtomFromTermError(name))
+            .context(
+                "supported items are backtrace, binary, catchlevel, current_function, \
+                 current_location, current_stacktrace, dictionary, error_handler, \
+                 garbage_collection, garbage_collection_info, group_leader, heap_size, \
+                 initial_call, links, last_calls, memory, message_queue_len, messages, \
+                 min_heap_size, min_bin_vheap_size, monitored_by, monitors, \
+                 message_queue_data, priority, reductions, registered_name, \
+                 sequential_trace_token, stack_size, status, suspending, \
+                 total_heap_size, trace, trap_exit",
+            )
+            .map_err(From::from),
+    }
+}
+
+fn current_function(process: &Process, target: &Process) -> Term {
+    match target.current_module_function_arity() {
+        Some(module_function_arity) => {
+            module_function_arity_tuple(process, &module_function_arity)
+        }
+        None => atom!("undefined"),
+    }
+}
+
+/// `minor_gcs` is the number of minor collections since the last full sweep, as in ERTS
+fn garbage_collection(process: &Process, target: &Process) -> Term {
+    let vec: Vec<Term> = [
+        ("max_heap_size", target.max_heap_size().to_term(process)),
+        (
+            "min_bin_vheap_size",
+            process.integer(target.min_bin_vheap_size()),
+        ),
+        ("min_heap_size", process.integer(target.min_heap_size())),
+        ("fullsweep_after", process.integer(target.fullsweep_after())),
+        ("minor_gcs", process.integer(target.minor_gcs())),
+    ]
+    .iter()
+    .map(|(tag, value)| process.tuple_from_slice(&[Atom::str_to_term(tag), *value]))
+    .collect();
+
+    process.list_from_slice(&vec)
+}
+
+fn links(process: &Process, target: &Process) -> Term {
+    let vec: Vec<Term> = target
+        .linked_pid_set
+        .iter()
+        .map(|ref_multi| ref_multi.encode().unwrap())
+        .collect();
+
+    process.list_from_slice(&vec)
+}
+
+fn messages(process: &Process, target: &Process) -> Term {
+    let is_self = process.pid() == target.pid();
+
+    let vec: Vec<Term> = target
+        .mailbox
+        .lock()
+        .borrow()
+        .iter()
+        .map(|message| match &message.data {
+            MessageData::Process(data) if is_self => *data,
+            MessageData::Process(data) => data.clone_to_process(process),
+            MessageData::HeapFragment(message::HeapFragment { data, .. })
+            | MessageData::Mailbox(message::HeapFragment { data, .. }) => {
+                data.clone_to_process(process)
+            }
+        })
+        .collect();
+
+    process.list_from_slice(&vec)
+}
+
+fn module_function_arity_tuple(
+    process: &Process,
+    module_function_arity: &ModuleFunctionArity,
+) -> Term {
+    process.tuple_from_slice(&[
+        module_function_arity.module.encode().unwrap(),
+        module_function_arity.function.encode().unwrap(),
+        process.integer(module_function_arity.arity),
+    ])
+}
+
+fn monitored_by(process: &Process, target: &Process) -> Term {
+    let vec: Vec<Term> = target
+        .monitor_by_reference
+        .iter()
+        .map(|ref_multi| ref_multi.monitoring_pid().encode().unwrap())
+        .collect();
+
+    process.list_from_slice(&vec)
+}
+
+fn monitors(process: &Process, target: &Process) -> Term {
+    let monitor_type = atom!("process");
+    let mut vec = Vec::new();
+
+    for ref_multi in target.monitored_pid_by_reference.iter() {
+        let pid = ref_multi.value();
+        let monitor_value = pid.encode().unwrap();
+        let monitor = process.tuple_from_slice(&[monitor_type, monitor_value]);
+        vec.push(monitor);
+    }
+
+    process.list_from_slice(&vec)
+}
+
+pub(crate) fn message_queue_data(target: &Process) -> Term {
+    if target.is_message_queue_off_heap() {
+        atom!("off_heap")
+    } else {
+        atom!("on_heap")
+    }
+}
+
+pub(crate) fn priority(target: &Process) -> Term {
+    match target.priority() {
+        Priority::Low => atom!("low"),
+        Priority::Normal => atom!("normal"),
+        Priority::High => atom!("high"),
+        Priority::Max => atom!("max"),
+    }
+}
+
+fn registered_name(target: &Process) -> Term {
+    match *target.registered_name.read() {
+        Some(registered_name) => registered_name.encode().unwrap(),
+        None => Term::NIL,
+    }
+}
+
+fn status(target: &Process) -> Term {
+    match *target.status.read() {
+        Status::Unrunnable | Status::Runnable => atom!("runnable"),
+        Status::Running | Status::MaybeWaiting => atom!("running"),
+        Status::Waiting => atom!("waiting"),
+        Status::Exited | Status::RuntimeException(_) => atom!("exiting"),
+    }
+}
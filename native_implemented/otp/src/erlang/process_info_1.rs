@@ -0,0 +1,26 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::process_info::{self, DEFAULT_ITEMS};
+
+#[native_implemented::function(erlang:process_info/1)]
+pub fn result(process: &Process, pid: Term) -> exception::Result<Term> {
+    process_info::with_process(process, pid, |target| {
+        let mut vec = Vec::with_capacity(DEFAULT_ITEMS.len());
+
+        for name in DEFAULT_ITEMS {
+            if *name == "registered_name" && !process_info::is_registered(target) {
+                continue;
+            }
+
+            let item = Atom::from_str(name);
+            vec.push(process_info::item(process, target, item)?);
+        }
+
+        Ok(process.list_from_slice(&vec))
+    })
+}
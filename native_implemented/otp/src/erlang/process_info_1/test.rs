@@ -0,0 +1,107 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::process_info_1::result;
+use crate::runtime::registry;
+use crate::test;
+use crate::test::{registered_name, with_process_arc};
+
+#[test]
+fn without_process_returns_undefined() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            result(&arc_process, Pid::next_term()),
+            Ok(Atom::str_to_term("undefined"))
+        );
+    });
+}
+
+#[test]
+fn without_registered_name_omits_registered_name() {
+    with_process_arc(|parent_arc_process| {
+        let child_arc_process = test::process::child(&parent_arc_process);
+
+        assert_eq!(
+            items(result(&parent_arc_process, child_arc_process.pid_term()).unwrap()),
+            [
+                "current_function",
+                "initial_call",
+                "status",
+                "message_queue_len",
+                "links",
+                "monitors",
+                "trap_exit",
+                "priority",
+                "group_leader",
+                "total_heap_size",
+                "heap_size",
+                "reductions"
+            ]
+        );
+    });
+}
+
+#[test]
+fn with_registered_name_starts_with_registered_name() {
+    with_process_arc(|parent_arc_process| {
+        let child_arc_process = test::process::child(&parent_arc_process);
+        let registered_name = registered_name();
+        let registered_name_atom: Atom = registered_name.try_into().unwrap();
+
+        assert!(registry::put_atom_to_process(
+            registered_name_atom,
+            child_arc_process.clone()
+        ));
+
+        let info = result(&parent_arc_process, child_arc_process.pid_term()).unwrap();
+        let first: Boxed<Cons> = info.try_into().unwrap();
+
+        assert_eq!(
+            first.head,
+            parent_arc_process.tuple_from_slice(&[
+                Atom::str_to_term("registered_name"),
+                registered_name
+            ])
+        );
+        assert_eq!(items(info).len(), 13);
+    });
+}
+
+#[test]
+fn with_messages_counts_message_queue_len() {
+    with_process_arc(|parent_arc_process| {
+        let child_arc_process = test::process::child(&parent_arc_process);
+        child_arc_process.test_inject_message(Atom::str_to_term("message"));
+
+        let info = result(&parent_arc_process, child_arc_process.pid_term()).unwrap();
+
+        assert!(contains(
+            info,
+            parent_arc_process.tuple_from_slice(&[
+                Atom::str_to_term("message_queue_len"),
+                parent_arc_process.integer(1)
+            ])
+        ));
+    });
+}
+
+/// Returns the item names of a `process_info/1` result, in order
+fn items(info: Term) -> Vec<String> {
+    let cons: Boxed<Cons> = info.try_into().unwrap();
+
+    cons.into_iter()
+        .map(|result| {
+            let tuple: Boxed<Tuple> = result.unwrap().try_into().unwrap();
+            let item: Atom = tuple[0].try_into().unwrap();
+
+            item.name().to_string()
+        })
+        .collect()
+}
+
+fn contains(info: Term, item: Term) -> bool {
+    let cons: Boxed<Cons> = info.try_into().unwrap();
+
+    cons.into_iter().any(|result| result.unwrap() == item)
+}
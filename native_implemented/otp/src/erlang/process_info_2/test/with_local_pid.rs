@@ -1,3 +1,4 @@
+mod with_item_list;
 mod with_registered_name;
 
 use super::*;
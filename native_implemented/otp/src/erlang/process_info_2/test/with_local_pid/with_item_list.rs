@@ -0,0 +1,175 @@
+use super::*;
+
+use liblumen_alloc::erts::process::Process;
+
+#[test]
+fn with_empty_list_returns_empty_list() {
+    with_process_arc(|arc_process| {
+        assert_eq!(
+            result(&arc_process, arc_process.pid_term(), Term::NIL),
+            Ok(Term::NIL)
+        );
+    });
+}
+
+#[test]
+fn without_process_returns_undefined() {
+    with_process_arc(|arc_process| {
+        let item_list = arc_process.list_from_slice(&[atom("status")]);
+
+        assert_eq!(
+            result(&arc_process, Pid::next_term(), item_list),
+            Ok(Atom::str_to_term("undefined"))
+        );
+    });
+}
+
+#[test]
+fn returns_items_in_requested_order() {
+    with_process_arc(|parent_arc_process| {
+        let child_arc_process = test::process::child(&parent_arc_process);
+        child_arc_process.test_inject_message(Atom::str_to_term("first"));
+        child_arc_process.test_inject_message(Atom::str_to_term("second"));
+
+        let item_list = parent_arc_process.list_from_slice(&[
+            atom("priority"),
+            atom("message_queue_len"),
+            atom("registered_name"),
+            atom("reductions"),
+            atom("links"),
+            atom("priority"),
+        ]);
+        let reductions = child_arc_process.reductions();
+
+        assert_eq!(
+            result(&parent_arc_process, child_arc_process.pid_term(), item_list),
+            Ok(parent_arc_process.list_from_slice(&[
+                tuple(&parent_arc_process, "priority", atom("normal")),
+                tuple(
+                    &parent_arc_process,
+                    "message_queue_len",
+                    parent_arc_process.integer(2)
+                ),
+                tuple(&parent_arc_process, "registered_name", Term::NIL),
+                tuple(
+                    &parent_arc_process,
+                    "reductions",
+                    parent_arc_process.integer(reductions)
+                ),
+                tuple(&parent_arc_process, "links", Term::NIL),
+                tuple(&parent_arc_process, "priority", atom("normal")),
+            ]))
+        );
+    });
+}
+
+#[test]
+fn with_heap_sizes_returns_words() {
+    with_process_arc(|arc_process| {
+        let item_list =
+            arc_process.list_from_slice(&[atom("heap_size"), atom("total_heap_size")]);
+        let heap_size = arc_process.heap_size();
+        let total_heap_size = arc_process.total_heap_size();
+
+        assert!(0 < heap_size);
+        assert!(heap_size <= total_heap_size);
+        assert_eq!(
+            result(&arc_process, arc_process.pid_term(), item_list),
+            Ok(arc_process.list_from_slice(&[
+                tuple(&arc_process, "heap_size", arc_process.integer(heap_size)),
+                tuple(
+                    &arc_process,
+                    "total_heap_size",
+                    arc_process.integer(total_heap_size)
+                ),
+            ]))
+        );
+    });
+}
+
+#[test]
+fn with_garbage_collection_returns_minor_collections_since_fullsweep() {
+    with_process_arc(|parent_arc_process| {
+        let child_arc_process = test::process::child(&parent_arc_process);
+        child_arc_process.set_fullsweep_after(10);
+        let mut roots = [];
+
+        for _ in 0..2 {
+            child_arc_process
+                .garbage_collect(0, &mut roots[..])
+                .unwrap();
+        }
+
+        let item_list = parent_arc_process.list_from_slice(&[atom("garbage_collection")]);
+        let garbage_collection = parent_arc_process.list_from_slice(&[
+            tuple(
+                &parent_arc_process,
+                "max_heap_size",
+                child_arc_process
+                    .max_heap_size()
+                    .to_term(&parent_arc_process),
+            ),
+            tuple(
+                &parent_arc_process,
+                "min_bin_vheap_size",
+                parent_arc_process.integer(child_arc_process.min_bin_vheap_size()),
+            ),
+            tuple(
+                &parent_arc_process,
+                "min_heap_size",
+                parent_arc_process.integer(child_arc_process.min_heap_size()),
+            ),
+            tuple(
+                &parent_arc_process,
+                "fullsweep_after",
+                parent_arc_process.integer(10),
+            ),
+            tuple(
+                &parent_arc_process,
+                "minor_gcs",
+                parent_arc_process.integer(2),
+            ),
+        ]);
+
+        assert_eq!(
+            result(&parent_arc_process, child_arc_process.pid_term(), item_list),
+            Ok(parent_arc_process.list_from_slice(&[tuple(
+                &parent_arc_process,
+                "garbage_collection",
+                garbage_collection
+            )]))
+        );
+    });
+}
+
+#[test]
+fn with_improper_list_errors_badarg() {
+    with_process_arc(|arc_process| {
+        let item_list = arc_process.cons(atom("status"), atom("links"));
+
+        assert_badarg!(
+            result(&arc_process, arc_process.pid_term(), item_list),
+            format!("item_list ({}) is improper", item_list)
+        );
+    });
+}
+
+#[test]
+fn with_non_atom_item_errors_badarg() {
+    with_process_arc(|arc_process| {
+        let item_list = arc_process.list_from_slice(&[arc_process.integer(0)]);
+
+        assert_badarg!(
+            result(&arc_process, arc_process.pid_term(), item_list),
+            "item"
+        );
+    });
+}
+
+fn atom(name: &str) -> Term {
+    Atom::str_to_term(name)
+}
+
+fn tuple(process: &Process, item: &str, value: Term) -> Term {
+    process.tuple_from_slice(&[atom(item), value])
+}
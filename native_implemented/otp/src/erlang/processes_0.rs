@@ -0,0 +1,19 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::runtime::registry;
+
+/// The pids of all local processes, including those that are exiting but are still in the
+/// process table, as in OTP.
+#[native_implemented::function(erlang:processes/0)]
+pub fn result(process: &Process) -> Term {
+    let pid_terms: Vec<Term> = registry::pids()
+        .into_iter()
+        .map(|pid| pid.encode().unwrap())
+        .collect();
+
+    process.list_from_slice(&pid_terms)
+}
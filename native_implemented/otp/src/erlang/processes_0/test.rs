@@ -0,0 +1,44 @@
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::processes_0::result;
+use crate::runtime::registry;
+use crate::test::{self, with_process_arc};
+
+#[test]
+fn includes_self_and_spawned_processes() {
+    with_process_arc(|arc_process| {
+        let children: Vec<_> = (0..10)
+            .map(|_| test::process::child(&arc_process))
+            .collect();
+
+        let processes = result(&arc_process);
+
+        assert!(contains(processes, arc_process.pid_term()));
+        for child in children {
+            assert!(contains(processes, child.pid_term()));
+        }
+    });
+}
+
+#[test]
+fn includes_exiting_process_until_it_is_removed() {
+    with_process_arc(|arc_process| {
+        let child = test::process::child(&arc_process);
+        let pid = child.pid_term();
+        child.exit_normal();
+
+        assert!(contains(result(&arc_process), pid));
+
+        registry::remove_pid_to_process(&child);
+
+        assert!(!contains(result(&arc_process), pid));
+    });
+}
+
+fn contains(processes: Term, pid: Term) -> bool {
+    match processes.decode().unwrap() {
+        TypedTerm::Nil => false,
+        TypedTerm::List(cons) => cons.contains(pid),
+        typed_term => panic!("Wrong TypedTerm ({:?})", typed_term),
+    }
+}
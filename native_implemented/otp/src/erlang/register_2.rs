@@ -21,14 +21,15 @@ pub fn result(arc_process: Arc<Process>, name: Term, pid_or_port: Term) -> excep
             match pid_or_port.decode()? {
                 TypedTerm::Pid(pid) => {
                     match registry::pid_to_self_or_process(pid, &arc_process) {
-                        Some(pid_arc_process) => {
+                        // An exiting process is about to be unregistered by `propagate_exit`
+                        Some(pid_arc_process) if !pid_arc_process.is_exiting() => {
                             if registry::put_atom_to_process(atom, pid_arc_process) {
                                 Ok(true.into())
                             } else {
                                 Err(anyhow!("{} could not be registered as {}.  It may already be registered.", pid, atom).into())
                             }
                         }
-                        None => Err(anyhow!("{} is not a pid of an alive process", pid).into()),
+                        _ => Err(anyhow!("{} is not a pid of an alive process", pid).into()),
                     }
                 }
                 TypedTerm::ExternalPid(_) => Err(anyhow!(
@@ -1,12 +1,16 @@
 mod with_atom_name;
 
 use std::convert::TryInto;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::thread;
 
 use proptest::strategy::Just;
 
+use liblumen_alloc::erts::process::trace::Trace;
 use liblumen_alloc::erts::term::prelude::{Atom, Encoded, Pid};
 
+use crate::runtime::process::propagate_exit;
 use crate::runtime::registry;
 
 use crate::erlang;
@@ -54,3 +54,91 @@ fn with_different_process() {
         );
     });
 }
+
+#[test]
+fn with_exiting_process_errors_badarg() {
+    with_process_arc(|process_arc| {
+        let exiting_process_arc = test::process::child(&process_arc);
+        exiting_process_arc.exit(Atom::str_to_term("abnormal"), Trace::capture(), None);
+        let pid_or_port = exiting_process_arc.pid_term();
+
+        assert_badarg!(
+            result(process_arc, registered_name(), pid_or_port),
+            format!("{} is not a pid of an alive process", pid_or_port)
+        );
+        assert_eq!(*exiting_process_arc.registered_name.read(), None);
+    });
+}
+
+#[test]
+fn when_process_exits_name_is_unregistered() {
+    with_process_arc(|process_arc| {
+        let name = registered_name();
+        let name_atom: Atom = name.try_into().unwrap();
+
+        let exiting_process_arc = test::process::child(&process_arc);
+
+        assert_eq!(
+            result(process_arc.clone(), name, exiting_process_arc.pid_term()),
+            Ok(true.into())
+        );
+
+        propagate_exit(&exiting_process_arc, None);
+
+        assert_eq!(registry::atom_to_process(&name_atom), None);
+        assert_eq!(*exiting_process_arc.registered_name.read(), None);
+
+        // the name can be reused
+        assert_eq!(
+            result(process_arc.clone(), name, process_arc.pid_term()),
+            Ok(true.into())
+        );
+        assert_eq!(registry::atom_to_process(&name_atom), Some(process_arc));
+    });
+}
+
+#[test]
+fn with_processes_racing_for_the_same_name_only_one_is_registered_at_a_time() {
+    const PROCESSES: usize = 16;
+    const ATTEMPTS_PER_PROCESS: usize = 1_000;
+
+    with_process_arc(|process_arc| {
+        let name = registered_name();
+        let name_atom: Atom = name.try_into().unwrap();
+        let holder_count = Arc::new(AtomicUsize::new(0));
+        let registration_count = Arc::new(AtomicUsize::new(0));
+
+        let threads: Vec<_> = (0..PROCESSES)
+            .map(|_| {
+                let racing_process_arc = test::process::child(&process_arc);
+                let holder_count = holder_count.clone();
+                let registration_count = registration_count.clone();
+
+                thread::spawn(move || {
+                    for _ in 0..ATTEMPTS_PER_PROCESS {
+                        let pid_or_port = racing_process_arc.pid_term();
+
+                        if result(racing_process_arc.clone(), name, pid_or_port).is_ok() {
+                            assert_eq!(holder_count.fetch_add(1, Ordering::SeqCst), 0);
+                            assert_eq!(
+                                registry::atom_to_process(&name_atom),
+                                Some(racing_process_arc.clone())
+                            );
+                            registration_count.fetch_add(1, Ordering::SeqCst);
+                            holder_count.fetch_sub(1, Ordering::SeqCst);
+
+                            assert_eq!(erlang::unregister_1::result(name), Ok(true.into()));
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert!(0 < registration_count.load(Ordering::SeqCst));
+        assert_eq!(registry::atom_to_process(&name_atom), None);
+    });
+}
@@ -0,0 +1,30 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::suspend_process_1::other_process;
+use crate::runtime::scheduler::Scheduled;
+
+/// Undoes one `erlang:suspend_process/1` of `pid`, which runs again once it has been resumed as
+/// many times as it was suspended.
+#[native_implemented::function(erlang:resume_process/1)]
+pub fn result(process: &Process, pid: Term) -> exception::Result<Term> {
+    let pid_arc_process = other_process(process, pid)?;
+
+    match pid_arc_process.resume() {
+        Some(0) => {
+            if let Some(scheduler) = pid_arc_process.scheduler() {
+                scheduler.resume(&pid_arc_process);
+            }
+
+            Ok(true.into())
+        }
+        Some(_) => Ok(true.into()),
+        None => Err(anyhow!("pid ({}) is not suspended", pid).into()),
+    }
+}
@@ -0,0 +1,55 @@
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::resume_process_1::result;
+use crate::erlang::suspend_process_1;
+use crate::test::{self, with_process, with_process_arc};
+
+#[test]
+fn with_self_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(
+            result(process, process.pid_term()),
+            "is the calling process"
+        );
+    });
+}
+
+#[test]
+fn with_non_existent_pid_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(result(process, Pid::next_term()), "is not alive");
+    });
+}
+
+#[test]
+fn without_suspended_process_errors_badarg() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = test::process::child(&arc_process);
+
+        assert_badarg!(
+            result(&arc_process, other_arc_process.pid_term()),
+            "is not suspended"
+        );
+    });
+}
+
+#[test]
+fn with_process_suspended_twice_stays_suspended_until_resumed_twice() {
+    with_process_arc(|arc_process| {
+        let other_arc_process = test::process::child(&arc_process);
+        let pid = other_arc_process.pid_term();
+
+        for _ in 0..2 {
+            assert_eq!(
+                suspend_process_1::result(&arc_process, pid),
+                Ok(true.into())
+            );
+        }
+
+        assert_eq!(result(&arc_process, pid), Ok(true.into()));
+        assert!(other_arc_process.is_suspended());
+
+        assert_eq!(result(&arc_process, pid), Ok(true.into()));
+        assert!(!other_arc_process.is_suspended());
+    });
+}
@@ -1,4 +1,5 @@
 mod with_atom_destination;
+mod with_external_pid_destination;
 mod with_local_pid_destination;
 mod with_tuple_destination;
 
@@ -11,7 +12,10 @@ use liblumen_alloc::erts::term::prelude::*;
 
 use crate::erlang;
 use crate::erlang::send_2::result;
+use crate::runtime::distribution::external_term_format::encode::term_to_byte_vec;
+use crate::runtime::distribution::transport;
 use crate::test;
+use crate::test::distribution::{with_transport, MockTransport};
 use crate::test::{
     external_arc_node, has_heap_message, has_process_message, registered_name, strategy,
     with_process, with_process_arc,
@@ -0,0 +1,78 @@
+use super::*;
+
+#[test]
+fn without_transport_drops_message_and_returns_message() {
+    with_transport(None, || {
+        with_process(|process| {
+            let destination = process.external_pid(external_arc_node(), 1, 2).unwrap();
+            let message = Atom::str_to_term("message");
+
+            assert_eq!(result(process, destination, message), Ok(message));
+            assert!(transport::connected().is_empty());
+        });
+    });
+}
+
+#[test]
+fn without_reachable_node_drops_message_and_returns_message() {
+    let mock_transport = MockTransport::new(None);
+
+    with_transport(Some(mock_transport.clone()), || {
+        with_process(|process| {
+            let destination = process.external_pid(external_arc_node(), 1, 2).unwrap();
+            let message = Atom::str_to_term("message");
+
+            assert_eq!(result(process, destination, message), Ok(message));
+            assert!(transport::connected().is_empty());
+        });
+    });
+
+    assert!(mock_transport.sent().is_empty());
+}
+
+#[test]
+fn with_transport_sends_control_message_and_payload_and_returns_message() {
+    let arc_node = external_arc_node();
+    let mock_transport = MockTransport::new(Some(arc_node.creation()));
+
+    with_transport(Some(mock_transport.clone()), || {
+        with_process(|process| {
+            let destination = process.external_pid(arc_node.clone(), 1, 2).unwrap();
+            let message = Atom::str_to_term("message");
+
+            assert_eq!(result(process, destination, message), Ok(message));
+            assert_eq!(transport::connected(), vec![arc_node.clone()]);
+
+            let sent = mock_transport.sent();
+
+            assert_eq!(sent.len(), 1);
+            assert_eq!(sent[0].node, arc_node);
+
+            // {2, '', destination}
+            let mut control_message = vec![
+                VERSION_NUMBER,
+                SMALL_TUPLE_EXT,
+                3,
+                SMALL_INTEGER_EXT,
+                2,
+                ATOM_EXT,
+                0,
+                0,
+            ];
+            control_message.extend_from_slice(&term_to_byte_vec(destination)[1..]);
+
+            assert_eq!(sent[0].control_message, control_message);
+
+            let mut payload = vec![VERSION_NUMBER, ATOM_EXT, 0, 7];
+            payload.extend_from_slice(b"message");
+
+            assert_eq!(sent[0].payload, payload);
+        });
+    });
+}
+
+const VERSION_NUMBER: u8 = 131;
+
+const SMALL_INTEGER_EXT: u8 = 97;
+const ATOM_EXT: u8 = 100;
+const SMALL_TUPLE_EXT: u8 = 104;
@@ -0,0 +1,48 @@
+use super::*;
+
+#[test]
+fn without_transport_drops_message_and_returns_message() {
+    with_transport(None, || {
+        with_process(|process| {
+            let name = registered_name();
+            let destination =
+                process.tuple_from_slice(&[name, external_arc_node().name().encode().unwrap()]);
+            let message = Atom::str_to_term("message");
+
+            assert_eq!(result(process, destination, message), Ok(message));
+            assert!(transport::connected().is_empty());
+        });
+    });
+}
+
+#[test]
+fn with_transport_sends_reg_send_control_message_and_payload_and_returns_message() {
+    let arc_node = external_arc_node();
+    let mock_transport = MockTransport::new(Some(arc_node.creation()));
+
+    with_transport(Some(mock_transport.clone()), || {
+        with_process(|process| {
+            let name = registered_name();
+            let destination = process.tuple_from_slice(&[name, arc_node.name().encode().unwrap()]);
+            let message = Atom::str_to_term("message");
+
+            assert_eq!(result(process, destination, message), Ok(message));
+
+            let sent = mock_transport.sent();
+
+            assert_eq!(sent.len(), 1);
+            assert_eq!(sent[0].node, arc_node);
+
+            // {6, FromPid, '', name}
+            let control_message = process.tuple_from_slice(&[
+                process.integer(6),
+                process.pid_term(),
+                Atom::str_to_term(""),
+                name,
+            ]);
+
+            assert_eq!(sent[0].control_message, term_to_byte_vec(control_message));
+            assert_eq!(sent[0].payload, term_to_byte_vec(message));
+        });
+    });
+}
@@ -1,5 +1,10 @@
 use super::*;
 
+use liblumen_alloc::erts::process::Status;
+
+use crate::runtime::scheduler;
+use crate::runtime::time::monotonic;
+
 #[test]
 fn with_different_process_sends_message_when_timer_expires() {
     run!(
@@ -115,3 +120,44 @@ fn without_process_sends_nothing_when_timer_expires() {
         },
     );
 }
+
+#[test]
+fn with_waiting_destination_message_arrives_only_after_scheduler_times_out_timer() {
+    crate::test::with_process_arc(|arc_process| {
+        let destination_arc_process = test::process::child(&arc_process);
+
+        // Runs until it waits for a message
+        assert!(scheduler::run_through(&destination_arc_process));
+        assert!(matches!(
+            *destination_arc_process.status.read(),
+            Status::Waiting
+        ));
+
+        let milliseconds = Milliseconds(100);
+        let message = Atom::str_to_term("message");
+        let start_monotonic = monotonic::freeze();
+
+        result(
+            arc_process.clone(),
+            arc_process.integer(milliseconds),
+            destination_arc_process.pid_term(),
+            message,
+        )
+        .unwrap();
+
+        // Each run of the scheduler times out the timers that expired since the last one
+        monotonic::freeze_at(start_monotonic + milliseconds - Milliseconds(1));
+        scheduler::current().run_once();
+
+        assert!(!has_message(&destination_arc_process, message));
+        assert!(matches!(
+            *destination_arc_process.status.read(),
+            Status::Waiting
+        ));
+
+        monotonic::freeze_at(start_monotonic + milliseconds + Milliseconds(1));
+
+        assert!(scheduler::run_through(&destination_arc_process));
+        assert!(has_message(&destination_arc_process, message));
+    });
+}
@@ -7,6 +7,7 @@ use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 
 use crate::runtime::process::spawn::options::Options;
+use crate::runtime::process::spawn::SpawnError;
 use crate::runtime::scheduler::Scheduled;
 
 pub(in crate::erlang) fn result(
@@ -20,7 +21,7 @@ pub(in crate::erlang) fn result(
 
     process
         .scheduler()
-        .unwrap()
+        .ok_or(SpawnError::ParentExiting)?
         .spawn_closure(Some(process), boxed_closure, options)
         .map(|spawned| spawned.to_term(process))
         .map_err(From::from)
@@ -4,6 +4,7 @@ use liblumen_alloc::erts::term::prelude::*;
 
 use crate::erlang::apply::arguments_term_to_vec;
 use crate::runtime::process::spawn::options::Options;
+use crate::runtime::process::spawn::SpawnError;
 use crate::runtime::scheduler::Scheduled;
 
 pub(in crate::erlang) fn result(
@@ -19,7 +20,7 @@ pub(in crate::erlang) fn result(
 
     process
         .scheduler()
-        .unwrap()
+        .ok_or(SpawnError::ParentExiting)?
         .spawn_module_function_arguments(
             Some(process),
             module_atom,
@@ -1,11 +1,16 @@
 mod with_function;
 
+use std::convert::TryInto;
+use std::sync::atomic::Ordering;
+
 use proptest::strategy::Just;
 
 use liblumen_alloc::atom;
+use liblumen_alloc::erts::process::priority::Priority;
 use liblumen_alloc::erts::term::prelude::*;
 
 use crate::erlang::spawn_opt_2::result;
+use crate::runtime::registry::pid_to_process;
 use crate::test::*;
 
 #[test]
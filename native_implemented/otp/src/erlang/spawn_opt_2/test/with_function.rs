@@ -24,3 +24,76 @@ fn without_proper_list_options_errors_badarg() {
         },
     );
 }
+
+#[test]
+fn with_unsupported_option_errors_badarg_naming_option() {
+    with_process(|process| {
+        let function = anonymous_0::anonymous_closure(process);
+
+        for option in [
+            atom!("unsupported"),
+            process.tuple_from_slice(&[atom!("priority")]),
+            process.tuple_from_slice(&[atom!("priority"), atom!("urgent")]),
+            process.tuple_from_slice(&[atom!("min_heap_size"), process.integer(-1)]),
+            process.tuple_from_slice(&[atom!("unsupported"), process.integer(1)]),
+            process.integer(1),
+        ] {
+            let options = process.list_from_slice(&[atom!("link"), option]);
+
+            assert_badarg!(
+                result(process, function, options),
+                format!("option ({}) is invalid", option)
+            );
+        }
+    });
+}
+
+#[test]
+fn with_high_priority_in_options_list_child_runs_before_normal_priority_siblings() {
+    with_process_arc(|arc_process| {
+        let scheduler = arc_process.scheduler().unwrap();
+        let high_run_queue_len_before = scheduler.run_queue_len(Priority::High);
+        let normal_run_queue_len_before = scheduler.run_queue_len(Priority::Normal);
+
+        let normal_priority = arc_process.list_from_slice(&[arc_process
+            .tuple_from_slice(&[atom!("priority"), atom!("normal")])]);
+        let normal_pid = result(
+            &arc_process,
+            anonymous_0::anonymous_closure(&arc_process),
+            normal_priority,
+        )
+        .unwrap();
+
+        let high_priority = arc_process
+            .list_from_slice(&[arc_process.tuple_from_slice(&[atom!("priority"), atom!("high")])]);
+        let high_pid = result(
+            &arc_process,
+            anonymous_0::anonymous_closure(&arc_process),
+            high_priority,
+        )
+        .unwrap();
+
+        assert_eq!(
+            scheduler.run_queue_len(Priority::High),
+            high_run_queue_len_before + 1
+        );
+        assert_eq!(
+            scheduler.run_queue_len(Priority::Normal),
+            normal_run_queue_len_before + 1
+        );
+
+        let normal_pid: Pid = normal_pid.decode().unwrap().try_into().unwrap();
+        let normal_arc_process = pid_to_process(&normal_pid).unwrap();
+        let high_pid: Pid = high_pid.decode().unwrap().try_into().unwrap();
+        let high_arc_process = pid_to_process(&high_pid).unwrap();
+
+        assert!(scheduler.run_once());
+
+        assert_eq!(
+            scheduler.run_queue_len(Priority::High),
+            high_run_queue_len_before
+        );
+        assert!(0 < high_arc_process.total_reductions.load(Ordering::SeqCst));
+        assert_eq!(normal_arc_process.total_reductions.load(Ordering::SeqCst), 0);
+    });
+}
@@ -149,3 +149,32 @@ fn without_process_sends_nothing_when_timer_expires() {
         )
         .unwrap();
 }
+
+#[test]
+fn with_future_time_sends_message_at_absolute_time_regardless_of_when_timer_was_started() {
+    crate::test::with_process_arc(|arc_process| {
+        let start_monotonic = freeze_timeout();
+        let absolute_monotonic = start_monotonic + Milliseconds(100);
+
+        // Started half-way to the absolute time, which a relative timer would add to
+        freeze_at_timeout(start_monotonic + Milliseconds(50));
+
+        let time = arc_process.integer(absolute_monotonic.0);
+        let destination = arc_process.pid_term();
+        let message = Atom::str_to_term("message");
+        let options = options(&arc_process);
+
+        let timer_reference =
+            result(arc_process.clone(), time, destination, message, options).unwrap();
+        let timeout_message =
+            arc_process.tuple_from_slice(&[Atom::str_to_term("timeout"), timer_reference, message]);
+
+        freeze_at_timeout(absolute_monotonic - Milliseconds(1));
+
+        assert!(!has_message(&arc_process, timeout_message));
+
+        freeze_at_timeout(absolute_monotonic + Milliseconds(1));
+
+        assert!(has_message(&arc_process, timeout_message));
+    });
+}
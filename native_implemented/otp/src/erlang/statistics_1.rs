@@ -0,0 +1,142 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use anyhow::*;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::{gc, system_reductions, Priority, Process};
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::time::Milliseconds;
+
+use crate::runtime::scheduler::{self, SchedulerStats, PRIORITIES};
+use crate::runtime::sys::io;
+use crate::runtime::time::monotonic;
+
+#[native_implemented::function(erlang:statistics/1)]
+pub fn result(process: &Process, item: Term) -> exception::Result<Term> {
+    let item_atom = term_try_into_atom!(item)?;
+
+    match item_atom.name() {
+        "context_switches" => {
+            let total: u64 = scheduler::all()
+                .iter()
+                .map(|scheduler| scheduler.counters().context_switches())
+                .sum();
+
+            Ok(process.tuple_from_slice(&[process.integer(total), process.integer(0)]))
+        }
+        "garbage_collection" => {
+            let (collections, words_reclaimed) = gc::statistics();
+
+            Ok(process.tuple_from_slice(&[
+                process.integer(collections),
+                process.integer(words_reclaimed),
+                process.integer(0),
+            ]))
+        }
+        "io" => {
+            let (input, output) = io::counters();
+
+            Ok(process.tuple_from_slice(&[
+                process.tuple_from_slice(&[atom!("input"), process.integer(input)]),
+                process.tuple_from_slice(&[atom!("output"), process.integer(output)]),
+            ]))
+        }
+        // Unlike in BEAM, the counters aren't the time spent in each state, but what
+        // `scheduler::stats` counts, and they are always enabled
+        "microstate_accounting" => {
+            let schedulers: Vec<Term> = scheduler::stats()
+                .iter()
+                .map(|stats| microstate_accounting(process, stats))
+                .collect();
+
+            Ok(process.list_from_slice(&schedulers))
+        }
+        "reductions" => {
+            // The current run of the calling process isn't counted in the system total yet
+            let total = system_reductions() + process.run_reductions();
+
+            Ok(total_and_since_last(process, item_atom, total))
+        }
+        "run_queue" => {
+            let len: usize = PRIORITIES.iter().map(|priority| run_queue_len(*priority)).sum();
+
+            Ok(process.integer(len))
+        }
+        "run_queue_lengths" => {
+            let lens: Vec<Term> = PRIORITIES
+                .iter()
+                .map(|priority| process.integer(run_queue_len(*priority)))
+                .collect();
+
+            Ok(process.list_from_slice(&lens))
+        }
+        "runtime" => {
+            // There is no portable way to get the CPU time of the scheduler threads, so the time
+            // spent running processes is used instead
+            let total = scheduler::busy_time().as_millis() as u64;
+
+            Ok(total_and_since_last(process, item_atom, total))
+        }
+        "wall_clock" => {
+            let total: u64 = Milliseconds::from(monotonic::time()).into();
+
+            Ok(total_and_since_last(process, item_atom, total))
+        }
+        _ => Err(anyhow!(
+            "item ({}) is not a supported atom (context_switches, garbage_collection, io, microstate_accounting, reductions, run_queue, run_queue_lengths, runtime, or wall_clock)",
+            item
+        )
+        .into()),
+    }
+}
+
+/// `#{type => scheduler, id => Id, counters => Counters}` for the scheduler `stats` are for
+fn microstate_accounting(process: &Process, stats: &SchedulerStats) -> Term {
+    let id: u32 = stats.id.into();
+    let run_queue_lengths: Vec<Term> = stats
+        .run_queue_lengths
+        .iter()
+        .map(|len| process.integer(*len))
+        .collect();
+    let counters = process.map_from_slice(&[
+        (
+            atom!("run_queue_lengths"),
+            process.list_from_slice(&run_queue_lengths),
+        ),
+        (
+            atom!("runs_this_second"),
+            process.integer(stats.runs_this_second),
+        ),
+        (atom!("reductions"), process.integer(stats.reductions)),
+        (
+            atom!("context_switches"),
+            process.integer(stats.context_switches),
+        ),
+        (atom!("steals"), process.integer(stats.steals)),
+        (atom!("stolen"), process.integer(stats.stolen)),
+        (atom!("parks"), process.integer(stats.parks)),
+        (atom!("unparks"), process.integer(stats.unparks)),
+    ]);
+
+    process.map_from_slice(&[
+        (atom!("type"), atom!("scheduler")),
+        (atom!("id"), process.integer(id as u64)),
+        (atom!("counters"), counters),
+    ])
+}
+
+/// The length of the run queues for `priority` across all schedulers
+fn run_queue_len(priority: Priority) -> usize {
+    scheduler::all()
+        .iter()
+        .map(|scheduler| scheduler.run_queue_len(priority))
+        .sum()
+}
+
+fn total_and_since_last(process: &Process, kind: Atom, total: u64) -> Term {
+    let since_last = process.statistics_since_last(kind, total);
+
+    process.tuple_from_slice(&[process.integer(total), process.integer(since_last)])
+}
@@ -0,0 +1,243 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::scheduler::ID;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::statistics_1::result;
+use crate::file::write_file_2;
+use crate::runtime::scheduler::{self, Scheduled, Spawned};
+use crate::test::{self, busy_loop_0, with_process, with_process_arc};
+
+#[test]
+fn without_supported_atom_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(
+            result(process, Atom::str_to_term("unsupported")),
+            "item (unsupported) is not a supported atom"
+        );
+    });
+}
+
+#[test]
+fn with_reductions_second_call_has_smaller_since_last_call() {
+    with_process_arc(|arc_process| {
+        for _ in 0..100 {
+            arc_process.reduce();
+        }
+
+        let (first_total, first_since_last) =
+            total_and_since_last(result(&arc_process, Atom::str_to_term("reductions")).unwrap());
+
+        assert!(100 <= first_since_last);
+
+        let (second_total, second_since_last) =
+            total_and_since_last(result(&arc_process, Atom::str_to_term("reductions")).unwrap());
+
+        assert!(first_total <= second_total);
+        assert!(
+            second_since_last < first_since_last,
+            "second since last call ({}) is not less than first ({})",
+            second_since_last,
+            first_since_last
+        );
+    });
+}
+
+#[test]
+fn with_reductions_total_increases_while_a_busy_process_runs() {
+    with_process_arc(|arc_process| {
+        let Spawned {
+            arc_process: busy_arc_process,
+            ..
+        } = arc_process
+            .scheduler()
+            .unwrap()
+            .spawn_module_function_arguments(
+                Some(&arc_process),
+                test::module(),
+                busy_loop_0::function(),
+                vec![],
+                Default::default(),
+            )
+            .unwrap();
+
+        let (first_total, _) =
+            total_and_since_last(result(&arc_process, Atom::str_to_term("reductions")).unwrap());
+        let (first_context_switches, _) = total_and_since_last(
+            result(&arc_process, Atom::str_to_term("context_switches")).unwrap(),
+        );
+
+        assert!(scheduler::run_through(&busy_arc_process));
+
+        let (second_total, second_since_last) =
+            total_and_since_last(result(&arc_process, Atom::str_to_term("reductions")).unwrap());
+
+        assert!(
+            first_total < second_total,
+            "total reductions ({}) did not increase from ({}) while a busy process ran",
+            second_total,
+            first_total
+        );
+        assert!(busy_arc_process.reductions() as usize <= second_since_last);
+
+        let (second_context_switches, _) = total_and_since_last(
+            result(&arc_process, Atom::str_to_term("context_switches")).unwrap(),
+        );
+
+        assert!(first_context_switches < second_context_switches);
+    });
+}
+
+#[test]
+fn since_last_call_is_tracked_separately_for_each_item() {
+    with_process(|process| {
+        result(process, Atom::str_to_term("reductions")).unwrap();
+
+        // `wall_clock` hasn't been called before, so all the time since the system started is
+        // since the last call
+        let (total, since_last) =
+            total_and_since_last(result(process, Atom::str_to_term("wall_clock")).unwrap());
+
+        assert_eq!(since_last, total);
+    });
+}
+
+#[test]
+fn with_run_queue_lengths_returns_a_length_for_each_priority() {
+    with_process(|process| {
+        let lengths = result(process, Atom::str_to_term("run_queue_lengths")).unwrap();
+        let boxed_cons: Boxed<Cons> = lengths.try_into().unwrap();
+
+        assert_eq!(boxed_cons.into_iter().count(), 4);
+    });
+}
+
+#[test]
+fn with_microstate_accounting_counters_increase_while_a_busy_process_runs() {
+    with_process_arc(|arc_process| {
+        let Spawned {
+            arc_process: busy_arc_process,
+            ..
+        } = arc_process
+            .scheduler()
+            .unwrap()
+            .spawn_module_function_arguments(
+                Some(&arc_process),
+                test::module(),
+                busy_loop_0::function(),
+                vec![],
+                Default::default(),
+            )
+            .unwrap();
+        let id = busy_arc_process.scheduler_id().unwrap();
+
+        let before = scheduler_counters(&arc_process, id);
+        assert!(scheduler::run_through(&busy_arc_process));
+        let after = scheduler_counters(&arc_process, id);
+
+        for counter in &["reductions", "context_switches"] {
+            let counter = Atom::str_to_term(counter);
+            let before: usize = before.get(counter).unwrap().try_into().unwrap();
+            let after: usize = after.get(counter).unwrap().try_into().unwrap();
+
+            assert!(
+                before < after,
+                "{} ({}) did not increase from ({}) while a busy process ran",
+                counter,
+                after,
+                before
+            );
+        }
+
+        let run_queue_lengths: Boxed<Cons> = after
+            .get(Atom::str_to_term("run_queue_lengths"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert_eq!(run_queue_lengths.into_iter().count(), 4);
+    });
+}
+
+#[test]
+fn with_io_output_increases_after_write_file() {
+    with_process(|process| {
+        let (_, output_before) = io(result(process, Atom::str_to_term("io")).unwrap());
+
+        let path = std::env::temp_dir().join(format!("statistics_1_io_{}", process.pid()));
+        let filename = process.binary_from_str(path.to_str().unwrap());
+        let bytes = process.binary_from_str("hello");
+
+        assert_eq!(
+            write_file_2::result(process, filename, bytes),
+            Ok(Atom::str_to_term("ok"))
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let (_, output_after) = io(result(process, Atom::str_to_term("io")).unwrap());
+
+        assert!(output_before + 5 <= output_after);
+    });
+}
+
+fn total_and_since_last(tuple: Term) -> (usize, usize) {
+    let boxed_tuple: Boxed<Tuple> = tuple.try_into().unwrap();
+
+    assert_eq!(boxed_tuple.len(), 2);
+
+    (
+        boxed_tuple[0].try_into().unwrap(),
+        boxed_tuple[1].try_into().unwrap(),
+    )
+}
+
+/// The `counters` of the scheduler with `id` in `statistics(microstate_accounting)`
+fn scheduler_counters(process: &Process, id: ID) -> Boxed<Map> {
+    let id: u32 = id.into();
+    let schedulers: Boxed<Cons> = result(process, Atom::str_to_term("microstate_accounting"))
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    let scheduler: Boxed<Map> = schedulers
+        .into_iter()
+        .map(|scheduler| {
+            let scheduler: Boxed<Map> = scheduler.unwrap().try_into().unwrap();
+            scheduler
+        })
+        .find(|scheduler| {
+            let scheduler_id: usize = scheduler
+                .get(Atom::str_to_term("id"))
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+            scheduler_id == id as usize
+        })
+        .unwrap();
+
+    assert_eq!(
+        scheduler.get(Atom::str_to_term("type")),
+        Some(Atom::str_to_term("scheduler"))
+    );
+
+    scheduler
+        .get(Atom::str_to_term("counters"))
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+fn io(tuple: Term) -> (usize, usize) {
+    let boxed_tuple: Boxed<Tuple> = tuple.try_into().unwrap();
+    let counter = |index: usize, tag: &str| -> usize {
+        let boxed_counter: Boxed<Tuple> = boxed_tuple[index].try_into().unwrap();
+
+        assert_eq!(boxed_counter[0], Atom::str_to_term(tag));
+
+        boxed_counter[1].try_into().unwrap()
+    };
+
+    (counter(0, "input"), counter(1, "output"))
+}
@@ -0,0 +1,37 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::sync::Arc;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::runtime::registry::pid_to_process;
+
+/// Suspends `pid`, which isn't run again until `erlang:resume_process/1` has resumed it as many
+/// times as it was suspended.  It still receives messages and signals, so it can be exited while
+/// suspended.
+#[native_implemented::function(erlang:suspend_process/1)]
+pub fn result(process: &Process, pid: Term) -> exception::Result<Term> {
+    let pid_arc_process = other_process(process, pid)?;
+    pid_arc_process.suspend();
+
+    Ok(true.into())
+}
+
+/// The process `pid`, which can't be `process` itself, as a process can't suspend or resume itself
+pub(crate) fn other_process(process: &Process, pid: Term) -> exception::Result<Arc<Process>> {
+    let pid_pid: Pid = term_try_into_local_pid!(pid)?;
+
+    if pid_pid == process.pid() {
+        Err(anyhow!("pid ({}) is the calling process", pid).into())
+    } else {
+        match pid_to_process(&pid_pid) {
+            Some(pid_arc_process) if !pid_arc_process.is_exiting() => Ok(pid_arc_process),
+            _ => Err(anyhow!("pid ({}) is not alive", pid).into()),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::suspend_process_1::result;
+use crate::erlang::{exit_2, resume_process_1, send_2};
+use crate::runtime::scheduler::{self, Scheduled, Spawned};
+use crate::test::{self, busy_loop_0, with_process, with_process_arc};
+
+#[test]
+fn without_pid_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(
+            result(process, Atom::str_to_term("not_a_pid")),
+            "pid (not_a_pid) is not a pid"
+        );
+    });
+}
+
+#[test]
+fn with_self_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(
+            result(process, process.pid_term()),
+            "is the calling process"
+        );
+        assert!(!process.is_suspended());
+    });
+}
+
+#[test]
+fn with_non_existent_pid_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(result(process, Pid::next_term()), "is not alive");
+    });
+}
+
+#[test]
+fn with_busy_process_stops_running_until_resumed() {
+    with_process_arc(|arc_process| {
+        let busy_arc_process = busy(&arc_process);
+        assert!(scheduler::run_through(&busy_arc_process));
+
+        assert_eq!(
+            result(&arc_process, busy_arc_process.pid_term()),
+            Ok(true.into())
+        );
+
+        let reductions_suspended = busy_arc_process.reductions();
+        run_others(&arc_process);
+        assert_eq!(busy_arc_process.reductions(), reductions_suspended);
+
+        assert_eq!(
+            resume_process_1::result(&arc_process, busy_arc_process.pid_term()),
+            Ok(true.into())
+        );
+        assert!(scheduler::run_through(&busy_arc_process));
+        assert!(reductions_suspended < busy_arc_process.reductions());
+    });
+}
+
+#[test]
+fn with_suspended_process_still_receives_messages() {
+    with_process_arc(|arc_process| {
+        let busy_arc_process = busy(&arc_process);
+
+        assert_eq!(
+            result(&arc_process, busy_arc_process.pid_term()),
+            Ok(true.into())
+        );
+        let message = Atom::str_to_term("message");
+        assert_eq!(
+            send_2::result(&arc_process, busy_arc_process.pid_term(), message),
+            Ok(message)
+        );
+        run_others(&arc_process);
+
+        assert_eq!(busy_arc_process.message_queue_len(), 1);
+        assert_eq!(busy_arc_process.reductions(), 0);
+    });
+}
+
+#[test]
+fn with_suspended_process_exits_when_exit_signalled() {
+    with_process_arc(|arc_process| {
+        let busy_arc_process = busy(&arc_process);
+
+        assert_eq!(
+            result(&arc_process, busy_arc_process.pid_term()),
+            Ok(true.into())
+        );
+        run_others(&arc_process);
+
+        let reason = Atom::str_to_term("shutdown");
+        assert_eq!(
+            exit_2::result(&arc_process, busy_arc_process.pid_term(), reason),
+            Ok(true.into())
+        );
+        run_others(&arc_process);
+
+        match *busy_arc_process.status.read() {
+            Status::RuntimeException(ref exception) => assert_eq!(exception.reason(), reason),
+            ref status => panic!("suspended process did not exit ({:?})", status),
+        };
+    });
+}
+
+fn busy(parent: &Process) -> Arc<Process> {
+    let Spawned { arc_process, .. } = parent
+        .scheduler()
+        .unwrap()
+        .spawn_module_function_arguments(
+            Some(parent),
+            test::module(),
+            busy_loop_0::function(),
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+
+    arc_process
+}
+
+/// Runs the scheduler of `process` enough times that every process in its run queues has been
+/// dequeued
+fn run_others(process: &Process) {
+    let scheduler = process.scheduler().unwrap();
+
+    for _ in 0..8 {
+        scheduler.run_once();
+    }
+}
@@ -1,17 +1,29 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::env;
+use std::mem;
+use std::thread;
+
 use anyhow::*;
 
+use liblumen_alloc::atom;
 use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::atom::{atom_count, MAX_ATOMS};
 use liblumen_alloc::erts::term::prelude::*;
 
+use crate::runtime::{registry, scheduler};
+
 #[native_implemented::function(erlang:system_info/1)]
-pub fn result(item: Term) -> exception::Result<Term> {
+pub fn result(process: &Process, item: Term) -> exception::Result<Term> {
     match item.decode().unwrap() {
         TypedTerm::Atom(atom) => match atom.name() {
             "alloc_util_allocators" => unimplemented!(),
             "allocated_areas" => unimplemented!(),
             "allocator" => unimplemented!(),
-            "atom_count" => unimplemented!(),
-            "atom_limit" => unimplemented!(),
+            "atom_count" => Ok(process.integer(atom_count())),
+            "atom_limit" => Ok(process.integer(MAX_ATOMS)),
             "build_type" => unimplemented!(),
             "c_compiler_used" => unimplemented!(),
             "check_io" => unimplemented!(),
@@ -43,6 +55,10 @@ pub fn result(item: Term) -> exception::Result<Term> {
             "loaded" => unimplemented!(),
             "logic_processors" => unimplemented!(),
             "logic_processors_available" => unimplemented!(),
+            "logical_processors" => match thread::available_parallelism() {
+                Ok(count) => Ok(process.integer(count.get())),
+                Err(_) => Ok(atom!("unknown")),
+            },
             "logical_processors_online" => unimplemented!(),
             "machine" => unimplemented!(),
             "max_heap_size" => unimplemented!(),
@@ -56,22 +72,22 @@ pub fn result(item: Term) -> exception::Result<Term> {
             "normal_multi_scheduling_blockers" => unimplemented!(),
             "os_monotonic_time_source" => unimplemented!(),
             "os_system_time_source" => unimplemented!(),
-            "otp_release" => unimplemented!(),
+            "otp_release" => Ok(process.charlist_from_str(OTP_RELEASE)),
             "port_count" => unimplemented!(),
             "port_limit" => unimplemented!(),
             "port_parallelism" => unimplemented!(),
-            "process_count" => unimplemented!(),
-            "process_limit" => unimplemented!(),
+            "process_count" => Ok(process.integer(registry::pid_count())),
+            "process_limit" => Ok(process.integer(registry::process_limit())),
             "procs" => unimplemented!(),
             "scheduler_bind_type" => unimplemented!(),
             "scheduler_bindings" => unimplemented!(),
             "scheduler_id" => unimplemented!(),
-            "schedulers" => unimplemented!(),
-            "schedulers_online" => unimplemented!(),
+            "schedulers" => Ok(process.integer(scheduler::all().len())),
+            "schedulers_online" => Ok(process.integer(scheduler::all().len())),
             "sequential_tracer" => unimplemented!(),
             "smp_support" => unimplemented!(),
             "start_time" => unimplemented!(),
-            "system_architecture" => unimplemented!(),
+            "system_architecture" => Ok(process.charlist_from_str(&system_architecture())),
             "system_logger" => unimplemented!(),
             "system_version" => unimplemented!(),
             "thread_pool_size" => unimplemented!(),
@@ -82,8 +98,8 @@ pub fn result(item: Term) -> exception::Result<Term> {
             "tolerant_timeofday" => unimplemented!(),
             "trace_control_word" => unimplemented!(),
             "update_cpu_info" => unimplemented!(),
-            "version" => unimplemented!(),
-            "wordsize" => unimplemented!(),
+            "version" => Ok(process.charlist_from_str(env!("CARGO_PKG_VERSION"))),
+            "wordsize" => Ok(process.integer(mem::size_of::<usize>())),
             _ => Err(anyhow!(
                 "item ({}) is not a supported atom ({})",
                 item,
@@ -100,7 +116,7 @@ pub fn result(item: Term) -> exception::Result<Term> {
                         "allocator" => unimplemented!(),
                         "allocator_sizes" => unimplemented!(),
                         "cpu_topology" => unimplemented!(),
-                        "wordsize" => unimplemented!(),
+                        "wordsize" => Ok(process.integer(mem::size_of::<usize>())),
                         _ => item_is_not_supported_tuple(item),
                     },
                     _ => item_is_not_supported_tuple(item),
@@ -121,7 +137,7 @@ pub fn result(item: Term) -> exception::Result<Term> {
 
 const SUPPORTED_ATOMS: &'static str = "`allocated_areas`, `allocator`, \
                  `alloc_util_allocators`, `elib_malloc`, `cpu_topology`, `logic_processors`, \
-                 `logic_processors_available`, `logical_processors_online`, \
+                 `logic_processors_available`, `logical_processors`, `logical_processors_online`, \
                  `cpu_quota`, `update_cpu_info`, `fullsweep_after`, `garbage_collection`, \
                  `heap_sizes`, `heap_type`, `max_heap_size`, `message_queue_data`, `min_heap_size` \
                  `min_bin_vheap_size`, `procs`, `atom_count`, `atom_limit`, `ets_count`, \
@@ -141,6 +157,12 @@ const SUPPORTED_ATOMS: &'static str = "`allocated_areas`, `allocator`, \
                  `system_architecture`, `system_logger`, `system_version`, `trace_control_word`, \
                  `version`, or `wordsize`";
 
+/// The OTP release whose behaviour is emulated, matching the release the compiler targets
+const OTP_RELEASE: &'static str = match option_env!("OTP_RELEASE") {
+    Some(release) => release,
+    None => "25",
+};
+
 const SUPPORTED_TUPLES: &'static str = "`{allocator, Alloc}`, `{allocator_sizes, Alloc}`, \
           `{cpu_topology, defined | detected | used}`, or `{wordsize, internal | external}`";
 
@@ -152,3 +174,7 @@ fn item_is_not_supported_tuple(item: Term) -> exception::Result<Term> {
     )
     .into())
 }
+
+fn system_architecture() -> String {
+    format!("{}-unknown-{}", env::consts::ARCH, env::consts::OS)
+}
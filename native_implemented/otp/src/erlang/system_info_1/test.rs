@@ -0,0 +1,136 @@
+use std::convert::TryInto;
+use std::env;
+use std::mem;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::system_info_1::result;
+use crate::runtime::{registry, scheduler};
+use crate::test::with_process;
+
+#[test]
+fn with_process_limit_returns_process_limit() {
+    with_process(|process| {
+        assert_eq!(
+            result(process, Atom::str_to_term("process_limit")),
+            Ok(process.integer(registry::process_limit()))
+        );
+    });
+}
+
+#[test]
+fn with_process_count_returns_positive_integer_no_greater_than_process_limit() {
+    with_process(|process| {
+        let count_term = result(process, Atom::str_to_term("process_count")).unwrap();
+        let count: usize = count_term.try_into().unwrap();
+
+        assert!(0 < count);
+        assert!(count <= registry::process_limit());
+    });
+}
+
+#[test]
+fn with_schedulers_and_schedulers_online_returns_positive_integer() {
+    with_process(|process| {
+        for item in &["schedulers", "schedulers_online"] {
+            let schedulers = positive_integer(result(process, Atom::str_to_term(item)).unwrap());
+
+            assert_eq!(schedulers, scheduler::all().len());
+        }
+    });
+}
+
+#[test]
+fn with_logical_processors_returns_positive_integer() {
+    with_process(|process| {
+        positive_integer(result(process, Atom::str_to_term("logical_processors")).unwrap());
+    });
+}
+
+#[test]
+fn with_otp_release_returns_charlist_of_digits() {
+    with_process(|process| {
+        let otp_release = charlist(result(process, Atom::str_to_term("otp_release")).unwrap());
+
+        assert!(!otp_release.is_empty());
+        assert!(otp_release.chars().all(|c| c.is_ascii_digit()));
+    });
+}
+
+#[test]
+fn with_version_returns_charlist() {
+    with_process(|process| {
+        let version = charlist(result(process, Atom::str_to_term("version")).unwrap());
+
+        assert_eq!(version, env!("CARGO_PKG_VERSION"));
+    });
+}
+
+#[test]
+fn with_wordsize_returns_bytes_in_word() {
+    with_process(|process| {
+        assert_eq!(
+            result(process, Atom::str_to_term("wordsize")),
+            Ok(process.integer(mem::size_of::<usize>()))
+        );
+    });
+}
+
+#[test]
+fn with_system_architecture_returns_charlist() {
+    with_process(|process| {
+        let system_architecture =
+            charlist(result(process, Atom::str_to_term("system_architecture")).unwrap());
+
+        assert!(system_architecture.starts_with(env::consts::ARCH));
+    });
+}
+
+#[test]
+fn with_atom_count_returns_positive_integer_no_greater_than_atom_limit() {
+    with_process(|process| {
+        let atom_count =
+            positive_integer(result(process, Atom::str_to_term("atom_count")).unwrap());
+        let atom_limit =
+            positive_integer(result(process, Atom::str_to_term("atom_limit")).unwrap());
+
+        assert!(atom_count <= atom_limit);
+    });
+}
+
+#[test]
+fn with_atom_count_counts_new_atoms() {
+    with_process(|process| {
+        let before = positive_integer(result(process, Atom::str_to_term("atom_count")).unwrap());
+        Atom::str_to_term("system_info_1_test_with_atom_count_counts_new_atoms");
+        let after = positive_integer(result(process, Atom::str_to_term("atom_count")).unwrap());
+
+        assert!(before < after);
+    });
+}
+
+#[test]
+fn with_unsupported_atom_errors_badarg_naming_item() {
+    with_process(|process| {
+        let item = Atom::str_to_term("unsupported_system_info_item");
+
+        assert_badarg!(
+            result(process, item),
+            format!("item ({}) is not a supported atom", item)
+        );
+    });
+}
+
+fn charlist(term: Term) -> String {
+    let cons: Boxed<Cons> = term.try_into().unwrap();
+
+    cons.try_into().unwrap()
+}
+
+fn positive_integer(term: Term) -> usize {
+    let integer: usize = term.try_into().unwrap();
+
+    assert!(0 < integer);
+
+    integer
+}
@@ -7,6 +7,7 @@ use liblumen_alloc::erts::exception;
 use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 
+use crate::runtime::process::unlink;
 use crate::runtime::registry::pid_to_process;
 
 #[native_implemented::function(erlang:unlink/1)]
@@ -18,7 +19,7 @@ fn result(process: &Process, pid_or_port: Term) -> exception::Result<Term> {
             } else {
                 match pid_to_process(&pid) {
                     Some(pid_arc_process) => {
-                        process.unlink(&pid_arc_process);
+                        unlink(process, &pid_arc_process);
                     }
                     None => (),
                 }
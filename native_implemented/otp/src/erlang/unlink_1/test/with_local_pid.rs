@@ -2,6 +2,7 @@ use super::*;
 
 use liblumen_alloc::erts::term::prelude::{Atom, Pid};
 
+use crate::runtime::process::handle_signals;
 use crate::runtime::scheduler;
 
 use crate::test;
@@ -57,6 +58,10 @@ fn with_existing_linked_pid_unlinks_processes_and_returns_true() {
         assert_eq!(result(process, other_process.pid_term()), Ok(true.into()));
 
         assert_eq!(link_count(process), process_link_count_before - 1);
+
+        // The other process removes its side of the link when it handles the unlink signal
+        assert_eq!(link_count(&other_process), other_process_link_count_before);
+        handle_signals(&other_process);
         assert_eq!(
             link_count(&other_process),
             other_process_link_count_before - 1
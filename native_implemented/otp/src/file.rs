@@ -0,0 +1,39 @@
+//! Mirrors [file](http://erlang.org/doc/man/file.html) module
+
+pub mod read_file_1;
+pub mod write_file_2;
+
+use std::io::ErrorKind;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::list_to_string::list_to_string;
+use crate::runtime::binary_to_string::binary_to_string;
+
+fn module() -> Atom {
+    Atom::from_str("file")
+}
+
+/// The path named by `filename`, which may be a binary or a string
+fn path(filename: Term) -> exception::Result<String> {
+    if filename.is_binary() {
+        binary_to_string(filename)
+    } else {
+        list_to_string(filename)
+    }
+}
+
+/// The POSIX error code for `kind`, as used for `Reason` by the `file` module
+fn reason(kind: ErrorKind) -> Term {
+    let name = match kind {
+        ErrorKind::AlreadyExists => "eexist",
+        ErrorKind::InvalidInput => "einval",
+        ErrorKind::NotFound => "enoent",
+        ErrorKind::PermissionDenied => "eacces",
+        ErrorKind::WriteZero => "enospc",
+        _ => "eio",
+    };
+
+    Atom::str_to_term(name)
+}
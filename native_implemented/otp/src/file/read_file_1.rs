@@ -0,0 +1,35 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+mod label_1;
+
+use std::fs;
+use std::io;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::runtime::blocking;
+
+use super::path;
+
+/// Reads the contents of the file named `filename` into a binary.
+///
+/// The file is read on the blocking pool, so the scheduler runs other processes while this one
+/// waits for it.  Returns `{ok, Binary}`, or `{error, Reason}` if the file can't be read.  The
+/// bytes read are counted as input in `erlang:statistics(io)`.
+#[native_implemented::function(file:read_file/1)]
+pub fn result(process: &Process, filename: Term) -> exception::Result<Term> {
+    // A filename that isn't a binary or string is an error of the caller, not of the read
+    path(filename)?;
+
+    blocking::schedule_blocking(process, &[filename], |arguments| -> io::Result<Vec<u8>> {
+        let path = path(arguments[0]).map_err(|_| io::ErrorKind::InvalidInput)?;
+
+        fs::read(path)
+    })?;
+    process.queue_frame_with_arguments(label_1::frame().with_arguments(false, &[]));
+
+    Ok(Term::NONE)
+}
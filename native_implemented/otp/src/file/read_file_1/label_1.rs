@@ -0,0 +1,37 @@
+//! ```elixir
+//! # label 1
+//! # pushed to stack: ()
+//! # returned from call: N/A
+//! # full stack: ()
+//! # returns: {:ok, binary} | {:error, reason}
+//! ```
+
+use std::io;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::file::reason;
+use crate::runtime::blocking;
+use crate::runtime::sys::io as sys_io;
+
+/// Waits again if woken, such as by a message, before the read completed
+#[native_implemented::label]
+pub fn result(process: &Process) -> Term {
+    match blocking::take::<io::Result<Vec<u8>>>(process) {
+        Some(Ok(bytes)) => {
+            sys_io::record_input(bytes.len());
+            let binary = process.binary_from_bytes(&bytes);
+
+            process.tuple_from_slice(&[atom!("ok"), binary])
+        }
+        Some(Err(error)) => process.tuple_from_slice(&[atom!("error"), reason(error.kind())]),
+        None => {
+            process.wait();
+            process.queue_frame_with_arguments(frame().with_arguments(false, &[]));
+
+            Term::NONE
+        }
+    }
+}
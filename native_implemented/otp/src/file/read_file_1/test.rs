@@ -0,0 +1,135 @@
+use std::fs;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::file::read_file_1::{label_1, result};
+use crate::runtime::blocking;
+use crate::runtime::scheduler::{Scheduled, Spawned};
+use crate::test::{self, busy_loop_0, with_process, with_process_arc};
+
+#[test]
+fn without_binary_or_string_filename_errors_badarg() {
+    with_process(|process| {
+        assert_badarg!(
+            result(process, Atom::str_to_term("filename")),
+            "list (filename) is not a list"
+        );
+    });
+}
+
+#[test]
+fn with_file_returns_ok_and_contents() {
+    with_process_arc(|arc_process| {
+        let path = std::env::temp_dir().join(format!("read_file_1_{}", arc_process.pid()));
+        fs::write(&path, b"hello world").unwrap();
+        let filename = arc_process.charlist_from_str(path.to_str().unwrap());
+
+        assert_eq!(result(&arc_process, filename), Ok(Term::NONE));
+        wait_for_blocking_operation(&arc_process);
+
+        assert_eq!(
+            label_1::result(&arc_process),
+            arc_process.tuple_from_slice(&[
+                Atom::str_to_term("ok"),
+                arc_process.binary_from_str("hello world")
+            ])
+        );
+
+        fs::remove_file(&path).unwrap();
+    });
+}
+
+#[test]
+fn with_missing_file_returns_enoent_error() {
+    with_process_arc(|arc_process| {
+        let path = std::env::temp_dir().join(format!("read_file_1_missing_{}", arc_process.pid()));
+        let filename = arc_process.binary_from_str(path.to_str().unwrap());
+
+        assert_eq!(result(&arc_process, filename), Ok(Term::NONE));
+        wait_for_blocking_operation(&arc_process);
+
+        assert_eq!(
+            label_1::result(&arc_process),
+            arc_process
+                .tuple_from_slice(&[Atom::str_to_term("error"), Atom::str_to_term("enoent")])
+        );
+    });
+}
+
+#[test]
+fn before_read_completes_waits_again() {
+    with_process_arc(|arc_process| {
+        assert_eq!(label_1::result(&arc_process), Term::NONE);
+        assert_eq!(*arc_process.status.read(), Status::Waiting);
+    });
+}
+
+#[test]
+fn other_processes_run_while_blocking_operation_runs() {
+    with_process_arc(|arc_process| {
+        let blocked_arc_process = test::process::child(&arc_process);
+        let busy_arc_process = busy(&arc_process);
+        let (finish_sender, finish_receiver) = mpsc::channel::<()>();
+
+        blocking::schedule_blocking(
+            &blocked_arc_process,
+            &[Atom::str_to_term("slow")],
+            move |arguments| {
+                // Deliberately slow: blocks until the test lets it finish
+                finish_receiver.recv().unwrap();
+
+                arguments[0] == Atom::str_to_term("slow")
+            },
+        )
+        .unwrap();
+
+        let scheduler = arc_process.scheduler().unwrap();
+        let reductions_before = busy_arc_process.reductions();
+
+        for _ in 0..8 {
+            scheduler.run_once();
+        }
+
+        assert!(reductions_before < busy_arc_process.reductions());
+        assert_eq!(*blocked_arc_process.status.read(), Status::Waiting);
+        assert_eq!(blocking::take::<bool>(&blocked_arc_process), None);
+
+        finish_sender.send(()).unwrap();
+        wait_for_blocking_operation(&blocked_arc_process);
+
+        assert_eq!(blocking::take::<bool>(&blocked_arc_process), Some(true));
+    });
+}
+
+fn busy(parent: &Process) -> Arc<Process> {
+    let Spawned { arc_process, .. } = parent
+        .scheduler()
+        .unwrap()
+        .spawn_module_function_arguments(
+            Some(parent),
+            test::module(),
+            busy_loop_0::function(),
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+
+    arc_process
+}
+
+/// Waits for the blocking operation of `process` to complete and wake it
+fn wait_for_blocking_operation(process: &Process) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    while *process.status.read() == Status::Waiting {
+        assert!(
+            Instant::now() < deadline,
+            "blocking operation did not complete"
+        );
+        thread::yield_now();
+    }
+}
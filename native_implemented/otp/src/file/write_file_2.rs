@@ -0,0 +1,38 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::fs;
+
+use anyhow::*;
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::erlang::iolist_to_binary_1;
+use crate::runtime::sys::io;
+
+use super::{path, reason};
+
+/// Writes `bytes`, an iodata, to the file named `filename`, replacing any existing contents.
+///
+/// Returns `{error, Reason}` if the file can't be written. The bytes written are counted as output
+/// in `erlang:statistics(io)`.
+#[native_implemented::function(file:write_file/2)]
+pub fn result(process: &Process, filename: Term, bytes: Term) -> exception::Result<Term> {
+    let path = path(filename)?;
+    let binary = iolist_to_binary_1::result(process, bytes)?;
+    let data = process
+        .bytes_from_binary(binary)
+        .with_context(|| format!("bytes ({})", bytes))?;
+
+    match fs::write(&path, data) {
+        Ok(()) => {
+            io::record_output(data.len());
+
+            Ok(atom!("ok"))
+        }
+        Err(error) => Ok(process.tuple_from_slice(&[atom!("error"), reason(error.kind())])),
+    }
+}
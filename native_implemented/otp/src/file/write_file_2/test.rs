@@ -0,0 +1,54 @@
+use std::fs;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::file::write_file_2::result;
+use crate::test::with_process;
+
+#[test]
+fn with_iodata_writes_bytes_to_file() {
+    with_process(|process| {
+        let path = std::env::temp_dir().join(format!("write_file_2_{}", process.pid()));
+        let filename = process.charlist_from_str(path.to_str().unwrap());
+        let bytes = process.list_from_slice(&[
+            process.binary_from_str("hello"),
+            process.integer(b' '),
+            process.charlist_from_str("world"),
+        ]);
+
+        assert_eq!(
+            result(process, filename, bytes),
+            Ok(Atom::str_to_term("ok"))
+        );
+        assert_eq!(fs::read(&path).unwrap(), b"hello world");
+
+        fs::remove_file(&path).unwrap();
+    });
+}
+
+#[test]
+fn with_missing_directory_returns_enoent_error() {
+    with_process(|process| {
+        let path = std::env::temp_dir()
+            .join(format!("write_file_2_missing_{}", process.pid()))
+            .join("file");
+        let filename = process.binary_from_str(path.to_str().unwrap());
+        let bytes = process.binary_from_str("hello");
+
+        assert_eq!(
+            result(process, filename, bytes),
+            Ok(process
+                .tuple_from_slice(&[Atom::str_to_term("error"), Atom::str_to_term("enoent")]))
+        );
+    });
+}
+
+#[test]
+fn without_string_filename_errors_badarg() {
+    with_process(|process| {
+        let filename = process.integer(1);
+        let bytes = process.binary_from_str("hello");
+
+        assert_badarg!(result(process, filename, bytes), "list (1) is not a list");
+    });
+}
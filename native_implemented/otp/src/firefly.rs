@@ -0,0 +1,10 @@
+//! Firefly intrinsics
+
+pub mod broadcast_2;
+pub mod cancel_timers_1;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+pub fn module() -> Atom {
+    Atom::from_str("firefly")
+}
@@ -0,0 +1,22 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::runtime::pg;
+use crate::runtime::send::send;
+
+/// Sends `message` to every member of `group`, once for every time the member joined it.
+///
+/// The members are those of the group when `broadcast` is called, so a process that joins while
+/// the message is being sent does not receive it.
+#[native_implemented::function(firefly:broadcast/2)]
+pub fn result(process: &Process, group: Term, message: Term) -> exception::Result<Term> {
+    for pid in pg::members(group) {
+        send(pid.encode()?, message, Default::default(), process)?;
+    }
+
+    Ok(Atom::str_to_term("ok"))
+}
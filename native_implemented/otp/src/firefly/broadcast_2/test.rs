@@ -0,0 +1,71 @@
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::firefly::broadcast_2::result;
+use crate::pg::{join_2, leave_2};
+use crate::test::{self, has_message, registered_name, with_process, with_process_arc};
+
+#[test]
+fn without_group_sends_to_no_one() {
+    with_process(|process| {
+        let message = Atom::str_to_term("message");
+
+        assert_eq!(
+            result(process, registered_name(), message),
+            Ok(Atom::str_to_term("ok"))
+        );
+        assert!(!has_message(process, message));
+    });
+}
+
+#[test]
+fn with_group_sends_to_members_at_the_time_of_the_broadcast() {
+    with_process_arc(|sender_arc_process| {
+        let group = registered_name();
+        let first_member_arc_process = test::process::child(&sender_arc_process);
+        let second_member_arc_process = test::process::child(&sender_arc_process);
+        let non_member_arc_process = test::process::child(&sender_arc_process);
+
+        assert_eq!(
+            join_2::result(
+                group,
+                sender_arc_process.list_from_slice(&[
+                    first_member_arc_process.pid_term(),
+                    second_member_arc_process.pid_term()
+                ])
+            ),
+            Ok(Atom::str_to_term("ok"))
+        );
+
+        let first_message = Atom::str_to_term("first");
+
+        assert_eq!(
+            result(&sender_arc_process, group, first_message),
+            Ok(Atom::str_to_term("ok"))
+        );
+
+        assert!(has_message(&first_member_arc_process, first_message));
+        assert!(has_message(&second_member_arc_process, first_message));
+        assert!(!has_message(&non_member_arc_process, first_message));
+        assert!(!has_message(&sender_arc_process, first_message));
+
+        assert_eq!(
+            leave_2::result(group, second_member_arc_process.pid_term()),
+            Ok(Atom::str_to_term("ok"))
+        );
+        assert_eq!(
+            join_2::result(group, non_member_arc_process.pid_term()),
+            Ok(Atom::str_to_term("ok"))
+        );
+
+        let second_message = Atom::str_to_term("second");
+
+        assert_eq!(
+            result(&sender_arc_process, group, second_message),
+            Ok(Atom::str_to_term("ok"))
+        );
+
+        assert!(has_message(&first_member_arc_process, second_message));
+        assert!(!has_message(&second_member_arc_process, second_message));
+        assert!(has_message(&non_member_arc_process, second_message));
+    });
+}
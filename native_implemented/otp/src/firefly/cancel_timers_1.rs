@@ -0,0 +1,57 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::convert::TryInto;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::runtime::timer;
+
+/// Cancels the timers in `timer_reference_list` as one batch, returning, in the same order, the
+/// milliseconds remaining for each timer as `erlang:cancel_timer/1` would, or `false` if the timer
+/// had already timed out or been canceled.
+#[native_implemented::function(firefly:cancel_timers/1)]
+pub fn result(process: &Process, timer_reference_list: Term) -> exception::Result<Term> {
+    let mut timer_reference_vec: Vec<Boxed<Reference>> = Vec::new();
+    let mut tail = timer_reference_list;
+
+    loop {
+        match tail.decode()? {
+            TypedTerm::Nil => break,
+            TypedTerm::List(cons) => {
+                let timer_reference = cons.head;
+                let boxed_timer_reference: Boxed<Reference> =
+                    timer_reference.try_into().with_context(|| {
+                        format!(
+                            "timer_reference ({}) is not a local reference",
+                            timer_reference
+                        )
+                    })?;
+                timer_reference_vec.push(boxed_timer_reference);
+                tail = cons.tail;
+            }
+            _ => {
+                return Err(ImproperListError)
+                    .context(format!(
+                        "timer_reference_list ({}) is improper",
+                        timer_reference_list
+                    ))
+                    .map_err(From::from)
+            }
+        }
+    }
+
+    let canceled_vec: Vec<Term> = timer::cancel_batch(&timer_reference_vec)
+        .into_iter()
+        .map(|canceled| match canceled {
+            Some(milliseconds_remaining) => process.integer(milliseconds_remaining),
+            None => false.into(),
+        })
+        .collect();
+
+    Ok(process.list_from_slice(&canceled_vec))
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::time::Milliseconds;
+
+use crate::erlang::start_timer_3;
+use crate::firefly::cancel_timers_1::result;
+use crate::runtime::process::propagate_exit;
+use crate::runtime::scheduler;
+use crate::test::{
+    self, freeze_at_timeout, freeze_timeout, has_message, timeout_message, with_process,
+    with_process_arc,
+};
+
+#[test]
+fn without_list_errors_badarg() {
+    with_process(|process| {
+        let timer_reference_list = process.next_reference();
+
+        assert_badarg!(
+            result(process, timer_reference_list),
+            format!(
+                "timer_reference_list ({}) is improper",
+                timer_reference_list
+            )
+        );
+    });
+}
+
+#[test]
+fn with_non_reference_element_errors_badarg() {
+    with_process(|process| {
+        let timer_reference = process.integer(0);
+        let timer_reference_list = process.list_from_slice(&[timer_reference]);
+
+        assert_badarg!(
+            result(process, timer_reference_list),
+            format!(
+                "timer_reference ({}) is not a local reference",
+                timer_reference
+            )
+        );
+    });
+}
+
+#[test]
+fn with_empty_list_returns_empty_list() {
+    with_process(|process| {
+        assert_eq!(result(process, Term::NIL), Ok(Term::NIL));
+    });
+}
+
+#[test]
+fn with_timer_that_timed_out_returns_false_for_it_and_milliseconds_remaining_for_the_rest() {
+    with_process_arc(|arc_process| {
+        let message = Atom::str_to_term("message");
+        let start_monotonic = freeze_timeout();
+
+        let timer_references: Vec<Term> = [10, 100, 200]
+            .iter()
+            .map(|milliseconds| start_timer(&arc_process, Milliseconds(*milliseconds), message))
+            .collect();
+
+        freeze_at_timeout(start_monotonic + Milliseconds(11));
+
+        assert!(has_message(
+            &arc_process,
+            timeout_message(timer_references[0], message, &arc_process)
+        ));
+
+        let timer_reference_list = arc_process.list_from_slice(&timer_references);
+
+        assert_eq!(
+            result(&arc_process, timer_reference_list),
+            Ok(arc_process.list_from_slice(&[
+                false.into(),
+                arc_process.integer(89),
+                arc_process.integer(189)
+            ]))
+        );
+
+        freeze_at_timeout(start_monotonic + Milliseconds(201));
+
+        for timer_reference in &timer_references[1..] {
+            assert!(!has_message(
+                &arc_process,
+                timeout_message(*timer_reference, message, &arc_process)
+            ));
+        }
+
+        // again, now that all are canceled
+        assert_eq!(
+            result(&arc_process, timer_reference_list),
+            Ok(arc_process.list_from_slice(&[false.into(), false.into(), false.into()]))
+        );
+    });
+}
+
+#[test]
+fn with_owner_exited_timers_are_canceled_and_do_not_time_out() {
+    with_process_arc(|parent_arc_process| {
+        let baseline_len = scheduler::current().hierarchy().read().len();
+
+        let owner_arc_process = test::process::child(&parent_arc_process);
+        let message = Atom::str_to_term("message");
+        let start_monotonic = freeze_timeout();
+
+        let timer_references: Vec<Term> = [10, 100]
+            .iter()
+            .map(|milliseconds| {
+                start_timer_3::result(
+                    owner_arc_process.clone(),
+                    owner_arc_process.integer(Milliseconds(*milliseconds)),
+                    parent_arc_process.pid_term(),
+                    message,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        assert_eq!(
+            scheduler::current().hierarchy().read().len(),
+            baseline_len + timer_references.len()
+        );
+
+        propagate_exit(&owner_arc_process, None);
+
+        assert_eq!(scheduler::current().hierarchy().read().len(), baseline_len);
+
+        freeze_at_timeout(start_monotonic + Milliseconds(101));
+
+        for timer_reference in timer_references {
+            assert!(!has_message(
+                &parent_arc_process,
+                timeout_message(timer_reference, message, &owner_arc_process)
+            ));
+        }
+    });
+}
+
+fn start_timer(arc_process: &Arc<Process>, milliseconds: Milliseconds, message: Term) -> Term {
+    start_timer_3::result(
+        arc_process.clone(),
+        arc_process.integer(milliseconds),
+        arc_process.pid_term(),
+        message,
+    )
+    .unwrap()
+}
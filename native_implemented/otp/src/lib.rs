@@ -8,14 +8,19 @@ mod macros;
 
 pub mod binary;
 pub mod erlang;
+pub mod file;
+pub mod firefly;
 pub mod lists;
 pub mod lumen;
 pub mod maps;
+pub mod net_kernel;
 pub mod number;
+pub mod pg;
+pub mod rand;
 #[cfg(not(test))]
-use lumen_rt_core as runtime;
+use firefly_rt_core as runtime;
 #[cfg(test)]
-use lumen_rt_full as runtime;
+use firefly_rt_full as runtime;
 pub mod timer;
 
 #[cfg(test)]
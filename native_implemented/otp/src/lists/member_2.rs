@@ -1,35 +1,65 @@
 #[cfg(all(not(target_arch = "wasm32"), test))]
 mod test;
 
+mod label_1;
+
 use anyhow::*;
 
 use liblumen_alloc::erts::exception;
 use liblumen_alloc::erts::term::prelude::*;
 
+use crate::runtime::process::{
+    consume_reductions, current_process, CELLS_PER_CONSUME, REDUCTIONS_PER_CONSUME,
+};
+
 #[native_implemented::function(lists:member/2)]
 pub fn result(element: Term, list: Term) -> exception::Result<Term> {
     match list.decode()? {
         TypedTerm::Nil => Ok(false.into()),
-        TypedTerm::List(cons) => {
-            for result in cons.into_iter() {
-                match result {
-                    Ok(term) => {
-                        if term == element {
-                            return Ok(true.into());
-                        }
-                    }
-                    Err(_) => {
-                        return Err(ImproperListError)
-                            .context(format!("list ({}) is improper", list))
-                            .map_err(From::from)
-                    }
-                };
-            }
-
-            Ok(false.into())
-        }
+        TypedTerm::List(_) => member(element, list, list),
         _ => Err(TypeError)
             .context(format!("list ({}) is not a list", list))
             .map_err(From::from),
     }
 }
+
+// Private
+
+/// Searches `remaining`, the part of `list` not yet searched, for `element`.
+///
+/// When the process uses up its budget, it yields with the rest of `list` left to `label_1`.
+fn member(element: Term, list: Term, mut remaining: Term) -> exception::Result<Term> {
+    let mut cells = 0;
+
+    loop {
+        match remaining.decode()? {
+            TypedTerm::Nil => return Ok(false.into()),
+            TypedTerm::List(cons) => {
+                if cons.head == element {
+                    return Ok(true.into());
+                }
+
+                remaining = cons.tail;
+                cells += 1;
+
+                if cells == CELLS_PER_CONSUME {
+                    cells = 0;
+
+                    // Only a process run by the scheduler can use up its budget
+                    if consume_reductions(REDUCTIONS_PER_CONSUME) && !remaining.is_nil() {
+                        current_process().queue_frame_with_arguments(
+                            label_1::frame().with_arguments(false, &[element, list, remaining]),
+                        );
+
+                        return Ok(Term::NONE);
+                    }
+                }
+            }
+            _ => {
+                return Err(ImproperListError)
+                    .context(format!("list ({}) is improper", list))
+                    .map_err(From::from)
+            }
+        }
+    }
+}
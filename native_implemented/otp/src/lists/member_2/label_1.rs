@@ -0,0 +1,17 @@
+//! ```elixir
+//! # label 1
+//! # pushed to stack: (element, list, remaining)
+//! # returned from call: N/A
+//! # full stack: (element, list, remaining)
+//! # returns: boolean
+//! ```
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::term::prelude::*;
+
+/// Continues searching `list` for `element` in the `remaining` part not searched before the
+/// process yielded
+#[native_implemented::label]
+fn result(element: Term, list: Term, remaining: Term) -> exception::Result<Term> {
+    super::member(element, list, remaining)
+}
@@ -1,36 +1,66 @@
 #[cfg(all(not(target_arch = "wasm32"), test))]
 mod test;
 
+mod label_1;
+
 use anyhow::*;
 
 use liblumen_alloc::erts::exception;
 use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 
+use crate::runtime::process::{consume_reductions, CELLS_PER_CONSUME, REDUCTIONS_PER_CONSUME};
+
 #[native_implemented::function(lists:reverse/2)]
 pub fn result(process: &Process, list: Term, tail: Term) -> exception::Result<Term> {
     match list.decode()? {
         TypedTerm::Nil => Ok(tail),
-        TypedTerm::List(cons) => {
-            let mut reversed = tail;
+        TypedTerm::List(_) => reverse(process, list, list, tail),
+        _ => Err(TypeError)
+            .context(format!("list ({}) is not a proper list", list))
+            .map_err(From::from),
+    }
+}
 
-            for result in cons.into_iter() {
-                match result {
-                    Ok(element) => {
-                        reversed = process.cons(element, reversed);
-                    }
-                    Err(_) => {
-                        return Err(ImproperListError)
-                            .context(format!("list ({}) is not a proper list", list))
-                            .map_err(From::from)
+// Private
+
+/// Conses the elements of `remaining`, the part of `list` not yet reversed, onto `reversed`.
+///
+/// When the process uses up its budget, it yields with the rest of `list` left to `label_1`, so
+/// reversing a long list doesn't keep other processes from running.
+fn reverse(
+    process: &Process,
+    list: Term,
+    mut remaining: Term,
+    mut reversed: Term,
+) -> exception::Result<Term> {
+    let mut cells = 0;
+
+    loop {
+        match remaining.decode()? {
+            TypedTerm::Nil => return Ok(reversed),
+            TypedTerm::List(cons) => {
+                reversed = process.cons(cons.head, reversed);
+                remaining = cons.tail;
+                cells += 1;
+
+                if cells == CELLS_PER_CONSUME {
+                    cells = 0;
+
+                    if consume_reductions(REDUCTIONS_PER_CONSUME) && !remaining.is_nil() {
+                        process.queue_frame_with_arguments(
+                            label_1::frame().with_arguments(false, &[list, remaining, reversed]),
+                        );
+
+                        return Ok(Term::NONE);
                     }
                 }
             }
-
-            Ok(reversed)
+            _ => {
+                return Err(ImproperListError)
+                    .context(format!("list ({}) is not a proper list", list))
+                    .map_err(From::from)
+            }
         }
-        _ => Err(TypeError)
-            .context(format!("list ({}) is not a proper list", list))
-            .map_err(From::from),
     }
 }
@@ -0,0 +1,22 @@
+//! ```elixir
+//! # label 1
+//! # pushed to stack: (list, remaining, reversed)
+//! # returned from call: N/A
+//! # full stack: (list, remaining, reversed)
+//! # returns: reversed list
+//! ```
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+/// Continues reversing `list` with the `remaining` part not reversed before the process yielded
+#[native_implemented::label]
+pub fn result(
+    process: &Process,
+    list: Term,
+    remaining: Term,
+    reversed: Term,
+) -> exception::Result<Term> {
+    super::reverse(process, list, remaining, reversed)
+}
@@ -1,13 +1,18 @@
 mod with_proper_list;
 
+use std::sync::Arc;
+
 use proptest::prop_assert_eq;
 use proptest::test_runner::{Config, TestRunner};
 
-use liblumen_alloc::erts::term::prelude::Term;
+use liblumen_alloc::erts::process::{Process, Status};
+use liblumen_alloc::erts::term::prelude::*;
 
-use crate::lists::reverse_2::result;
-use crate::test::strategy;
-use crate::test::with_process_arc;
+use crate::lists::reverse_2::{label_1, result};
+use crate::runtime::process::spawn::Options;
+use crate::runtime::process::CELLS_PER_CONSUME;
+use crate::runtime::scheduler::{Scheduled, Spawned};
+use crate::test::{self, busy_loop_0, strategy, with_process_arc};
 
 #[test]
 fn without_proper_list_errors_badarg() {
@@ -30,3 +35,109 @@ fn without_proper_list_errors_badarg() {
             .unwrap();
     });
 }
+
+#[test]
+fn after_yielding_reverses_remaining_elements_onto_those_reversed() {
+    with_process_arc(|arc_process| {
+        let list = arc_process.list_from_slice(&[
+            arc_process.integer(1),
+            arc_process.integer(2),
+            arc_process.integer(3),
+        ]);
+        let remaining =
+            arc_process.list_from_slice(&[arc_process.integer(2), arc_process.integer(3)]);
+        let reversed = arc_process.list_from_slice(&[arc_process.integer(1)]);
+
+        assert_eq!(
+            label_1::result(&arc_process, list, remaining, reversed),
+            Ok(arc_process.list_from_slice(&[
+                arc_process.integer(3),
+                arc_process.integer(2),
+                arc_process.integer(1)
+            ]))
+        );
+    });
+}
+
+#[test]
+fn with_long_list_other_processes_run_while_it_is_reversed() {
+    with_process_arc(|arc_process| {
+        let len = 50 * CELLS_PER_CONSUME;
+        let elements: Vec<Term> = (0..len).map(|i| arc_process.integer(i)).collect();
+        let list = arc_process.list_from_slice(&elements);
+        let reversing_arc_process = reverse(&arc_process, list, 5 * len);
+        let busy_arc_process = busy(&arc_process);
+        let scheduler = arc_process.scheduler().unwrap();
+
+        let mut runs = 0;
+        let mut reversing_runs = 0;
+        let mut busy_runs_while_reversing = 0;
+
+        while !reversing_arc_process.is_exiting() {
+            assert!(runs < len, "reversing did not finish");
+            runs += 1;
+
+            let reversing_reductions_before = reversing_arc_process.reductions();
+            let busy_reductions_before = busy_arc_process.reductions();
+
+            scheduler.run_once();
+
+            if reversing_reductions_before < reversing_arc_process.reductions() {
+                reversing_runs += 1;
+            }
+
+            // Between runs of the reversing process, which was only part way through the list
+            if 0 < reversing_runs
+                && !reversing_arc_process.is_exiting()
+                && busy_reductions_before < busy_arc_process.reductions()
+            {
+                busy_runs_while_reversing += 1;
+            }
+        }
+
+        assert_eq!(*reversing_arc_process.status.read(), Status::Exited);
+        assert!(1 < reversing_runs);
+        assert!(0 < busy_runs_while_reversing);
+    });
+}
+
+fn busy(parent: &Process) -> Arc<Process> {
+    spawn(
+        parent,
+        test::module(),
+        busy_loop_0::function(),
+        vec![],
+        Default::default(),
+    )
+}
+
+/// Spawns a process that reverses `list`, with a heap of `min_heap_size` so that neither `list`
+/// nor its reverse need to be collected
+fn reverse(parent: &Process, list: Term, min_heap_size: usize) -> Arc<Process> {
+    let mut options: Options = Default::default();
+    options.min_heap_size = Some(min_heap_size);
+
+    spawn(
+        parent,
+        Atom::from_str("lists"),
+        Atom::from_str("reverse"),
+        vec![list, Term::NIL],
+        options,
+    )
+}
+
+fn spawn(
+    parent: &Process,
+    module: Atom,
+    function: Atom,
+    arguments: Vec<Term>,
+    options: Options,
+) -> Arc<Process> {
+    let Spawned { arc_process, .. } = parent
+        .scheduler()
+        .unwrap()
+        .spawn_module_function_arguments(Some(parent), module, function, arguments, options)
+        .unwrap();
+
+    arc_process
+}
@@ -0,0 +1,9 @@
+//! Only the parts of `net_kernel` that don't need distribution to be implemented.
+
+pub mod connect_node_1;
+
+use liblumen_alloc::erts::term::prelude::Atom;
+
+fn module() -> Atom {
+    Atom::from_str("net_kernel")
+}
@@ -0,0 +1,15 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::runtime::distribution::transport;
+
+/// Returns `false` when no distribution transport is registered.
+#[native_implemented::function(net_kernel:connect_node/1)]
+pub fn result(node: Term) -> exception::Result<Term> {
+    let node_atom = term_try_into_atom!(node)?;
+
+    Ok(transport::connect(node_atom).into())
+}
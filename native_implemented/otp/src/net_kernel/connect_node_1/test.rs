@@ -0,0 +1,55 @@
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::net_kernel::connect_node_1::result;
+use crate::runtime::distribution::transport;
+use crate::test::distribution::{with_transport, MockTransport};
+use crate::test::{external_arc_node, with_process};
+
+#[test]
+fn without_atom_errors_badarg() {
+    with_process(|process| {
+        let node = process.integer(0);
+
+        assert_badarg!(result(node), format!("node ({}) is not an atom", node));
+    });
+}
+
+#[test]
+fn without_transport_returns_false() {
+    with_transport(None, || {
+        let node = external_arc_node().name().encode().unwrap();
+
+        assert_eq!(result(node), Ok(false.into()));
+        assert!(transport::connected().is_empty());
+    });
+}
+
+#[test]
+fn without_reachable_node_returns_false() {
+    with_transport(Some(MockTransport::new(None)), || {
+        let node = external_arc_node().name().encode().unwrap();
+
+        assert_eq!(result(node), Ok(false.into()));
+        assert!(transport::connected().is_empty());
+    });
+}
+
+#[test]
+fn with_reachable_node_returns_true_and_connects() {
+    let arc_node = external_arc_node();
+
+    with_transport(Some(MockTransport::new(Some(arc_node.creation()))), || {
+        let node = arc_node.name().encode().unwrap();
+
+        assert_eq!(result(node), Ok(true.into()));
+        assert_eq!(transport::connected(), vec![arc_node.clone()]);
+
+        // already connected
+        assert_eq!(result(node), Ok(true.into()));
+        assert_eq!(transport::connected(), vec![arc_node.clone()]);
+
+        transport::node_down(arc_node.name());
+
+        assert!(transport::connected().is_empty());
+    });
+}
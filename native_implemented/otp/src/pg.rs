@@ -0,0 +1,51 @@
+//! Process groups in the default scope, for the local node only.
+
+pub mod get_members_1;
+pub mod join_2;
+pub mod leave_2;
+pub mod which_groups_0;
+
+use std::convert::TryInto;
+
+use anyhow::*;
+
+use liblumen_alloc::erts::exception::InternalResult;
+use liblumen_alloc::erts::term::prelude::*;
+
+fn module() -> Atom {
+    Atom::from_str("pg")
+}
+
+fn pid_or_pids_to_vec(pid_or_pids: Term) -> InternalResult<Vec<Pid>> {
+    match pid_or_pids.decode()? {
+        TypedTerm::Pid(pid) => Ok(vec![pid]),
+        TypedTerm::Nil => Ok(Vec::new()),
+        TypedTerm::List(cons) => {
+            let mut pid_vec = Vec::new();
+
+            for result in cons.into_iter() {
+                match result {
+                    Ok(element) => {
+                        let pid: Pid = element.try_into().with_context(|| {
+                            format!("pid_or_pids element ({}) is not a local pid", element)
+                        })?;
+                        pid_vec.push(pid);
+                    }
+                    Err(_) => {
+                        return Err(ImproperListError)
+                            .context(format!("pid_or_pids ({}) is improper", pid_or_pids))
+                            .map_err(From::from)
+                    }
+                }
+            }
+
+            Ok(pid_vec)
+        }
+        _ => Err(TypeError)
+            .context(format!(
+                "pid_or_pids ({}) is neither a local pid nor a list of local pids",
+                pid_or_pids
+            ))
+            .map_err(From::from),
+    }
+}
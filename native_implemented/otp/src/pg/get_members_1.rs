@@ -0,0 +1,17 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::runtime::pg;
+
+#[native_implemented::function(pg:get_members/1)]
+pub fn result(process: &Process, group: Term) -> Term {
+    let member_vec: Vec<Term> = pg::members(group)
+        .into_iter()
+        .map(|pid| pid.encode().unwrap())
+        .collect();
+
+    process.list_from_slice(&member_vec)
+}
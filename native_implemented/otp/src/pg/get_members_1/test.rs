@@ -0,0 +1,41 @@
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::pg::get_members_1::result;
+use crate::pg::{join_2, leave_2};
+use crate::test::{registered_name, with_process};
+
+#[test]
+fn without_group_returns_empty_list() {
+    with_process(|process| {
+        assert_eq!(result(process, registered_name()), Term::NIL);
+    });
+}
+
+#[test]
+fn with_group_named_by_term_on_another_heap_returns_members() {
+    with_process(|joining_process| {
+        let name = registered_name();
+        let joining_group = joining_process.tuple_from_slice(&[name, joining_process.integer(1)]);
+        let pid = joining_process.pid_term();
+
+        assert_eq!(
+            join_2::result(joining_group, pid),
+            Ok(Atom::str_to_term("ok"))
+        );
+
+        with_process(|process| {
+            let group = process.tuple_from_slice(&[name, process.integer(1)]);
+
+            assert_eq!(result(process, group), process.list_from_slice(&[pid]));
+
+            let other_group = process.tuple_from_slice(&[name, process.integer(2)]);
+
+            assert_eq!(result(process, other_group), Term::NIL);
+        });
+
+        assert_eq!(
+            leave_2::result(joining_group, pid),
+            Ok(Atom::str_to_term("ok"))
+        );
+    });
+}
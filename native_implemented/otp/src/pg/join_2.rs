@@ -0,0 +1,18 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::pg::pid_or_pids_to_vec;
+use crate::runtime::pg;
+
+/// Joins `pid_or_pids` to `group`. A process can join a group more than once, and is then a
+/// member once for every join.
+#[native_implemented::function(pg:join/2)]
+pub fn result(group: Term, pid_or_pids: Term) -> exception::Result<Term> {
+    let pid_vec = pid_or_pids_to_vec(pid_or_pids)?;
+    pg::join(group, &pid_vec)?;
+
+    Ok(Atom::str_to_term("ok"))
+}
@@ -0,0 +1,117 @@
+use std::thread;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::pg::get_members_1;
+use crate::pg::join_2::result;
+use crate::runtime::pg;
+use crate::test::{registered_name, with_process};
+
+#[test]
+fn without_pid_or_list_errors_badarg() {
+    with_process(|process| {
+        let group = registered_name();
+        let pid_or_pids = process.integer(0);
+
+        assert_badarg!(
+            result(group, pid_or_pids),
+            format!(
+                "pid_or_pids ({}) is neither a local pid nor a list of local pids",
+                pid_or_pids
+            )
+        );
+    });
+}
+
+#[test]
+fn with_list_with_non_pid_element_errors_badarg_and_joins_none() {
+    with_process(|process| {
+        let group = registered_name();
+        let element = Atom::str_to_term("not_a_pid");
+        let pid_or_pids = process.list_from_slice(&[process.pid_term(), element]);
+
+        assert_badarg!(
+            result(group, pid_or_pids),
+            format!("pid_or_pids element ({}) is not a local pid", element)
+        );
+
+        assert_eq!(get_members_1::result(process, group), Term::NIL);
+    });
+}
+
+#[test]
+fn with_pid_joins_group() {
+    with_process(|process| {
+        let group = registered_name();
+
+        assert_eq!(
+            result(group, process.pid_term()),
+            Ok(Atom::str_to_term("ok"))
+        );
+        assert_eq!(
+            get_members_1::result(process, group),
+            process.list_from_slice(&[process.pid_term()])
+        );
+    });
+}
+
+#[test]
+fn with_list_of_pids_joins_each_in_order() {
+    with_process(|process| {
+        let group = registered_name();
+        let pids = process.list_from_slice(&[Pid::next_term(), Pid::next_term()]);
+
+        assert_eq!(result(group, pids), Ok(Atom::str_to_term("ok")));
+        assert_eq!(get_members_1::result(process, group), pids);
+    });
+}
+
+#[test]
+fn joining_twice_is_a_member_twice() {
+    with_process(|process| {
+        let group = registered_name();
+        let pid = process.pid_term();
+
+        assert_eq!(result(group, pid), Ok(Atom::str_to_term("ok")));
+        assert_eq!(result(group, pid), Ok(Atom::str_to_term("ok")));
+        assert_eq!(
+            get_members_1::result(process, group),
+            process.list_from_slice(&[pid, pid])
+        );
+    });
+}
+
+#[test]
+fn concurrent_joins_lose_no_members() {
+    const THREADS: usize = 16;
+    const JOINS_PER_THREAD: usize = 100;
+
+    let group = registered_name();
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|_| {
+            thread::spawn(move || {
+                (0..JOINS_PER_THREAD)
+                    .map(|_| {
+                        let pid = Pid::next();
+                        result(group, pid.encode().unwrap()).unwrap();
+
+                        pid
+                    })
+                    .collect::<Vec<Pid>>()
+            })
+        })
+        .collect();
+
+    let mut joined_pids: Vec<Pid> = handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect();
+    let mut member_pids = pg::members(group);
+
+    joined_pids.sort();
+    member_pids.sort();
+
+    assert_eq!(member_pids.len(), THREADS * JOINS_PER_THREAD);
+    assert_eq!(member_pids, joined_pids);
+}
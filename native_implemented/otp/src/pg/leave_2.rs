@@ -0,0 +1,23 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::pg::pid_or_pids_to_vec;
+use crate::runtime::pg;
+
+/// Removes one membership in `group` of each of `pid_or_pids`, returning `not_joined` if none of
+/// them were members.
+#[native_implemented::function(pg:leave/2)]
+pub fn result(group: Term, pid_or_pids: Term) -> exception::Result<Term> {
+    let pid_vec = pid_or_pids_to_vec(pid_or_pids)?;
+
+    let left = if pg::leave(group, &pid_vec) {
+        "ok"
+    } else {
+        "not_joined"
+    };
+
+    Ok(Atom::str_to_term(left))
+}
@@ -0,0 +1,124 @@
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::pg::leave_2::result;
+use crate::pg::{get_members_1, join_2};
+use crate::runtime::process::propagate_exit;
+use crate::test::{self, registered_name, with_process, with_process_arc};
+
+#[test]
+fn without_pid_or_list_errors_badarg() {
+    with_process(|process| {
+        let group = registered_name();
+        let pid_or_pids = process.integer(0);
+
+        assert_badarg!(
+            result(group, pid_or_pids),
+            format!(
+                "pid_or_pids ({}) is neither a local pid nor a list of local pids",
+                pid_or_pids
+            )
+        );
+    });
+}
+
+#[test]
+fn without_group_returns_not_joined() {
+    with_process(|process| {
+        let group = registered_name();
+
+        assert_eq!(
+            result(group, process.pid_term()),
+            Ok(Atom::str_to_term("not_joined"))
+        );
+    });
+}
+
+#[test]
+fn without_member_returns_not_joined_and_leaves_members() {
+    with_process(|process| {
+        let group = registered_name();
+        let member = Pid::next_term();
+
+        assert_eq!(join_2::result(group, member), Ok(Atom::str_to_term("ok")));
+        assert_eq!(
+            result(group, process.pid_term()),
+            Ok(Atom::str_to_term("not_joined"))
+        );
+        assert_eq!(
+            get_members_1::result(process, group),
+            process.list_from_slice(&[member])
+        );
+    });
+}
+
+#[test]
+fn with_member_that_joined_twice_removes_one_membership() {
+    with_process(|process| {
+        let group = registered_name();
+        let pid = process.pid_term();
+
+        assert_eq!(join_2::result(group, pid), Ok(Atom::str_to_term("ok")));
+        assert_eq!(join_2::result(group, pid), Ok(Atom::str_to_term("ok")));
+
+        assert_eq!(result(group, pid), Ok(Atom::str_to_term("ok")));
+        assert_eq!(
+            get_members_1::result(process, group),
+            process.list_from_slice(&[pid])
+        );
+
+        assert_eq!(result(group, pid), Ok(Atom::str_to_term("ok")));
+        assert_eq!(get_members_1::result(process, group), Term::NIL);
+
+        assert_eq!(result(group, pid), Ok(Atom::str_to_term("not_joined")));
+    });
+}
+
+#[test]
+fn with_list_with_some_members_returns_ok() {
+    with_process(|process| {
+        let group = registered_name();
+        let member = Pid::next_term();
+        let non_member = Pid::next_term();
+
+        assert_eq!(join_2::result(group, member), Ok(Atom::str_to_term("ok")));
+        assert_eq!(
+            result(group, process.list_from_slice(&[non_member, member])),
+            Ok(Atom::str_to_term("ok"))
+        );
+        assert_eq!(get_members_1::result(process, group), Term::NIL);
+    });
+}
+
+#[test]
+fn member_exiting_leaves_all_its_groups() {
+    with_process_arc(|parent_arc_process| {
+        let child_arc_process = test::process::child(&parent_arc_process);
+        let child_pid = child_arc_process.pid_term();
+        let parent_pid = parent_arc_process.pid_term();
+        let first_group = registered_name();
+        let second_group = registered_name();
+
+        assert_eq!(
+            join_2::result(
+                first_group,
+                parent_arc_process.list_from_slice(&[child_pid, parent_pid, child_pid])
+            ),
+            Ok(Atom::str_to_term("ok"))
+        );
+        assert_eq!(
+            join_2::result(second_group, child_pid),
+            Ok(Atom::str_to_term("ok"))
+        );
+
+        propagate_exit(&child_arc_process, None);
+
+        assert_eq!(
+            get_members_1::result(&parent_arc_process, first_group),
+            parent_arc_process.list_from_slice(&[parent_pid])
+        );
+        assert_eq!(
+            get_members_1::result(&parent_arc_process, second_group),
+            Term::NIL
+        );
+    });
+}
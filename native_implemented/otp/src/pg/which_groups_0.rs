@@ -0,0 +1,12 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::runtime::pg;
+
+#[native_implemented::function(pg:which_groups/0)]
+pub fn result(process: &Process) -> Term {
+    pg::which_groups(process)
+}
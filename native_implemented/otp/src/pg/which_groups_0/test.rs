@@ -0,0 +1,32 @@
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::pg::which_groups_0::result;
+use crate::pg::{join_2, leave_2};
+use crate::test::{registered_name, with_process};
+
+#[test]
+fn with_group_with_members_includes_group() {
+    with_process(|process| {
+        let group = registered_name();
+        let pid = process.pid_term();
+
+        assert!(!includes(process, group));
+
+        assert_eq!(join_2::result(group, pid), Ok(Atom::str_to_term("ok")));
+
+        assert!(includes(process, group));
+
+        assert_eq!(leave_2::result(group, pid), Ok(Atom::str_to_term("ok")));
+
+        assert!(!includes(process, group));
+    });
+}
+
+fn includes(process: &Process, group: Term) -> bool {
+    match result(process).decode().unwrap() {
+        TypedTerm::Nil => false,
+        TypedTerm::List(cons) => cons.into_iter().any(|result| result.unwrap() == group),
+        typed_term => panic!("which_groups returned {:?}", typed_term),
+    }
+}
@@ -0,0 +1,186 @@
+//! Mirrors [rand](http://erlang.org/doc/man/rand.html) module
+//!
+//! Only the default `exsss` algorithm is supported.  As in OTP, the state is kept in the process
+//! dictionary under `rand_seed` as `{AlgHandler, AlgState}`, but `AlgHandler` is only the
+//! `#{bits => 58, type => exsss}` part of the map, as there are no Erlang funs to put in it.
+
+mod exsss;
+
+pub mod seed_1;
+pub mod seed_2;
+pub mod uniform_0;
+pub mod uniform_1;
+pub mod uniform_real_0;
+
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryInto;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::*;
+use num_bigint::{BigInt, Sign};
+
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use self::exsss::State;
+
+fn module() -> Atom {
+    Atom::from_str("rand")
+}
+
+fn seed_key() -> Term {
+    atom!("rand_seed")
+}
+
+/// `rand:seed(Alg)`: seeds `alg` from entropy
+fn seed_from_alg(process: &Process, alg: Term) -> anyhow::Result<Term> {
+    alg_try_into_exsss(alg)?;
+
+    Ok(put_state(process, default_seed(process)))
+}
+
+/// `rand:seed(Alg, Seed)`
+fn seed_from_alg_seed(process: &Process, alg: Term, seed: Term) -> anyhow::Result<Term> {
+    alg_try_into_exsss(alg)?;
+
+    let state = match seed.decode().unwrap() {
+        TypedTerm::SmallInteger(_) | TypedTerm::BigInteger(_) => {
+            State::from_integer(integer_mask_64(seed)?)
+        }
+        TypedTerm::Tuple(tuple) if tuple.len() == 3 => State::from_tuple(
+            integer_mask_64(tuple[0])?,
+            integer_mask_64(tuple[1])?,
+            integer_mask_64(tuple[2])?,
+        ),
+        _ => {
+            return Err(anyhow!(
+                "seed ({}) is not an integer or a tuple of 3 integers",
+                seed
+            ))
+        }
+    };
+
+    Ok(put_state(process, state))
+}
+
+/// Only `exsss` and `default`, which is `exsss`, are supported
+fn alg_try_into_exsss(alg: Term) -> anyhow::Result<()> {
+    let alg_atom: Atom = alg
+        .try_into()
+        .with_context(|| format!("alg ({}) is not an atom", alg))?;
+
+    match alg_atom.name() {
+        "default" | "exsss" => Ok(()),
+        _ => Err(anyhow!(
+            "alg ({}) is not a supported algorithm (`default` or `exsss`)",
+            alg
+        )),
+    }
+}
+
+/// `?MASK(64, X)`: the low 64 bits of the two's complement of `X`
+fn integer_mask_64(term: Term) -> anyhow::Result<u64> {
+    let big_int: BigInt = term
+        .try_into()
+        .with_context(|| format!("seed ({}) is not an integer", term))?;
+    let (sign, digits) = big_int.to_u64_digits();
+    let low = digits.first().copied().unwrap_or(0);
+
+    Ok(match sign {
+        Sign::Minus => low.wrapping_neg(),
+        _ => low,
+    })
+}
+
+/// The state in the process dictionary, seeding it from entropy first if there is none
+fn get_state(process: &Process) -> anyhow::Result<State> {
+    let term = process.get_value_from_key(seed_key());
+
+    if term == atom!("undefined") {
+        Ok(default_seed(process))
+    } else {
+        state_try_from_term(term)
+            .with_context(|| format!("rand_seed ({}) in process dictionary is not a state", term))
+    }
+}
+
+fn put_state(process: &Process, state: State) -> Term {
+    let term = state_to_term(process, state);
+    process.put(seed_key(), term);
+
+    term
+}
+
+/// Runs `f` with the state from the process dictionary and stores the advanced state
+fn with_state<T, F: FnOnce(&mut State) -> T>(process: &Process, f: F) -> anyhow::Result<T> {
+    let mut state = get_state(process)?;
+    let output = f(&mut state);
+    put_state(process, state);
+
+    Ok(output)
+}
+
+fn state_to_term(process: &Process, state: State) -> Term {
+    let alg_handler = process.map_from_slice(&[
+        (atom!("bits"), process.integer(exsss::BITS)),
+        (atom!("type"), atom!("exsss")),
+    ]);
+    let alg_state = process.cons(process.integer(state.s1), process.integer(state.s0));
+
+    process.tuple_from_slice(&[alg_handler, alg_state])
+}
+
+/// Accepts both the state, `{#{type => exsss, ...}, [S1 | S0]}`, and the exported state,
+/// `{exsss, [S1 | S0]}`
+fn state_try_from_term(term: Term) -> anyhow::Result<State> {
+    let tuple: Boxed<Tuple> = term
+        .try_into()
+        .with_context(|| format!("state ({}) is not a tuple", term))?;
+
+    if tuple.len() != 2 {
+        return Err(anyhow!("state ({}) is not a 2-tuple", term));
+    }
+
+    let alg = match tuple[0].decode().unwrap() {
+        TypedTerm::Map(alg_handler) => alg_handler
+            .get(atom!("type"))
+            .ok_or_else(|| anyhow!("alg handler ({}) is missing type", tuple[0]))?,
+        _ => tuple[0],
+    };
+    alg_try_into_exsss(alg)?;
+
+    let alg_state: Boxed<Cons> = tuple[1].try_into()?;
+    let state = State {
+        s1: alg_state.head.try_into()?,
+        s0: alg_state.tail.try_into()?,
+    };
+
+    if state.is_valid() {
+        Ok(state)
+    } else {
+        Err(anyhow!("alg state ({}) is not two 58-bit words", tuple[1]))
+    }
+}
+
+/// Seeds from the process, the time and a counter, like `rand:seed/1`'s
+/// `{erlang:phash2([{node(), self()}]), erlang:system_time(), erlang:unique_integer()}`
+fn default_seed(process: &Process) -> State {
+    let mut hasher = DefaultHasher::new();
+    process.pid().hash(&mut hasher);
+
+    let system_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0);
+
+    State::from_tuple(
+        hasher.finish(),
+        system_time,
+        UNIQUE.fetch_add(1, Ordering::Relaxed),
+    )
+}
+
+static UNIQUE: AtomicU64 = AtomicU64::new(0);
@@ -0,0 +1,227 @@
+//! The `exsss` algorithm: Xorshift116** on 58-bit words, as used by OTP's `rand` as its default
+//! algorithm.
+//!
+//! Every step mirrors `rand.erl`, so that the same seed produces the same sequence as C-BEAM.
+//!
+//! - https://github.com/erlang/otp/blob/OTP-25.0/lib/stdlib/src/rand.erl
+
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+
+/// The number of bits produced by each step
+pub const BITS: u32 = 58;
+
+const MASK_58: u64 = (1 << BITS) - 1;
+
+/// The two 58-bit words of state, stored in Erlang as the improper list `[S1 | S0]`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct State {
+    pub s1: u64,
+    pub s0: u64,
+}
+
+impl State {
+    /// `rand:seed(exsss, Integer)`
+    pub fn from_integer(x: u64) -> Self {
+        let (s1, x1) = seed58(x);
+        let (s0, _) = seed58(x1);
+
+        Self { s1, s0 }
+    }
+
+    /// `rand:seed(exsss, {A1, A2, A3})`
+    pub fn from_tuple(a1: u64, a2: u64, a3: u64) -> Self {
+        let (_, x0) = seed58(a1);
+        let (s1, x1) = seed58(a2 ^ x0);
+        let (s0, _) = seed58(a3 ^ x1);
+
+        Self { s1, s0 }
+    }
+
+    /// Both words must be 58-bit and they can't both be zero or the generator only produces zeros
+    pub fn is_valid(&self) -> bool {
+        self.s1 <= MASK_58 && self.s0 <= MASK_58 && (self.s1 != 0 || self.s0 != 0)
+    }
+
+    /// The next 58-bit value
+    pub fn next(&mut self) -> u64 {
+        let Self { s1, s0 } = *self;
+
+        let s1_b = s1 ^ bsl(s1, 24);
+        self.s1 = s0;
+        self.s0 = s1_b ^ s0 ^ (s1_b >> 11) ^ (s0 >> 41);
+
+        // StarStar scrambler, `s0 * 5` then rotate then `* 9`, all in 58 bits
+        let v_a = (s0 + bsl(s0, 2)) & MASK_58;
+        let v_b = rotl(v_a, 7);
+
+        (v_b + bsl(v_b, 3)) & MASK_58
+    }
+
+    /// `rand:uniform/0`: a float in `0.0 =< X < 1.0` of the form `N * 2^-53`
+    pub fn uniform(&mut self) -> f64 {
+        ((self.next() >> (BITS - 53)) as f64) * pow2(-53)
+    }
+
+    /// `rand:uniform/1` for `1 =< range =< 2^58`
+    pub fn uniform_u64(&mut self, range: u64) -> u64 {
+        debug_assert!(1 <= range && range <= (1 << BITS));
+
+        let max_minus_range = (1 << BITS) - range;
+
+        loop {
+            let v = self.next();
+
+            // Really work saving in odd cases; large ranges in particular
+            if v < range {
+                break v + 1;
+            }
+
+            let i = v % range;
+
+            // Otherwise `v` is in the truncated top range, so retry
+            if v - i <= max_minus_range {
+                break i + 1;
+            }
+        }
+    }
+
+    /// `rand:uniform/1` for any `1 =< range`, drawing as many words as needed to cover `range`
+    pub fn uniform_big_int(&mut self, range: &BigInt) -> BigInt {
+        let max: BigInt = BigInt::one() << BITS;
+
+        if range <= &max {
+            let range_u64 = u64::try_from(range).unwrap();
+
+            return self.uniform_u64(range_u64).into();
+        }
+
+        let range_minus_1 = range - 1;
+        let mut v = self.next();
+
+        if (range & &range_minus_1).is_zero() {
+            // Power of 2, so generate at least the number of bits for the range
+            let (v1, _) = self.extend(range >> BITS, v);
+
+            (v1 & range_minus_1) + 1
+        } else {
+            // Generate a value with at least two bits more than the range so that the probability
+            // of drawing a value in the truncated top range, and having to retry, is under 0.25
+            loop {
+                let (v1, bits) = self.extend(range >> (BITS - 2), v);
+                let i = &v1 % range;
+
+                if (&v1 - &i) <= ((BigInt::one() << bits) - range) {
+                    break i + 1;
+                }
+
+                v = self.next();
+            }
+        }
+    }
+
+    /// Shifts in new words below `v` until `range` is exhausted, returning the value and its bits
+    fn extend(&mut self, mut range: BigInt, v: u64) -> (BigInt, u32) {
+        let mut v = BigInt::from(v);
+        let mut bits = BITS;
+
+        while range > BigInt::one() {
+            v = (v << BITS) | BigInt::from(self.next());
+            range >>= BITS;
+            bits += BITS;
+        }
+
+        (v, bits)
+    }
+
+    /// `rand:uniform_real/0`: a float in `0.0 < X < 1.0` using all 53 bits of mantissa, so that
+    /// values close to 0.0 have the same precision as values close to 1.0
+    pub fn uniform_real(&mut self) -> f64 {
+        'start: loop {
+            let v1 = self.next();
+
+            if let Some(float) = top_56_bits(v1, 0) {
+                break float;
+            }
+
+            let mut m0 = v1 >> (BITS - 56);
+            let mut bit_no: i32 = -56;
+            let mut v1 = self.next();
+
+            loop {
+                if bit_no == -1064 || m0 != 0 {
+                    // Fill in the bits missing from `m0` with the top of `v1`
+                    let b0 = 53 - (64 - m0.leading_zeros());
+
+                    break 'start (((m0 << b0) | (v1 >> (BITS - b0))) as f64)
+                        * pow2(bit_no - b0 as i32);
+                }
+
+                if let Some(float) = top_56_bits(v1, bit_no) {
+                    break 'start float;
+                }
+
+                let m1 = v1 >> (BITS - 56);
+
+                // For the last round there can't be 14 zeros or more at the top of `m1`, because
+                // then the result would underflow 2^-1022, so start all over
+                if bit_no == -1008 && m1 < (1 << 42) {
+                    continue 'start;
+                }
+
+                m0 = m1;
+                bit_no -= 56;
+                v1 = self.next();
+            }
+        }
+    }
+}
+
+/// Converts the top 56 bits of `v` if they have at least 53 significant bits
+fn top_56_bits(v: u64, bit_no: i32) -> Option<f64> {
+    let m1 = v >> (BITS - 56);
+
+    (0..4).rev().find_map(|shift| {
+        if (1 << (52 + shift)) <= m1 {
+            Some(((m1 >> shift) as f64) * pow2(bit_no - 56 + shift as i32))
+        } else {
+            None
+        }
+    })
+}
+
+/// `?BSL(58, X, N)`: shift left discarding the bits shifted past 58
+fn bsl(x: u64, n: u32) -> u64 {
+    (x & ((1 << (BITS - n)) - 1)) << n
+}
+
+/// `?ROTL(58, X, N)`
+fn rotl(x: u64, n: u32) -> u64 {
+    bsl(x, n) | (x >> (BITS - n))
+}
+
+/// Seeds a 58-bit word from SplitMix64, skipping zero words
+fn seed58(mut x: u64) -> (u64, u64) {
+    loop {
+        let (z, next_x) = splitmix64_next(x);
+        x = next_x;
+
+        let word = z & MASK_58;
+
+        if word != 0 {
+            break (word, x);
+        }
+    }
+}
+
+fn splitmix64_next(x: u64) -> (u64, u64) {
+    let x = x.wrapping_add(0x9e3779b97f4a7c15);
+    let z = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+    (z ^ (z >> 31), x)
+}
+
+fn pow2(exponent: i32) -> f64 {
+    2.0_f64.powi(exponent)
+}
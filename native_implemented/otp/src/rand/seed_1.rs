@@ -0,0 +1,20 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::rand::{put_state, seed_from_alg, state_try_from_term};
+
+/// Seeds from entropy when given an algorithm, or restores a state or exported state
+#[native_implemented::function(rand:seed/1)]
+pub fn result(process: &Process, alg_or_state: Term) -> exception::Result<Term> {
+    if alg_or_state.is_atom() {
+        seed_from_alg(process, alg_or_state).map_err(From::from)
+    } else {
+        let state = state_try_from_term(alg_or_state)?;
+
+        Ok(put_state(process, state))
+    }
+}
@@ -0,0 +1,60 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::rand::{seed_1, seed_2, uniform_1};
+use crate::test::with_process;
+
+#[test]
+fn with_alg_seeds_from_entropy() {
+    with_process(|process| {
+        let first = seed_1::result(process, Atom::str_to_term("exsss")).unwrap();
+        let second = seed_1::result(process, Atom::str_to_term("exsss")).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(
+            process.get_value_from_key(Atom::str_to_term("rand_seed")),
+            second
+        );
+    });
+}
+
+#[test]
+fn with_state_restores_sequence() {
+    with_process(|process| {
+        let state =
+            seed_2::result(process, Atom::str_to_term("exsss"), process.integer(7)).unwrap();
+        let n = process.integer(1_000_000);
+        let first = uniform_1::result(process, n).unwrap();
+
+        assert_eq!(seed_1::result(process, state), Ok(state));
+        assert_eq!(uniform_1::result(process, n), Ok(first));
+    });
+}
+
+#[test]
+fn with_exported_state_restores_sequence() {
+    with_process(|process| {
+        let state =
+            seed_2::result(process, Atom::str_to_term("exsss"), process.integer(7)).unwrap();
+        let state_tuple: Boxed<Tuple> = state.try_into().unwrap();
+        let exported = process.tuple_from_slice(&[Atom::str_to_term("exsss"), state_tuple[1]]);
+        let n = process.integer(1_000_000);
+        let first = uniform_1::result(process, n).unwrap();
+
+        assert_eq!(seed_1::result(process, exported), Ok(state));
+        assert_eq!(uniform_1::result(process, n), Ok(first));
+    });
+}
+
+#[test]
+fn with_all_zero_state_errors_badarg() {
+    with_process(|process| {
+        let exported = process.tuple_from_slice(&[
+            Atom::str_to_term("exsss"),
+            process.cons(process.integer(0), process.integer(0)),
+        ]);
+
+        assert_badarg!(seed_1::result(process, exported), "is not two 58-bit words");
+    });
+}
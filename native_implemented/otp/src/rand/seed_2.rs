@@ -0,0 +1,13 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::rand::seed_from_alg_seed;
+
+#[native_implemented::function(rand:seed/2)]
+pub fn result(process: &Process, alg: Term, seed: Term) -> exception::Result<Term> {
+    seed_from_alg_seed(process, alg, seed).map_err(From::from)
+}
@@ -0,0 +1,98 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::rand::seed_2::result;
+use crate::test::with_process;
+
+// `rand:seed(exsss, 42)` in OTP 25
+#[test]
+fn with_exsss_and_integer_returns_state() {
+    with_process(|process| {
+        let state = result(process, Atom::str_to_term("exsss"), process.integer(42)).unwrap();
+
+        assert_eq!(
+            alg_state(state),
+            process.cons(
+                process.integer(132629853624823445_u64),
+                process.integer(67522330609774851_u64)
+            )
+        );
+        assert_eq!(
+            process.get_value_from_key(Atom::str_to_term("rand_seed")),
+            state
+        );
+    });
+}
+
+// `rand:seed(exsss, {1, 2, 3})` in OTP 25
+#[test]
+fn with_exsss_and_tuple_returns_state() {
+    with_process(|process| {
+        let seed =
+            process.tuple_from_slice(&[process.integer(1), process.integer(2), process.integer(3)]);
+        let state = result(process, Atom::str_to_term("exsss"), seed).unwrap();
+
+        assert_eq!(
+            alg_state(state),
+            process.cons(
+                process.integer(117085240290607817_u64),
+                process.integer(199386643319833935_u64)
+            )
+        );
+    });
+}
+
+#[test]
+fn with_default_is_exsss() {
+    with_process(|process| {
+        let seed = process.integer(42);
+
+        assert_eq!(
+            result(process, Atom::str_to_term("default"), seed),
+            result(process, Atom::str_to_term("exsss"), seed)
+        );
+    });
+}
+
+#[test]
+fn with_negative_integer_masks_to_64_bits() {
+    with_process(|process| {
+        let alg = Atom::str_to_term("exsss");
+
+        assert_eq!(
+            result(process, alg, process.integer(-1)),
+            result(process, alg, process.integer(u64::MAX))
+        );
+    });
+}
+
+#[test]
+fn with_unsupported_alg_errors_badarg() {
+    with_process(|process| {
+        let alg = Atom::str_to_term("exrop");
+
+        assert_badarg!(
+            result(process, alg, process.integer(42)),
+            format!("alg ({}) is not a supported algorithm", alg)
+        );
+    });
+}
+
+#[test]
+fn with_non_integer_seed_errors_badarg() {
+    with_process(|process| {
+        let seed = Atom::str_to_term("seed");
+
+        assert_badarg!(
+            result(process, Atom::str_to_term("exsss"), seed),
+            format!("seed ({}) is not an integer or a tuple of 3 integers", seed)
+        );
+    });
+}
+
+fn alg_state(state: Term) -> Term {
+    let tuple: Boxed<Tuple> = state.try_into().unwrap();
+
+    tuple[1]
+}
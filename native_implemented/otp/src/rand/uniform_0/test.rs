@@ -0,0 +1,57 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::rand::{seed_2, uniform_0::result};
+use crate::test::with_process;
+
+// `rand:seed(exsss, 42), [rand:uniform() || _ <- lists:seq(1, 20)]` in OTP 25
+#[test]
+fn with_seed_returns_same_floats_as_otp() {
+    with_process(|process| {
+        seed_2::result(process, Atom::str_to_term("exsss"), process.integer(42)).unwrap();
+        let actual: Vec<Term> = (0..20).map(|_| result(process).unwrap()).collect();
+        let expected: Vec<Term> = [
+            0.3672301478324621,
+            0.899364294071664,
+            0.008882807305278462,
+            0.8979947493669225,
+            0.8303407866585315,
+            0.5665765936300625,
+            0.8198691259725277,
+            0.767517848863382,
+            0.2397222378779541,
+            0.13787877441800678,
+            0.3892631373883074,
+            0.2576297565419873,
+            0.6533447156039618,
+            0.5025987601700626,
+            0.44111980517765037,
+            0.7341651834616125,
+            0.8899748094923234,
+            0.7552430724713219,
+            0.4954069909002813,
+            0.04839930767891365,
+        ]
+        .iter()
+        .map(|f| process.float(*f))
+        .collect();
+
+        assert_eq!(actual, expected);
+    });
+}
+
+#[test]
+fn without_seed_seeds_from_entropy() {
+    with_process(|process| {
+        process.erase_value_from_key(Atom::str_to_term("rand_seed"));
+
+        let float: f64 = result(process).unwrap().try_into().unwrap();
+
+        assert!(0.0 <= float && float < 1.0);
+        assert_ne!(
+            process.get_value_from_key(Atom::str_to_term("rand_seed")),
+            Atom::str_to_term("undefined")
+        );
+    });
+}
@@ -0,0 +1,29 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use std::convert::TryInto;
+
+use anyhow::*;
+use num_bigint::BigInt;
+use num_traits::One;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::rand::with_state;
+use crate::runtime::context::*;
+
+/// Returns an integer in `1 =< X =< N`, which may be a big integer
+#[native_implemented::function(rand:uniform/1)]
+pub fn result(process: &Process, n: Term) -> exception::Result<Term> {
+    let range: BigInt = n.try_into().with_context(|| term_is_not_integer("n", n))?;
+
+    if range < BigInt::one() {
+        return Err(anyhow!("n ({}) is not at least 1", n).into());
+    }
+
+    let integer = with_state(process, |state| state.uniform_big_int(&range))?;
+
+    Ok(process.integer(integer))
+}
@@ -0,0 +1,120 @@
+use std::convert::TryInto;
+
+use num_bigint::BigInt;
+
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::rand::{seed_2, uniform_1::result};
+use crate::test::with_process;
+
+// `rand:seed(exsss, 42), [rand:uniform(1000) || _ <- lists:seq(1, 20)]` in OTP 25
+#[test]
+fn with_seed_returns_same_integers_as_otp() {
+    with_process(|process| {
+        seed(process);
+        let n = process.integer(1000);
+        let actual: Vec<Term> = (0..20).map(|_| result(process, n).unwrap()).collect();
+        let expected: Vec<Term> = [
+            294, 431, 615, 198, 771, 458, 832, 264, 842, 111, 320, 936, 44, 92, 979, 44, 402, 648,
+            714, 722,
+        ]
+        .iter()
+        .map(|i| process.integer(*i))
+        .collect();
+
+        assert_eq!(actual, expected);
+    });
+}
+
+// `rand:seed(exsss, 42), [rand:uniform(1 bsl 100) || _ <- lists:seq(1, 3)]` in OTP 25
+#[test]
+fn with_power_of_2_big_integer_returns_same_integers_as_otp() {
+    with_process(|process| {
+        seed(process);
+        let n = process.integer(BigInt::from(1) << 100);
+
+        for expected in &[
+            "1007742103813162524825312912607",
+            "182110125860585667389907724182",
+            "271016667331692323436933669546",
+        ] {
+            assert_eq!(result(process, n), Ok(big_integer(process, expected)));
+        }
+    });
+}
+
+// `rand:seed(exsss, 42), [rand:uniform(1000000000000000000000000000000) || _ <- lists:seq(1, 3)]`
+// in OTP 25
+#[test]
+fn with_big_integer_returns_same_integers_as_otp() {
+    with_process(|process| {
+        seed(process);
+        let n = big_integer(process, "1000000000000000000000000000000");
+
+        for expected in &[
+            "287087196381938944484653491423",
+            "954759458690097338471173253014",
+            "13729286891033569535260615338",
+        ] {
+            assert_eq!(result(process, n), Ok(big_integer(process, expected)));
+        }
+    });
+}
+
+#[test]
+fn with_1_returns_1() {
+    with_process(|process| {
+        let one = process.integer(1);
+
+        for _ in 0..10 {
+            assert_eq!(result(process, one), Ok(one));
+        }
+    });
+}
+
+#[test]
+fn with_n_returns_integer_between_1_and_n() {
+    with_process(|process| {
+        for n in &[2_u64, 3, 1 << 57, (1 << 58) - 1, 1 << 58, (1 << 58) + 1] {
+            let n_term = process.integer(*n);
+
+            for _ in 0..10 {
+                let x: u64 = result(process, n_term).unwrap().try_into().unwrap();
+
+                assert!(1 <= x && x <= *n);
+            }
+        }
+    });
+}
+
+#[test]
+fn with_less_than_1_errors_badarg() {
+    with_process(|process| {
+        for n in &[0, -1] {
+            let n_term = process.integer(*n);
+
+            assert_badarg!(
+                result(process, n_term),
+                format!("n ({}) is not at least 1", n_term)
+            );
+        }
+    });
+}
+
+#[test]
+fn without_integer_errors_badarg() {
+    with_process(|process| {
+        let n = process.float(10.0);
+
+        assert_badarg!(result(process, n), format!("n ({}) is not an integer", n));
+    });
+}
+
+fn seed(process: &Process) {
+    seed_2::result(process, Atom::str_to_term("exsss"), process.integer(42)).unwrap();
+}
+
+fn big_integer(process: &Process, s: &str) -> Term {
+    process.integer(s.parse::<BigInt>().unwrap())
+}
@@ -0,0 +1,15 @@
+#[cfg(all(not(target_arch = "wasm32"), test))]
+mod test;
+
+use liblumen_alloc::erts::exception;
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::rand::with_state;
+
+#[native_implemented::function(rand:uniform_real/0)]
+pub fn result(process: &Process) -> exception::Result<Term> {
+    let float = with_state(process, |state| state.uniform_real())?;
+
+    Ok(process.float(float))
+}
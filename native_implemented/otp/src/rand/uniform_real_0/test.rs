@@ -0,0 +1,56 @@
+use std::convert::TryInto;
+
+use liblumen_alloc::erts::term::prelude::*;
+
+use crate::rand::{seed_2, uniform_real_0::result};
+use crate::test::with_process;
+
+// `rand:seed(exsss, 42), [rand:uniform_real() || _ <- lists:seq(1, 20)]` in OTP 25
+//
+// The third value is under 2^-4, so more bits are drawn to fill its mantissa, which is why the
+// values after it are shifted by one compared to `rand:uniform/0`.
+#[test]
+fn with_seed_returns_same_floats_as_otp() {
+    with_process(|process| {
+        seed_2::result(process, Atom::str_to_term("exsss"), process.integer(42)).unwrap();
+        let actual: Vec<Term> = (0..20).map(|_| result(process).unwrap()).collect();
+        let expected: Vec<Term> = [
+            0.36723014783246216,
+            0.899364294071664,
+            0.008882807305278571,
+            0.8303407866585315,
+            0.5665765936300625,
+            0.8198691259725277,
+            0.767517848863382,
+            0.23972223787795413,
+            0.13787877441800678,
+            0.3892631373883075,
+            0.25762975654198733,
+            0.6533447156039618,
+            0.5025987601700626,
+            0.44111980517765037,
+            0.7341651834616125,
+            0.8899748094923234,
+            0.7552430724713219,
+            0.4954069909002813,
+            0.048399307678913685,
+            0.9553583644320797,
+        ]
+        .iter()
+        .map(|f| process.float(*f))
+        .collect();
+
+        assert_eq!(actual, expected);
+    });
+}
+
+#[test]
+fn returns_float_greater_than_0_and_less_than_1() {
+    with_process(|process| {
+        for _ in 0..100 {
+            let float: f64 = result(process).unwrap().try_into().unwrap();
+
+            assert!(0.0 < float && float < 1.0);
+        }
+    });
+}
@@ -1,5 +1,7 @@
 pub mod anonymous_0;
 pub mod anonymous_1;
+pub mod busy_loop_0;
+pub mod distribution;
 mod init;
 pub mod loop_0;
 pub mod process;
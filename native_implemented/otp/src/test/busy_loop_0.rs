@@ -0,0 +1,13 @@
+use liblumen_alloc::erts::process::Process;
+use liblumen_alloc::erts::term::prelude::*;
+
+pub use super::module;
+
+/// Does a reduction and loops, so the process only stops running when it is out of reductions
+#[native_implemented::function(test:busy_loop/0)]
+fn result(process: &Process) -> Term {
+    process.reduce();
+    process.queue_frame_with_arguments(frame().with_arguments(false, &[]));
+
+    Term::NONE
+}
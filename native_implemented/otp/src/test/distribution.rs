@@ -0,0 +1,79 @@
+use std::sync::{Arc, Mutex};
+
+use lazy_static::lazy_static;
+
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::Node;
+
+use crate::runtime::distribution::transport::{self, DistributionTransport};
+
+/// Records the messages it is asked to send instead of carrying them to other nodes.
+pub struct MockTransport {
+    // Returned by `connect` for all nodes, so `None` makes all nodes unreachable
+    creation: Option<u32>,
+    sent: Mutex<Vec<SentToNode>>,
+}
+
+impl MockTransport {
+    pub fn new(creation: Option<u32>) -> Arc<Self> {
+        Arc::new(Self {
+            creation,
+            sent: Default::default(),
+        })
+    }
+
+    pub fn sent(&self) -> Vec<SentToNode> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+impl DistributionTransport for MockTransport {
+    fn connect(&self, _name: Atom) -> Option<u32> {
+        self.creation
+    }
+
+    fn send_to_node(&self, node: &Arc<Node>, control_message: Vec<u8>, payload: Vec<u8>) {
+        self.sent.lock().unwrap().push(SentToNode {
+            node: node.clone(),
+            control_message,
+            payload,
+        });
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SentToNode {
+    pub node: Arc<Node>,
+    pub control_message: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+
+/// Runs `f` with `option_transport` as the registered transport.
+///
+/// The transport is global, so all tests that depend on which transport is registered, including
+/// none, must use this to not run at the same time.
+pub fn with_transport<F>(option_transport: Option<Arc<dyn DistributionTransport>>, f: F)
+where
+    F: FnOnce(),
+{
+    let _guard = TRANSPORT_TEST_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    match option_transport {
+        Some(transport) => {
+            transport::register(transport);
+        }
+        None => {
+            transport::unregister();
+        }
+    }
+
+    f();
+
+    transport::unregister();
+}
+
+lazy_static! {
+    static ref TRANSPORT_TEST_LOCK: Mutex<()> = Mutex::new(());
+}
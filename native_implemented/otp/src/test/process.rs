@@ -8,7 +8,7 @@ use liblumen_alloc::erts::term::prelude::*;
 use crate::runtime::process::set_log_exit;
 use crate::runtime::process::spawn::Options;
 use crate::runtime::scheduler::{self, Scheduled, Spawned};
-use crate::{erlang, runtime};
+use crate::{erlang, lists, runtime};
 
 use super::loop_0;
 
@@ -22,8 +22,10 @@ pub fn init() -> Arc<Process> {
         erlang::exit_1::function_symbol(),
         erlang::number_or_badarith_1::function_symbol(),
         erlang::self_0::function_symbol(),
+        lists::reverse_2::function_symbol(),
         super::anonymous_0::function_symbol(),
         super::anonymous_1::function_symbol(),
+        super::busy_loop_0::function_symbol(),
         super::init::start_0::function_symbol(),
         loop_0::function_symbol(),
     ]);
@@ -18,6 +18,8 @@ use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::{exception, Node};
 
+use crate::runtime::distribution::nodes;
+use crate::runtime::process::handle_signals;
 use crate::test::strategy::term::binary;
 use crate::test::strategy::term::binary::sub::{bit_offset, byte_count, byte_offset};
 
@@ -68,24 +70,20 @@ pub fn arc_process_to_arc_process_subbinary_zero_start_byte_count_length(
 }
 
 pub fn external_arc_node() -> Arc<Node> {
-    Arc::new(Node::new(
-        1,
-        Atom::try_from_str("node@external").unwrap(),
-        0,
-    ))
+    nodes::get_or_insert(Atom::try_from_str("node@external").unwrap(), 0)
 }
 
+/// Signals sent to `process` are handled first, as its scheduler would before running it, so that
+/// any message they deliver, or any message sent behind them, is in the mailbox.
 pub fn has_message(process: &Process, data: Term) -> bool {
-    process.mailbox.lock().borrow().iter().any(|message| {
-        &data
-            == match message {
-                Message::Process(message::Process { data }) => data,
-                Message::HeapFragment(message::HeapFragment { data, .. }) => data,
-            }
-    })
+    handle_signals(process);
+
+    process.test_mailbox_snapshot().contains(&data)
 }
 
 pub fn has_heap_message(process: &Process, data: Term) -> bool {
+    handle_signals(process);
+
     process
         .mailbox
         .lock()
@@ -100,6 +98,8 @@ pub fn has_heap_message(process: &Process, data: Term) -> bool {
 }
 
 pub fn has_process_message(process: &Process, data: Term) -> bool {
+    handle_signals(process);
+
     process
         .mailbox
         .lock()
@@ -117,7 +117,10 @@ pub fn monitor_count(process: &Process) -> usize {
     process.monitor_by_reference.len()
 }
 
+/// Signals are handled first, as a `DOWN` signal removes the monitor when it is handled
 pub fn monitored_count(process: &Process) -> usize {
+    handle_signals(process);
+
     process.monitored_pid_by_reference.len()
 }
 
@@ -163,6 +166,8 @@ pub fn number_to_integer_with_float(
 }
 
 pub fn receive_message(process: &Process) -> Option<Term> {
+    handle_signals(process);
+
     process
         .mailbox
         .lock()
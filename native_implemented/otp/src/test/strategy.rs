@@ -40,10 +40,9 @@ pub fn byte_vec() -> BoxedStrategy<Vec<u8>> {
 pub fn milliseconds() -> BoxedStrategy<Milliseconds> {
     prop_oneof![
         Just(crate::runtime::timer::at_once_milliseconds()),
-        Just(crate::runtime::timer::soon_milliseconds()) /* TODO make timer::timeout() faster when there are lots of empty slots to skip,
-                                                         Just(crate::runtime::timer::later_milliseconds()),
-                                                         Just(crate::runtime::timer::long_term_milliseconds())
-                                                         */
+        Just(crate::runtime::timer::soon_milliseconds()),
+        Just(crate::runtime::timer::later_milliseconds()),
+        Just(crate::runtime::timer::long_term_milliseconds())
     ]
     .boxed()
 }
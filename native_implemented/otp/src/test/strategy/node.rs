@@ -1,5 +1,4 @@
 mod atom;
-mod id;
 
 use std::sync::Arc;
 
@@ -11,17 +10,7 @@ use liblumen_alloc::erts::Node;
 use crate::runtime::distribution::nodes;
 
 pub fn external() -> BoxedStrategy<Arc<Node>> {
-    (id::external(), atom::external(), any::<u32>())
-        .prop_map(|(id, atom, creation)| {
-            let arc_node = Arc::new(Node::new(id, atom, creation));
-
-            nodes::insert(arc_node.clone());
-
-            arc_node
-        })
+    (atom::external(), any::<u32>())
+        .prop_map(|(atom, creation)| nodes::get_or_insert(atom, creation))
         .boxed()
 }
-
-fn id() -> BoxedStrategy<usize> {
-    any::<usize>().boxed()
-}
@@ -3,3 +3,10 @@ test_stdout!(
     with_number_returns_non_negative,
     "18446744073709551616\n18446744073709551616\n1.2\n0.0\n3.4\n1\n0\n1\n"
 );
+test_stdout!(with_signed_zero_float_returns_positive_zero, "0.0\n0.0\n");
+test_stdout!(with_negative_float_returns_positive_float, "2.5\n");
+test_stdout!(
+    with_smallest_small_integer_returns_big_integer,
+    "true\ntrue\n"
+);
+test_stdout!(with_i64_min_returns_big_integer, "9223372036854775808\n");
@@ -0,0 +1,9 @@
+-module(init).
+-export([start/0]).
+-import(erlang, [display/1]).
+
+start() ->
+  %% Multiplied at runtime, so that the sign of the zero isn't lost
+  NegativeZero = test_float:zero() * -1.0,
+  display(abs(NegativeZero)),
+  display(abs(test_float:zero())).
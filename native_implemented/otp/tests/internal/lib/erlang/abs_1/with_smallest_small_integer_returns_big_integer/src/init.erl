@@ -0,0 +1,18 @@
+-module(init).
+-export([start/0]).
+-import(erlang, [display/1]).
+-import(lumen, [is_big_integer/1, is_small_integer/1]).
+
+start() ->
+  Smallest = smallest_small_integer(-1),
+  Abs = abs(Smallest),
+  display(is_big_integer(Abs)),
+  display(Abs =:= -Smallest).
+
+%% The smallest small integer is a power of two, but how large depends on the architecture
+smallest_small_integer(N) ->
+  Next = N * 2,
+  case is_small_integer(Next) of
+    true -> smallest_small_integer(Next);
+    false -> N
+  end.
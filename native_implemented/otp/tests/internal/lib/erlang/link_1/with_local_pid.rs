@@ -17,3 +17,11 @@ test_stdout!(
     when_the_process_does_not_exit_normal_linked_processes_exit_too,
     "true\n{parent, exited, abnormal}\n{child, exited, abnormal}\n"
 );
+test_stdout!(
+    when_a_linked_process_exits_normal_and_the_process_traps_exits_it_receives_exit_message,
+    "true\n{child, exited, normal}\n"
+);
+test_stdout!(
+    with_exited_pid_when_trapping_exits_receives_noproc_exit_message,
+    "true\n{child, exited, noproc}\n"
+);
@@ -0,0 +1,26 @@
+-module(init).
+-export([start/0]).
+-import(erlang, [display/1]).
+
+start() ->
+  process_flag(trap_exit, true),
+  ChildPid = spawn(fun () ->
+    wait_to_shutdown()
+  end),
+  display(link(ChildPid)),
+  shutdown(ChildPid),
+  receive
+    {'EXIT', ChildPid, Reason} ->
+      display({child, exited, Reason})
+  after
+    10 ->
+      display({child, alive, is_process_alive(ChildPid)})
+  end.
+
+shutdown(Pid) ->
+  Pid ! shutdown.
+
+wait_to_shutdown() ->
+  receive
+    shutdown -> ok
+  end.
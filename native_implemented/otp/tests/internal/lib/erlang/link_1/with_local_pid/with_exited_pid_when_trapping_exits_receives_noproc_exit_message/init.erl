@@ -0,0 +1,21 @@
+-module(init).
+-export([start/0]).
+-import(erlang, [display/1]).
+
+start() ->
+  process_flag(trap_exit, true),
+  {ChildPid, ChildMonitorReference} = spawn_monitor(fun () ->
+    ok
+  end),
+  receive
+    {'DOWN', ChildMonitorReference, process, _, _} ->
+      ok
+  end,
+  display(link(ChildPid)),
+  receive
+    {'EXIT', ChildPid, Reason} ->
+      display({child, exited, Reason})
+  after
+    10 ->
+      display(no_exit_message)
+  end.
@@ -6,3 +6,9 @@ test_substrings!(
     vec!["{parent, alive, true}"],
     vec!["Process (#PID<0.3.0>) exited abnormally.", "undef"]
 );
+
+test_substrings!(
+    with_unexported_function_when_run_exits_undef_and_parent_does_not_exit,
+    vec!["{parent, alive, true}"],
+    vec!["Process (#PID<0.3.0>) exited abnormally.", "undef"]
+);
@@ -0,0 +1,21 @@
+-module(init).
+-export([start/0]).
+-import(erlang, [display/1]).
+
+start() ->
+  Module = init,
+  %% Defined, but not exported
+  Function = private,
+  Args = [],
+  ChildPid = spawn(Module, Function, Args),
+  ChildMonitorReference = monitor(process, ChildPid),
+  receive
+    {'DOWN', ChildMonitorReference, process, _, _} ->
+      display({parent, alive, true})
+  after
+    10 ->
+      display({child, alive, is_process_alive(ChildPid)})
+  end.
+
+private() ->
+  display({child, ran}).
@@ -6,3 +6,7 @@ test_stdout!(
     with_normal_exit_in_child_process_does_not_exit_linked_parent_process,
     "{child, exited, normal}\n{parent, alive, true}\n"
 );
+test_stdout!(
+    when_child_exits_immediately_and_parent_traps_exits_receives_exit_message,
+    "{child, exited, abnormal}\n"
+);
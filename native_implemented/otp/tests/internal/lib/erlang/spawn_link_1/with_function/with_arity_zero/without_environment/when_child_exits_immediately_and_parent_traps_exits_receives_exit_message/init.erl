@@ -0,0 +1,17 @@
+-module(init).
+-export([start/0]).
+-import(erlang, [display/1]).
+-import(lumen, [log_exit/1]).
+
+start() ->
+  log_exit(false),
+  process_flag(trap_exit, true),
+  ChildPid = spawn_link(fun () ->
+    exit(abnormal)
+  end),
+  receive
+    {'EXIT', ChildPid, Reason} ->
+      display({child, exited, Reason})
+  after 10 ->
+    display(timeout)
+  end.
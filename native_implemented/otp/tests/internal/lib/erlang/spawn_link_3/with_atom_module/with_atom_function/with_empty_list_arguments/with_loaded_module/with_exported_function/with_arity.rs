@@ -7,3 +7,7 @@ test_substrings!(
     vec!["{in, child}", "{parent, exited, abnormal}"],
     vec!["Process (#PID<0.3.0>) exited abnormally.", "abnormal"]
 );
+test_stdout!(
+    when_child_exits_immediately_and_parent_traps_exits_receives_exit_message,
+    "{child, exited, abnormal}\n"
+);
@@ -7,3 +7,7 @@ test_substrings!(
     vec!["{child, exited, undef}", "{parent, alive, true}"],
     vec!["Process (#PID<0.3.0>) exited abnormally.", "undef"]
 );
+test_stdout!(
+    with_arity_when_child_exits_immediately_sends_down_message_to_parent,
+    "{child, exited, abnormal}\n"
+);
@@ -0,0 +1,17 @@
+-module(init).
+-export([start/0]).
+-import(erlang, [display/1]).
+-import(lumen, [log_exit/1]).
+
+start() ->
+  log_exit(false),
+  {ChildPid, ChildMonitorReference} = spawn_monitor(init, child, []),
+  receive
+    {'DOWN', ChildMonitorReference, process, ChildPid, Reason} ->
+      display({child, exited, Reason})
+  after 10 ->
+    display(timeout)
+  end.
+
+child() ->
+  exit(abnormal).
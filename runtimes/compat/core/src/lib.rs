@@ -0,0 +1,21 @@
+//! `lumen_rt_core` under its `firefly` name.
+//!
+//! The runtime crates are being renamed from `lumen_*` and `liblumen_*` to `firefly_*` one at a
+//! time.  Code written against the paths here keeps building as each crate's name flips, as only
+//! this crate's re-exports need to follow it.
+#![deny(warnings)]
+
+pub use lumen_rt_core::*;
+
+/// The runtime's process functions, along with the process types from `liblumen_alloc`
+pub mod process {
+    pub use liblumen_alloc::erts::process::{
+        Frame, FrameWithArguments, Native, Priority, Process, Status,
+    };
+    pub use lumen_rt_core::process::*;
+}
+
+/// The term types from `liblumen_alloc`
+pub mod term {
+    pub use liblumen_alloc::erts::term::*;
+}
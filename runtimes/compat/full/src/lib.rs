@@ -0,0 +1,27 @@
+//! `lumen_rt_full` under its `firefly` name.
+//!
+//! Along with everything `lumen_rt_full` exports, this re-exports the parts of `firefly_rt_core`
+//! that `lumen_rt_full` used to, so that one crate covers the scheduler, processes, terms, the
+//! registry and timers.  The old paths in `lumen_rt_full` are deprecated in favor of these, and are
+//! removed with the `strict-naming` feature.
+
+pub use lumen_rt_full::*;
+
+pub use firefly_rt_core::{registry, term, timer};
+
+/// The runtime's process functions, along with the process types from `liblumen_alloc`
+pub mod process {
+    pub use firefly_rt_core::process::*;
+    pub use lumen_rt_full::process::*;
+}
+
+/// The runtime's scheduler, along with the scheduler functions and traits from `firefly_rt_core`.
+///
+/// `Scheduler` is the runtime's scheduler, which implements the trait of the same name in
+/// `firefly_rt_core`, so the trait is re-exported as `SchedulerTrait`.
+pub mod scheduler {
+    pub use firefly_rt_core::scheduler::Scheduler as SchedulerTrait;
+    pub use firefly_rt_core::scheduler::*;
+    pub use lumen_rt_full::scheduler::Scheduler;
+    pub use lumen_rt_full::scheduler::*;
+}
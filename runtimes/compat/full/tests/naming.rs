@@ -0,0 +1,14 @@
+//! The old `lumen_rt_full` paths for modules that moved to `firefly_rt_full` are deprecated by
+//! default and removed with the `strict-naming` feature.
+
+#[cfg(not(feature = "strict-naming"))]
+#[test]
+fn old_paths_are_deprecated() {
+    trybuild::TestCases::new().compile_fail("tests/ui/deprecated/*.rs");
+}
+
+#[cfg(feature = "strict-naming")]
+#[test]
+fn old_paths_are_removed() {
+    trybuild::TestCases::new().compile_fail("tests/ui/strict_naming/*.rs");
+}
@@ -0,0 +1,64 @@
+//! How `examples/spawn-chain` spawns its chain of processes, written against only the `firefly_*`
+//! paths, so that this stops compiling if the facade loses anything the example needs, or if any
+//! of it is deprecated.
+#![deny(deprecated)]
+
+use std::sync::Arc;
+
+use firefly_rt_full::process::spawn::options::Options;
+use firefly_rt_full::process::Process;
+use firefly_rt_full::registry;
+use firefly_rt_full::scheduler::{self, Spawned};
+use firefly_rt_full::term::prelude::*;
+
+#[test]
+fn spawns_chain_of_linked_processes() {
+    let length = 16;
+    let chain = spawn_chain(length);
+
+    assert_eq!(chain.len(), length);
+
+    for pair in chain.windows(2) {
+        let (parent, child) = (&pair[0], &pair[1]);
+
+        assert!(parent.linked_pid_set.contains(&child.pid()));
+        assert!(child.linked_pid_set.contains(&parent.pid()));
+    }
+
+    for arc_process in chain.iter() {
+        let registered = registry::pid_to_process(&arc_process.pid()).unwrap();
+
+        assert!(Arc::ptr_eq(&registered, arc_process));
+    }
+}
+
+/// Spawns `length` processes, each from, and linked to, the one before it
+fn spawn_chain(length: usize) -> Vec<Arc<Process>> {
+    let scheduler = scheduler::current();
+    let mut chain: Vec<Arc<Process>> = Vec::with_capacity(length);
+
+    for _ in 0..length {
+        let parent = chain.last().map(|arc_process| arc_process.as_ref());
+        let mut options: Options = Default::default();
+        options.link = parent.is_some();
+
+        let Spawned {
+            arc_process,
+            connection,
+        } = scheduler
+            .spawn_module_function_arguments(
+                parent,
+                Atom::from_str("spawn_chain"),
+                Atom::from_str("link"),
+                vec![],
+                options,
+            )
+            .unwrap();
+
+        assert_eq!(connection.linked, parent.is_some());
+
+        chain.push(arc_process);
+    }
+
+    chain
+}
@@ -0,0 +1,7 @@
+#![deny(deprecated)]
+
+use lumen_rt_full::registry::pid_count;
+
+fn main() {
+    pid_count();
+}
@@ -0,0 +1,7 @@
+#![deny(deprecated)]
+
+use lumen_rt_full::timer::timeout;
+
+fn main() {
+    timeout();
+}
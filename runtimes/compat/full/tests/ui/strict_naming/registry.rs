@@ -0,0 +1,5 @@
+use lumen_rt_full::registry::pid_count;
+
+fn main() {
+    pid_count();
+}
@@ -0,0 +1,5 @@
+use lumen_rt_full::timer::timeout;
+
+fn main() {
+    timeout();
+}
@@ -0,0 +1,167 @@
+//! A small pool of threads for natives that block, such as on file I/O, so that they don't stall
+//! the scheduler running the calling process.
+//!
+//! The calling process waits while its operation runs on a pool thread, and is woken with
+//! `stop_waiting` once the result of the operation can be taken with [take].
+use std::any::Any;
+use std::ptr::NonNull;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use hashbrown::HashMap;
+use lazy_static::lazy_static;
+
+use liblumen_core::locks::Mutex;
+
+use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{CloneToProcess, HeapFragment, Process};
+
+use crate::registry;
+use crate::scheduler::Scheduled;
+
+/// The number of threads running blocking operations
+pub const THREADS: usize = 4;
+
+lazy_static! {
+    static ref POOL: Pool = Pool::new(THREADS);
+    static ref RESULT_BY_PID: Mutex<HashMap<Pid, Box<dyn Any + Send>>> = Mutex::new(HashMap::new());
+}
+
+/// Runs `operation` on a thread of the blocking pool and puts `process` in the waiting status
+/// until it completes.
+///
+/// `operation` is passed copies of `arguments`, so it never touches the heap of `process`, which
+/// keeps running other natives, and collecting, on its scheduler.  When `operation` returns,
+/// `process` is woken, and the native it continues with converts the result of [take] to a term
+/// on its own heap.  A result for a process that exited in the meantime is dropped.
+pub fn schedule_blocking<F, R>(
+    process: &Process,
+    arguments: &[Term],
+    operation: F,
+) -> AllocResult<()>
+where
+    F: FnOnce(&[Term]) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let arguments = Arguments::new(arguments)?;
+    let pid = process.pid();
+
+    // Waits before the operation is sent, so that the operation can't complete, and wake the
+    // process, before it is waiting
+    process.wait();
+
+    POOL.execute(Box::new(move || {
+        let result = operation(&arguments.terms);
+        drop(arguments);
+
+        complete(pid, Box::new(result));
+    }));
+
+    Ok(())
+}
+
+/// Takes the result of the blocking operation scheduled for `process`, or `None` if it hasn't
+/// completed yet, in which case `process` was woken by something else, such as a message, and
+/// should wait again.
+pub fn take<R: 'static>(process: &Process) -> Option<R> {
+    RESULT_BY_PID.lock().remove(&process.pid()).map(|result| {
+        *result
+            .downcast::<R>()
+            .expect("blocking operation result taken as a different type than it returned")
+    })
+}
+
+// Private
+
+fn complete(pid: Pid, result: Box<dyn Any + Send>) {
+    if let Some(arc_process) = registry::pid_to_process(&pid) {
+        RESULT_BY_PID.lock().insert(pid, result);
+
+        match arc_process.scheduler() {
+            Some(scheduler) => scheduler.stop_waiting(&arc_process),
+            None => {
+                arc_process.stop_waiting();
+            }
+        }
+    }
+}
+
+/// The arguments of a blocking operation, each copied to its own heap fragment, so that they
+/// outlive the heap of the process, which may be collected while the operation runs
+struct Arguments {
+    terms: Vec<Term>,
+    heap_fragments: Vec<NonNull<HeapFragment>>,
+}
+
+impl Arguments {
+    fn new(arguments: &[Term]) -> AllocResult<Self> {
+        let mut terms = Vec::with_capacity(arguments.len());
+        let mut heap_fragments = Vec::with_capacity(arguments.len());
+
+        for argument in arguments {
+            let (term, heap_fragment) = argument.clone_to_fragment()?;
+            terms.push(term);
+            heap_fragments.push(heap_fragment);
+        }
+
+        Ok(Self {
+            terms,
+            heap_fragments,
+        })
+    }
+}
+
+impl Drop for Arguments {
+    fn drop(&mut self) {
+        for heap_fragment in &self.heap_fragments {
+            unsafe { heap_fragment.as_ptr().drop_in_place() };
+        }
+    }
+}
+
+// Only accessed by the operation they are the arguments of
+unsafe impl Send for Arguments {}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Pool {
+    sender: Mutex<Sender<Job>>,
+}
+
+impl Pool {
+    fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for index in 0..threads {
+            let receiver = receiver.clone();
+
+            thread::Builder::new()
+                .name(format!("blocking-{}", index))
+                .spawn(move || Self::run(receiver))
+                .unwrap();
+        }
+
+        Self {
+            sender: Mutex::new(sender),
+        }
+    }
+
+    fn execute(&self, job: Job) {
+        self.sender.lock().send(job).unwrap();
+    }
+
+    fn run(receiver: Arc<Mutex<Receiver<Job>>>) {
+        loop {
+            // The lock is released before the job runs, so the other threads can take jobs
+            let received = receiver.lock().recv();
+
+            match received {
+                Ok(job) => job(),
+                Err(_) => break,
+            }
+        }
+    }
+}
@@ -1,2 +1,3 @@
 pub mod external_term_format;
 pub mod nodes;
+pub mod transport;
@@ -4,6 +4,7 @@ mod atom_utf8;
 mod big;
 mod binary;
 mod bit_binary;
+pub mod encode;
 mod export;
 mod f64;
 mod i32;
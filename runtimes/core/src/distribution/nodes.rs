@@ -1,6 +1,10 @@
+//! The table of the nodes known to this node, which is the only place that nodes are given ids.
+//!
+//! A node's id is how it is referred to in the formatted pids of `pid_to_list` and `list_to_pid`.
 pub mod node;
 
 use std::backtrace::Backtrace;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use hashbrown::HashMap;
@@ -49,29 +53,43 @@ pub fn try_id_to_arc_node(id: &usize) -> Result<Arc<Node>, NodeNotFound> {
     }
 }
 
-pub fn insert(arc_node: Arc<Node>) {
-    let id = arc_node.id();
-    let name = arc_node.name();
+/// Returns the node named `name` with `creation`, adding it to the table under a new id if it is not
+/// known.
+///
+/// A known name with a different `creation` is a new incarnation of that node, so it replaces the
+/// old incarnation in the table, but terms referring to the old incarnation keep referring to it.
+pub fn get_or_insert(name: Atom, creation: u32) -> Arc<Node> {
+    if name == node::atom() {
+        return node::arc_node();
+    }
 
     let mut arc_node_by_id = RW_LOCK_ARC_NODE_BY_ID.write();
     let mut arc_node_by_name = RW_LOCK_ARC_NODE_BY_NAME.write();
 
-    if let Some(id_arc_node) = arc_node_by_id.remove(&id) {
-        if let Some(id_name_arc_node) = arc_node_by_name.remove(&id_arc_node.name()) {
-            assert_eq!(id_name_arc_node.id(), id);
-        }
-    }
+    match arc_node_by_name.get(&name) {
+        Some(arc_node) if arc_node.creation() == creation => arc_node.clone(),
+        _ => {
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            let arc_node = Arc::new(Node::new(id, name, creation));
+
+            if let Some(name_arc_node) = arc_node_by_name.remove(&name) {
+                arc_node_by_id.remove(&name_arc_node.id());
+            }
 
-    if let Some(name_arc_node) = arc_node_by_name.remove(&name) {
-        if let Some(name_id_arc_node) = arc_node_by_id.remove(&name_arc_node.id()) {
-            assert_eq!(name_id_arc_node.name(), name);
+            arc_node_by_id.insert(id, arc_node.clone());
+            arc_node_by_name.insert(name, arc_node.clone());
+
+            arc_node
         }
     }
+}
 
-    assert!(arc_node_by_id
-        .insert(arc_node.id(), arc_node.clone())
-        .is_none());
-    assert!(arc_node_by_name.insert(arc_node.name(), arc_node).is_none());
+/// All the nodes in the table, including the local node, in the order they were added.
+pub fn known() -> Vec<Arc<Node>> {
+    let mut known: Vec<Arc<Node>> = RW_LOCK_ARC_NODE_BY_ID.read().values().cloned().collect();
+    known.sort_by_key(|arc_node| arc_node.id());
+
+    known
 }
 
 #[derive(Debug, Error)]
@@ -98,6 +116,10 @@ impl From<NodeNotFound> for RuntimeException {
     }
 }
 
+// Ids are never reused, so that a term can't refer to a different node than the one it was created
+// for.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(node::ID + 1);
+
 lazy_static! {
     static ref RW_LOCK_ARC_NODE_BY_ID: RwLock<HashMap<usize, Arc<Node>>> = {
         let mut hash_map = HashMap::new();
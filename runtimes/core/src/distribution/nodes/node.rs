@@ -32,4 +32,4 @@ pub fn term() -> Term {
 }
 
 const CREATION: u32 = 0;
-const ID: usize = 0;
+pub(super) const ID: usize = 0;
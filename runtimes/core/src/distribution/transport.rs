@@ -0,0 +1,201 @@
+//! The seam between the runtime and whatever carries messages to other nodes.
+//!
+//! Distribution itself is not implemented, so no transport is registered by default.  Without one,
+//! this node is not connected to any other, connecting to a node fails, and messages sent to other
+//! nodes are dropped, the same as messages sent to a process that is not alive.
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+
+use liblumen_core::locks::RwLock;
+
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::erts::Node;
+use liblumen_alloc::Process;
+
+use crate::distribution::external_term_format::encode::term_to_byte_vec;
+use crate::distribution::nodes::{self, node};
+
+/// Carries messages to other nodes.
+///
+/// The runtime calls `connect` the first time a node is needed, so a transport should report a
+/// lost connection with [node_down], after which the runtime will connect again when needed.
+pub trait DistributionTransport: Send + Sync {
+    /// Sets up a connection to the node named `name`, returning the creation of its current
+    /// incarnation, or `None` if it could not be reached.
+    fn connect(&self, name: Atom) -> Option<u32>;
+
+    /// Sends a message to connected `node`.  Both the `control_message` and the `payload` are
+    /// encoded in the external term format, starting with the version number.
+    fn send_to_node(&self, node: &Arc<Node>, control_message: Vec<u8>, payload: Vec<u8>);
+
+    /// Called after `node` is connected.
+    fn node_up(&self, _node: &Arc<Node>) {}
+
+    /// Called after `node` is disconnected.
+    fn node_down(&self, _node: &Arc<Node>) {}
+}
+
+/// Registers `transport` to carry messages to other nodes, returning the previous transport.
+///
+/// Nodes connected with the previous transport are disconnected.
+pub fn register(
+    transport: Arc<dyn DistributionTransport>,
+) -> Option<Arc<dyn DistributionTransport>> {
+    let previous = RW_LOCK_TRANSPORT.write().replace(transport);
+    disconnect_all(&previous);
+
+    previous
+}
+
+/// Unregisters the current transport, disconnecting all nodes, and returns it.
+pub fn unregister() -> Option<Arc<dyn DistributionTransport>> {
+    let previous = RW_LOCK_TRANSPORT.write().take();
+    disconnect_all(&previous);
+
+    previous
+}
+
+/// Whether a transport is registered.
+pub fn is_registered() -> bool {
+    RW_LOCK_TRANSPORT.read().is_some()
+}
+
+/// Connects to the node named `name` if it is not already connected.
+///
+/// Returns `false` if there is no transport or the transport could not reach the node.
+pub fn connect(name: Atom) -> bool {
+    if name == node::atom() {
+        is_registered()
+    } else {
+        transport_connected_to(name).is_some()
+    }
+}
+
+/// Marks the node named `name` as disconnected.  Called by the transport when it loses its
+/// connection to the node.
+pub fn node_down(name: Atom) {
+    let option_arc_node = {
+        let mut connected = RW_LOCK_CONNECTED.write();
+
+        connected
+            .iter()
+            .position(|arc_node| arc_node.name() == name)
+            .map(|index| connected.remove(index))
+    };
+
+    if let (Some(arc_node), Some(transport)) = (option_arc_node, current()) {
+        transport.node_down(&arc_node);
+    }
+}
+
+/// The nodes that are connected, in the order they were connected.
+pub fn connected() -> Vec<Arc<Node>> {
+    RW_LOCK_CONNECTED.read().clone()
+}
+
+/// Sends `message` to `external_pid`, which is the term `destination`, with a `SEND` control
+/// message, connecting to its node first if needed.
+///
+/// The message is dropped if there is no transport or the node can't be reached.
+pub fn send_to_pid(
+    destination: Term,
+    external_pid: &ExternalPid,
+    message: Term,
+    process: &Process,
+) {
+    if let Some((transport, arc_node)) = transport_connected_to(external_pid.arc_node().name()) {
+        // {SEND, Unused, ToPid}
+        let control_message =
+            process.tuple_from_slice(&[process.integer(SEND), Atom::str_to_term(""), destination]);
+
+        transport.send_to_node(
+            &arc_node,
+            term_to_byte_vec(control_message),
+            term_to_byte_vec(message),
+        );
+    }
+}
+
+/// Sends `message` to the process registered as `name` on the node named `node_name`, with a
+/// `REG_SEND` control message, connecting to the node first if needed.
+///
+/// The message is dropped if there is no transport or the node can't be reached.
+pub fn send_to_name(name: Atom, node_name: Atom, message: Term, process: &Process) {
+    if let Some((transport, arc_node)) = transport_connected_to(node_name) {
+        // {REG_SEND, FromPid, Unused, ToName}
+        let control_message = process.tuple_from_slice(&[
+            process.integer(REG_SEND),
+            process.pid_term(),
+            Atom::str_to_term(""),
+            name.encode().unwrap(),
+        ]);
+
+        transport.send_to_node(
+            &arc_node,
+            term_to_byte_vec(control_message),
+            term_to_byte_vec(message),
+        );
+    }
+}
+
+// Private
+
+// Control message operations from http://erlang.org/doc/apps/erts/erl_dist_protocol.html#control-messages
+const SEND: isize = 2;
+const REG_SEND: isize = 6;
+
+fn current() -> Option<Arc<dyn DistributionTransport>> {
+    RW_LOCK_TRANSPORT.read().clone()
+}
+
+fn disconnect_all(option_transport: &Option<Arc<dyn DistributionTransport>>) {
+    let disconnected = std::mem::take(&mut *RW_LOCK_CONNECTED.write());
+
+    if let Some(transport) = option_transport {
+        for arc_node in disconnected {
+            transport.node_down(&arc_node);
+        }
+    }
+}
+
+fn transport_connected_to(name: Atom) -> Option<(Arc<dyn DistributionTransport>, Arc<Node>)> {
+    let transport = current()?;
+
+    let option_connected = RW_LOCK_CONNECTED
+        .read()
+        .iter()
+        .find(|arc_node| arc_node.name() == name)
+        .cloned();
+
+    let arc_node = match option_connected {
+        Some(arc_node) => arc_node,
+        None => {
+            let creation = transport.connect(name)?;
+            let arc_node = nodes::get_or_insert(name, creation);
+
+            let mut connected = RW_LOCK_CONNECTED.write();
+
+            // Another process may have connected while the lock was released
+            match connected.iter().find(|connected| connected.name() == name) {
+                Some(connected_arc_node) => connected_arc_node.clone(),
+                None => {
+                    connected.push(arc_node.clone());
+                    drop(connected);
+
+                    transport.node_up(&arc_node);
+
+                    arc_node
+                }
+            }
+        }
+    };
+
+    Some((transport, arc_node))
+}
+
+lazy_static! {
+    static ref RW_LOCK_TRANSPORT: RwLock<Option<Arc<dyn DistributionTransport>>> =
+        RwLock::new(None);
+    static ref RW_LOCK_CONNECTED: RwLock<Vec<Arc<Node>>> = RwLock::new(Vec::new());
+}
@@ -7,10 +7,12 @@
 
 pub mod base;
 pub mod binary_to_string;
+pub mod blocking;
 pub mod builtins;
 pub mod context;
 pub mod distribution;
 pub mod integer_to_string;
+pub mod pg;
 pub mod process;
 pub mod proplist;
 pub mod registry;
@@ -0,0 +1,129 @@
+//! Process groups, like the default scope of OTP's `pg`, but only for the local node.
+//!
+//! A group is named by any term, compared with exact equality, and a process is a member of a
+//! group once for every time it joined it.
+use std::ptr::NonNull;
+
+use lazy_static::lazy_static;
+
+use liblumen_core::locks::Mutex;
+
+use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::{CloneToProcess, HeapFragment, Process};
+
+lazy_static! {
+    // In the order the groups were created, so that `which_groups` is stable
+    static ref GROUPS: Mutex<Vec<Group>> = Mutex::new(Vec::new());
+}
+
+/// Adds a membership of each of `pids` to the group named `name`, creating the group if needed.
+pub fn join(name: Term, pids: &[Pid]) -> AllocResult<()> {
+    let mut groups = GROUPS.lock();
+
+    match groups.iter_mut().find(|group| group.is_named(name)) {
+        Some(group) => group.members.extend_from_slice(pids),
+        None => {
+            let (name, heap_fragment) = name.clone_to_fragment()?;
+
+            groups.push(Group {
+                name,
+                heap_fragment,
+                members: pids.to_vec(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes one membership of each of `pids` from the group named `name`, removing the group when
+/// it has no members left.
+///
+/// Returns `false` if none of `pids` were members of the group.
+pub fn leave(name: Term, pids: &[Pid]) -> bool {
+    let mut groups = GROUPS.lock();
+
+    match groups.iter().position(|group| group.is_named(name)) {
+        Some(index) => {
+            let group = &mut groups[index];
+            let mut left = false;
+
+            for pid in pids {
+                if let Some(member_index) = group.members.iter().position(|member| member == pid) {
+                    group.members.remove(member_index);
+                    left = true;
+                }
+            }
+
+            if group.members.is_empty() {
+                groups.remove(index);
+            }
+
+            left
+        }
+        None => false,
+    }
+}
+
+/// Removes all the memberships of `pid`, so that a process that exits leaves all its groups.
+pub fn leave_all(pid: Pid) {
+    let mut groups = GROUPS.lock();
+
+    for group in groups.iter_mut() {
+        group.members.retain(|member| *member != pid);
+    }
+
+    groups.retain(|group| !group.members.is_empty());
+}
+
+/// Returns the members of the group named `name`, in the order they joined.
+///
+/// The members are a snapshot, so processes that join or leave after it is taken are not
+/// reflected in it.
+pub fn members(name: Term) -> Vec<Pid> {
+    GROUPS
+        .lock()
+        .iter()
+        .find(|group| group.is_named(name))
+        .map(|group| group.members.clone())
+        .unwrap_or_default()
+}
+
+/// Returns the list of group names, copied to the heap of `process`.
+pub fn which_groups(process: &Process) -> Term {
+    let name_vec: Vec<Term> = GROUPS
+        .lock()
+        .iter()
+        .map(|group| group.name.clone_to_process(process))
+        .collect();
+
+    process.list_from_slice(&name_vec)
+}
+
+// Private
+
+struct Group {
+    // Copied to `heap_fragment`, so that it outlives the process that created the group
+    name: Term,
+    heap_fragment: NonNull<HeapFragment>,
+    members: Vec<Pid>,
+}
+
+impl Group {
+    fn is_named(&self, name: Term) -> bool {
+        self.name
+            .decode()
+            .unwrap()
+            .exact_eq(&name.decode().unwrap())
+    }
+}
+
+impl Drop for Group {
+    fn drop(&mut self) {
+        unsafe { self.heap_fragment.as_ptr().drop_in_place() };
+    }
+}
+
+// Only accessed while `GROUPS` is locked
+unsafe impl Send for Group {}
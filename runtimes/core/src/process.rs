@@ -3,16 +3,21 @@ pub mod spawn;
 
 use std::cell::{Cell, RefCell};
 use std::convert::TryInto;
+use std::io::{self, Write};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use liblumen_alloc::erts::exception::{self, RuntimeException};
+use liblumen_alloc::erts::exception::{self, ArcError, RuntimeException};
 use liblumen_alloc::erts::process::alloc::{Heap, TermAlloc};
-use liblumen_alloc::erts::process::{Process, ProcessHeap};
+use liblumen_alloc::erts::process::trace::Trace;
+use liblumen_alloc::erts::process::{Process, ProcessHeap, Signal, SignalData, Status};
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::{atom, CloneToProcess, HeapFragment, Monitor};
 
+use crate::pg;
 use crate::registry::*;
 use crate::scheduler::{Scheduled, SchedulerDependentAlloc};
+use crate::timer;
 
 thread_local! {
   pub static CURRENT_PROCESS: RefCell<Option<Arc<Process>>> = RefCell::new(None);
@@ -26,6 +31,31 @@ pub fn maybe_current_process() -> Option<Arc<Process>> {
     CURRENT_PROCESS.with(|cp| cp.borrow().clone())
 }
 
+/// Natives that do work proportional to the size of their arguments call [consume_reductions]
+/// after each this many list cells they visit
+pub const CELLS_PER_CONSUME: usize = 1_000;
+
+/// Natives that copy data call [consume_reductions] after each this many bytes they copy
+pub const BYTES_PER_CONSUME: usize = 4_096;
+
+/// The reductions natives consume each time, so that a native visiting a million cells uses up
+/// the budget of many runs
+pub const REDUCTIONS_PER_CONSUME: u16 = 100;
+
+/// Charges `reductions` for work done by a native to the current process, returning `true` once
+/// the process has used up its budget.
+///
+/// A native that can then return a continuation should, so the process yields to the scheduler.
+/// One that can't runs on, and the reductions it overruns by come out of the next run of the
+/// process.  Nothing is charged when no process is running, such as when a native is called
+/// directly rather than by the scheduler.
+pub fn consume_reductions(reductions: u16) -> bool {
+    match maybe_current_process() {
+        Some(arc_process) => arc_process.consume_reductions(reductions),
+        None => false,
+    }
+}
+
 pub fn is_expected_exception(exception: &RuntimeException) -> bool {
     use exception::Class;
     match exception.class() {
@@ -88,82 +118,191 @@ pub fn monitor(process: &Process, monitored_process: &Process) -> Term {
 }
 
 pub fn propagate_exit(process: &Process, exception: Option<&RuntimeException>) {
+    timer::cancel_all(process.pid());
+    unregister_process(process);
+    pg::leave_all(process.pid());
     monitor::propagate_exit(process, exception);
     propagate_exit_to_links(process, exception);
 }
 
 pub fn propagate_exit_to_links(process: &Process, exception: Option<&RuntimeException>) {
-    // TODO: Shouldn't normal exits propagate?
-    if exception.is_none() {
+    // Collected first, so that no lock on this process's links is held while the linked processes
+    // are signalled, which could deadlock with a linked process exiting at the same time
+    let linked_pids: Vec<Pid> = process
+        .linked_pid_set
+        .iter()
+        .map(|linked_pid| *linked_pid.key())
+        .collect();
+
+    for linked_pid in linked_pids {
+        if let Some(linked_pid_arc_process) = pid_to_process(&linked_pid) {
+            propagate_exit_to_link(process, &linked_pid_arc_process, exception);
+        }
+    }
+}
+
+/// Signals the exit of `process` to `linked_process` over their link.
+///
+/// The signal removes the link, as `process` is gone, and only whoever removes the link from
+/// `process` sends the signal, so `linked_process` is signalled at most once even if the exit is
+/// propagated to it from more than one place, such as when `process` exits while spawning it.
+pub fn propagate_exit_to_link(
+    process: &Process,
+    linked_process: &Process,
+    exception: Option<&RuntimeException>,
+) {
+    if process
+        .linked_pid_set
+        .remove(&linked_process.pid())
+        .is_none()
+    {
         return;
     }
-    let exception = exception.unwrap();
-    if !is_expected_exception(exception) {
-        let tag = atom!("EXIT");
-        let from = process.pid_term();
-        let reason = exception.reason();
-        let reason_word_size = reason.size_in_words();
-        let exit_message_elements: &[Term] = &[tag, from, reason];
-        let exit_message_word_size = Tuple::need_in_words_from_elements(exit_message_elements);
-
-        for linked_pid in process.linked_pid_set.iter() {
-            if let Some(linked_pid_arc_process) = pid_to_process(linked_pid.key()) {
-                if linked_pid_arc_process.traps_exit() {
-                    match linked_pid_arc_process.try_acquire_heap() {
-                        Some(ref mut linked_pid_heap) => {
-                            if exit_message_word_size <= linked_pid_heap.heap_available() {
-                                send_self_exit_message(
-                                    &linked_pid_arc_process,
-                                    linked_pid_heap,
-                                    exit_message_elements,
-                                );
-                            } else {
-                                send_heap_exit_message(
-                                    &linked_pid_arc_process,
-                                    exit_message_elements,
-                                );
-                            }
-                        }
-                        None => {
-                            send_heap_exit_message(&linked_pid_arc_process, exit_message_elements);
-                        }
-                    }
-                } else {
-                    // only tell the linked process to exit.  When it is run by its scheduler, it
-                    // will go through propagating its own exit.
-                    match linked_pid_arc_process.try_acquire_heap() {
-                        Some(ref mut linked_pid_heap) => {
-                            if reason_word_size <= linked_pid_heap.heap_available() {
-                                exit_in_heap(
-                                    &linked_pid_arc_process,
-                                    linked_pid_heap,
-                                    reason,
-                                    exception.clone(),
-                                );
-                            } else {
-                                exit_in_heap_fragment(
-                                    &linked_pid_arc_process,
-                                    reason,
-                                    exception.clone(),
-                                );
-                            }
-                        }
-                        None => {
-                            exit_in_heap_fragment(
-                                &linked_pid_arc_process,
-                                reason,
-                                exception.clone(),
-                            );
-                        }
-                    }
-                }
 
-                linked_pid_arc_process
-                    .scheduler()
-                    .unwrap()
-                    .stop_waiting(&linked_pid_arc_process);
+    let (reason, trace, source) = match exception {
+        Some(exception) => (
+            exception.reason(),
+            exception.stacktrace(),
+            exception.source(),
+        ),
+        None => (atom!("normal"), Trace::capture(), None),
+    };
+
+    send_exit_signal(process.pid(), linked_process, reason, true, trace, source);
+}
+
+/// Sends an exit signal with `reason` from `sender` to `receiver`, as `exit(Pid, Reason)` does.
+///
+/// `kill` can't be trapped, so `receiver` is killed even if it traps exits, and exits with
+/// `killed`, so that the processes linked to it can trap its exit.  Any other `reason` is sent to
+/// a `receiver` trapping exits as an `{'EXIT', Sender, Reason}` message, and otherwise exits
+/// `receiver`, unless `reason` is `normal`.
+///
+/// The signal takes effect when the scheduler of `receiver` handles it, after any signal or
+/// message `sender` sent to `receiver` before it.
+pub fn exit_signal(sender: &Process, receiver: &Process, reason: Term) {
+    send_exit_signal(
+        sender.pid(),
+        receiver,
+        reason,
+        false,
+        Trace::capture(),
+        None,
+    );
+}
+
+/// Removes the link between `process` and `linked_process`, as `unlink/1` does.
+///
+/// `process` removes its side of the link at once, and `linked_process` when it handles the
+/// unlink signal, so any exit signal `linked_process` sends over the link in the meantime is
+/// ignored by `process`.
+pub fn unlink(process: &Process, linked_process: &Process) {
+    if process
+        .linked_pid_set
+        .remove(&linked_process.pid())
+        .is_some()
+    {
+        linked_process.send_signal(Signal::Unlink {
+            sender: process.pid(),
+        });
+    }
+}
+
+/// Sets the group leader of `process` to `group_leader`, as `group_leader/2` does for another
+/// process, once the scheduler of `process` handles the signal.
+pub fn group_leader_signal(process: &Process, group_leader: Pid) {
+    process.send_signal(Signal::GroupLeader { group_leader });
+}
+
+/// Handles the signals sent to `process` in the order they were sent, which its scheduler does
+/// before running it, returning whether there were any.
+pub fn handle_signals(process: &Process) -> bool {
+    process.handle_signals(|signal| match signal {
+        Signal::Exit {
+            sender,
+            reason,
+            linked,
+            trace,
+            source,
+        } => handle_exit_signal(process, sender, reason.data(), linked, trace, source),
+        Signal::Down { reference, message } => monitor::handle_down(process, &reference, message),
+        Signal::Unlink { sender } => {
+            process.linked_pid_set.remove(&sender);
+        }
+        Signal::GroupLeader { group_leader } => process.set_group_leader_pid(group_leader),
+    })
+}
+
+fn send_exit_signal(
+    sender: Pid,
+    receiver: &Process,
+    reason: Term,
+    linked: bool,
+    trace: Arc<Trace>,
+    source: Option<ArcError>,
+) {
+    receiver.send_signal(Signal::Exit {
+        sender,
+        reason: SignalData::new(reason).unwrap(),
+        linked,
+        trace,
+        source,
+    });
+    wake(receiver);
+}
+
+fn handle_exit_signal(
+    process: &Process,
+    sender: Pid,
+    reason: Term,
+    linked: bool,
+    trace: Arc<Trace>,
+    source: Option<ArcError>,
+) {
+    // The process unlinked `sender` after the signal was sent
+    if linked && process.linked_pid_set.remove(&sender).is_none() {
+        return;
+    }
+
+    if is_kill_reason(reason) {
+        kill(process);
+    } else if process.traps_exit() {
+        send_exit_message(process, sender.encode().unwrap(), reason);
+    } else if !is_expected_exit_reason(reason) && !process.is_exiting() {
+        // Only processes trapping exits are told about an expected exit, as an `EXIT` message,
+        // and an exiting process keeps its own reason
+        exit_with_reason(process, reason, trace, source);
+    }
+}
+
+fn is_kill_reason(reason: Term) -> bool {
+    match reason.decode() {
+        Ok(TypedTerm::Atom(atom)) => atom == "kill",
+        _ => false,
+    }
+}
+
+fn wake(process: &Process) {
+    if let Some(scheduler) = process.scheduler() {
+        scheduler.stop_waiting(process);
+    }
+}
+
+fn send_exit_message(process: &Process, from: Term, reason: Term) {
+    let exit_message_elements: &[Term] = &[atom!("EXIT"), from, reason];
+    let exit_message_word_size = Tuple::need_in_words_from_elements(exit_message_elements);
+
+    match process.try_acquire_heap() {
+        Some(ref mut heap) => {
+            if exit_message_word_size <= heap.heap_available() {
+                send_self_exit_message(process, heap, exit_message_elements);
+            } else {
+                send_heap_exit_message(process, exit_message_elements);
             }
         }
+        None => {
+            send_heap_exit_message(process, exit_message_elements);
+        }
     }
 }
 
@@ -189,31 +328,700 @@ fn send_heap_exit_message(process: &Process, exit_message_elements: &[Term]) {
     let ptr = heap_fragment_ref
         .tuple_from_slice(exit_message_elements)
         .unwrap();
-    process.send_heap_message(heap_fragment, ptr.into());
+    // Sent while handling signals, so it must not wait behind them
+    process.deliver_heap_message(heap_fragment, ptr.into());
+}
+
+fn exit_with_reason(process: &Process, reason: Term, trace: Arc<Trace>, source: Option<ArcError>) {
+    match process.try_acquire_heap() {
+        Some(ref mut heap) => {
+            if reason.size_in_words() <= heap.heap_available() {
+                exit_in_heap(process, heap, reason, trace, source);
+            } else {
+                exit_in_heap_fragment(process, reason, trace, source);
+            }
+        }
+        None => {
+            exit_in_heap_fragment(process, reason, trace, source);
+        }
+    }
 }
 
 fn exit_in_heap(
     process: &Process,
     heap: &mut ProcessHeap,
     reason: Term,
-    exception: RuntimeException,
+    trace: Arc<Trace>,
+    source: Option<ArcError>,
 ) {
     let data = reason.clone_to_heap(heap).unwrap();
 
-    process.exit(data, exception.stacktrace(), exception.source());
+    process.exit_from_signal(exception::exit(data, trace, source));
 }
 
-fn exit_in_heap_fragment(process: &Process, reason: Term, exception: RuntimeException) {
+fn exit_in_heap_fragment(
+    process: &Process,
+    reason: Term,
+    trace: Arc<Trace>,
+    source: Option<ArcError>,
+) {
     let (heap_fragment_data, mut heap_fragment) = reason.clone_to_fragment().unwrap();
 
     process.attach_fragment(unsafe { heap_fragment.as_mut() });
-    process.exit(
-        heap_fragment_data,
-        exception.stacktrace(),
-        exception.source(),
-    );
+    process.exit_from_signal(exception::exit(heap_fragment_data, trace, source));
+}
+
+/// Exits `process` with reason `killed`, as if by `exit(Pid, kill)`, waking it if necessary
+/// so that its scheduler observes the exit.
+///
+/// If `process` is already exiting, it keeps its own reason.
+pub fn kill(process: &Process) {
+    process.exit_from_signal(exception::exit(atom!("killed"), Trace::capture(), None));
+
+    wake(process);
+}
+
+/// Writes a summary of every live process to `writer`, in the style of the BEAM break menu's
+/// `(p)roc info` command.
+///
+/// The processes are a snapshot of the process table, and no lock on a process is held while
+/// writing, so that a slow `writer` doesn't hold up the schedulers running them.
+pub fn dump_state(writer: &mut dyn Write) -> io::Result<()> {
+    for process in processes() {
+        let pid = process.pid();
+        let state = state_name(&process.status.read());
+        let registered_name = *process.registered_name.read();
+        let current = process.current_module_function_arity();
+        let message_queue_len = process.message_queue_len();
+        let total_heap_size = process.total_heap_size();
+        let reductions = process.total_reductions.load(Ordering::Relaxed);
+
+        writeln!(writer, "=proc:<0.{}.{}>", pid.number(), pid.serial())?;
+        writeln!(writer, "State: {}", state)?;
+        if let Some(name) = registered_name {
+            writeln!(writer, "Name: {}", name.name())?;
+        }
+        writeln!(
+            writer,
+            "Spawned as: {}",
+            process.initial_module_function_arity
+        )?;
+        if let Some(current) = current {
+            writeln!(writer, "Current call: {}", current)?;
+        }
+        writeln!(writer, "Message queue length: {}", message_queue_len)?;
+        // In words, including heap fragments, like `process_info(Pid, total_heap_size)`
+        writeln!(writer, "Heap size: {}", total_heap_size)?;
+        writeln!(writer, "Reductions: {}", reductions)?;
+    }
+
+    Ok(())
+}
+
+/// The most messages of each process rendered by `dump_crash_state`
+pub const CRASH_DUMP_MESSAGES: usize = 10;
+/// The most frames of each process's stack rendered by `dump_crash_state`
+pub const CRASH_DUMP_FRAMES: usize = 32;
+/// The most bytes of each term rendered by `dump_crash_state`
+pub const CRASH_DUMP_TERM_BYTES: usize = 256;
+
+/// Writes the memory used by processes, the size of the atom table and every process, including
+/// its links, monitors, and the start of its message queue and stack, for a crash dump.
+///
+/// As this is called while the system is going down, possibly from a panic hook on a thread still
+/// holding locks, no lock that may already be held is waited for: anything behind one is written
+/// as `locked` instead. Terms are rendered into a fixed-size buffer on the stack, and never by
+/// allocating on a process heap, so that neither a huge nor a partially collected term can make
+/// the dump itself fail.
+pub fn dump_crash_state(writer: &mut dyn Write) -> io::Result<()> {
+    let processes = processes();
+
+    writeln!(writer, "=memory")?;
+    let words: usize = processes
+        .iter()
+        .filter_map(|process| process.try_total_heap_size())
+        .sum();
+    writeln!(writer, "processes: {}", words * std::mem::size_of::<Term>())?;
+
+    writeln!(writer, "=index_table:atom_tab")?;
+    match liblumen_alloc::erts::term::atom::try_atom_count() {
+        Some(atom_count) => writeln!(writer, "entries: {}", atom_count)?,
+        None => writeln!(writer, "entries: locked")?,
+    }
+
+    for process in processes {
+        dump_crash_process(writer, &process)?;
+    }
+
+    Ok(())
+}
+
+fn dump_crash_process(writer: &mut dyn Write, process: &Process) -> io::Result<()> {
+    let pid = process.pid();
+
+    writeln!(writer, "=proc:<0.{}.{}>", pid.number(), pid.serial())?;
+    match process.status.try_read() {
+        Some(status) => writeln!(writer, "State: {}", state_name(&status))?,
+        None => writeln!(writer, "State: locked")?,
+    }
+    if let Some(Some(name)) = process.registered_name.try_read().map(|name| *name) {
+        writeln!(writer, "Name: {}", name.name())?;
+    }
+    writeln!(
+        writer,
+        "Spawned as: {}",
+        process.initial_module_function_arity
+    )?;
+    match process.try_total_heap_size() {
+        Some(total_heap_size) => writeln!(writer, "Heap size: {}", total_heap_size)?,
+        None => writeln!(writer, "Heap size: locked")?,
+    }
+    writeln!(
+        writer,
+        "Reductions: {}",
+        process.total_reductions.load(Ordering::Relaxed)
+    )?;
+
+    write!(writer, "Link list: [")?;
+    for (index, linked_pid) in process.linked_pid_set.iter().enumerate() {
+        write_pid(writer, index, *linked_pid)?;
+    }
+    writeln!(writer, "]")?;
+    // The processes this one monitors, then those monitoring it
+    write!(writer, "Monitors: [")?;
+    for (index, entry) in process.monitored_pid_by_reference.iter().enumerate() {
+        write_pid(writer, index, *entry.value())?;
+    }
+    writeln!(writer, "]")?;
+    write!(writer, "Monitored by: [")?;
+    for (index, entry) in process.monitor_by_reference.iter().enumerate() {
+        write_pid(writer, index, *entry.value().monitoring_pid())?;
+    }
+    writeln!(writer, "]")?;
+
+    writeln!(
+        writer,
+        "=proc_messages:<0.{}.{}>",
+        pid.number(),
+        pid.serial()
+    )?;
+    let mailbox = process.mailbox.try_lock();
+    match mailbox
+        .as_ref()
+        .and_then(|mailbox| mailbox.try_borrow().ok())
+    {
+        Some(mailbox) => {
+            // Oldest first, in the order they would be received
+            let mut cursor = mailbox.cursor();
+            for _ in 0..CRASH_DUMP_MESSAGES {
+                match cursor.get() {
+                    Some(message) => {
+                        write_bounded_term(writer, message.data())?;
+                        writeln!(writer)?;
+                    }
+                    None => break,
+                }
+                cursor.move_prev();
+            }
+            if CRASH_DUMP_MESSAGES < mailbox.len() {
+                writeln!(writer, "... {} more", mailbox.len() - CRASH_DUMP_MESSAGES)?;
+            }
+        }
+        None => writeln!(writer, "locked")?,
+    }
+
+    writeln!(writer, "=proc_stack:<0.{}.{}>", pid.number(), pid.serial())?;
+    match process.frames.try_lock() {
+        Some(frames) => {
+            let mut len = 0;
+            for frame in frames.iter() {
+                if len < CRASH_DUMP_FRAMES {
+                    writeln!(writer, "{}", frame.module_function_arity())?;
+                }
+                len += 1;
+            }
+            if CRASH_DUMP_FRAMES < len {
+                writeln!(writer, "... {} more", len - CRASH_DUMP_FRAMES)?;
+            }
+        }
+        None => writeln!(writer, "locked")?,
+    }
+
+    Ok(())
+}
+
+fn write_pid(writer: &mut dyn Write, index: usize, pid: Pid) -> io::Result<()> {
+    if 0 < index {
+        write!(writer, ", ")?;
+    }
+    write!(writer, "<0.{}.{}>", pid.number(), pid.serial())
+}
+
+/// Writes at most `CRASH_DUMP_TERM_BYTES` of `term`, followed by `...` if it is longer.
+///
+/// Rendering stops as soon as the buffer is full, so a long list isn't walked to its end.
+pub fn write_bounded_term(writer: &mut dyn Write, term: Term) -> io::Result<()> {
+    use std::fmt::Write as _;
+
+    let mut buffer = BoundedBuffer {
+        bytes: [0; CRASH_DUMP_TERM_BYTES],
+        len: 0,
+        truncated: false,
+    };
+    let _ = write!(buffer, "{}", term);
+
+    writer.write_all(&buffer.bytes[..buffer.len])?;
+    if buffer.truncated {
+        writer.write_all(b"...")?;
+    }
+
+    Ok(())
+}
+
+struct BoundedBuffer {
+    bytes: [u8; CRASH_DUMP_TERM_BYTES],
+    len: usize,
+    truncated: bool,
+}
+impl std::fmt::Write for BoundedBuffer {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        if self.truncated {
+            return Err(std::fmt::Error);
+        }
+        let available = self.bytes.len() - self.len;
+
+        if s.len() <= available {
+            self.bytes[self.len..self.len + s.len()].copy_from_slice(s.as_bytes());
+            self.len += s.len();
+
+            Ok(())
+        } else {
+            // Only whole characters, so the dump stays valid UTF-8
+            let fits = (0..=available)
+                .rev()
+                .find(|&index| s.is_char_boundary(index))
+                .unwrap();
+            self.bytes[self.len..self.len + fits].copy_from_slice(&s.as_bytes()[..fits]);
+            self.len += fits;
+            self.truncated = true;
+
+            // Stops the rendering of the rest of the term
+            Err(std::fmt::Error)
+        }
+    }
+}
+
+fn state_name(status: &Status) -> &'static str {
+    match status {
+        Status::Unrunnable => "Unrunnable",
+        Status::Runnable => "Scheduled",
+        Status::Running | Status::MaybeWaiting => "Running",
+        Status::Waiting => "Waiting",
+        Status::Exited | Status::RuntimeException(_) => "Exiting",
+    }
 }
 
 thread_local! {
    static LOG_EXIT: Cell<bool> = Cell::new(true);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use liblumen_alloc::erts::process::{alloc, MaxHeapSize};
+    use liblumen_alloc::erts::ModuleFunctionArity;
+
+    use crate::registry::test::with_process_limit;
+    use crate::registry::{self, DEFAULT_PROCESS_LIMIT};
+
+    #[test]
+    fn trapping_parent_survives_crashing_linked_child_and_receives_exit_message() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let parent = register(process("parent"));
+            parent.trap_exit(true);
+            let child = register(process("child"));
+            parent.link(&child);
+
+            let exception = exit(&child, atom!("badarith"));
+            propagate_exit_to_links(&child, Some(&exception));
+            handle_signals(&parent);
+
+            assert!(!parent.is_exiting());
+            assert_eq!(
+                messages(&parent),
+                vec![vec![atom!("EXIT"), child.pid_term(), atom!("badarith")]]
+            );
+            assert!(parent.linked_pid_set.is_empty());
+
+            unregister(&[&parent, &child]);
+        });
+    }
+
+    #[test]
+    fn not_trapping_parent_exits_with_reason_of_crashing_linked_child() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let parent = register(process("parent"));
+            let child = register(process("child"));
+            parent.link(&child);
+
+            let exception = exit(&child, atom!("badarith"));
+            propagate_exit_to_links(&child, Some(&exception));
+            handle_signals(&parent);
+
+            assert_eq!(exit_reason(&parent), Some(atom!("badarith")));
+            assert_eq!(parent.message_queue_len(), 0);
+
+            unregister(&[&parent, &child]);
+        });
+    }
+
+    #[test]
+    fn exit_signal_kill_terminates_trapping_process_with_killed() {
+        let sender = process("sender");
+        let receiver = process("receiver");
+        receiver.trap_exit(true);
+
+        exit_signal(&sender, &receiver, atom!("kill"));
+        handle_signals(&receiver);
+
+        assert_eq!(exit_reason(&receiver), Some(atom!("killed")));
+        assert_eq!(receiver.message_queue_len(), 0);
+    }
+
+    #[test]
+    fn exit_signal_is_message_to_trapping_process() {
+        let sender = process("sender");
+        let receiver = process("receiver");
+        receiver.trap_exit(true);
+
+        exit_signal(&sender, &receiver, atom!("normal"));
+        exit_signal(&sender, &receiver, atom!("shutdown"));
+        handle_signals(&receiver);
+
+        assert!(!receiver.is_exiting());
+        assert_eq!(
+            messages(&receiver),
+            vec![
+                vec![atom!("EXIT"), sender.pid_term(), atom!("normal")],
+                vec![atom!("EXIT"), sender.pid_term(), atom!("shutdown")]
+            ]
+        );
+    }
+
+    #[test]
+    fn exit_signal_normal_is_ignored_by_not_trapping_process() {
+        let sender = process("sender");
+        let receiver = process("receiver");
+
+        exit_signal(&sender, &receiver, atom!("normal"));
+        handle_signals(&receiver);
+        assert!(!receiver.is_exiting());
+
+        exit_signal(&sender, &receiver, atom!("shutdown"));
+        handle_signals(&receiver);
+        assert_eq!(exit_reason(&receiver), Some(atom!("shutdown")));
+    }
+
+    #[test]
+    fn exit_signal_is_ordered_with_messages_from_the_same_sender() {
+        let sender = process("sender");
+        let receiver = process("receiver");
+        receiver.trap_exit(true);
+
+        receiver.send_from_other(tuple(&sender, &[atom!("first")]));
+        exit_signal(&sender, &receiver, atom!("normal"));
+        receiver.send_from_other(tuple(&sender, &[atom!("second")]));
+
+        // Nothing behind the exit signal is delivered until the signal is handled
+        assert_eq!(messages(&receiver), vec![vec![atom!("first")]]);
+
+        handle_signals(&receiver);
+
+        assert_eq!(
+            messages(&receiver),
+            vec![
+                vec![atom!("first")],
+                vec![atom!("EXIT"), sender.pid_term(), atom!("normal")],
+                vec![atom!("second")]
+            ]
+        );
+    }
+
+    #[test]
+    fn messages_sent_before_exit_signal_are_delivered_before_not_trapping_process_exits() {
+        let sender = process("sender");
+        let receiver = process("receiver");
+
+        receiver.send_from_other(tuple(&sender, &[atom!("first")]));
+        exit_signal(&sender, &receiver, atom!("shutdown"));
+        receiver.send_from_other(tuple(&sender, &[atom!("second")]));
+
+        assert!(!receiver.is_exiting());
+
+        handle_signals(&receiver);
+
+        assert_eq!(exit_reason(&receiver), Some(atom!("shutdown")));
+        assert_eq!(messages(&receiver)[0], vec![atom!("first")]);
+    }
+
+    #[test]
+    fn unlinked_process_ignores_exit_signal_sent_over_link_before_unlink() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let parent = register(process("parent"));
+            let child = register(process("child"));
+            parent.link(&child);
+
+            let exception = exit(&child, atom!("badarith"));
+            unlink(&parent, &child);
+            propagate_exit_to_links(&child, Some(&exception));
+            handle_signals(&parent);
+
+            assert!(!parent.is_exiting());
+            assert!(parent.linked_pid_set.is_empty());
+
+            unregister(&[&parent, &child]);
+        });
+    }
+
+    #[test]
+    fn processes_linked_to_killed_process_can_trap_its_exit() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let supervisor = register(process("supervisor"));
+            supervisor.trap_exit(true);
+            let worker = register(process("worker"));
+            worker.trap_exit(true);
+            supervisor.link(&worker);
+
+            exit_signal(&supervisor, &worker, atom!("kill"));
+            handle_signals(&worker);
+            let exception = match *worker.status.read() {
+                Status::RuntimeException(ref exception) => exception.clone(),
+                ref status => panic!("unexpected status: {:?}", status),
+            };
+            propagate_exit_to_links(&worker, Some(&exception));
+            handle_signals(&supervisor);
+
+            assert!(!supervisor.is_exiting());
+            assert_eq!(
+                messages(&supervisor),
+                vec![vec![atom!("EXIT"), worker.pid_term(), atom!("killed")]]
+            );
+
+            unregister(&[&supervisor, &worker]);
+        });
+    }
+
+    #[test]
+    fn process_growing_past_max_heap_size_is_killed_and_trapping_linked_parent_receives_exit() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let parent = register(process("parent"));
+            parent.trap_exit(true);
+            let child = register(process("child"));
+            let mut max_heap_size = MaxHeapSize::new(1_000);
+            max_heap_size.error_logger(false);
+            child.set_max_heap_size(max_heap_size);
+            parent.link(&child);
+
+            // An infinite list, which is collected whenever the heap is full, keeping it all live
+            let mut list = [Term::NIL];
+            for _ in 0..100_000 {
+                if child.is_exiting() {
+                    break;
+                }
+
+                let cons = child.acquire_heap().cons(Term::NIL, list[0]);
+                match cons {
+                    Ok(cons) => list[0] = cons.into(),
+                    Err(_) => {
+                        child.garbage_collect(2, &mut list[..]).unwrap();
+                    }
+                }
+            }
+
+            assert_eq!(exit_reason(&child), Some(atom!("killed")));
+
+            let exception = match *child.status.read() {
+                Status::RuntimeException(ref exception) => exception.clone(),
+                ref status => panic!("unexpected status: {:?}", status),
+            };
+            propagate_exit_to_links(&child, Some(&exception));
+            handle_signals(&parent);
+
+            assert!(!parent.is_exiting());
+            assert_eq!(
+                messages(&parent),
+                vec![vec![atom!("EXIT"), child.pid_term(), atom!("killed")]]
+            );
+
+            unregister(&[&parent, &child]);
+        });
+    }
+
+    #[test]
+    fn dump_state_includes_every_process_in_the_process_table() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let named = register(process("named"));
+            let name = Atom::from_str("dump_state_named");
+            assert!(registry::put_atom_to_process(name, named.clone()));
+            let waiting = register(process("waiting"));
+            waiting.wait();
+            let messaged = register(process("messaged"));
+            messaged.send_from_other(atom!("message"));
+
+            let mut dump = Vec::new();
+            dump_state(&mut dump).unwrap();
+            let dump = String::from_utf8(dump).unwrap();
+
+            for process in &[&named, &waiting, &messaged] {
+                let pid = process.pid();
+                let section = format!("=proc:<0.{}.{}>\n", pid.number(), pid.serial());
+
+                assert!(
+                    dump.contains(&section),
+                    "{} is not in dump:\n{}",
+                    section,
+                    dump
+                );
+            }
+            assert!(dump.contains("Name: dump_state_named\n"));
+            assert!(dump.contains("State: Waiting\n"));
+            assert!(dump.contains("Spawned as: test:messaged/0\n"));
+            assert!(dump.contains("Message queue length: 1\n"));
+            assert!(dump.contains(&format!("Heap size: {}\n", messaged.total_heap_size())));
+
+            registry::unregister(&name);
+            unregister(&[&named, &waiting, &messaged]);
+        });
+    }
+
+    #[test]
+    fn dump_crash_state_includes_links_monitors_and_bounded_messages() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let crashed = register(process("crashed"));
+            let linked = register(process("linked"));
+            crashed.link(&linked);
+            let watcher = register(process("watcher"));
+            let reference = Reference::new(0.into(), 1);
+            watcher.monitor(reference.clone(), crashed.pid());
+            crashed.monitored(
+                reference,
+                Monitor::Pid {
+                    monitoring_pid: watcher.pid(),
+                },
+            );
+
+            let long = linked.list_from_iter((0..100).map(|i| linked.integer(i)));
+            crashed.send_from_other(long);
+            for _ in 0..CRASH_DUMP_MESSAGES {
+                crashed.send_from_other(atom!("message"));
+            }
+
+            let mut dump = Vec::new();
+            dump_crash_state(&mut dump).unwrap();
+            let dump = String::from_utf8(dump).unwrap();
+
+            let crashed_pid = crashed.pid();
+            let section = dump
+                .split(&format!(
+                    "=proc:<0.{}.{}>\n",
+                    crashed_pid.number(),
+                    crashed_pid.serial()
+                ))
+                .nth(1)
+                .unwrap();
+            let section = section.split("=proc:").next().unwrap();
+            let pid = |pid: Pid| format!("<0.{}.{}>", pid.number(), pid.serial());
+
+            assert!(dump.starts_with("=memory\nprocesses: "));
+            assert!(dump.contains("=index_table:atom_tab\nentries: "));
+            assert!(section.contains(&format!("Link list: [{}]\n", pid(linked.pid()))));
+            assert!(section.contains(&format!("Monitored by: [{}]\n", pid(watcher.pid()))));
+            assert!(dump.contains(&format!("Monitors: [{}]\n", pid(crashed_pid))));
+
+            let messages = section
+                .split("=proc_messages:")
+                .nth(1)
+                .unwrap()
+                .split("=proc_stack:")
+                .next()
+                .unwrap();
+            let mut lines = messages.lines().skip(1);
+            let oldest = lines.next().unwrap();
+            assert!(oldest.starts_with("[0, 1, 2"));
+            assert!(oldest.ends_with("..."));
+            assert_eq!(oldest.len(), CRASH_DUMP_TERM_BYTES + "...".len());
+            assert_eq!(lines.next(), Some("message"));
+            assert_eq!(lines.last(), Some("... 1 more"));
+
+            unregister(&[&crashed, &linked, &watcher]);
+        });
+    }
+
+    fn process(function: &str) -> Process {
+        let (heap, heap_size) = alloc::default_heap().unwrap();
+
+        Process::new(
+            Default::default(),
+            None,
+            ModuleFunctionArity {
+                module: Atom::from_str("test"),
+                function: Atom::from_str(function),
+                arity: 0,
+            },
+            heap,
+            heap_size,
+        )
+    }
+
+    /// Registers `process`, as scheduling it would, so that exits can be propagated to it
+    fn register(process: Process) -> Arc<Process> {
+        let reservation = registry::reserve_pid().unwrap();
+        let arc_process = Arc::new(process);
+        registry::put_pid_to_process(&arc_process, reservation);
+
+        arc_process
+    }
+
+    fn unregister(processes: &[&Process]) {
+        for process in processes {
+            registry::remove_pid_to_process(process);
+        }
+    }
+
+    fn exit(process: &Process, reason: Term) -> RuntimeException {
+        process.exit(reason, Trace::capture(), None);
+
+        match *process.status.read() {
+            Status::RuntimeException(ref exception) => exception.clone(),
+            ref status => panic!("unexpected status: {:?}", status),
+        }
+    }
+
+    fn exit_reason(process: &Process) -> Option<Term> {
+        match *process.status.read() {
+            Status::RuntimeException(ref exception) => Some(exception.reason()),
+            _ => None,
+        }
+    }
+
+    /// A tuple of `elements` on the heap of `sender`, to send to another process
+    fn tuple(sender: &Process, elements: &[Term]) -> Term {
+        sender.tuple_from_slice(elements)
+    }
+
+    /// The elements of each of the tuples in the mailbox of `process`, oldest first
+    fn messages(process: &Process) -> Vec<Vec<Term>> {
+        process
+            .mailbox
+            .lock()
+            .borrow()
+            .iter()
+            .rev()
+            .map(|message| {
+                let tuple: Boxed<Tuple> = message.data().try_into().unwrap();
+                tuple.elements().to_vec()
+            })
+            .collect()
+    }
+}
@@ -1,11 +1,14 @@
 use core::convert::TryInto;
 use core::mem;
 
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+
 use liblumen_alloc::erts::exception::RuntimeException;
-use liblumen_alloc::erts::process::alloc::{Heap, TermAlloc};
-use liblumen_alloc::erts::process::{Monitor, Process};
+use liblumen_alloc::erts::process::alloc::TermAlloc;
+use liblumen_alloc::erts::process::{Monitor, Process, Signal, SignalData};
 use liblumen_alloc::erts::term::prelude::*;
-use liblumen_alloc::erts::{self, Message};
+use liblumen_alloc::erts::Message;
 use liblumen_alloc::{CloneToProcess, HeapFragment};
 
 use liblumen_core::alloc::Layout;
@@ -14,6 +17,23 @@ use crate::distribution::nodes::node;
 use crate::registry::pid_to_process;
 use crate::scheduler::Scheduled;
 
+/// Called with the exiting process and its exit reason, while the reason is still on the process's
+/// heap
+pub type ExitObserver = Box<dyn FnOnce(&Process, Term) + Send>;
+
+lazy_static! {
+    static ref EXIT_OBSERVERS_BY_PID: DashMap<Pid, Vec<ExitObserver>> = Default::default();
+}
+
+/// Calls `observer` when the process with `pid` exits, right after its `DOWN` messages are sent.
+///
+/// This allows host code, which has no process to receive a `DOWN` message, to monitor a process.
+/// Register the observer before the process is scheduled, as it is never called for a process
+/// that has already exited.
+pub fn observe_exit(pid: Pid, observer: ExitObserver) {
+    EXIT_OBSERVERS_BY_PID.entry(pid).or_default().push(observer);
+}
+
 pub fn is_down(message: &Message, reference: &Reference) -> bool {
     let message_data = message.data();
 
@@ -43,38 +63,11 @@ pub fn propagate_exit(process: &Process, exception: Option<&RuntimeException>) {
         let reference = entry.key();
         let monitor = entry.value();
         if let Some(monitoring_pid_arc_process) = pid_to_process(&monitor.monitoring_pid()) {
-            let down_layout = down_message_layout(monitor, info);
-            let down_layout_words = erts::to_word_size(down_layout.size());
-
-            match monitoring_pid_arc_process.try_acquire_heap() {
-                Some(ref mut monitoring_heap) => {
-                    if down_layout_words <= monitoring_heap.heap_available() {
-                        let monitoring_heap_data =
-                            down(monitoring_heap, reference, process, monitor, info);
-
-                        monitoring_pid_arc_process.send_from_self(monitoring_heap_data);
-                    } else {
-                        send_heap_down_message(
-                            &monitoring_pid_arc_process,
-                            down_layout,
-                            reference,
-                            process,
-                            monitor,
-                            info,
-                        );
-                    }
-                }
-                None => {
-                    send_heap_down_message(
-                        &monitoring_pid_arc_process,
-                        down_layout,
-                        reference,
-                        process,
-                        monitor,
-                        info,
-                    );
-                }
-            }
+            let message = down_signal_data(reference, process, monitor, info);
+            monitoring_pid_arc_process.send_signal(Signal::Down {
+                reference: reference.clone(),
+                message,
+            });
 
             monitoring_pid_arc_process
                 .scheduler()
@@ -82,6 +75,25 @@ pub fn propagate_exit(process: &Process, exception: Option<&RuntimeException>) {
                 .stop_waiting(&monitoring_pid_arc_process);
         }
     }
+
+    if let Some((_, observers)) = EXIT_OBSERVERS_BY_PID.remove(&process.pid()) {
+        for observer in observers {
+            observer(process, info);
+        }
+    }
+}
+
+/// Delivers the `DOWN` `message` of the monitor with `reference` to `process`, which handles the
+/// signal.
+///
+/// The monitor has fired, so it is no longer active in `process`.  If `process` already removed it
+/// with `demonitor`, before handling the signal, it must not receive the `DOWN` message.
+pub fn handle_down(process: &Process, reference: &Reference, message: SignalData) {
+    if process.demonitor(reference).is_some() {
+        let (data, heap_fragment) = message.into_fragment();
+
+        process.deliver_heap_message(heap_fragment, data);
+    }
 }
 
 // Private
@@ -155,18 +167,19 @@ fn identifier_layout(monitor: &Monitor) -> Layout {
     }
 }
 
-fn send_heap_down_message(
-    monitoring_process: &Process,
-    down_layout: Layout,
+/// The `DOWN` message is built in a heap fragment, as only the monitoring process can put it on its
+/// heap, once it handles the signal
+fn down_signal_data(
     reference: &Reference,
     monitored_process: &Process,
     monitor: &Monitor,
     info: Term,
-) {
+) -> SignalData {
+    let down_layout = down_message_layout(monitor, info);
     let mut non_null_heap_fragment = HeapFragment::new(down_layout).unwrap();
     let heap_fragment = unsafe { non_null_heap_fragment.as_mut() };
 
     let heap_fragment_data = down(heap_fragment, reference, monitored_process, monitor, info);
 
-    monitoring_process.send_heap_message(non_null_heap_fragment, heap_fragment_data);
+    SignalData::from_fragment(heap_fragment_data, non_null_heap_fragment)
 }
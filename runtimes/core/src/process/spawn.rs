@@ -1,3 +1,5 @@
+mod error;
 pub mod options;
 
+pub use self::error::{SpawnError, SpawnOptionError};
 pub use self::options::{Connection, Options};
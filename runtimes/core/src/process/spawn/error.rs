@@ -0,0 +1,70 @@
+use thiserror::Error;
+
+use liblumen_alloc::erts::exception::{self, ArcError, Exception};
+use liblumen_alloc::erts::process::alloc::StackAllocError;
+use liblumen_alloc::erts::process::trace::Trace;
+
+/// Produced when a process cannot be spawned.
+///
+/// Nothing allocated for the new process outlives the failed spawn: its heap and stack are
+/// reclaimed, and it is never linked, monitored or registered.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SpawnError {
+    /// The parent process is exiting, or is no longer scheduled
+    #[error("parent process is exiting")]
+    ParentExiting,
+    /// The heap for the new process could not be allocated
+    #[error("unable to allocate heap for process")]
+    HeapAllocation,
+    /// The stack for the new process could not be allocated
+    #[error("unable to allocate stack for process")]
+    StackAllocation,
+    /// The spawn options cannot be applied
+    #[error("invalid spawn options: {0}")]
+    InvalidOptions(String),
+    /// The spawn options are inconsistent with how the process is spawned
+    #[error(transparent)]
+    InvalidOption(#[from] SpawnOptionError),
+    /// A limit on the number of processes or atoms has been reached
+    #[error("system limit reached")]
+    SystemLimit,
+}
+
+/// Produced when the spawn options ask for something that can't be done for the process being
+/// spawned, rather than silently ignoring it.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnOptionError {
+    /// `monitor` was requested, but there is no parent process to monitor the child
+    #[error("monitor requires a parent process")]
+    MonitorWithoutParent,
+    /// `link` was requested, but there is no parent process to link the child to
+    #[error("link requires a parent process")]
+    LinkWithoutParent,
+}
+
+impl From<StackAllocError> for SpawnError {
+    fn from(err: StackAllocError) -> Self {
+        match err {
+            StackAllocError::Exhausted { .. } => Self::SystemLimit,
+            StackAllocError::Alloc => Self::StackAllocation,
+        }
+    }
+}
+
+impl From<SpawnError> for Exception {
+    fn from(err: SpawnError) -> Self {
+        let trace = Trace::capture();
+        let source = Some(ArcError::from_err(err.clone()));
+
+        let runtime = match err {
+            SpawnError::ParentExiting
+            | SpawnError::InvalidOptions(_)
+            | SpawnError::InvalidOption(_) => exception::badarg(trace, source),
+            SpawnError::HeapAllocation | SpawnError::StackAllocation | SpawnError::SystemLimit => {
+                exception::system_limit(trace, source)
+            }
+        };
+
+        Self::Runtime(runtime)
+    }
+}
@@ -4,16 +4,19 @@ use std::convert::{TryFrom, TryInto};
 
 use anyhow::*;
 
-use liblumen_alloc::erts::exception::Alloc;
-use liblumen_alloc::erts::process::alloc::{default_heap_size, heap, next_heap_size};
+use liblumen_alloc::erts::exception::AllocResult;
+use liblumen_alloc::erts::process::alloc::{default_heap_size, next_heap_size};
 use liblumen_alloc::erts::process::priority::Priority;
-use liblumen_alloc::erts::process::Process;
+pub use liblumen_alloc::erts::process::MaxHeapSize;
+use liblumen_alloc::erts::process::{Process, Status};
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::ModuleFunctionArity;
 
 use crate::process;
 use crate::proplist::TryPropListFromTermError;
 
+use super::{SpawnError, SpawnOptionError};
+
 use message_queue_data::*;
 
 #[must_use]
@@ -22,28 +25,26 @@ pub struct Connection {
     #[must_use]
     pub monitor_reference: Option<Term>,
 }
+impl Connection {
+    /// Delivers the exit of `parent_process` to `child_process` if the parent started exiting
+    /// while the child was being spawned linked to it.
+    ///
+    /// Once linked, the parent's exit is propagated over the link as usual, but only to processes
+    /// in the registry, so a parent exiting before the child is scheduled would leave the child
+    /// linked to a dead process. As `spawn_link` is atomic in OTP, the child gets the parent's
+    /// exit, exactly once, whichever of the parent's own propagation and this comes first.
+    ///
+    /// This must be called after the child is scheduled, so that it is in the registry.
+    pub fn propagate_parent_exit(&self, parent_process: Option<&Process>, child_process: &Process) {
+        let Some(parent_process) = parent_process.filter(|_| self.linked) else { return; };
+
+        let exception = match *parent_process.status.read() {
+            Status::RuntimeException(ref exception) => Some(exception.clone()),
+            Status::Exited => None,
+            _ => return,
+        };
 
-#[derive(Clone, Copy, Debug)]
-pub struct MaxHeapSize {
-    size: usize,
-    kill: Option<bool>,
-    error_logger: Option<bool>,
-}
-impl MaxHeapSize {
-    pub fn new(size: usize) -> Self {
-        Self {
-            size,
-            kill: None,
-            error_logger: None,
-        }
-    }
-
-    pub fn kill(&mut self, kill: bool) {
-        self.kill = Some(kill);
-    }
-
-    pub fn error_logger(&mut self, error_logger: bool) {
-        self.error_logger = Some(error_logger);
+        process::propagate_exit_to_link(parent_process, child_process, exception.as_ref());
     }
 }
 
@@ -62,42 +63,76 @@ pub struct Options {
 }
 
 impl Options {
+    /// The priority of a process spawned from `parent_process` with these options.
+    ///
+    /// An explicit `priority` option always applies. Otherwise, unlike in OTP, where it would be
+    /// `normal`, the child inherits its parent's priority, or is `normal` without a parent. As
+    /// `max` is the highest priority, an inherited priority never exceeds what could be
+    /// requested explicitly.
     pub fn cascaded_priority(&self, parent_process: Option<&Process>) -> Priority {
         match self.priority {
             Some(priority) => priority,
             None => match parent_process {
-                Some(process) => process.priority,
+                Some(process) => process.priority(),
                 None => Default::default(),
             },
         }
     }
 
-    pub fn connect(&self, parent_process: Option<&Process>, child_process: &Process) -> Connection {
-        let linked = if self.link {
-            parent_process.unwrap().link(child_process);
+    /// Checks that a process can be spawned from `parent_process` with these options.
+    ///
+    /// This should be called before anything is allocated for the new process, so that a spawn
+    /// which cannot succeed fails without side effects.
+    pub fn validate(&self, parent_process: Option<&Process>) -> Result<(), SpawnError> {
+        match parent_process {
+            Some(parent_process) if parent_process.is_exiting() => Err(SpawnError::ParentExiting),
+            Some(_) => Ok(()),
+            None => {
+                self.check_parent(None)?;
+
+                Ok(())
+            }
+        }
+    }
 
-            true
-        } else {
-            false
+    /// Links and/or monitors `child_process` from `parent_process`, as the options request.
+    ///
+    /// The link is made even if the parent is exiting by now; once the child is scheduled,
+    /// [Connection::propagate_parent_exit] delivers that exit to it.
+    pub fn connect(
+        &self,
+        parent_process: Option<&Process>,
+        child_process: &Process,
+    ) -> Result<Connection, SpawnOptionError> {
+        // Check before linking, so a failure never leaves a half-connected child behind
+        let Some(parent_process) = self.check_parent(parent_process)? else {
+            return Ok(Connection {
+                linked: false,
+                monitor_reference: None,
+            });
         };
 
-        let monitor_reference = if self.monitor {
-            let reference = process::monitor(parent_process.unwrap(), child_process);
+        if self.link {
+            parent_process.link(child_process);
+        }
 
-            Some(reference)
+        let monitor_reference = if self.monitor {
+            Some(process::monitor(parent_process, child_process))
         } else {
             None
         };
 
-        Connection {
-            linked,
+        Ok(Connection {
+            linked: self.link,
             monitor_reference,
-        }
+        })
     }
 
-    pub fn sized_heap(&self) -> Result<(*mut Term, usize), Alloc> {
-        let heap_size = self.heap_size().map_err(|_| Alloc::new())?;
-        let heap = heap(heap_size)?;
+    pub fn sized_heap(&self) -> Result<(*mut Term, usize), SpawnError> {
+        let heap_size = self
+            .heap_size()
+            .map_err(|err| SpawnError::InvalidOptions(err.to_string()))?;
+        let heap = heap(heap_size).map_err(|_| SpawnError::HeapAllocation)?;
 
         Ok((heap, heap_size))
     }
@@ -112,7 +147,9 @@ impl Options {
         module: Atom,
         function: Atom,
         arity: u8,
-    ) -> Result<Process, Alloc> {
+    ) -> Result<Process, SpawnError> {
+        self.validate(parent_process)?;
+
         let priority = self.cascaded_priority(parent_process);
         let module_function_arity = ModuleFunctionArity {
             module,
@@ -128,12 +165,42 @@ impl Options {
             heap,
             heap_size,
         );
+        self.configure(&process);
 
         Ok(process)
     }
 
+    /// Applies the options that are flags of the process, rather than how it is created, to a
+    /// `process` just created with the heap from `sized_heap`.
+    pub fn configure(&self, process: &Process) {
+        if let Some(fullsweep_after) = self.fullsweep_after {
+            process.set_fullsweep_after(fullsweep_after);
+        }
+        if let Some(max_heap_size) = self.max_heap_size {
+            process.set_max_heap_size(max_heap_size);
+        }
+        if let MessageQueueData::OffHeap = self.message_queue_data {
+            process.message_queue_off_heap(true);
+        }
+    }
+
     // Private
 
+    /// Returns the parent process to connect to, if the options link or monitor, or an error if
+    /// they do but there is no parent
+    fn check_parent<'a>(
+        &self,
+        parent_process: Option<&'a Process>,
+    ) -> Result<Option<&'a Process>, SpawnOptionError> {
+        match parent_process {
+            Some(parent_process) if self.link || self.monitor => Ok(Some(parent_process)),
+            Some(_) => Ok(None),
+            None if self.link => Err(SpawnOptionError::LinkWithoutParent),
+            None if self.monitor => Err(SpawnOptionError::MonitorWithoutParent),
+            None => Ok(None),
+        }
+    }
+
     /// `heap` size in words.
     fn heap_size(&self) -> Result<usize, anyhow::Error> {
         let size = match self.min_heap_size {
@@ -141,15 +208,14 @@ impl Options {
             None => default_heap_size(),
         };
         match self.max_heap_size {
-            Some(MaxHeapSize {
-                size: max_size,
-                kill,
-                ..
-            }) if size >= max_size => {
-                if kill.unwrap_or_default() {
-                    Err(anyhow!("exceeded maximum heap size of {}", max_size))
+            Some(max_heap_size) if max_heap_size.is_exceeded_by(size) => {
+                if max_heap_size.kills() {
+                    Err(anyhow!(
+                        "exceeded maximum heap size of {}",
+                        max_heap_size.size()
+                    ))
                 } else {
-                    Ok(max_size)
+                    Ok(max_heap_size.size())
                 }
             }
             _ => Ok(size),
@@ -194,41 +260,10 @@ impl Options {
                     Ok(self)
                 }
                 "max_heap_size" => {
-                    let element = tuple[1];
-                    let max_heap_size: Result<usize, _> = element.try_into();
-                    if let Ok(max_heap_size) = max_heap_size {
-                        self.max_heap_size = Some(MaxHeapSize::new(max_heap_size));
-                        return Ok(self);
-                    }
-                    let max_heap_size_config: Result<Boxed<Map>, _> = element.try_into();
-                    match max_heap_size_config {
-                        Ok(max_heap_config) => {
-                            let max_heap_size_opt = Atom::from_str("max_heap_size");
-                            let max_heap_size = max_heap_config
-                                .get(max_heap_size_opt.encode().unwrap())
-                                .ok_or_else(|| anyhow!("missing max_heap_size key in map"))
-                                .and_then(|term| {
-                                    term.try_into()
-                                        .map_err(|err: TryIntoIntegerError| anyhow!(err))
-                                })
-                                .context("max_heap_size")?;
-                            let mut max_heap_size = MaxHeapSize::new(max_heap_size);
-                            let kill_opt = Atom::from_str("kill");
-                            if let Some(kill) = max_heap_config.get(kill_opt.encode().unwrap()) {
-                                max_heap_size.kill(kill.try_into().context("kill")?);
-                            }
-                            let error_logger_opt = Atom::from_str("error_logger");
-                            if let Some(error_logger) =
-                                max_heap_config.get(error_logger_opt.encode().unwrap())
-                            {
-                                max_heap_size
-                                    .error_logger(error_logger.try_into().context("error_logger")?);
-                            }
-                            self.max_heap_size = Some(max_heap_size);
-                            Ok(self)
-                        }
-                        Err(err) => Err(anyhow!(err).context("max_heap_size")),
-                    }
+                    let max_heap_size = tuple[1].try_into().context("max_heap_size")?;
+                    self.max_heap_size = Some(max_heap_size);
+
+                    Ok(self)
                 }
                 "message_queue_data" => {
                     let message_queue_data = tuple[1].try_into().context("message_queue_data")?;
@@ -262,6 +297,34 @@ impl Options {
     }
 }
 
+/// Allocates a process heap of `size` words
+#[cfg(not(test))]
+#[inline]
+fn heap(size: usize) -> AllocResult<*mut Term> {
+    liblumen_alloc::erts::process::alloc::heap(size)
+}
+
+/// Allocates a process heap of `size` words, unless a failure has been injected
+#[cfg(test)]
+fn heap(size: usize) -> AllocResult<*mut Term> {
+    use liblumen_alloc::erts::exception::Alloc;
+
+    let fail = test::HEAP_ALLOCATIONS_BEFORE_FAILURE.with(|remaining| match remaining.get() {
+        Some(0) => true,
+        Some(n) => {
+            remaining.set(Some(n - 1));
+            false
+        }
+        None => false,
+    });
+
+    if fail {
+        Err(Alloc::new())
+    } else {
+        liblumen_alloc::erts::process::alloc::heap(size)
+    }
+}
+
 impl Default for Options {
     fn default() -> Self {
         Self {
@@ -296,8 +359,10 @@ impl TryFrom<Term> for Options {
             match options_term.decode().unwrap() {
                 TypedTerm::Nil => return Ok(options),
                 TypedTerm::List(cons) => {
+                    let option = cons.head;
                     options
-                        .put_option_term(cons.head)
+                        .put_option_term(option)
+                        .with_context(|| format!("option ({}) is invalid", option))
                         .context(SUPPORTED_OPTIONS_CONTEXT)?;
                     options_term = cons.tail;
 
@@ -308,3 +373,238 @@ impl TryFrom<Term> for Options {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::cell::Cell;
+    use std::sync::Arc;
+    use std::thread;
+
+    use liblumen_alloc::atom;
+    use liblumen_alloc::erts::exception::{Exception, RuntimeException};
+    use liblumen_alloc::erts::process::{alloc, DEFAULT_FULLSWEEP_AFTER};
+    use liblumen_alloc::erts::process::trace::Trace;
+
+    use crate::registry::test::with_process_limit;
+    use crate::registry::{self, DEFAULT_PROCESS_LIMIT};
+
+    thread_local! {
+        /// The number of heap allocations which succeed before the next one fails, if any
+        pub(super) static HEAP_ALLOCATIONS_BEFORE_FAILURE: Cell<Option<usize>> = Cell::new(None);
+    }
+
+    #[test]
+    fn spawn_from_exiting_parent_is_parent_exiting() {
+        let parent = parent();
+        parent.exit_normal();
+
+        assert_eq!(
+            spawn(&Default::default(), Some(&parent)).unwrap_err(),
+            SpawnError::ParentExiting
+        );
+    }
+
+    #[test]
+    fn spawn_from_concurrently_exiting_parent_does_not_panic() {
+        let parent = Arc::new(parent());
+        let exiting = parent.clone();
+
+        let exiter = thread::spawn(move || exiting.exit_normal());
+
+        for _ in 0..100 {
+            match spawn(&Default::default(), Some(&parent)) {
+                Ok(_) | Err(SpawnError::ParentExiting) => (),
+                Err(err) => panic!("unexpected spawn error: {:?}", err),
+            }
+        }
+
+        exiter.join().unwrap();
+
+        assert_eq!(
+            spawn(&Default::default(), Some(&parent)).unwrap_err(),
+            SpawnError::ParentExiting
+        );
+    }
+
+    #[test]
+    fn spawn_with_failed_heap_allocation_is_heap_allocation() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let processes_before = registry::pid_count();
+
+            HEAP_ALLOCATIONS_BEFORE_FAILURE.with(|remaining| remaining.set(Some(0)));
+            let result = spawn(&Default::default(), None);
+            HEAP_ALLOCATIONS_BEFORE_FAILURE.with(|remaining| remaining.set(None));
+
+            assert_eq!(result.unwrap_err(), SpawnError::HeapAllocation);
+            assert_eq!(registry::pid_count(), processes_before);
+        });
+    }
+
+    #[test]
+    fn link_without_parent_is_link_without_parent() {
+        let mut options: Options = Default::default();
+        options.link = true;
+
+        assert_eq!(
+            spawn(&options, None).unwrap_err(),
+            SpawnError::InvalidOption(SpawnOptionError::LinkWithoutParent)
+        );
+    }
+
+    #[test]
+    fn monitor_without_parent_is_monitor_without_parent_and_badarg() {
+        let mut options: Options = Default::default();
+        options.monitor = true;
+
+        let err = spawn(&options, None).unwrap_err();
+        assert_eq!(
+            err,
+            SpawnError::InvalidOption(SpawnOptionError::MonitorWithoutParent)
+        );
+        assert!(matches!(
+            options.connect(None, &parent()),
+            Err(SpawnOptionError::MonitorWithoutParent)
+        ));
+
+        match Exception::from(err) {
+            Exception::Runtime(runtime) => assert_eq!(runtime.reason(), atom!("badarg")),
+            exception => panic!("unexpected exception: {:?}", exception),
+        }
+    }
+
+    #[test]
+    fn child_inherits_parent_priority_unless_overridden() {
+        let parent = parent();
+        parent.set_priority(Priority::Max);
+        let mut options: Options = Default::default();
+
+        assert_eq!(options.cascaded_priority(None), Priority::Normal);
+        assert_eq!(options.cascaded_priority(Some(&parent)), Priority::Max);
+
+        options.priority = Some(Priority::Low);
+        assert_eq!(options.cascaded_priority(None), Priority::Low);
+        assert_eq!(options.cascaded_priority(Some(&parent)), Priority::Low);
+    }
+
+    #[test]
+    fn fullsweep_after_is_set_on_child() {
+        let mut options: Options = Default::default();
+
+        assert_eq!(
+            spawn(&options, None).unwrap().fullsweep_after(),
+            DEFAULT_FULLSWEEP_AFTER
+        );
+
+        options.fullsweep_after = Some(10);
+
+        assert_eq!(spawn(&options, None).unwrap().fullsweep_after(), 10);
+    }
+
+    #[test]
+    fn spawn_link_from_parent_exiting_before_scheduling_delivers_exit_once() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let parent = parent();
+            let (connection, child) = spawn_link(&parent);
+
+            // The child isn't registered yet, so the parent's own propagation can't reach it
+            let exception = exit(&parent);
+            process::propagate_exit_to_links(&parent, Some(&exception));
+            assert_eq!(child.message_queue_len(), 0);
+
+            let child = schedule(child);
+            connection.propagate_parent_exit(Some(&parent), &child);
+            process::propagate_exit_to_links(&parent, Some(&exception));
+            connection.propagate_parent_exit(Some(&parent), &child);
+            process::handle_signals(&child);
+
+            assert_eq!(child.message_queue_len(), 1);
+            registry::remove_pid_to_process(&child);
+        });
+    }
+
+    #[test]
+    fn spawn_link_from_parent_exiting_after_scheduling_delivers_exit_once() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let parent = parent();
+            let (connection, child) = spawn_link(&parent);
+            let child = schedule(child);
+
+            let exception = exit(&parent);
+            process::propagate_exit_to_links(&parent, Some(&exception));
+            connection.propagate_parent_exit(Some(&parent), &child);
+            process::handle_signals(&child);
+
+            assert_eq!(child.message_queue_len(), 1);
+            registry::remove_pid_to_process(&child);
+        });
+    }
+
+    #[test]
+    fn spawn_link_from_exited_parent_exits_child_before_it_runs() {
+        with_process_limit(DEFAULT_PROCESS_LIMIT, || {
+            let parent = parent();
+            let (connection, child) = spawn_link(&parent);
+            child.trap_exit(false);
+            exit(&parent);
+
+            let child = schedule(child);
+            connection.propagate_parent_exit(Some(&parent), &child);
+            process::handle_signals(&child);
+
+            assert!(child.is_exiting());
+            registry::remove_pid_to_process(&child);
+        });
+    }
+
+    /// Spawns a child trapping exits and linked to `parent`, without scheduling it
+    fn spawn_link(parent: &Process) -> (Connection, Process) {
+        let mut options: Options = Default::default();
+        options.link = true;
+
+        let child = spawn(&options, Some(parent)).unwrap();
+        child.trap_exit(true);
+        let connection = options.connect(Some(parent), &child).unwrap();
+
+        (connection, child)
+    }
+
+    /// Registers `child`, as scheduling it would, so that exits can be propagated to it
+    fn schedule(child: Process) -> Arc<Process> {
+        let reservation = registry::reserve_pid().unwrap();
+        let child = Arc::new(child);
+        registry::put_pid_to_process(&child, reservation);
+
+        child
+    }
+
+    fn exit(process: &Process) -> RuntimeException {
+        process.exit(atom!("shutdown"), Trace::capture(), None);
+
+        match *process.status.read() {
+            Status::RuntimeException(ref exception) => exception.clone(),
+            ref status => panic!("unexpected status: {:?}", status),
+        }
+    }
+
+    fn parent() -> Process {
+        let (heap, heap_size) = alloc::default_heap().unwrap();
+
+        Process::new(
+            Default::default(),
+            None,
+            ModuleFunctionArity {
+                module: Atom::from_str("test"),
+                function: Atom::from_str("parent"),
+                arity: 0,
+            },
+            heap,
+            heap_size,
+        )
+    }
+
+    fn spawn(options: &Options, parent: Option<&Process>) -> Result<Process, SpawnError> {
+        options.spawn(parent, Atom::from_str("test"), Atom::from_str("child"), 0)
+    }
+}
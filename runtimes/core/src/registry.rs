@@ -1,6 +1,8 @@
 /// Maps registered names (`Atom`) to `LocalPid` or `Port`
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Weak};
 
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use lazy_static::lazy_static;
 
@@ -9,6 +11,16 @@ use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::exception;
 use liblumen_alloc::Process;
 
+use crate::process::spawn::SpawnError;
+
+/// The number of processes that may exist at once unless configured otherwise with `+P`, the same
+/// as BEAM's default
+pub const DEFAULT_PROCESS_LIMIT: usize = 262_144;
+
+static PROCESS_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_PROCESS_LIMIT);
+// Processes in the process table plus outstanding `PidReservation`s
+static PROCESS_COUNT: AtomicUsize = AtomicUsize::new(0);
+
 lazy_static! {
     static ref REGISTERED_BY_NAME: DashMap<Atom, Registered> = Default::default();
     // Strong references are owned by the scheduler run queues
@@ -51,32 +63,157 @@ pub fn pid_to_self_or_process(pid: Pid, process_arc: &Arc<Process>) -> Option<Ar
     }
 }
 
+/// Registers `arc_process` as `name`, unless `name` is already registered or `arc_process` is
+/// already registered under another name.
+///
+/// The check and the registration happen under the lock for `name`, so when processes race to
+/// register the same name, only one of them succeeds.
 pub fn put_atom_to_process(name: Atom, arc_process: Arc<Process>) -> bool {
-    if !REGISTERED_BY_NAME.contains_key(&name) {
-        register_in(arc_process, name)
-    } else {
-        false
+    match REGISTERED_BY_NAME.entry(name) {
+        Entry::Occupied(_) => false,
+        Entry::Vacant(vacant) => {
+            let mut writable_registered_name = arc_process.registered_name.write();
+
+            if writable_registered_name.is_none() {
+                vacant.insert(Registered::Process(Arc::downgrade(&arc_process)));
+                *writable_registered_name = Some(name);
+
+                true
+            } else {
+                false
+            }
+        }
     }
 }
 
-pub fn register_in(arc_process: Arc<Process>, name: Atom) -> bool {
-    let mut writable_registered_name = arc_process.registered_name.write();
+/// Returns the number of processes in the process table
+pub fn pid_count() -> usize {
+    WEAK_PROCESS_CONTROL_BLOCK_BY_PID.len()
+}
+
+/// Returns all live processes in the process table, ordered by pid
+pub fn processes() -> Vec<Arc<Process>> {
+    let mut processes: Vec<Arc<Process>> = WEAK_PROCESS_CONTROL_BLOCK_BY_PID
+        .iter()
+        .filter_map(|entry| entry.value().upgrade())
+        .collect();
+    processes.sort_by_key(|process| process.pid());
 
-    if let None = *writable_registered_name {
-        REGISTERED_BY_NAME.insert(name, Registered::Process(Arc::downgrade(&arc_process)));
-        *writable_registered_name = Some(name);
-        true
-    } else {
-        false
-    }
+    processes
+}
+
+/// Returns the pids in the process table, ordered by pid, including those of processes that are
+/// exiting, but have not yet been removed.
+///
+/// The pids are copied out, so the table isn't locked while the caller allocates terms for them.
+pub fn pids() -> Vec<Pid> {
+    let mut pids: Vec<Pid> = WEAK_PROCESS_CONTROL_BLOCK_BY_PID
+        .iter()
+        .map(|entry| *entry.key())
+        .collect();
+    pids.sort();
+
+    pids
 }
 
-pub fn put_pid_to_process(arc_process: &Arc<Process>) {
+/// Adds `arc_process` to the process table, taking the room `reservation` holds for it until the
+/// process is removed with `remove_pid_to_process`.
+pub fn put_pid_to_process(arc_process: &Arc<Process>, reservation: PidReservation) {
     if let Some(_) =
         WEAK_PROCESS_CONTROL_BLOCK_BY_PID.insert(arc_process.pid(), Arc::downgrade(&arc_process))
     {
         panic!("Process already registered with pid");
     }
+
+    std::mem::forget(reservation);
+}
+
+/// Removes `process` from the process table once it has exited and its exit has been propagated,
+/// so that a new process can take its room.
+pub fn remove_pid_to_process(process: &Process) {
+    let removed = WEAK_PROCESS_CONTROL_BLOCK_BY_PID
+        .remove_if(&process.pid(), |_, weak_process| {
+            std::ptr::eq(weak_process.as_ptr(), process)
+        })
+        .is_some();
+
+    // Only the removal releases the room, so a process removed twice is only counted once
+    if removed {
+        PROCESS_COUNT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The maximum number of processes that may exist at once
+pub fn process_limit() -> usize {
+    PROCESS_LIMIT.load(Ordering::SeqCst)
+}
+
+/// Sets the maximum number of processes that may exist at once.
+///
+/// Processes that already exist are not affected if there are more of them than `limit`, but no
+/// more can be spawned until enough have exited.
+pub fn set_process_limit(limit: usize) {
+    PROCESS_LIMIT.store(limit, Ordering::SeqCst);
+}
+
+/// Reserves room in the process table for a process about to be spawned, failing with
+/// `SpawnError::SystemLimit` if `process_limit` processes already exist.
+///
+/// Reserve before anything is allocated for the process, so that a spawn over the limit fails
+/// without doing any work.  The reservation is released if it is dropped before being passed to
+/// `put_pid_to_process`, so a spawn that fails later does not leak room.
+pub fn reserve_pid() -> Result<PidReservation, SpawnError> {
+    try_reserve_pid()
+        .or_else(|_| {
+            // Processes dropped without exiting, such as those still queued when their scheduler is
+            // dropped, are never removed, so their room is only reclaimed once it is needed
+            remove_dropped_processes();
+            try_reserve_pid()
+        })
+        .map(|_| PidReservation { _private: () })
+        .map_err(|_| SpawnError::SystemLimit)
+}
+
+fn try_reserve_pid() -> Result<usize, usize> {
+    let limit = process_limit();
+
+    PROCESS_COUNT.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+        if count < limit {
+            Some(count + 1)
+        } else {
+            None
+        }
+    })
+}
+
+fn remove_dropped_processes() {
+    let dropped_pids: Vec<Pid> = WEAK_PROCESS_CONTROL_BLOCK_BY_PID
+        .iter()
+        .filter(|entry| entry.value().strong_count() == 0)
+        .map(|entry| *entry.key())
+        .collect();
+
+    for pid in dropped_pids {
+        if WEAK_PROCESS_CONTROL_BLOCK_BY_PID
+            .remove_if(&pid, |_, weak_process| weak_process.strong_count() == 0)
+            .is_some()
+        {
+            PROCESS_COUNT.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Room in the process table for one process, from `reserve_pid`
+#[must_use]
+#[derive(Debug)]
+pub struct PidReservation {
+    _private: (),
+}
+
+impl Drop for PidReservation {
+    fn drop(&mut self) {
+        PROCESS_COUNT.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 pub fn unregister(name: &Atom) -> bool {
@@ -94,6 +231,20 @@ pub fn unregister(name: &Atom) -> bool {
     }
 }
 
+/// Removes the registration of `process`, if any, so that its name can be registered again once it
+/// has exited.
+pub fn unregister_process(process: &Process) {
+    // Taken before `REGISTERED_BY_NAME` is locked, as `put_atom_to_process` locks the two in the
+    // opposite order
+    let option_name = process.registered_name.write().take();
+
+    if let Some(name) = option_name {
+        REGISTERED_BY_NAME.remove_if(&name, |_, registered| match registered {
+            Registered::Process(weak_process) => std::ptr::eq(weak_process.as_ptr(), process),
+        });
+    }
+}
+
 #[cfg_attr(test, derive(Debug))]
 pub enum Registered {
     Process(Weak<Process>),
@@ -108,3 +259,103 @@ impl PartialEq for Registered {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    use liblumen_alloc::erts::process::alloc;
+    use liblumen_alloc::erts::ModuleFunctionArity;
+
+    #[test]
+    fn spawns_over_process_limit_are_system_limit_until_processes_are_removed() {
+        with_process_limit(50, || {
+            let results: Vec<Result<Arc<Process>, SpawnError>> = (0..60).map(|_| spawn()).collect();
+
+            assert!(results[..50].iter().all(Result::is_ok));
+            assert!(results[50..]
+                .iter()
+                .all(|result| result.as_ref().unwrap_err() == &SpawnError::SystemLimit));
+            assert_eq!(spawn().unwrap_err(), SpawnError::SystemLimit);
+
+            for result in results.iter().take(50) {
+                let arc_process = result.as_ref().unwrap();
+                arc_process.exit_normal();
+                remove_pid_to_process(arc_process);
+            }
+
+            let respawned: Vec<Arc<Process>> = (0..50).map(|_| spawn().unwrap()).collect();
+
+            assert_eq!(spawn().unwrap_err(), SpawnError::SystemLimit);
+
+            for arc_process in respawned.iter() {
+                remove_pid_to_process(arc_process);
+                // Removing a process again does not release more room
+                remove_pid_to_process(arc_process);
+            }
+        });
+    }
+
+    #[test]
+    fn dropped_reservation_releases_room() {
+        with_process_limit(1, || {
+            let reservation = reserve_pid().unwrap();
+
+            assert_eq!(reserve_pid().unwrap_err(), SpawnError::SystemLimit);
+
+            drop(reservation);
+
+            let arc_process = spawn().unwrap();
+            remove_pid_to_process(&arc_process);
+        });
+    }
+
+    #[test]
+    fn dropped_process_releases_room_when_needed() {
+        with_process_limit(1, || {
+            drop(spawn().unwrap());
+
+            let arc_process = spawn().unwrap();
+            remove_pid_to_process(&arc_process);
+        });
+    }
+
+    /// Runs `f` with `limit` processes allowed, while no other test changes the process table
+    pub(crate) fn with_process_limit<F>(limit: usize, f: F)
+    where
+        F: FnOnce(),
+    {
+        let _guard = PROCESS_LIMIT_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        set_process_limit(limit);
+        f();
+        set_process_limit(DEFAULT_PROCESS_LIMIT);
+    }
+
+    fn spawn() -> Result<Arc<Process>, SpawnError> {
+        let reservation = reserve_pid()?;
+        let (heap, heap_size) = alloc::default_heap().unwrap();
+        let arc_process = Arc::new(Process::new(
+            Default::default(),
+            None,
+            ModuleFunctionArity {
+                module: Atom::from_str("test"),
+                function: Atom::from_str("child"),
+                arity: 0,
+            },
+            heap,
+            heap_size,
+        ));
+        put_pid_to_process(&arc_process, reservation);
+
+        Ok(arc_process)
+    }
+
+    lazy_static! {
+        static ref PROCESS_LIMIT_TEST_LOCK: Mutex<()> = Mutex::new(());
+    }
+}
@@ -1,9 +1,13 @@
+pub mod counter;
 pub mod run_queue;
 
 use std::any::Any;
 use std::fmt::Debug;
-use std::sync::atomic::Ordering;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Weak};
+use std::thread::{self, Thread};
+use std::time::Duration;
 
 use hashbrown::HashMap;
 
@@ -11,12 +15,17 @@ use lazy_static::lazy_static;
 
 use liblumen_core::locks::{Mutex, RwLock};
 
+use liblumen_alloc::erts::process::alloc::StackAllocatorStats;
 use liblumen_alloc::erts::process::Process;
 pub use liblumen_alloc::erts::scheduler::id::ID;
 use liblumen_alloc::erts::term::prelude::*;
+use liblumen_alloc::time::{Milliseconds, Monotonic};
 use liblumen_alloc::Priority;
 
 use crate::process::spawn::options::{Connection, Options};
+use crate::process::spawn::SpawnError;
+use crate::registry::PidReservation;
+use crate::time::monotonic;
 use crate::timer::Hierarchy;
 
 extern "Rust" {
@@ -49,8 +58,15 @@ pub fn from_id(id: &ID) -> Option<Arc<dyn Scheduler>> {
 }
 
 fn registered() -> Arc<dyn Scheduler> {
+    // The system starts with its first scheduler, which is when `statistics(wall_clock)` counts from
+    monotonic::start();
+
     let mut locked_scheduler_by_id = SCHEDULER_BY_ID.lock();
     let arc_scheduler = unsafe { unregistered() };
+    // Schedulers are registered from the thread-local of the thread that runs them
+    THREAD_BY_ID
+        .lock()
+        .insert(arc_scheduler.id(), thread::current());
 
     if let Some(_) =
         locked_scheduler_by_id.insert(arc_scheduler.id().clone(), Arc::downgrade(&arc_scheduler))
@@ -73,6 +89,207 @@ pub fn unregister(id: &ID) {
     locked_scheduler_by_id
         .remove(id)
         .expect("Scheduler not registered");
+    THREAD_BY_ID.lock().remove(id);
+}
+
+/// Wakes the thread running the scheduler with `id` if it is parked because it was idle, so that
+/// it runs a process another thread made runnable without waiting for its idle timeout.
+///
+/// If the thread isn't parked, its next park returns immediately, so a wakeup that races with the
+/// scheduler deciding to sleep isn't lost.
+pub fn unpark(id: &ID) {
+    if let Some(thread) = THREAD_BY_ID.lock().get(id) {
+        thread.unpark();
+    }
+}
+
+/// Wakes every parked scheduler, such as when a signal is broadcast that they need to handle
+pub fn unpark_all() {
+    for thread in THREAD_BY_ID.lock().values() {
+        thread.unpark();
+    }
+}
+
+/// Returns all registered schedulers
+pub fn all() -> Vec<Arc<dyn Scheduler>> {
+    SCHEDULER_BY_ID
+        .lock()
+        .values()
+        .filter_map(|arc_scheduler| arc_scheduler.upgrade())
+        .collect()
+}
+
+/// Runs `f`, counting the time it takes as time that the schedulers were busy.
+///
+/// Schedulers call this around running a process, so that `busy_time` approximates the CPU time
+/// used by all processes.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn busy<T, F: FnOnce() -> T>(f: F) -> T {
+    let started = std::time::Instant::now();
+    let result = f();
+    BUSY_MICROSECONDS.fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+
+    result
+}
+
+/// Runs `f`, counting the time it takes as time that the schedulers were busy.
+///
+/// `std::time::Instant` is not available, so only whole milliseconds are counted.
+#[cfg(target_arch = "wasm32")]
+pub fn busy<T, F: FnOnce() -> T>(f: F) -> T {
+    let started = crate::time::monotonic::time();
+    let result = f();
+    let elapsed: u64 = (crate::time::monotonic::time() - started).into();
+    BUSY_MICROSECONDS.fetch_add(elapsed * 1_000, Ordering::Relaxed);
+
+    result
+}
+
+/// The total time that all schedulers have been busy running processes
+pub fn busy_time() -> Duration {
+    Duration::from_micros(BUSY_MICROSECONDS.load(Ordering::Relaxed))
+}
+
+/// Returns a snapshot of the resource usage of every registered scheduler, in the order they were
+/// created
+pub fn stats() -> Vec<SchedulerStats> {
+    let mut stats: Vec<SchedulerStats> = all().iter().map(|scheduler| scheduler.stats()).collect();
+    stats.sort_by_key(|stats| stats.id);
+
+    stats
+}
+
+/// Writes the run queue lengths of every scheduler to `writer`, as the `=scheduler` sections of a
+/// BEAM crash dump
+pub fn dump_run_queues(writer: &mut dyn Write) -> io::Result<()> {
+    for stats in stats() {
+        writeln!(writer, "=scheduler:{}", stats.id)?;
+        for (priority, len) in PRIORITIES.iter().zip(stats.run_queue_lengths.iter()) {
+            writeln!(writer, "Run Queue {:?} Length: {}", priority, len)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The priorities in the order `SchedulerStats::run_queue_lengths` and
+/// `statistics(run_queue_lengths)` report them
+pub const PRIORITIES: [Priority; 4] = [
+    Priority::Max,
+    Priority::High,
+    Priority::Normal,
+    Priority::Low,
+];
+
+/// Counts the work done by a scheduler.
+///
+/// Only the thread running the scheduler updates the counters, so they are relaxed atomics, which
+/// other threads read for `stats` without slowing down the scheduler.
+#[derive(Debug, Default)]
+pub struct Counters {
+    reductions: AtomicU64,
+    context_switches: AtomicU64,
+    /// The second since the system started that `runs_this_second` counts the runs in
+    second: AtomicU64,
+    runs_this_second: AtomicU64,
+    steals: AtomicU64,
+    stolen: AtomicU64,
+    parks: AtomicU64,
+    unparks: AtomicU64,
+    /// One more than the logical CPU the thread running the scheduler is bound to, or `0` if it
+    /// isn't bound
+    bound_cpu: AtomicUsize,
+}
+impl Counters {
+    /// Counts a run of a process, in which it did `reductions`
+    pub fn ran(&self, reductions: u64) {
+        self.reductions.fetch_add(reductions, Ordering::Relaxed);
+        self.context_switches.fetch_add(1, Ordering::Relaxed);
+
+        let second = current_second();
+        if self.second.load(Ordering::Relaxed) == second {
+            self.runs_this_second.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.second.store(second, Ordering::Relaxed);
+            self.runs_this_second.store(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts stealing `processes` from another scheduler
+    pub fn stole(&self, processes: usize) {
+        self.steals.fetch_add(1, Ordering::Relaxed);
+        self.stolen.fetch_add(processes as u64, Ordering::Relaxed);
+    }
+
+    /// Parks the thread running this scheduler, which must be the current thread, until another
+    /// thread unparks it or `timeout` passes, counting the park, and the unpark if there was one.
+    ///
+    /// A spurious wakeup is counted as an unpark, as the two can't be told apart.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn park(&self, timeout: Duration) {
+        self.parks.fetch_add(1, Ordering::Relaxed);
+
+        let started = std::time::Instant::now();
+        thread::park_timeout(timeout);
+
+        if started.elapsed() < timeout {
+            self.unparks.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The reductions done by all the processes this scheduler has run
+    pub fn reductions(&self) -> u64 {
+        self.reductions.load(Ordering::Relaxed)
+    }
+
+    /// The number of times this scheduler has switched to running a process
+    pub fn context_switches(&self) -> u64 {
+        self.context_switches.load(Ordering::Relaxed)
+    }
+
+    /// The number of processes this scheduler has run in the current second
+    pub fn runs_this_second(&self) -> u64 {
+        // No process has run since the second began if the runs counted are from an earlier one
+        if self.second.load(Ordering::Relaxed) == current_second() {
+            self.runs_this_second.load(Ordering::Relaxed)
+        } else {
+            0
+        }
+    }
+
+    /// The number of times this scheduler has stolen processes from another scheduler
+    pub fn steals(&self) -> u64 {
+        self.steals.load(Ordering::Relaxed)
+    }
+
+    /// The number of processes this scheduler has stolen from other schedulers
+    pub fn stolen(&self) -> u64 {
+        self.stolen.load(Ordering::Relaxed)
+    }
+
+    /// The number of times the thread running this scheduler has parked because it was idle
+    pub fn parks(&self) -> u64 {
+        self.parks.load(Ordering::Relaxed)
+    }
+
+    /// The number of times the thread running this scheduler was unparked before its idle timeout
+    pub fn unparks(&self) -> u64 {
+        self.unparks.load(Ordering::Relaxed)
+    }
+
+    /// Records that the thread running this scheduler was bound to the logical CPU `cpu`
+    pub fn bound(&self, cpu: usize) {
+        self.bound_cpu.store(cpu + 1, Ordering::Relaxed);
+    }
+
+    /// The logical CPU the thread running this scheduler is bound to, if it is bound
+    pub fn bound_cpu(&self) -> Option<usize> {
+        self.bound_cpu.load(Ordering::Relaxed).checked_sub(1)
+    }
+}
+
+fn current_second() -> u64 {
+    u64::from(Milliseconds::from(monotonic::time())) / 1_000
 }
 
 /// Returns `true` if `arc_process` was run; otherwise, `false`.
@@ -118,6 +335,37 @@ pub enum Run {
     None,
 }
 
+/// The longest a scheduler sleeps when idle, even if no timer is due sooner.
+///
+/// Schedulers are woken with [unpark] when another thread makes one of their processes runnable
+/// and with [unpark_all] when a signal arrives, so this only bounds how long an idle scheduler
+/// goes without looking for work it isn't told about, such as processes it could steal.
+pub const MAX_IDLE_SLEEP: Duration = Duration::from_millis(100);
+
+/// How long a scheduler can sleep after [Scheduler::run_once] ran no process
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Idle {
+    /// The earliest timer times out at this time, which may wake a process waiting in a
+    /// `receive ... after`, so the scheduler should run again then
+    Until(Monotonic),
+    /// There are no timers, so only a message or exit signal from another thread can make a
+    /// process runnable
+    Indefinitely,
+}
+impl Idle {
+    /// How long, from `now`, the scheduler can sleep without missing a timer, but no longer than
+    /// `max`, so that it still notices work that arrives from other threads
+    pub fn timeout(self, now: Monotonic, max: Duration) -> Duration {
+        match self {
+            Self::Until(deadline) => match deadline.checked_sub(now) {
+                Some(Milliseconds(milliseconds)) => Duration::from_millis(milliseconds).min(max),
+                None => Duration::ZERO,
+            },
+            Self::Indefinitely => max,
+        }
+    }
+}
+
 pub trait Scheduled {
     fn scheduler(&self) -> Option<Arc<dyn Scheduler>>;
 }
@@ -140,6 +388,8 @@ pub trait Scheduler: Debug + Send + Sync {
     fn id(&self) -> ID;
     fn hierarchy(&self) -> &RwLock<Hierarchy>;
     fn next_reference_number(&self) -> ReferenceNumber;
+    /// The work this scheduler has done running processes
+    fn counters(&self) -> &Counters;
 
     /// Gets the next available unique integer
     fn next_unique_integer(&self) -> u64;
@@ -154,15 +404,26 @@ pub trait Scheduler: Debug + Send + Sync {
     /// > 8. Pick a process to execute
     /// > -- [The Scheduler Loop](https://blog.stenmans.org/theBeamBook/#_the_scheduler_loop)
     ///
-    /// Returns `true` if a process was run.  Returns `false` if no process could be run and the
-    /// scheduler should sleep or work steal.
+    /// Returns `true` if a process was run.  Returns `false` if no process could be run, including
+    /// when all processes are waiting, and the scheduler should sleep, for as long as
+    /// [Scheduler::idle] allows, or work steal.
     #[must_use]
     fn run_once(&self) -> bool;
+    /// Returns how long the scheduler can sleep after `run_once` ran no process, before a timer
+    /// may make a process runnable again.
+    fn idle(&self) -> Idle {
+        match self.hierarchy().read().next_deadline() {
+            Some(deadline) => Idle::Until(deadline),
+            None => Idle::Indefinitely,
+        }
+    }
+    /// Returns the number of processes with `priority` in the current scheduler's run queue
     fn run_queue_len(&self, priority: Priority) -> usize;
     /// Returns the length of the current scheduler's run queue
     fn run_queues_len(&self) -> usize;
-    /// Schedules the given process for execution
-    fn schedule(&self, process: Process) -> Arc<Process>;
+    /// Schedules the given process for execution, adding it to the process table in the room
+    /// `reservation` holds for it
+    fn schedule(&self, process: Process, reservation: PidReservation) -> Arc<Process>;
     /// Spawns the init process, should be called immediately after
     /// (primary) scheduler creation.
     fn spawn_init(&self, minimum_heap_size: usize) -> anyhow::Result<Arc<Process>>;
@@ -172,12 +433,18 @@ pub trait Scheduler: Debug + Send + Sync {
     /// ## Error handling
     /// The `closure` having the wrong arity (that is, not 0-arity) should cause the spawned process
     /// to exit `badarity`.
+    ///
+    /// If the process cannot be spawned, a `SpawnError` is returned, and nothing allocated for
+    /// the process outlives the call.
+    ///
+    /// If `options` links or monitors, the link or monitor is established before the process is
+    /// scheduled, so the parent is notified even if the process exits before the call returns.
     fn spawn_closure(
         &self,
         parent: Option<&Process>,
         closure: Boxed<Closure>,
         options: Options,
-    ) -> anyhow::Result<Spawned>;
+    ) -> Result<Spawned, SpawnError>;
     /// Spawns a new process from the given `parent`, using the given `module`, `function`, and
     /// `arguments` with `options`.
     ///
@@ -186,6 +453,12 @@ pub trait Scheduler: Debug + Send + Sync {
     /// up in the child process.
     ///
     /// If the function does not exist with arity arguments it is an `undef` error, not `badarity`.
+    ///
+    /// If the process cannot be spawned, a `SpawnError` is returned, and nothing allocated for
+    /// the process outlives the call.
+    ///
+    /// If `options` links or monitors, the link or monitor is established before the process is
+    /// scheduled, so the parent is notified even if the process exits before the call returns.
     fn spawn_module_function_arguments(
         &self,
         parent: Option<&Process>,
@@ -193,9 +466,69 @@ pub trait Scheduler: Debug + Send + Sync {
         function: Atom,
         arguments: Vec<Term>,
         options: Options,
-    ) -> anyhow::Result<Spawned>;
+    ) -> Result<Spawned, SpawnError>;
     fn shutdown(&self) -> anyhow::Result<()>;
     fn stop_waiting(&self, process: &Process);
+    /// Runs `process` again if it was parked while suspended, and is no longer suspended
+    fn resume(&self, process: &Process);
+    /// Sets the priority of `process`, moving it to the run queue for `priority` if it is in one,
+    /// and returns its previous priority
+    fn set_priority(&self, process: &Process, priority: Priority) -> Priority;
+
+    /// Returns a snapshot of this scheduler's resource usage
+    fn stats(&self) -> SchedulerStats {
+        SchedulerStats::new(self, None)
+    }
+}
+
+/// A snapshot of a scheduler's resource usage, as returned by `Scheduler::stats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchedulerStats {
+    pub id: ID,
+    /// The number of processes in the scheduler's run queues, including those that are waiting
+    pub run_queues_len: usize,
+    /// The number of runnable processes in the scheduler's run queue for each priority, in the
+    /// order of `PRIORITIES`
+    pub run_queue_lengths: [usize; 4],
+    /// The number of processes the scheduler has run in the current second
+    pub runs_this_second: u64,
+    /// The reductions done by all the processes the scheduler has run
+    pub reductions: u64,
+    /// The number of times the scheduler has switched to running a process
+    pub context_switches: u64,
+    /// The number of times the scheduler has stolen processes from another scheduler
+    pub steals: u64,
+    /// The number of processes the scheduler has stolen from other schedulers
+    pub stolen: u64,
+    /// The number of times the thread running the scheduler has parked because it was idle
+    pub parks: u64,
+    /// The number of times the thread running the scheduler was unparked before its idle timeout
+    pub unparks: u64,
+    /// The logical CPU the thread running the scheduler is bound to, if it is bound
+    pub bound_cpu: Option<usize>,
+    /// The occupancy of the scheduler's process stacks, if its processes have native stacks
+    pub stacks: Option<StackAllocatorStats>,
+}
+impl SchedulerStats {
+    /// Takes a snapshot of the resource usage of `scheduler`, whose processes use `stacks`
+    pub fn new<S: Scheduler + ?Sized>(scheduler: &S, stacks: Option<StackAllocatorStats>) -> Self {
+        let counters = scheduler.counters();
+
+        Self {
+            id: scheduler.id(),
+            run_queues_len: scheduler.run_queues_len(),
+            run_queue_lengths: PRIORITIES.map(|priority| scheduler.run_queue_len(priority)),
+            runs_this_second: counters.runs_this_second(),
+            reductions: counters.reductions(),
+            context_switches: counters.context_switches(),
+            steals: counters.steals(),
+            stolen: counters.stolen(),
+            parks: counters.parks(),
+            unparks: counters.unparks(),
+            bound_cpu: counters.bound_cpu(),
+            stacks,
+        }
+    }
 }
 
 pub trait SchedulerDependentAlloc {
@@ -229,6 +562,9 @@ impl Spawned {
     }
 }
 
+/// The time counted by `busy`
+static BUSY_MICROSECONDS: AtomicU64 = AtomicU64::new(0);
+
 thread_local! {
   static SCHEDULER: Arc<dyn Scheduler> = registered();
 }
@@ -238,4 +574,82 @@ lazy_static! {
         RwLock::new(None);
     static ref SCHEDULER_BY_ID: Mutex<HashMap<ID, Weak<dyn Scheduler>>> =
         Mutex::new(Default::default());
+    static ref THREAD_BY_ID: Mutex<HashMap<ID, Thread>> = Mutex::new(Default::default());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counters_count_runs_reductions_and_context_switches() {
+        let counters = Counters::default();
+
+        counters.ran(10);
+        counters.ran(5);
+
+        assert_eq!(counters.reductions(), 15);
+        assert_eq!(counters.context_switches(), 2);
+        // Unless the second just turned over between the runs
+        assert!(1 <= counters.runs_this_second());
+        assert!(counters.runs_this_second() <= 2);
+    }
+
+    #[test]
+    fn runs_this_second_are_not_counted_in_a_later_second() {
+        let counters = Counters::default();
+
+        monotonic::freeze_at(Monotonic::from_millis(1_500));
+        counters.ran(1);
+        counters.ran(1);
+        assert_eq!(counters.runs_this_second(), 2);
+
+        monotonic::freeze_at(Monotonic::from_millis(2_000));
+        assert_eq!(counters.runs_this_second(), 0);
+
+        counters.ran(1);
+        assert_eq!(counters.runs_this_second(), 1);
+        assert_eq!(counters.context_switches(), 3);
+    }
+
+    #[test]
+    fn counters_count_steals_and_processes_stolen() {
+        let counters = Counters::default();
+
+        counters.stole(3);
+        counters.stole(1);
+
+        assert_eq!(counters.steals(), 2);
+        assert_eq!(counters.stolen(), 4);
+    }
+
+    #[test]
+    fn counters_count_parks_and_unparks_before_timeout() {
+        let counters = Counters::default();
+
+        let parked = thread::current();
+        let unparker = thread::spawn(move || parked.unpark());
+        counters.park(Duration::from_secs(10));
+        unparker.join().unwrap();
+
+        assert_eq!(counters.parks(), 1);
+        assert_eq!(counters.unparks(), 1);
+
+        counters.park(Duration::from_millis(1));
+
+        assert_eq!(counters.parks(), 2);
+        assert_eq!(counters.unparks(), 1);
+    }
+
+    #[test]
+    fn counters_record_the_bound_cpu() {
+        let counters = Counters::default();
+        assert_eq!(counters.bound_cpu(), None);
+
+        counters.bound(0);
+        assert_eq!(counters.bound_cpu(), Some(0));
+
+        counters.bound(3);
+        assert_eq!(counters.bound_cpu(), Some(3));
+    }
 }
@@ -0,0 +1,197 @@
+use std::cell::UnsafeCell;
+use std::fmt::{self, Debug};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// How many numbers the owning thread reserves from the shared counter at a time
+pub const CHUNK_LEN: u64 = 1 << 20;
+
+/// A counter handing out numbers that are unique across all threads, such as a scheduler's
+/// reference numbers and non-monotonic unique integers.
+///
+/// Almost all numbers are taken by the scheduler's own thread, so the thread that creates the
+/// counter owns it: it reserves `CHUNK_LEN` numbers at a time from the shared counter and hands
+/// them out with a non-atomic increment. Any other thread takes its numbers from the shared
+/// counter directly. As every number is reserved by a single `fetch_add` on the shared counter,
+/// numbers are never handed out twice, but they are only increasing within each thread.
+pub struct ChunkedCounter {
+    shared: AtomicU64,
+    owner: usize,
+    // Only accessed by the `owner` thread
+    local: UnsafeCell<Chunk>,
+}
+// This guarantee holds as long as `local` is only ever accessed by the `owner` thread
+unsafe impl Sync for ChunkedCounter {}
+impl ChunkedCounter {
+    /// Creates a counter owned by the current thread
+    pub fn new() -> Self {
+        Self {
+            shared: AtomicU64::new(0),
+            owner: thread_id(),
+            local: UnsafeCell::new(Chunk { next: 0, end: 0 }),
+        }
+    }
+
+    /// Returns the next unique number
+    #[inline]
+    pub fn next(&self) -> u64 {
+        if thread_id() == self.owner {
+            let chunk = unsafe { &mut *self.local.get() };
+
+            if chunk.next == chunk.end {
+                let start = self.reserve(CHUNK_LEN);
+                chunk.next = start;
+                chunk.end = start + CHUNK_LEN;
+            }
+
+            let next = chunk.next;
+            chunk.next += 1;
+
+            next
+        } else {
+            self.reserve(1)
+        }
+    }
+
+    /// Reserves `len` numbers from the shared counter, returning the first of them
+    #[inline]
+    pub fn reserve(&self, len: u64) -> u64 {
+        // Uniqueness only needs the read-modify-write to be atomic, not any ordering with other
+        // memory
+        self.shared.fetch_add(len, Ordering::Relaxed)
+    }
+}
+impl Default for ChunkedCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Debug for ChunkedCounter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChunkedCounter")
+            .field("reserved", &self.shared.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+struct Chunk {
+    next: u64,
+    end: u64,
+}
+
+/// Returns a number identifying the current thread, which unlike the address of a thread local, is
+/// never reused by a later thread
+#[inline]
+fn thread_id() -> usize {
+    static NEXT_THREAD_ID: AtomicUsize = AtomicUsize::new(1);
+
+    thread_local! {
+        static THREAD_ID: usize = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+    }
+
+    THREAD_ID.with(|thread_id| *thread_id)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn owner_hands_out_consecutive_numbers_across_chunks() {
+        let counter = ChunkedCounter::new();
+
+        for expected in 0..(2 * CHUNK_LEN + 1) {
+            assert_eq!(counter.next(), expected);
+        }
+        assert_eq!(counter.reserve(0), 3 * CHUNK_LEN);
+    }
+
+    #[test]
+    fn other_threads_take_numbers_after_owners_chunk() {
+        let counter = Arc::new(ChunkedCounter::new());
+        assert_eq!(counter.next(), 0);
+
+        let other_counter = counter.clone();
+        let other = thread::spawn(move || (other_counter.next(), other_counter.next()))
+            .join()
+            .unwrap();
+
+        assert_eq!(other, (CHUNK_LEN, CHUNK_LEN + 1));
+        assert_eq!(counter.next(), 1);
+    }
+
+    #[test]
+    fn numbers_are_unique_while_another_thread_reserves_chunks() {
+        const LEN: usize = 10_000_000;
+        const OWNER_LEN: usize = 8_000_000;
+
+        let counter = Arc::new(ChunkedCounter::new());
+
+        let other_counter = counter.clone();
+        let other = thread::spawn(move || {
+            let mut numbers = Vec::with_capacity(LEN - OWNER_LEN);
+
+            while numbers.len() < LEN - OWNER_LEN {
+                // Interleave single numbers with whole chunks, so that the owner's refills race
+                // with both
+                numbers.push(other_counter.next());
+
+                if numbers.len() % 1_000 == 0 {
+                    let start = other_counter.reserve(CHUNK_LEN);
+                    let remaining = (LEN - OWNER_LEN - numbers.len()).min(256);
+                    numbers.extend(start..start + remaining as u64);
+                }
+            }
+
+            numbers
+        });
+
+        let mut numbers = Vec::with_capacity(LEN);
+        while numbers.len() < OWNER_LEN {
+            numbers.push(counter.next());
+        }
+        numbers.extend(other.join().unwrap());
+        assert_eq!(numbers.len(), LEN);
+
+        numbers.sort_unstable();
+        numbers.dedup();
+
+        assert_eq!(numbers.len(), LEN);
+    }
+
+    /// Compares `next` on the owning thread to the `SeqCst` `fetch_add` it replaces. Run with
+    /// `cargo test --release -p lumen_rt_core -- --ignored --nocapture next_throughput`
+    #[test]
+    #[ignore]
+    fn next_throughput() {
+        const LEN: u64 = 100_000_000;
+
+        let counter = ChunkedCounter::new();
+        let start = Instant::now();
+        let mut sum = 0u64;
+        for _ in 0..LEN {
+            sum = sum.wrapping_add(counter.next());
+        }
+        let chunked = start.elapsed();
+
+        let atomic = AtomicU64::new(0);
+        let start = Instant::now();
+        for _ in 0..LEN {
+            sum = sum.wrapping_add(atomic.fetch_add(1, Ordering::SeqCst));
+        }
+        let seq_cst = start.elapsed();
+
+        println!(
+            "{} numbers: chunked {:?} ({:.2} ns each), SeqCst fetch_add {:?} ({:.2} ns each), checksum {}",
+            LEN,
+            chunked,
+            chunked.as_nanos() as f64 / LEN as f64,
+            seq_cst,
+            seq_cst.as_nanos() as f64 / LEN as f64,
+            sum
+        );
+    }
+}
@@ -6,12 +6,23 @@ use std::hash::Hash;
 use std::sync::Arc;
 
 use liblumen_alloc::erts::process::{Priority, Process, Status};
+use liblumen_alloc::erts::term::prelude::Pid;
 
 use crate::scheduler::Run;
 
+/// The run queues of a scheduler, which are selected from as in BEAM: `Priority::Max` processes
+/// always run before `Priority::High`, which run before `Priority::Normal` and `Priority::Low`.
+///
+/// Normal and low priority processes share a queue, where a low priority process is skipped the
+/// first 7 times it reaches the front, so it only runs every 8th time, but is never starved by
+/// normal priority processes.
+///
+/// Suspended processes are parked apart from the run queues when they would otherwise be pushed
+/// back, until they are resumed.
 #[derive(Debug, Default)]
 pub struct Queues {
     waiting: Waiting,
+    suspended: Waiting,
     normal_low: Delayed,
     high: Immediate,
     max: Immediate,
@@ -19,6 +30,7 @@ pub struct Queues {
 impl Queues {
     pub fn contains(&self, value: &Arc<Process>) -> bool {
         self.waiting.contains(value)
+            || self.suspended.contains(value)
             || self.normal_low.contains(value)
             || self.high.contains(value)
             || self.max.contains(value)
@@ -26,7 +38,7 @@ impl Queues {
 
     pub fn run_queue_len(&self, priority: Priority) -> usize {
         match priority {
-            Priority::Low | Priority::Normal => self.normal_low.len(),
+            Priority::Low | Priority::Normal => self.normal_low.priority_len(priority),
             Priority::High => self.high.len(),
             Priority::Max => self.max.len(),
         }
@@ -39,7 +51,7 @@ impl Queues {
             self.high.dequeue()
         } else if 0 < self.normal_low.len() {
             self.normal_low.dequeue()
-        } else if 0 < self.waiting.len() {
+        } else if 0 < self.waiting.len() || 0 < self.suspended.len() {
             Run::Waiting
         } else {
             Run::None
@@ -47,7 +59,7 @@ impl Queues {
     }
 
     pub fn enqueue(&mut self, arc_process: Arc<Process>) {
-        match arc_process.priority {
+        match arc_process.priority() {
             Priority::Low | Priority::Normal => self.normal_low.enqueue(arc_process),
             Priority::High => self.high.enqueue(arc_process),
             Priority::Max => self.max.enqueue(arc_process),
@@ -55,10 +67,16 @@ impl Queues {
     }
 
     pub fn len(&self) -> usize {
-        self.waiting.len() + self.normal_low.len() + self.high.len() + self.max.len()
+        self.waiting.len()
+            + self.suspended.len()
+            + self.normal_low.len()
+            + self.high.len()
+            + self.max.len()
     }
 
-    /// Returns the process is not pushed back because it is exiting
+    /// Returns the process is not pushed back because it is exiting.
+    ///
+    /// A runnable process that is suspended is parked until `resume`.
     #[must_use]
     pub fn requeue(&mut self, arc_process: Arc<Process>) -> Option<Arc<Process>> {
         let next = Next::from_status(&arc_process.status.read());
@@ -69,6 +87,10 @@ impl Queues {
                 self.waiting.insert(arc_process);
                 None
             }
+            Next::PushBack if arc_process.is_suspended() => {
+                self.suspended.insert(arc_process);
+                None
+            }
             Next::PushBack => {
                 self.enqueue(arc_process);
                 None
@@ -77,15 +99,70 @@ impl Queues {
         }
     }
 
-    pub fn stop_waiting(&mut self, process: &Process) {
-        match self.waiting.get(process) {
-            Some(arc_process) => {
+    /// Puts `process` back in the run queues if it was parked by `requeue` while suspended, and
+    /// is no longer suspended.
+    pub fn resume(&mut self, process: &Process) {
+        match self.suspended.get(process) {
+            Some(arc_process) if !arc_process.is_suspended() => {
                 let arc_process = Arc::clone(arc_process);
-                self.waiting.remove(&arc_process);
+                self.suspended.remove(&arc_process);
+
+                self.enqueue(arc_process);
+            }
+            _ => (),
+        }
+    }
 
+    /// Sets the priority of `process`, returning its previous priority, and moves it to the back
+    /// of the run queue for its new priority if it is in one.
+    ///
+    /// A process that is running, waiting or suspended is enqueued for its new priority when it is
+    /// next runnable.
+    pub fn set_priority(&mut self, process: &Process, priority: Priority) -> Priority {
+        match self.remove(process.pid()) {
+            Some(arc_process) => {
+                let old_priority = arc_process.set_priority(priority);
                 self.enqueue(arc_process);
+
+                old_priority
             }
-            None => (),
+            None => process.set_priority(priority),
+        }
+    }
+
+    /// Removes the process with `pid` from the run queues, if it is in one.  Waiting and suspended
+    /// processes aren't in a run queue, so aren't removed.
+    pub fn remove(&mut self, pid: Pid) -> Option<Arc<Process>> {
+        self.max
+            .remove(pid)
+            .or_else(|| self.high.remove(pid))
+            .or_else(|| self.normal_low.remove(pid))
+    }
+
+    /// Takes half, rounded up, of the `Priority::Normal` processes for another scheduler to run.
+    ///
+    /// Only normal priority processes are stolen: `Priority::Low` processes would lose their
+    /// delay and `Priority::High` and `Priority::Max` are expected to be few enough that moving
+    /// them isn't worth the latency.  Waiting processes stay so `stop_waiting` can find them.
+    pub fn steal(&mut self) -> Vec<Arc<Process>> {
+        self.normal_low.steal_half(Priority::Normal)
+    }
+
+    /// Puts `process` in the run queues if it is waiting or suspended, so that its scheduler
+    /// handles the message or signal sent to it.  A suspended process is only run to handle its
+    /// signals, which may exit it, and is parked again by `requeue` otherwise.
+    pub fn stop_waiting(&mut self, process: &Process) {
+        let option_arc_process = self
+            .waiting
+            .get(process)
+            .or_else(|| self.suspended.get(process))
+            .cloned();
+
+        if let Some(arc_process) = option_arc_process {
+            self.waiting.remove(&arc_process);
+            self.suspended.remove(&arc_process);
+
+            self.enqueue(arc_process);
         }
     }
 }
@@ -104,7 +181,7 @@ impl Next {
             Status::Runnable => Next::PushBack,
             Status::Waiting => Next::Wait,
             Status::Exited | Status::RuntimeException(_) => Next::Exit,
-            Status::Running => {
+            Status::Running | Status::MaybeWaiting => {
                 unreachable!("Process.stop_running() should have been called before this")
             }
             Status::Unrunnable => {
@@ -179,6 +256,15 @@ impl Immediate {
     pub fn enqueue(&mut self, process: Arc<Process>) {
         self.0.push_back(process);
     }
+
+    pub fn remove(&mut self, pid: Pid) -> Option<Arc<Process>> {
+        let index = self
+            .0
+            .iter()
+            .position(|arc_process| arc_process.pid() == pid)?;
+
+        self.0.remove(index)
+    }
 }
 
 /// A run queue where the `Arc<Process` is run only when its delay is `0`.  This allows
@@ -198,6 +284,14 @@ impl Delayed {
         self.0.len()
     }
 
+    /// Returns the number of processes with `priority`, as they share this queue
+    pub fn priority_len(&self, priority: Priority) -> usize {
+        self.0
+            .iter()
+            .filter(|delayed_process| delayed_process.arc_process.priority() == priority)
+            .count()
+    }
+
     pub fn dequeue(&mut self) -> Run {
         match self.0.pop_front() {
             Some(mut delayed_process) => {
@@ -218,6 +312,35 @@ impl Delayed {
         let delayed_process = DelayedProcess::new(arc_process);
         self.0.push_back(delayed_process);
     }
+
+    pub fn remove(&mut self, pid: Pid) -> Option<Arc<Process>> {
+        let index = self
+            .0
+            .iter()
+            .position(|delayed_process| delayed_process.arc_process.pid() == pid)?;
+
+        self.0
+            .remove(index)
+            .map(|delayed_process| delayed_process.arc_process)
+    }
+
+    /// Removes half, rounded up, of the processes with `priority`, taking them from the back of
+    /// the queue as those would have run last.
+    pub fn steal_half(&mut self, priority: Priority) -> Vec<Arc<Process>> {
+        let count = (self.priority_len(priority) + 1) / 2;
+        let mut stolen = Vec::with_capacity(count);
+        let mut index = self.0.len();
+
+        while stolen.len() < count {
+            index -= 1;
+
+            if self.0[index].arc_process.priority() == priority {
+                stolen.push(self.0.remove(index).unwrap().arc_process);
+            }
+        }
+
+        stolen
+    }
 }
 
 type Delay = u8;
@@ -231,7 +354,7 @@ struct DelayedProcess {
 impl DelayedProcess {
     fn new(arc_process: Arc<Process>) -> DelayedProcess {
         DelayedProcess {
-            delay: Self::priority_to_delay(arc_process.priority),
+            delay: Self::priority_to_delay(arc_process.priority()),
             arc_process,
         }
     }
@@ -246,3 +369,343 @@ impl DelayedProcess {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use liblumen_alloc::erts::process::alloc;
+    use liblumen_alloc::erts::term::prelude::Atom;
+    use liblumen_alloc::erts::ModuleFunctionArity;
+
+    #[test]
+    fn dequeue_runs_max_then_high_then_normal_and_low() {
+        let mut queues = Queues::default();
+        let low = process(Priority::Low);
+        let normal = process(Priority::Normal);
+        let high = process(Priority::High);
+        let max = process(Priority::Max);
+
+        for arc_process in [&low, &normal, &high, &max] {
+            queues.enqueue(arc_process.clone());
+        }
+
+        assert_eq!(run(&mut queues, 2, false), vec![max.clone(), high.clone()]);
+        // `low` has to be skipped before `normal` behind it can run
+        assert!(matches!(queues.dequeue(), Run::Delayed));
+        assert_eq!(run(&mut queues, 1, false), vec![normal.clone()]);
+    }
+
+    #[test]
+    fn max_and_high_starve_normal() {
+        let mut queues = Queues::default();
+        let normal = process(Priority::Normal);
+        let high = process(Priority::High);
+        let max = process(Priority::Max);
+
+        for arc_process in [&normal, &high, &max] {
+            queues.enqueue(arc_process.clone());
+        }
+
+        assert_eq!(run(&mut queues, 3, true), vec![max.clone(); 3]);
+    }
+
+    #[test]
+    fn low_runs_once_for_every_eight_normal_runs() {
+        let mut queues = Queues::default();
+        let normal = process(Priority::Normal);
+        let low = process(Priority::Low);
+
+        queues.enqueue(normal.clone());
+        queues.enqueue(low.clone());
+
+        let mut expected = Vec::new();
+        for _ in 0..3 {
+            expected.extend(vec![normal.clone(); 8]);
+            expected.push(low.clone());
+        }
+
+        assert_eq!(run(&mut queues, expected.len(), true), expected);
+    }
+
+    #[test]
+    fn steal_takes_half_rounded_up_of_normal_priority_from_the_back() {
+        let mut queues = Queues::default();
+        let normal: Vec<_> = (0..5).map(|_| process(Priority::Normal)).collect();
+        let low = process(Priority::Low);
+        let high = process(Priority::High);
+
+        queues.enqueue(normal[0].clone());
+        queues.enqueue(low.clone());
+        for arc_process in &normal[1..] {
+            queues.enqueue(arc_process.clone());
+        }
+        queues.enqueue(high.clone());
+
+        let stolen = queues.steal();
+
+        assert_eq!(
+            stolen,
+            vec![normal[4].clone(), normal[3].clone(), normal[2].clone()]
+        );
+        assert_eq!(queues.run_queue_len(Priority::Normal), 2);
+        assert_eq!(queues.run_queue_len(Priority::Low), 1);
+        assert_eq!(queues.run_queue_len(Priority::High), 1);
+        assert!(queues.contains(&normal[0]));
+        assert!(queues.contains(&normal[1]));
+    }
+
+    #[test]
+    fn steal_from_queues_without_normal_priority_takes_nothing() {
+        let mut queues = Queues::default();
+        queues.enqueue(process(Priority::Low));
+        queues.enqueue(process(Priority::Max));
+
+        assert!(queues.steal().is_empty());
+        assert_eq!(queues.len(), 2);
+    }
+
+    #[test]
+    fn requeue_parks_suspended_process_until_resumed() {
+        let mut queues = Queues::default();
+        let normal = process(Priority::Normal);
+        queues.enqueue(normal.clone());
+
+        normal.suspend();
+        assert_eq!(run(&mut queues, 1, true), vec![normal.clone()]);
+        assert!(matches!(queues.dequeue(), Run::Waiting));
+        assert!(queues.contains(&normal));
+
+        // Still suspended
+        queues.resume(&normal);
+        assert!(matches!(queues.dequeue(), Run::Waiting));
+
+        assert_eq!(normal.resume(), Some(0));
+        queues.resume(&normal);
+        assert_eq!(run(&mut queues, 1, true), vec![normal]);
+    }
+
+    #[test]
+    fn stop_waiting_dequeues_suspended_process_so_its_signals_are_handled() {
+        let mut queues = Queues::default();
+        let normal = process(Priority::Normal);
+        queues.enqueue(normal.clone());
+
+        normal.suspend();
+        assert_eq!(run(&mut queues, 1, true), vec![normal.clone()]);
+
+        queues.stop_waiting(&normal);
+        assert_eq!(run(&mut queues, 1, true), vec![normal.clone()]);
+        assert!(matches!(queues.dequeue(), Run::Waiting));
+        assert_eq!(queues.len(), 1);
+    }
+
+    #[test]
+    fn set_priority_of_queued_process_to_max_runs_it_next() {
+        let mut queues = Queues::default();
+        let normal = process(Priority::Normal);
+        let high = process(Priority::High);
+        let raised = process(Priority::Normal);
+
+        for arc_process in [&normal, &high, &raised] {
+            queues.enqueue(arc_process.clone());
+        }
+
+        assert_eq!(
+            queues.set_priority(&raised, Priority::Max),
+            Priority::Normal
+        );
+        assert_eq!(raised.priority(), Priority::Max);
+        assert_eq!(queues.run_queue_len(Priority::Normal), 1);
+        assert_eq!(queues.run_queue_len(Priority::Max), 1);
+
+        assert_eq!(run(&mut queues, 3, false), vec![raised, high, normal]);
+    }
+
+    #[test]
+    fn set_priority_of_running_process_takes_effect_when_it_yields() {
+        let mut queues = Queues::default();
+        let running = process(Priority::High);
+        let normal = process(Priority::Normal);
+
+        queues.enqueue(running.clone());
+        queues.enqueue(normal.clone());
+
+        let ran = run(&mut queues, 1, false);
+        assert_eq!(ran, vec![running.clone()]);
+
+        // While running it isn't in a run queue, so only its priority changes
+        assert_eq!(queues.set_priority(&running, Priority::Low), Priority::High);
+        assert_eq!(queues.len(), 1);
+
+        // Yields
+        assert!(queues.requeue(running.clone()).is_none());
+        assert_eq!(queues.run_queue_len(Priority::Low), 1);
+        assert_eq!(run(&mut queues, 1, false), vec![normal]);
+        // Now `running` is low priority, it is skipped before it runs
+        assert!(matches!(queues.dequeue(), Run::Delayed));
+    }
+
+    #[test]
+    fn remove_takes_process_out_of_its_run_queue() {
+        let mut queues = Queues::default();
+        let low = process(Priority::Low);
+        let max = process(Priority::Max);
+
+        queues.enqueue(low.clone());
+        queues.enqueue(max.clone());
+
+        assert_eq!(queues.remove(low.pid()), Some(low.clone()));
+        assert_eq!(queues.remove(low.pid()), None);
+        assert_eq!(queues.remove(max.pid()), Some(max));
+        assert_eq!(queues.len(), 0);
+    }
+
+    #[test]
+    fn wakeups_between_beginning_to_wait_and_being_parked_are_not_lost() {
+        const ROUNDS: usize = 5_000;
+
+        let ping = process(Priority::Normal);
+        let pong = process(Priority::Normal);
+        let ping_queues = Arc::new(Mutex::new(Queues::default()));
+        let pong_queues = Arc::new(Mutex::new(Queues::default()));
+        ping_queues.lock().unwrap().enqueue(ping.clone());
+        pong_queues.lock().unwrap().enqueue(pong.clone());
+
+        // `ping` starts the exchange, and each process replies to every message it receives, until
+        // `ping` has received `ROUNDS` replies
+        ping.send_from_other(Atom::str_to_term("pong"));
+
+        let schedulers = [
+            (
+                ping.clone(),
+                ping_queues.clone(),
+                pong.clone(),
+                pong_queues.clone(),
+            ),
+            (pong, pong_queues, ping, ping_queues),
+        ]
+        .map(|(process, queues, other, other_queues)| {
+            thread::spawn(move || {
+                schedule_ping_pong(&process, &queues, &other, &other_queues, ROUNDS)
+            })
+        });
+
+        for scheduler in schedulers {
+            assert_eq!(scheduler.join().unwrap(), ROUNDS);
+        }
+    }
+
+    /// Runs `process`, on a scheduler with `queues`, as a process that replies to each message it
+    /// receives by sending one to `other`, on another scheduler with `other_queues`, until it has
+    /// received `rounds` messages, waiting when its mailbox has no message it hasn't received.
+    ///
+    /// Returns the number of messages received, or panics if `process` is left waiting while a
+    /// message is in its mailbox, as it never would be woken.
+    fn schedule_ping_pong(
+        process: &Arc<Process>,
+        queues: &Mutex<Queues>,
+        other: &Process,
+        other_queues: &Mutex<Queues>,
+        rounds: usize,
+    ) -> usize {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let mut received = 0;
+
+        while received < rounds {
+            let run = queues.lock().unwrap().dequeue();
+
+            match run {
+                Run::Now(arc_process) => {
+                    assert!(Arc::ptr_eq(&arc_process, process));
+                    *arc_process.status.write() = Status::Running;
+
+                    // As a receive does, with the mailbox locked from the check until waiting
+                    let replying = {
+                        let mailbox_guard = arc_process.mailbox.lock();
+                        let mailbox = RefCell::borrow(&mailbox_guard);
+
+                        if received < mailbox.len() {
+                            received += 1;
+                            true
+                        } else {
+                            arc_process.wait();
+                            false
+                        }
+                    };
+
+                    if replying {
+                        // As sending does, on this thread, while the other process may be running
+                        other.send_from_other(Atom::str_to_term("ping"));
+                        other.stop_waiting();
+                        other_queues.lock().unwrap().stop_waiting(other);
+                    }
+
+                    arc_process.descheduled();
+                    assert!(queues.lock().unwrap().requeue(arc_process).is_none());
+                }
+                Run::Delayed => continue,
+                Run::Waiting | Run::None => {
+                    assert!(
+                        Instant::now() < deadline,
+                        "{} is waiting after receiving {} of {} messages, with {} in its mailbox",
+                        process,
+                        received,
+                        rounds,
+                        RefCell::borrow(&process.mailbox.lock()).len()
+                    );
+                    thread::yield_now();
+                }
+            }
+        }
+
+        received
+    }
+
+    /// Runs `count` processes from `queues` as a scheduler would, skipping delayed processes and
+    /// requeuing each process run if `requeue`, returning the processes in the order they ran
+    fn run(queues: &mut Queues, count: usize, requeue: bool) -> Vec<Arc<Process>> {
+        let mut ran = Vec::with_capacity(count);
+
+        while ran.len() < count {
+            match queues.dequeue() {
+                Run::Now(arc_process) => {
+                    ran.push(arc_process.clone());
+
+                    if requeue {
+                        assert!(queues.requeue(arc_process).is_none());
+                    }
+                }
+                Run::Delayed => continue,
+                Run::Waiting | Run::None => panic!("ran out of processes after {:?}", ran),
+            }
+        }
+
+        ran
+    }
+
+    fn process(priority: Priority) -> Arc<Process> {
+        let (heap, heap_size) = alloc::default_heap().unwrap();
+
+        let process = Process::new(
+            priority,
+            None,
+            ModuleFunctionArity {
+                module: Atom::from_str("test"),
+                function: Atom::from_str("queued"),
+                arity: 0,
+            },
+            heap,
+            heap_size,
+        );
+        // As if spawned, so that it can be requeued
+        *process.status.write() = Status::Runnable;
+
+        Arc::new(process)
+    }
+}
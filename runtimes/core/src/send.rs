@@ -9,6 +9,7 @@ use liblumen_alloc::term::prelude::*;
 use liblumen_alloc::Process;
 
 use crate::distribution::nodes::node;
+use crate::distribution::transport;
 use crate::registry::{self, pid_to_process};
 use crate::scheduler::Scheduled;
 
@@ -39,15 +40,9 @@ pub fn send(
 
                 match node_atom.name() {
                     node::DEAD_ATOM_NAME => send_to_name(name_atom, message, options, process),
-                    _ => {
-                        if !options.connect {
-                            Ok(Sent::ConnectRequired)
-                        } else if !options.suspend {
-                            Ok(Sent::SuspendRequired)
-                        } else {
-                            unimplemented!("distribution")
-                        }
-                    }
+                    _ => Ok(send_to_node(options, || {
+                        transport::send_to_name(name_atom, node_atom, message, process)
+                    })),
                 }
             } else {
                 Err(anyhow!("destination ({}) is a tuple, but not 2-arity", destination).into())
@@ -73,6 +68,9 @@ pub fn send(
                 }
             }
         }
+        TypedTerm::ExternalPid(external_pid) => Ok(send_to_node(options, || {
+            transport::send_to_pid(destination, &external_pid, message, process)
+        })),
         _ => Err(TypeError)
             .context(format!(
                 "destination ({}) is not registered_name (atom), {{registered_name, node}}, or pid",
@@ -90,6 +88,20 @@ pub enum Sent {
 
 // Private
 
+// Sends to other nodes are dropped when there is no transport, like sends to local processes that
+// are not alive.
+fn send_to_node<F: FnOnce()>(options: Options, send: F) -> Sent {
+    if !options.connect {
+        Sent::ConnectRequired
+    } else if !options.suspend {
+        Sent::SuspendRequired
+    } else {
+        send();
+
+        Sent::Sent
+    }
+}
+
 // `options` will only be used once ports are supported
 fn send_to_name(
     destination: Atom,
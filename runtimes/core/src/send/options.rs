@@ -10,7 +10,7 @@ pub struct Options {
     // Send only suspends for some sends to ports and for remote (`ExternalPid` or
     // `{name, remote_node}`) sends, so it does not apply at this time.
     pub suspend: bool,
-    // Connect only applies when there is distribution, which isn't implemented yet.
+    // Connect only applies to sends to other nodes, which need a distribution transport.
     pub connect: bool,
 }
 
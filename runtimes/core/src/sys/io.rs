@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
@@ -11,9 +13,34 @@ extern "C" {
 #[cfg(not(target_arch = "wasm32"))]
 pub fn puts(s: &str) {
     println!("{}", s);
+    record_output(s.len() + 1);
 }
 
 #[cfg(target_arch = "wasm32")]
 pub fn puts(s: &str) {
     console_log(s);
+    record_output(s.len() + 1);
+}
+
+/// The bytes read from files, the console and sockets since the system started
+static INPUT_BYTES: AtomicU64 = AtomicU64::new(0);
+/// The bytes written to files, the console and sockets since the system started
+static OUTPUT_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Counts `bytes` read from a file, the console or a socket
+pub fn record_input(bytes: usize) {
+    INPUT_BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+/// Counts `bytes` written to a file, the console or a socket
+pub fn record_output(bytes: usize) {
+    OUTPUT_BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+/// Returns the bytes input and output since the system started, as in `erlang:statistics(io)`
+pub fn counters() -> (u64, u64) {
+    (
+        INPUT_BYTES.load(Ordering::Relaxed),
+        OUTPUT_BYTES.load(Ordering::Relaxed),
+    )
 }
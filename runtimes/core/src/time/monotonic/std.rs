@@ -17,6 +17,11 @@ pub fn freeze_at(monotonic: Monotonic) {
     FROZEN.with(|frozen| *frozen.borrow_mut() = Some(monotonic));
 }
 
+/// Starts the clock, if it hasn't been started yet, so that times are relative to now
+pub fn start() {
+    lazy_static::initialize(&START);
+}
+
 pub fn time() -> Monotonic {
     FROZEN.with(|frozen| {
         frozen
@@ -1,5 +1,8 @@
 use super::Monotonic;
 
+/// The clock starts when the page is loaded, so there is nothing to do
+pub fn start() {}
+
 pub fn time() -> Monotonic {
     let window = web_sys::window().expect("should have a window in this context");
     let performance = window
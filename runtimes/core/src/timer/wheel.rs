@@ -0,0 +1,584 @@
+//! A hierarchical timing wheel, like the one described in
+//! [Hashed and Hierarchical Timing Wheels](http://www.cs.columbia.edu/~nahum/w6998/papers/ton97-timing-wheels.pdf).
+//!
+//! Each of the `LEVELS` levels has `SLOTS` slots, and a slot in a level spans all the slots of
+//! the level below it, so with a tick of 1 millisecond, the wheel covers `HORIZON`, ~49.7 days.
+//! Timers beyond the horizon wait in an overflow map until the wheel comes around to them.
+//!
+//! When the wheel reaches the start of a slot in a level above the first, the timers in that
+//! slot are cascaded down to the levels below, so that timers only ever time out from the first
+//! level, which has a slot per millisecond.
+//!
+//! Timers are stored in a slab, and each slot is a doubly-linked list through the slab, so that
+//! a timer can be removed in O(1) with the `Handle` returned when it was inserted.
+use core::fmt::{self, Debug};
+
+use std::collections::BTreeMap;
+
+use liblumen_alloc::time::{Milliseconds, Monotonic};
+
+/// The number of bits of the time used to index the slots of each level
+const SLOT_BITS: u32 = 8;
+/// The number of slots in each level
+pub const SLOTS: usize = 1 << SLOT_BITS;
+/// The number of levels
+pub const LEVELS: usize = 4;
+/// How far in the future the wheel covers, relative to its time.  Timers further away than this
+/// are kept in the overflow map.
+pub const HORIZON: Milliseconds = Milliseconds(1 << (SLOT_BITS * LEVELS as u32));
+/// The duration of a slot in the first level
+pub const TICK: Milliseconds = Milliseconds(1);
+
+/// Identifies a timer inserted into a `Wheel`.
+///
+/// The generation distinguishes the timer from any later timer which reuses its place in the
+/// slab after it has timed out or been removed, so a stale handle never removes another timer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Handle {
+    index: usize,
+    generation: u32,
+}
+
+pub struct Wheel<T> {
+    /// The next tick to time out.  All earlier ticks have timed out.
+    elapsed: u64,
+    entries: Vec<Entry<T>>,
+    /// The head of the list of vacant entries
+    free: Option<usize>,
+    /// The head of the list for each slot of each level, indexed by `level * SLOTS + slot`
+    slots: Vec<Option<usize>>,
+    /// The head of the list of timers that were already due when they were inserted
+    expired: Option<usize>,
+    /// The head of the list of timers due at each time beyond the horizon
+    overflow: BTreeMap<u64, usize>,
+    len: usize,
+    /// Orders timers due at the same time by when they were inserted
+    next_sequence: u64,
+}
+
+impl<T> Wheel<T> {
+    /// Creates a wheel whose time is `now`, so that timers due before `now` time out on the next
+    /// call to `advance`.
+    pub fn new(now: Monotonic) -> Self {
+        Self {
+            elapsed: now.0,
+            entries: Vec::new(),
+            free: None,
+            slots: vec![None; LEVELS * SLOTS],
+            expired: None,
+            overflow: BTreeMap::new(),
+            len: 0,
+            next_sequence: 0,
+        }
+    }
+
+    /// The number of timers that have neither timed out nor been removed
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The time up to which timers have timed out
+    pub fn elapsed(&self) -> Monotonic {
+        Monotonic(self.elapsed)
+    }
+
+    /// Inserts `value` to time out at `deadline`, returning the handle to remove it
+    pub fn insert(&mut self, deadline: Monotonic, value: T) -> Handle {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        let occupied = Occupied {
+            deadline: deadline.0,
+            sequence,
+            location: Location::Expired,
+            prev: None,
+            next: None,
+            value,
+        };
+
+        let index = match self.free {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                self.free = match entry.state {
+                    State::Vacant { next_free } => next_free,
+                    State::Occupied(_) => unreachable!("free list contains occupied entry"),
+                };
+                entry.state = State::Occupied(occupied);
+
+                index
+            }
+            None => {
+                self.entries.push(Entry {
+                    generation: 0,
+                    state: State::Occupied(occupied),
+                });
+
+                self.entries.len() - 1
+            }
+        };
+
+        self.link(index);
+        self.len += 1;
+
+        Handle {
+            index,
+            generation: self.entries[index].generation,
+        }
+    }
+
+    /// Returns the value of the timer for `handle`, unless it has timed out or been removed
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        match self.entries.get(handle.index) {
+            Some(Entry {
+                generation,
+                state: State::Occupied(occupied),
+            }) if *generation == handle.generation => Some(&occupied.value),
+            _ => None,
+        }
+    }
+
+    /// Removes the timer for `handle`, returning its value, unless it has timed out or already
+    /// been removed
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        self.get(handle)?;
+        self.unlink(handle.index);
+
+        Some(self.vacate(handle.index))
+    }
+
+    /// Iterates over the deadlines and values of all timers, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Monotonic, &T)> {
+        self.entries.iter().filter_map(|entry| match &entry.state {
+            State::Occupied(occupied) => Some((Monotonic(occupied.deadline), &occupied.value)),
+            State::Vacant { .. } => None,
+        })
+    }
+
+    /// Advances the wheel to `now`, returning the values of the timers that have timed out, in
+    /// the order of their deadlines, and for the same deadline, the order they were inserted.
+    ///
+    /// A timer times out once `now` is past its deadline, or if it was already due when it was
+    /// inserted.  Ticks without timers to time out or cascade are skipped, so advancing far into
+    /// the future costs no more than advancing to the next timer.
+    pub fn advance(&mut self, now: Monotonic) -> Vec<T> {
+        let mut timed_out = Vec::new();
+
+        let expired = self.expired.take();
+        let expired = self.take_list(expired);
+        self.vacate_in_order(expired, &mut timed_out);
+
+        while let Some(tick) = self.next_tick().filter(|tick| *tick < now.0) {
+            self.elapsed = tick;
+
+            if tick % HORIZON.0 == 0 {
+                self.cascade_overflow(tick);
+            }
+
+            for level in (1..LEVELS).rev() {
+                let shift = SLOT_BITS * level as u32;
+
+                if tick % (1 << shift) == 0 {
+                    let slot = Self::slot(tick, level);
+                    let head = self.slots[slot].take();
+                    let cascading = self.take_list(head);
+
+                    for index in cascading {
+                        self.link(index);
+                    }
+                }
+            }
+
+            let slot = Self::slot(tick, 0);
+            let head = self.slots[slot].take();
+            let due = self.take_list(head);
+            self.vacate_in_order(due, &mut timed_out);
+
+            self.elapsed = tick + 1;
+        }
+
+        self.elapsed = self.elapsed.max(now.0);
+
+        timed_out
+    }
+
+    /// The next tick at which a timer times out or is cascaded
+    fn next_tick(&self) -> Option<u64> {
+        let mut next_tick = self
+            .overflow
+            .keys()
+            .next()
+            .map(|deadline| Self::round_down(*deadline, SLOT_BITS * LEVELS as u32));
+
+        for level in 0..LEVELS {
+            let shift = SLOT_BITS * level as u32;
+            let current = ((self.elapsed >> shift) as usize) % SLOTS;
+            let level_start = Self::round_down(self.elapsed, shift + SLOT_BITS);
+
+            if let Some(slot) =
+                (current..SLOTS).find(|slot| self.slots[level * SLOTS + slot].is_some())
+            {
+                let tick = (level_start + ((slot as u64) << shift)).max(self.elapsed);
+
+                next_tick = Some(next_tick.map_or(tick, |next_tick| next_tick.min(tick)));
+            }
+        }
+
+        next_tick
+    }
+
+    /// Moves the timers in the overflow map that are now within the horizon into the levels
+    fn cascade_overflow(&mut self, tick: u64) {
+        let within_horizon: Vec<u64> = self
+            .overflow
+            .range(..tick.saturating_add(HORIZON.0))
+            .map(|(deadline, _)| *deadline)
+            .collect();
+
+        for deadline in within_horizon {
+            let head = self.overflow.remove(&deadline);
+
+            for index in self.take_list(head) {
+                self.link(index);
+            }
+        }
+    }
+
+    /// Adds the timer at `index` to the list for its deadline relative to `elapsed`
+    fn link(&mut self, index: usize) {
+        let deadline = self.occupied(index).deadline;
+        let location = self.location(deadline);
+        let head = self.head(location, deadline);
+
+        {
+            let occupied = self.occupied_mut(index);
+            occupied.location = location;
+            occupied.prev = None;
+            occupied.next = head;
+        }
+
+        if let Some(head) = head {
+            self.occupied_mut(head).prev = Some(index);
+        }
+
+        self.set_head(location, deadline, Some(index));
+    }
+
+    /// Removes the timer at `index` from the list it is in
+    fn unlink(&mut self, index: usize) {
+        let Occupied {
+            deadline,
+            location,
+            prev,
+            next,
+            ..
+        } = *self.occupied(index);
+
+        match prev {
+            Some(prev) => self.occupied_mut(prev).next = next,
+            None => self.set_head(location, deadline, next),
+        }
+
+        if let Some(next) = next {
+            self.occupied_mut(next).prev = prev;
+        }
+    }
+
+    /// Returns the indices of the timers in the list starting at `head`, which must already have
+    /// been detached from the wheel
+    fn take_list(&self, head: Option<usize>) -> Vec<usize> {
+        let mut indices = Vec::new();
+        let mut next = head;
+
+        while let Some(index) = next {
+            indices.push(index);
+            next = self.occupied(index).next;
+        }
+
+        indices
+    }
+
+    fn vacate_in_order(&mut self, mut indices: Vec<usize>, timed_out: &mut Vec<T>) {
+        indices.sort_unstable_by_key(|index| {
+            let occupied = self.occupied(*index);
+
+            (occupied.deadline, occupied.sequence)
+        });
+
+        timed_out.extend(indices.into_iter().map(|index| self.vacate(index)));
+    }
+
+    /// Frees the entry at `index`, which must have been unlinked, returning its value
+    fn vacate(&mut self, index: usize) -> T {
+        let entry = &mut self.entries[index];
+        let state = std::mem::replace(
+            &mut entry.state,
+            State::Vacant {
+                next_free: self.free,
+            },
+        );
+        entry.generation = entry.generation.wrapping_add(1);
+        self.free = Some(index);
+        self.len -= 1;
+
+        match state {
+            State::Occupied(occupied) => occupied.value,
+            State::Vacant { .. } => unreachable!("vacated entry was already vacant"),
+        }
+    }
+
+    fn location(&self, deadline: u64) -> Location {
+        if deadline < self.elapsed {
+            return Location::Expired;
+        }
+
+        // The highest bit that differs between the deadline and the time determines the level,
+        // as it is the slot of that level that the time must reach before the deadline is within
+        // a single slot of the level below.
+        let differing = deadline ^ self.elapsed;
+
+        (0..LEVELS)
+            .find(|level| differing < 1 << (SLOT_BITS * (*level as u32 + 1)))
+            .map(|level| Location::Slot(Self::slot(deadline, level)))
+            .unwrap_or(Location::Overflow)
+    }
+
+    fn head(&self, location: Location, deadline: u64) -> Option<usize> {
+        match location {
+            Location::Expired => self.expired,
+            Location::Slot(slot) => self.slots[slot],
+            Location::Overflow => self.overflow.get(&deadline).copied(),
+        }
+    }
+
+    fn set_head(&mut self, location: Location, deadline: u64, head: Option<usize>) {
+        match location {
+            Location::Expired => self.expired = head,
+            Location::Slot(slot) => self.slots[slot] = head,
+            Location::Overflow => match head {
+                Some(head) => {
+                    self.overflow.insert(deadline, head);
+                }
+                None => {
+                    self.overflow.remove(&deadline);
+                }
+            },
+        }
+    }
+
+    fn occupied(&self, index: usize) -> &Occupied<T> {
+        match &self.entries[index].state {
+            State::Occupied(occupied) => occupied,
+            State::Vacant { .. } => unreachable!("linked entry is vacant"),
+        }
+    }
+
+    fn occupied_mut(&mut self, index: usize) -> &mut Occupied<T> {
+        match &mut self.entries[index].state {
+            State::Occupied(occupied) => occupied,
+            State::Vacant { .. } => unreachable!("linked entry is vacant"),
+        }
+    }
+
+    /// The index into `slots` of the slot of `level` for `time`
+    fn slot(time: u64, level: usize) -> usize {
+        let slot = ((time >> (SLOT_BITS * level as u32)) as usize) % SLOTS;
+
+        level * SLOTS + slot
+    }
+
+    fn round_down(time: u64, bits: u32) -> u64 {
+        (time >> bits) << bits
+    }
+}
+
+impl<T: Debug> Debug for Wheel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "  elapsed: {} ms", self.elapsed)?;
+
+        let mut occupied: Vec<&Occupied<T>> = self
+            .entries
+            .iter()
+            .filter_map(|entry| match &entry.state {
+                State::Occupied(occupied) => Some(occupied),
+                State::Vacant { .. } => None,
+            })
+            .collect();
+        occupied.sort_unstable_by_key(|occupied| (occupied.deadline, occupied.sequence));
+
+        if occupied.is_empty() {
+            writeln!(f, "  No timers")?;
+        } else {
+            for occupied in occupied {
+                write!(f, "{:?} ", occupied.value)?;
+
+                match occupied.location {
+                    Location::Expired => writeln!(f, "(already due)")?,
+                    Location::Slot(slot) => {
+                        writeln!(f, "(level {} slot {})", slot / SLOTS, slot % SLOTS)?
+                    }
+                    Location::Overflow => writeln!(f, "(overflow)")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct Entry<T> {
+    generation: u32,
+    state: State<T>,
+}
+
+enum State<T> {
+    Vacant { next_free: Option<usize> },
+    Occupied(Occupied<T>),
+}
+
+struct Occupied<T> {
+    deadline: u64,
+    sequence: u64,
+    location: Location,
+    prev: Option<usize>,
+    next: Option<usize>,
+    value: T,
+}
+
+/// The list a timer is in
+#[derive(Clone, Copy, Debug)]
+enum Location {
+    Expired,
+    /// The index into `Wheel::slots`
+    Slot(usize),
+    Overflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000;
+    const HOUR: u64 = 60 * 60 * SECOND;
+    const DAY: u64 = 24 * HOUR;
+
+    fn at(milliseconds: u64) -> Monotonic {
+        Monotonic(milliseconds)
+    }
+
+    #[test]
+    fn timers_at_every_level_time_out_in_order_of_deadline() {
+        let start = 123_456;
+        let mut wheel = Wheel::new(at(start));
+
+        wheel.insert(at(start + 30 * DAY), "30 days");
+        wheel.insert(at(start + HOUR), "1 hour");
+        wheel.insert(at(start + 1), "1 millisecond");
+        wheel.insert(at(start + 10 * SECOND), "10 seconds");
+        assert_eq!(wheel.len(), 4);
+
+        // Nothing times out until the clock is past the deadline
+        assert!(wheel.advance(at(start + 1)).is_empty());
+        assert_eq!(wheel.advance(at(start + 2)), vec!["1 millisecond"]);
+        assert!(wheel.advance(at(start + 10 * SECOND)).is_empty());
+        assert_eq!(
+            wheel.advance(at(start + 10 * SECOND + 1)),
+            vec!["10 seconds"]
+        );
+        assert_eq!(wheel.advance(at(start + 29 * DAY)), vec!["1 hour"]);
+        assert_eq!(wheel.advance(at(start + 30 * DAY + 1)), vec!["30 days"]);
+
+        assert!(wheel.is_empty());
+        assert_eq!(wheel.elapsed(), at(start + 30 * DAY + 1));
+    }
+
+    #[test]
+    fn advancing_past_several_deadlines_times_them_out_together_in_order() {
+        let start = 7;
+        let mut wheel = Wheel::new(at(start));
+
+        wheel.insert(at(start + 30 * DAY), 4);
+        wheel.insert(at(start + 10 * SECOND), 2);
+        wheel.insert(at(start + HOUR), 3);
+        wheel.insert(at(start + 1), 1);
+        // Same deadline as an earlier timer, so times out after it
+        wheel.insert(at(start + HOUR), 5);
+
+        assert_eq!(wheel.advance(at(start + 31 * DAY)), vec![1, 2, 3, 5, 4]);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn timers_beyond_the_horizon_overflow_until_within_it() {
+        let start = 0;
+        let mut wheel = Wheel::new(at(start));
+
+        let beyond_horizon = start + HORIZON.0 + 30 * DAY;
+        wheel.insert(at(beyond_horizon), "beyond horizon");
+        wheel.insert(at(start + 30 * DAY), "30 days");
+
+        assert_eq!(wheel.advance(at(start + 30 * DAY + 1)), vec!["30 days"]);
+        assert!(wheel.advance(at(beyond_horizon)).is_empty());
+        assert_eq!(
+            wheel.advance(at(beyond_horizon + 1)),
+            vec!["beyond horizon"]
+        );
+    }
+
+    #[test]
+    fn removed_timers_do_not_time_out() {
+        let start = 1_000;
+        let mut wheel = Wheel::new(at(start));
+
+        let millisecond = wheel.insert(at(start + 1), "1 millisecond");
+        let ten_seconds = wheel.insert(at(start + 10 * SECOND), "10 seconds");
+        let hour = wheel.insert(at(start + HOUR), "1 hour");
+        let thirty_days = wheel.insert(at(start + 30 * DAY), "30 days");
+
+        assert_eq!(wheel.remove(ten_seconds), Some("10 seconds"));
+        assert_eq!(wheel.remove(thirty_days), Some("30 days"));
+        assert_eq!(wheel.len(), 2);
+
+        // Removing again finds nothing
+        assert_eq!(wheel.remove(ten_seconds), None);
+
+        // Once cascaded towards its deadline, the timer can still be removed
+        assert_eq!(wheel.advance(at(start + HOUR - 1)), vec!["1 millisecond"]);
+        assert_eq!(wheel.get(hour), Some(&"1 hour"));
+        assert_eq!(wheel.remove(hour), Some("1 hour"));
+
+        assert!(wheel.advance(at(start + 31 * DAY)).is_empty());
+        assert!(wheel.is_empty());
+
+        // A timed out timer can't be removed
+        assert_eq!(wheel.remove(millisecond), None);
+    }
+
+    #[test]
+    fn stale_handles_do_not_remove_timers_reusing_their_entry() {
+        let mut wheel = Wheel::new(at(0));
+
+        let removed = wheel.insert(at(10), "removed");
+        assert_eq!(wheel.remove(removed), Some("removed"));
+
+        let reusing = wheel.insert(at(10), "reusing");
+        assert_eq!(reusing.index, removed.index);
+
+        assert_eq!(wheel.get(removed), None);
+        assert_eq!(wheel.remove(removed), None);
+        assert_eq!(wheel.remove(reusing), Some("reusing"));
+    }
+
+    #[test]
+    fn timers_already_due_time_out_on_the_next_advance() {
+        let mut wheel = Wheel::new(at(100));
+
+        wheel.insert(at(50), "late");
+        wheel.insert(at(10), "later");
+
+        assert_eq!(wheel.advance(at(100)), vec!["later", "late"]);
+    }
+}
@@ -23,7 +23,7 @@ fn main() {
     let sysroot = PathBuf::from(output(&mut sysroot_cmd).trim());
     println!("Found sysroot at {}", sysroot.display());
     // Search through all of the libs bundled with the toolchain for libstd-<hash>.rlib
-    let toolchain_libs = sysroot.join("lib/rustlib").join(target).join("lib");
+    let toolchain_libs = sysroot.join("lib/rustlib").join(&target).join("lib");
     println!("Searching for libstd rlib in {}", toolchain_libs.display());
     let libstd_rlib = toolchain_libs
         .read_dir()
@@ -74,7 +74,7 @@ fn main() {
                 Some(line)
             }
         })
-        .map(postprocess_lang_start_symbol_name)
+        .map(|symbol| postprocess_lang_start_symbol_name(&target, symbol))
         .expect("unable to locate lang_start_symbol in libstd rlib, has it been removed?");
     // Success!
     println!(
@@ -101,15 +101,14 @@ pub fn output(cmd: &mut Command) -> String {
     String::from_utf8(output.stdout).unwrap()
 }
 
-#[cfg(target_os = "macos")]
-fn postprocess_lang_start_symbol_name(lang_start_symbol: &str) -> &str {
-    // Strip off leading `_` when printing symbol name
-    &lang_start_symbol[1..]
-}
-
-#[cfg(not(target_os = "macos"))]
-fn postprocess_lang_start_symbol_name(lang_start_symbol: &str) -> &str {
-    lang_start_symbol
+// NOTE: This is decided by the target, not with `cfg`, which is for the host running this script
+fn postprocess_lang_start_symbol_name<'a>(target: &str, lang_start_symbol: &'a str) -> &'a str {
+    if target.contains("-apple-") {
+        // Strip off leading `_` when printing symbol name
+        &lang_start_symbol[1..]
+    } else {
+        lang_start_symbol
+    }
 }
 
 fn fail(s: &str) -> ! {
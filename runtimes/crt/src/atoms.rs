@@ -1,9 +1,13 @@
-use firefly_rt::term::AtomData;
+use firefly_rt::term::{AtomData, ModuleAtoms};
 
 extern "C-unwind" {
     /// This function is defined in `firefly_alloc::erts::term::atom`
     #[link_name = "__firefly_initialize_atom_table"]
     pub fn init(start: *const AtomData, end: *const AtomData) -> bool;
+
+    /// This function is defined in `firefly_rt::term::atom`
+    #[link_name = "__firefly_initialize_module_atom_tables"]
+    pub fn init_module_tables(start: *const ModuleAtoms, end: *const ModuleAtoms) -> bool;
 }
 
 #[cfg(target_os = "macos")]
@@ -13,6 +17,12 @@ extern "C" {
 
     #[link_name = "\x01section$end$__DATA$__atoms"]
     static ATOMS_END: AtomData;
+
+    #[link_name = "\x01section$start$__DATA$__module_atoms"]
+    static MODULE_ATOMS_START: ModuleAtoms;
+
+    #[link_name = "\x01section$end$__DATA$__module_atoms"]
+    static MODULE_ATOMS_END: ModuleAtoms;
 }
 
 #[cfg(all(unix, not(target_os = "macos")))]
@@ -22,6 +32,12 @@ extern "C" {
 
     #[link_name = "__stop___atoms"]
     static ATOMS_END: AtomData;
+
+    #[link_name = "__start___module_atoms"]
+    static MODULE_ATOMS_START: ModuleAtoms;
+
+    #[link_name = "__stop___module_atoms"]
+    static MODULE_ATOMS_END: ModuleAtoms;
 }
 
 pub(super) fn start() -> *const AtomData {
@@ -31,3 +47,11 @@ pub(super) fn start() -> *const AtomData {
 pub(super) fn end() -> *const AtomData {
     unsafe { &ATOMS_END }
 }
+
+pub(super) fn module_tables_start() -> *const ModuleAtoms {
+    unsafe { &MODULE_ATOMS_START }
+}
+
+pub(super) fn module_tables_end() -> *const ModuleAtoms {
+    unsafe { &MODULE_ATOMS_END }
+}
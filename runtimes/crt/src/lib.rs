@@ -37,6 +37,14 @@ pub fn main_internal() -> i32 {
         return 102;
     }
 
+    // Resolve the literal atoms of each module to the atoms in the table
+    if unsafe {
+        atoms::init_module_tables(atoms::module_tables_start(), atoms::module_tables_end())
+    } == false
+    {
+        return 104;
+    }
+
     // Initialize the dispatch table
     if unsafe { symbols::init(symbols::start(), symbols::end()) } == false {
         return 103;
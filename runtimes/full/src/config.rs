@@ -2,9 +2,15 @@ use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
 use std::fs;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use clap::{App, AppSettings, Arg, SubCommand};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use log::LevelFilter;
+
+use firefly_rt_core::registry::DEFAULT_PROCESS_LIMIT;
+
+use crate::logging::{self, BackendConfig, LogFilter};
+use crate::setting::Setting;
 
 pub type ConfigResult<T> = std::result::Result<T, ConfigError>;
 //TODO: Needs to be HashMap<Atom, HashMap<Atom, Term>>
@@ -22,6 +28,8 @@ pub enum Command {
 #[derive(Debug)]
 pub enum ConfigError {
     FileError(OsString, io::Error),
+    /// The flags, from the command line or an args file, are not valid
+    Invalid(String),
 }
 
 impl std::fmt::Display for ConfigError {
@@ -33,6 +41,7 @@ impl std::fmt::Display for ConfigError {
                 path.to_string_lossy(),
                 err.to_string()
             ),
+            ConfigError::Invalid(ref message) => write!(f, "Invalid configuration: {}", message),
         }
     }
 }
@@ -41,6 +50,7 @@ impl std::error::Error for ConfigError {
     fn cause(&self) -> Option<&dyn std::error::Error> {
         match *self {
             ConfigError::FileError(ref _path, ref err) => Some(err),
+            ConfigError::Invalid(_) => None,
         }
     }
 }
@@ -49,16 +59,143 @@ pub struct Config {
     pub config: AppConfig,
     pub boot: Option<BootScript>,
     pub debug: bool,
+    /// Whether SIGINT opens the interactive break menu, rather than shutting down
+    pub break_menu: bool,
+    /// The backends to start the logger with, in the order records are written to them
+    pub log_backends: Vec<BackendConfig>,
+    /// Which records are logged, which can be changed by reloading
+    pub log_filter: LogFilter,
     pub name: Option<String>,
     pub cookie: Option<String>,
+    /// The maximum number of processes that may exist at once
+    pub process_limit: usize,
     pub command: Command,
     pub extra: Vec<String>,
+    /// Where the configuration was read from, so that it can be read again when reloading
+    source: Source,
+}
+
+/// The settings which were changed or not by `Config::reload`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Reload {
+    /// The settings which changed and now apply
+    pub applied: Vec<&'static str>,
+    /// The settings which changed, but can only be set at startup, so still have their old value
+    pub ignored: Vec<&'static str>,
+}
+
+#[derive(Clone)]
+struct Source {
+    app: String,
+    version: String,
+    argv: Vec<String>,
+}
+impl Source {
+    /// The arguments, with the flags of any args files in place of the `--args_file` flags
+    /// naming them, and emulator flags rewritten as long options.
+    ///
+    /// Args files are read each time, so they can be changed before reloading.
+    fn args(&self) -> ConfigResult<Vec<String>> {
+        let mut expanded = Vec::with_capacity(self.argv.len());
+        let mut args = self.argv.iter();
+
+        while let Some(arg) = args.next() {
+            let path = match arg.as_str() {
+                "--args_file" => args.next().cloned(),
+                "--" => {
+                    expanded.push(arg.clone());
+                    expanded.extend(args.cloned());
+                    break;
+                }
+                _ => arg.strip_prefix("--args_file=").map(str::to_string),
+            };
+            match path {
+                Some(path) => expanded.extend(with_file(
+                    Some(OsStr::new(&path)),
+                    Vec::new(),
+                    load_args_file,
+                )?),
+                None => expanded.push(arg.clone()),
+            }
+        }
+
+        Ok(emulator_flags_to_long(expanded))
+    }
 }
 
 impl Config {
     pub fn from_argv(app: String, version: String, argv: Vec<String>) -> ConfigResult<Config> {
-        let matches = App::new(app)
-            .version(version.as_str())
+        let source = Source { app, version, argv };
+        let args = source.args()?;
+        // Invalid flags at startup print the usage and exit
+        let matches = Self::app(&source).get_matches_from(args);
+
+        Self::from_matches(&matches, source)
+    }
+
+    /// Reads the configuration again from where it was first read, and applies the settings which
+    /// can be changed while the system runs, i.e. the log level and module filters, to `self` and
+    /// `log_filter`.
+    ///
+    /// Each applied change is logged, as is each change to a setting that can only be set at
+    /// startup, which is ignored. If the configuration can't be read or is invalid, nothing is
+    /// changed.
+    pub fn reload(&mut self, log_filter: &Setting<LogFilter>) -> ConfigResult<Reload> {
+        let args = self.source.args()?;
+        let matches = Self::app(&self.source)
+            .get_matches_from_safe(args)
+            .map_err(|err| ConfigError::Invalid(err.message))?;
+        let reloaded = Self::from_matches(&matches, self.source.clone())?;
+
+        Ok(self.apply_reload(reloaded, log_filter))
+    }
+
+    fn apply_reload(&mut self, reloaded: Config, log_filter: &Setting<LogFilter>) -> Reload {
+        let mut reload = Reload::default();
+
+        if reloaded.log_filter.level != self.log_filter.level {
+            reload.applied.push("log_level");
+        }
+        if reloaded.log_filter.modules != self.log_filter.modules {
+            reload.applied.push("log_filter");
+        }
+        if !reload.applied.is_empty() {
+            log::info!(
+                "reloaded log filter {} (was {})",
+                reloaded.log_filter,
+                self.log_filter
+            );
+            logging::set_filter(log_filter, reloaded.log_filter.clone());
+            self.log_filter = reloaded.log_filter;
+        }
+
+        let startup_only = [
+            ("break_menu", reloaded.break_menu != self.break_menu),
+            ("cookie", reloaded.cookie != self.cookie),
+            ("debug", reloaded.debug != self.debug),
+            ("log_backends", reloaded.log_backends != self.log_backends),
+            ("name", reloaded.name != self.name),
+            (
+                "process_limit",
+                reloaded.process_limit != self.process_limit,
+            ),
+        ];
+        for (key, changed) in startup_only {
+            if changed {
+                log::warn!(
+                    "ignoring the reloaded {}, which can only be set at startup",
+                    key
+                );
+                reload.ignored.push(key);
+            }
+        }
+
+        reload
+    }
+
+    fn app(source: &Source) -> App<'_, '_> {
+        App::new(source.app.as_str())
+            .version(source.version.as_str())
             .setting(AppSettings::TrailingVarArg)
             .arg(Arg::with_name("args_file")
                      .long("args_file")
@@ -79,6 +216,54 @@ impl Config {
             .arg(Arg::with_name("debug")
                      .long("debug")
                      .help("Enable debug output from the runtime"))
+            .arg(Arg::with_name("break_menu")
+                     .long("break-menu")
+                     .help("Open the interactive break menu on SIGINT, rather than shutting down"))
+            .arg(Arg::with_name("log_file")
+                     .long("log-file")
+                     .help("Write log records to the given file, rotating it when full")
+                     .takes_value(true))
+            .arg(Arg::with_name("log_max_size")
+                     .long("log-max-size")
+                     .help("The size in bytes at which the log file is rotated")
+                     .takes_value(true)
+                     .default_value("10485760")
+                     .validator(is_valid_number))
+            .arg(Arg::with_name("log_max_files")
+                     .long("log-max-files")
+                     .help("The number of log files to keep, including the one being written")
+                     .takes_value(true)
+                     .default_value("5")
+                     .validator(is_valid_number))
+            .arg(Arg::with_name("log_ring_buffer")
+                     .long("log-ring-buffer")
+                     .help("Keep the given number of recent log records in memory for crash dumps")
+                     .takes_value(true)
+                     .validator(is_valid_number))
+            .arg(Arg::with_name("log_level")
+                     .long("log-level")
+                     .help("Log records at or above the given level: error, warn, info, debug or trace")
+                     .takes_value(true)
+                     .default_value("info")
+                     .validator(is_valid_log_level))
+            .arg(Arg::with_name("verbose")
+                     .short("v")
+                     .long("verbose")
+                     .help("Log records at or above debug level, even if --log-level is higher, including scheduler statistics every second"))
+            .arg(Arg::with_name("log_filter")
+                     .long("log-filter")
+                     .help("Log records from the given module at or above the given level, as MODULE=LEVEL")
+                     .takes_value(true)
+                     .multiple(true)
+                     .number_of_values(1)
+                     .validator(|v| logging::parse_module_filter(&v).map(|_| ())))
+            .arg(Arg::with_name("no_log_stderr")
+                     .long("no-log-stderr")
+                     .help("Do not write log records to stderr"))
+            .arg(Arg::with_name("noshell")
+                     .long("noshell")
+                     .global(true)
+                     .help("Run without an interactive shell, which disables the break menu"))
             .arg(Arg::with_name("name")
                      .long("name")
                      .global(true)
@@ -92,6 +277,11 @@ impl Config {
                      .help("The secret cookie to use in distributed mode")
                      .takes_value(true)
                      .env("COOKIE"))
+            .arg(Arg::with_name("process_limit")
+                     .long("process-limit")
+                     .help("The maximum number of processes that may exist at once, also given as +P")
+                     .takes_value(true)
+                     .validator(is_valid_process_limit))
             .arg(Arg::with_name("extra")
                      .last(true)
                      .multiple(true)
@@ -104,8 +294,9 @@ impl Config {
                             .help("Connects a remote shell to the specified host")
                             .takes_value(true)
                             .validator(is_valid_node_name)))
-            .get_matches_from(argv);
+    }
 
+    fn from_matches(matches: &ArgMatches, source: Source) -> ConfigResult<Config> {
         let command: Command;
         let extra: Vec<&str>;
         if let Some(matches) = matches.subcommand_matches("shell") {
@@ -122,6 +313,9 @@ impl Config {
             };
             command = Command::Run;
         }
+        // Like BEAM, an interactive shell gets the break menu unless -noshell is given
+        let break_menu = !matches.is_present("noshell")
+            && (matches.is_present("break_menu") || matches!(command, Command::Shell));
         Ok(Config {
             config: with_file(
                 matches.value_of_os("config"),
@@ -130,14 +324,109 @@ impl Config {
             )?,
             boot: with_file(matches.value_of_os("boot"), None, load_boot_script)?,
             debug: matches.is_present("debug"),
+            break_menu,
+            log_backends: log_backends(matches),
+            log_filter: log_filter(matches),
             name: matches.value_of("name").map(|v| v.to_string()),
             cookie: matches.value_of("cookie").map(|v| v.to_string()),
+            process_limit: matches
+                .value_of("process_limit")
+                .map_or(DEFAULT_PROCESS_LIMIT, |v| v.parse().unwrap()),
             command,
             extra: extra.iter().map(|v| v.to_string()).collect(),
+            source,
         })
     }
 }
 
+fn log_backends(matches: &ArgMatches) -> Vec<BackendConfig> {
+    let mut backends = Vec::new();
+    if !matches.is_present("no_log_stderr") {
+        backends.push(BackendConfig::Stderr { color: true });
+    }
+    if let Some(path) = matches.value_of_os("log_file") {
+        backends.push(BackendConfig::File {
+            path: PathBuf::from(path),
+            // Both have defaults which pass validation
+            max_size: matches.value_of("log_max_size").unwrap().parse().unwrap(),
+            max_files: matches.value_of("log_max_files").unwrap().parse().unwrap(),
+        });
+    }
+    if let Some(capacity) = matches.value_of("log_ring_buffer") {
+        backends.push(BackendConfig::RingBuffer {
+            capacity: capacity.parse().unwrap(),
+        });
+    }
+    backends
+}
+
+fn log_filter(matches: &ArgMatches) -> LogFilter {
+    // Both pass validation, and the level has a default
+    let level: LevelFilter = matches.value_of("log_level").unwrap().parse().unwrap();
+
+    LogFilter {
+        level: if matches.is_present("verbose") {
+            level.max(LevelFilter::Debug)
+        } else {
+            level
+        },
+        modules: matches
+            .values_of("log_filter")
+            .map_or_else(Vec::new, |filters| {
+                filters
+                    .map(|filter| logging::parse_module_filter(filter).unwrap())
+                    .collect()
+            }),
+    }
+}
+
+fn is_valid_log_level(v: String) -> Result<(), String> {
+    v.parse::<LevelFilter>()
+        .map(|_| ())
+        .map_err(|_| format!("expected error, warn, info, debug or trace, got {}", v))
+}
+
+fn is_valid_number(v: String) -> Result<(), String> {
+    v.parse::<u64>()
+        .map(|_| ())
+        .map_err(|_| format!("expected a non-negative integer, got {}", v))
+}
+
+fn is_valid_process_limit(v: String) -> Result<(), String> {
+    match v.parse::<usize>() {
+        Ok(limit) if (MIN_PROCESS_LIMIT..=MAX_PROCESS_LIMIT).contains(&limit) => Ok(()),
+        _ => Err(format!(
+            "expected an integer from {} to {}, got {}",
+            MIN_PROCESS_LIMIT, MAX_PROCESS_LIMIT, v
+        )),
+    }
+}
+
+// The range BEAM accepts for `+P`
+const MIN_PROCESS_LIMIT: usize = 1024;
+const MAX_PROCESS_LIMIT: usize = 134_217_727;
+
+/// Rewrites BEAM's `+Flag` emulator flags, which `clap` can't parse, as the equivalent long options,
+/// leaving the arguments after `--` as they are
+fn emulator_flags_to_long(argv: Vec<String>) -> Vec<String> {
+    let mut rewritten = Vec::with_capacity(argv.len());
+    let mut args = argv.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "+P" => rewritten.push("--process-limit".to_string()),
+            "--" => {
+                rewritten.push(arg);
+                rewritten.extend(args);
+                break;
+            }
+            _ => rewritten.push(arg),
+        }
+    }
+
+    rewritten
+}
+
 fn is_valid_node_name(_f: String) -> Result<(), String> {
     //TODO: Validate name
     Ok(())
@@ -156,6 +445,17 @@ fn with_file<T>(v: Option<&OsStr>, default: T, fun: fn(String) -> T) -> ConfigRe
     }
 }
 
+/// Splits the contents of a `vm.args` style file into flags, one or more per line, ignoring
+/// comments from `#` to the end of the line
+fn load_args_file(contents: String) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap())
+        .flat_map(str::split_whitespace)
+        .map(str::to_string)
+        .collect()
+}
+
 fn load_app_config(_contents: String) -> AppConfig {
     AppConfig::new()
 }
@@ -163,3 +463,129 @@ fn load_app_config(_contents: String) -> AppConfig {
 fn load_boot_script(_contents: String) -> Option<BootScript> {
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Creates an args file with `contents` unique to this test run
+    fn args_file(name: &str, contents: &str) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "lumen-config-{}-{}-{}.args",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn from_args_file(path: &Path) -> Config {
+        Config::from_argv(
+            "lumen".to_string(),
+            "0.1.0".to_string(),
+            vec![
+                "lumen".to_string(),
+                "--args_file".to_string(),
+                path.to_str().unwrap().to_string(),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn args_files_are_split_into_flags_without_comments() {
+        assert_eq!(
+            load_args_file(
+                "# VM flags\n+P 2048\n--log-level debug # more output\n\n--no-log-stderr\n"
+                    .to_string()
+            ),
+            ["+P", "2048", "--log-level", "debug", "--no-log-stderr"]
+        );
+    }
+
+    #[test]
+    fn flags_are_read_from_args_file() {
+        let path = args_file(
+            "read",
+            "+P 2048\n--log-level warn\n--log-filter firefly_rt_core::timer=trace\n",
+        );
+        let config = from_args_file(&path);
+
+        assert_eq!(config.process_limit, 2048);
+        assert_eq!(
+            config.log_filter,
+            LogFilter {
+                level: LevelFilter::Warn,
+                modules: vec![("firefly_rt_core::timer".to_string(), LevelFilter::Trace)],
+            }
+        );
+    }
+
+    #[test]
+    fn verbose_logs_at_least_debug_level() {
+        let path = args_file("verbose", "--log-level warn\n-v\n");
+        assert_eq!(from_args_file(&path).log_filter.level, LevelFilter::Debug);
+
+        let path = args_file("verbose_trace", "--log-level trace\n--verbose\n");
+        assert_eq!(from_args_file(&path).log_filter.level, LevelFilter::Trace);
+    }
+
+    #[test]
+    fn reload_applies_log_filter_and_ignores_startup_only_settings() {
+        let path = args_file("reload", "+P 2048\n--log-level info\n");
+        let mut config = from_args_file(&path);
+        let log_filter = Setting::new(config.log_filter.clone());
+
+        fs::write(
+            &path,
+            "+P 4096\n--log-level debug\n--log-filter firefly_rt_core::timer=trace\n",
+        )
+        .unwrap();
+        let reload = config.reload(&log_filter).unwrap();
+
+        assert_eq!(
+            reload,
+            Reload {
+                applied: vec!["log_level", "log_filter"],
+                ignored: vec!["process_limit"],
+            }
+        );
+        let expected = LogFilter {
+            level: LevelFilter::Debug,
+            modules: vec![("firefly_rt_core::timer".to_string(), LevelFilter::Trace)],
+        };
+        assert_eq!(*log_filter.load(), expected);
+        assert_eq!(config.log_filter, expected);
+        assert_eq!(config.process_limit, 2048);
+
+        // Reloading unchanged flags changes nothing
+        assert_eq!(config.reload(&log_filter).unwrap(), Reload::default());
+    }
+
+    #[test]
+    fn reload_of_invalid_flags_changes_nothing() {
+        let path = args_file("invalid", "--log-level info\n");
+        let mut config = from_args_file(&path);
+        let log_filter = Setting::new(config.log_filter.clone());
+
+        fs::write(&path, "--log-level loud\n").unwrap();
+        assert!(matches!(
+            config.reload(&log_filter),
+            Err(ConfigError::Invalid(_))
+        ));
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            config.reload(&log_filter),
+            Err(ConfigError::FileError(_, _))
+        ));
+
+        assert_eq!(log_filter.load().level, LevelFilter::Info);
+        assert_eq!(config.log_filter.level, LevelFilter::Info);
+    }
+}
@@ -1,16 +1,23 @@
+mod process_future;
+mod term_view;
+
 use std::convert::TryInto;
 use std::sync::Arc;
 
 use liblumen_core::locks::Mutex;
 
 use liblumen_alloc::erts::exception::{self, AllocResult, Exception};
-use liblumen_alloc::erts::process::{FrameWithArguments, Process, Status};
-use liblumen_alloc::erts::term::prelude::*;
+
+use firefly_rt_core::process::{FrameWithArguments, Process, Status};
+use firefly_rt_core::term::prelude::*;
 
 use crate::process::current_process;
 use crate::process::spawn::Options;
 use crate::scheduler;
 
+pub use process_future::{spawn, FutureError, ProcessFuture};
+pub use term_view::TermView;
+
 pub fn run_until_ready(
     options: Options,
     frames_with_arguments_fn: Box<dyn FnOnce(&Process) -> AllocResult<Vec<FrameWithArguments>>>,
@@ -19,7 +26,7 @@ pub fn run_until_ready(
     assert!(!options.link);
     assert!(!options.monitor);
 
-    let spawned = spawn(options, frames_with_arguments_fn)?;
+    let spawned = spawn_frames_with_arguments(options, frames_with_arguments_fn)?;
 
     spawned.run_until_ready(max_scheduler_runs)
 }
@@ -87,7 +94,9 @@ impl Spawned {
         let scheduler = scheduler::current();
 
         for _ in 0..max_scheduler_runs {
-            assert!(scheduler.run_once());
+            // Nothing may be runnable while the process waits for a timer, which a later run
+            // times out
+            let _ = scheduler.run_once();
 
             if let Future::Ready(ref ready) = *self.arc_mutex_future.lock() {
                 return Ok(ready.clone());
@@ -131,7 +140,7 @@ pub extern "C" fn native(value: Term, future: Term) -> Term {
     value
 }
 
-fn spawn(
+fn spawn_frames_with_arguments(
     _options: Options,
     _frames_with_arguments_fn: Box<dyn FnOnce(&Process) -> AllocResult<Vec<FrameWithArguments>>>,
 ) -> exception::Result<Spawned> {
@@ -0,0 +1,604 @@
+use std::convert::TryInto;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use liblumen_alloc::{Arity, ModuleFunctionArity};
+
+use firefly_rt_core::process::spawn::options::Options;
+use firefly_rt_core::process::spawn::SpawnError;
+use firefly_rt_core::process::{kill, Frame, FrameWithArguments, Native, Process};
+use firefly_rt_core::registry;
+use firefly_rt_core::scheduler::Scheduler as SchedulerTrait;
+use firefly_rt_core::scheduler::MAX_IDLE_SLEEP;
+use firefly_rt_core::term::prelude::*;
+use firefly_rt_core::time::monotonic;
+
+use crate::process::{monitor, out_of_code};
+use crate::scheduler::{self, Scheduler};
+
+use super::TermView;
+
+/// Why a [`ProcessFuture`] did not resolve with the value returned by its process
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum FutureError {
+    #[error("process exited ({0:?}) without returning")]
+    Exited(TermView),
+    #[error("process did not return within {0:?}")]
+    Timeout(Duration),
+    #[error("process was cancelled")]
+    Cancelled,
+}
+
+/// Spawns a process on `scheduler` that applies `module_function_arity` to `arguments`.
+///
+/// The returned [`ProcessFuture`] resolves when the process exits: with the value returned by the
+/// function if it exits normally, or with the exit reason otherwise.
+pub fn spawn(
+    scheduler: Arc<dyn SchedulerTrait>,
+    module_function_arity: ModuleFunctionArity,
+    arguments: Vec<Term>,
+) -> Result<ProcessFuture, SpawnError> {
+    assert_eq!(
+        module_function_arity.arity as usize,
+        arguments.len(),
+        "arity of {} does not match the number of arguments",
+        module_function_arity
+    );
+
+    spawn_frame_with_arguments(scheduler, module_function_arity, |process| {
+        Scheduler::spawn_module_function_arguments_frame_with_arguments(
+            process,
+            module_function_arity.module,
+            module_function_arity.function,
+            arguments,
+        )
+    })
+}
+
+pub struct ProcessFuture {
+    arc_process: Arc<Process>,
+    scheduler: Arc<dyn SchedulerTrait>,
+    shared: Arc<Shared>,
+}
+
+impl ProcessFuture {
+    pub fn process(&self) -> &Arc<Process> {
+        &self.arc_process
+    }
+
+    /// Blocks until the future resolves or `timeout` elapses.
+    ///
+    /// When called on the thread of the process's scheduler, the scheduler is run while waiting,
+    /// as nothing else would run it. On timeout, the process keeps running, so `wait` can be
+    /// called again.
+    pub fn wait(&mut self, timeout: Duration) -> Result<TermView, FutureError> {
+        let deadline = Instant::now() + timeout;
+        let runs_scheduler = scheduler::current().id() == self.scheduler.id();
+
+        loop {
+            let mut state = self.shared.lock();
+
+            if let Some(result) = state.take() {
+                return result;
+            }
+
+            let now = Instant::now();
+
+            if deadline <= now {
+                return Err(FutureError::Timeout(timeout));
+            }
+
+            if runs_scheduler {
+                drop(state);
+
+                // Nothing on the scheduler is runnable, so sleep until its next timer, unless the
+                // process resolves from another thread first
+                if !self.scheduler.run_once() {
+                    let idle = self
+                        .scheduler
+                        .idle()
+                        .timeout(monotonic::time(), MAX_IDLE_SLEEP);
+                    self.shared
+                        .wait_until(self.shared.lock(), (now + idle).min(deadline));
+                }
+            } else {
+                self.shared.wait_until(state, deadline);
+            }
+        }
+    }
+
+    /// Kills the process, as if by `exit(Pid, kill)`, and resolves the future with
+    /// [`FutureError::Cancelled`].
+    ///
+    /// Does nothing if the future has already resolved.
+    pub fn cancel(&self) {
+        if self.shared.resolve(Err(FutureError::Cancelled)) {
+            kill(&self.arc_process);
+        }
+    }
+}
+
+impl Future for ProcessFuture {
+    type Output = Result<TermView, FutureError>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.lock();
+
+        match state.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(context.waker().clone());
+
+                Poll::Pending
+            }
+        }
+    }
+}
+
+// Private
+
+/// Shared between the [`ProcessFuture`], the process, and the observer of the process's exit
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    condvar: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    fn returned(&self, value: TermView) {
+        self.lock().returned = Some(value);
+    }
+
+    fn exited(&self, reason: Term) {
+        let returned = self.lock().returned.take();
+
+        let result = match returned {
+            Some(value) if reason == Atom::str_to_term("normal") => Ok(value),
+            _ => Err(FutureError::Exited(reason.into())),
+        };
+
+        self.resolve(result);
+    }
+
+    /// Returns `false` if the future was already resolved, in which case `result` is dropped
+    fn resolve(&self, result: Result<TermView, FutureError>) -> bool {
+        let mut state = self.lock();
+
+        if state.resolved {
+            return false;
+        }
+
+        state.resolved = true;
+        state.result = Some(result);
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+
+        self.condvar.notify_all();
+
+        true
+    }
+
+    fn wait_until(&self, state: MutexGuard<'_, State>, deadline: Instant) {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let _ = self.condvar.wait_timeout(state, timeout).unwrap();
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// The value returned by the process, which becomes the result when the process exits
+    /// normally
+    returned: Option<TermView>,
+    resolved: bool,
+    /// `None` before the future resolves, and after the result is taken
+    result: Option<Result<TermView, FutureError>>,
+    waker: Option<Waker>,
+}
+
+impl State {
+    fn take(&mut self) -> Option<Result<TermView, FutureError>> {
+        self.result.take()
+    }
+}
+
+fn spawn_frame_with_arguments<F>(
+    scheduler: Arc<dyn SchedulerTrait>,
+    initial_module_function_arity: ModuleFunctionArity,
+    frame_with_arguments_fn: F,
+) -> Result<ProcessFuture, SpawnError>
+where
+    F: FnOnce(&Process) -> Result<FrameWithArguments, SpawnError>,
+{
+    let options: Options = Default::default();
+    let reservation = registry::reserve_pid()?;
+    let (heap, heap_size) = options.sized_heap()?;
+    let priority = options.cascaded_priority(None);
+    let process = Process::new(
+        priority,
+        None,
+        initial_module_function_arity,
+        heap,
+        heap_size,
+    );
+
+    let shared = Arc::new(Shared::default());
+    let frame_with_arguments = frame_with_arguments_fn(&process)?;
+    let shared_resource = process.resource(shared.clone());
+
+    process.runnable(|| {
+        process.queue_frame_with_arguments(frame_with_arguments);
+        process
+            .queue_frame_with_arguments(returned_frame().with_arguments(true, &[shared_resource]));
+        process.queue_frame_with_arguments(out_of_code::frame().with_arguments(false, &[]));
+        process.stack_queued_frames_with_arguments();
+    });
+
+    // Observe the exit before the process is scheduled, as the scheduler may run it immediately
+    let exited_shared = shared.clone();
+    monitor::observe_exit(
+        process.pid(),
+        Box::new(move |_, reason| exited_shared.exited(reason)),
+    );
+
+    let arc_process = scheduler.schedule(process, reservation);
+
+    Ok(ProcessFuture {
+        arc_process,
+        scheduler,
+        shared,
+    })
+}
+
+const RETURNED_ARITY: Arity = 2;
+
+fn returned_frame() -> Frame {
+    let module_function_arity = ModuleFunctionArity {
+        module: Atom::from_str("lumen"),
+        function: Atom::from_str("future_returned"),
+        arity: RETURNED_ARITY,
+    };
+
+    Frame::new(module_function_arity, Native::Two(returned_native))
+}
+
+/// Holds onto the value returned by the spawned function until the process exits
+extern "C-unwind" fn returned_native(value: Term, shared: Term) -> ErlangResult {
+    let shared_resource_box: Boxed<Resource> = shared.try_into().unwrap();
+    let shared_resource: Resource = shared_resource_box.into();
+    let shared: &Arc<Shared> = shared_resource.downcast_ref().unwrap();
+
+    shared.returned(value.into());
+
+    ErlangResult::ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::Wake;
+    use std::thread;
+
+    use liblumen_alloc::atom;
+    use liblumen_alloc::erts::message::Message;
+    use liblumen_alloc::erts::process::trace::Trace;
+    use liblumen_alloc::time::{Milliseconds, Monotonic};
+
+    use crate::process::current_process;
+    use firefly_rt_core::process::Status;
+    use firefly_rt_core::registry::pid_to_process;
+    use firefly_rt_core::scheduler::Idle;
+    use firefly_rt_core::timer::{self, SourceEvent};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+    /// How long `receive_after` waits for a message
+    const AFTER: Milliseconds = Milliseconds(50);
+    /// The granularity of the scheduler's timer wheel
+    const TICK: Milliseconds = Milliseconds(1);
+
+    #[test]
+    fn returning_resolves_with_value() {
+        let mut future = spawn_native(Native::Zero(return_42)).unwrap();
+
+        assert_eq!(future.wait(TIMEOUT), Ok(TermView::Integer(42.into())));
+    }
+
+    #[test]
+    fn returning_wakes_waker() {
+        let mut future = spawn_native(Native::Zero(return_42)).unwrap();
+        let flag_waker = Arc::new(FlagWaker::default());
+        let waker: Waker = flag_waker.clone().into();
+        let mut context = Context::from_waker(&waker);
+
+        assert_eq!(Pin::new(&mut future).poll(&mut context), Poll::Pending);
+
+        let scheduler = scheduler::current();
+
+        while !flag_waker.0.load(Ordering::SeqCst) {
+            assert!(scheduler.run_once());
+        }
+
+        assert_eq!(
+            Pin::new(&mut future).poll(&mut context),
+            Poll::Ready(Ok(TermView::Integer(42.into())))
+        );
+    }
+
+    #[test]
+    fn crashing_resolves_with_exit_reason() {
+        let mut future = spawn_native(Native::Zero(exit_boom)).unwrap();
+
+        assert_eq!(
+            future.wait(TIMEOUT),
+            Err(FutureError::Exited(TermView::atom("boom")))
+        );
+    }
+
+    #[test]
+    fn timeout_returns_error_and_leaves_process_running() {
+        let mut future = spawn_native(Native::Zero(wait_forever)).unwrap();
+        let timeout = Duration::from_millis(10);
+
+        assert_eq!(future.wait(timeout), Err(FutureError::Timeout(timeout)));
+        assert!(is_process_alive(future.process()));
+    }
+
+    #[test]
+    fn cancel_resolves_with_cancelled_and_kills_process() {
+        let mut future = spawn_native(Native::Zero(wait_forever)).unwrap();
+
+        assert!(scheduler::current().run_once());
+        assert!(is_process_alive(future.process()));
+
+        future.cancel();
+
+        assert_eq!(future.wait(TIMEOUT), Err(FutureError::Cancelled));
+
+        let scheduler = scheduler::current();
+
+        while is_process_alive(future.process()) {
+            assert!(scheduler.run_once());
+        }
+    }
+
+    #[test]
+    fn receive_after_times_out_on_time_without_spinning() {
+        let mut future = spawn_native(Native::Zero(receive_after)).unwrap();
+        let scheduler = scheduler::current();
+        let start = monotonic::time();
+
+        run_until_waiting(future.process());
+
+        let wakeup = match scheduler.idle() {
+            Idle::Until(wakeup) => wakeup,
+            Idle::Indefinitely => panic!("the receive's timeout is not pending"),
+        };
+        assert!(start + AFTER <= wakeup);
+
+        let mut wakeups = 0;
+
+        while is_waiting(future.process()) {
+            if !scheduler.run_once() {
+                thread::sleep(scheduler.idle().timeout(monotonic::time(), MAX_IDLE_SLEEP));
+                wakeups += 1;
+            }
+        }
+
+        let timed_out = monotonic::time();
+
+        assert!(wakeup <= timed_out, "timed out before {}", wakeup);
+        assert!(
+            timed_out <= wakeup + TICK,
+            "timed out at {}, more than a tick after {}",
+            timed_out,
+            wakeup
+        );
+        // Spinning through the scheduler would wake it on every pass, not once per sleep
+        let max_wakeups = AFTER.as_u64() / (MAX_IDLE_SLEEP.as_millis() as u64) + 2;
+        assert!(
+            wakeups <= max_wakeups,
+            "woke {} times, more than {}",
+            wakeups,
+            max_wakeups
+        );
+
+        assert_eq!(future.wait(TIMEOUT), Ok(TermView::atom("timeout")));
+    }
+
+    #[test]
+    fn message_preempts_receive_after_timeout() {
+        let mut future = spawn_native(Native::Zero(receive_after)).unwrap();
+        let scheduler = scheduler::current();
+        let start = monotonic::freeze();
+
+        run_until_waiting(future.process());
+
+        assert_eq!(scheduler.idle(), Idle::Until(start + AFTER + TICK));
+
+        monotonic::freeze_at(start + Milliseconds(10));
+        assert!(!scheduler.run_once());
+        assert!(is_waiting(future.process()));
+
+        future.process().send_from_other(atom!("message"));
+        scheduler.stop_waiting(future.process());
+
+        assert_eq!(future.wait(TIMEOUT), Ok(TermView::atom("message")));
+        // Receiving the message canceled the timeout
+        assert!(scheduler.hierarchy().read().is_empty());
+        assert_eq!(scheduler.idle(), Idle::Indefinitely);
+
+        // Nothing times out once the receive's time has passed either
+        monotonic::freeze_at(start + AFTER + Milliseconds(10));
+        let _ = scheduler.run_once();
+
+        assert!(scheduler.hierarchy().read().is_empty());
+        assert!(!is_process_alive(future.process()));
+    }
+
+    #[test]
+    fn stop_waiting_from_another_thread_unparks_idle_scheduler() {
+        let future = spawn_native(Native::Zero(wait_forever)).unwrap();
+        let scheduler = scheduler::current();
+
+        run_until_waiting(future.process());
+        assert!(!scheduler.run_once());
+
+        let arc_scheduler = scheduler.clone();
+        let arc_process = future.process().clone();
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            arc_scheduler.stop_waiting(&arc_process);
+        });
+
+        let start = Instant::now();
+
+        // Sleeps for as long as the test could take, so only the other thread can wake it in time
+        while is_waiting(future.process()) {
+            thread::park_timeout(TIMEOUT);
+        }
+
+        assert!(start.elapsed() < TIMEOUT, "was not unparked");
+        sender.join().unwrap();
+        assert!(scheduler.run_once());
+    }
+
+    #[derive(Default)]
+    struct FlagWaker(AtomicBool);
+
+    impl Wake for FlagWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// The same check as `erlang:is_process_alive/1`
+    fn is_process_alive(process: &Process) -> bool {
+        match pid_to_process(&process.pid()) {
+            Some(arc_process) => !arc_process.is_exiting(),
+            None => false,
+        }
+    }
+
+    fn spawn_native(native: Native) -> Result<ProcessFuture, SpawnError> {
+        let module_function_arity = ModuleFunctionArity {
+            module: Atom::from_str("future_test"),
+            function: Atom::from_str("native"),
+            arity: 0,
+        };
+        let frame = Frame::new(module_function_arity, native);
+
+        spawn_frame_with_arguments(scheduler::current(), module_function_arity, |_| {
+            Ok(frame.with_arguments(false, &[]))
+        })
+    }
+
+    extern "C-unwind" fn return_42() -> ErlangResult {
+        ErlangResult::ok(current_process().integer(42))
+    }
+
+    extern "C-unwind" fn exit_boom() -> ErlangResult {
+        current_process().exit(atom!("boom"), Trace::capture(), None);
+
+        ErlangResult::ok(Term::NONE)
+    }
+
+    extern "C-unwind" fn wait_forever() -> ErlangResult {
+        let process = current_process();
+        process.reduce();
+        process.wait();
+
+        let module_function_arity = ModuleFunctionArity {
+            module: Atom::from_str("future_test"),
+            function: Atom::from_str("wait_forever"),
+            arity: 0,
+        };
+        let frame = Frame::new(module_function_arity, Native::Zero(wait_forever));
+        process.queue_frame_with_arguments(frame.with_arguments(false, &[]));
+
+        ErlangResult::ok(Term::NONE)
+    }
+
+    /// `receive Message -> Message after 50 -> timeout end`, which times out with a timer that
+    /// stops it waiting, as `__lumen_builtin_receive_start` does
+    extern "C-unwind" fn receive_after() -> ErlangResult {
+        let arc_process = current_process();
+        let deadline = monotonic::time() + AFTER;
+        let timer_reference =
+            timer::start(deadline, SourceEvent::StopWaiting, arc_process.clone()).unwrap();
+
+        receive_after_wait(arc_process.integer(deadline.0), timer_reference)
+    }
+
+    extern "C-unwind" fn receive_after_wait(deadline: Term, timer_reference: Term) -> ErlangResult {
+        let process = current_process();
+        process.reduce();
+
+        let option_message = {
+            let mailbox_guard = process.mailbox.lock();
+            let mut mailbox = mailbox_guard.borrow_mut();
+            let option_message = mailbox
+                .cursor()
+                .get()
+                .map(|message| (message as *const Message, message.data()));
+
+            option_message.map(|(message, data)| {
+                if let Some(fragment) = mailbox.remove(message) {
+                    process.attach_fragment(unsafe { &mut *fragment.as_ptr() });
+                }
+                data
+            })
+        };
+
+        if let Some(message) = option_message {
+            let boxed_timer_reference: Boxed<Reference> = timer_reference.try_into().unwrap();
+            timer::cancel(&boxed_timer_reference);
+
+            return ErlangResult::ok(message);
+        }
+
+        let deadline_milliseconds: u64 = deadline.try_into().unwrap();
+
+        // The timer has timed out, which is what stopped the wait
+        if Monotonic::from_millis(deadline_milliseconds) <= monotonic::time() {
+            return ErlangResult::ok(atom!("timeout"));
+        }
+
+        process.wait();
+
+        let module_function_arity = ModuleFunctionArity {
+            module: Atom::from_str("future_test"),
+            function: Atom::from_str("receive_after_wait"),
+            arity: 2,
+        };
+        let frame = Frame::new(module_function_arity, Native::Two(receive_after_wait));
+        process
+            .queue_frame_with_arguments(frame.with_arguments(false, &[deadline, timer_reference]));
+
+        ErlangResult::ok(Term::NONE)
+    }
+
+    fn is_waiting(process: &Process) -> bool {
+        matches!(*process.status.read(), Status::Waiting)
+    }
+
+    /// Runs the scheduler until `process` waits in its receive
+    fn run_until_waiting(process: &Process) {
+        let scheduler = scheduler::current();
+
+        while !is_waiting(process) {
+            assert!(scheduler.run_once());
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use num_bigint::BigInt;
+
+use firefly_rt_core::term::prelude::*;
+
+/// An owned copy of a term, which remains valid after the process whose heap held the term exits
+#[derive(Clone, Debug, PartialEq)]
+pub enum TermView {
+    Atom(String),
+    Integer(BigInt),
+    Float(f64),
+    Binary(Vec<u8>),
+    /// A proper list, including the empty list
+    List(Vec<TermView>),
+    ImproperList(Vec<TermView>, Box<TermView>),
+    Tuple(Vec<TermView>),
+    /// Key-value pairs, in no particular order
+    Map(Vec<(TermView, TermView)>),
+    Pid(Pid),
+    /// Any other term, such as a reference, closure, or bitstring, as it is displayed
+    Other(String),
+}
+
+impl TermView {
+    pub fn atom(name: &str) -> Self {
+        Self::Atom(name.to_string())
+    }
+}
+
+impl From<Term> for TermView {
+    fn from(term: Term) -> Self {
+        match term.decode().unwrap() {
+            TypedTerm::Atom(atom) => Self::Atom(atom.name().to_string()),
+            TypedTerm::SmallInteger(small_integer) => Self::Integer(small_integer.into()),
+            TypedTerm::BigInteger(big_integer) => Self::Integer(big_integer.into()),
+            #[cfg(target_arch = "x86_64")]
+            TypedTerm::Float(float) => Self::Float(float.into()),
+            #[cfg(not(target_arch = "x86_64"))]
+            TypedTerm::Float(float) => Self::Float((*float).into()),
+            TypedTerm::HeapBinary(heap_binary) => Self::Binary(heap_binary.as_bytes().to_vec()),
+            TypedTerm::ProcBin(process_binary) => Self::Binary(process_binary.as_bytes().to_vec()),
+            TypedTerm::BinaryLiteral(binary_literal) => {
+                Self::Binary(binary_literal.as_bytes().to_vec())
+            }
+            TypedTerm::SubBinary(subbinary) if subbinary.is_binary() => {
+                Self::Binary(subbinary.full_byte_iter().collect())
+            }
+            TypedTerm::Nil => Self::List(Vec::new()),
+            TypedTerm::List(cons) => {
+                let mut elements = Vec::new();
+
+                for result in cons.iter() {
+                    match result {
+                        Ok(element) => elements.push(element.into()),
+                        Err(ImproperList { tail }) => {
+                            return Self::ImproperList(elements, Box::new(tail.into()))
+                        }
+                    }
+                }
+
+                Self::List(elements)
+            }
+            TypedTerm::Tuple(tuple) => {
+                Self::Tuple(tuple.iter().map(|element| (*element).into()).collect())
+            }
+            TypedTerm::Map(map) => Self::Map(
+                map.iter()
+                    .map(|(key, value)| ((*key).into(), (*value).into()))
+                    .collect(),
+            ),
+            TypedTerm::Pid(pid) => Self::Pid(pid),
+            _ => Self::Other(term.to_string()),
+        }
+    }
+}
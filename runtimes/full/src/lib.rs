@@ -19,7 +19,7 @@
 #![feature(type_ascription)]
 // for `crate::term::Term`
 #![feature(untagged_unions)]
-// `crate::registry::<Registered as PartialEq>::eq`
+// `firefly_rt_core::registry::<Registered as PartialEq>::eq`
 #![feature(weak_ptr_eq)]
 // Layout helpers
 #![feature(alloc_layout_extra)]
@@ -30,6 +30,8 @@
 #![feature(thread_local)]
 // Unwinding across C ABI
 #![feature(c_unwind)]
+// Any use of a deprecated `lumen_*` path is an error when checking the migration to `firefly_*`
+#![cfg_attr(feature = "strict-naming", deny(deprecated))]
 
 extern crate alloc;
 extern crate cfg_if;
@@ -37,18 +39,35 @@ extern crate chrono;
 
 use anyhow::anyhow;
 
-pub use lumen_rt_core::{
-    base, binary_to_string, context, distribution, integer_to_string, proplist, registry, send,
-    test, time, timer,
+pub use firefly_rt_core::{
+    base, binary_to_string, blocking, context, distribution, integer_to_string, pg, proplist, send, test,
+    time,
 };
 
-#[cfg(not(any(test, target_arch = "wasm32")))]
+/// The old path of `firefly_rt_full::registry`
+#[cfg(not(feature = "strict-naming"))]
+#[deprecated(note = "use `firefly_rt_full::registry` instead")]
+pub mod registry {
+    pub use firefly_rt_core::registry::*;
+}
+
+/// The old path of `firefly_rt_full::timer`
+#[cfg(not(feature = "strict-naming"))]
+#[deprecated(note = "use `firefly_rt_full::timer` instead")]
+pub mod timer {
+    pub use firefly_rt_core::timer::*;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg_attr(test, allow(dead_code))]
 mod config;
 pub mod future;
-mod logging;
+// `pub` for `sys::dump`
+pub mod logging;
 pub mod process;
 // `pub` for `examples/spawn-chain`
 pub mod scheduler;
+pub mod setting;
 // `pub` for `examples/spawn-chain`
 pub mod sys;
 // `pub` for `examples/spawn-chain`
@@ -76,19 +95,24 @@ fn main() -> i32 {
 fn main_internal(name: &str, version: &str, argv: Vec<String>) -> anyhow::Result<()> {
     use self::config::Config;
     use self::logging::Logger;
+    use self::sys::break_handler::menu::{BreakHandler, Interrupt};
     use self::sys::break_handler::{self, Signal};
     use bus::Bus;
-    use log::Level;
-    use std::thread;
+    use firefly_rt_core::scheduler::MAX_IDLE_SLEEP;
+    use firefly_rt_core::time::monotonic;
+    use std::time::{Duration, Instant};
 
     // Load system configuration
-    let _config = match Config::from_argv(name.to_string(), version.to_string(), argv) {
+    let mut config = match Config::from_argv(name.to_string(), version.to_string(), argv) {
         Ok(config) => config,
         Err(err) => {
             return Err(anyhow!(err));
         }
     };
 
+    // Before any process is spawned, so that none are spawned over the limit
+    firefly_rt_core::registry::set_process_limit(config.process_limit);
+
     // This bus is used to receive signals across threads in the system
     let mut bus: Bus<break_handler::Signal> = Bus::new(1);
     // Each thread needs a reader
@@ -97,54 +121,100 @@ fn main_internal(name: &str, version: &str, argv: Vec<String>) -> anyhow::Result
     break_handler::init(bus);
 
     // Start logger
-    Logger::init(Level::Info).expect("Unexpected failure initializing logger");
+    let log_filter = Logger::init(config.log_filter.clone(), &config.log_backends)
+        .expect("Unexpected failure initializing logger");
+    // Once the logger is started, so the ring buffer backend's records are in crash dumps
+    sys::dump::install_panic_hook();
+    scheduler::set_halt_on_init_exit(true);
+
+    let break_menu = BreakHandler::new();
 
     let scheduler = scheduler::current();
+    // With `-v`, the schedulers' statistics are logged every `STATS_LOG_INTERVAL`
+    let mut stats_logged = Instant::now();
     loop {
+        // While the break menu is open, scheduling is paused, but we must keep
+        // checking for signals, as a second SIGINT aborts
+        if break_menu.is_paused() {
+            break_menu.park_while_paused(Duration::from_millis(10));
+        } else if break_menu.take_quit_request() {
+            scheduler.shutdown()?;
+            break;
+        }
         // Run the scheduler for a cycle
-        let scheduled = scheduler.run_once();
+        let scheduled = !break_menu.is_paused() && scheduler.run_once();
         // Check for system signals, and terminate if needed
         if let Ok(sig) = rx1.try_recv() {
             match sig {
-                // For now, SIGINT initiates a controlled shutdown
+                Signal::INT if config.break_menu => match break_menu.interrupt() {
+                    Interrupt::OpenMenu => break_menu.spawn_menu(),
+                    Interrupt::Abort => std::process::abort(),
+                },
+                // Otherwise, SIGINT initiates a controlled shutdown
                 Signal::INT => {
                     // If an error occurs, report it before shutdown
                     if let Err(err) = scheduler.shutdown() {
+                        let ring = logging::ring_buffer();
+                        let _ = sys::dump::write_crash_dump(
+                            &mut std::io::stderr(),
+                            &err.to_string(),
+                            ring.as_deref(),
+                        );
+                        log::logger().flush();
                         return Err(anyhow!(err));
                     } else {
                         break;
                     }
                 }
+                // Like many daemons, SIGHUP reloads the configuration, rather than terminating
+                Signal::HUP => {
+                    if let Err(err) = config.reload(&log_filter) {
+                        log::error!("not reloading configuration: {}", err);
+                    }
+                }
                 // Technically, we may never see these signals directly,
                 // we may just be terminated out of hand; but just in case,
                 // we handle them explicitly by immediately terminating, so
                 // that we are good citizens of the operating system
                 sig if sig.should_terminate() => {
+                    log::logger().flush();
                     return Ok(());
                 }
                 // All other signals can be surfaced to other parts of the
-                // system for custom use, e.g. SIGCHLD, SIGALRM, SIGUSR1/2
+                // system for custom use, e.g. SIGCHLD, SIGALRM, SIGUSR2
                 _ => (),
             }
         }
+        if scheduler::STATS_LOG_INTERVAL <= stats_logged.elapsed()
+            && log::log_enabled!(log::Level::Debug)
+        {
+            scheduler::log_stats();
+            stats_logged = Instant::now();
+        }
         // If the scheduler scheduled a process this cycle, then we're busy
         // and should keep working until we have an idle period
         if scheduled {
             continue;
         }
-        // Otherwise,
+        // Otherwise, every process is waiting, so sleep until a timer may wake one, or another
+        // thread unparks us because it made one runnable or a signal arrived.
+        //
         // In some configurations, it makes more sense for us to spin and use
         // spin_loop_hint here instead; namely when we're supposed to be the primary
         // software on a system, and threads are pinned to cores, it makes no sense
         // to yield to the system scheduler. However on a system with contention for
         // system resources, or where threads aren't pinned to cores, we're better off
-        // explicitly yielding to the scheduler, rather than waiting to be preempted at
-        // a potentially inopportune time.
+        // sleeping, rather than spinning through cycles that can't run anything.
         //
-        // In any case, for now, we always explicitly yield until we've got proper support
+        // In any case, for now, we always sleep until we've got proper support
         // for configuring the system
-        thread::yield_now()
+        let timeout = scheduler
+            .idle()
+            .timeout(monotonic::time(), MAX_IDLE_SLEEP);
+        scheduler.counters().park(timeout);
     }
 
+    log::logger().flush();
+
     Ok(())
 }
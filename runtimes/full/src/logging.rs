@@ -1,84 +1,321 @@
-#[cfg(not(target_arch = "wasm32"))]
-use colored::*;
+mod backend;
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use lazy_static::lazy_static;
 #[cfg(not(any(test, target_arch = "wasm32")))]
 use log::SetLoggerError;
-use log::{Level, Log, Metadata, Record};
+use log::{Level, LevelFilter, Log, Metadata, Record};
 
+use crate::setting::Setting;
 use crate::time::system;
 use crate::time::Unit::Second;
 
-pub struct Logger {
-    level: Level,
-    color: bool,
+pub use self::backend::{
+    Backend, BackendConfig, FileBackend, RingBuffer, RingBufferBackend, StderrBackend,
+};
+
+lazy_static! {
+    static ref RING_BUFFER: Mutex<Option<Arc<RingBuffer>>> = Mutex::new(None);
 }
 
-impl Logger {
-    #[cfg(not(any(test, target_arch = "wasm32")))]
-    pub fn init(level: Level) -> Result<(), SetLoggerError> {
-        log::set_max_level(level.to_level_filter());
+/// Returns the ring buffer backend's records, if one was configured, for inclusion in dumps
+pub fn ring_buffer() -> Option<Arc<RingBuffer>> {
+    RING_BUFFER.lock().unwrap().clone()
+}
+
+/// Which records are logged: those at or above `level`, unless a more specific level is given for
+/// the module they are logged from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    pub level: LevelFilter,
+    /// Levels for modules and their submodules, by module path, e.g. `firefly_rt_core::timer`
+    pub modules: Vec<(String, LevelFilter)>,
+}
+impl LogFilter {
+    pub fn new(level: LevelFilter) -> Self {
+        Self {
+            level,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Returns the level for records logged from `target`, which is the level of the longest
+    /// module path that contains it
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target == module
+                    || (target.starts_with(module.as_str())
+                        && target[module.len()..].starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
+
+    /// Returns the most verbose level of any module, for `log::set_max_level`
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.level, Ord::max)
+    }
+}
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.level.to_string().to_lowercase())?;
+        for (module, level) in &self.modules {
+            write!(f, ",{}={}", module, level.to_string().to_lowercase())?;
+        }
         Ok(())
     }
+}
 
-    #[cfg(not(target_arch = "wasm32"))]
-    fn log_color(record: &Record) {
-        let level = match record.level() {
-            Level::Error => record.level().to_string().red(),
-            Level::Warn => record.level().to_string().yellow(),
-            Level::Info => record.level().to_string().cyan(),
-            Level::Debug => record.level().to_string().purple(),
-            Level::Trace => record.level().to_string().normal(),
-        };
-        println!(
-            "{} {:<5} [{}] {}",
-            system::time_in_unit(Second),
-            level,
-            record.module_path().unwrap_or_default(),
-            record.args(),
-        )
+/// Parses a `MODULE=LEVEL` module filter, as given to `--log-filter`
+pub fn parse_module_filter(filter: &str) -> Result<(String, LevelFilter), String> {
+    match filter.split_once('=') {
+        Some((module, level)) if !module.is_empty() => LevelFilter::from_str(level)
+            .map(|level| (module.to_string(), level))
+            .map_err(|_| format!("expected a log level, got {}", level)),
+        _ => Err(format!("expected MODULE=LEVEL, got {}", filter)),
     }
-    #[cfg(not(target_arch = "wasm32"))]
-    fn log_plain(record: &Record) {
-        println!(
-            "{} {:<5} [{}] {}",
-            system::time_in_unit(Second),
-            record.level(),
-            record.module_path().unwrap_or_default(),
-            record.args(),
+}
+
+/// Replaces the filter of the logger reading `setting`, and raises or lowers the maximum level
+/// of the `log` macros to match
+pub fn set_filter(setting: &Setting<LogFilter>, filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    setting.store(filter);
+}
+
+/// Dispatches each record to all configured backends.
+///
+/// A backend which fails is reported once on stderr and then disabled, so a full disk or a
+/// closed pipe never takes down the system.
+pub struct Logger {
+    filter: Arc<Setting<LogFilter>>,
+    backends: Mutex<Vec<Option<Box<dyn Backend>>>>,
+}
+
+impl Logger {
+    pub fn new(level: Level, backends: Vec<Box<dyn Backend>>) -> Self {
+        Self::with_filter(
+            Arc::new(Setting::new(LogFilter::new(level.to_level_filter()))),
+            backends,
         )
     }
 
-    #[cfg(target_arch = "wasm32")]
-    fn log_color(record: &Record) {
-        Self::log_plain(record);
+    /// Creates a logger whose filter can be replaced through `filter` while it is in use
+    pub fn with_filter(filter: Arc<Setting<LogFilter>>, backends: Vec<Box<dyn Backend>>) -> Self {
+        Self {
+            filter,
+            backends: Mutex::new(backends.into_iter().map(Some).collect()),
+        }
+    }
+
+    /// Builds a logger from `configs`
+    ///
+    /// Backends which cannot be started are reported on stderr and skipped. If a ring buffer
+    /// is configured, it becomes the one returned by `ring_buffer()`.
+    pub fn from_config(filter: Arc<Setting<LogFilter>>, configs: &[BackendConfig]) -> Self {
+        let mut backends = Vec::with_capacity(configs.len());
+        for config in configs {
+            let backend: Box<dyn Backend> = match config {
+                BackendConfig::RingBuffer { capacity } => {
+                    let buffer = RingBuffer::new(*capacity);
+                    *RING_BUFFER.lock().unwrap() = Some(buffer.clone());
+                    Box::new(RingBufferBackend::new(buffer))
+                }
+                config => match config.build() {
+                    Ok(backend) => backend,
+                    Err(err) => {
+                        eprintln!("failed to start logger backend {:?}: {}", config, err);
+                        continue;
+                    }
+                },
+            };
+            backends.push(backend);
+        }
+        Self::with_filter(filter, backends)
+    }
+
+    /// Installs the logger, returning the setting through which its filter can be replaced
+    #[cfg(not(any(test, target_arch = "wasm32")))]
+    pub fn init(
+        filter: LogFilter,
+        configs: &[BackendConfig],
+    ) -> Result<Arc<Setting<LogFilter>>, SetLoggerError> {
+        let max_level = filter.max_level();
+        let setting = Arc::new(Setting::new(filter));
+        let logger = Self::from_config(setting.clone(), configs);
+        log::set_logger(Box::leak(Box::new(logger)))?;
+        log::set_max_level(max_level);
+        Ok(setting)
     }
 
-    #[cfg(target_arch = "wasm32")]
-    fn log_plain(record: &Record) {
-        let msg = format!(
+    fn format(record: &Record) -> String {
+        format!(
             "{} {:<5} [{}] {}",
             system::time_in_unit(Second),
             record.level(),
             record.module_path().unwrap_or_default(),
-            record.args()
-        );
-        crate::sys::io::puts(&msg);
+            record.args(),
+        )
     }
 }
 
 impl Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.level
+        metadata.level() <= self.filter.load().level_for(metadata.target())
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        let mut backends = self.backends.lock().unwrap();
+        for slot in backends.iter_mut() {
+            if let Some(backend) = slot {
+                if let Err(err) = backend.flush() {
+                    eprintln!("disabling {} logger backend: {}", backend.name(), err);
+                    *slot = None;
+                }
+            }
+        }
+    }
 
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
-            if self.color {
-                Self::log_color(record);
-            } else {
-                Self::log_plain(record);
+            let line = Self::format(record);
+            let mut backends = self.backends.lock().unwrap();
+            for slot in backends.iter_mut() {
+                if let Some(backend) = slot {
+                    if let Err(err) = backend.log(record.level(), &line) {
+                        eprintln!("disabling {} logger backend: {}", backend.name(), err);
+                        *slot = None;
+                    }
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    struct FailingBackend(Arc<Mutex<usize>>);
+    impl Backend for FailingBackend {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn log(&mut self, _level: Level, _line: &str) -> io::Result<()> {
+            *self.0.lock().unwrap() += 1;
+            Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn log(logger: &Logger, message: &str) {
+        logger.log(
+            &Record::builder()
+                .level(Level::Warn)
+                .args(format_args!("{}", message))
+                .build(),
+        );
+    }
+
+    fn enabled(logger: &Logger, level: Level, target: &str) -> bool {
+        logger.enabled(&Metadata::builder().level(level).target(target).build())
+    }
+
+    #[test]
+    fn module_filters_apply_to_submodules_by_longest_path() {
+        let filter = LogFilter {
+            level: LevelFilter::Info,
+            modules: vec![
+                ("firefly_rt_core".to_string(), LevelFilter::Warn),
+                ("firefly_rt_core::timer".to_string(), LevelFilter::Trace),
+            ],
+        };
+
+        assert_eq!(filter.level_for("firefly_rt_full"), LevelFilter::Info);
+        assert_eq!(filter.level_for("firefly_rt_core"), LevelFilter::Warn);
+        assert_eq!(
+            filter.level_for("firefly_rt_core::registry"),
+            LevelFilter::Warn
+        );
+        assert_eq!(
+            filter.level_for("firefly_rt_core::timer"),
+            LevelFilter::Trace
+        );
+        assert_eq!(
+            filter.level_for("firefly_rt_core::timer::wheel"),
+            LevelFilter::Trace
+        );
+        // Only whole path segments match
+        assert_eq!(filter.level_for("firefly_rt_core_extra"), LevelFilter::Info);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+        assert_eq!(
+            filter.to_string(),
+            "info,firefly_rt_core=warn,firefly_rt_core::timer=trace"
+        );
+    }
+
+    #[test]
+    fn module_filters_are_parsed_from_module_and_level() {
+        assert_eq!(
+            parse_module_filter("firefly_rt_core::timer=debug"),
+            Ok(("firefly_rt_core::timer".to_string(), LevelFilter::Debug))
+        );
+        assert!(parse_module_filter("firefly_rt_core").is_err());
+        assert!(parse_module_filter("=debug").is_err());
+        assert!(parse_module_filter("firefly_rt_core=loud").is_err());
+    }
+
+    #[test]
+    fn replaced_filter_applies_to_logger_in_use() {
+        let setting = Arc::new(Setting::new(LogFilter::new(LevelFilter::Info)));
+        let logger = Logger::with_filter(setting.clone(), vec![]);
+
+        assert!(!enabled(&logger, Level::Debug, "firefly_rt_core::timer"));
+
+        let mut filter = LogFilter::new(LevelFilter::Warn);
+        filter
+            .modules
+            .push(("firefly_rt_core::timer".to_string(), LevelFilter::Debug));
+        set_filter(&setting, filter);
+
+        assert!(enabled(&logger, Level::Debug, "firefly_rt_core::timer"));
+        assert!(!enabled(&logger, Level::Info, "firefly_rt_core::registry"));
+        assert!(enabled(&logger, Level::Warn, "firefly_rt_core::registry"));
+    }
+
+    #[test]
+    fn failing_backend_is_disabled_without_affecting_others() {
+        let attempts = Arc::new(Mutex::new(0));
+        let buffer = RingBuffer::new(4);
+        let logger = Logger::new(
+            Level::Info,
+            vec![
+                Box::new(FailingBackend(attempts.clone())),
+                Box::new(RingBufferBackend::new(buffer.clone())),
+            ],
+        );
+
+        log(&logger, "first");
+        log(&logger, "second");
+
+        assert_eq!(*attempts.lock().unwrap(), 1);
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("first"));
+        assert!(lines[1].ends_with("second"));
+    }
+}
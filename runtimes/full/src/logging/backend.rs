@@ -0,0 +1,356 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[cfg(not(target_arch = "wasm32"))]
+use colored::*;
+use log::Level;
+
+/// A destination for log records
+///
+/// Backends receive each record already formatted as a single line, without a trailing
+/// newline. Errors are handled by the `Logger`, which disables a backend the first time it
+/// fails, so implementations should not attempt to recover from them.
+pub trait Backend: Send {
+    /// A short name for this backend, used when reporting errors
+    fn name(&self) -> &'static str;
+
+    fn log(&mut self, level: Level, line: &str) -> io::Result<()>;
+
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// Describes a backend to be started with the logger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendConfig {
+    Stderr { color: bool },
+    File {
+        path: PathBuf,
+        max_size: u64,
+        max_files: usize,
+    },
+    RingBuffer { capacity: usize },
+}
+impl BackendConfig {
+    pub fn build(&self) -> io::Result<Box<dyn Backend>> {
+        match self {
+            Self::Stderr { color } => Ok(Box::new(StderrBackend::new(*color))),
+            Self::File {
+                path,
+                max_size,
+                max_files,
+            } => Ok(Box::new(FileBackend::open(path, *max_size, *max_files)?)),
+            Self::RingBuffer { capacity } => {
+                Ok(Box::new(RingBufferBackend::new(RingBuffer::new(*capacity))))
+            }
+        }
+    }
+}
+
+/// Writes records to stderr, optionally colored by level
+pub struct StderrBackend {
+    color: bool,
+}
+impl StderrBackend {
+    pub fn new(color: bool) -> Self {
+        Self { color }
+    }
+}
+impl Backend for StderrBackend {
+    fn name(&self) -> &'static str {
+        "stderr"
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn log(&mut self, level: Level, line: &str) -> io::Result<()> {
+        let mut stderr = io::stderr();
+        if !self.color {
+            return writeln!(stderr, "{}", line);
+        }
+        let line = match level {
+            Level::Error => line.red(),
+            Level::Warn => line.yellow(),
+            Level::Info => line.cyan(),
+            Level::Debug => line.purple(),
+            Level::Trace => line.normal(),
+        };
+        writeln!(stderr, "{}", line)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn log(&mut self, _level: Level, line: &str) -> io::Result<()> {
+        crate::sys::io::puts(line);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// Writes records to a file, rotating it once it would exceed `max_size` bytes.
+///
+/// At most `max_files` files are kept: the active file at `path`, and archives at `path.1`
+/// (the most recent) through `path.{max_files - 1}` (the oldest). Rotation renames each file
+/// over the next, so every file is always either complete or absent.
+///
+/// Writes are buffered, and flushed when an error is logged, or the logger is flushed.
+pub struct FileBackend {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    writer: BufWriter<File>,
+    size: u64,
+}
+impl FileBackend {
+    pub fn open<P: AsRef<Path>>(path: P, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_size,
+            max_files: max_files.max(1),
+            writer: BufWriter::new(file),
+            size,
+        })
+    }
+
+    fn archive_path(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+
+        if self.max_files > 1 {
+            // Shift each archive up by one, replacing the oldest
+            for n in (1..self.max_files - 1).rev() {
+                let from = self.archive_path(n);
+                if from.exists() {
+                    fs::rename(&from, self.archive_path(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.archive_path(1))?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.size = 0;
+
+        Ok(())
+    }
+}
+impl Backend for FileBackend {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn log(&mut self, level: Level, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.size += len;
+
+        // Errors often precede a crash, so make sure they reach the disk
+        if level == Level::Error {
+            self.writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Retains the most recent records in memory, so they can be included in crash dumps
+pub struct RingBuffer {
+    capacity: usize,
+    lines: Mutex<VecDeque<String>>,
+}
+impl RingBuffer {
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            capacity,
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+        })
+    }
+
+    pub fn push(&self, line: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_string());
+    }
+
+    /// Returns the retained records, oldest first
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Writes the retained records to `writer`, oldest first
+    pub fn dump(&self, writer: &mut dyn Write) -> io::Result<()> {
+        for line in self.lines.lock().unwrap().iter() {
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct RingBufferBackend {
+    buffer: Arc<RingBuffer>,
+}
+impl RingBufferBackend {
+    pub fn new(buffer: Arc<RingBuffer>) -> Self {
+        Self { buffer }
+    }
+
+    pub fn buffer(&self) -> &Arc<RingBuffer> {
+        &self.buffer
+    }
+}
+impl Backend for RingBufferBackend {
+    fn name(&self) -> &'static str {
+        "ring buffer"
+    }
+
+    fn log(&mut self, _level: Level, line: &str) -> io::Result<()> {
+        self.buffer.push(line);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Creates an empty directory unique to this test run
+    fn temp_dir(name: &str) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = std::env::temp_dir().join(format!(
+            "lumen-logging-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read_lines(path: &Path) -> Vec<String> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn file_rotates_at_size_threshold() {
+        let dir = temp_dir("rotation");
+        let path = dir.join("lumen.log");
+        // Each line is 10 bytes with its newline, so three fit within the limit
+        let mut backend = FileBackend::open(&path, 30, 3).unwrap();
+        for i in 0..10 {
+            backend
+                .log(Level::Info, &format!("record {:02}", i))
+                .unwrap();
+        }
+        backend.flush().unwrap();
+
+        let mut files = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, ["lumen.log", "lumen.log.1", "lumen.log.2"]);
+
+        assert_eq!(read_lines(&path), ["record 09"]);
+        assert_eq!(
+            read_lines(&dir.join("lumen.log.1")),
+            ["record 06", "record 07", "record 08"]
+        );
+        assert_eq!(
+            read_lines(&dir.join("lumen.log.2")),
+            ["record 03", "record 04", "record 05"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_with_one_file_truncates_on_rotation() {
+        let dir = temp_dir("truncate");
+        let path = dir.join("lumen.log");
+        let mut backend = FileBackend::open(&path, 20, 1).unwrap();
+        for line in &["first", "second", "third", "fourth"] {
+            backend.log(Level::Info, line).unwrap();
+        }
+        backend.flush().unwrap();
+
+        assert_eq!(read_lines(&path), ["fourth"]);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_flushes_on_error() {
+        let dir = temp_dir("flush");
+        let path = dir.join("lumen.log");
+        let mut backend = FileBackend::open(&path, 1024, 2).unwrap();
+
+        backend.log(Level::Info, "buffered").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        backend.log(Level::Error, "failed").unwrap();
+        assert_eq!(read_lines(&path), ["buffered", "failed"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ring_buffer_retains_last_records_in_order() {
+        let buffer = RingBuffer::new(3);
+        let mut backend = RingBufferBackend::new(buffer.clone());
+        for i in 0..5 {
+            backend.log(Level::Info, &format!("record {}", i)).unwrap();
+        }
+
+        assert_eq!(buffer.lines(), ["record 2", "record 3", "record 4"]);
+
+        let mut dump = Vec::new();
+        buffer.dump(&mut dump).unwrap();
+        assert_eq!(
+            String::from_utf8(dump).unwrap(),
+            "record 2\nrecord 3\nrecord 4\n"
+        );
+    }
+}
@@ -5,11 +5,15 @@ use std::mem::transmute;
 
 use liblumen_alloc::erts::apply::DynamicCallee;
 use liblumen_alloc::erts::process::ffi::ProcessSignal;
-use liblumen_alloc::erts::process::{Frame, Native};
-use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::{Arity, ModuleFunctionArity};
 
-pub use lumen_rt_core::process::{current_process, monitor, replace_log_exit, set_log_exit, spawn};
+use firefly_rt_core::process::{Frame, Native};
+use firefly_rt_core::term::prelude::*;
+
+pub use firefly_rt_core::process::{
+    current_process, exit_signal, group_leader_signal, handle_signals, monitor, propagate_exit,
+    replace_log_exit, set_log_exit, spawn, unlink,
+};
 
 #[no_mangle]
 pub unsafe extern "C-unwind" fn __lumen_panic(term: Term) {
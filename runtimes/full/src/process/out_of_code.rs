@@ -1,8 +1,7 @@
-use liblumen_alloc::erts::process::{Frame, Native};
-use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::{Arity, ModuleFunctionArity};
 
-use lumen_rt_core::process::current_process;
+use firefly_rt_core::process::{current_process, Frame, Native};
+use firefly_rt_core::term::prelude::*;
 
 pub fn frame() -> Frame {
     Frame::new(module_function_arity(), Native::Zero(native))
@@ -2,25 +2,35 @@ use std::any::Any;
 use std::convert::TryInto;
 use std::ffi::c_void;
 use std::fmt::{self, Debug};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use liblumen_core::locks::RwLock;
 
 use liblumen_alloc::borrow::clone_to_process::CloneToProcess;
-use liblumen_alloc::erts::process::{Frame, FrameWithArguments, Native, Priority, Process, Status};
 pub use liblumen_alloc::erts::scheduler::{id, ID};
-use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::{Arity, ModuleFunctionArity, Ran};
 
-use lumen_rt_core::process::spawn::options::Options;
-use lumen_rt_core::process::{log_exit, propagate_exit, CURRENT_PROCESS};
-use lumen_rt_core::registry::put_pid_to_process;
-pub use lumen_rt_core::scheduler::{
-    current, from_id, run_through, Scheduled, SchedulerDependentAlloc, Spawned,
+use firefly_rt_core::process::spawn::options::Options;
+use firefly_rt_core::process::spawn::SpawnError;
+use firefly_rt_core::process::{
+    handle_signals, log_exit, propagate_exit, Frame, FrameWithArguments, Native, Priority, Process,
+    Status, CURRENT_PROCESS,
 };
-use lumen_rt_core::scheduler::{run_queue, unregister, Run, Scheduler as SchedulerTrait};
-use lumen_rt_core::timer::Hierarchy;
+use firefly_rt_core::registry::{
+    put_pid_to_process, remove_pid_to_process, reserve_pid, PidReservation,
+};
+use firefly_rt_core::scheduler::counter::ChunkedCounter;
+pub use firefly_rt_core::scheduler::{
+    all, busy_time, current, from_id, run_through, stats, Scheduled, SchedulerDependentAlloc,
+    SchedulerStats, Spawned, PRIORITIES,
+};
+use firefly_rt_core::scheduler::{
+    busy, run_queue, unpark, unregister, Counters, Run, Scheduler as SchedulerTrait,
+};
+use firefly_rt_core::term::prelude::*;
+use firefly_rt_core::timer::Hierarchy;
 
 use crate::process::out_of_code;
 
@@ -33,14 +43,50 @@ extern "C-unwind" {
     fn apply_3(module: Term, function: Term, arguments: Term) -> Term;
 }
 
+/// Whether the system halts with a crash dump when an init process exits abnormally.
+///
+/// Only the runtime's `main` sets it, as tests spawn an init process as the parent of the
+/// processes in each test, which is free to exit.
+static HALT_ON_INIT_EXIT: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether the system halts with a crash dump when the process spawned by `spawn_init`
+/// exits abnormally, as BEAM does, since the system can't run without it
+pub fn set_halt_on_init_exit(halt: bool) {
+    HALT_ON_INIT_EXIT.store(halt, Ordering::SeqCst);
+}
+
+/// How often the main loop logs `log_stats` when debug logging is enabled, as it is with `-v`
+pub const STATS_LOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Logs the statistics of every scheduler at debug level
+pub fn log_stats() {
+    for stats in stats() {
+        log::debug!(
+            "scheduler {}: {} queued, run queue lengths (max, high, normal, low) {:?}, {} runs this second, {} reductions, {} context switches, {} steals ({} processes), {} parks, {} unparks",
+            stats.id,
+            stats.run_queues_len,
+            stats.run_queue_lengths,
+            stats.runs_this_second,
+            stats.reductions,
+            stats.context_switches,
+            stats.steals,
+            stats.stolen,
+            stats.parks,
+            stats.unparks
+        );
+    }
+}
+
 #[export_name = "lumen_rt_scheduler_unregistered"]
-fn unregistered() -> Arc<dyn lumen_rt_core::scheduler::Scheduler> {
+fn unregistered() -> Arc<dyn firefly_rt_core::scheduler::Scheduler> {
     Arc::new(Scheduler {
         id: id::next(),
         hierarchy: Default::default(),
-        reference_count: AtomicU64::new(0),
+        reference_count: ChunkedCounter::new(),
         run_queues: Default::default(),
-        unique_integer: AtomicU64::new(0),
+        unique_integer: ChunkedCounter::new(),
+        counters: Default::default(),
+        init: Default::default(),
     })
 }
 
@@ -48,11 +94,14 @@ pub struct Scheduler {
     pub id: ID,
     pub hierarchy: RwLock<Hierarchy>,
     // References are always 64-bits even on 32-bit platforms
-    reference_count: AtomicU64,
+    reference_count: ChunkedCounter,
     run_queues: RwLock<run_queue::Queues>,
     // Non-monotonic unique integers are scoped to the scheduler ID and then use this per-scheduler
     // `u64`.
-    unique_integer: AtomicU64,
+    unique_integer: ChunkedCounter,
+    counters: Counters,
+    // The process spawned by `spawn_init` on this scheduler
+    init: RwLock<Option<Pid>>,
 }
 
 impl Scheduler {
@@ -103,12 +152,12 @@ impl Scheduler {
         frame.with_arguments(false, &[process_closure, process_arguments])
     }
 
-    fn spawn_module_function_arguments_frame_with_arguments(
+    pub(crate) fn spawn_module_function_arguments_frame_with_arguments(
         process: &Process,
         module: Atom,
         function: Atom,
         arguments: Vec<Term>,
-    ) -> FrameWithArguments {
+    ) -> Result<FrameWithArguments, SpawnError> {
         let module_function_arity = ModuleFunctionArity {
             module: Atom::from_str("erlang"),
             function: Atom::from_str("apply"),
@@ -118,18 +167,18 @@ impl Scheduler {
         let native = unsafe { Native::from_ptr(apply_3 as *const c_void, 3) };
         let frame = Frame::new(module_function_arity, native);
 
-        let process_module = module.encode().unwrap();
-        let process_function = function.encode().unwrap();
+        let process_module = module.encode().map_err(|_| SpawnError::SystemLimit)?;
+        let process_function = function.encode().map_err(|_| SpawnError::SystemLimit)?;
         let process_argument_vec: Vec<Term> = arguments
             .iter()
             .map(|arguments| arguments.clone_to_process(process))
             .collect();
         let process_arguments = process.list_from_slice(&process_argument_vec);
 
-        frame.with_arguments(
+        Ok(frame.with_arguments(
             false,
             &[process_module, process_function, process_arguments],
-        )
+        ))
     }
 }
 
@@ -170,11 +219,15 @@ impl SchedulerTrait for Scheduler {
     }
 
     fn next_reference_number(&self) -> ReferenceNumber {
-        self.reference_count.fetch_add(1, Ordering::SeqCst)
+        self.reference_count.next()
+    }
+
+    fn counters(&self) -> &Counters {
+        &self.counters
     }
 
     fn next_unique_integer(&self) -> u64 {
-        self.unique_integer.fetch_add(1, Ordering::SeqCst)
+        self.unique_integer.next()
     }
 
     fn run_once(&self) -> bool {
@@ -190,12 +243,30 @@ impl SchedulerTrait for Scheduler {
                     CURRENT_PROCESS
                         .with(|current_process| current_process.replace(Some(arc_process.clone())));
 
+                    // Signals from other processes take effect before the process runs, which
+                    // may exit it
+                    handle_signals(&arc_process);
+
                     // Don't allow exiting processes to run again.
                     //
                     // Without this check, a process.exit() from outside the process during WAITING
                     // will return to the Frame that called `process.wait()`
-                    if !arc_process.is_exiting() {
-                        arc_process.run();
+                    if arc_process.is_suspended() && !arc_process.is_exiting() {
+                        // A suspended process is only dequeued to handle its signals, and is
+                        // parked by `requeue` until it is resumed
+                    } else if !arc_process.is_exiting() {
+                        // Collections requested by `erlang:garbage_collect/1` from other processes
+                        // can only be done here, while no native holds references into the heap
+                        if arc_process.is_full_sweep_requested() {
+                            if let Err(gc_err) = arc_process.full_sweep() {
+                                panic!("garbage collection of {} failed: {}", arc_process, gc_err);
+                            }
+                        }
+
+                        let reductions_before = arc_process.reductions();
+                        busy(|| arc_process.run());
+                        self.counters
+                            .ran(arc_process.reductions() - reductions_before);
                     } else {
                         arc_process.reduce();
                     }
@@ -212,10 +283,19 @@ impl SchedulerTrait for Scheduler {
                             }
                             Status::RuntimeException(ref exception) => {
                                 log_exit(&exiting_arc_process, exception);
+                                // Before its exit is propagated, so its links are in the dump
+                                if HALT_ON_INIT_EXIT.load(Ordering::SeqCst)
+                                    && Some(exiting_arc_process.pid()) == *self.init.read()
+                                {
+                                    crate::sys::dump::init_exited(exception.reason());
+                                }
                                 propagate_exit(&exiting_arc_process, Some(exception));
                             }
                             _ => unreachable!(),
                         }
+
+                        // Only once its exit has been propagated can a new process take its room
+                        remove_pid_to_process(&exiting_arc_process);
                     }
 
                     CURRENT_PROCESS.with(|current_process| current_process.replace(None));
@@ -223,7 +303,9 @@ impl SchedulerTrait for Scheduler {
                     break true;
                 }
                 Run::Delayed => continue,
-                Run::Waiting => break true,
+                // Waiting processes are woken by messages or their timers, until which there is
+                // nothing to run
+                Run::Waiting => break false,
                 // TODO steal processes or sleep if nothing to steal
                 Run::None => break false,
             }
@@ -238,7 +320,7 @@ impl SchedulerTrait for Scheduler {
         self.run_queues.read().len()
     }
 
-    fn schedule(&self, process: Process) -> Arc<Process> {
+    fn schedule(&self, process: Process, reservation: PidReservation) -> Arc<Process> {
         debug_assert_ne!(
             Some(self.id),
             process.scheduler_id(),
@@ -250,8 +332,9 @@ impl SchedulerTrait for Scheduler {
 
         let arc_process = Arc::new(process);
 
+        // In the process table before it can run, so it can't exit before it is there to remove
+        put_pid_to_process(&arc_process, reservation);
         self.run_queues.write().enqueue(arc_process.clone());
-        put_pid_to_process(&arc_process);
 
         arc_process
     }
@@ -279,6 +362,7 @@ impl SchedulerTrait for Scheduler {
             vec![],
             options,
         )?;
+        *self.init.write() = Some(arc_process.pid());
 
         Ok(arc_process)
     }
@@ -288,7 +372,10 @@ impl SchedulerTrait for Scheduler {
         parent: Option<&Process>,
         closure: Boxed<Closure>,
         options: Options,
-    ) -> anyhow::Result<Spawned> {
+    ) -> Result<Spawned, SpawnError> {
+        options.validate(parent)?;
+        let reservation = reserve_pid()?;
+
         let (heap, heap_size) = options.sized_heap()?;
         let priority = options.cascaded_priority(parent);
         let initial_module_function_arity = closure.module_function_arity();
@@ -299,16 +386,24 @@ impl SchedulerTrait for Scheduler {
             heap,
             heap_size,
         );
+        options.configure(&process);
 
         let frame_with_arguments = Self::spawn_closure_frame_with_arguments(&process, closure);
         Self::runnable(&process, frame_with_arguments);
 
-        let connection = options.connect(parent, &process);
+        // Resolve where the process will run before connecting it, so that a failure doesn't leave
+        // the parent linked to, or monitoring, a process which never runs
+        let scheduler = match parent {
+            Some(parent) => Some(parent.scheduler().ok_or(SpawnError::ParentExiting)?),
+            None => None,
+        };
+        let connection = options.connect(parent, &process)?;
 
-        let arc_process = match parent {
-            Some(parent) => parent.scheduler().unwrap().schedule(process),
-            None => self.schedule(process),
+        let arc_process = match scheduler {
+            Some(scheduler) => scheduler.schedule(process, reservation),
+            None => self.schedule(process, reservation),
         };
+        connection.propagate_parent_exit(parent, &arc_process);
 
         Ok(Spawned {
             arc_process,
@@ -323,7 +418,10 @@ impl SchedulerTrait for Scheduler {
         function: Atom,
         arguments: Vec<Term>,
         options: Options,
-    ) -> anyhow::Result<Spawned> {
+    ) -> Result<Spawned, SpawnError> {
+        options.validate(parent)?;
+        let reservation = reserve_pid()?;
+
         let (heap, heap_size) = options.sized_heap()?;
         let priority = options.cascaded_priority(parent);
         let initial_module_function_arity = ModuleFunctionArity {
@@ -338,18 +436,26 @@ impl SchedulerTrait for Scheduler {
             heap,
             heap_size,
         );
+        options.configure(&process);
 
         let frame_with_arguments = Self::spawn_module_function_arguments_frame_with_arguments(
             &process, module, function, arguments,
-        );
+        )?;
         Self::runnable(&process, frame_with_arguments);
 
-        let connection = options.connect(parent, &process);
+        // Resolve where the process will run before connecting it, so that a failure doesn't leave
+        // the parent linked to, or monitoring, a process which never runs
+        let scheduler = match parent {
+            Some(parent) => Some(parent.scheduler().ok_or(SpawnError::ParentExiting)?),
+            None => None,
+        };
+        let connection = options.connect(parent, &process)?;
 
-        let arc_process = match parent {
-            Some(parent) => parent.scheduler().unwrap().schedule(process),
-            None => self.schedule(process),
+        let arc_process = match scheduler {
+            Some(scheduler) => scheduler.schedule(process, reservation),
+            None => self.schedule(process, reservation),
         };
+        connection.propagate_parent_exit(parent, &arc_process);
 
         Ok(Spawned {
             arc_process,
@@ -360,5 +466,17 @@ impl SchedulerTrait for Scheduler {
     fn stop_waiting(&self, process: &Process) {
         process.stop_waiting();
         self.run_queues.write().stop_waiting(process);
+        // The process may have been made runnable by another thread while this one is idle
+        unpark(&self.id);
+    }
+
+    fn resume(&self, process: &Process) {
+        self.run_queues.write().resume(process);
+        // The process may have been made runnable by another thread while this one is idle
+        unpark(&self.id);
+    }
+
+    fn set_priority(&self, process: &Process, priority: Priority) -> Priority {
+        self.run_queues.write().set_priority(process, priority)
     }
 }
@@ -0,0 +1,61 @@
+use std::sync::{Arc, RwLock};
+
+/// A setting which can be replaced while the system runs, such as when the configuration is
+/// reloaded on SIGHUP.
+///
+/// Readers get a snapshot of the whole value, so they never observe a value that is partly old
+/// and partly new, and holding on to a snapshot does not block the value from being replaced.
+pub struct Setting<T>(RwLock<Arc<T>>);
+impl<T> Setting<T> {
+    pub fn new(value: T) -> Self {
+        Self(RwLock::new(Arc::new(value)))
+    }
+
+    /// Returns a snapshot of the current value
+    pub fn load(&self) -> Arc<T> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Replaces the value, returning the one it replaced
+    pub fn store(&self, value: T) -> Arc<T> {
+        std::mem::replace(&mut *self.0.write().unwrap(), Arc::new(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn snapshots_are_unaffected_by_store() {
+        let setting = Setting::new(1);
+        let snapshot = setting.load();
+
+        assert_eq!(*setting.store(2), 1);
+        assert_eq!(*snapshot, 1);
+        assert_eq!(*setting.load(), 2);
+    }
+
+    #[test]
+    fn readers_only_observe_whole_values() {
+        let setting = Setting::new((0, 0));
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for i in 1..1000 {
+                    setting.store((i, i));
+                }
+            });
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        let value = setting.load();
+                        assert_eq!(value.0, value.1);
+                    }
+                });
+            }
+        });
+    }
+}
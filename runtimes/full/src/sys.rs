@@ -1,4 +1,5 @@
 pub mod break_handler;
+pub mod dump;
 pub mod host;
 pub mod io;
 pub mod random;
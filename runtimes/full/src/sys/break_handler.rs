@@ -1,3 +1,5 @@
+pub mod menu;
+
 cfg_if::cfg_if! {
   if #[cfg(target_arch = "wasm32")] {
      mod wasm32;
@@ -30,7 +32,7 @@ pub enum Signal {
 impl Signal {
     pub fn should_terminate(&self) -> bool {
         match self {
-            Self::TERM | Self::QUIT | Self::HUP | Self::ABRT => true,
+            Self::TERM | Self::QUIT | Self::ABRT => true,
             _ => false,
         }
     }
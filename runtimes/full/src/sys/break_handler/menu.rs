@@ -0,0 +1,306 @@
+//! The interactive break menu, opened by SIGINT when enabled, as in BEAM.
+//!
+//! The first interrupt pauses scheduling and opens the menu; a second interrupt while the
+//! menu is still open aborts the system immediately.
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use firefly_rt_core::process::{dump_state, kill};
+use firefly_rt_core::registry::pid_to_process;
+use firefly_rt_core::term::prelude::Pid;
+
+const MENU: &str = "\nBREAK: (a)bort (c)ontinue (p)roc info (k)ill (q)uit\n";
+
+/// What to do in response to an interrupt
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interrupt {
+    /// Scheduling has been paused, and the menu should be opened
+    OpenMenu,
+    /// The menu was already open, so the system must abort
+    Abort,
+}
+
+/// The command the break menu was closed with
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BreakOutcome {
+    /// Scheduling has resumed
+    Continue,
+    /// The system must abort immediately
+    Abort,
+    /// Scheduling has resumed, and the system should shut down
+    Quit,
+}
+
+/// Coordinates pausing scheduling while the break menu is open.
+///
+/// Schedulers only park between process runs, in `park_while_paused`, at which point they
+/// hold no process or heap locks. A pause therefore never waits on a scheduler that is in
+/// the middle of running a process or collecting its heap; such a scheduler simply parks once
+/// it has finished.
+#[derive(Default)]
+pub struct BreakHandler {
+    menu_open: AtomicBool,
+    paused: Mutex<bool>,
+    resumed: Condvar,
+    quit_requested: AtomicBool,
+}
+impl BreakHandler {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Handles an interrupt, pausing scheduling if this is the first
+    pub fn interrupt(&self) -> Interrupt {
+        if self.menu_open.swap(true, Ordering::SeqCst) {
+            return Interrupt::Abort;
+        }
+        *self.paused.lock().unwrap() = true;
+        Interrupt::OpenMenu
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.lock().unwrap()
+    }
+
+    /// Parks the calling scheduler while scheduling is paused, for at most `timeout`, so that
+    /// the caller can continue to observe signals.
+    ///
+    /// Must only be called between process runs.
+    pub fn park_while_paused(&self, timeout: Duration) {
+        let paused = self.paused.lock().unwrap();
+        if *paused {
+            let _ = self
+                .resumed
+                .wait_timeout_while(paused, timeout, |paused| *paused);
+        }
+    }
+
+    /// Returns true once if the menu was closed with `(q)uit`
+    pub fn take_quit_request(&self) -> bool {
+        self.quit_requested.swap(false, Ordering::SeqCst)
+    }
+
+    fn resume(&self) {
+        *self.paused.lock().unwrap() = false;
+        self.menu_open.store(false, Ordering::SeqCst);
+        self.resumed.notify_all();
+    }
+
+    /// Opens the menu on the controlling terminal in a new thread
+    pub fn spawn_menu(self: &Arc<Self>) {
+        let handler = self.clone();
+        thread::spawn(move || {
+            let stdin = io::stdin();
+            let mut reader = stdin.lock();
+            let mut writer = io::stderr();
+            match handler.run_menu(&mut reader, &mut writer) {
+                Ok(BreakOutcome::Abort) => std::process::abort(),
+                Ok(_) => (),
+                // Without a terminal there is nothing to interact with, so carry on
+                Err(_) => handler.resume(),
+            }
+        });
+    }
+
+    /// Runs the menu until a command closes it, reading commands from `reader` and writing
+    /// output to `writer`.
+    ///
+    /// Scheduling is resumed before returning, unless the outcome is `Abort`.
+    pub fn run_menu<R: BufRead, W: Write>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> io::Result<BreakOutcome> {
+        loop {
+            writer.write_all(MENU.as_bytes())?;
+            writer.flush()?;
+
+            let mut line = String::new();
+            // End of input is treated as `(c)ontinue`
+            if reader.read_line(&mut line)? == 0 {
+                self.resume();
+                return Ok(BreakOutcome::Continue);
+            }
+
+            match line.trim().chars().next() {
+                Some('c') => {
+                    self.resume();
+                    return Ok(BreakOutcome::Continue);
+                }
+                Some('a') => return Ok(BreakOutcome::Abort),
+                Some('q') => {
+                    self.quit_requested.store(true, Ordering::SeqCst);
+                    self.resume();
+                    return Ok(BreakOutcome::Quit);
+                }
+                Some('p') => dump_state(writer)?,
+                Some('k') => self.kill_interactively(reader, writer)?,
+                None => (),
+                Some(_) => writeln!(writer, "Eh?")?,
+            }
+        }
+    }
+
+    fn kill_interactively<R: BufRead, W: Write>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        write!(writer, "Pid: ")?;
+        writer.flush()?;
+
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let input = line.trim();
+
+        match parse_pid(input).and_then(|pid| pid_to_process(&pid)) {
+            Some(process) => {
+                kill(&process);
+                writeln!(writer, "Killed {}", process)
+            }
+            None => writeln!(writer, "No such process: {}", input),
+        }
+    }
+}
+
+/// Parses a local pid written as `<0.N.S>` or `0.N.S`
+fn parse_pid(input: &str) -> Option<Pid> {
+    let input = input.strip_prefix('<').unwrap_or(input);
+    let input = input.strip_suffix('>').unwrap_or(input);
+
+    let mut parts = input.split('.');
+    let node = parts.next()?;
+    let number = parts.next()?.parse().ok()?;
+    let serial = parts.next()?.parse().ok()?;
+    if node != "0" || parts.next().is_some() {
+        return None;
+    }
+
+    Pid::new(number, serial).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+    use std::sync::atomic::AtomicUsize;
+
+    use liblumen_alloc::erts::process::alloc::{default_heap_size, heap};
+    use liblumen_alloc::ModuleFunctionArity;
+
+    use firefly_rt_core::process::{Priority, Process};
+    use firefly_rt_core::registry::{put_pid_to_process, reserve_pid};
+    use firefly_rt_core::term::prelude::Atom;
+
+    fn run_menu(handler: &BreakHandler, input: &str) -> (BreakOutcome, String) {
+        let mut reader = Cursor::new(input.as_bytes().to_vec());
+        let mut writer = Vec::new();
+        let outcome = handler.run_menu(&mut reader, &mut writer).unwrap();
+
+        (outcome, String::from_utf8(writer).unwrap())
+    }
+
+    #[test]
+    fn continue_resumes_scheduling() {
+        let handler = BreakHandler::new();
+        let progress = Arc::new(AtomicUsize::new(0));
+        let done = Arc::new(AtomicBool::new(false));
+
+        // Stands in for a scheduler thread, which parks between process runs
+        let scheduler = {
+            let handler = handler.clone();
+            let progress = progress.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                while !done.load(Ordering::SeqCst) {
+                    handler.park_while_paused(Duration::from_millis(10));
+                    if !handler.is_paused() {
+                        progress.fetch_add(1, Ordering::SeqCst);
+                    }
+                    thread::yield_now();
+                }
+            })
+        };
+
+        assert_eq!(handler.interrupt(), Interrupt::OpenMenu);
+        assert!(handler.is_paused());
+        let paused_at = progress.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(50));
+        assert!(progress.load(Ordering::SeqCst) <= paused_at + 1);
+
+        let (outcome, output) = run_menu(&handler, "c\n");
+        assert_eq!(outcome, BreakOutcome::Continue);
+        assert!(output.contains("BREAK:"));
+        assert!(!handler.is_paused());
+
+        let resumed_at = progress.load(Ordering::SeqCst);
+        while progress.load(Ordering::SeqCst) <= resumed_at {
+            thread::yield_now();
+        }
+
+        done.store(true, Ordering::SeqCst);
+        scheduler.join().unwrap();
+    }
+
+    #[test]
+    fn kill_terminates_the_named_pid() {
+        let heap_size = default_heap_size();
+        let process = Process::new(
+            Priority::Normal,
+            None,
+            ModuleFunctionArity {
+                module: Atom::from_str("break_menu_test"),
+                function: Atom::from_str("loop"),
+                arity: 0,
+            },
+            heap(heap_size).unwrap(),
+            heap_size,
+        );
+        let process = Arc::new(process);
+        put_pid_to_process(&process, reserve_pid().unwrap());
+        let pid = process.pid();
+
+        let handler = BreakHandler::new();
+        assert_eq!(handler.interrupt(), Interrupt::OpenMenu);
+        let input = format!("k\n<0.{}.{}>\nc\n", pid.number(), pid.serial());
+        let (outcome, output) = run_menu(&handler, &input);
+
+        assert_eq!(outcome, BreakOutcome::Continue);
+        assert!(output.contains("Killed"));
+        assert!(process.is_exiting());
+    }
+
+    #[test]
+    fn kill_of_unknown_pid_is_reported() {
+        let handler = BreakHandler::new();
+        handler.interrupt();
+        let (outcome, output) = run_menu(&handler, "k\nnot a pid\nq\n");
+
+        assert_eq!(outcome, BreakOutcome::Quit);
+        assert!(output.contains("No such process: not a pid"));
+        assert!(handler.take_quit_request());
+        assert!(!handler.take_quit_request());
+    }
+
+    #[test]
+    fn double_interrupt_aborts() {
+        let handler = BreakHandler::new();
+
+        assert_eq!(handler.interrupt(), Interrupt::OpenMenu);
+        assert_eq!(handler.interrupt(), Interrupt::Abort);
+    }
+
+    #[test]
+    fn abort_leaves_scheduling_paused() {
+        let handler = BreakHandler::new();
+        handler.interrupt();
+        let (outcome, _) = run_menu(&handler, "x\na\n");
+
+        assert_eq!(outcome, BreakOutcome::Abort);
+        assert!(handler.is_paused());
+    }
+}
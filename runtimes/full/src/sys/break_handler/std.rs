@@ -2,6 +2,10 @@ use std::thread;
 
 use bus::Bus;
 
+use firefly_rt_core::scheduler;
+
+use crate::sys::dump;
+
 use super::Signal;
 
 impl From<usize> for Signal {
@@ -41,7 +45,14 @@ pub fn init(mut bus: Bus<Signal>) {
         for signal in signals.forever() {
             match Signal::from(signal as usize) {
                 Signal::Unknown => (),
-                sig => bus.broadcast(sig),
+                // The dump only reads the state of the schedulers, so it is written from here,
+                // rather than by a scheduler, which would stop running processes to write it
+                Signal::USR1 => dump::write_signal_dump(),
+                sig => {
+                    bus.broadcast(sig);
+                    // Idle schedulers only check for signals once they wake
+                    scheduler::unpark_all();
+                }
             }
         }
     });
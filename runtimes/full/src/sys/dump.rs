@@ -0,0 +1,305 @@
+//! Post-mortem dumps of the system state, in a subset of the BEAM `erl_crash.dump` format.
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::panic;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use firefly_rt_core::process::{dump_crash_state, dump_state, write_bounded_term};
+use firefly_rt_core::scheduler::dump_run_queues;
+use firefly_rt_core::term::prelude::Term;
+
+use crate::logging::{self, RingBuffer};
+
+/// The environment variable naming the file crash dumps are written to, like BEAM's
+/// `ERL_CRASH_DUMP`
+pub const CRASH_DUMP_ENV: &str = "FIREFLY_CRASH_DUMP";
+/// The file crash dumps are written to when `FIREFLY_CRASH_DUMP` isn't set, relative to the
+/// working directory
+pub const DEFAULT_CRASH_DUMP: &str = "erl_crash.dump";
+
+/// Set once a crash dump file has been written, so that only the first cause of the system going
+/// down is dumped
+static CRASH_DUMPED: AtomicBool = AtomicBool::new(false);
+
+/// Writes the state dump to stderr, as BEAM does on `SIGUSR1`, but without writing a crash dump
+/// file or stopping the system.
+///
+/// The dump is formatted before any of it is written, so that the schedulers aren't held up by
+/// stderr.
+pub fn write_signal_dump() {
+    let ring = logging::ring_buffer();
+    let mut dump = Vec::new();
+    let _ = write_state_dump(&mut dump, ring.as_deref());
+    let _ = io::stderr().write_all(&dump);
+}
+
+/// Writes the run queues of every scheduler and the state of every process, followed by the most
+/// recent log records when a ring buffer backend is configured
+pub fn write_state_dump(writer: &mut dyn Write, ring: Option<&RingBuffer>) -> io::Result<()> {
+    dump_run_queues(writer)?;
+    dump_state(writer)?;
+    write_log(writer, ring)
+}
+
+/// Writes a crash dump with the given slogan, i.e. the reason the system is going down.
+///
+/// Unlike the state dump, each process includes its links, monitors, and the start of its
+/// message queue and stack.
+pub fn write_crash_dump(
+    writer: &mut dyn Write,
+    slogan: &str,
+    ring: Option<&RingBuffer>,
+) -> io::Result<()> {
+    writeln!(writer, "=erl_crash_dump:0.5")?;
+    writeln!(writer, "Slogan: {}", slogan)?;
+    writeln!(
+        writer,
+        "System version: {} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    dump_run_queues(writer)?;
+    dump_crash_state(writer)?;
+    write_log(writer, ring)
+}
+
+fn write_log(writer: &mut dyn Write, ring: Option<&RingBuffer>) -> io::Result<()> {
+    if let Some(ring) = ring {
+        writeln!(writer, "=log")?;
+        ring.dump(writer)?;
+    }
+    writer.flush()
+}
+
+/// The file crash dumps are written to: `FIREFLY_CRASH_DUMP` if set, otherwise
+/// `DEFAULT_CRASH_DUMP`
+pub fn crash_dump_path() -> PathBuf {
+    env::var_os(CRASH_DUMP_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CRASH_DUMP))
+}
+
+/// Writes a crash dump with the given slogan to the file at `crash_dump_path`, reporting where to
+/// stderr.
+///
+/// Only the first crash dump is written, as the panics of other schedulers while the system goes
+/// down would otherwise replace it with one that doesn't show the original cause.
+pub fn write_crash_dump_file(slogan: &str) {
+    if CRASH_DUMPED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let path = crash_dump_path();
+    let ring = logging::ring_buffer();
+    let written = File::create(&path)
+        .and_then(|file| write_crash_dump(&mut BufWriter::new(file), slogan, ring.as_deref()));
+
+    match written {
+        Ok(()) => eprintln!("Crash dump was written to: {}", path.display()),
+        Err(err) => eprintln!(
+            "Crash dump could not be written to {}: {}",
+            path.display(),
+            err
+        ),
+    }
+}
+
+/// Installs a panic hook that writes a crash dump file once the previous hook, by default the one
+/// printing the panic message, has reported the panic.
+///
+/// The hook runs on the panicking thread before it unwinds, so any locks it holds are still held,
+/// which is why the crash dump doesn't wait for locks on processes.
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+        write_crash_dump_file(&info.to_string());
+    }));
+}
+
+/// Writes a crash dump for the init process exiting with `reason`, and halts, as the system can't
+/// run without init
+pub fn init_exited(reason: Term) -> ! {
+    let mut rendered = Vec::new();
+    let _ = write_bounded_term(&mut rendered, reason);
+    let slogan = format!("Init terminating ({})", String::from_utf8_lossy(&rendered));
+
+    log::error!("{}", slogan);
+    write_crash_dump_file(&slogan);
+    log::logger().flush();
+
+    std::process::exit(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::process::{Command, ExitStatus, Stdio};
+
+    use log::Level;
+
+    use liblumen_alloc::erts::process::alloc::default_heap_size;
+
+    use firefly_rt_core::process::kill;
+    use firefly_rt_core::scheduler::Scheduler;
+
+    use crate::logging::{Backend, RingBufferBackend};
+    use crate::scheduler;
+
+    /// Set to the name of the test in the subprocess it runs the crashing half of itself in
+    const CHILD_ENV: &str = "FIREFLY_CRASH_DUMP_TEST_CHILD";
+
+    #[test]
+    fn crash_dump_includes_ring_buffer_contents() {
+        let buffer = RingBuffer::new(2);
+        let mut backend = RingBufferBackend::new(buffer.clone());
+        for line in &["dropped", "kept 1", "kept 2"] {
+            backend.log(Level::Info, line).unwrap();
+        }
+
+        let mut dump = Vec::new();
+        write_crash_dump(&mut dump, "simulated crash", Some(&buffer)).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+
+        assert!(dump.starts_with("=erl_crash_dump:0.5\nSlogan: simulated crash\n"));
+        assert!(dump.ends_with("=log\nkept 1\nkept 2\n"));
+        assert!(!dump.contains("dropped"));
+    }
+
+    #[test]
+    fn state_dump_includes_run_queues_of_every_scheduler() {
+        let id = scheduler::current().id();
+
+        let mut dump = Vec::new();
+        write_state_dump(&mut dump, None).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+
+        assert!(dump.contains(&format!(
+            "=scheduler:{}\nRun Queue Max Length: 0\nRun Queue High Length: 0\n",
+            id
+        )));
+    }
+
+    #[test]
+    fn state_dump_without_ring_buffer_has_no_log_section() {
+        let mut dump = Vec::new();
+        write_state_dump(&mut dump, None).unwrap();
+
+        assert!(!String::from_utf8(dump).unwrap().contains("=log"));
+    }
+
+    #[test]
+    fn init_crash_writes_crash_dump_file_and_halts() {
+        let name = "init_crash_writes_crash_dump_file_and_halts";
+        if is_child(name) {
+            scheduler::set_halt_on_init_exit(true);
+            let init = scheduler::current()
+                .spawn_init(default_heap_size())
+                .unwrap();
+            kill(&init);
+            scheduler::current().run_once();
+
+            panic!("the system did not halt when init exited");
+        }
+
+        let (status, sections) = crash_in_subprocess(name);
+
+        assert_eq!(status.code(), Some(1));
+        assert_eq!(sections[0].0, "erl_crash_dump:0.5");
+        assert_eq!(sections[0].1[0], "Slogan: Init terminating (killed)");
+        assert!(sections[0].1[1].starts_with("System version: "));
+        assert!(section(&sections, "memory")[0].starts_with("processes: "));
+        let atom_count: usize = section(&sections, "index_table:atom_tab")[0]
+            .strip_prefix("entries: ")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(0 < atom_count);
+
+        let (_, init) = sections
+            .iter()
+            .filter(|(tag, _)| tag.starts_with("proc:"))
+            .find(|(_, lines)| lines.iter().any(|line| line == "Spawned as: init:start/0"))
+            .expect("init is not in the crash dump");
+        assert!(init.iter().any(|line| line == "State: Exiting"));
+        assert!(init.iter().any(|line| line == "Link list: []"));
+    }
+
+    #[test]
+    fn panic_hook_writes_crash_dump_file() {
+        let name = "panic_hook_writes_crash_dump_file";
+        if is_child(name) {
+            install_panic_hook();
+
+            panic!("simulated scheduler panic");
+        }
+
+        let (status, sections) = crash_in_subprocess(name);
+
+        assert!(!status.success());
+        assert_eq!(sections[0].0, "erl_crash_dump:0.5");
+        assert!(sections[0].1[0].starts_with("Slogan: panicked at "));
+        assert!(sections[0].1[0].contains("simulated scheduler panic"));
+        assert!(sections.iter().any(|(tag, _)| tag == "memory"));
+    }
+
+    fn is_child(name: &str) -> bool {
+        env::var(CHILD_ENV).map_or(false, |child| child == name)
+    }
+
+    /// Runs the test `name` again in a subprocess, where `is_child` is true so that it crashes,
+    /// returning how the subprocess exited and the sections of the crash dump it wrote
+    fn crash_in_subprocess(name: &str) -> (ExitStatus, Vec<(String, Vec<String>)>) {
+        let path = env::temp_dir().join(format!("{}-{}.dump", name, std::process::id()));
+        // Without the crate name, as the test harness names tests
+        let module_path = module_path!().splitn(2, "::").nth(1).unwrap();
+        let status = Command::new(env::current_exe().unwrap())
+            .args(&[
+                "--exact",
+                &format!("{}::{}", module_path, name),
+                "--test-threads=1",
+                "--nocapture",
+            ])
+            .env(CHILD_ENV, name)
+            .env(CRASH_DUMP_ENV, &path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        let dump = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        (status, parse(&dump))
+    }
+
+    /// Splits `dump` into its sections, each of which starts with a `=tag` line
+    fn parse(dump: &str) -> Vec<(String, Vec<String>)> {
+        let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+
+        for line in dump.lines() {
+            match line.strip_prefix('=') {
+                Some(tag) => sections.push((tag.to_string(), Vec::new())),
+                None => sections
+                    .last_mut()
+                    .expect("crash dump doesn't start with a section")
+                    .1
+                    .push(line.to_string()),
+            }
+        }
+
+        sections
+    }
+
+    fn section<'a>(sections: &'a [(String, Vec<String>)], tag: &str) -> &'a [String] {
+        &sections
+            .iter()
+            .find(|(section_tag, _)| section_tag == tag)
+            .unwrap_or_else(|| panic!("no {} section", tag))
+            .1
+    }
+}
@@ -4,7 +4,7 @@ use std::ffi::CStr;
 #[cfg(not(target_arch = "wasm32"))]
 use libc;
 
-pub use lumen_rt_core::sys::io::puts;
+pub use firefly_rt_core::sys::io::{counters, puts, record_input, record_output};
 
 #[allow(dead_code)]
 #[no_mangle]
@@ -1,8 +1,9 @@
-use liblumen_alloc::erts::process::{Frame, Native, Process};
-use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::ModuleFunctionArity;
 use liblumen_alloc::Arity;
 
+use firefly_rt_core::process::{Frame, Native, Process};
+use firefly_rt_core::term::prelude::*;
+
 use crate::process::current_process;
 
 pub const NATIVE: Native = Native::Zero(native);
@@ -6,8 +6,8 @@ use std::panic;
 
 use liblumen_alloc::erts::term::prelude::*;
 
-use lumen_rt_core::process::current_process;
-use lumen_rt_core::registry;
+use firefly_rt_core::process::current_process;
+use firefly_rt_core::registry;
 
 #[export_name = "erlang:!/2"]
 pub extern "C-unwind" fn builtin_send(to_term: Term, msg: Term) -> Term {
@@ -5,7 +5,8 @@ use cfg_if::cfg_if;
 use stackmaps::{FrameInfo, StackMap};
 
 use liblumen_alloc::erts::term::prelude::{Boxed, Encoded, Term};
-use lumen_rt_core::process::current_process;
+
+use firefly_rt_core::process::current_process;
 
 cfg_if! {
     if #[cfg(all(unix, target_arch = "x86_64"))] {
@@ -53,8 +54,16 @@ pub unsafe extern "C-unwind" fn builtin_gc_run(
 ) -> bool {
     let iter = RootsIter::new(StackMap::get(), return_address, base_pointer);
     let roots = iter.collect::<Vec<_>>();
-    match current_process().garbage_collect(1, roots) {
-        Ok(_) => true,
+    let process = current_process();
+    match process.garbage_collect(1, roots) {
+        Ok(_) => {
+            // Killed for exceeding its `max_heap_size`, so the scheduler never resumes it
+            if process.is_exiting() {
+                crate::scheduler::process_yield();
+            }
+
+            true
+        }
         Err(err) => panic!("garbage collection failed: {}", err),
     }
 }
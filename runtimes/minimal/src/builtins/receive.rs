@@ -1,14 +1,20 @@
 use std::convert::TryInto;
 use std::sync::Arc;
 
+use anyhow::anyhow;
+
+use liblumen_alloc::erts::exception::badarg;
 use liblumen_alloc::erts::message::Message;
+use liblumen_alloc::erts::process::ffi::ErlangResult;
+use liblumen_alloc::erts::process::trace::Trace;
 use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
-use liblumen_alloc::erts::timeout::{ReceiveTimeout, Timeout};
+use liblumen_alloc::erts::time::Monotonic;
+use liblumen_alloc::erts::timeout::{InvalidTimeoutError, ReceiveTimeout, Timeout};
 
-use lumen_rt_core::process::current_process;
-use lumen_rt_core::time::monotonic;
-use lumen_rt_core::timer::{self, SourceEvent};
+use firefly_rt_core::process::current_process;
+use firefly_rt_core::time::monotonic;
+use firefly_rt_core::timer::{self, SourceEvent};
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -130,11 +136,7 @@ impl ReceiveContext {
 /// NOTE: As of this writing, `peek` is implemented via generated code that directly accesses the message data.
 #[export_name = "__lumen_builtin_receive_start"]
 pub extern "C-unwind" fn builtin_receive_start(timeout: Term) -> ReceiveContext {
-    let to = match timeout.decode().unwrap() {
-        TypedTerm::Atom(atom) if atom == "infinity" => Timeout::Infinity,
-        TypedTerm::SmallInteger(si) => Timeout::from_millis(si).expect("invalid timeout value"),
-        _ => unreachable!("should never get non-atom/non-integer receive timeout"),
-    };
+    let to = timeout_from_term(timeout).expect("invalid timeout value");
     let p = current_process();
     ReceiveContext::new(p.clone(), to)
 }
@@ -181,14 +183,17 @@ pub extern "C-unwind" fn builtin_receive_next(context: &mut ReceiveContext) -> R
 /// but its storage is left as-is, i.e. messages allocated in heap fragments remain in their
 /// fragment until a garbage collection is performed. Since a GC cycle fixes up any pointers
 /// contained in roots or the heap, we don't need to concern ourselves with where the terms
-/// live at this stage.
+/// live at this stage. Fragments owned by an off heap message queue are attached to the process
+/// here, so that it is the next collection which copies the received data on to the heap.
 #[export_name = "__lumen_builtin_receive_pop"]
 pub extern "C-unwind" fn builtin_receive_pop(context: &mut ReceiveContext) {
     let p = current_process();
     let mbox_lock = p.mailbox.lock();
     let mut mbox = mbox_lock.borrow_mut();
     // Remove the message at the current cursor
-    mbox.remove(context.message);
+    if let Some(fragment) = mbox.remove(context.message) {
+        p.attach_fragment(unsafe { &mut *fragment.as_ptr() });
+    }
     // Reset the cursor state in the receive context
     context.message = core::ptr::null();
 }
@@ -200,3 +205,400 @@ pub extern "C-unwind" fn builtin_receive_pop(context: &mut ReceiveContext) {
 pub extern "C-unwind" fn builtin_receive_done(context: &mut ReceiveContext) {
     context.cancel_timer();
 }
+
+/// This function is called by a receive once it has looked at every message in the mailbox
+/// without finding one that matches, and waits for either another message to arrive, in which
+/// case it returns `false`, or for `timeout` to pass, in which case it returns `true`.
+///
+/// `timeout` is the atom `infinity`, which never passes, or the milliseconds to wait, from 0,
+/// which only checks for a message without waiting, up to `Timeout::MAX_MILLISECONDS`. Anything
+/// else is a `badarg`.
+///
+/// The timeout is from when this is called, so a receive which has to wait again after a
+/// message which doesn't match waits for the whole timeout again.
+#[export_name = "erlang:recv_wait_timeout/1"]
+pub extern "C-unwind" fn recv_wait_timeout_1(timeout: Term) -> ErlangResult {
+    let arc_process = current_process();
+
+    let timeout = match timeout_from_term(timeout) {
+        Ok(timeout) => timeout,
+        Err(error) => {
+            let exception = badarg(Trace::capture(), Some(anyhow!(error).into()));
+
+            return ErlangResult::error(arc_process.raise(exception));
+        }
+    };
+
+    // The receive has looked at every message already in the mailbox
+    let seen = arc_process.mailbox.lock().borrow().len();
+    let mut context = ReceiveContext::new(arc_process.clone(), timeout);
+
+    loop {
+        match wait_or_time_out(&arc_process, seen, context.timeout, monotonic::time()) {
+            Wait::Message => {
+                context.cancel_timer();
+
+                return ErlangResult::ok(false.into());
+            }
+            Wait::TimedOut => {
+                context.cancel_timer();
+
+                return ErlangResult::ok(true.into());
+            }
+            // Woken by a message, or the timer, so check which
+            Wait::Waiting => unsafe {
+                crate::scheduler::process_yield();
+            },
+        }
+    }
+}
+
+/// The result of `erlang:recv_peek_message/0`, which is `true` and the first message the receive
+/// hasn't looked at, or `false` and `Term::NONE` if it has looked at every message
+#[derive(Debug)]
+#[repr(C)]
+pub struct PeekResult {
+    pub available: Term,
+    pub message: Term,
+}
+
+/// This function is called by a receive to get the next message to match against its patterns,
+/// which is the oldest message on entry, unless `erlang:recv_set/1` started it from a marker.
+///
+/// Each message looked at costs a reduction.
+#[export_name = "erlang:recv_peek_message/0"]
+pub extern "C-unwind" fn recv_peek_message_0() -> PeekResult {
+    peek_message(&current_process())
+}
+
+/// This function is called by a receive when the message from `erlang:recv_peek_message/0`
+/// doesn't match any of its patterns, so that it peeks at the message after it next.
+#[export_name = "erlang:recv_next/0"]
+pub extern "C-unwind" fn recv_next_0() {
+    current_process().mailbox.lock().borrow_mut().next();
+}
+
+/// This function is called by a receive when the message from `erlang:recv_peek_message/0`
+/// matches one of its patterns, to remove it from the mailbox. The next receive starts from the
+/// oldest message again.
+#[export_name = "erlang:remove_message/0"]
+pub extern "C-unwind" fn remove_message_0() {
+    remove_message(&current_process());
+}
+
+/// This function is called after creating a reference which a later receive only matches
+/// messages containing. It records the position of the mailbox, so that the receive can skip the
+/// messages before it, which can't contain the reference.
+///
+/// Markers are kept for intermediate receives, until used or `MAX_MARKERS` newer ones are created.
+#[export_name = "erlang:recv_mark/1"]
+pub extern "C-unwind" fn recv_mark_1(reference: Term) {
+    if let Some(number) = reference_number(reference) {
+        current_process().mailbox.lock().borrow_mut().mark(number);
+    }
+}
+
+/// This function is called on entry to a receive which only matches messages containing the
+/// reference, so that it starts from the marker recorded by `erlang:recv_mark/1`, if it was.
+#[export_name = "erlang:recv_set/1"]
+pub extern "C-unwind" fn recv_set_1(reference: Term) {
+    if let Some(number) = reference_number(reference) {
+        current_process()
+            .mailbox
+            .lock()
+            .borrow_mut()
+            .set_save_to_marker(number);
+    }
+}
+
+/// Removes and returns the oldest message in the mailbox of `arc_process`, the current process,
+/// for which `matches` is `true`, waiting for one to arrive if there is none, as a receive without
+/// an `after` does.
+///
+/// This is how natives, such as the `io` functions, wait for replies to their requests.
+pub(crate) fn receive<F>(arc_process: &Arc<Process>, mut matches: F) -> Term
+where
+    F: FnMut(Term) -> bool,
+{
+    loop {
+        let peeked = peek_message(arc_process);
+
+        if peeked.message == Term::NONE {
+            let seen = arc_process.mailbox.lock().borrow().len();
+            let timeout = ReceiveTimeout::new(monotonic::time(), Timeout::Infinity);
+
+            while wait_or_time_out(arc_process, seen, timeout, monotonic::time()) == Wait::Waiting {
+                unsafe {
+                    crate::scheduler::process_yield();
+                }
+            }
+        } else if matches(peeked.message) {
+            remove_message(arc_process);
+
+            return peeked.message;
+        } else {
+            arc_process.mailbox.lock().borrow_mut().next();
+        }
+    }
+}
+
+fn peek_message(process: &Process) -> PeekResult {
+    process.reduce();
+
+    let mailbox_guard = process.mailbox.lock();
+    let mailbox = mailbox_guard.borrow();
+
+    match mailbox.peek() {
+        Some(message) => PeekResult {
+            available: true.into(),
+            message: message.data(),
+        },
+        None => PeekResult {
+            available: false.into(),
+            message: Term::NONE,
+        },
+    }
+}
+
+fn remove_message(process: &Process) {
+    let mailbox_guard = process.mailbox.lock();
+    let mut mailbox = mailbox_guard.borrow_mut();
+
+    if let Some(fragment) = mailbox.remove_peeked() {
+        process.attach_fragment(unsafe { &mut *fragment.as_ptr() });
+    }
+}
+
+fn reference_number(reference: Term) -> Option<ReferenceNumber> {
+    let reference: Result<Boxed<Reference>, _> = reference.try_into();
+
+    reference.ok().map(|reference| reference.number())
+}
+
+/// Decodes the timeout of a receive, which is either `infinity` or the milliseconds to wait, up
+/// to `Timeout::MAX_MILLISECONDS`
+fn timeout_from_term(timeout: Term) -> Result<Timeout, InvalidTimeoutError> {
+    match timeout.decode() {
+        Ok(TypedTerm::Atom(atom)) if atom == "infinity" => Ok(Timeout::Infinity),
+        Ok(TypedTerm::SmallInteger(small_integer)) => Timeout::from_millis(small_integer),
+        _ => Err(InvalidTimeoutError),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Wait {
+    /// A message arrived after the receive saw the mailbox
+    Message,
+    /// No message arrived before the timeout passed
+    TimedOut,
+    /// Neither, so the process is now waiting
+    Waiting,
+}
+
+/// Checks whether a message arrived since the receive saw `seen` messages in the mailbox of
+/// `process`, or the receive timed out as of `now`, and if neither, puts `process` in the waiting
+/// status.
+///
+/// The mailbox stays locked from the check until the process is waiting. As senders push to the
+/// mailbox before they stop the process waiting, a message either arrives before the check, or
+/// stops the wait, and can't be lost in between, leaving the process waiting with a message to
+/// receive.
+fn wait_or_time_out(
+    process: &Process,
+    seen: usize,
+    timeout: ReceiveTimeout,
+    now: Monotonic,
+) -> Wait {
+    let mailbox_guard = process.mailbox.lock();
+    let mut mailbox = mailbox_guard.borrow_mut();
+
+    if seen < mailbox.len() {
+        Wait::Message
+    } else if timeout.is_timed_out(now) {
+        // The receive ends without a message
+        mailbox.reset_save();
+
+        Wait::TimedOut
+    } else {
+        process.wait();
+
+        Wait::Waiting
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use liblumen_alloc::atom;
+    use liblumen_alloc::erts::process::{alloc, Status};
+    use liblumen_alloc::erts::time::Milliseconds;
+    use liblumen_alloc::erts::ModuleFunctionArity;
+
+    #[test]
+    fn after_0_times_out_without_waiting_when_mailbox_is_empty() {
+        let process = process();
+        let now = Monotonic::from_millis(100u64);
+        let timeout = timeout_from_term(process.integer(0)).unwrap();
+
+        assert_eq!(timeout, Timeout::Immediate);
+        assert_eq!(
+            wait_or_time_out(&process, 0, ReceiveTimeout::new(now, timeout), now),
+            Wait::TimedOut
+        );
+        assert!(!is_waiting(&process));
+    }
+
+    #[test]
+    fn after_infinity_waits_until_a_message_arrives() {
+        let process = process();
+        let now = Monotonic::from_millis(100u64);
+        let timeout = ReceiveTimeout::new(now, timeout_from_term(atom!("infinity")).unwrap());
+
+        assert_eq!(wait_or_time_out(&process, 0, timeout, now), Wait::Waiting);
+        assert!(is_waiting(&process));
+
+        // As sending does
+        process.send_from_other(atom!("message"));
+        assert!(process.stop_waiting());
+
+        // However long it waited
+        let later = Monotonic::from_millis(u64::MAX - 1);
+        assert_eq!(wait_or_time_out(&process, 0, timeout, later), Wait::Message);
+    }
+
+    #[test]
+    fn message_arriving_before_the_wait_stops_it_waiting() {
+        let process = process();
+        let now = Monotonic::from_millis(100u64);
+        let timeout = ReceiveTimeout::new(now, Timeout::Infinity);
+        let seen = process.mailbox.lock().borrow().len();
+
+        // Between the receive looking at the mailbox and waiting
+        process.send_from_other(atom!("message"));
+
+        assert_eq!(
+            wait_or_time_out(&process, seen, timeout, now),
+            Wait::Message
+        );
+        assert!(!is_waiting(&process));
+    }
+
+    #[test]
+    fn negative_float_and_too_long_timeouts_are_invalid() {
+        let process = process();
+
+        assert!(timeout_from_term(process.integer(-1)).is_err());
+        assert!(timeout_from_term(process.float(1.0)).is_err());
+        assert!(timeout_from_term(atom!("never")).is_err());
+        assert!(timeout_from_term(process.integer(Timeout::MAX_MILLISECONDS + 1)).is_err());
+
+        assert_eq!(
+            timeout_from_term(process.integer(Timeout::MAX_MILLISECONDS)).unwrap(),
+            Timeout::Duration(Milliseconds(Timeout::MAX_MILLISECONDS))
+        );
+    }
+
+    #[test]
+    fn marked_receive_only_looks_at_messages_after_the_reference_was_created() {
+        let unmarked = process();
+        let marked = process();
+
+        for process in [&unmarked, &marked] {
+            for _ in 0..10_000 {
+                process.send_from_other(atom!("noise"));
+            }
+        }
+
+        // As `Ref = make_ref(), Pid ! {self(), Ref, Request}`, replied to by `Pid`
+        let reference = 1;
+        marked.mailbox.lock().borrow_mut().mark(reference);
+        for process in [&unmarked, &marked] {
+            let reply = process.tuple_from_slice(&[process.reference(reference), atom!("reply")]);
+            process.send_from_other(reply);
+        }
+
+        // As `receive {Ref, Reply} -> Reply end`
+        assert!(marked
+            .mailbox
+            .lock()
+            .borrow_mut()
+            .set_save_to_marker(reference));
+        assert_eq!(receive_reply(&marked, reference), 1);
+        assert_eq!(receive_reply(&unmarked, reference), 10_001);
+
+        for process in [&unmarked, &marked] {
+            assert_eq!(process.mailbox.lock().borrow().len(), 10_000);
+        }
+    }
+
+    #[test]
+    fn marker_survives_intermediate_receives() {
+        let process = process();
+        process.send_from_other(atom!("before"));
+        process.send_from_other(atom!("received"));
+
+        let reference = 1;
+        process.mailbox.lock().borrow_mut().mark(reference);
+        process.send_from_other(atom!("after"));
+
+        // Receives the message the marker is at
+        let mailbox_guard = process.mailbox.lock();
+        let mut mailbox = mailbox_guard.borrow_mut();
+        mailbox.next();
+        assert_eq!(mailbox.peek().unwrap().data(), atom!("received"));
+        assert!(mailbox.remove_peeked().is_none());
+
+        // The receive of the reply still skips the message before
+        assert!(mailbox.set_save_to_marker(reference));
+        assert_eq!(mailbox.peek().unwrap().data(), atom!("after"));
+
+        // Markers are only used once
+        mailbox.reset_save();
+        assert!(!mailbox.set_save_to_marker(reference));
+        assert_eq!(mailbox.peek().unwrap().data(), atom!("before"));
+    }
+
+    /// Receives `{Reference, _}` as a compiled receive does, returning the reductions it took
+    fn receive_reply(process: &Process, reference: ReferenceNumber) -> u64 {
+        let before = process.reductions();
+
+        loop {
+            let peeked = peek_message(process);
+            assert_ne!(peeked.message, Term::NONE, "no reply was received");
+
+            let tuple: Result<Boxed<Tuple>, _> = peeked.message.try_into();
+            let is_reply = match tuple {
+                Ok(tuple) => reference_number(tuple.get_element(0).unwrap()) == Some(reference),
+                Err(_) => false,
+            };
+            if is_reply {
+                remove_message(process);
+
+                return process.reductions() - before;
+            }
+
+            process.mailbox.lock().borrow_mut().next();
+        }
+    }
+
+    fn is_waiting(process: &Process) -> bool {
+        *process.status.read() == Status::Waiting
+    }
+
+    fn process() -> Process {
+        let (heap, heap_size) = alloc::default_heap().unwrap();
+
+        Process::new(
+            Default::default(),
+            None,
+            ModuleFunctionArity {
+                module: Atom::from_str("test"),
+                function: Atom::from_str("receive"),
+                arity: 0,
+            },
+            heap,
+            heap_size,
+        )
+    }
+}
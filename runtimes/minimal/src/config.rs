@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
 use std::fs;
 use std::io;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::path::Path;
 
 use clap::{App, AppSettings, Arg, SubCommand};
@@ -51,6 +52,15 @@ pub struct Config {
     pub debug: bool,
     pub name: Option<String>,
     pub cookie: Option<String>,
+    /// The number of reductions a process runs before it yields to the scheduler
+    pub reductions: Option<NonZeroU32>,
+    /// The number of scheduler threads, which defaults to the number of logical CPUs available
+    pub schedulers: Option<NonZeroUsize>,
+    /// Whether each scheduler thread is bound to a distinct logical CPU, as with BEAM's `+sbt db`
+    pub bind_schedulers: bool,
+    /// The number of process stacks each scheduler preallocates in a fixed pool, beyond which
+    /// spawning fails with `system_limit`, or `None` to allocate stacks as processes are spawned
+    pub process_stacks: Option<NonZeroUsize>,
     pub command: Command,
     pub extra: Vec<String>,
 }
@@ -92,6 +102,31 @@ impl Config {
                      .help("The secret cookie to use in distributed mode")
                      .takes_value(true)
                      .env("COOKIE"))
+            .arg(Arg::with_name("reductions")
+                     .long("reductions")
+                     .help("The number of reductions a process runs before yielding to the scheduler")
+                     .takes_value(true)
+                     .env("LUMEN_REDUCTIONS")
+                     .validator(is_valid_reductions))
+            .arg(Arg::with_name("schedulers")
+                     .long("schedulers")
+                     .help("The number of scheduler threads, defaulting to the number of logical CPUs available")
+                     .takes_value(true)
+                     .env("LUMEN_SCHEDULERS")
+                     .validator(is_valid_schedulers))
+            .arg(Arg::with_name("scheduler_bind_type")
+                     .long("scheduler-bind-type")
+                     .help("Whether scheduler threads are unbound (u) or each bound to a distinct logical CPU (db), also given as +sbt")
+                     .takes_value(true)
+                     .possible_values(&["u", "db"])
+                     .default_value("u")
+                     .env("LUMEN_SCHEDULER_BIND_TYPE"))
+            .arg(Arg::with_name("process_stacks")
+                     .long("process-stacks")
+                     .help("The number of process stacks each scheduler preallocates in a fixed pool, by default stacks are allocated as processes are spawned")
+                     .takes_value(true)
+                     .env("LUMEN_PROCESS_STACKS")
+                     .validator(is_valid_process_stacks))
             .arg(Arg::with_name("extra")
                      .last(true)
                      .multiple(true)
@@ -104,7 +139,7 @@ impl Config {
                             .help("Connects a remote shell to the specified host")
                             .takes_value(true)
                             .validator(is_valid_node_name)))
-            .get_matches_from(argv);
+            .get_matches_from(emulator_flags_to_long(argv));
 
         let command: Command;
         let extra: Vec<&str>;
@@ -132,6 +167,13 @@ impl Config {
             debug: matches.is_present("debug"),
             name: matches.value_of("name").map(|v| v.to_string()),
             cookie: matches.value_of("cookie").map(|v| v.to_string()),
+            reductions: matches.value_of("reductions").map(|v| v.parse().unwrap()),
+            schedulers: matches.value_of("schedulers").map(|v| v.parse().unwrap()),
+            // Has a default which is one of the possible values
+            bind_schedulers: matches.value_of("scheduler_bind_type").unwrap() == "db",
+            process_stacks: matches
+                .value_of("process_stacks")
+                .map(|v| v.parse().unwrap()),
             command,
             extra: extra.iter().map(|v| v.to_string()).collect(),
         })
@@ -143,6 +185,48 @@ fn is_valid_node_name(_f: String) -> Result<(), String> {
     Ok(())
 }
 
+fn is_valid_reductions(reductions: String) -> Result<(), String> {
+    match reductions.parse::<NonZeroU32>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("{} is not a positive integer", reductions)),
+    }
+}
+
+fn is_valid_schedulers(schedulers: String) -> Result<(), String> {
+    match schedulers.parse::<NonZeroUsize>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("{} is not a positive integer", schedulers)),
+    }
+}
+
+fn is_valid_process_stacks(process_stacks: String) -> Result<(), String> {
+    match process_stacks.parse::<NonZeroUsize>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("{} is not a positive integer", process_stacks)),
+    }
+}
+
+/// Rewrites BEAM's `+Flag` emulator flags, which `clap` can't parse, as the equivalent long options,
+/// leaving the arguments after `--` as they are
+fn emulator_flags_to_long(argv: Vec<String>) -> Vec<String> {
+    let mut rewritten = Vec::with_capacity(argv.len());
+    let mut args = argv.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "+sbt" => rewritten.push("--scheduler-bind-type".to_string()),
+            "--" => {
+                rewritten.push(arg);
+                rewritten.extend(args);
+                break;
+            }
+            _ => rewritten.push(arg),
+        }
+    }
+
+    rewritten
+}
+
 fn with_file<T>(v: Option<&OsStr>, default: T, fun: fn(String) -> T) -> ConfigResult<T> {
     match v {
         None => Ok(default),
@@ -163,3 +247,51 @@ fn load_app_config(_contents: String) -> AppConfig {
 fn load_boot_script(_contents: String) -> Option<BootScript> {
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_args(args: &[&str]) -> Config {
+        let argv = std::iter::once("lumen")
+            .chain(args.iter().copied())
+            .map(str::to_string)
+            .collect();
+
+        Config::from_argv("lumen".to_string(), "0.1.0".to_string(), argv).unwrap()
+    }
+
+    #[test]
+    fn schedulers_are_unbound_by_default() {
+        assert!(!from_args(&[]).bind_schedulers);
+        assert!(!from_args(&["+sbt", "u"]).bind_schedulers);
+    }
+
+    #[test]
+    fn schedulers_are_bound_with_default_bind_type() {
+        assert!(from_args(&["+sbt", "db"]).bind_schedulers);
+        assert!(from_args(&["--scheduler-bind-type", "db"]).bind_schedulers);
+    }
+
+    #[test]
+    fn process_stacks_are_allocated_dynamically_by_default() {
+        assert_eq!(from_args(&[]).process_stacks, None);
+        assert_eq!(
+            from_args(&["--process-stacks", "64"]).process_stacks,
+            NonZeroUsize::new(64)
+        );
+    }
+
+    #[test]
+    fn emulator_flags_after_double_dash_are_not_rewritten() {
+        assert_eq!(
+            emulator_flags_to_long(
+                ["lumen", "+sbt", "db", "--", "+sbt"]
+                    .iter()
+                    .map(|arg| arg.to_string())
+                    .collect()
+            ),
+            ["lumen", "--scheduler-bind-type", "db", "--", "+sbt"]
+        );
+    }
+}
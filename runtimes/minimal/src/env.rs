@@ -5,7 +5,7 @@ use std::lazy::SyncOnceCell;
 use liblumen_alloc::erts::process::{Process, ProcessFlags};
 use liblumen_alloc::erts::term::prelude::*;
 
-use lumen_rt_core::process::current_process;
+use firefly_rt_core::process::current_process;
 
 static ARGV: SyncOnceCell<Vec<String>> = SyncOnceCell::new();
 static ARGV_TERM: SyncOnceCell<Vec<BinaryLiteral>> = SyncOnceCell::new();
@@ -21,18 +21,26 @@ pub mod process;
 pub mod scheduler;
 pub mod sys;
 
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
 use liblumen_alloc::erts::process::alloc::default_heap_size;
 
-pub use lumen_rt_core::{
+pub use firefly_rt_core::{
     base, binary_to_string, context, distribution, integer_to_string, proplist, registry, send,
     time, timer,
 };
 
 use anyhow::anyhow;
-use bus::Bus;
+use bus::{Bus, BusReader};
 use log::Level;
 
+use firefly_rt_core::scheduler::{Scheduler, MAX_IDLE_SLEEP};
+
 use self::config::Config;
+use self::sys::affinity;
 use self::sys::break_handler::{self, Signal};
 
 #[liblumen_core::entry]
@@ -47,17 +55,35 @@ fn main() -> i32 {
 fn main_internal(name: &str, version: &str, argv: Vec<String>) -> anyhow::Result<()> {
     self::env::init_argv_from_slice(std::env::args_os()).unwrap();
     // Load system configuration
-    let _config = match Config::from_argv(name.to_string(), version.to_string(), argv) {
+    let config = match Config::from_argv(name.to_string(), version.to_string(), argv) {
         Ok(config) => config,
         Err(err) => {
             return Err(anyhow!(err));
         }
     };
+    if let Some(budget) = config.reductions {
+        scheduler::set_reduction_budget(budget);
+    }
+    if let Some(process_stacks) = config.process_stacks {
+        scheduler::set_process_stacks(process_stacks);
+    }
+
+    let cpus = sys::cpus::logical_ids();
+    let schedulers = config
+        .schedulers
+        .unwrap_or_else(|| NonZeroUsize::new(cpus.len()).unwrap_or(NonZeroUsize::new(1).unwrap()));
+    // The CPUs the schedulers are bound to, in the order of the schedulers
+    let bind_cpus = if config.bind_schedulers {
+        Some(Arc::new(cpus))
+    } else {
+        None
+    };
 
     // This bus is used to receive signals across threads in the system
     let mut bus: Bus<break_handler::Signal> = Bus::new(1);
-    // Each thread needs a reader
-    let mut rx1 = bus.add_rx();
+    // Each scheduler thread needs a reader
+    let mut receivers: Vec<BusReader<Signal>> =
+        (0..schedulers.get()).map(|_| bus.add_rx()).collect();
     // Initialize the break handler with the bus, which will broadcast on it
     break_handler::init(bus);
 
@@ -65,13 +91,65 @@ fn main_internal(name: &str, version: &str, argv: Vec<String>) -> anyhow::Result
     let level_filter = Level::Info.to_level_filter();
     logging::init(level_filter).expect("Unexpected failure initializing logger");
 
+    let main_rx = receivers.remove(0);
     let scheduler = scheduler::current();
+    if let Some(cpus) = &bind_cpus {
+        affinity::bind(&affinity::Platform, scheduler.counters(), 0, cpus);
+    }
     scheduler.spawn_init(default_heap_size()).unwrap();
+
+    // The other schedulers start once init exists, so they don't see an empty system and stop,
+    // and then steal processes from this scheduler as init spawns them
+    let mut threads = Vec::with_capacity(receivers.len());
+    for (index, rx) in receivers.into_iter().enumerate() {
+        let bind_cpus = bind_cpus.clone();
+        let thread = thread::Builder::new()
+            .name(format!("scheduler-{}", index + 1))
+            .spawn(move || {
+                let _guard = PanicGuard;
+                let scheduler = scheduler::current();
+                if let Some(cpus) = bind_cpus {
+                    affinity::bind(&affinity::Platform, scheduler.counters(), index + 1, &cpus);
+                }
+                run(scheduler, rx)
+            })?;
+        threads.push(thread);
+    }
+
+    let mut result = run(scheduler, main_rx);
+    for thread in threads {
+        let name = thread.thread().name().unwrap().to_string();
+        let joined = thread
+            .join()
+            .unwrap_or_else(|_| Err(anyhow!("{} panicked", name)));
+        // Report the first error, but still wait for every thread
+        result = result.and(joined);
+    }
+
+    result
+}
+
+/// Set when a scheduler thread panics, so the others stop rather than wait forever for the
+/// processes it was running to exit
+static PANICKED: AtomicBool = AtomicBool::new(false);
+
+struct PanicGuard;
+impl Drop for PanicGuard {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            PANICKED.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Runs `scheduler` on the current thread until no processes remain in any scheduler or it is
+/// signalled to stop
+fn run(scheduler: Arc<dyn Scheduler>, mut rx: BusReader<Signal>) -> anyhow::Result<()> {
     loop {
         // Run the scheduler for a cycle
         let scheduled = scheduler.run_once();
         // Check for system signals, and terminate if needed
-        if let Ok(sig) = rx1.try_recv() {
+        if let Ok(sig) = rx.try_recv() {
             match sig {
                 // For now, SIGINT initiates a controlled shutdown
                 Signal::INT => {
@@ -90,15 +168,27 @@ fn main_internal(name: &str, version: &str, argv: Vec<String>) -> anyhow::Result
                     return Ok(());
                 }
                 // All other signals can be surfaced to other parts of the
-                // system for custom use, e.g. SIGCHLD, SIGALRM, SIGUSR1/2
+                // system for custom use, e.g. SIGCHLD, SIGALRM, SIGUSR2
                 _ => (),
             }
         }
+        if PANICKED.load(Ordering::SeqCst) {
+            break;
+        }
         // If the scheduler scheduled a process this cycle, then we're busy
         // and should keep working until we have an idle period
         if scheduled {
             continue;
         }
+        // If processes remain, they are waiting or running on another scheduler, so sleep until
+        // a timer may wake one, another thread unparks us, or there may be processes to steal
+        if registry::pid_count() > 0 {
+            let timeout = scheduler
+                .idle()
+                .timeout(time::monotonic::time(), MAX_IDLE_SLEEP);
+            scheduler.counters().park(timeout);
+            continue;
+        }
 
         break;
     }
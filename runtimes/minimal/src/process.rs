@@ -4,7 +4,9 @@ use liblumen_alloc::erts::process::ffi::ErlangResult;
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::ModuleFunctionArity;
 
-pub use lumen_rt_core::process::{current_process, monitor, replace_log_exit, set_log_exit, spawn};
+pub use firefly_rt_core::process::{
+    current_process, monitor, replace_log_exit, set_log_exit, spawn,
+};
 
 #[export_name = "lumen_rt_apply_2"]
 pub fn apply_2(function_boxed_closure: Boxed<Closure>, mut arguments: Vec<Term>) -> ErlangResult {
@@ -4,15 +4,24 @@ use std::any::Any;
 use std::ffi::c_void;
 use std::fmt::{self, Debug};
 use std::mem;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::ptr;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use anyhow::anyhow;
 use log::info;
 
 use liblumen_alloc::erts::apply::DynamicCallee;
+use liblumen_alloc::erts::exception::badarg;
+use liblumen_alloc::erts::process::alloc::{
+    DynamicStackAllocator, FixedPoolStackAllocator, StackAllocator,
+};
 use liblumen_alloc::erts::process::ffi::ErlangResult;
-use liblumen_alloc::erts::process::{CalleeSavedRegisters, Priority, Process, Status};
+use liblumen_alloc::erts::process::trace::Trace;
+use liblumen_alloc::erts::process::{
+    CalleeSavedRegisters, Priority, Process, Status, MAX_REDUCTIONS_PER_RUN,
+};
 use liblumen_alloc::erts::scheduler::{id, ID};
 use liblumen_alloc::erts::term::prelude::*;
 use liblumen_alloc::erts::ModuleFunctionArity;
@@ -21,15 +30,25 @@ use liblumen_core::locks::RwLock;
 use liblumen_core::util::thread_local::ThreadLocalCell;
 use liblumen_term::TermKind;
 
-use lumen_rt_core::process::spawn::options::Options;
-use lumen_rt_core::process::{log_exit, propagate_exit, CURRENT_PROCESS};
-use lumen_rt_core::registry::put_pid_to_process;
-use lumen_rt_core::scheduler::Scheduler as SchedulerTrait;
-use lumen_rt_core::scheduler::{self, run_queue, unregister, Run};
-pub use lumen_rt_core::scheduler::{
+use firefly_rt_core::process::spawn::options::Options;
+use firefly_rt_core::process::spawn::SpawnError;
+use firefly_rt_core::process::{
+    current_process, handle_signals, log_exit, propagate_exit, CURRENT_PROCESS,
+};
+use firefly_rt_core::registry::{
+    put_atom_to_process, put_pid_to_process, remove_pid_to_process, reserve_pid, PidReservation,
+};
+use firefly_rt_core::scheduler::counter::ChunkedCounter;
+use firefly_rt_core::scheduler::Scheduler as SchedulerTrait;
+use firefly_rt_core::scheduler::{
+    self, run_queue, unpark, unregister, Counters, Run, SchedulerStats,
+};
+pub use firefly_rt_core::scheduler::{
     current, from_id, run_through, Scheduled, SchedulerDependentAlloc, Spawned,
 };
-use lumen_rt_core::timer::Hierarchy;
+use firefly_rt_core::timer::Hierarchy;
+
+use crate::sys::io;
 
 // External thread locals owned by the generated code
 extern "C" {
@@ -38,6 +57,38 @@ extern "C" {
     static mut CURRENT_REDUCTION_COUNT: u32;
 }
 
+/// The number of reductions a process runs before it yields to the scheduler
+///
+/// Generated code compares `__lumen_process_reductions` against this, rather than a constant of
+/// its own, so that it can be configured when the runtime starts.
+#[export_name = "__lumen_process_reduction_budget"]
+static REDUCTION_BUDGET: AtomicU32 = AtomicU32::new(MAX_REDUCTIONS_PER_RUN as u32);
+
+/// Returns the number of reductions a process runs before it yields to the scheduler
+pub fn reduction_budget() -> u32 {
+    REDUCTION_BUDGET.load(Ordering::Relaxed)
+}
+
+/// Sets the number of reductions a process runs before it yields to the scheduler
+pub fn set_reduction_budget(budget: NonZeroU32) {
+    REDUCTION_BUDGET.store(budget.get(), Ordering::Relaxed);
+}
+
+/// The number of process stacks each scheduler preallocates, or 0 to allocate them as processes
+/// are spawned
+static PROCESS_STACKS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of process stacks each scheduler preallocates in a fixed pool, if any
+pub fn process_stacks() -> Option<NonZeroUsize> {
+    NonZeroUsize::new(PROCESS_STACKS.load(Ordering::Relaxed))
+}
+
+/// Makes schedulers created from now on preallocate `process_stacks` process stacks in a fixed
+/// pool, rather than allocating them as processes are spawned
+pub fn set_process_stacks(process_stacks: NonZeroUsize) {
+    PROCESS_STACKS.store(process_stacks.get(), Ordering::Relaxed);
+}
+
 // External functions defined in OTP
 extern "C-unwind" {
     #[link_name = "lumen:apply_apply_2/1"]
@@ -65,6 +116,49 @@ pub unsafe extern "C-unwind" fn process_yield() -> bool {
         .process_yield()
 }
 
+#[export_name = "erlang:yield/0"]
+pub unsafe extern "C-unwind" fn yield_0() -> ErlangResult {
+    process_yield();
+    ErlangResult::ok(true.into())
+}
+
+/// Adds `reductions` to those run by the current process, yielding to the scheduler if that
+/// exhausts its budget
+#[export_name = "erlang:bump_reductions/1"]
+pub unsafe extern "C-unwind" fn bump_reductions_1(reductions: Term) -> ErlangResult {
+    // More than fit in the counter exhaust the budget all the same
+    let bump = match reductions.decode() {
+        Ok(TypedTerm::SmallInteger(small_integer)) => {
+            let bump: isize = small_integer.into();
+            if bump < 0 {
+                None
+            } else {
+                Some(u32::try_from(bump).unwrap_or(u32::MAX))
+            }
+        }
+        Ok(TypedTerm::BigInteger(big_integer)) if SmallInteger::from(0u8) < *big_integer => {
+            Some(u32::MAX)
+        }
+        _ => None,
+    };
+
+    match bump {
+        Some(bump) => {
+            CURRENT_REDUCTION_COUNT = CURRENT_REDUCTION_COUNT.saturating_add(bump);
+            if reduction_budget() <= CURRENT_REDUCTION_COUNT {
+                process_yield();
+            }
+
+            ErlangResult::ok(true.into())
+        }
+        None => {
+            let source = anyhow!("reductions ({}) is not a non-negative integer", reductions);
+            let exception = badarg(Trace::capture(), Some(source.into()));
+            ErlangResult::error(current_process().raise(exception))
+        }
+    }
+}
+
 #[export_name = "__lumen_builtin_exit"]
 pub unsafe extern "C-unwind" fn process_exit(result: ErlangResult) {
     let arc_dyn_scheduler = scheduler::current();
@@ -86,6 +180,40 @@ pub unsafe extern "C-unwind" fn process_exit(result: ErlangResult) {
     scheduler.process_yield();
 }
 
+/// Hibernates the current process until it is sent a message, when it restarts with
+/// `apply(module, function, arguments)`, so this never returns unless the arguments are invalid
+///
+/// The stack of the process is discarded, and its heap shrunk to fit what is still live without it.
+#[export_name = "erlang:hibernate/3"]
+pub unsafe extern "C-unwind" fn hibernate(
+    module: Term,
+    function: Term,
+    arguments: Term,
+) -> ErlangResult {
+    let arc_dyn_scheduler = scheduler::current();
+    let scheduler = arc_dyn_scheduler
+        .as_any()
+        .downcast_ref::<Scheduler>()
+        .unwrap();
+    let process: &Process = &scheduler.current;
+
+    let is_proper_list = arguments
+        .decode()
+        .map_or(false, |arguments| arguments.is_proper_list());
+    if !(module.is_atom() && function.is_atom() && is_proper_list) {
+        let source = anyhow!("cannot hibernate to {}:{}({})", module, function, arguments);
+        let exception = badarg(Trace::capture(), Some(source.into()));
+        return ErlangResult::error(process.raise(exception));
+    }
+
+    if let Err(gc_err) = process.hibernate(module, function, arguments) {
+        panic!("garbage collection of {} failed: {}", process, gc_err);
+    }
+    // `swap_process` restarts the process from its continuation, so this never returns
+    scheduler.process_yield();
+    unreachable!("{} resumed the stack it discarded by hibernating", process)
+}
+
 #[export_name = "__lumen_builtin_malloc"]
 pub unsafe extern "C-unwind" fn builtin_malloc(kind: TermKind, arity: usize) -> *mut u8 {
     use liblumen_alloc::erts::term::closure::ClosureLayout;
@@ -106,9 +234,15 @@ pub unsafe extern "C-unwind" fn builtin_malloc(kind: TermKind, arity: usize) ->
         }
     };
 
-    let result = process
-        .alloc_nofrag_layout(layout.clone())
-        .or_else(|_| process.alloc_fragment_layout(layout));
+    let result = process.alloc_nofrag_layout(layout.clone()).or_else(|_| {
+        let fragment = process.alloc_fragment_layout(layout);
+        // Heap fragments count towards `max_heap_size` too, and may be all that grows
+        if process.check_max_heap_size() {
+            s.process_yield();
+        }
+
+        fragment
+    });
 
     match result {
         Ok(nn) => nn.as_ptr() as *mut u8,
@@ -117,7 +251,7 @@ pub unsafe extern "C-unwind" fn builtin_malloc(kind: TermKind, arity: usize) ->
 }
 
 #[export_name = "lumen_rt_scheduler_unregistered"]
-fn unregistered() -> Arc<dyn lumen_rt_core::scheduler::Scheduler> {
+fn unregistered() -> Arc<dyn firefly_rt_core::scheduler::Scheduler> {
     Arc::new(Scheduler::new().unwrap())
 }
 
@@ -125,11 +259,14 @@ pub struct Scheduler {
     pub id: id::ID,
     pub hierarchy: RwLock<Hierarchy>,
     // References are always 64-bits even on 32-bit platforms
-    reference_count: AtomicU64,
+    reference_count: ChunkedCounter,
     run_queues: RwLock<run_queue::Queues>,
     // Non-monotonic unique integers are scoped to the scheduler ID and then use this per-scheduler
     // `u64`.
-    unique_integer: AtomicU64,
+    unique_integer: ChunkedCounter,
+    counters: Counters,
+    // The source of native stacks for processes spawned by this scheduler
+    stack_allocator: Arc<dyn StackAllocator>,
     root: Arc<Process>,
     init: ThreadLocalCell<Arc<Process>>,
     current: ThreadLocalCell<Arc<Process>>,
@@ -140,6 +277,21 @@ unsafe impl Sync for Scheduler {}
 impl Scheduler {
     /// Creates a new scheduler with the default configuration
     fn new() -> anyhow::Result<Scheduler> {
+        let stack_allocator: Arc<dyn StackAllocator> = match process_stacks() {
+            Some(process_stacks) => Arc::new(FixedPoolStackAllocator::new(
+                process_stacks.get(),
+                Process::stack_size_hint(),
+            )?),
+            None => Arc::new(DynamicStackAllocator::new()),
+        };
+
+        Self::with_stack_allocator(stack_allocator)
+    }
+
+    /// Creates a new scheduler which allocates process stacks using `stack_allocator`
+    pub fn with_stack_allocator(
+        stack_allocator: Arc<dyn StackAllocator>,
+    ) -> anyhow::Result<Scheduler> {
         let id = id::next();
 
         // The root process is how the scheduler gets time for itself,
@@ -181,9 +333,11 @@ impl Scheduler {
             root,
             init: ThreadLocalCell::new(init),
             current,
+            stack_allocator,
             hierarchy: Default::default(),
-            reference_count: AtomicU64::new(0),
-            unique_integer: AtomicU64::new(0),
+            reference_count: ChunkedCounter::new(),
+            unique_integer: ChunkedCounter::new(),
+            counters: Default::default(),
         })
     }
 
@@ -200,6 +354,7 @@ impl Debug for Scheduler {
             // The hiearchy slots take a lot of space, so don't print them by default
             .field("reference_count", &self.reference_count)
             .field("run_queues", &self.run_queues)
+            .field("stack_allocator", &self.stack_allocator)
             .finish()
     }
 }
@@ -227,11 +382,15 @@ impl SchedulerTrait for Scheduler {
     }
 
     fn next_reference_number(&self) -> ReferenceNumber {
-        self.reference_count.fetch_add(1, Ordering::SeqCst)
+        self.reference_count.next()
+    }
+
+    fn counters(&self) -> &Counters {
+        &self.counters
     }
 
     fn next_unique_integer(&self) -> u64 {
-        self.unique_integer.fetch_add(1, Ordering::SeqCst)
+        self.unique_integer.next()
     }
 
     fn run_once(&self) -> bool {
@@ -247,7 +406,7 @@ impl SchedulerTrait for Scheduler {
         self.run_queues.read().len()
     }
 
-    fn schedule(&self, process: Process) -> Arc<Process> {
+    fn schedule(&self, process: Process, reservation: PidReservation) -> Arc<Process> {
         debug_assert_ne!(
             Some(self.id),
             process.scheduler_id(),
@@ -259,8 +418,9 @@ impl SchedulerTrait for Scheduler {
 
         let arc_process = Arc::new(process);
 
+        // In the process table before it can run, so it can't exit before it is there to remove
+        put_pid_to_process(&arc_process, reservation);
         self.run_queues.write().enqueue(arc_process.clone());
-        put_pid_to_process(&arc_process);
 
         arc_process
     }
@@ -286,6 +446,10 @@ impl SchedulerTrait for Scheduler {
             self.init.set(arc_process.clone());
         }
 
+        // Everything init spawns inherits its group leader, so their output goes to `user`
+        let Spawned { arc_process: user, .. } = self.spawn_user(&arc_process)?;
+        arc_process.set_group_leader_pid(user.pid());
+
         Ok(arc_process)
     }
 
@@ -295,7 +459,10 @@ impl SchedulerTrait for Scheduler {
         parent: Option<&Process>,
         closure: Boxed<Closure>,
         options: Options,
-    ) -> anyhow::Result<Spawned> {
+    ) -> Result<Spawned, SpawnError> {
+        options.validate(parent)?;
+        let reservation = reserve_pid()?;
+
         let (heap, heap_size) = options.sized_heap()?;
         let priority = options.cascaded_priority(parent);
         let initial_module_function_arity = closure.module_function_arity();
@@ -305,17 +472,26 @@ impl SchedulerTrait for Scheduler {
             initial_module_function_arity,
             heap,
             heap_size,
+            self.stack_allocator.as_ref(),
         )?;
+        options.configure(&process);
 
         let (init_fn, env) = Self::spawn_closure_init_env(&process, closure);
         Self::runnable(&process, init_fn, env);
 
-        let connection = options.connect(parent, &process);
+        // Resolve where the process will run before connecting it, so that a failure doesn't leave
+        // the parent linked to, or monitoring, a process which never runs
+        let scheduler = match parent {
+            Some(parent) => Some(parent.scheduler().ok_or(SpawnError::ParentExiting)?),
+            None => None,
+        };
+        let connection = options.connect(parent, &process)?;
 
-        let arc_process = match parent {
-            Some(parent) => parent.scheduler().unwrap().schedule(process),
-            None => self.schedule(process),
+        let arc_process = match scheduler {
+            Some(scheduler) => scheduler.schedule(process, reservation),
+            None => self.schedule(process, reservation),
         };
+        connection.propagate_parent_exit(parent, &arc_process);
 
         Ok(Spawned {
             arc_process,
@@ -330,7 +506,10 @@ impl SchedulerTrait for Scheduler {
         function: Atom,
         arguments: Vec<Term>,
         options: Options,
-    ) -> anyhow::Result<Spawned> {
+    ) -> Result<Spawned, SpawnError> {
+        options.validate(parent)?;
+        let reservation = reserve_pid()?;
+
         let (heap, heap_size) = options.sized_heap()?;
         let priority = options.cascaded_priority(parent);
 
@@ -345,17 +524,26 @@ impl SchedulerTrait for Scheduler {
             initial_module_function_arity,
             heap,
             heap_size,
+            self.stack_allocator.as_ref(),
         )?;
+        options.configure(&process);
         let (init_fn, env) =
-            Self::spawn_module_function_arguments_init_env(&process, module, function, arguments);
+            Self::spawn_module_function_arguments_init_env(&process, module, function, arguments)?;
         Self::runnable(&process, init_fn, env);
 
-        let connection = options.connect(parent, &process);
+        // Resolve where the process will run before connecting it, so that a failure doesn't leave
+        // the parent linked to, or monitoring, a process which never runs
+        let scheduler = match parent {
+            Some(parent) => Some(parent.scheduler().ok_or(SpawnError::ParentExiting)?),
+            None => None,
+        };
+        let connection = options.connect(parent, &process)?;
 
-        let arc_process = match parent {
-            Some(parent) => parent.scheduler().unwrap().schedule(process),
-            None => self.schedule(process),
+        let arc_process = match scheduler {
+            Some(scheduler) => scheduler.schedule(process, reservation),
+            None => self.schedule(process, reservation),
         };
+        connection.propagate_parent_exit(parent, &arc_process);
 
         Ok(Spawned {
             arc_process,
@@ -376,9 +564,25 @@ impl SchedulerTrait for Scheduler {
         Ok(())
     }
 
+    fn stats(&self) -> SchedulerStats {
+        SchedulerStats::new(self, Some(self.stack_allocator.stats()))
+    }
+
     fn stop_waiting(&self, process: &Process) {
         process.stop_waiting();
         self.run_queues.write().stop_waiting(process);
+        // The process may have been made runnable by another thread while this one is idle
+        unpark(&self.id);
+    }
+
+    fn resume(&self, process: &Process) {
+        self.run_queues.write().resume(process);
+        // The process may have been made runnable by another thread while this one is idle
+        unpark(&self.id);
+    }
+
+    fn set_priority(&self, process: &Process, priority: Priority) -> Priority {
+        self.run_queues.write().set_priority(process, priority)
     }
 }
 
@@ -422,20 +626,28 @@ impl Scheduler {
             match next {
                 Run::Now(process) => {
                     info!("found process to schedule");
+                    // Signals from other processes take effect before the process runs, which
+                    // may exit it
+                    handle_signals(&process);
+
                     // Don't allow exiting processes to run again.
                     //
                     // Without this check, a process.exit() from outside the process during WAITING
                     // will return to code that called `process.wait()`
-                    let requeue_arc_process = if !process.is_exiting() {
+                    let requeue_arc_process = if process.is_suspended() && !process.is_exiting() {
+                        // A suspended process is only dequeued to handle its signals, and is
+                        // parked by `requeue` until it is resumed
+                        info!("process is suspended");
+
+                        process
+                    } else if !process.is_exiting() {
                         info!("swapping into process {:?}", process.pid());
                         // The swap takes care of setting up the to-be-scheduled process
                         // as the current process, and swaps to its stack. The code below
                         // is executed when that process has yielded and we're resetting
                         // the state of the scheduler such that the "current process" is
                         // the scheduler itself
-                        unsafe {
-                            self.swap_process(process);
-                        }
+                        scheduler::busy(|| unsafe { self.swap_process(process) });
 
                         // When we reach here, the process has yielded
                         // back to the scheduler, and is still marked
@@ -447,16 +659,12 @@ impl Scheduler {
 
                         // Increment reduction count if not the root process
                         let prev_reductions = reset_reduction_counter();
-                        prev.total_reductions
-                            .fetch_add(prev_reductions as u64, Ordering::Relaxed);
+                        prev.add_reductions(prev_reductions as u64);
+                        self.counters.ran(prev_reductions);
 
-                        // Change the previous process status to Runnable
-                        {
-                            let mut prev_status = prev.status.write();
-                            if Status::Running == *prev_status {
-                                *prev_status = Status::Runnable
-                            }
-                        }
+                        // Change the previous process status to Runnable, or publish the wait
+                        // it began, now it is no longer running
+                        prev.descheduled();
 
                         prev
                     } else {
@@ -476,16 +684,7 @@ impl Scheduler {
 
                     // If the process is exiting, then handle the exit
                     if let Some(exiting_arc_process) = option_exiting_arc_process {
-                        match *exiting_arc_process.status.read() {
-                            Status::Exited => {
-                                propagate_exit(&exiting_arc_process, None);
-                            }
-                            Status::RuntimeException(ref exception) => {
-                                log_exit(&exiting_arc_process, exception);
-                                propagate_exit(&exiting_arc_process, Some(exception));
-                            }
-                            _ => unreachable!(),
-                        }
+                        Self::exit(&exiting_arc_process);
                     }
 
                     info!("exiting scheduler loop after run");
@@ -498,21 +697,28 @@ impl Scheduler {
                     info!("found process, but it is delayed");
                     continue;
                 }
+                Run::Waiting if self.steal() => {
+                    info!("stole processes to run while others are waiting");
+                    continue;
+                }
                 Run::Waiting => {
                     info!("exiting scheduler loop because waiting");
-                    // Return to main scheduler loop to check for signals and to re-enter from
-                    // `run_once` and increment timeouts to knock out of waiting.
-                    break true;
+                    // Return to main scheduler loop to check for signals and to sleep until a
+                    // timer may knock a process out of waiting, when `run_once` re-enters and
+                    // times it out.
+                    break false;
                 }
                 Run::None if self.current.pid() == self.root.pid() => {
-                    info!("no processes remaining to schedule, exiting loop");
                     // If no processes are available, then the scheduler should steal,
-                    // but if it can't/doesn't, then it must terminate, as there is
-                    // nothing we can swap to. When we break here, we're returning
-                    // to the core scheduler loop, which _must_ terminate, if it does
-                    // not, we'll just end up right back here again.
-                    //
-                    // TODO: stealing
+                    // but if it can't/doesn't, then there is nothing we can swap to.
+                    // When we break here, we're returning to the core scheduler loop,
+                    // which decides whether to sleep or terminate.
+                    if self.steal() {
+                        info!("stole processes to run");
+                        continue;
+                    }
+
+                    info!("no processes remaining to schedule, exiting loop");
                     break false;
                 }
                 Run::None => unreachable!(),
@@ -520,6 +726,58 @@ impl Scheduler {
         }
     }
 
+    /// Propagates the exit of `exiting_process` once `requeue` has taken it out of the run queues
+    ///
+    /// Its stack returns to the stack allocator when the last reference to it is dropped.
+    fn exit(exiting_process: &Process) {
+        match *exiting_process.status.read() {
+            Status::Exited => {
+                propagate_exit(exiting_process, None);
+            }
+            Status::RuntimeException(ref exception) => {
+                log_exit(exiting_process, exception);
+                propagate_exit(exiting_process, Some(exception));
+            }
+            _ => unreachable!(),
+        }
+
+        // Only once its exit has been propagated can a new process take its room
+        remove_pid_to_process(exiting_process);
+    }
+
+    /// Moves half of the normal priority processes of the first other scheduler that has any
+    /// into this scheduler's run queue, returning whether any were stolen.
+    fn steal(&self) -> bool {
+        for arc_dyn_scheduler in scheduler::all() {
+            if arc_dyn_scheduler.id() == self.id {
+                continue;
+            }
+
+            let victim = arc_dyn_scheduler
+                .as_any()
+                .downcast_ref::<Scheduler>()
+                .unwrap();
+            // Release the victim's lock before taking our own, so that two schedulers stealing
+            // from each other can't deadlock
+            let stolen = victim.run_queues.write().steal();
+
+            if !stolen.is_empty() {
+                self.counters.stole(stolen.len());
+                let mut run_queues = self.run_queues.write();
+
+                for arc_process in stolen {
+                    // So that `stop_waiting` and timers find the process in our queues
+                    arc_process.schedule_with(self.id);
+                    run_queues.enqueue(arc_process);
+                }
+
+                return true;
+            }
+        }
+
+        false
+    }
+
     /// This function takes care of coordinating the scheduling of a new
     /// process/descheduling of the current process.
     ///
@@ -532,6 +790,14 @@ impl Scheduler {
     /// at which point execution resumes where the newly scheduled process left
     /// off previously, or in its init function.
     unsafe fn swap_process(&self, new: Arc<Process>) {
+        // A process that hibernated restarts from its continuation on a fresh stack, rather than
+        // returning from the `process_yield` it last called
+        if let Some(continuation) = new.take_hibernation() {
+            let init_fn = mem::transmute::<_, DynamicCallee>(apply_apply_3 as *const c_void);
+            let env = Some(new.list_from_slice(&continuation));
+            Self::init_registers(&new, init_fn, env);
+        }
+
         // Mark the new process as Running
         let new_ctx = &new.registers as *const _;
         {
@@ -543,13 +809,9 @@ impl Scheduler {
         let _ = CURRENT_PROCESS.with(|cp| cp.replace(Some(new.clone())));
         let prev = self.current.replace(new.clone());
 
-        // Change the previous process status to Runnable
-        {
-            let mut prev_status = prev.status.write();
-            if Status::Running == *prev_status {
-                *prev_status = Status::Runnable
-            }
-        }
+        // Change the previous process status to Runnable, or publish the wait it began, now it is
+        // no longer running
+        prev.descheduled();
 
         // Save the previous process registers for the stack swap
         let prev_ctx = &prev.registers as *const _ as *mut _;
@@ -569,10 +831,51 @@ impl Scheduler {
         //
         // When swapping to a previously spawned process, we return to the end
         // of `process_yield`, which is what the process last called before the
-        // scheduler was swapped in.
+        // scheduler was swapped in, unless it hibernated, as above.
         swap_stack(prev_ctx, new_ctx, FIRST_SWAP);
     }
 
+    /// Spawns the `user` process, which writes the output of the processes it is the group leader
+    /// of to stdout, as a registered child of `init`
+    ///
+    /// It is linked to `init` and traps exits, so that it exits once `init` has.
+    fn spawn_user(&self, init: &Process) -> Result<Spawned, SpawnError> {
+        let mut options: Options = Default::default();
+        options.link = true;
+        options.validate(Some(init))?;
+        let reservation = reserve_pid()?;
+
+        let (heap, heap_size) = options.sized_heap()?;
+        let priority = options.cascaded_priority(Some(init));
+        let initial_module_function_arity = ModuleFunctionArity {
+            module: Atom::from_str("user"),
+            function: Atom::from_str("start"),
+            arity: 0,
+        };
+        let process = Process::new_with_stack(
+            priority,
+            Some(init),
+            initial_module_function_arity,
+            heap,
+            heap_size,
+            self.stack_allocator.as_ref(),
+        )?;
+        process.trap_exit(true);
+
+        let init_fn = unsafe { mem::transmute::<_, DynamicCallee>(io::user as *const c_void) };
+        Self::runnable(&process, init_fn, None);
+
+        let connection = options.connect(Some(init), &process)?;
+        let arc_process = self.schedule(process, reservation);
+        connection.propagate_parent_exit(Some(init), &arc_process);
+        put_atom_to_process(Atom::from_str("user"), arc_process.clone());
+
+        Ok(Spawned {
+            arc_process,
+            connection,
+        })
+    }
+
     // Root process uses the original thread stack, no initialization required.
     //
     // It also starts "running", so we don't put it on the run queue
@@ -609,11 +912,11 @@ impl Scheduler {
         module: Atom,
         function: Atom,
         arguments: Vec<Term>,
-    ) -> (DynamicCallee, Option<Term>) {
+    ) -> Result<(DynamicCallee, Option<Term>), SpawnError> {
         let init_fn = unsafe { mem::transmute::<_, DynamicCallee>(apply_apply_3 as *const c_void) };
 
-        let process_module = module.encode().unwrap();
-        let process_function = function.encode().unwrap();
+        let process_module = module.encode().map_err(|_| SpawnError::SystemLimit)?;
+        let process_function = function.encode().map_err(|_| SpawnError::SystemLimit)?;
         let process_argument_vec: Vec<Term> = arguments
             .iter()
             .map(|argument| argument.clone_to_process(process))
@@ -622,85 +925,89 @@ impl Scheduler {
         let env =
             Some(process.list_from_slice(&[process_module, process_function, process_arguments]));
 
-        (init_fn, env)
+        Ok((init_fn, env))
     }
 
     fn runnable(process: &Process, init_fn: DynamicCallee, env: Option<Term>) {
-        process.runnable(|| {
-            #[allow(unused)]
-            #[inline(always)]
-            unsafe fn push(sp: &mut StackPointer, value: u64) {
-                sp.0 = sp.0.offset(-1);
-                ptr::write(sp.0, value);
-            }
+        process.runnable(|| Self::init_registers(process, init_fn, env))
+    }
 
-            #[inline(always)]
-            #[cfg(target_arch = "aarch64")]
-            unsafe fn set_stack_pointer(registers: &CalleeSavedRegisters, value: u64) {
-                let fp = registers.sp as *const u64 as *mut _;
-                ptr::write(fp, value);
-            }
+    /// Sets up the registers of `process` so that the next swap to it calls `init_fn` with `env`
+    /// at the top of its stack, discarding anything already on the stack
+    fn init_registers(process: &Process, init_fn: DynamicCallee, env: Option<Term>) {
+        #[allow(unused)]
+        #[inline(always)]
+        unsafe fn push(sp: &mut StackPointer, value: u64) {
+            sp.0 = sp.0.offset(-1);
+            ptr::write(sp.0, value);
+        }
 
-            #[inline(always)]
-            #[cfg(target_arch = "aarch64")]
-            unsafe fn set_frame_pointer(registers: &CalleeSavedRegisters, value: u64) {
-                let fp = registers.x29 as *const u64 as *mut _;
-                ptr::write(fp, value);
-            }
+        #[inline(always)]
+        #[cfg(target_arch = "aarch64")]
+        unsafe fn set_stack_pointer(registers: &CalleeSavedRegisters, value: u64) {
+            let fp = registers.sp as *const u64 as *mut _;
+            ptr::write(fp, value);
+        }
 
-            #[inline(always)]
-            #[cfg(target_arch = "x86_64")]
-            unsafe fn set_stack_pointer(registers: &CalleeSavedRegisters, value: u64) {
-                let fp = registers.rsp as *const u64 as *mut _;
-                ptr::write(fp, value);
-            }
+        #[inline(always)]
+        #[cfg(target_arch = "aarch64")]
+        unsafe fn set_frame_pointer(registers: &CalleeSavedRegisters, value: u64) {
+            let fp = registers.x29 as *const u64 as *mut _;
+            ptr::write(fp, value);
+        }
 
-            #[inline(always)]
-            #[cfg(target_arch = "x86_64")]
-            unsafe fn set_frame_pointer(registers: &CalleeSavedRegisters, value: u64) {
-                let fp = registers.rbp as *const u64 as *mut _;
-                ptr::write(fp, value);
-            }
+        #[inline(always)]
+        #[cfg(target_arch = "x86_64")]
+        unsafe fn set_stack_pointer(registers: &CalleeSavedRegisters, value: u64) {
+            let fp = registers.rsp as *const u64 as *mut _;
+            ptr::write(fp, value);
+        }
 
-            // Write the return function and init function to the end of the stack,
-            // when execution resumes, the pointer before the stack pointer will be
-            // used as the return address - the first time that will be the init function.
-            //
-            // When execution returns from the init function, then it will return via
-            // `process_return`, which will return to the scheduler and indicate that
-            // the process exited. The nature of the exit is indicated by error state
-            // in the process itself
-            unsafe {
-                let stack = process.stack.lock();
-                // This can be used to push items on the process
-                // stack before it starts executing. For now that
-                // is not being done
-                let sp = StackPointer(stack.top as *mut u64);
-
-                // Update process stack pointer
-                let s_top = &stack.top as *const _ as *mut _;
-                ptr::write(s_top, sp.0 as *const u8);
-
-                // Write stack/frame pointer initial values
-                set_stack_pointer(&process.registers, sp.0 as u64);
-                set_frame_pointer(&process.registers, sp.0 as u64);
-
-                // If this init function has a closure env, place it in
-                // the first callee-save register, which will be moved to
-                // the first argument register (e.g. %rsi) by swap_stack for
-                // the call to the entry point
-                set_register(&process.registers, 0, env.unwrap_or(Term::NONE));
-
-                // This is used to indicate to swap_stack that this process
-                // is being swapped to for the first time, which allows the
-                // function to perform some initial one-time setup to link
-                // call frames for the unwinder and call the entry point
-                set_register(&process.registers, 1, FIRST_SWAP);
-
-                // The function that swap_stack will call as entry
-                set_register(&process.registers, 2, init_fn as u64);
-            }
-        })
+        #[inline(always)]
+        #[cfg(target_arch = "x86_64")]
+        unsafe fn set_frame_pointer(registers: &CalleeSavedRegisters, value: u64) {
+            let fp = registers.rbp as *const u64 as *mut _;
+            ptr::write(fp, value);
+        }
+
+        // Write the return function and init function to the end of the stack,
+        // when execution resumes, the pointer before the stack pointer will be
+        // used as the return address - the first time that will be the init function.
+        //
+        // When execution returns from the init function, then it will return via
+        // `process_return`, which will return to the scheduler and indicate that
+        // the process exited. The nature of the exit is indicated by error state
+        // in the process itself
+        unsafe {
+            let stack = process.stack.lock();
+            // This can be used to push items on the process
+            // stack before it starts executing. For now that
+            // is not being done
+            let sp = StackPointer(stack.top as *mut u64);
+
+            // Update process stack pointer
+            let s_top = &stack.top as *const _ as *mut _;
+            ptr::write(s_top, sp.0 as *const u8);
+
+            // Write stack/frame pointer initial values
+            set_stack_pointer(&process.registers, sp.0 as u64);
+            set_frame_pointer(&process.registers, sp.0 as u64);
+
+            // If this init function has a closure env, place it in
+            // the first callee-save register, which will be moved to
+            // the first argument register (e.g. %rsi) by swap_stack for
+            // the call to the entry point
+            set_register(&process.registers, 0, env.unwrap_or(Term::NONE));
+
+            // This is used to indicate to swap_stack that this process
+            // is being swapped to for the first time, which allows the
+            // function to perform some initial one-time setup to link
+            // call frames for the unwinder and call the entry point
+            set_register(&process.registers, 1, FIRST_SWAP);
+
+            // The function that swap_stack will call as entry
+            set_register(&process.registers, 2, init_fn as u64);
+        }
     }
 }
 
@@ -739,6 +1046,78 @@ extern "C-unwind" {
     );
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use liblumen_alloc::atom;
+    use liblumen_alloc::erts::exception::{Class, Exception};
+
+    const POOL_SIZE: usize = 2;
+
+    #[test]
+    fn spawning_past_the_stack_pool_fails_with_system_limit() {
+        let scheduler = scheduler_with_stack_pool();
+        let _spawned = (0..POOL_SIZE)
+            .map(|_| spawn(&scheduler).unwrap())
+            .collect::<Vec<_>>();
+
+        let err = spawn(&scheduler).unwrap_err();
+
+        assert_eq!(err, SpawnError::SystemLimit);
+        match Exception::from(err) {
+            Exception::Runtime(exception) => {
+                assert_eq!(exception.class(), Class::Error { arguments: None });
+                assert_eq!(exception.reason(), atom!("system_limit"));
+            }
+            Exception::System(err) => panic!("expected system_limit, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn stack_of_exited_process_is_reused() {
+        let scheduler = scheduler_with_stack_pool();
+        let mut spawned = (0..POOL_SIZE)
+            .map(|_| spawn(&scheduler).unwrap())
+            .collect::<Vec<_>>();
+        let exiting = spawned.pop().unwrap();
+        let exiting_stack = exiting.stack.lock().base;
+
+        // As the scheduler does when the process exits after running
+        exiting.exit_normal();
+        let exiting = scheduler.run_queues.write().remove(exiting.pid()).unwrap();
+        let exiting = scheduler.run_queues.write().requeue(exiting).unwrap();
+        Scheduler::exit(&exiting);
+        drop(exiting);
+
+        assert_eq!(scheduler.stack_allocator.remaining(), Some(1));
+
+        let respawned = spawn(&scheduler).unwrap();
+
+        assert_eq!(respawned.stack.lock().base, exiting_stack);
+        assert_eq!(scheduler.stack_allocator.remaining(), Some(0));
+    }
+
+    fn scheduler_with_stack_pool() -> Scheduler {
+        let stack_allocator =
+            FixedPoolStackAllocator::new(POOL_SIZE, Process::stack_size_hint()).unwrap();
+
+        Scheduler::with_stack_allocator(Arc::new(stack_allocator)).unwrap()
+    }
+
+    fn spawn(scheduler: &Scheduler) -> Result<Arc<Process>, SpawnError> {
+        scheduler
+            .spawn_module_function_arguments(
+                None,
+                Atom::from_str("erlang"),
+                Atom::from_str("self"),
+                vec![],
+                Default::default(),
+            )
+            .map(|Spawned { arc_process, .. }| arc_process)
+    }
+}
+
 #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
 global_asm!(include_str!(
     "scheduler/swap_stack/swap_stack_linux_x86_64.s"
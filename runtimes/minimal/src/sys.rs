@@ -1,3 +1,5 @@
+pub mod affinity;
 pub mod break_handler;
 pub mod cpus;
+pub mod dump;
 pub mod io;
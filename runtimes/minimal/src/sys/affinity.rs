@@ -0,0 +1,175 @@
+//! Binds scheduler threads to logical CPUs, as BEAM does with `+sbt db`, for when the runtime is the
+//! primary software on its cores, so the operating system doesn't move schedulers between them.
+use std::io;
+
+use firefly_rt_core::scheduler::Counters;
+
+/// Sets which logical CPU the current thread runs on
+pub trait Affinity {
+    /// Binds the current thread to the logical CPU `cpu`
+    fn bind_current_thread(&self, cpu: usize) -> io::Result<()>;
+}
+
+/// Binds threads with the affinity API of the platform: `sched_setaffinity` on Linux, and, as a
+/// best effort, an affinity tag set with `thread_policy_set` on macOS, which only asks that threads
+/// with different tags run on different CPUs.
+pub struct Platform;
+
+impl Affinity for Platform {
+    #[cfg(target_os = "linux")]
+    fn bind_current_thread(&self, cpu: usize) -> io::Result<()> {
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+
+        if unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) } == 0
+        {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    fn bind_current_thread(&self, cpu: usize) -> io::Result<()> {
+        // Tag `0` is `THREAD_AFFINITY_TAG_NULL`, which is no affinity
+        let mut policy = libc::thread_affinity_policy_data_t {
+            affinity_tag: cpu as libc::integer_t + 1,
+        };
+        let result = unsafe {
+            libc::thread_policy_set(
+                libc::pthread_mach_thread_np(libc::pthread_self()),
+                libc::THREAD_AFFINITY_POLICY as libc::thread_policy_flavor_t,
+                &mut policy as *mut _ as libc::thread_policy_t,
+                libc::THREAD_AFFINITY_POLICY_COUNT,
+            )
+        };
+
+        if result == libc::KERN_SUCCESS {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("thread_policy_set returned {}", result),
+            ))
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    fn bind_current_thread(&self, _cpu: usize) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "binding threads to CPUs is not supported on this platform",
+        ))
+    }
+}
+
+/// Binds the current thread, which runs the scheduler at `index` with `counters`, to the logical
+/// CPU at `index` in `cpus`, so that each scheduler has a distinct CPU, and records the binding
+/// in `counters`.
+///
+/// A scheduler without a CPU of its own, or whose thread can't be bound, is left unbound, with a
+/// warning, as the system still runs, only without the binding.
+pub fn bind(
+    affinity: &dyn Affinity,
+    counters: &Counters,
+    index: usize,
+    cpus: &[usize],
+) -> Option<usize> {
+    match cpus.get(index) {
+        Some(&cpu) => match affinity.bind_current_thread(cpu) {
+            Ok(()) => {
+                counters.bound(cpu);
+
+                Some(cpu)
+            }
+            Err(err) => {
+                log::warn!(
+                    "scheduler {} is not bound, as binding it to CPU {} failed: {}",
+                    index,
+                    cpu,
+                    err
+                );
+
+                None
+            }
+        },
+        None => {
+            log::warn!(
+                "scheduler {} is not bound, as there are only {} CPUs to bind schedulers to",
+                index,
+                cpus.len()
+            );
+
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    /// Records the CPUs it is asked to bind to, failing for those in `failing`
+    #[derive(Default)]
+    struct Recording {
+        bound: Mutex<Vec<usize>>,
+        failing: Vec<usize>,
+    }
+
+    impl Affinity for Recording {
+        fn bind_current_thread(&self, cpu: usize) -> io::Result<()> {
+            self.bound.lock().unwrap().push(cpu);
+
+            if self.failing.contains(&cpu) {
+                Err(io::Error::new(io::ErrorKind::PermissionDenied, "denied"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn each_scheduler_is_bound_to_a_distinct_cpu() {
+        let affinity = Recording::default();
+        let cpus = [0, 2, 4, 6];
+        let counters: Vec<Counters> = cpus.iter().map(|_| Counters::default()).collect();
+
+        for (index, counters) in counters.iter().enumerate() {
+            assert_eq!(bind(&affinity, counters, index, &cpus), Some(cpus[index]));
+        }
+
+        assert_eq!(*affinity.bound.lock().unwrap(), cpus);
+        let bound_cpus: Vec<Option<usize>> = counters
+            .iter()
+            .map(|counters| counters.bound_cpu())
+            .collect();
+        assert_eq!(bound_cpus, [Some(0), Some(2), Some(4), Some(6)]);
+    }
+
+    #[test]
+    fn scheduler_without_a_cpu_of_its_own_is_not_bound() {
+        let affinity = Recording::default();
+        let counters = Counters::default();
+
+        assert_eq!(bind(&affinity, &counters, 2, &[0, 1]), None);
+
+        assert!(affinity.bound.lock().unwrap().is_empty());
+        assert_eq!(counters.bound_cpu(), None);
+    }
+
+    #[test]
+    fn scheduler_is_not_recorded_as_bound_when_binding_fails() {
+        let affinity = Recording {
+            failing: vec![1],
+            ..Default::default()
+        };
+        let counters = Counters::default();
+
+        assert_eq!(bind(&affinity, &counters, 1, &[0, 1]), None);
+
+        assert_eq!(*affinity.bound.lock().unwrap(), [1]);
+        assert_eq!(counters.bound_cpu(), None);
+    }
+}
@@ -39,6 +39,34 @@ pub fn num_total() -> usize {
     get_num_cpus()
 }
 
+/// Returns the ids of the logical CPUs available to the current process, in ascending order.
+///
+/// There are [`num_logical()`] of them, but on Linux, where [sched affinity] can exclude CPUs, the
+/// ids need not be contiguous.
+///
+/// [sched affinity]: http://www.gnu.org/software/libc/manual/html_node/CPU-Affinity.html
+pub fn logical_ids() -> Vec<usize> {
+    get_cpu_ids()
+}
+
+#[cfg(target_os = "linux")]
+fn get_cpu_ids() -> Vec<usize> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) } == 0
+    {
+        (0..libc::CPU_SETSIZE as usize)
+            .filter(|i| unsafe { libc::CPU_ISSET(*i, &set) })
+            .collect()
+    } else {
+        (0..get_num_cpus()).collect()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_cpu_ids() -> Vec<usize> {
+    (0..get_num_cpus()).collect()
+}
+
 #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
 #[inline]
 fn get_num_physical_cpus() -> usize {
@@ -357,6 +385,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_logical_ids() {
+        let ids = super::logical_ids();
+
+        assert_eq!(ids.len(), super::num_logical());
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
     #[test]
     fn test_num_physical() {
         let num = super::num_physical();
@@ -0,0 +1,23 @@
+//! Dumps of the system state, in a subset of the BEAM `erl_crash.dump` format.
+use std::io::{self, Write};
+
+use firefly_rt_core::process::dump_state;
+use firefly_rt_core::scheduler::dump_run_queues;
+
+/// Writes the state dump to stderr, as BEAM does on `SIGUSR1`, but without writing a crash dump
+/// file or stopping the system.
+///
+/// The dump is formatted before any of it is written, so that the schedulers aren't held up by
+/// stderr.
+pub fn write_signal_dump() {
+    let mut dump = Vec::new();
+    let _ = write_state_dump(&mut dump);
+    let _ = io::stderr().write_all(&dump);
+}
+
+/// Writes the run queues of every scheduler and the state of every process
+pub fn write_state_dump(writer: &mut dyn Write) -> io::Result<()> {
+    dump_run_queues(writer)?;
+    dump_state(writer)?;
+    writer.flush()
+}
@@ -1,16 +1,32 @@
-use std::ffi::CStr;
+use std::convert::TryInto;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::sync::Arc;
 
-use libc;
+use anyhow::anyhow;
 
+use liblumen_alloc::atom;
+use liblumen_alloc::erts::exception::{self, badarg};
+use liblumen_alloc::erts::process::ffi::ErlangResult;
+use liblumen_alloc::erts::process::trace::Trace;
+use liblumen_alloc::erts::process::Process;
 use liblumen_alloc::erts::term::prelude::*;
 
-pub use lumen_rt_core::sys::io::puts;
+use firefly_rt_core::binary_to_string::binary_to_string;
+use firefly_rt_core::process::{current_process, monitor};
+use firefly_rt_core::registry::pid_to_process;
+
+use crate::builtins::receive;
+
+pub use firefly_rt_core::sys::io::{puts, record_output};
 
 #[export_name = "__lumen_builtin_printf"]
 pub extern "C" fn printf_1(term: Term) -> Term {
     match term.decode() {
         Ok(tt) => {
-            println!("{}", tt);
+            let s = tt.to_string();
+            println!("{}", s);
+            record_output(s.len() + 1);
             Atom::from_str("ok").encode().unwrap()
         }
         Err(reason) => {
@@ -20,24 +36,416 @@ pub extern "C" fn printf_1(term: Term) -> Term {
     }
 }
 
+/// Writes `chars` through the group leader of the calling process
 #[export_name = "io:put_chars/1"]
-pub extern "C" fn put_chars_1(s: *const libc::c_char) -> Term {
-    let sref = unsafe { CStr::from_ptr(s).to_string_lossy() };
-    println!("{}", &sref);
-    ok!()
+pub extern "C-unwind" fn put_chars_1(chars: Term) -> ErlangResult {
+    let arc_process = current_process();
+
+    if chardata_to_string(chars).is_none() {
+        let source = anyhow!("chars ({}) is not chardata", chars);
+
+        return ErlangResult::error(
+            arc_process.raise(badarg(Trace::capture(), Some(source.into()))),
+        );
+    }
+
+    request_put_chars(&arc_process, chars)
+}
+
+#[export_name = "io:format/1"]
+pub extern "C-unwind" fn format_1(format: Term) -> ErlangResult {
+    format_and_put_chars(format, Term::NIL)
 }
 
 #[export_name = "io:format/2"]
-pub extern "C" fn format_2(
-    _s: *const libc::c_char,
-    _argv: *const Term,
-    _argc: libc::c_uint,
-) -> Term {
-    unimplemented!();
+pub extern "C-unwind" fn format_2(format: Term, arguments: Term) -> ErlangResult {
+    format_and_put_chars(format, arguments)
 }
 
 #[export_name = "io:nl/0"]
-pub extern "C" fn nl_0() -> Term {
-    println!();
-    ok!()
+pub extern "C-unwind" fn nl_0() -> ErlangResult {
+    let arc_process = current_process();
+    let chars = arc_process.binary_from_str("\n");
+
+    request_put_chars(&arc_process, chars)
+}
+
+/// The entry point of the `user` process, which `init` spawns as the group leader of every other
+/// process, so that their output is written to stdout.
+///
+/// It serves `put_chars` requests until `init`, which it is linked to and traps the exit of,
+/// exits.
+pub extern "C-unwind" fn user(_env: Term) -> ErlangResult {
+    let arc_process = current_process();
+
+    loop {
+        let message = receive::receive(&arc_process, |_| true);
+        let tuple: Boxed<Tuple> = match message.try_into() {
+            Ok(tuple) => tuple,
+            Err(_) => continue,
+        };
+
+        if tuple.len() == 3 && tuple[0] == atom!("EXIT") {
+            return ErlangResult::ok(ok!());
+        }
+
+        if !(tuple.len() == 4 && tuple[0] == atom!("io_request")) {
+            continue;
+        }
+
+        let mut output = String::new();
+        let reply = if serve_put_chars(tuple[3], &mut output) {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(output.as_bytes()).unwrap();
+            stdout.flush().unwrap();
+            record_output(output.len());
+
+            ok!()
+        } else {
+            arc_process.tuple_from_slice(&[atom!("error"), atom!("request")])
+        };
+
+        let from: Option<Pid> = tuple[1].decode().ok().and_then(|from| from.try_into().ok());
+        if let Some(from_arc_process) = from.and_then(|from| pid_to_process(&from)) {
+            let io_reply = arc_process.tuple_from_slice(&[atom!("io_reply"), tuple[2], reply]);
+            from_arc_process.send_from_other(io_reply);
+            crate::scheduler::stop_waiting(&from_arc_process);
+        }
+    }
+}
+
+fn format_and_put_chars(format: Term, arguments: Term) -> ErlangResult {
+    let arc_process = current_process();
+
+    match self::format(format, arguments) {
+        Some(output) => {
+            let chars = arc_process.binary_from_str(&output);
+
+            request_put_chars(&arc_process, chars)
+        }
+        None => {
+            let source = anyhow!(
+                "format ({}) does not match arguments ({})",
+                format,
+                arguments
+            );
+
+            ErlangResult::error(arc_process.raise(badarg(Trace::capture(), Some(source.into()))))
+        }
+    }
+}
+
+/// Sends `chars` to the group leader of `arc_process` in a `put_chars` request, and waits for its
+/// reply, as the `io` functions in OTP do.
+///
+/// The group leader is monitored while waiting, and the monitor reference is the `ReplyAs` of the
+/// request, so that the wait ends with `terminated` if the group leader exits before replying.
+fn request_put_chars(arc_process: &Arc<Process>, chars: Term) -> ErlangResult {
+    let group_leader_pid = arc_process.get_group_leader_pid();
+
+    let group_leader = match pid_to_process(&group_leader_pid) {
+        Some(group_leader) => group_leader,
+        None => return terminated(arc_process, group_leader_pid),
+    };
+
+    let reply_as = monitor(arc_process, &group_leader);
+    let reference: Boxed<Reference> = reply_as.try_into().unwrap();
+    // Only the reply, or `DOWN`, can match, so neither can be before the request is sent
+    arc_process
+        .mailbox
+        .lock()
+        .borrow_mut()
+        .mark(reference.number());
+
+    send_put_chars(arc_process, &group_leader, reply_as, chars);
+    crate::scheduler::stop_waiting(&group_leader);
+
+    arc_process
+        .mailbox
+        .lock()
+        .borrow_mut()
+        .set_save_to_marker(reference.number());
+    let message = receive::receive(arc_process, |message| is_reply(message, reply_as));
+    let tuple: Boxed<Tuple> = message.try_into().unwrap();
+
+    if tuple[0] == atom!("io_reply") {
+        demonitor(arc_process, &reference);
+
+        if tuple[2] == ok!() {
+            ErlangResult::ok(ok!())
+        } else {
+            let source = anyhow!("group leader replied {}", tuple[2]);
+
+            ErlangResult::error(arc_process.raise(badarg(Trace::capture(), Some(source.into()))))
+        }
+    } else {
+        terminated(arc_process, group_leader_pid)
+    }
+}
+
+/// Sends `{io_request, From, ReplyAs, {put_chars, unicode, Chars}}` from `process` to
+/// `group_leader`
+fn send_put_chars(process: &Process, group_leader: &Process, reply_as: Term, chars: Term) {
+    let request = process.tuple_from_slice(&[atom!("put_chars"), atom!("unicode"), chars]);
+    let io_request =
+        process.tuple_from_slice(&[atom!("io_request"), process.pid_term(), reply_as, request]);
+
+    group_leader.send_from_other(io_request);
+}
+
+/// Whether `message` is the `{io_reply, ReplyAs, Reply}` to a request, or the `DOWN` message of
+/// the group leader the request was sent to, as both start with `reply_as` as their second element
+fn is_reply(message: Term, reply_as: Term) -> bool {
+    let tuple: Result<Boxed<Tuple>, _> = message.try_into();
+
+    match tuple {
+        Ok(tuple) => {
+            ((tuple.len() == 3 && tuple[0] == atom!("io_reply"))
+                || (tuple.len() == 5 && tuple[0] == atom!("DOWN")))
+                && tuple[1] == reply_as
+        }
+        Err(_) => false,
+    }
+}
+
+/// Removes the monitor of the group leader once it has replied, with its `DOWN` message, in case
+/// it exited since
+fn demonitor(process: &Process, reference: &Reference) {
+    if let Some(monitored_pid) = process.demonitor(reference) {
+        if let Some(monitored_arc_process) = pid_to_process(&monitored_pid) {
+            monitored_arc_process.demonitored(reference);
+        }
+    }
+
+    process
+        .mailbox
+        .lock()
+        .borrow_mut()
+        .flush(|message| monitor::is_down(message, reference));
+}
+
+fn terminated(process: &Process, group_leader_pid: Pid) -> ErlangResult {
+    let source = anyhow!("group leader ({}) is not alive", group_leader_pid);
+    let exception = exception::error(
+        atom!("terminated"),
+        None,
+        Trace::capture(),
+        Some(source.into()),
+    );
+
+    ErlangResult::error(process.raise(exception))
+}
+
+/// Writes the characters of a `{put_chars, Encoding, Chars}` request to `output`, returning
+/// `false` if it is any other request
+fn serve_put_chars(request: Term, output: &mut String) -> bool {
+    let tuple: Result<Boxed<Tuple>, _> = request.try_into();
+
+    match tuple {
+        Ok(tuple)
+            if tuple.len() == 3
+                && tuple[0] == atom!("put_chars")
+                && (tuple[1] == atom!("unicode") || tuple[1] == atom!("latin1")) =>
+        {
+            write_chardata(tuple[2], output).is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Formats `arguments` according to `format`, which may be an atom, a binary, or a charlist,
+/// supporting the `~n`, `~~`, `~p`, `~w` and `~s` control sequences.
+///
+/// Returns `None` if a control sequence is unsupported or the arguments don't match them.
+fn format(format: Term, arguments: Term) -> Option<String> {
+    let format = match format.decode().ok()? {
+        TypedTerm::Atom(atom) => atom.name().to_string(),
+        _ => chardata_to_string(format)?,
+    };
+    let arguments: Vec<Term> = match arguments.decode().ok()? {
+        TypedTerm::Nil => Vec::new(),
+        TypedTerm::List(cons) => cons.iter().collect::<Result<_, _>>().ok()?,
+        _ => return None,
+    };
+
+    let mut arguments = arguments.into_iter();
+    let mut output = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            '~' => output.push('~'),
+            'n' => output.push('\n'),
+            'p' | 'w' => write!(output, "{}", arguments.next()?).unwrap(),
+            's' => {
+                let argument = arguments.next()?;
+
+                match argument.decode().ok()? {
+                    TypedTerm::Atom(atom) => output.push_str(atom.name()),
+                    _ => write_chardata(argument, &mut output)?,
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    match arguments.next() {
+        Some(_) => None,
+        None => Some(output),
+    }
+}
+
+fn chardata_to_string(chardata: Term) -> Option<String> {
+    let mut string = String::new();
+    write_chardata(chardata, &mut string)?;
+
+    Some(string)
+}
+
+/// Writes `chardata`, which is a binary or a possibly deep list of characters and binaries, with
+/// a binary tail, to `output`, returning `None` if it is anything else
+fn write_chardata(chardata: Term, output: &mut String) -> Option<()> {
+    match chardata.decode().ok()? {
+        TypedTerm::Nil => (),
+        TypedTerm::List(cons) => {
+            for result in cons.iter() {
+                match result {
+                    Ok(element) => match element.decode().ok()? {
+                        TypedTerm::SmallInteger(small_integer) => {
+                            let code: isize = small_integer.into();
+                            output.push(char::from_u32(code.try_into().ok()?)?);
+                        }
+                        _ => write_chardata(element, output)?,
+                    },
+                    Err(ImproperList { tail }) => {
+                        output.push_str(&binary_to_string(tail).ok()?);
+                    }
+                }
+            }
+        }
+        _ => output.push_str(&binary_to_string(chardata).ok()?),
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use liblumen_alloc::erts::process::alloc;
+    use liblumen_alloc::erts::ModuleFunctionArity;
+
+    use firefly_rt_core::registry::{put_pid_to_process, remove_pid_to_process, reserve_pid};
+
+    #[test]
+    fn put_chars_is_sent_to_the_group_leader_as_an_io_request() {
+        let process = process("client");
+        let group_leader = Arc::new(self::process("group_leader"));
+        put_pid_to_process(&group_leader, reserve_pid().unwrap());
+
+        // As `group_leader(GroupLeader, self())`
+        process.set_group_leader_pid(group_leader.pid());
+
+        let reply_as = process.reference(1);
+        let chars = process.charlist_from_str("Hello, world!\n");
+        let group_leader_of_process = pid_to_process(&process.get_group_leader_pid()).unwrap();
+        send_put_chars(&process, &group_leader_of_process, reply_as, chars);
+
+        let expected = process.tuple_from_slice(&[
+            atom!("io_request"),
+            process.pid_term(),
+            reply_as,
+            process.tuple_from_slice(&[atom!("put_chars"), atom!("unicode"), chars]),
+        ]);
+        let mailbox_guard = group_leader.mailbox.lock();
+        let mailbox = mailbox_guard.borrow();
+        assert_eq!(mailbox.len(), 1);
+        let io_request = mailbox.peek().unwrap().data();
+        assert_eq!(io_request, expected);
+
+        // The request is served by writing its characters
+        let io_request: Boxed<Tuple> = io_request.try_into().unwrap();
+        let mut output = String::new();
+        assert!(serve_put_chars(io_request[3], &mut output));
+        assert_eq!(output, "Hello, world!\n");
+        assert!(is_reply(
+            process.tuple_from_slice(&[atom!("io_reply"), reply_as, ok!()]),
+            reply_as
+        ));
+
+        remove_pid_to_process(&group_leader);
+    }
+
+    #[test]
+    fn requests_other_than_put_chars_are_not_served() {
+        let process = process("client");
+        let mut output = String::new();
+
+        let get_line = process.tuple_from_slice(&[
+            atom!("get_line"),
+            atom!("unicode"),
+            process.charlist_from_str("> "),
+        ]);
+        assert!(!serve_put_chars(get_line, &mut output));
+
+        let bad_chars =
+            process.tuple_from_slice(&[atom!("put_chars"), atom!("unicode"), atom!("chars")]);
+        assert!(!serve_put_chars(bad_chars, &mut output));
+    }
+
+    #[test]
+    fn chardata_may_be_deep_with_a_binary_tail() {
+        let process = process("client");
+        let deep = process
+            .list_from_slice(&[process.charlist_from_str("a"), process.binary_from_str("b")]);
+        let chardata = process.improper_list_from_slice(&[deep], process.binary_from_str("c"));
+
+        assert_eq!(chardata_to_string(chardata), Some("abc".to_string()));
+    }
+
+    #[test]
+    fn format_writes_arguments_for_control_sequences() {
+        let process = process("client");
+        let arguments = process.list_from_slice(&[
+            process.tuple_from_slice(&[atom!("ok"), process.integer(1)]),
+            process.charlist_from_str("text"),
+        ]);
+
+        assert_eq!(
+            format(process.charlist_from_str("~p ~s~~~n"), arguments),
+            Some("{ok, 1} text~\n".to_string())
+        );
+    }
+
+    #[test]
+    fn format_with_mismatched_arguments_is_an_error() {
+        let process = process("client");
+        let one = process.list_from_slice(&[process.integer(1)]);
+
+        assert_eq!(format(process.charlist_from_str("~p ~p"), one), None);
+        assert_eq!(format(process.charlist_from_str("none"), one), None);
+        assert_eq!(format(process.charlist_from_str("~q"), one), None);
+    }
+
+    fn process(function: &str) -> Process {
+        let (heap, heap_size) = alloc::default_heap().unwrap();
+
+        Process::new(
+            Default::default(),
+            None,
+            ModuleFunctionArity {
+                module: Atom::from_str("io"),
+                function: Atom::from_str(function),
+                arity: 0,
+            },
+            heap,
+            heap_size,
+        )
+    }
 }
@@ -1,26 +1,84 @@
 use std::alloc::Layout;
 use std::borrow::Borrow;
+use std::collections::BTreeMap;
 use std::env::ArgsOs;
+use std::ffi::{OsStr, OsString};
 use std::mem;
 use std::path::Path;
 use std::ptr;
-use std::sync::OnceLock;
+use std::sync::{OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use anyhow::anyhow;
+use lazy_static::lazy_static;
 
 use firefly_arena::DroplessArena;
 use firefly_binary::{BinaryFlags, Encoding};
-use firefly_rt::term::BinaryData;
+use firefly_rt::term::{Atom, BinaryData, Node};
 
 static ARGV: OnceLock<EnvTable> = OnceLock::new();
 
+lazy_static! {
+    static ref VARIABLES: RwLock<Variables> = RwLock::new(Variables::default());
+}
+
 /// Returns all arguments this executable was invoked with
 pub fn argv() -> &'static [&'static BinaryData] {
     ARGV.get().unwrap().argv.as_slice()
 }
 
+/// Acquires shared access to the environment variables of the system
+pub fn variables() -> RwLockReadGuard<'static, Variables> {
+    VARIABLES.read().unwrap()
+}
+
+/// Acquires exclusive access to the environment variables of the system
+pub fn variables_mut() -> RwLockWriteGuard<'static, Variables> {
+    VARIABLES.write().unwrap()
+}
+
+/// The environment variables visible to Erlang code
+///
+/// Modifying the environment of the OS process with `std::env::set_var` is unsound while other
+/// threads may be reading it, which the schedulers, or any C library they call into, may be doing
+/// at any time. Instead, variables set by `os:putenv/2` are kept in an overlay, which is consulted
+/// before the environment this executable was started with. The overlay is not visible to
+/// child processes or native code.
+#[derive(Default)]
+pub struct Variables {
+    overlay: BTreeMap<OsString, OsString>,
+}
+impl Variables {
+    /// Returns the value of the variable `key`, if it is set
+    pub fn get(&self, key: &OsStr) -> Option<OsString> {
+        match self.overlay.get(key) {
+            Some(value) => Some(value.clone()),
+            None => std::env::var_os(key),
+        }
+    }
+
+    /// Sets the variable `key` to `value`, replacing any value it already has
+    ///
+    /// NOTE: It is up to the caller to ensure that `key` is non-empty and contains neither `=` nor
+    /// NUL, and that `value` does not contain NUL.
+    pub fn set(&mut self, key: OsString, value: OsString) {
+        self.overlay.insert(key, value);
+    }
+
+    /// Returns all of the variables which are set, ordered by key
+    pub fn all(&self) -> Vec<(OsString, OsString)> {
+        let mut vars = std::env::vars_os().collect::<BTreeMap<_, _>>();
+        vars.extend(
+            self.overlay
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        vars.into_iter().collect()
+    }
+}
+
 /// Performs one-time initialization of the environment for the current executable.
-/// This is used to cache the arguments vector as constant binary values.
+/// This is used to cache the arguments vector as constant binary values, and to name
+/// the local node if `-name` or `-sname` were given.
 pub fn init(mut argv: ArgsOs) -> anyhow::Result<()> {
     let mut table = EnvTable::with_capacity(argv.len());
 
@@ -67,11 +125,31 @@ pub fn init(mut argv: ArgsOs) -> anyhow::Result<()> {
         }
     }
 
+    let mut node_name = None;
+    let mut naming = None;
     for arg in argv {
         let arg = arg.to_string_lossy();
         unsafe {
             table.insert(arg.as_bytes());
         }
+        match naming.take() {
+            Some(long) => node_name = Some(qualify_node_name(&arg, long)?),
+            None => {
+                naming = match &*arg {
+                    "-name" | "--name" => Some(true),
+                    "-sname" | "--sname" => Some(false),
+                    _ => None,
+                }
+            }
+        }
+    }
+    if naming.is_some() {
+        return Err(anyhow!("expected a node name after -name/-sname"));
+    }
+    if let Some(node_name) = node_name {
+        let name = Atom::try_from(node_name.as_str())
+            .map_err(|error| anyhow!("invalid node name {}: {}", node_name, error))?;
+        Node::set_local_name(name);
     }
 
     ARGV.set(table)
@@ -81,6 +159,36 @@ pub fn init(mut argv: ArgsOs) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Returns the full node name for `name`, given with `-name` if `long`, or with `-sname` otherwise
+///
+/// Like `erl`, a name without a host part is qualified with the host name of this machine, which is
+/// shortened to its first component for short names.
+fn qualify_node_name(name: &str, long: bool) -> anyhow::Result<String> {
+    if name.is_empty() || name.starts_with('@') {
+        return Err(anyhow!("invalid node name '{}'", name));
+    }
+    if name.contains('@') {
+        return Ok(name.to_string());
+    }
+    let host = hostname()?;
+    let host = if long {
+        host.as_str()
+    } else {
+        host.split('.').next().unwrap()
+    };
+    Ok(format!("{}@{}", name, host))
+}
+
+fn hostname() -> anyhow::Result<String> {
+    let mut buf = [0u8; 256];
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    Ok(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
 #[derive(Default)]
 struct EnvTable {
     argv: Vec<&'static BinaryData>,
@@ -126,3 +234,70 @@ impl EnvTable {
 }
 unsafe impl Send for EnvTable {}
 unsafe impl Sync for EnvTable {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cargo sets this for the test executable, and nothing in the tests modifies the environment
+    const CARGO_PKG_NAME: &str = "CARGO_PKG_NAME";
+
+    #[test]
+    fn unset_variables_have_no_value() {
+        let vars = Variables::default();
+        let key = OsStr::new("FIREFLY_TEST_UNSET_VARIABLE");
+
+        assert_eq!(vars.get(key), None);
+        assert!(vars.all().iter().all(|(k, _)| k != key));
+    }
+
+    #[test]
+    fn variables_are_read_from_the_environment() {
+        let vars = Variables::default();
+        let expected = std::env::var_os(CARGO_PKG_NAME).unwrap();
+
+        assert_eq!(vars.get(OsStr::new(CARGO_PKG_NAME)), Some(expected.clone()));
+        assert!(vars.all().contains(&(CARGO_PKG_NAME.into(), expected)));
+    }
+
+    #[test]
+    fn overlay_takes_precedence_over_the_environment() {
+        let mut vars = Variables::default();
+        vars.set(CARGO_PKG_NAME.into(), "overlaid".into());
+        vars.set("FIREFLY_TEST_SET_VARIABLE".into(), "set".into());
+
+        assert_eq!(
+            vars.get(OsStr::new(CARGO_PKG_NAME)),
+            Some("overlaid".into())
+        );
+        assert_eq!(
+            vars.get(OsStr::new("FIREFLY_TEST_SET_VARIABLE")),
+            Some("set".into())
+        );
+        let all = vars.all();
+        let pkg_names = all
+            .iter()
+            .filter(|(key, _)| key == CARGO_PKG_NAME)
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(pkg_names, vec![OsString::from("overlaid")]);
+        assert!(all.contains(&("FIREFLY_TEST_SET_VARIABLE".into(), "set".into())));
+        // The environment of the OS process is left untouched
+        assert_ne!(
+            std::env::var_os(CARGO_PKG_NAME),
+            Some(OsString::from("overlaid"))
+        );
+    }
+
+    #[test]
+    fn later_values_replace_earlier_ones() {
+        let mut vars = Variables::default();
+        vars.set("FIREFLY_TEST_SET_VARIABLE".into(), "first".into());
+        vars.set("FIREFLY_TEST_SET_VARIABLE".into(), "second".into());
+
+        assert_eq!(
+            vars.get(OsStr::new("FIREFLY_TEST_SET_VARIABLE")),
+            Some("second".into())
+        );
+    }
+}
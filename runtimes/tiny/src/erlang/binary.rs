@@ -0,0 +1,494 @@
+use std::alloc::Global;
+use std::ops::Range;
+
+use firefly_alloc::gc::GcBox;
+use firefly_alloc::rc::Rc;
+use firefly_rt::backtrace::Trace;
+use firefly_rt::function::ErlangResult;
+use firefly_rt::process::Process;
+use firefly_rt::term::*;
+
+use crate::scheduler;
+
+use super::{badarg, system_limit};
+
+/// The largest binary `binary:copy/2` will try to allocate, so that its size in bits fits in a
+/// word, and its layout can't overflow
+const MAX_BINARY_BYTES: usize = isize::MAX as usize / 8;
+
+#[export_name = "binary:split/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn split2(subject: OpaqueTerm, pattern: OpaqueTerm) -> ErlangResult {
+    split3(subject, pattern, OpaqueTerm::NIL)
+}
+
+#[export_name = "binary:split/3"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn split3(
+    subject: OpaqueTerm,
+    pattern: OpaqueTerm,
+    options: OpaqueTerm,
+) -> ErlangResult {
+    let t: Term = subject.into();
+    let Some(bits) = t.as_bitstring() else { return badarg(Trace::capture()); };
+    if !bits.is_binary() {
+        return badarg(Trace::capture());
+    }
+    let Some(pattern) = Pattern::from_term(pattern) else { return badarg(Trace::capture()); };
+    let Some(options) = SplitOptions::from_term(options, bits.byte_size()) else { return badarg(Trace::capture()); };
+
+    // Only an unaligned binary is copied, the pieces are slices of `subject` either way
+    let selection = bits.select_all();
+    let bytes = selection.to_bytes();
+    let pieces = split(&bytes, &pattern, &options);
+
+    scheduler::with_current_process(|proc| {
+        let pieces = pieces
+            .into_iter()
+            .map(|piece| sub_binary(subject, piece, proc))
+            .collect::<Vec<_>>();
+        let list = match Cons::from_slice(&pieces, proc).unwrap() {
+            None => OpaqueTerm::NIL,
+            Some(cons) => cons.into(),
+        };
+        ErlangResult::Ok(list)
+    })
+}
+
+#[export_name = "binary:match/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn match2(subject: OpaqueTerm, pattern: OpaqueTerm) -> ErlangResult {
+    match3(subject, pattern, OpaqueTerm::NIL)
+}
+
+#[export_name = "binary:match/3"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn match3(
+    subject: OpaqueTerm,
+    pattern: OpaqueTerm,
+    options: OpaqueTerm,
+) -> ErlangResult {
+    let t: Term = subject.into();
+    let Some(bits) = t.as_bitstring() else { return badarg(Trace::capture()); };
+    if !bits.is_binary() {
+        return badarg(Trace::capture());
+    }
+    let Some(pattern) = Pattern::from_term(pattern) else { return badarg(Trace::capture()); };
+    let Some(scope) = match_options(options, bits.byte_size()) else { return badarg(Trace::capture()); };
+
+    let selection = bits.select_all();
+    let bytes = selection.to_bytes();
+    match pattern.find(&bytes[..scope.end], scope.start) {
+        None => ErlangResult::Ok(atoms::Nomatch.into()),
+        Some(found) => scheduler::with_current_process(|proc| {
+            let start = Term::Int(found.start as i64);
+            let len = Term::Int(found.len() as i64);
+            let tuple = Tuple::from_slice(&[start.into(), len.into()], proc).unwrap();
+            ErlangResult::Ok(tuple.into())
+        }),
+    }
+}
+
+#[export_name = "binary:copy/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn copy1(subject: OpaqueTerm) -> ErlangResult {
+    copy2(subject, Term::Int(1).into())
+}
+
+#[export_name = "binary:copy/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn copy2(subject: OpaqueTerm, n: OpaqueTerm) -> ErlangResult {
+    let t: Term = subject.into();
+    let Some(bits) = t.as_bitstring() else { return badarg(Trace::capture()); };
+    if !bits.is_binary() {
+        return badarg(Trace::capture());
+    }
+    let Term::Int(n) = n.into() else { return badarg(Trace::capture()); };
+    let Ok(n) = usize::try_from(n) else { return badarg(Trace::capture()); };
+    let size = bits.byte_size();
+    let total = match size.checked_mul(n) {
+        Some(total) if total <= MAX_BINARY_BYTES => total,
+        _ => return system_limit(Trace::capture()),
+    };
+
+    let selection = bits.select_all();
+    let bytes = selection.to_bytes();
+    scheduler::with_current_process(|proc| {
+        if total <= BinaryData::MAX_HEAP_BYTES {
+            let mut bin = BinaryData::with_capacity_small(total, proc).unwrap();
+            for i in 0..n {
+                bin[(i * size)..((i + 1) * size)].copy_from_slice(&bytes);
+            }
+            return ErlangResult::Ok(bin.into());
+        }
+        // Large binaries live outside of the process heap, and a count large enough to exhaust
+        // memory is an error for the caller rather than an abort
+        let Ok(mut bin) = BinaryData::with_capacity_large(total, Global) else { return system_limit(Trace::capture()); };
+        {
+            // SAFETY: There can be no other references to this Rc yet
+            let b = unsafe { Rc::get_mut(&mut bin).unwrap_unchecked() };
+            for i in 0..n {
+                b[(i * size)..((i + 1) * size)].copy_from_slice(&bytes);
+            }
+        }
+        ErlangResult::Ok(bin.into())
+    })
+}
+
+/// Allocates a sub-binary of `subject` covering the bytes in `range`
+fn sub_binary(subject: OpaqueTerm, range: Range<usize>, proc: &Process) -> OpaqueTerm {
+    let part = BitSlice::binary_part(subject, range.start, range.len() as isize).unwrap();
+    GcBox::new_in(part, proc).unwrap().into()
+}
+
+/// A compiled search pattern of one or more non-empty binaries
+#[derive(Debug)]
+enum Pattern {
+    Single(Horspool),
+    /// Several patterns are searched for naively, at each position in turn
+    Multiple(Vec<Vec<u8>>),
+}
+impl Pattern {
+    /// Parses a pattern from a binary, or a non-empty list of binaries, none of which may be
+    /// empty
+    fn from_term(term: OpaqueTerm) -> Option<Self> {
+        let mut patterns = match term.into() {
+            Term::Cons(cons) => {
+                let cons = unsafe { cons.as_ref() };
+                cons.iter()
+                    .map(|result| result.ok().and_then(pattern_bytes))
+                    .collect::<Option<Vec<_>>>()?
+            }
+            t => vec![pattern_bytes(t)?],
+        };
+        if patterns.len() == 1 {
+            Some(Self::Single(Horspool::new(patterns.pop().unwrap())))
+        } else {
+            Some(Self::Multiple(patterns))
+        }
+    }
+
+    /// Returns the range of the first match in `haystack` starting at or after `start`.
+    ///
+    /// Where several patterns match at the same position, the longest of them is used.
+    fn find(&self, haystack: &[u8], start: usize) -> Option<Range<usize>> {
+        match self {
+            Self::Single(horspool) => horspool.find(haystack, start),
+            Self::Multiple(patterns) => (start..haystack.len()).find_map(|pos| {
+                let rest = &haystack[pos..];
+                let longest = patterns
+                    .iter()
+                    .filter(|pattern| rest.starts_with(pattern))
+                    .map(|pattern| pattern.len())
+                    .max()?;
+                Some(pos..(pos + longest))
+            }),
+        }
+    }
+}
+
+fn pattern_bytes(term: Term) -> Option<Vec<u8>> {
+    let bits = term.as_bitstring()?;
+    if !bits.is_binary() || bits.byte_size() == 0 {
+        return None;
+    }
+    Some(bits.bytes().collect())
+}
+
+/// A single pattern, searched for with the Boyer-Moore-Horspool algorithm
+#[derive(Debug)]
+struct Horspool {
+    needle: Vec<u8>,
+    /// How far the needle can be moved along when the haystack byte under its last byte is the
+    /// byte at this index
+    shifts: [usize; 256],
+}
+impl Horspool {
+    fn new(needle: Vec<u8>) -> Self {
+        let last = needle.len() - 1;
+        let mut shifts = [needle.len(); 256];
+        for (i, byte) in needle[..last].iter().enumerate() {
+            shifts[*byte as usize] = last - i;
+        }
+        Self { needle, shifts }
+    }
+
+    fn find(&self, haystack: &[u8], start: usize) -> Option<Range<usize>> {
+        let len = self.needle.len();
+        let mut pos = start;
+        while pos + len <= haystack.len() {
+            let window = &haystack[pos..(pos + len)];
+            if window == self.needle.as_slice() {
+                return Some(pos..(pos + len));
+            }
+            pos += self.shifts[window[len - 1] as usize];
+        }
+        None
+    }
+}
+
+/// Parses `{scope, {Start, Length}}` into the range of bytes it covers, which must be within
+/// a binary of `size` bytes. A negative length covers the bytes preceding `Start` instead.
+fn scope(term: Term, size: usize) -> Option<Range<usize>> {
+    let Term::Tuple(tuple) = term else { return None; };
+    let [tag, part] = unsafe { tuple.as_ref() }.as_slice() else { return None; };
+    if *tag != atoms::Scope.into() {
+        return None;
+    }
+    let Term::Tuple(part) = (*part).into() else { return None; };
+    let [start, length] = unsafe { part.as_ref() }.as_slice() else { return None; };
+    let Term::Int(start) = (*start).into() else { return None; };
+    let Term::Int(length) = (*length).into() else { return None; };
+    let (start, end) = if length < 0 {
+        (start.checked_add(length)?, start)
+    } else {
+        (start, start.checked_add(length)?)
+    };
+    let start = usize::try_from(start).ok()?;
+    let end = usize::try_from(end).ok()?;
+    if end > size {
+        return None;
+    }
+    Some(start..end)
+}
+
+/// Parses the options of `binary:match/3`, returning the range to search
+fn match_options(options: OpaqueTerm, size: usize) -> Option<Range<usize>> {
+    let mut range = 0..size;
+    match options.into() {
+        Term::Nil => (),
+        Term::Cons(cons) => {
+            for option in unsafe { cons.as_ref() }.iter() {
+                range = scope(option.ok()?, size)?;
+            }
+        }
+        _ => return None,
+    }
+    Some(range)
+}
+
+/// The options of `binary:split/3`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SplitOptions {
+    scope: Range<usize>,
+    global: bool,
+    trim: bool,
+    trim_all: bool,
+}
+impl SplitOptions {
+    fn from_term(options: OpaqueTerm, size: usize) -> Option<Self> {
+        let mut parsed = Self {
+            scope: 0..size,
+            global: false,
+            trim: false,
+            trim_all: false,
+        };
+        match options.into() {
+            Term::Nil => (),
+            Term::Cons(cons) => {
+                for option in unsafe { cons.as_ref() }.iter() {
+                    match option.ok()? {
+                        Term::Atom(a) if a == atoms::Global => parsed.global = true,
+                        Term::Atom(a) if a == atoms::Trim => parsed.trim = true,
+                        Term::Atom(a) if a == atoms::TrimAll => parsed.trim_all = true,
+                        option => parsed.scope = scope(option, size)?,
+                    }
+                }
+            }
+            _ => return None,
+        }
+        Some(parsed)
+    }
+}
+
+/// Splits `subject` around the matches of `pattern` within the scope, returning the ranges of
+/// the pieces, which together with the matches cover all of `subject`
+fn split(subject: &[u8], pattern: &Pattern, options: &SplitOptions) -> Vec<Range<usize>> {
+    let haystack = &subject[..options.scope.end];
+    let mut pieces = vec![];
+    let mut start = 0;
+    let mut pos = options.scope.start;
+    while let Some(found) = pattern.find(haystack, pos) {
+        pieces.push(start..found.start);
+        start = found.end;
+        pos = found.end;
+        if !options.global {
+            break;
+        }
+    }
+    pieces.push(start..subject.len());
+
+    if options.trim_all {
+        pieces.retain(|piece| !piece.is_empty());
+    } else if options.trim {
+        while pieces.last().map_or(false, |piece| piece.is_empty()) {
+            pieces.pop();
+        }
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary(bytes: &[u8]) -> Term {
+        let binary: OpaqueTerm = BinaryData::from_bytes(bytes).into();
+        binary.into()
+    }
+
+    fn list(elements: &[Term], process: &Process) -> OpaqueTerm {
+        let elements = elements.iter().map(|t| (*t).into()).collect::<Vec<_>>();
+        match Cons::from_slice(&elements, process).unwrap() {
+            None => OpaqueTerm::NIL,
+            Some(cons) => cons.into(),
+        }
+    }
+
+    fn process() -> Process {
+        use firefly_rt::process::ProcessId;
+
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn pattern(bytes: &[u8]) -> Pattern {
+        Pattern::from_term(binary(bytes).into()).unwrap()
+    }
+
+    fn options(global: bool, trim: bool, trim_all: bool, size: usize) -> SplitOptions {
+        SplitOptions {
+            scope: 0..size,
+            global,
+            trim,
+            trim_all,
+        }
+    }
+
+    /// Splits `subject`, returning the pieces as byte slices
+    fn pieces<'a>(subject: &'a [u8], pattern: &Pattern, options: &SplitOptions) -> Vec<&'a [u8]> {
+        split(subject, pattern, options)
+            .into_iter()
+            .map(|range| &subject[range])
+            .collect()
+    }
+
+    #[test]
+    fn patterns_are_found_at_the_boundaries() {
+        let subject = b"abcxyzabc";
+        let abc = pattern(b"abc");
+
+        assert_eq!(abc.find(subject, 0), Some(0..3));
+        assert_eq!(abc.find(subject, 1), Some(6..9));
+        assert_eq!(abc.find(subject, 7), None);
+        assert_eq!(pattern(b"abcxyzabc").find(subject, 0), Some(0..9));
+        assert_eq!(pattern(b"abcxyzabcd").find(subject, 0), None);
+
+        let global = options(true, false, false, subject.len());
+        assert_eq!(
+            pieces(subject, &abc, &global),
+            vec![&b""[..], &b"xyz"[..], &b""[..]]
+        );
+    }
+
+    #[test]
+    fn empty_patterns_are_rejected() {
+        let process = process();
+
+        assert!(Pattern::from_term(binary(b"").into()).is_none());
+        assert!(Pattern::from_term(OpaqueTerm::NIL).is_none());
+        let patterns = list(&[binary(b"a"), binary(b"")], &process);
+        assert!(Pattern::from_term(patterns).is_none());
+        assert!(Pattern::from_term(Term::Int(1).into()).is_none());
+    }
+
+    #[test]
+    fn overlapping_matches_are_not_counted_twice() {
+        let subject = b"aaaaa";
+        let global = options(true, false, false, subject.len());
+
+        assert_eq!(
+            pieces(subject, &pattern(b"aa"), &global),
+            vec![&b""[..], &b""[..], &b"a"[..]]
+        );
+    }
+
+    #[test]
+    fn the_longest_of_several_patterns_is_used() {
+        let process = process();
+        let patterns = list(&[binary(b"ab"), binary(b"abc"), binary(b"c")], &process);
+        let patterns = Pattern::from_term(patterns).unwrap();
+
+        assert_eq!(patterns.find(b"xabcd", 0), Some(1..4));
+        assert_eq!(patterns.find(b"xcab", 0), Some(1..2));
+
+        let subject = b"1ab2abc3c4";
+        let global = options(true, false, false, subject.len());
+        assert_eq!(
+            pieces(subject, &patterns, &global),
+            vec![&b"1"[..], &b"2"[..], &b"3"[..], &b"4"[..]]
+        );
+    }
+
+    #[test]
+    fn only_the_first_match_is_split_unless_global() {
+        let subject = b"a,b,c";
+        let comma = pattern(b",");
+
+        let first = options(false, false, false, subject.len());
+        assert_eq!(
+            pieces(subject, &comma, &first),
+            vec![&b"a"[..], &b"b,c"[..]]
+        );
+    }
+
+    #[test]
+    fn trim_removes_trailing_empty_pieces() {
+        let subject = b",a,,b,,";
+        let comma = pattern(b",");
+
+        let trim = options(true, true, false, subject.len());
+        assert_eq!(
+            pieces(subject, &comma, &trim),
+            vec![&b""[..], &b"a"[..], &b""[..], &b"b"[..]]
+        );
+        let trim_all = options(true, false, true, subject.len());
+        assert_eq!(
+            pieces(subject, &comma, &trim_all),
+            vec![&b"a"[..], &b"b"[..]]
+        );
+    }
+
+    #[test]
+    fn matches_are_clipped_to_the_scope() {
+        let process = process();
+        let subject = b"banana";
+        let scoped = |start: i64, length: i64| {
+            let part = Tuple::from_slice(
+                &[Term::Int(start).into(), Term::Int(length).into()],
+                &process,
+            )
+            .unwrap();
+            let option = Tuple::from_slice(&[atoms::Scope.into(), part.into()], &process).unwrap();
+            let options = list(&[Term::Tuple(option), Term::Atom(atoms::Global)], &process);
+            SplitOptions::from_term(options, subject.len())
+        };
+
+        // The match must be entirely within the scope, but the pieces cover the whole subject
+        let options = scoped(2, 3).unwrap();
+        assert_eq!(options.scope, 2..5);
+        assert_eq!(
+            pieces(subject, &pattern(b"a"), &options),
+            vec![&b"ban"[..], &b"na"[..]]
+        );
+        assert_eq!(
+            pieces(subject, &pattern(b"na"), &options),
+            vec![&b"ba"[..], &b"na"[..]]
+        );
+
+        // A negative length covers the bytes before the start
+        assert_eq!(scoped(6, -2).unwrap().scope, 4..6);
+        assert!(scoped(5, 2).is_none());
+        assert!(scoped(1, -2).is_none());
+        assert!(scoped(-1, 2).is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+use firefly_alloc::gc::GcBox;
+use firefly_rt::backtrace::Trace;
+use firefly_rt::function::ErlangResult;
+use firefly_rt::term::*;
+
+use crate::ets::{self, Access, EtsError, TableOptions, TableRef, TableType};
+use crate::scheduler;
+
+use super::badarg;
+
+#[export_name = "ets:new/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn new2(name: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let Term::Atom(name) = name.into() else { return badarg(Trace::capture()); };
+    let Ok(options) = parse_options(options) else { return badarg(Trace::capture()); };
+
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+
+        let id = scheduler.next_reference_id();
+        match ets::write().new_table(id, name, proc.pid(), options) {
+            Ok(()) if options.named => ErlangResult::Ok(name.into()),
+            Ok(()) => {
+                let reference = GcBox::new_in(Reference::Local { id }, proc).unwrap();
+                ErlangResult::Ok(Term::Reference(reference).into())
+            }
+            Err(_) => badarg(Trace::capture()),
+        }
+    })
+}
+
+#[export_name = "ets:insert/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn insert2(table: OpaqueTerm, objects: OpaqueTerm) -> ErlangResult {
+    let Ok(table) = table_ref(table) else { return badarg(Trace::capture()); };
+    let objects = match objects.into() {
+        Term::Tuple(tuple) => vec![tuple],
+        Term::Nil => vec![],
+        Term::Cons(cons) => {
+            let cons = unsafe { cons.as_ref() };
+            let tuples = cons
+                .iter()
+                .map(|result| match result {
+                    Ok(Term::Tuple(tuple)) => Ok(tuple),
+                    _ => Err(()),
+                })
+                .collect::<Result<Vec<NonNull<Tuple>>, _>>();
+            let Ok(tuples) = tuples else { return badarg(Trace::capture()); };
+            tuples
+        }
+        _ => return badarg(Trace::capture()),
+    };
+
+    let caller = current_pid();
+    into_result(
+        ets::write()
+            .insert(table, caller, &objects)
+            .map(|_| true.into()),
+    )
+}
+
+#[export_name = "ets:lookup/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn lookup2(table: OpaqueTerm, key: OpaqueTerm) -> ErlangResult {
+    let Ok(table) = table_ref(table) else { return badarg(Trace::capture()); };
+
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+
+        // The objects are copied onto our heap while the tables are locked, so they can't be
+        // deleted meanwhile
+        let found = ets::read().lookup(table, proc.pid(), key.into(), proc);
+        into_result(
+            found.map(|objects| match Cons::from_slice(&objects, proc).unwrap() {
+                None => OpaqueTerm::NIL,
+                Some(cons) => cons.into(),
+            }),
+        )
+    })
+}
+
+#[export_name = "ets:delete/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn delete1(table: OpaqueTerm) -> ErlangResult {
+    let Ok(table) = table_ref(table) else { return badarg(Trace::capture()); };
+
+    let caller = current_pid();
+    into_result(ets::write().delete(table, caller).map(|_| true.into()))
+}
+
+#[export_name = "ets:delete/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn delete2(table: OpaqueTerm, key: OpaqueTerm) -> ErlangResult {
+    let Ok(table) = table_ref(table) else { return badarg(Trace::capture()); };
+
+    let caller = current_pid();
+    into_result(
+        ets::write()
+            .delete_key(table, caller, key.into())
+            .map(|_| true.into()),
+    )
+}
+
+fn current_pid() -> ProcessId {
+    scheduler::with_current(|scheduler| scheduler.current_process().pid())
+}
+
+fn into_result(result: Result<OpaqueTerm, EtsError>) -> ErlangResult {
+    match result {
+        Ok(term) => ErlangResult::Ok(term),
+        Err(_) => badarg(Trace::capture()),
+    }
+}
+
+/// Tables are referred to by the reference returned from `ets:new/2`, or by name if named
+fn table_ref(table: OpaqueTerm) -> Result<TableRef, ()> {
+    match table.into() {
+        Term::Atom(name) => Ok(TableRef::Name(name)),
+        Term::Reference(reference) => Ok(TableRef::Id(reference.id())),
+        _ => Err(()),
+    }
+}
+
+/// Parses the options given to `ets:new/2`.
+///
+/// Options that only tune performance are accepted and ignored, but the `bag` and `duplicate_bag`
+/// table types, and any other options which would change the behavior of the table, are not
+/// supported.
+fn parse_options(options: OpaqueTerm) -> Result<TableOptions, ()> {
+    let mut parsed = TableOptions::default();
+
+    let cons = match options.into() {
+        Term::Nil => return Ok(parsed),
+        Term::Cons(cons) => unsafe { cons.as_ref() },
+        _ => return Err(()),
+    };
+
+    for option in cons.iter() {
+        match option.map_err(|_| ())? {
+            Term::Atom(option) => match option.as_str() {
+                "set" => parsed.table_type = TableType::Set,
+                "ordered_set" => parsed.table_type = TableType::OrderedSet,
+                "public" => parsed.access = Access::Public,
+                "protected" => parsed.access = Access::Protected,
+                "private" => parsed.access = Access::Private,
+                "named_table" => parsed.named = true,
+                "compressed" => (),
+                _ => return Err(()),
+            },
+            Term::Tuple(tuple) => {
+                let tuple = unsafe { tuple.as_ref() };
+                let [tag, value] = tuple.as_slice() else { return Err(()); };
+                let Term::Atom(tag) = (*tag).into() else { return Err(()); };
+                match (tag.as_str(), (*value).into()) {
+                    ("keypos", Term::Int(keypos)) => {
+                        let keypos = OneBasedIndex::try_from(keypos).map_err(|_| ())?;
+                        parsed.keypos = keypos.into();
+                    }
+                    (
+                        "read_concurrency" | "write_concurrency" | "decentralized_counters",
+                        Term::Bool(_),
+                    ) => (),
+                    _ => return Err(()),
+                }
+            }
+            _ => return Err(()),
+        }
+    }
+
+    Ok(parsed)
+}
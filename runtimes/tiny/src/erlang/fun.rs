@@ -0,0 +1,273 @@
+use firefly_alloc::gc::GcBox;
+use firefly_rt::backtrace::Trace;
+use firefly_rt::function::ErlangResult;
+use firefly_rt::process::Process;
+use firefly_rt::term::*;
+
+use crate::scheduler;
+
+use super::badarg;
+
+/// Constructs `fun Module:Function/Arity`
+///
+/// The function is not looked up here, but each time the fun is called, so it is not an error for
+/// it to not exist (yet).
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:make_fun/3"]
+pub extern "C-unwind" fn make_fun3(
+    module: OpaqueTerm,
+    function: OpaqueTerm,
+    arity: OpaqueTerm,
+) -> ErlangResult {
+    scheduler::with_current_process(|proc| match make_fun(module, function, arity, proc) {
+        Ok(fun) => ErlangResult::Ok(fun),
+        Err(_) => badarg(Trace::capture()),
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:fun_info/1"]
+pub extern "C-unwind" fn fun_info1(fun: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current_process(|proc| match fun_info(fun, proc) {
+        Ok(info) => ErlangResult::Ok(info),
+        Err(_) => badarg(Trace::capture()),
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:fun_to_list/1"]
+pub extern "C-unwind" fn fun_to_list1(fun: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current_process(|proc| match fun_to_list(fun, proc) {
+        Ok(list) => ErlangResult::Ok(list),
+        Err(_) => badarg(Trace::capture()),
+    })
+}
+
+fn make_fun(
+    module: OpaqueTerm,
+    function: OpaqueTerm,
+    arity: OpaqueTerm,
+    process: &Process,
+) -> Result<OpaqueTerm, ()> {
+    let Term::Atom(module) = module.into() else { return Err(()); };
+    let Term::Atom(function) = function.into() else { return Err(()); };
+    let Term::Int(arity) = arity.into() else { return Err(()); };
+    let arity = u8::try_from(arity).map_err(|_| ())?;
+
+    let fun = ExternalFun::new(module, function, arity);
+    Ok(GcBox::new_in(fun, process).unwrap().into())
+}
+
+/// Returns the information `erlang:fun_info/1` does, except for that which only makes sense for
+/// funs defined in BEAM modules, i.e. the pid, index and uniq of local funs
+fn fun_info(fun: OpaqueTerm, process: &Process) -> Result<OpaqueTerm, ()> {
+    let (module, name, arity, env, r#type) = match fun.into() {
+        Term::Closure(fun) => {
+            let env = fun
+                .env()
+                .iter()
+                .map(|term| (*term).into())
+                .collect::<Vec<Term>>();
+            let env = list(&env, process);
+            (
+                fun.module,
+                fun.name,
+                fun.effective_arity(),
+                env,
+                atoms::Local,
+            )
+        }
+        Term::ExternalFun(fun) => (
+            fun.module,
+            fun.function,
+            fun.arity as usize,
+            OpaqueTerm::NIL,
+            atoms::External,
+        ),
+        _ => return Err(()),
+    };
+
+    let items = [
+        (atoms::Module, module.into()),
+        (atoms::Name, name.into()),
+        (atoms::Arity, Term::Int(arity as i64).into()),
+        (atoms::Env, env),
+        (atoms::Type, r#type.into()),
+    ];
+    let items = items
+        .iter()
+        .map(|(key, value)| {
+            Term::Tuple(Tuple::from_slice(&[(*key).into(), *value], process).unwrap())
+        })
+        .collect::<Vec<Term>>();
+    Ok(list(&items, process))
+}
+
+fn fun_to_list(fun: OpaqueTerm, process: &Process) -> Result<OpaqueTerm, ()> {
+    let fun: Term = fun.into();
+    if !matches!(fun, Term::Closure(_) | Term::ExternalFun(_)) {
+        return Err(());
+    }
+    match Cons::charlist_from_str(&fun.to_string(), process).unwrap() {
+        None => Ok(OpaqueTerm::NIL),
+        Some(cons) => Ok(cons.into()),
+    }
+}
+
+fn list(elements: &[Term], process: &Process) -> OpaqueTerm {
+    match Cons::from_slice(elements, process).unwrap() {
+        None => OpaqueTerm::NIL,
+        Some(cons) => cons.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use firefly_rt::cmp::ExactEq;
+    use firefly_rt::function::{self, FunctionSymbol};
+
+    use crate::erlang::{apply2, is_function2};
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn atom(name: &str) -> OpaqueTerm {
+        Atom::try_from(name).unwrap().into()
+    }
+
+    fn int(i: i64) -> OpaqueTerm {
+        Term::Int(i).into()
+    }
+
+    fn external_fun(module: &str, function: &str, arity: i64, process: &Process) -> OpaqueTerm {
+        make_fun(atom(module), atom(function), int(arity), process).unwrap()
+    }
+
+    fn list_elements(list: OpaqueTerm) -> Vec<Term> {
+        match list.into() {
+            Term::Nil => vec![],
+            Term::Cons(cons) => unsafe { cons.as_ref() }
+                .iter()
+                .map(|result| result.unwrap())
+                .collect(),
+            other => panic!("expected a list, but got {:?}", other),
+        }
+    }
+
+    fn string(list: OpaqueTerm) -> String {
+        list_elements(list)
+            .into_iter()
+            .map(|term| match term {
+                Term::Int(c) => char::from_u32(c as u32).unwrap(),
+                other => panic!("expected a character, but got {:?}", other),
+            })
+            .collect()
+    }
+
+    extern "C-unwind" fn double(x: OpaqueTerm) -> ErlangResult {
+        let Term::Int(x) = x.into() else { panic!("expected an integer"); };
+        ErlangResult::Ok(Term::Int(x * 2).into())
+    }
+
+    #[test]
+    fn external_funs_are_equal_by_mfa() {
+        let process = process();
+        let abs = external_fun("erlang", "abs", 1, &process);
+        let other_abs = external_fun("erlang", "abs", 1, &process);
+
+        assert_ne!(abs.raw(), other_abs.raw());
+        assert_eq!(Term::from(abs), Term::from(other_abs));
+        assert!(Term::from(abs).exact_eq(&other_abs.into()));
+        assert_ne!(
+            Term::from(abs),
+            Term::from(external_fun("erlang", "abs", 2, &process))
+        );
+        assert_ne!(
+            Term::from(abs),
+            Term::from(external_fun("math", "abs", 1, &process))
+        );
+    }
+
+    #[test]
+    fn make_fun_rejects_invalid_arguments() {
+        let process = process();
+
+        assert!(make_fun(int(1), atom("abs"), int(1), &process).is_err());
+        assert!(make_fun(atom("erlang"), int(1), int(1), &process).is_err());
+        assert!(make_fun(atom("erlang"), atom("abs"), atom("one"), &process).is_err());
+        assert!(make_fun(atom("erlang"), atom("abs"), int(-1), &process).is_err());
+        assert!(make_fun(atom("erlang"), atom("abs"), int(256), &process).is_err());
+    }
+
+    #[test]
+    fn external_funs_are_functions_of_their_declared_arity() {
+        let process = process();
+        let fun = external_fun("erlang", "abs", 1, &process);
+
+        assert_eq!(fun.r#typeof(), TermType::Closure);
+        assert_eq!(is_function2(fun, int(1)), ErlangResult::Ok(true.into()));
+        assert_eq!(is_function2(fun, int(2)), ErlangResult::Ok(false.into()));
+    }
+
+    #[test]
+    fn external_funs_are_resolved_when_called() {
+        let process = process();
+        // The fun is constructed before its module is loaded
+        let fun = external_fun("fun_late_binding_test", "double", 1, &process);
+        let args = list(&[Term::Int(21)], &process);
+
+        let ErlangResult::Err(ptr) = apply2(fun, args) else { panic!("expected undef"); };
+        let exception = unsafe { Box::from_raw(ptr.as_ptr()) };
+        assert_eq!(exception.kind(), atoms::Error);
+        assert_eq!(exception.reason(), Term::Atom(atoms::Undef));
+
+        let symbols = Box::leak(Box::new([FunctionSymbol {
+            module: Atom::try_from("fun_late_binding_test").unwrap(),
+            function: Atom::try_from("double").unwrap(),
+            arity: 1,
+            ptr: double as *const (),
+        }]));
+        let range = symbols.as_ptr_range();
+        assert!(unsafe { function::init(range.start, range.end) });
+
+        assert_eq!(apply2(fun, args), ErlangResult::Ok(int(42)));
+    }
+
+    #[test]
+    fn fun_info_reports_external_funs() {
+        let process = process();
+        let fun = external_fun("erlang", "abs", 1, &process);
+
+        let info = list_elements(fun_info(fun, &process).unwrap());
+        let expected = [
+            ("module", atom("erlang")),
+            ("name", atom("abs")),
+            ("arity", int(1)),
+            ("env", OpaqueTerm::NIL),
+            ("type", atom("external")),
+        ];
+        assert_eq!(info.len(), expected.len());
+        for (item, (key, value)) in info.iter().zip(expected.iter()) {
+            let Term::Tuple(item) = item else { panic!("expected a tuple, but got {:?}", item); };
+            let item = unsafe { item.as_ref() };
+            assert_eq!(item.as_slice(), &[atom(key), *value]);
+        }
+
+        assert!(fun_info(atom("erlang"), &process).is_err());
+    }
+
+    #[test]
+    fn fun_to_list_prints_external_funs_as_fun_expressions() {
+        let process = process();
+        let fun = external_fun("erlang", "abs", 1, &process);
+
+        assert_eq!(
+            string(fun_to_list(fun, &process).unwrap()),
+            "fun erlang:abs/1"
+        );
+        assert!(fun_to_list(atom("erlang"), &process).is_err());
+    }
+}
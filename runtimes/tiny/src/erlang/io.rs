@@ -0,0 +1,627 @@
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use firefly_rt::backtrace::Trace;
+use firefly_rt::function::{ErlangResult, ModuleFunctionArity};
+use firefly_rt::term::*;
+
+use super::badarg;
+
+/// The width of the lines `~p` breaks terms to fit within, when no field width is given
+const LINE_WIDTH: usize = 80;
+
+#[export_name = "io:format/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn format1(format: OpaqueTerm) -> ErlangResult {
+    match format_to_string(format, OpaqueTerm::NIL) {
+        Some(output) => put(&output),
+        None => badarg_called_as("io:format/1", &[format]),
+    }
+}
+
+#[export_name = "io:format/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn format2(format: OpaqueTerm, args: OpaqueTerm) -> ErlangResult {
+    match format_to_string(format, args) {
+        Some(output) => put(&output),
+        None => badarg_called_as("io:format/2", &[format, args]),
+    }
+}
+
+#[export_name = "io:put_chars/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn put_chars1(chars: OpaqueTerm) -> ErlangResult {
+    let mut output = String::new();
+    match write_chardata(chars.into(), true, &mut output) {
+        Some(()) => put(&output),
+        None => badarg_called_as("io:put_chars/1", &[chars]),
+    }
+}
+
+fn put(output: &str) -> ErlangResult {
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(output.as_bytes()).unwrap();
+    ErlangResult::Ok(atoms::Ok.into())
+}
+
+/// Raises `badarg`, with the function and the arguments it was called with, which include the
+/// format string, as the top frame of the stacktrace
+fn badarg_called_as(mfa: &str, args: &[OpaqueTerm]) -> ErlangResult {
+    let mfa: ModuleFunctionArity = mfa.parse().unwrap();
+    let trace = Trace::capture();
+    trace.set_top_frame(&mfa, args);
+    badarg(trace)
+}
+
+/// Formats the list of `args` according to `format`, which may be an atom, a binary, or a
+/// charlist, or returns `None` if either is invalid
+fn format_to_string(format: OpaqueTerm, args: OpaqueTerm) -> Option<String> {
+    let format = match format.into() {
+        Term::Atom(atom) => atom.as_str().to_string(),
+        term => {
+            let mut format = String::new();
+            write_chardata(term, true, &mut format)?;
+            format
+        }
+    };
+    let args = match args.into() {
+        Term::Nil => vec![],
+        Term::Cons(ptr) => unsafe { ptr.as_ref() }
+            .iter()
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?,
+        _ => return None,
+    };
+
+    self::format(&format, &args)
+}
+
+/// Formats `args` according to the control sequences of `format`.
+///
+/// Returns `None` if a control sequence is invalid or unsupported, if an argument is not of the
+/// type its control sequence requires, or if there are more or fewer arguments than the control
+/// sequences use.
+fn format(format: &str, args: &[Term]) -> Option<String> {
+    let mut out = String::with_capacity(format.len());
+    let mut args = args.iter().copied();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            out.push(c);
+            continue;
+        }
+        let control = ControlSequence::parse(&mut chars, &mut args)?;
+        control.write(&mut args, &mut out)?;
+    }
+
+    match args.next() {
+        None => Some(out),
+        Some(_) => None,
+    }
+}
+
+/// A control sequence of a format string, i.e. `~F.P.PadModC`, of which only the control
+/// character `C` is required. `F`, `P` and `Pad` may be `*`, in which case they are taken from
+/// the arguments.
+#[derive(Debug)]
+struct ControlSequence {
+    /// The field width, `F`
+    width: Option<usize>,
+    /// Whether `F` was negative, in which case the text is left-justified within the field
+    left: bool,
+    /// The precision, `P`, whose meaning depends on the control character
+    precision: Option<usize>,
+    /// The character the field is padded with
+    pad: char,
+    /// Whether the `t` modifier was given, so that characters are Unicode rather than latin1
+    unicode: bool,
+    control: char,
+}
+impl ControlSequence {
+    fn parse<I>(chars: &mut Peekable<Chars<'_>>, args: &mut I) -> Option<Self>
+    where
+        I: Iterator<Item = Term>,
+    {
+        let (width, left) = match Self::parse_field(chars, args)? {
+            None => (None, false),
+            Some(width) => (Some(width.unsigned_abs()), width < 0),
+        };
+        let mut precision = None;
+        let mut pad = ' ';
+        if chars.next_if_eq(&'.').is_some() {
+            precision = match Self::parse_field(chars, args)? {
+                None => None,
+                Some(precision) => Some(usize::try_from(precision).ok()?),
+            };
+            if chars.next_if_eq(&'.').is_some() {
+                pad = match chars.next()? {
+                    '*' => args.next()?.as_char().ok()?,
+                    pad => pad,
+                };
+            }
+        }
+        let unicode = chars.next_if_eq(&'t').is_some();
+        let control = chars.next()?;
+
+        Some(Self {
+            width,
+            left,
+            precision,
+            pad,
+            unicode,
+            control,
+        })
+    }
+
+    /// Parses an optional, possibly negative, field width or precision
+    fn parse_field<I>(chars: &mut Peekable<Chars<'_>>, args: &mut I) -> Option<Option<isize>>
+    where
+        I: Iterator<Item = Term>,
+    {
+        if chars.next_if_eq(&'*').is_some() {
+            let Term::Int(value) = args.next()? else { return None; };
+            return Some(Some(isize::try_from(value).ok()?));
+        }
+
+        let negative = chars.next_if_eq(&'-').is_some();
+        let mut value: Option<isize> = None;
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            let digit = digit.to_digit(10).unwrap() as isize;
+            value = Some(value.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+        }
+        match value {
+            None if negative => None,
+            None => Some(None),
+            Some(value) if negative => Some(Some(-value)),
+            Some(value) => Some(Some(value)),
+        }
+    }
+
+    fn write<I>(&self, args: &mut I, out: &mut String) -> Option<()>
+    where
+        I: Iterator<Item = Term>,
+    {
+        match self.control {
+            '~' => out.push('~'),
+            'n' => out.push('\n'),
+            'c' => {
+                let Term::Int(c) = args.next()? else { return None; };
+                // Without the `t` modifier, only the low byte of the character is printed
+                let c = if self.unicode { c } else { c & 0xff };
+                let c = char::from_u32(u32::try_from(c).ok()?)?;
+                let count = self.precision.or(self.width).unwrap_or(1);
+                let chars = std::iter::repeat(c).take(count).collect::<String>();
+                self.adjust(&chars, out);
+            }
+            'b' => {
+                let base = self.precision.unwrap_or(10);
+                if !(2..=36).contains(&base) {
+                    return None;
+                }
+                let integer: Integer = args.next()?.try_into().ok()?;
+                self.adjust_term(&integer.to_string_radix(base as u32), out);
+            }
+            's' => {
+                let mut chars = String::new();
+                match args.next()? {
+                    Term::Atom(atom) => chars.push_str(atom.as_str()),
+                    Term::Bool(b) => write!(chars, "{}", b).unwrap(),
+                    term => write_chardata(term, self.unicode, &mut chars)?,
+                }
+                // The string is truncated to the precision, which defaults to the field width
+                let chars = match self.precision.or(self.width) {
+                    Some(precision) => chars.chars().take(precision).collect(),
+                    None => chars,
+                };
+                self.adjust(&chars, out);
+            }
+            'w' => {
+                let term = args.next()?;
+                self.adjust_term(&term.to_string(), out);
+            }
+            'p' => {
+                // The field width of `~p` is the width of the lines to fit the term within
+                let term = args.next()?;
+                let width = self.width.unwrap_or(LINE_WIDTH);
+                pretty(term, column(out), width, out);
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Pads `text` to the field width, if there is one and `text` is narrower
+    fn adjust(&self, text: &str, out: &mut String) {
+        let len = text.chars().count();
+        let padding = match self.width {
+            Some(width) if width > len => width - len,
+            _ => 0,
+        };
+        if self.left {
+            out.push_str(text);
+        }
+        out.extend(std::iter::repeat(self.pad).take(padding));
+        if !self.left {
+            out.push_str(text);
+        }
+    }
+
+    /// Pads `text` to the field width like `adjust`, but fills the field with `*` if `text` is
+    /// too wide for it, as terms are never truncated
+    fn adjust_term(&self, text: &str, out: &mut String) {
+        match self.width {
+            Some(width) if width < text.chars().count() => {
+                out.extend(std::iter::repeat('*').take(width));
+            }
+            _ => self.adjust(text, out),
+        }
+    }
+}
+
+/// Returns the column that text appended to `out` will start at
+fn column(out: &str) -> usize {
+    let line = match out.rfind('\n') {
+        Some(newline) => &out[(newline + 1)..],
+        None => out,
+    };
+    line.chars().count()
+}
+
+/// Writes `term` as `~p` does, starting at `column`.
+///
+/// A tuple, list or map which doesn't fit before `width` is broken over several lines, with one
+/// element on each, aligned after its opening bracket, and the same is done for each element
+/// which doesn't fit in turn. Anything else is written as is, even if it doesn't fit.
+fn pretty(term: Term, column: usize, width: usize, out: &mut String) {
+    // On a single line, `~p` writes terms as they are displayed
+    let flat = term.to_string();
+    if column + flat.chars().count() <= width {
+        out.push_str(&flat);
+        return;
+    }
+
+    match term {
+        Term::Tuple(ptr) => {
+            let tuple = unsafe { ptr.as_ref() };
+            out.push('{');
+            pretty_elements(tuple.iter(), column + 1, width, out);
+            out.push('}');
+        }
+        Term::Cons(ptr) if !unsafe { ptr.as_ref() }.is_printable_string() => {
+            let cons = unsafe { ptr.as_ref() };
+            let mut tail = None;
+            let elements = cons.iter().map_while(|element| match element {
+                Ok(element) => Some(element),
+                Err(improper) => {
+                    tail = Some(improper.tail);
+                    None
+                }
+            });
+            out.push('[');
+            pretty_elements(elements, column + 1, width, out);
+            if let Some(tail) = tail {
+                out.push('|');
+                pretty(tail, self::column(out), width, out);
+            }
+            out.push(']');
+        }
+        Term::Map(map) => {
+            out.push_str("#{");
+            for (i, key) in map.sorted_keys().into_iter().enumerate() {
+                if i > 0 {
+                    newline(column + 2, out);
+                }
+                write!(out, "{} => ", key).unwrap();
+                pretty(map.get(key).unwrap(), self::column(out), width, out);
+            }
+            out.push('}');
+        }
+        _ => out.push_str(&flat),
+    }
+}
+
+fn pretty_elements<I>(elements: I, column: usize, width: usize, out: &mut String)
+where
+    I: Iterator<Item = Term>,
+{
+    for (i, element) in elements.enumerate() {
+        if i > 0 {
+            newline(column, out);
+        }
+        pretty(element, column, width, out);
+    }
+}
+
+/// Ends an element of a broken tuple, list or map, so that the next starts at `column`
+fn newline(column: usize, out: &mut String) {
+    out.push_str(",\n");
+    out.extend(std::iter::repeat(' ').take(column));
+}
+
+/// Appends `term` as chardata to `out`, i.e. a binary, or a possibly deep list of characters and
+/// binaries, any of which may be improper as long as the tail is a binary.
+///
+/// When `unicode` is true, characters may be any codepoint and binaries are decoded as UTF-8, as
+/// `io:put_chars/1` and `~ts` do, otherwise both are latin1, as with `~s`.
+///
+/// Returns `None` if `term` is not chardata.
+fn write_chardata(term: Term, unicode: bool, out: &mut String) -> Option<()> {
+    // The tails of the lists still to be visited once the nested list being visited is done
+    let mut pending: Vec<Term> = Vec::new();
+    let mut list = term;
+
+    loop {
+        match list {
+            Term::Nil => (),
+            Term::Cons(ptr) => {
+                let cons = unsafe { ptr.as_ref() };
+                match cons.head() {
+                    Term::Int(c) => {
+                        let c = u32::try_from(c).ok()?;
+                        if !unicode && c > 0xff {
+                            return None;
+                        }
+                        out.push(char::from_u32(c)?);
+                    }
+                    head @ (Term::Nil | Term::Cons(_)) => {
+                        pending.push(cons.tail());
+                        list = head;
+                        continue;
+                    }
+                    head => write_binary(head, unicode, out)?,
+                }
+                list = cons.tail();
+                continue;
+            }
+            tail => write_binary(tail, unicode, out)?,
+        }
+
+        match pending.pop() {
+            None => return Some(()),
+            Some(tail) => list = tail,
+        }
+    }
+}
+
+fn write_binary(term: Term, unicode: bool, out: &mut String) -> Option<()> {
+    let bits = term.as_bitstring()?;
+    if !bits.is_binary() {
+        return None;
+    }
+    if unicode {
+        let bytes = bits.bytes().collect::<Vec<u8>>();
+        out.push_str(std::str::from_utf8(&bytes).ok()?);
+    } else {
+        out.extend(bits.bytes().map(char::from));
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use firefly_rt::process::{Process, ProcessId};
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn int(i: i64) -> Term {
+        Term::Int(i)
+    }
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(Atom::try_from(name).unwrap())
+    }
+
+    fn tuple(elements: &[Term], process: &Process) -> Term {
+        let elements = elements.iter().map(|t| (*t).into()).collect::<Vec<_>>();
+        Term::Tuple(Tuple::from_slice(&elements, process).unwrap())
+    }
+
+    fn list(elements: &[Term], process: &Process) -> Term {
+        let elements = elements.iter().map(|t| (*t).into()).collect::<Vec<_>>();
+        match Cons::from_slice(&elements, process).unwrap() {
+            None => Term::Nil,
+            Some(cons) => Term::Cons(cons),
+        }
+    }
+
+    /// Allocates a single cell, so `tail` can make the list improper
+    fn cons(head: Term, tail: Term, process: &Process) -> Term {
+        let ptr = Cons::new_in(process).unwrap();
+        unsafe {
+            ptr.as_ptr().write(Cons::cons(head, tail));
+        }
+        Term::Cons(ptr)
+    }
+
+    fn charlist(s: &str, process: &Process) -> Term {
+        match Cons::charlist_from_str(s, process).unwrap() {
+            None => Term::Nil,
+            Some(cons) => Term::Cons(cons),
+        }
+    }
+
+    fn binary(s: &str) -> Term {
+        let binary: OpaqueTerm = BinaryData::from_str(s).into();
+        binary.into()
+    }
+
+    fn map(entries: &[(Term, Term)], process: &Process) -> Term {
+        Term::Map(Map::new_from_iter_in(entries.iter().copied(), process).unwrap())
+    }
+
+    fn chardata(term: Term, unicode: bool) -> Option<String> {
+        let mut out = String::new();
+        write_chardata(term, unicode, &mut out).map(|()| out)
+    }
+
+    #[test]
+    fn plain_text_and_escapes_are_copied() {
+        assert_eq!(format("hello~n~~", &[]).as_deref(), Some("hello\n~"));
+    }
+
+    #[test]
+    fn nested_tuples_are_printed_without_spaces() {
+        let process = process();
+        let inner = tuple(
+            &[atom("b"), list(&[int(1), int(2), int(3)], &process)],
+            &process,
+        );
+        let outer = tuple(&[atom("a"), inner, charlist("str", &process)], &process);
+
+        assert_eq!(
+            format("~p", &[outer]).as_deref(),
+            Some("{a,{b,[1,2,3]},\"str\"}")
+        );
+    }
+
+    #[test]
+    fn maps_are_printed_in_key_order() {
+        let process = process();
+        let value = list(&[atom("x")], &process);
+        let m = map(&[(atom("b"), int(2)), (atom("a"), value)], &process);
+
+        assert_eq!(format("~p", &[m]).as_deref(), Some("#{a => [x],b => 2}"));
+    }
+
+    #[test]
+    fn charlists_are_printed_as_strings_only_when_printable() {
+        let process = process();
+        let printable = charlist("hello", &process);
+        let unprintable = list(&[int(1), int(2)], &process);
+        let improper = cons(int(1), int(2), &process);
+
+        assert_eq!(format("~p", &[printable]).as_deref(), Some("\"hello\""));
+        assert_eq!(format("~p", &[unprintable]).as_deref(), Some("[1,2]"));
+        assert_eq!(format("~p", &[improper]).as_deref(), Some("[1|2]"));
+        assert_eq!(format("~s", &[printable]).as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn binaries_are_printed_as_terms_or_characters() {
+        let bin = binary("héllo");
+
+        assert_eq!(
+            format("~p", &[binary("abc")]).as_deref(),
+            Some("<<\"abc\">>")
+        );
+        assert_eq!(format("~ts", &[bin]).as_deref(), Some("héllo"));
+        // Without the `t` modifier, each byte is a latin1 character
+        assert_eq!(format("~s", &[bin]).as_deref(), Some("hÃ©llo"));
+    }
+
+    #[test]
+    fn terms_which_do_not_fit_are_broken_over_lines() {
+        let process = process();
+        let inner = tuple(&[atom("cccccccccc"), atom("dddddddddd")], &process);
+        let outer = tuple(&[atom("aaaaaaaaaa"), atom("bbbbbbbbbb"), inner], &process);
+
+        assert_eq!(
+            format("~30p", &[outer]).as_deref(),
+            Some("{aaaaaaaaaa,\n bbbbbbbbbb,\n {cccccccccc,dddddddddd}}")
+        );
+        // Elements are aligned with the column the term starts at
+        assert_eq!(
+            format("x = ~30p", &[outer]).as_deref(),
+            Some("x = {aaaaaaaaaa,\n     bbbbbbbbbb,\n     {cccccccccc,dddddddddd}}")
+        );
+    }
+
+    #[test]
+    fn maps_which_do_not_fit_are_broken_over_lines() {
+        let process = process();
+        let m = map(
+            &[
+                (atom("key"), atom("aaaaaaaaaa")),
+                (atom("other"), atom("bbbbbbbbbb")),
+            ],
+            &process,
+        );
+
+        assert_eq!(
+            format("~20p", &[m]).as_deref(),
+            Some("#{key => aaaaaaaaaa,\n  other => bbbbbbbbbb}")
+        );
+    }
+
+    #[test]
+    fn field_widths_pad_and_truncate() {
+        assert_eq!(format("~5w|", &[atom("a")]).as_deref(), Some("    a|"));
+        assert_eq!(format("~-5w|", &[atom("a")]).as_deref(), Some("a    |"));
+        assert_eq!(format("~3w", &[int(12345)]).as_deref(), Some("***"));
+        assert_eq!(
+            format("~5.3s|", &[binary("abcdef")]).as_deref(),
+            Some("  abc|")
+        );
+        assert_eq!(format("~*w", &[int(3), int(7)]).as_deref(), Some("  7"));
+    }
+
+    #[test]
+    fn integers_are_printed_in_any_base() {
+        assert_eq!(format("~b", &[int(-42)]).as_deref(), Some("-42"));
+        assert_eq!(format("~.2b", &[int(5)]).as_deref(), Some("101"));
+        assert_eq!(format("~5.16.0b", &[int(255)]).as_deref(), Some("000FF"));
+        assert_eq!(format("~b", &[atom("a")]), None);
+        assert_eq!(format("~.37b", &[int(1)]), None);
+    }
+
+    #[test]
+    fn characters_are_repeated_to_the_precision() {
+        assert_eq!(format("~c", &[int('a' as i64)]).as_deref(), Some("a"));
+        assert_eq!(format("~3c", &[int('a' as i64)]).as_deref(), Some("aaa"));
+        assert_eq!(format("~tc", &[int('λ' as i64)]).as_deref(), Some("λ"));
+    }
+
+    #[test]
+    fn argument_count_mismatches_are_errors() {
+        assert_eq!(format("~p", &[]), None);
+        assert_eq!(format("~p", &[int(1), int(2)]), None);
+        assert_eq!(format("no controls", &[int(1)]), None);
+    }
+
+    #[test]
+    fn bad_control_sequences_are_errors() {
+        assert_eq!(format("~q", &[int(1)]), None);
+        assert_eq!(format("trailing ~", &[]), None);
+        assert_eq!(format("~-w", &[int(1)]), None);
+    }
+
+    #[test]
+    fn format_raises_badarg() {
+        let process = process();
+        let format: OpaqueTerm = charlist("~p~p", &process).into();
+        let args: OpaqueTerm = list(&[int(1)], &process).into();
+
+        let exception = match format2(format, args) {
+            ErlangResult::Err(exception) => unsafe { Box::from_raw(exception.as_ptr()) },
+            ErlangResult::Ok(term) => panic!("expected badarg, but got {:?}", term),
+        };
+        assert_eq!(exception.reason(), Term::Atom(atoms::Badarg));
+    }
+
+    #[test]
+    fn chardata_may_be_deep_and_improper() {
+        let process = process();
+        let tail = cons(int('e' as i64), binary("f"), &process);
+        let deep = list(&[charlist("ab", &process), binary("cd"), tail], &process);
+
+        assert_eq!(chardata(deep, true).as_deref(), Some("abcdef"));
+        assert_eq!(chardata(binary("bin"), false).as_deref(), Some("bin"));
+    }
+
+    #[test]
+    fn chardata_is_only_unicode_when_requested() {
+        let process = process();
+        let lambda = list(&[int('λ' as i64)], &process);
+
+        assert_eq!(chardata(lambda, true).as_deref(), Some("λ"));
+        assert_eq!(chardata(lambda, false), None);
+        assert_eq!(chardata(atom("atom"), true), None);
+    }
+}
@@ -1,6 +1,7 @@
 use std::ops::Deref;
 
 use firefly_rt::backtrace::Trace;
+use firefly_rt::cmp::ExactEq;
 use firefly_rt::function::ErlangResult;
 use firefly_rt::term::*;
 
@@ -8,6 +9,15 @@ use crate::scheduler;
 
 use super::badarg;
 
+/// Like the list BIFs in ERTS, a reduction is counted for each this many elements visited, so that
+/// long lists still make the process yield
+const ELEMENTS_PER_REDUCTION: usize = 16;
+
+/// Counts the reductions for visiting `len` elements, which may yield to the scheduler
+fn reduce(len: usize) {
+    scheduler::with_current(|scheduler| scheduler.reduce(1 + len / ELEMENTS_PER_REDUCTION))
+}
+
 #[export_name = "lists:reverse/2"]
 #[allow(improper_ctypes_definitions)]
 pub extern "C-unwind" fn reverse(list: OpaqueTerm, tail: OpaqueTerm) -> ErlangResult {
@@ -16,38 +26,94 @@ pub extern "C-unwind" fn reverse(list: OpaqueTerm, tail: OpaqueTerm) -> ErlangRe
         return ErlangResult::Ok(tail);
     }
 
+    let Term::Cons(cons) = list.into() else { return badarg(Trace::capture()); };
+    let cons = unsafe { cons.as_ref() };
+    let reversed: Result<_, ReverseError> = scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let mut builder = ListBuilder::with_tail(proc, tail);
+        let len = builder.reverse_onto(cons)?;
+        // We know we have at least one cell because the list in this branch is nonempty
+        Ok((builder.finish().unwrap(), len))
+    });
+
+    match reversed {
+        Ok((reversed, len)) => {
+            reduce(len);
+            ErlangResult::Ok(reversed.into())
+        }
+        Err(ReverseError::ImproperList) => badarg(Trace::capture()),
+        Err(ReverseError::AllocError) => panic!("unable to allocate reversed list"),
+    }
+}
+
+#[export_name = "lists:member/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn member(element: OpaqueTerm, list: OpaqueTerm) -> ErlangResult {
+    let cons = match list.into() {
+        Term::Nil => return ErlangResult::Ok(false.into()),
+        Term::Cons(cons) => unsafe { cons.as_ref() },
+        _ => return badarg(Trace::capture()),
+    };
+
+    let element: Term = element.into();
+    let mut len = 0;
+    for result in cons.iter() {
+        // The list is only required to be proper up to the element, if found
+        let Ok(candidate) = result else { return badarg(Trace::capture()); };
+        len += 1;
+        if candidate.exact_eq(&element) {
+            reduce(len);
+            return ErlangResult::Ok(true.into());
+        }
+    }
+
+    reduce(len);
+    ErlangResult::Ok(false.into())
+}
+
+#[export_name = "lists:keyfind/3"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn keyfind(
+    key: OpaqueTerm,
+    index: OpaqueTerm,
+    list: OpaqueTerm,
+) -> ErlangResult {
+    match keysearch(key, index, list) {
+        Ok(Some(tuple)) => ErlangResult::Ok(tuple.into()),
+        Ok(None) => ErlangResult::Ok(false.into()),
+        Err(()) => badarg(Trace::capture()),
+    }
+}
+
+#[export_name = "lists:keymember/3"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn keymember(
+    key: OpaqueTerm,
+    index: OpaqueTerm,
+    list: OpaqueTerm,
+) -> ErlangResult {
+    match keysearch(key, index, list) {
+        Ok(found) => ErlangResult::Ok(found.is_some().into()),
+        Err(()) => badarg(Trace::capture()),
+    }
+}
+
+/// Searches `list` for a tuple with `key` at `index`, as `lists:keyfind/3`, erroring if `index`
+/// isn't a positive integer, `list` isn't a list, or `list` is improper up to the match
+fn keysearch(key: OpaqueTerm, index: OpaqueTerm, list: OpaqueTerm) -> Result<Option<Term>, ()> {
+    // Only small integers can be positions, as no tuple could be big enough for anything larger
+    let Term::Int(index) = index.into() else { return Err(()); };
+    let index = OneBasedIndex::try_from(index).map_err(|_| ())?;
+
     match list.into() {
+        Term::Nil => Ok(None),
         Term::Cons(cons) => {
             let cons = unsafe { cons.as_ref() };
-            scheduler::with_current(|scheduler| {
-                let arc_proc = scheduler.current_process();
-                let proc = arc_proc.deref();
-                let mut current = None;
-                for item in cons.iter() {
-                    let head = item
-                        .map_err(|_| unsafe { badarg(Trace::capture()).unwrap_err_unchecked() })?;
-                    match current.take() {
-                        None => {
-                            // This is the first iteration
-                            let mut ptr = Cons::new_in(proc).unwrap();
-                            let cell = unsafe { ptr.as_mut() };
-                            cell.tail = tail;
-                            cell.head = head.into();
-                            current = Some(OpaqueTerm::from(ptr));
-                        }
-                        Some(tail) => {
-                            let mut next_ptr = Cons::new_in(proc).unwrap();
-                            let cell = unsafe { next_ptr.as_mut() };
-                            cell.tail = tail;
-                            cell.head = head.into();
-                            current = Some(OpaqueTerm::from(next_ptr));
-                        }
-                    }
-                }
-                // We know we have at least one cell because the list in this branch is nonempty
-                ErlangResult::Ok(current.unwrap())
-            })
+            let (visited, found) = cons.keysearch(index, key).map_err(|_| ())?;
+            reduce(visited);
+            Ok(found)
         }
-        _other => badarg(Trace::capture()),
+        _ => Err(()),
     }
 }
@@ -0,0 +1,346 @@
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+use firefly_alloc::gc::GcBox;
+use firefly_rt::backtrace::Trace;
+use firefly_rt::error::ErlangException;
+use firefly_rt::function::ErlangResult;
+use firefly_rt::process::Process;
+use firefly_rt::term::*;
+
+use crate::scheduler;
+
+use super::make_reason;
+
+/// The errors raised by the `maps` BIFs
+#[derive(Debug, Copy, Clone)]
+enum MapError {
+    /// `{badmap, Map}`, when an argument expected to be a map is not one
+    BadMap(OpaqueTerm),
+    /// `{badkey, Key}`, when a key required to be present in a map is not
+    BadKey(OpaqueTerm),
+}
+impl MapError {
+    fn raise(self) -> ErlangResult {
+        let reason = match self {
+            Self::BadMap(map) => make_reason(atoms::Badmap, map),
+            Self::BadKey(key) => make_reason(atoms::Badkey, key),
+        };
+        let err = ErlangException::new(atoms::Error, reason.into(), Trace::capture());
+        ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(err)) })
+    }
+}
+
+/// Converts the result of one of the helpers below into the result of a BIF
+fn into_result(result: Result<OpaqueTerm, MapError>) -> ErlangResult {
+    match result {
+        Ok(term) => ErlangResult::Ok(term),
+        Err(err) => err.raise(),
+    }
+}
+
+fn with_current_process<F>(fun: F) -> ErlangResult
+where
+    F: FnOnce(&Process) -> Result<OpaqueTerm, MapError>,
+{
+    let result = scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        fun(arc_proc.deref())
+    });
+    into_result(result)
+}
+
+fn to_map(map: OpaqueTerm) -> Result<GcBox<Map>, MapError> {
+    match map.into() {
+        Term::Map(m) => Ok(m),
+        _ => Err(MapError::BadMap(map)),
+    }
+}
+
+#[export_name = "maps:get/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn get2(key: OpaqueTerm, map: OpaqueTerm) -> ErlangResult {
+    into_result(get(key, map))
+}
+
+#[export_name = "maps:get/3"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn get3(
+    key: OpaqueTerm,
+    map: OpaqueTerm,
+    default: OpaqueTerm,
+) -> ErlangResult {
+    match get(key, map) {
+        Err(MapError::BadKey(_)) => ErlangResult::Ok(default),
+        result => into_result(result),
+    }
+}
+
+#[export_name = "maps:put/3"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn put3(key: OpaqueTerm, value: OpaqueTerm, map: OpaqueTerm) -> ErlangResult {
+    with_current_process(|process| put(key, value, map, process))
+}
+
+#[export_name = "maps:remove/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn remove2(key: OpaqueTerm, map: OpaqueTerm) -> ErlangResult {
+    with_current_process(|process| remove(key, map, process))
+}
+
+#[export_name = "maps:keys/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn keys1(map: OpaqueTerm) -> ErlangResult {
+    with_current_process(|process| keys(map, process))
+}
+
+#[export_name = "maps:values/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn values1(map: OpaqueTerm) -> ErlangResult {
+    with_current_process(|process| values(map, process))
+}
+
+#[export_name = "maps:merge/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn merge2(map1: OpaqueTerm, map2: OpaqueTerm) -> ErlangResult {
+    with_current_process(|process| merge(map1, map2, process))
+}
+
+fn get(key: OpaqueTerm, map: OpaqueTerm) -> Result<OpaqueTerm, MapError> {
+    let m = to_map(map)?;
+    m.get(Term::from(key))
+        .map(|value| value.into())
+        .ok_or(MapError::BadKey(key))
+}
+
+fn put(
+    key: OpaqueTerm,
+    value: OpaqueTerm,
+    map: OpaqueTerm,
+    process: &Process,
+) -> Result<OpaqueTerm, MapError> {
+    let m = to_map(map)?;
+    let updated = GcBox::new_in(m.insert(key.into(), value.into()), process).unwrap();
+    Ok(updated.into())
+}
+
+fn remove(key: OpaqueTerm, map: OpaqueTerm, process: &Process) -> Result<OpaqueTerm, MapError> {
+    let m = to_map(map)?;
+    let key: Term = key.into();
+    // Removing a key that isn't present leaves the map unchanged, so there is nothing to allocate
+    if !m.contains_key(key) {
+        return Ok(map);
+    }
+    let updated = GcBox::new_in(m.remove(key), process).unwrap();
+    Ok(updated.into())
+}
+
+fn keys(map: OpaqueTerm, process: &Process) -> Result<OpaqueTerm, MapError> {
+    let m = to_map(map)?;
+    Ok(list(&m.sorted_keys(), process))
+}
+
+fn values(map: OpaqueTerm, process: &Process) -> Result<OpaqueTerm, MapError> {
+    let m = to_map(map)?;
+    Ok(list(&m.sorted_values(), process))
+}
+
+fn merge(map1: OpaqueTerm, map2: OpaqueTerm, process: &Process) -> Result<OpaqueTerm, MapError> {
+    let m1 = to_map(map1)?;
+    let m2 = to_map(map2)?;
+    // Merging with an empty map is the identity, so we can return the other map as-is
+    if m2.is_empty() {
+        return Ok(map1);
+    }
+    if m1.is_empty() {
+        return Ok(map2);
+    }
+    let merged = GcBox::new_in(m1.merge(&m2), process).unwrap();
+    Ok(merged.into())
+}
+
+fn list(elements: &[Term], process: &Process) -> OpaqueTerm {
+    match Cons::from_slice(elements, process).unwrap() {
+        None => OpaqueTerm::NIL,
+        Some(cons) => cons.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use firefly_rt::cmp::ExactEq;
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn int(i: i64) -> OpaqueTerm {
+        Term::Int(i).into()
+    }
+
+    fn atom(name: &str) -> OpaqueTerm {
+        Atom::try_from(name).unwrap().into()
+    }
+
+    fn map(entries: &[(OpaqueTerm, OpaqueTerm)], process: &Process) -> OpaqueTerm {
+        let entries = entries.iter().map(|(k, v)| ((*k).into(), (*v).into()));
+        Map::new_from_iter_in(entries, process).unwrap().into()
+    }
+
+    fn list_elements(list: OpaqueTerm) -> Vec<Term> {
+        match list.into() {
+            Term::Nil => vec![],
+            Term::Cons(cons) => unsafe { cons.as_ref() }
+                .iter()
+                .map(|result| result.unwrap())
+                .collect(),
+            other => panic!("expected a list, but got {:?}", other),
+        }
+    }
+
+    fn assert_list(list: OpaqueTerm, expected: &[OpaqueTerm]) {
+        let actual = list_elements(list);
+        let expected = expected.iter().map(|t| (*t).into()).collect::<Vec<Term>>();
+        assert_eq!(
+            actual.len(),
+            expected.len(),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!(a.exact_eq(e), "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    fn assert_badmap(result: Result<OpaqueTerm, MapError>, expected: OpaqueTerm) {
+        match result {
+            Err(MapError::BadMap(term)) => assert_eq!(term, expected),
+            other => panic!("expected {{badmap, {:?}}}, but got {:?}", expected, other),
+        }
+    }
+
+    #[test]
+    fn keys_and_values_are_in_term_order() {
+        let process = process();
+        let one = int(1);
+        let float_one: OpaqueTerm = Term::from(1.0f64).into();
+        let m = map(
+            &[
+                (atom("b"), int(1)),
+                (OpaqueTerm::NIL, int(2)),
+                (one, int(3)),
+                (atom("a"), int(4)),
+                (float_one, int(5)),
+            ],
+            &process,
+        );
+
+        assert_list(
+            keys(m, &process).unwrap(),
+            &[float_one, one, atom("a"), atom("b"), OpaqueTerm::NIL],
+        );
+        assert_list(
+            values(m, &process).unwrap(),
+            &[int(5), int(3), int(4), int(1), int(2)],
+        );
+
+        let empty = map(&[], &process);
+        assert_list(keys(empty, &process).unwrap(), &[]);
+        assert_list(values(empty, &process).unwrap(), &[]);
+    }
+
+    #[test]
+    fn get_raises_badkey_or_returns_default() {
+        let process = process();
+        let m = map(&[(atom("a"), int(1))], &process);
+
+        assert_eq!(get(atom("a"), m).unwrap(), int(1));
+        match get(atom("b"), m) {
+            Err(MapError::BadKey(key)) => assert_eq!(key, atom("b")),
+            other => panic!("expected {{badkey, b}}, but got {:?}", other),
+        }
+        // Keys are matched exactly, so 1.0 does not find 1
+        let m = map(&[(int(1), atom("int"))], &process);
+        assert!(matches!(
+            get(Term::from(1.0f64).into(), m),
+            Err(MapError::BadKey(_))
+        ));
+
+        assert_eq!(
+            get3(atom("b"), m, atom("default")),
+            ErlangResult::Ok(atom("default"))
+        );
+    }
+
+    #[test]
+    fn non_map_arguments_raise_badmap() {
+        let process = process();
+        let m = map(&[(atom("a"), int(1))], &process);
+        let not_map = atom("not_a_map");
+
+        assert_badmap(get(atom("a"), not_map), not_map);
+        assert_badmap(put(atom("a"), int(1), not_map, &process), not_map);
+        assert_badmap(remove(atom("a"), not_map, &process), not_map);
+        assert_badmap(keys(not_map, &process), not_map);
+        assert_badmap(values(not_map, &process), not_map);
+        assert_badmap(merge(not_map, m, &process), not_map);
+        assert_badmap(merge(m, not_map, &process), not_map);
+        // The first argument is checked first
+        assert_badmap(merge(not_map, OpaqueTerm::NIL, &process), not_map);
+    }
+
+    #[test]
+    fn put_and_remove_leave_the_original_map_unchanged() {
+        let process = process();
+        let m = map(&[(atom("a"), int(1))], &process);
+
+        let put_map = put(atom("a"), int(2), m, &process).unwrap();
+        let put_map = put(atom("b"), int(3), put_map, &process).unwrap();
+        assert_list(keys(put_map, &process).unwrap(), &[atom("a"), atom("b")]);
+        assert_list(values(put_map, &process).unwrap(), &[int(2), int(3)]);
+
+        let removed = remove(atom("a"), put_map, &process).unwrap();
+        assert_list(keys(removed, &process).unwrap(), &[atom("b")]);
+        assert_eq!(remove(atom("missing"), removed, &process).unwrap(), removed);
+
+        assert_list(values(m, &process).unwrap(), &[int(1)]);
+        assert_list(keys(put_map, &process).unwrap(), &[atom("a"), atom("b")]);
+    }
+
+    #[test]
+    fn merge_gives_precedence_to_the_second_map() {
+        let process = process();
+        let m1 = map(
+            &[
+                (atom("a"), int(1)),
+                (atom("b"), int(2)),
+                (atom("c"), int(3)),
+            ],
+            &process,
+        );
+        let m2 = map(&[(atom("b"), int(20)), (atom("d"), int(40))], &process);
+
+        let merged = merge(m1, m2, &process).unwrap();
+        assert_list(
+            keys(merged, &process).unwrap(),
+            &[atom("a"), atom("b"), atom("c"), atom("d")],
+        );
+        assert_list(
+            values(merged, &process).unwrap(),
+            &[int(1), int(20), int(3), int(40)],
+        );
+
+        let merged = merge(m2, m1, &process).unwrap();
+        assert_list(
+            values(merged, &process).unwrap(),
+            &[int(1), int(2), int(3), int(40)],
+        );
+
+        let empty = map(&[], &process);
+        assert_eq!(merge(m1, empty, &process).unwrap(), m1);
+        assert_eq!(merge(empty, m2, &process).unwrap(), m2);
+    }
+}
@@ -1,8 +1,16 @@
+pub mod binary;
+pub mod ets;
 pub mod file;
+pub mod fun;
+pub mod io;
 pub mod lists;
+pub mod maps;
+pub mod os;
+pub mod persistent_term;
 pub mod unicode;
 
 use std::io::Write;
+use std::iter;
 use std::ops::Deref;
 use std::ptr::NonNull;
 use std::sync::Arc;
@@ -10,10 +18,15 @@ use std::sync::Arc;
 use smallvec::SmallVec;
 
 use firefly_alloc::gc::GcBox;
+use firefly_binary::Encoding;
 use firefly_rt::backtrace::Trace;
 use firefly_rt::error::ErlangException;
 use firefly_rt::function::{self, ErlangResult, ModuleFunctionArity};
+use firefly_rt::memory::{self, Category};
+use firefly_rt::process::Process;
+use firefly_rt::term::encoding::{self, DecodeOptions, EncodeError, EncodeOptions};
 use firefly_rt::term::*;
+use firefly_rt::time::{self, TimeUnit};
 
 use crate::scheduler;
 
@@ -206,19 +219,25 @@ pub extern "C-unwind" fn bxor2(lhs: OpaqueTerm, rhs: OpaqueTerm) -> ErlangResult
 #[export_name = "erlang:apply/2"]
 pub extern "C-unwind" fn apply2(term: OpaqueTerm, arglist: OpaqueTerm) -> ErlangResult {
     let mut args = SmallVec::<[OpaqueTerm; 3]>::new();
-    let callee = match (term.into(), arglist.into()) {
-        (Term::Closure(fun), Term::Nil) => fun,
-        (Term::Closure(fun), Term::Cons(ptr)) => {
+    match arglist.into() {
+        Term::Nil => (),
+        Term::Cons(ptr) => {
             for element in unsafe { ptr.as_ref().iter().map(list_element_or_err) } {
                 args.push(element?);
             }
-            fun
         }
         _ => return badarg(Trace::capture()),
+    }
+    let result = match term.into() {
+        Term::Closure(fun) => function::apply_closure(&fun, args.as_slice()),
+        // External funs are resolved now, so this raises undef if the function doesn't exist
+        Term::ExternalFun(fun) => function::apply_external(&fun, args.as_slice()),
+        _ => return badarg(Trace::capture()),
     };
-    // Ensure the call is in tail position to allow for tail call optimization
-    // if it can be applied by the compiler
-    callee.apply(args.as_slice())
+    match result {
+        Ok(result) => result,
+        Err(_) => badarity(term, arglist, Trace::capture()),
+    }
 }
 
 #[allow(improper_ctypes_definitions)]
@@ -235,21 +254,17 @@ pub extern "C-unwind" fn apply3(
             for element in unsafe { ptr.as_ref().iter().map(list_element_or_err) } {
                 args.push(element?);
             }
+            // There can't be a function of this arity, but unlike BEAM we can't represent its MFA
+            if args.len() > function::MAX_ARITY {
+                return badarg(Trace::capture());
+            }
             ModuleFunctionArity::new(m, f, args.len())
         }
         _ => return badarg(Trace::capture()),
     };
-    let callee = match function::find_symbol(&mfa) {
-        None => {
-            let trace = Trace::capture();
-            trace.set_top_frame(&mfa, args.as_slice());
-            return undef(trace);
-        }
-        Some(callee) => callee,
-    };
     // Ensure the call is in tail position to allow for tail call optimization
     // if it can be applied by the compiler
-    unsafe { function::apply_callee(callee, args.as_slice()) }
+    function::apply_export(&mfa, args.as_slice())
 }
 
 #[track_caller]
@@ -268,30 +283,56 @@ fn list_element_or_err(element: Result<Term, ImproperList>) -> ErlangResult {
 }
 
 #[allow(improper_ctypes_definitions)]
-#[export_name = "erlang:make_fun/3"]
-pub extern "C-unwind" fn make_fun3(
+#[export_name = "erlang:function_exported/3"]
+pub extern "C-unwind" fn function_exported3(
     module: OpaqueTerm,
     function: OpaqueTerm,
     arity: OpaqueTerm,
 ) -> ErlangResult {
-    let Term::Atom(m) = module.into() else { panic!("invalid make_fun/3 bif module argument, expected atom, got: {:?}", module.r#typeof()); };
-    let Term::Atom(f) = function.into() else { panic!("invalid make_fun/3 bif function argument, expected atom, got: {:?}", function.r#typeof()); };
-    let Term::Int(a) = arity.into() else { panic!("invalid make_fun/3 bif arity argument, expected integer, got: {:?}", arity.r#typeof()); };
+    match mfa_arg(module, function, arity) {
+        Ok(Some(mfa)) => ErlangResult::Ok(function::function_exported(&mfa).into()),
+        Ok(None) => ErlangResult::Ok(false.into()),
+        Err(_) => badarg(Trace::capture()),
+    }
+}
 
-    let mfa = ModuleFunctionArity::new(m, f, a as usize);
-    match function::find_symbol(&mfa) {
-        Some(callee) => scheduler::with_current(|scheduler| {
-            let arc_proc = scheduler.current_process();
-            let proc = arc_proc.deref();
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:is_builtin/3"]
+pub extern "C-unwind" fn is_builtin3(
+    module: OpaqueTerm,
+    function: OpaqueTerm,
+    arity: OpaqueTerm,
+) -> ErlangResult {
+    match mfa_arg(module, function, arity) {
+        Ok(Some(mfa)) => ErlangResult::Ok(function::is_builtin(&mfa).into()),
+        Ok(None) => ErlangResult::Ok(false.into()),
+        Err(_) => badarg(Trace::capture()),
+    }
+}
 
-            ErlangResult::Ok(
-                Closure::new_in(m, f, mfa.arity, callee as *const (), &[], proc)
-                    .unwrap()
-                    .into(),
-            )
-        }),
-        None => undef(Trace::capture()),
+/// Validates the `Module`, `Function` and `Arity` arguments of `function_exported/3` and
+/// `is_builtin/3`, where the arity must be a non-negative small integer
+///
+/// Returns `None` for an arity larger than any function can have, which is not an error.
+fn mfa_arg(
+    module: OpaqueTerm,
+    function: OpaqueTerm,
+    arity: OpaqueTerm,
+) -> Result<Option<ModuleFunctionArity>, ()> {
+    let (Term::Atom(module), Term::Atom(function), Term::Int(arity)) =
+        (module.into(), function.into(), arity.into())
+    else {
+        return Err(());
+    };
+    if arity < 0 {
+        return Err(());
     }
+
+    Ok(u8::try_from(arity).ok().map(|arity| ModuleFunctionArity {
+        module,
+        function,
+        arity,
+    }))
 }
 
 #[allow(improper_ctypes_definitions)]
@@ -316,32 +357,857 @@ pub extern "C-unwind" fn list_to_atom(term: OpaqueTerm) -> ErlangResult {
 #[export_name = "erlang:binary_to_list/1"]
 pub extern "C-unwind" fn binary_to_list(term: OpaqueTerm) -> ErlangResult {
     let t: Term = term.into();
+    let Some(bits) = t.as_bitstring() else { return badarg(Trace::capture()); };
+
+    scheduler::with_current_process(|proc| match Cons::from_binary(bits, proc) {
+        Ok(None) => ErlangResult::Ok(OpaqueTerm::NIL),
+        Ok(Some(cons)) => ErlangResult::Ok(cons.into()),
+        Err(_) => badarg(Trace::capture()),
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_to_list/3"]
+pub extern "C-unwind" fn binary_to_list3(
+    term: OpaqueTerm,
+    start: OpaqueTerm,
+    stop: OpaqueTerm,
+) -> ErlangResult {
+    let t: Term = term.into();
+    let Some(bits) = t.as_bitstring() else { return badarg(Trace::capture()); };
+    let Ok(start) = OneBasedIndex::try_from(start) else { return badarg(Trace::capture()); };
+    let Ok(stop) = OneBasedIndex::try_from(stop) else { return badarg(Trace::capture()); };
+
+    scheduler::with_current_process(
+        |proc| match Cons::from_binary_range(bits, start, stop, proc) {
+            Ok(cons) => ErlangResult::Ok(cons.into()),
+            Err(_) => badarg(Trace::capture()),
+        },
+    )
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:integer_to_binary/1"]
+pub extern "C-unwind" fn integer_to_binary1(integer: OpaqueTerm) -> ErlangResult {
+    integer_to_binary(integer, 10)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:integer_to_binary/2"]
+pub extern "C-unwind" fn integer_to_binary2(integer: OpaqueTerm, base: OpaqueTerm) -> ErlangResult {
+    let Some(base) = integer_base(base) else { return badarg(Trace::capture()); };
+    integer_to_binary(integer, base)
+}
+
+fn integer_to_binary(integer: OpaqueTerm, base: u32) -> ErlangResult {
+    let Ok(integer): Result<Integer, _> = Term::from(integer).try_into() else { return badarg(Trace::capture()); };
+
+    ErlangResult::Ok(BinaryData::from_str(&integer.to_string_radix(base)).into())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_to_integer/1"]
+pub extern "C-unwind" fn binary_to_integer1(binary: OpaqueTerm) -> ErlangResult {
+    binary_to_integer(binary, 10)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_to_integer/2"]
+pub extern "C-unwind" fn binary_to_integer2(binary: OpaqueTerm, base: OpaqueTerm) -> ErlangResult {
+    let Some(base) = integer_base(base) else { return badarg(Trace::capture()); };
+    binary_to_integer(binary, base)
+}
+
+fn binary_to_integer(binary: OpaqueTerm, base: u32) -> ErlangResult {
+    let t: Term = binary.into();
+    let Some(bits) = t.as_bitstring() else { return badarg(Trace::capture()); };
+    if !bits.is_binary() {
+        return badarg(Trace::capture());
+    }
+
+    // Only an unaligned binary is copied, the digits are parsed from the bytes either way
+    let selection = bits.select_all();
+    let bytes = selection.to_bytes();
+    match Integer::from_bytes_radix(&bytes, base) {
+        Some(integer) => handle_safe_integer_arith_result!(integer),
+        None => badarg(Trace::capture()),
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:list_to_integer/1"]
+pub extern "C-unwind" fn list_to_integer1(list: OpaqueTerm) -> ErlangResult {
+    list_to_integer(list, 10)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:list_to_integer/2"]
+pub extern "C-unwind" fn list_to_integer2(list: OpaqueTerm, base: OpaqueTerm) -> ErlangResult {
+    let Some(base) = integer_base(base) else { return badarg(Trace::capture()); };
+    list_to_integer(list, base)
+}
+
+fn list_to_integer(list: OpaqueTerm, base: u32) -> ErlangResult {
+    let Some(bytes) = charlist_to_bytes(list) else { return badarg(Trace::capture()); };
+
+    match Integer::from_bytes_radix(&bytes, base) {
+        Some(integer) => handle_safe_integer_arith_result!(integer),
+        None => badarg(Trace::capture()),
+    }
+}
+
+/// Converts the base argument of the integer/binary conversion functions, which must be in 2..36
+fn integer_base(base: OpaqueTerm) -> Option<u32> {
+    match base.into() {
+        Term::Int(base) if (2..=36).contains(&base) => Some(base as u32),
+        _ => None,
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:float_to_binary/1"]
+pub extern "C-unwind" fn float_to_binary1(float: OpaqueTerm) -> ErlangResult {
+    float_to_binary(float, FloatFormat::default())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:float_to_binary/2"]
+pub extern "C-unwind" fn float_to_binary2(float: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let Some(format) = float_format_options(options) else { return badarg(Trace::capture()); };
+    float_to_binary(float, format)
+}
+
+fn float_to_binary(float: OpaqueTerm, format: FloatFormat) -> ErlangResult {
+    let Some(formatted) = format_float(float, format) else { return badarg(Trace::capture()); };
+
+    ErlangResult::Ok(BinaryData::from_str(&formatted).into())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:float_to_list/1"]
+pub extern "C-unwind" fn float_to_list1(float: OpaqueTerm) -> ErlangResult {
+    float_to_list(float, FloatFormat::default())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:float_to_list/2"]
+pub extern "C-unwind" fn float_to_list2(float: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let Some(format) = float_format_options(options) else { return badarg(Trace::capture()); };
+    float_to_list(float, format)
+}
+
+fn float_to_list(float: OpaqueTerm, format: FloatFormat) -> ErlangResult {
+    let Some(formatted) = format_float(float, format) else { return badarg(Trace::capture()); };
+
+    scheduler::with_current_process(|proc| match Cons::charlist_from_str(&formatted, proc) {
+        Ok(None) => ErlangResult::Ok(OpaqueTerm::NIL),
+        Ok(Some(cons)) => ErlangResult::Ok(cons.into()),
+        Err(_) => badarg(Trace::capture()),
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:list_to_float/1"]
+pub extern "C-unwind" fn list_to_float(list: OpaqueTerm) -> ErlangResult {
+    let Some(bytes) = charlist_to_bytes(list) else { return badarg(Trace::capture()); };
+
+    match Float::from_bytes(&bytes) {
+        Some(float) => ErlangResult::Ok(float.into()),
+        None => badarg(Trace::capture()),
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:tuple_to_list/1"]
+pub extern "C-unwind" fn tuple_to_list1(tuple: OpaqueTerm) -> ErlangResult {
+    let Term::Tuple(ptr) = tuple.into() else { return badarg(Trace::capture()); };
+    scheduler::with_current_process(|proc| {
+        ErlangResult::Ok(tuple_to_list(unsafe { ptr.as_ref() }, proc))
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:list_to_tuple/1"]
+pub extern "C-unwind" fn list_to_tuple1(list: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current_process(|proc| match list_to_tuple(list, proc) {
+        Some(tuple) => ErlangResult::Ok(tuple.into()),
+        None => badarg(Trace::capture()),
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:make_tuple/2"]
+pub extern "C-unwind" fn make_tuple2(arity: OpaqueTerm, default: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current_process(|proc| {
+        match make_tuple(arity, default, OpaqueTerm::NIL, proc) {
+            Some(tuple) => ErlangResult::Ok(tuple.into()),
+            None => badarg(Trace::capture()),
+        }
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:make_tuple/3"]
+pub extern "C-unwind" fn make_tuple3(
+    arity: OpaqueTerm,
+    default: OpaqueTerm,
+    init_list: OpaqueTerm,
+) -> ErlangResult {
+    scheduler::with_current_process(|proc| match make_tuple(arity, default, init_list, proc) {
+        Some(tuple) => ErlangResult::Ok(tuple.into()),
+        None => badarg(Trace::capture()),
+    })
+}
+
+/// Returns a copy of `tuple` with the element at the 1-based `index` replaced by `value`
+///
+/// The tuple is always copied: this BIF can't tell whether the caller still holds `tuple`, as in
+/// `{setelement(1, T, x), T}`, even when it is the most recent allocation on the heap. Updating
+/// fresh tuples in place is left to the compiler, which knows when that is safe and emits
+/// `tuple.set.mut` for it.
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:setelement/3"]
+pub extern "C-unwind" fn setelement3(
+    index: OpaqueTerm,
+    tuple: OpaqueTerm,
+    value: OpaqueTerm,
+) -> ErlangResult {
+    scheduler::with_current_process(|proc| match setelement(index, tuple, value, proc) {
+        Some(tuple) => ErlangResult::Ok(tuple.into()),
+        None => badarg(Trace::capture()),
+    })
+}
+
+fn setelement(
+    index: OpaqueTerm,
+    tuple: OpaqueTerm,
+    value: OpaqueTerm,
+    process: &Process,
+) -> Option<NonNull<Tuple>> {
+    let Term::Tuple(ptr) = tuple.into() else { return None; };
+    let tuple = unsafe { ptr.as_ref() };
+    let index = OneBasedIndex::try_from(index).ok()?;
+    if index >= tuple.len() {
+        return None;
+    }
+    Some(tuple.set_element(index, value, process).unwrap())
+}
+
+fn tuple_to_list(tuple: &Tuple, process: &Process) -> OpaqueTerm {
+    let mut builder = ListBuilder::new(process);
+    for element in tuple.as_slice().iter().rev() {
+        builder.push((*element).into()).unwrap();
+    }
+    match builder.finish() {
+        None => OpaqueTerm::NIL,
+        Some(cons) => cons.into(),
+    }
+}
+
+/// Returns `None` if `list` is not a proper list, or is too long for a tuple
+fn list_to_tuple(list: OpaqueTerm, process: &Process) -> Option<NonNull<Tuple>> {
+    let elements = match list.into() {
+        Term::Nil => vec![],
+        Term::Cons(ptr) => unsafe { ptr.as_ref() }
+            .iter()
+            .map(|element| element.ok().map(OpaqueTerm::from))
+            .collect::<Option<Vec<_>>>()?,
+        _ => return None,
+    };
+    if elements.len() > Tuple::MAX_LEN {
+        return None;
+    }
+    Some(Tuple::from_slice(&elements, process).unwrap())
+}
+
+/// Returns a tuple of `arity` elements of `default`, except those set by the `{Position, Value}`
+/// pairs of `init_list`, in order, so that the last pair for a position wins
+///
+/// Returns `None` if `arity` is not a valid arity, or `init_list` is not a proper list of pairs
+/// with positions in `1..=arity`.
+fn make_tuple(
+    arity: OpaqueTerm,
+    default: OpaqueTerm,
+    init_list: OpaqueTerm,
+    process: &Process,
+) -> Option<NonNull<Tuple>> {
+    let Term::Int(arity) = arity.into() else { return None; };
+    let arity = usize::try_from(arity)
+        .ok()
+        .filter(|arity| *arity <= Tuple::MAX_LEN)?;
+
+    let mut elements = vec![default; arity];
+    let inits = match init_list.into() {
+        Term::Nil => None,
+        Term::Cons(ptr) => Some(unsafe { ptr.as_ref() }),
+        _ => return None,
+    };
+    for init in inits.into_iter().flat_map(|cons| cons.iter()) {
+        let Ok(Term::Tuple(ptr)) = init else { return None; };
+        let &[position, value] = unsafe { ptr.as_ref() }.as_slice() else { return None; };
+        let index: usize = OneBasedIndex::try_from(position).ok()?.into();
+        *elements.get_mut(index)? = value;
+    }
+
+    Some(Tuple::from_slice(&elements, process).unwrap())
+}
+
+fn format_float(float: OpaqueTerm, format: FloatFormat) -> Option<String> {
+    let Term::Float(float) = float.into() else { return None; };
+    format.format(float.inner())
+}
+
+/// Collects the characters of a non-empty charlist as bytes, for the parsers of numbers in text
+///
+/// Returns `None` if `list` is empty, improper, or contains anything but integers in 0..=255;
+/// whatever characters remain are validated by the parser.
+fn charlist_to_bytes(list: OpaqueTerm) -> Option<Vec<u8>> {
+    let Term::Cons(ptr) = list.into() else { return None; };
+    let cons = unsafe { ptr.as_ref() };
+
+    cons.iter()
+        .map(|element| match element.ok()? {
+            Term::Int(c) => u8::try_from(c).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parses the option list of `float_to_binary/2` and `float_to_list/2`
+///
+/// As in BEAM, a later `decimals` or `scientific` option replaces an earlier one, and `compact`
+/// only affects `decimals`, wherever it appears in the list
+fn float_format_options(options: OpaqueTerm) -> Option<FloatFormat> {
+    let mut format = FloatFormat::default();
+    let mut compact = false;
+
+    let list = match options.into() {
+        Term::Nil => return Some(format),
+        Term::Cons(ptr) => unsafe { ptr.as_ref() },
+        _ => return None,
+    };
+    for option in list.iter() {
+        match option.ok()? {
+            Term::Atom(a) if a == atoms::Compact => compact = true,
+            Term::Tuple(ptr) => {
+                let tuple = unsafe { ptr.as_ref() };
+                let [key, value] = tuple.as_slice() else { return None; };
+                let Term::Atom(key) = (*key).into() else { return None; };
+                let Term::Int(value) = (*value).into() else { return None; };
+                let digits = u8::try_from(value).ok()?;
+                if key == atoms::Decimals && digits <= MAX_DECIMALS {
+                    format = FloatFormat::Decimals {
+                        digits,
+                        compact: false,
+                    };
+                } else if key == atoms::Scientific && digits <= MAX_SCIENTIFIC_DIGITS {
+                    format = FloatFormat::Scientific(digits);
+                } else {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    if let FloatFormat::Decimals { digits, .. } = format {
+        format = FloatFormat::Decimals { digits, compact };
+    }
+
+    Some(format)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:iolist_to_binary/1"]
+pub extern "C-unwind" fn iolist_to_binary1(iolist: OpaqueTerm) -> ErlangResult {
+    let t: Term = iolist.into();
+    // A binary is returned as-is rather than copied
     if let Some(bits) = t.as_bitstring() {
-        assert!(bits.is_binary());
-        assert!(bits.is_aligned());
-
-        scheduler::with_current(|scheduler| {
-            let arc_proc = scheduler.current_process();
-            let proc = arc_proc.deref();
-
-            let bytes = unsafe { bits.as_bytes_unchecked() };
-            let result = match core::str::from_utf8(bytes).ok() {
-                Some(s) => Cons::charlist_from_str(s, proc).unwrap(),
-                None => Cons::from_bytes(bytes, proc).unwrap(),
-            };
-            match result {
-                None => ErlangResult::Ok(Term::Nil.into()),
-                Some(cons) => ErlangResult::Ok(cons.into()),
+        if bits.is_binary() {
+            return ErlangResult::Ok(iolist);
+        }
+    }
+
+    scheduler::with_current_process(|proc| match t.iodata_to_binary(proc) {
+        Ok(bin) => ErlangResult::Ok(bin.into()),
+        Err(_) => badarg(Trace::capture()),
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:iolist_size/1"]
+pub extern "C-unwind" fn iolist_size1(iolist: OpaqueTerm) -> ErlangResult {
+    match Term::from(iolist).iodata_size() {
+        Ok(size) => handle_safe_integer_arith_result!(Integer::new(size as i64)),
+        Err(_) => badarg(Trace::capture()),
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_part/2"]
+pub extern "C-unwind" fn binary_part2(bin: OpaqueTerm, pos_len: OpaqueTerm) -> ErlangResult {
+    let Term::Tuple(ptr) = pos_len.into() else { return badarg(Trace::capture()); };
+    let tuple = unsafe { ptr.as_ref() };
+    let [start, length] = tuple.as_slice() else { return badarg(Trace::capture()); };
+
+    binary_part3(bin, *start, *length)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_part/3"]
+pub extern "C-unwind" fn binary_part3(
+    bin: OpaqueTerm,
+    start: OpaqueTerm,
+    length: OpaqueTerm,
+) -> ErlangResult {
+    let Term::Int(start) = start.into() else { return badarg(Trace::capture()); };
+    let Ok(start) = usize::try_from(start) else { return badarg(Trace::capture()); };
+    let Term::Int(length) = length.into() else { return badarg(Trace::capture()); };
+    let Ok(length) = isize::try_from(length) else { return badarg(Trace::capture()); };
+    let Some(part) = BitSlice::binary_part(bin, start, length) else { return badarg(Trace::capture()); };
+
+    scheduler::with_current_process(|proc| {
+        let part = GcBox::new_in(part, proc).unwrap();
+        ErlangResult::Ok(part.into())
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:split_binary/2"]
+pub extern "C-unwind" fn split_binary2(bin: OpaqueTerm, pos: OpaqueTerm) -> ErlangResult {
+    let t: Term = bin.into();
+    let Some(bits) = t.as_bitstring() else { return badarg(Trace::capture()); };
+    let size = bits.bit_size() / 8;
+    let Term::Int(pos) = pos.into() else { return badarg(Trace::capture()); };
+    let Ok(pos) = usize::try_from(pos) else { return badarg(Trace::capture()); };
+    if pos > size {
+        return badarg(Trace::capture());
+    }
+    // Both halves refer to `bin`, so neither copies any bytes
+    let Some(head) = BitSlice::binary_part(bin, 0, pos as isize) else { return badarg(Trace::capture()); };
+    let Some(tail) = BitSlice::binary_part(bin, pos, (size - pos) as isize) else { return badarg(Trace::capture()); };
+
+    scheduler::with_current_process(|proc| {
+        let head = GcBox::new_in(head, proc).unwrap();
+        let tail = GcBox::new_in(tail, proc).unwrap();
+        let tuple = Tuple::from_slice(&[head.into(), tail.into()], proc).unwrap();
+        ErlangResult::Ok(tuple.into())
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:atom_to_binary/2"]
+pub extern "C-unwind" fn atom_to_binary2(atom: OpaqueTerm, encoding: OpaqueTerm) -> ErlangResult {
+    let Ok(atom): Result<Atom, _> = Term::from(atom).try_into() else { return badarg(Trace::capture()); };
+    let Some(encoding) = atom_encoding(encoding) else { return badarg(Trace::capture()); };
+
+    let name = atom.as_str();
+    let bin = match encoding {
+        Encoding::Latin1 => {
+            // Atoms containing characters outside of Latin-1 cannot be represented
+            let mut bytes = Vec::with_capacity(name.len());
+            for c in name.chars() {
+                let Ok(byte) = u8::try_from(c) else { return badarg(Trace::capture()); };
+                bytes.push(byte);
             }
-        })
+            unsafe { BinaryData::from_bytes_with_encoding(&bytes, Encoding::Latin1) }
+        }
+        _ => BinaryData::from_str(name),
+    };
+
+    ErlangResult::Ok(bin.into())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_to_atom/2"]
+pub extern "C-unwind" fn binary_to_atom2(binary: OpaqueTerm, encoding: OpaqueTerm) -> ErlangResult {
+    binary_to_atom(binary, encoding, false)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_to_existing_atom/2"]
+pub extern "C-unwind" fn binary_to_existing_atom2(
+    binary: OpaqueTerm,
+    encoding: OpaqueTerm,
+) -> ErlangResult {
+    binary_to_atom(binary, encoding, true)
+}
+
+fn binary_to_atom(binary: OpaqueTerm, encoding: OpaqueTerm, existing: bool) -> ErlangResult {
+    let t: Term = binary.into();
+    let Some(bits) = t.as_bitstring() else { return badarg(Trace::capture()); };
+    if !bits.is_binary() {
+        return badarg(Trace::capture());
+    }
+    let Some(encoding) = atom_encoding(encoding) else { return badarg(Trace::capture()); };
+
+    let selection = bits.select_all();
+    let bytes = selection.to_bytes();
+    let result = match encoding {
+        Encoding::Latin1 if existing => Atom::try_from_latin1_bytes_existing(&bytes),
+        Encoding::Latin1 => Atom::try_from_latin1_bytes(&bytes),
+        _ if existing => Atom::try_from_utf8_bytes_existing(&bytes),
+        _ => Atom::try_from(bytes.as_ref()),
+    };
+
+    match result {
+        Ok(atom) => ErlangResult::Ok(atom.into()),
+        Err(AtomError::InvalidLength(_)) => system_limit(Trace::capture()),
+        Err(_) => badarg(Trace::capture()),
+    }
+}
+
+/// Converts the encoding argument of the atom/binary conversion functions
+///
+/// `unicode` is accepted as an alias for `utf8`
+fn atom_encoding(encoding: OpaqueTerm) -> Option<Encoding> {
+    match encoding.into() {
+        Term::Atom(a) if a == atoms::Utf8 || a == atoms::Unicode => Some(Encoding::Utf8),
+        Term::Atom(a) if a == atoms::Latin1 => Some(Encoding::Latin1),
+        _ => None,
+    }
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:term_to_binary/1"]
+pub extern "C-unwind" fn term_to_binary1(term: OpaqueTerm) -> ErlangResult {
+    term_to_binary(term, EncodeOptions::default())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:term_to_binary/2"]
+pub extern "C-unwind" fn term_to_binary2(term: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let Some(options) = term_to_binary_options(options) else { return badarg(Trace::capture()); };
+    term_to_binary(term, options)
+}
+
+fn term_to_binary(term: OpaqueTerm, options: EncodeOptions) -> ErlangResult {
+    match encoding::encode(term.into(), options) {
+        Ok(bytes) => ErlangResult::Ok(BinaryData::from_bytes(&bytes).into()),
+        Err(EncodeError::TooLarge) => system_limit(Trace::capture()),
+        Err(EncodeError::Unsupported) => badarg(Trace::capture()),
+    }
+}
+
+/// Parses the option list of `term_to_binary/2`
+///
+/// The encoding is always deterministic, so `deterministic` is accepted and ignored
+fn term_to_binary_options(options: OpaqueTerm) -> Option<EncodeOptions> {
+    let mut parsed = EncodeOptions::default();
+
+    let list = match options.into() {
+        Term::Nil => return Some(parsed),
+        Term::Cons(ptr) => unsafe { ptr.as_ref() },
+        _ => return None,
+    };
+    for option in list.iter() {
+        match option.ok()? {
+            Term::Atom(a) if a == atoms::Compressed => {
+                parsed.compression = encoding::DEFAULT_COMPRESSION_LEVEL
+            }
+            Term::Atom(a) if a == atoms::Deterministic => (),
+            Term::Tuple(ptr) => {
+                let tuple = unsafe { ptr.as_ref() };
+                let [key, value] = tuple.as_slice() else { return None; };
+                let Term::Atom(key) = (*key).into() else { return None; };
+                let Term::Int(value) = (*value).into() else { return None; };
+                match value {
+                    0..=9 if key == atoms::Compressed => parsed.compression = value as u8,
+                    0..=2 if key == atoms::MinorVersion => parsed.minor_version = value as u8,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    Some(parsed)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_to_term/1"]
+pub extern "C-unwind" fn binary_to_term1(binary: OpaqueTerm) -> ErlangResult {
+    binary_to_term(binary, DecodeOptions::default())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:binary_to_term/2"]
+pub extern "C-unwind" fn binary_to_term2(binary: OpaqueTerm, options: OpaqueTerm) -> ErlangResult {
+    let Some(options) = binary_to_term_options(options) else { return badarg(Trace::capture()); };
+    binary_to_term(binary, options)
+}
+
+fn binary_to_term(binary: OpaqueTerm, options: DecodeOptions) -> ErlangResult {
+    let t: Term = binary.into();
+    let Some(bits) = t.as_bitstring() else { return badarg(Trace::capture()); };
+    if !bits.is_binary() {
+        return badarg(Trace::capture());
+    }
+
+    let selection = bits.select_all();
+    let bytes = selection.to_bytes();
+    // Malformed input, and new atoms when `safe` is given, are all reported as badarg
+    match scheduler::with_current_process(|proc| encoding::decode(&bytes, options, proc)) {
+        Ok(term) => ErlangResult::Ok(term.into()),
+        Err(_) => badarg(Trace::capture()),
+    }
+}
+
+/// Parses the option list of `binary_to_term/2`
+///
+/// Only `safe` is supported; since we never decode funs, it only guards against atom creation.
+fn binary_to_term_options(options: OpaqueTerm) -> Option<DecodeOptions> {
+    let mut parsed = DecodeOptions::default();
+
+    let list = match options.into() {
+        Term::Nil => return Some(parsed),
+        Term::Cons(ptr) => unsafe { ptr.as_ref() },
+        _ => return None,
+    };
+    for option in list.iter() {
+        match option.ok()? {
+            Term::Atom(a) if a == atoms::Safe => parsed.safe = true,
+            _ => return None,
+        }
+    }
+
+    Some(parsed)
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:phash2/1"]
+pub extern "C-unwind" fn phash2_1(term: OpaqueTerm) -> ErlangResult {
+    let hash = phash2(term.into()) & ((1 << 27) - 1);
+    ErlangResult::Ok(Term::Int(hash as i64).into())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:phash2/2"]
+pub extern "C-unwind" fn phash2_2(term: OpaqueTerm, range: OpaqueTerm) -> ErlangResult {
+    // The range must be in 1..=2^32
+    let range = match range.into() {
+        Term::Int(range) if range > 0 && range <= (1 << 32) => range as u64,
+        _ => return badarg(Trace::capture()),
+    };
+    let hash = phash2(term.into()) as u64 % range;
+    ErlangResult::Ok(Term::Int(hash as i64).into())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:is_function/2"]
+pub extern "C-unwind" fn is_function2(term: OpaqueTerm, arity: OpaqueTerm) -> ErlangResult {
+    let arity = match arity.into() {
+        Term::Int(arity) if arity >= 0 => arity as usize,
+        Term::BigInt(_) => usize::MAX,
+        _ => return badarg(Trace::capture()),
+    };
+    match term.into() {
+        Term::Closure(fun) => ErlangResult::Ok((fun.effective_arity() == arity).into()),
+        Term::ExternalFun(fun) => ErlangResult::Ok((fun.arity as usize == arity).into()),
+        _ => ErlangResult::Ok(false.into()),
+    }
+}
+
+#[export_name = "erlang:make_ref/0"]
+pub extern "C-unwind" fn make_ref0() -> ErlangResult {
+    scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+
+        let id = scheduler.next_reference_id();
+        let reference = GcBox::new_in(Reference::Local { id }, proc).unwrap();
+        ErlangResult::Ok(Term::Reference(reference).into())
+    })
+}
+
+#[export_name = "erlang:node/0"]
+pub extern "C-unwind" fn node0() -> ErlangResult {
+    ErlangResult::Ok(Node::local_name().into())
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:node/1"]
+pub extern "C-unwind" fn node1(term: OpaqueTerm) -> ErlangResult {
+    match Term::from(term).node_name() {
+        Some(name) => ErlangResult::Ok(name.into()),
+        None => badarg(Trace::capture()),
+    }
+}
+
+/// Until distribution is supported, this node is never connected to any other
+#[export_name = "erlang:nodes/0"]
+pub extern "C-unwind" fn nodes0() -> ErlangResult {
+    ErlangResult::Ok(OpaqueTerm::NIL)
+}
+
+/// Returns a list of `{Category, Bytes}` for each category of memory, see `firefly_rt::memory`
+#[export_name = "erlang:memory/0"]
+pub extern "C-unwind" fn memory0() -> ErlangResult {
+    scheduler::with_current_process(|proc| ErlangResult::Ok(memory(proc)))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:memory/1"]
+pub extern "C-unwind" fn memory1(category: OpaqueTerm) -> ErlangResult {
+    let Term::Atom(name) = category.into() else { return badarg(Trace::capture()); };
+    let bytes = if name == atoms::Total {
+        memory::total()
     } else {
-        let reason = make_reason(atoms::Badarg, term);
-        raise2(reason, unsafe {
-            NonNull::new_unchecked(Trace::into_raw(Trace::capture()))
+        match Category::from_atom(name) {
+            Some(category) => category.bytes(),
+            None => return badarg(Trace::capture()),
+        }
+    };
+    handle_safe_integer_arith_result!(Integer::new(bytes as i64))
+}
+
+fn memory(process: &Process) -> OpaqueTerm {
+    let total = (atoms::Total, memory::total());
+    let categories = Category::ALL
+        .iter()
+        .map(|category| (category.as_atom(), category.bytes()));
+    let items = iter::once(total)
+        .chain(categories)
+        .map(|(name, bytes)| {
+            let item = [name.into(), Term::Int(bytes as i64).into()];
+            Term::Tuple(Tuple::from_slice(&item, process).unwrap())
+        })
+        .collect::<Vec<_>>();
+    Cons::from_slice(&items, process).unwrap().unwrap().into()
+}
+
+/// Sets `key` to `value` in the process dictionary, returning the previous value or `undefined`
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:put/2"]
+pub extern "C-unwind" fn put2(key: OpaqueTerm, value: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current_process(|proc| ErlangResult::Ok(put(proc, key, value)))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:get/1"]
+pub extern "C-unwind" fn get1(key: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current_process(|proc| {
+        let value = proc.dictionary().get(key.into());
+        ErlangResult::Ok(value.map_or(atoms::Undefined.into(), Into::into))
+    })
+}
+
+/// Returns the process dictionary as a list of `{Key, Value}`
+#[export_name = "erlang:get/0"]
+pub extern "C-unwind" fn get0() -> ErlangResult {
+    scheduler::with_current_process(|proc| {
+        let entries = proc.dictionary().iter().collect::<Vec<_>>();
+        ErlangResult::Ok(dictionary_entries(&entries, proc))
+    })
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:erase/1"]
+pub extern "C-unwind" fn erase1(key: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current_process(|proc| {
+        let value = unsafe { proc.erase(key.into()) };
+        ErlangResult::Ok(value.map_or(atoms::Undefined.into(), Into::into))
+    })
+}
+
+/// Empties the process dictionary, returning what it held as a list of `{Key, Value}`
+#[export_name = "erlang:erase/0"]
+pub extern "C-unwind" fn erase0() -> ErlangResult {
+    scheduler::with_current_process(|proc| ErlangResult::Ok(erase_all(proc)))
+}
+
+/// Returns the keys in the process dictionary whose value is exactly `value`
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:get_keys/1"]
+pub extern "C-unwind" fn get_keys1(value: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current_process(|proc| ErlangResult::Ok(get_keys(proc, value)))
+}
+
+fn put(process: &Process, key: OpaqueTerm, value: OpaqueTerm) -> OpaqueTerm {
+    let previous = unsafe { process.put(key.into(), value.into()) }.unwrap();
+    previous.map_or(atoms::Undefined.into(), Into::into)
+}
+
+fn erase_all(process: &Process) -> OpaqueTerm {
+    let entries = unsafe { process.erase_all() };
+    dictionary_entries(&entries, process)
+}
+
+fn get_keys(process: &Process, value: OpaqueTerm) -> OpaqueTerm {
+    let keys = process
+        .dictionary()
+        .keys_with_value(value.into())
+        .collect::<Vec<_>>();
+    match Cons::from_slice(&keys, process).unwrap() {
+        None => OpaqueTerm::NIL,
+        Some(cons) => cons.into(),
+    }
+}
+
+fn dictionary_entries(entries: &[(Term, Term)], process: &Process) -> OpaqueTerm {
+    let items = entries
+        .iter()
+        .map(|(key, value)| {
+            let item = [(*key).into(), (*value).into()];
+            Term::Tuple(Tuple::from_slice(&item, process).unwrap())
         })
+        .collect::<Vec<_>>();
+    match Cons::from_slice(&items, process).unwrap() {
+        None => OpaqueTerm::NIL,
+        Some(cons) => cons.into(),
     }
 }
 
+#[export_name = "erlang:monotonic_time/0"]
+pub extern "C-unwind" fn monotonic_time0() -> ErlangResult {
+    handle_safe_integer_arith_result!(Integer::new(time::monotonic_time(TimeUnit::Native)))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:monotonic_time/1"]
+pub extern "C-unwind" fn monotonic_time1(unit: OpaqueTerm) -> ErlangResult {
+    let Ok(unit) = TimeUnit::try_from(Term::from(unit)) else { return badarg(Trace::capture()); };
+    handle_safe_integer_arith_result!(Integer::new(time::monotonic_time(unit)))
+}
+
+#[export_name = "erlang:system_time/0"]
+pub extern "C-unwind" fn system_time0() -> ErlangResult {
+    handle_safe_integer_arith_result!(Integer::new(time::system_time(TimeUnit::Native)))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:system_time/1"]
+pub extern "C-unwind" fn system_time1(unit: OpaqueTerm) -> ErlangResult {
+    let Ok(unit) = TimeUnit::try_from(Term::from(unit)) else { return badarg(Trace::capture()); };
+    handle_safe_integer_arith_result!(Integer::new(time::system_time(unit)))
+}
+
+#[export_name = "erlang:time_offset/0"]
+pub extern "C-unwind" fn time_offset0() -> ErlangResult {
+    handle_safe_integer_arith_result!(Integer::new(time::time_offset(TimeUnit::Native)))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:time_offset/1"]
+pub extern "C-unwind" fn time_offset1(unit: OpaqueTerm) -> ErlangResult {
+    let Ok(unit) = TimeUnit::try_from(Term::from(unit)) else { return badarg(Trace::capture()); };
+    handle_safe_integer_arith_result!(Integer::new(time::time_offset(unit)))
+}
+
+#[allow(improper_ctypes_definitions)]
+#[export_name = "erlang:convert_time_unit/3"]
+pub extern "C-unwind" fn convert_time_unit3(
+    time: OpaqueTerm,
+    from: OpaqueTerm,
+    to: OpaqueTerm,
+) -> ErlangResult {
+    let Term::Int(time) = time.into() else { return badarg(Trace::capture()); };
+    let Ok(from) = TimeUnit::try_from(Term::from(from)) else { return badarg(Trace::capture()); };
+    let Ok(to) = TimeUnit::try_from(Term::from(to)) else { return badarg(Trace::capture()); };
+    handle_safe_integer_arith_result!(Integer::new(time::convert(time, from, to)))
+}
+
+/// Prints `term` followed by a newline, written as BEAM's `erlang:display/1` does
 #[export_name = "erlang:display/1"]
 pub extern "C-unwind" fn display(term: OpaqueTerm) -> ErlangResult {
     let term: Term = term.into();
@@ -398,24 +1264,73 @@ pub extern "C-unwind" fn puts(printable: OpaqueTerm) -> ErlangResult {
 #[allow(improper_ctypes_definitions)]
 #[export_name = "erlang:error/1"]
 pub extern "C-unwind" fn error1(reason: OpaqueTerm) -> ErlangResult {
-    let err = ErlangException::new(atoms::Error, reason.into(), Trace::capture());
+    let trace = Trace::capture();
+    let reason = match function_clause_frame(reason) {
+        Some((mfa, args)) => {
+            trace.set_top_frame(&mfa, args.as_slice());
+            atoms::FunctionClause.into()
+        }
+        None => reason,
+    };
+    let err = ErlangException::new(atoms::Error, reason.into(), trace);
     ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(err)) })
 }
 
 #[allow(improper_ctypes_definitions)]
 #[export_name = "erlang:error/2"]
-pub extern "C-unwind" fn error2(reason: OpaqueTerm, _args: OpaqueTerm) -> ErlangResult {
-    error1(reason)
+pub extern "C-unwind" fn error2(reason: OpaqueTerm, args: OpaqueTerm) -> ErlangResult {
+    let trace = Trace::capture();
+    if let Some(args) = list_to_args(args) {
+        trace.set_top_arguments(args.as_slice());
+    }
+    let err = ErlangException::new(atoms::Error, reason.into(), trace);
+    ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(err)) })
 }
 
 #[allow(improper_ctypes_definitions)]
 #[export_name = "erlang:error/3"]
 pub extern "C-unwind" fn error3(
     reason: OpaqueTerm,
-    _args: OpaqueTerm,
+    args: OpaqueTerm,
     _options: OpaqueTerm,
 ) -> ErlangResult {
-    error1(reason)
+    error2(reason, args)
+}
+
+/// Compiled code raises `function_clause` errors with a reason of
+/// `{function_clause, {Module, Function, Args, Location}}`, which is converted here to the form
+/// used by BEAM, i.e. a reason of `function_clause`, with the function and the arguments it was
+/// called with as the top frame of the stacktrace.
+fn function_clause_frame(
+    reason: OpaqueTerm,
+) -> Option<(ModuleFunctionArity, SmallVec<[OpaqueTerm; 3]>)> {
+    let Term::Tuple(reason) = reason.into() else { return None; };
+    let [tag, frame] = unsafe { reason.as_ref() }.as_slice() else { return None; };
+    if *tag != atoms::FunctionClause.into() {
+        return None;
+    }
+    let Term::Tuple(frame) = (*frame).into() else { return None; };
+    let [module, function, args, _] = unsafe { frame.as_ref() }.as_slice() else { return None; };
+    let Term::Atom(module) = (*module).into() else { return None; };
+    let Term::Atom(function) = (*function).into() else { return None; };
+    let args = list_to_args(*args)?;
+
+    Some((ModuleFunctionArity::new(module, function, args.len()), args))
+}
+
+/// Returns the elements of `list`, or `None` if it is not a proper list
+fn list_to_args(list: OpaqueTerm) -> Option<SmallVec<[OpaqueTerm; 3]>> {
+    let mut args = SmallVec::new();
+    match list.into() {
+        Term::Nil => (),
+        Term::Cons(ptr) => {
+            for element in unsafe { ptr.as_ref().iter() } {
+                args.push(element.ok()?.into());
+            }
+        }
+        _ => return None,
+    }
+    Some(args)
 }
 
 #[allow(improper_ctypes_definitions)]
@@ -456,8 +1371,15 @@ fn make_reason<R: Into<OpaqueTerm>>(tag: Atom, reason: R) -> OpaqueTerm {
     })
 }
 
-pub(self) fn undef(trace: Arc<Trace>) -> ErlangResult {
-    raise2(atoms::Undef.into(), unsafe {
+/// Raises `{badarity, {Fun, Args}}`
+fn badarity(fun: OpaqueTerm, args: OpaqueTerm, trace: Arc<Trace>) -> ErlangResult {
+    let reason = scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        let proc = arc_proc.deref();
+        let fun_args = Tuple::from_slice(&[fun, args], proc).unwrap();
+        Tuple::from_slice(&[atoms::Badarity.into(), fun_args.into()], proc).unwrap()
+    });
+    raise2(reason.into(), unsafe {
         NonNull::new_unchecked(Trace::into_raw(trace))
     })
 }
@@ -466,7 +1388,498 @@ pub(self) fn badarg(trace: Arc<Trace>) -> ErlangResult {
     ErlangResult::Err(badarg_err(trace))
 }
 
+pub(self) fn system_limit(trace: Arc<Trace>) -> ErlangResult {
+    let err = ErlangException::new(atoms::Error, atoms::SystemLimit.into(), trace);
+    ErlangResult::Err(unsafe { NonNull::new_unchecked(Box::into_raw(err)) })
+}
+
 pub(self) fn badarg_err(trace: Arc<Trace>) -> NonNull<ErlangException> {
     let err = ErlangException::new(atoms::Error, atoms::Badarg.into(), trace);
     unsafe { NonNull::new_unchecked(Box::into_raw(err)) }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::hint::black_box;
+
+    use firefly_alloc::heap::Heap;
+    use firefly_rt::backtrace::Symbolication;
+    use firefly_rt::process::{Process, ProcessId};
+
+    use super::*;
+
+    use crate::intrinsic::build_stacktrace;
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn mfa(function: &str, arity: usize) -> ModuleFunctionArity {
+        let module = Atom::try_from("stacktrace_test").unwrap();
+        let function = Atom::try_from(function).unwrap();
+        ModuleFunctionArity::new(module, function, arity)
+    }
+
+    fn atom(name: &str) -> OpaqueTerm {
+        Atom::try_from(name).unwrap().into()
+    }
+
+    fn list(elements: &[OpaqueTerm], process: &Process) -> OpaqueTerm {
+        let elements = elements.iter().copied().map(Term::from).collect::<Vec<_>>();
+        match Cons::from_slice(&elements, process).unwrap() {
+            None => OpaqueTerm::NIL,
+            Some(cons) => cons.into(),
+        }
+    }
+
+    fn list_elements(list: Term) -> Vec<Term> {
+        match list {
+            Term::Nil => vec![],
+            Term::Cons(cons) => unsafe { cons.as_ref() }
+                .iter()
+                .map(|result| result.unwrap())
+                .collect(),
+            other => panic!("expected a list, but got {:?}", other),
+        }
+    }
+
+    fn unwrap_err(result: ErlangResult) -> Box<ErlangException> {
+        match result {
+            ErlangResult::Err(exception) => unsafe { Box::from_raw(exception.as_ptr()) },
+            ErlangResult::Ok(term) => panic!("expected an exception, but got {:?}", term),
+        }
+    }
+
+    /// Returns the frames of the stacktrace of `exception` as a `try .. catch` observes them
+    fn stacktrace(exception: &ErlangException) -> Vec<Term> {
+        let trace = exception.trace();
+        let term = unsafe { build_stacktrace(NonNull::from(&*trace)) };
+        list_elements(term.into())
+    }
+
+    fn symbolicate(frame: Term) -> Symbolication {
+        frame.try_into().unwrap()
+    }
+
+    /// Returns the argument list of `frame`, if it has one in place of its arity
+    fn arguments(frame: Term) -> Option<Vec<Term>> {
+        let Term::Tuple(ptr) = frame else { panic!("expected a tuple, but got {:?}", frame); };
+        match unsafe { ptr.as_ref() }.as_slice()[2].into() {
+            Term::Int(_) => None,
+            args => Some(list_elements(args)),
+        }
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    #[export_name = "stacktrace_test:raise_error/1"]
+    #[inline(never)]
+    extern "C-unwind" fn raise_error(reason: OpaqueTerm) -> ErlangResult {
+        black_box(error1(reason))
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    #[export_name = "stacktrace_test:raise_error_with_args/2"]
+    #[inline(never)]
+    extern "C-unwind" fn raise_error_with_args(
+        reason: OpaqueTerm,
+        args: OpaqueTerm,
+    ) -> ErlangResult {
+        black_box(error2(reason, args))
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    #[export_name = "stacktrace_test:raise_function_clause/1"]
+    #[inline(never)]
+    extern "C-unwind" fn raise_function_clause(arg: OpaqueTerm) -> ErlangResult {
+        // This is the reason compiled code raises when no clause of this function matches
+        let process = process();
+        let args = list(&[arg], &process);
+        let module = atom("stacktrace_test");
+        let function = atom("raise_function_clause");
+        let frame =
+            Tuple::from_slice(&[module, function, args, OpaqueTerm::NIL], &process).unwrap();
+        let reason =
+            Tuple::from_slice(&[atoms::FunctionClause.into(), frame.into()], &process).unwrap();
+        black_box(error1(reason.into()))
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    #[export_name = "stacktrace_test:caller/1"]
+    #[inline(never)]
+    extern "C-unwind" fn caller(
+        raise: extern "C-unwind" fn(OpaqueTerm) -> ErlangResult,
+    ) -> ErlangResult {
+        black_box(raise(Term::Int(1).into()))
+    }
+
+    #[test]
+    fn stacktrace_begins_with_the_function_which_raised() {
+        let exception = unwrap_err(caller(raise_error));
+        let frames = stacktrace(&exception);
+
+        assert_eq!(exception.reason(), Term::Int(1));
+        assert_eq!(symbolicate(frames[0]).mfa(), Some(mfa("raise_error", 1)));
+        assert_eq!(symbolicate(frames[1]).mfa(), Some(mfa("caller", 1)));
+        assert_eq!(arguments(frames[0]), None);
+        let location = symbolicate(frames[0]);
+        assert!(location.filename().unwrap().ends_with("mod.rs"));
+        assert!(location.line().is_some());
+    }
+
+    #[test]
+    fn function_clause_has_the_arguments_in_the_top_frame() {
+        let exception = unwrap_err(caller(raise_function_clause));
+        let frames = stacktrace(&exception);
+
+        assert_eq!(exception.reason(), Term::Atom(atoms::FunctionClause));
+        let top = symbolicate(frames[0]);
+        assert_eq!(top.mfa(), Some(mfa("raise_function_clause", 1)));
+        assert!(top.filename().is_some());
+        assert_eq!(arguments(frames[0]), Some(vec![Term::Int(1)]));
+        assert_eq!(symbolicate(frames[1]).mfa(), Some(mfa("caller", 1)));
+        assert_eq!(arguments(frames[1]), None);
+    }
+
+    #[test]
+    fn error_with_args_replaces_the_arity_of_the_caller() {
+        let process = process();
+        let args = list(&[Term::Int(1).into(), Term::Int(2).into()], &process);
+        let exception = unwrap_err(raise_error_with_args(atoms::Badarg.into(), args));
+        let frames = stacktrace(&exception);
+
+        assert_eq!(exception.reason(), Term::Atom(atoms::Badarg));
+        assert_eq!(
+            symbolicate(frames[0]).mfa(),
+            Some(mfa("raise_error_with_args", 2))
+        );
+        assert_eq!(arguments(frames[0]), Some(vec![Term::Int(1), Term::Int(2)]));
+    }
+
+    #[test]
+    fn stacktrace_is_the_same_each_time_it_is_caught() {
+        let exception = unwrap_err(caller(raise_error));
+        let trace = exception.trace();
+
+        let first = unsafe { build_stacktrace(NonNull::from(&*trace)) };
+        let second = unsafe { build_stacktrace(NonNull::from(&*trace)) };
+        assert_eq!(first.raw(), second.raw());
+    }
+
+    #[test]
+    fn apply_with_too_many_arguments_is_badarg() {
+        let process = process();
+        let args = vec![OpaqueTerm::NIL; function::MAX_ARITY + 1];
+        let arglist = list(args.as_slice(), &process);
+        let exception = unwrap_err(apply3(atom("stacktrace_test"), atom("caller"), arglist));
+
+        assert_eq!(exception.reason(), Term::Atom(atoms::Badarg));
+    }
+
+    fn memory_of(category: &str) -> i64 {
+        match memory1(atom(category)) {
+            ErlangResult::Ok(bytes) => match bytes.into() {
+                Term::Int(bytes) => bytes,
+                other => panic!("expected an integer, but got {:?}", other),
+            },
+            ErlangResult::Err(_) => panic!("memory({}) raised an exception", category),
+        }
+    }
+
+    #[test]
+    fn memory_reports_each_category() {
+        let process = process();
+        let items = list_elements(memory(&process).into());
+        let names = items
+            .iter()
+            .map(|item| {
+                let Term::Tuple(ptr) = item else { panic!("expected a tuple, but got {:?}", item); };
+                let tuple = unsafe { ptr.as_ref() };
+                assert_eq!(tuple.len(), 2);
+                assert!(matches!(tuple.as_slice()[1].into(), Term::Int(bytes) if bytes >= 0));
+                Term::from(tuple.as_slice()[0]).to_string()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["total", "processes", "atom", "binary", "ets"]);
+        // The heap and stack of `process` are still allocated
+        assert!(memory_of("processes") > 0);
+        assert!(memory_of("total") >= memory_of("processes"));
+    }
+
+    #[test]
+    fn memory_of_unknown_category_is_badarg() {
+        for category in [atom("code"), atom("unknown"), Term::Int(1).into()] {
+            let exception = unwrap_err(memory1(category));
+
+            assert_eq!(exception.reason(), Term::Atom(atoms::Badarg));
+        }
+    }
+
+    #[test]
+    fn memory_counts_refc_binaries_until_they_are_dropped() {
+        // Large enough that binaries allocated and freed by tests running concurrently don't hide
+        // the difference
+        const SIZE: usize = 16 * 1024 * 1024;
+
+        let before = memory_of("binary");
+        let binary = BinaryData::with_capacity_large(SIZE, std::alloc::Global).unwrap();
+        let during = memory_of("binary");
+
+        assert!(
+            during - before >= (SIZE / 2) as i64,
+            "binary memory grew from {} to {}",
+            before,
+            during
+        );
+
+        // There is no collector in this runtime, so a binary is freed as soon as its last
+        // reference is dropped, rather than when a collection finds it unreachable
+        drop(binary);
+        let after = memory_of("binary");
+
+        assert!(
+            during - after >= (SIZE / 2) as i64,
+            "binary memory shrank from {} to {}",
+            during,
+            after
+        );
+    }
+
+    #[test]
+    fn put_returns_previous_value_or_undefined() {
+        let process = process();
+        let key = atom("key");
+
+        assert_eq!(put(&process, key, Term::Int(1).into()), atom("undefined"));
+        assert_eq!(put(&process, key, Term::Int(2).into()), Term::Int(1).into());
+        assert_eq!(process.dictionary().get(key.into()), Some(Term::Int(2)));
+    }
+
+    #[test]
+    fn put_copies_key_and_value_onto_the_process_heap() {
+        let other = process();
+        let process = process();
+        let elements = [atom("boxed"), Term::Int(1).into()];
+        let key = Term::Tuple(Tuple::from_slice(&elements, &other).unwrap());
+        let value = list(&elements, &other);
+
+        put(&process, key.into(), value);
+
+        let (stored_key, stored_value) = process.dictionary().iter().next().unwrap();
+        let Term::Tuple(key_ptr) = stored_key else { panic!("expected a tuple, but got {:?}", stored_key); };
+        let Term::Cons(value_ptr) = stored_value else { panic!("expected a list, but got {:?}", stored_value); };
+        assert!(process.contains(key_ptr.as_ptr()));
+        assert!(process.contains(value_ptr.as_ptr()));
+        assert_eq!(
+            list_elements(stored_value),
+            vec![Term::from(elements[0]), Term::Int(1)]
+        );
+    }
+
+    #[test]
+    fn erase_all_returns_every_entry_and_empties_the_dictionary() {
+        let process = process();
+        put(&process, atom("a"), Term::Int(1).into());
+        put(&process, atom("b"), Term::Int(2).into());
+
+        let mut entries = list_elements(erase_all(&process).into())
+            .into_iter()
+            .map(|entry| {
+                let Term::Tuple(ptr) = entry else { panic!("expected a tuple, but got {:?}", entry); };
+                let pair = unsafe { ptr.as_ref() }.as_slice();
+                (Term::from(pair[0]).to_string(), Term::from(pair[1]))
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            entries,
+            vec![
+                ("a".to_string(), Term::Int(1)),
+                ("b".to_string(), Term::Int(2))
+            ]
+        );
+        assert!(process.dictionary().is_empty());
+        assert_eq!(erase_all(&process), OpaqueTerm::NIL);
+    }
+
+    #[test]
+    fn get_keys_returns_keys_with_exactly_value() {
+        let process = process();
+        put(&process, atom("a"), Term::Int(1).into());
+        put(&process, atom("b"), Term::Int(2).into());
+        put(&process, atom("c"), Term::Int(1).into());
+        put(&process, atom("d"), Term::from(1.0).into());
+
+        let mut keys = list_elements(get_keys(&process, Term::Int(1).into()).into())
+            .into_iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>();
+        keys.sort();
+
+        assert_eq!(keys, ["a", "c"]);
+    }
+
+    fn tuple_elements(tuple: NonNull<Tuple>) -> Vec<Term> {
+        let tuple = unsafe { tuple.as_ref() };
+        tuple.as_slice().iter().copied().map(Term::from).collect()
+    }
+
+    fn pair(position: i64, value: i64, process: &Process) -> OpaqueTerm {
+        let elements = [Term::Int(position).into(), Term::Int(value).into()];
+        Tuple::from_slice(&elements, process).unwrap().into()
+    }
+
+    #[test]
+    fn tuple_to_list_of_empty_tuple_is_nil() {
+        let process = process();
+        let tuple = Tuple::from_slice(&[], &process).unwrap();
+
+        assert_eq!(
+            tuple_to_list(unsafe { tuple.as_ref() }, &process),
+            OpaqueTerm::NIL
+        );
+    }
+
+    #[test]
+    fn tuple_to_list_and_list_to_tuple_round_trip() {
+        let process = process();
+        let elements = [atom("a"), Term::Int(2).into(), atom("c")];
+        let tuple = Tuple::from_slice(&elements, &process).unwrap();
+
+        let list = tuple_to_list(unsafe { tuple.as_ref() }, &process);
+        assert_eq!(
+            list_elements(list.into()),
+            elements.iter().copied().map(Term::from).collect::<Vec<_>>()
+        );
+
+        let round_tripped = list_to_tuple(list, &process).unwrap();
+        assert_eq!(tuple_elements(round_tripped), tuple_elements(tuple));
+    }
+
+    #[test]
+    fn list_to_tuple_of_nil_is_empty_tuple() {
+        let process = process();
+        let tuple = list_to_tuple(OpaqueTerm::NIL, &process).unwrap();
+
+        assert!(unsafe { tuple.as_ref() }.is_empty());
+    }
+
+    #[test]
+    fn list_to_tuple_of_improper_list_is_badarg() {
+        let process = process();
+        let cons = Cons::new_in(&process).unwrap();
+        unsafe {
+            cons.as_ptr().write(Cons {
+                head: Term::Int(1).into(),
+                tail: atom("tail"),
+            });
+        }
+
+        assert!(list_to_tuple(cons.into(), &process).is_none());
+        assert!(list_to_tuple(atom("list"), &process).is_none());
+    }
+
+    #[test]
+    fn make_tuple_fills_with_default() {
+        let process = process();
+        let tuple = make_tuple(Term::Int(3).into(), atom("x"), OpaqueTerm::NIL, &process).unwrap();
+
+        assert_eq!(tuple_elements(tuple), vec![Term::from(atom("x")); 3]);
+    }
+
+    #[test]
+    fn make_tuple_with_init_list_applies_pairs_in_order() {
+        let process = process();
+        let init_list = list(
+            &[
+                pair(1, 10, &process),
+                pair(3, 30, &process),
+                pair(1, 11, &process),
+            ],
+            &process,
+        );
+        let tuple = make_tuple(Term::Int(3).into(), atom("x"), init_list, &process).unwrap();
+
+        assert_eq!(
+            tuple_elements(tuple),
+            vec![Term::Int(11), Term::from(atom("x")), Term::Int(30)]
+        );
+    }
+
+    #[test]
+    fn make_tuple_with_out_of_range_position_is_badarg() {
+        let process = process();
+        for position in [0, 4, -1] {
+            let init_list = list(&[pair(position, 10, &process)], &process);
+
+            assert!(make_tuple(Term::Int(3).into(), atom("x"), init_list, &process).is_none());
+        }
+    }
+
+    #[test]
+    fn make_tuple_with_invalid_arity_is_badarg() {
+        let process = process();
+        for arity in [-1, Tuple::MAX_LEN as i64 + 1] {
+            let arity = Term::Int(arity).into();
+
+            assert!(make_tuple(arity, atom("x"), OpaqueTerm::NIL, &process).is_none());
+        }
+        assert!(make_tuple(atom("three"), atom("x"), OpaqueTerm::NIL, &process).is_none());
+    }
+
+    #[test]
+    fn setelement_replaces_first_and_last_elements() {
+        let process = process();
+        let elements = [atom("a"), atom("b"), atom("c")];
+        let tuple = Tuple::from_slice(&elements, &process).unwrap();
+
+        let first = setelement(Term::Int(1).into(), tuple.into(), atom("x"), &process).unwrap();
+        let last = setelement(Term::Int(3).into(), tuple.into(), atom("x"), &process).unwrap();
+
+        assert_eq!(
+            tuple_elements(first),
+            [atom("x"), atom("b"), atom("c")].map(Term::from)
+        );
+        assert_eq!(
+            tuple_elements(last),
+            [atom("a"), atom("b"), atom("x")].map(Term::from)
+        );
+    }
+
+    #[test]
+    fn setelement_leaves_the_original_tuple_unchanged() {
+        let process = process();
+        let elements = [atom("a"), atom("b")];
+        // The most recent allocation on the heap, but still referenced here
+        let tuple = Tuple::from_slice(&elements, &process).unwrap();
+
+        let updated = setelement(Term::Int(2).into(), tuple.into(), atom("x"), &process).unwrap();
+
+        assert_ne!(updated, tuple);
+        assert_eq!(tuple_elements(tuple), elements.map(Term::from));
+    }
+
+    #[test]
+    fn setelement_with_index_out_of_range_is_badarg() {
+        let process = process();
+        let tuple = Tuple::from_slice(&[atom("a"), atom("b")], &process).unwrap();
+
+        for index in [
+            Term::Int(0),
+            Term::Int(3),
+            Term::Int(-1),
+            Term::from(atom("one")),
+        ] {
+            assert!(setelement(index.into(), tuple.into(), atom("x"), &process).is_none());
+        }
+    }
+
+    #[test]
+    fn setelement_of_non_tuple_is_badarg() {
+        let process = process();
+        let list = list(&[atom("a")], &process);
+
+        assert!(setelement(Term::Int(1).into(), list, atom("x"), &process).is_none());
+    }
+}
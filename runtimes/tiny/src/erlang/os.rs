@@ -0,0 +1,272 @@
+//! Access to the environment variables of the system, see [`crate::env::Variables`]
+//!
+//! Names and values are given as charlists of Unicode codepoints, which are encoded as UTF-8 in
+//! the environment. Variables set outside of Erlang may contain values which are not valid UTF-8
+//! though, so when converting them to charlists, any invalid sequence of bytes is replaced with
+//! `U+FFFD REPLACEMENT CHARACTER`, just as the arguments of the executable are. Such values
+//! therefore can't be recovered exactly from Erlang.
+use std::ffi::{OsStr, OsString};
+
+use firefly_rt::backtrace::Trace;
+use firefly_rt::function::ErlangResult;
+use firefly_rt::process::Process;
+use firefly_rt::term::*;
+
+use crate::env::{self, Variables};
+use crate::scheduler;
+
+use super::badarg;
+
+#[export_name = "os:getenv/0"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn getenv0() -> ErlangResult {
+    scheduler::with_current_process(|proc| ErlangResult::Ok(getenv_all(&env::variables(), proc)))
+}
+
+#[export_name = "os:getenv/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn getenv1(name: OpaqueTerm) -> ErlangResult {
+    getenv2(name, false.into())
+}
+
+#[export_name = "os:getenv/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn getenv2(name: OpaqueTerm, default: OpaqueTerm) -> ErlangResult {
+    scheduler::with_current_process(
+        |proc| match getenv(&env::variables(), name, default, proc) {
+            Ok(value) => ErlangResult::Ok(value),
+            Err(_) => badarg(Trace::capture()),
+        },
+    )
+}
+
+#[export_name = "os:putenv/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn putenv2(name: OpaqueTerm, value: OpaqueTerm) -> ErlangResult {
+    match putenv(&mut env::variables_mut(), name, value) {
+        Ok(_) => ErlangResult::Ok(true.into()),
+        Err(_) => badarg(Trace::capture()),
+    }
+}
+
+/// Returns the value of `name` as a charlist, or `default` if it is not set
+///
+/// A name which can never be set, such as one containing `=`, is not an error, it just has no value.
+fn getenv(
+    vars: &Variables,
+    name: OpaqueTerm,
+    default: OpaqueTerm,
+    process: &Process,
+) -> Result<OpaqueTerm, ()> {
+    let name = string(name)?;
+    if !is_valid_name(&name) {
+        return Ok(default);
+    }
+    match vars.get(OsStr::new(&name)) {
+        Some(value) => Ok(charlist(&value.to_string_lossy(), process)),
+        None => Ok(default),
+    }
+}
+
+/// Returns a list of all the variables which are set, each as a `"NAME=VALUE"` charlist
+fn getenv_all(vars: &Variables, process: &Process) -> OpaqueTerm {
+    let vars = vars
+        .all()
+        .iter()
+        .map(|(name, value)| {
+            let var = format!("{}={}", name.to_string_lossy(), value.to_string_lossy());
+            charlist(&var, process).into()
+        })
+        .collect::<Vec<Term>>();
+    match Cons::from_slice(&vars, process).unwrap() {
+        None => OpaqueTerm::NIL,
+        Some(cons) => cons.into(),
+    }
+}
+
+fn putenv(vars: &mut Variables, name: OpaqueTerm, value: OpaqueTerm) -> Result<(), ()> {
+    let name = string(name)?;
+    let value = string(value)?;
+    if !is_valid_name(&name) || value.contains('\0') {
+        return Err(());
+    }
+    vars.set(OsString::from(name), OsString::from(value));
+    Ok(())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', '\0'])
+}
+
+/// Converts a proper list of codepoints to a string
+fn string(list: OpaqueTerm) -> Result<String, ()> {
+    match list.into() {
+        Term::Nil => Ok(String::new()),
+        Term::Cons(cons) => unsafe { cons.as_ref() }.to_string().ok_or(()),
+        _ => Err(()),
+    }
+}
+
+fn charlist(s: &str, process: &Process) -> OpaqueTerm {
+    match Cons::charlist_from_str(s, process).unwrap() {
+        None => OpaqueTerm::NIL,
+        Some(cons) => cons.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cargo sets this for the test executable, and nothing in the tests modifies the environment
+    const CARGO_PKG_NAME: &str = "CARGO_PKG_NAME";
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn text(list: OpaqueTerm) -> String {
+        string(list).unwrap()
+    }
+
+    fn getenv_text(vars: &Variables, name: &str, process: &Process) -> Option<String> {
+        match getenv(vars, charlist(name, process), false.into(), process).unwrap() {
+            value if value == false.into() => None,
+            value => Some(text(value)),
+        }
+    }
+
+    fn putenv_text(
+        vars: &mut Variables,
+        name: &str,
+        value: &str,
+        process: &Process,
+    ) -> Result<(), ()> {
+        putenv(vars, charlist(name, process), charlist(value, process))
+    }
+
+    fn getenv_all_text(vars: &Variables, process: &Process) -> Vec<String> {
+        match getenv_all(vars, process).into() {
+            Term::Nil => vec![],
+            Term::Cons(cons) => unsafe { cons.as_ref() }
+                .iter()
+                .map(|var| text(var.unwrap().into()))
+                .collect(),
+            other => panic!("expected a list, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unset_variables_return_the_default() {
+        let process = process();
+        let vars = Variables::default();
+        let name = charlist("FIREFLY_TEST_UNSET_VARIABLE", &process);
+        let default = Term::Int(42).into();
+
+        assert_eq!(
+            getenv(&vars, name, false.into(), &process),
+            Ok(false.into())
+        );
+        assert_eq!(getenv(&vars, name, default, &process), Ok(default));
+        // Names which can never be set are treated the same way
+        assert_eq!(
+            getenv(&vars, OpaqueTerm::NIL, default, &process),
+            Ok(default)
+        );
+        assert_eq!(
+            getenv(&vars, charlist("A=B", &process), default, &process),
+            Ok(default)
+        );
+    }
+
+    #[test]
+    fn variables_are_read_from_the_environment() {
+        let process = process();
+        let vars = Variables::default();
+        let expected = std::env::var(CARGO_PKG_NAME).unwrap();
+
+        assert_eq!(
+            getenv_text(&vars, CARGO_PKG_NAME, &process),
+            Some(expected.clone())
+        );
+        assert!(
+            getenv_all_text(&vars, &process).contains(&format!("{}={}", CARGO_PKG_NAME, expected))
+        );
+    }
+
+    #[test]
+    fn put_variables_take_precedence() {
+        let process = process();
+        let mut vars = Variables::default();
+
+        putenv_text(&mut vars, CARGO_PKG_NAME, "overlaid", &process).unwrap();
+        putenv_text(&mut vars, "FIREFLY_TEST_SET_VARIABLE", "", &process).unwrap();
+
+        assert_eq!(
+            getenv_text(&vars, CARGO_PKG_NAME, &process),
+            Some("overlaid".to_string())
+        );
+        assert_eq!(
+            getenv_text(&vars, "FIREFLY_TEST_SET_VARIABLE", &process),
+            Some(String::new())
+        );
+        let all = getenv_all_text(&vars, &process);
+        let pkg_names = all
+            .iter()
+            .filter(|var| var.starts_with("CARGO_PKG_NAME="))
+            .collect::<Vec<_>>();
+        assert_eq!(pkg_names, vec!["CARGO_PKG_NAME=overlaid"]);
+        assert!(all.contains(&"FIREFLY_TEST_SET_VARIABLE=".to_string()));
+    }
+
+    #[test]
+    fn unicode_values_are_preserved() {
+        let process = process();
+        let mut vars = Variables::default();
+        let value = "héllo wörld ✓ 🦀";
+
+        putenv_text(&mut vars, "FIREFLY_TEST_ÜNICODE", value, &process).unwrap();
+
+        assert_eq!(
+            getenv_text(&vars, "FIREFLY_TEST_ÜNICODE", &process),
+            Some(value.to_string())
+        );
+        let value = getenv(
+            &vars,
+            charlist("FIREFLY_TEST_ÜNICODE", &process),
+            false.into(),
+            &process,
+        )
+        .unwrap();
+        let Term::Cons(cons) = value.into() else { panic!("expected a charlist"); };
+        let codepoints = unsafe { cons.as_ref() }
+            .iter()
+            .map(|c| c.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(codepoints.len(), 15);
+        assert_eq!(codepoints[14], Term::Int(0x1f980));
+        assert!(getenv_all_text(&vars, &process)
+            .contains(&"FIREFLY_TEST_ÜNICODE=héllo wörld ✓ 🦀".to_string()));
+    }
+
+    #[test]
+    fn invalid_variables_are_rejected() {
+        let process = process();
+        let mut vars = Variables::default();
+
+        assert!(putenv_text(&mut vars, "", "value", &process).is_err());
+        assert!(putenv_text(&mut vars, "A=B", "value", &process).is_err());
+        assert!(putenv_text(&mut vars, "A\0B", "value", &process).is_err());
+        assert!(putenv_text(&mut vars, "NAME", "va\0lue", &process).is_err());
+        assert!(putenv(&mut vars, Term::Int(1).into(), OpaqueTerm::NIL).is_err());
+        let improper = Cons::new_in(&process).unwrap();
+        unsafe {
+            improper
+                .as_ptr()
+                .write(Cons::cons(Term::Int(65), Term::Int(66)));
+        }
+        assert!(putenv(&mut vars, improper.into(), OpaqueTerm::NIL).is_err());
+        assert!(getenv(&vars, Term::Int(1).into(), false.into(), &process).is_err());
+        assert!(vars.all().iter().all(|(name, _)| name != "NAME"));
+    }
+}
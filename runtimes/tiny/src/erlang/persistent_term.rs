@@ -0,0 +1,39 @@
+use firefly_rt::backtrace::Trace;
+use firefly_rt::function::ErlangResult;
+use firefly_rt::term::*;
+
+use crate::persistent_term;
+
+use super::badarg;
+
+#[export_name = "persistent_term:put/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn put2(key: OpaqueTerm, value: OpaqueTerm) -> ErlangResult {
+    persistent_term::write().put(key.into(), value.into());
+    ErlangResult::Ok(atoms::Ok.into())
+}
+
+#[export_name = "persistent_term:get/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn get1(key: OpaqueTerm) -> ErlangResult {
+    match persistent_term::read().get(key.into()) {
+        Some(value) => ErlangResult::Ok(value.into()),
+        None => badarg(Trace::capture()),
+    }
+}
+
+#[export_name = "persistent_term:get/2"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn get2(key: OpaqueTerm, default: OpaqueTerm) -> ErlangResult {
+    match persistent_term::read().get(key.into()) {
+        Some(value) => ErlangResult::Ok(value.into()),
+        None => ErlangResult::Ok(default),
+    }
+}
+
+#[export_name = "persistent_term:erase/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn erase1(key: OpaqueTerm) -> ErlangResult {
+    let erased = persistent_term::write().erase(key.into());
+    ErlangResult::Ok(erased.into())
+}
@@ -1,15 +1,643 @@
+use std::mem;
+use std::ops::{ControlFlow, Deref};
+
+use firefly_binary::Endianness;
 use firefly_rt::backtrace::Trace;
 use firefly_rt::function::ErlangResult;
+use firefly_rt::process::Process;
 use firefly_rt::term::*;
 
+use crate::scheduler;
+
 use super::badarg;
 
+#[export_name = "unicode:characters_to_binary/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn characters_to_binary1(data: OpaqueTerm) -> ErlangResult {
+    characters_to_binary(data, CharEncoding::Utf8, CharEncoding::Utf8)
+}
+
+#[export_name = "unicode:characters_to_binary/3"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn characters_to_binary3(
+    data: OpaqueTerm,
+    in_encoding: OpaqueTerm,
+    out_encoding: OpaqueTerm,
+) -> ErlangResult {
+    let Some(in_encoding) = CharEncoding::from_term(in_encoding) else { return badarg(Trace::capture()); };
+    let Some(out_encoding) = CharEncoding::from_term(out_encoding) else { return badarg(Trace::capture()); };
+    characters_to_binary(data, in_encoding, out_encoding)
+}
+
+fn characters_to_binary(
+    data: OpaqueTerm,
+    in_encoding: CharEncoding,
+    out_encoding: CharEncoding,
+) -> ErlangResult {
+    with_current_process(|process| {
+        let converted = convert(data.into(), in_encoding, out_encoding, process)?;
+        Some(converted.into_term(process, |chars| {
+            binary_from_bytes(&out_encoding.encode(chars), process)
+        }))
+    })
+}
+
+#[export_name = "unicode:characters_to_list/1"]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn characters_to_list1(data: OpaqueTerm) -> ErlangResult {
+    characters_to_list(data, CharEncoding::Utf8)
+}
+
 #[export_name = "unicode:characters_to_list/2"]
 #[allow(improper_ctypes_definitions)]
-pub extern "C-unwind" fn characters_to_list(
-    _data: OpaqueTerm,
-    encoding: OpaqueTerm,
+pub extern "C-unwind" fn characters_to_list2(
+    data: OpaqueTerm,
+    in_encoding: OpaqueTerm,
 ) -> ErlangResult {
-    let Term::Atom(_encoding) = encoding.into() else { return badarg(Trace::capture()) };
-    todo!()
+    let Some(in_encoding) = CharEncoding::from_term(in_encoding) else { return badarg(Trace::capture()); };
+    characters_to_list(data, in_encoding)
+}
+
+fn characters_to_list(data: OpaqueTerm, in_encoding: CharEncoding) -> ErlangResult {
+    with_current_process(|process| {
+        let converted = convert(data.into(), in_encoding, CharEncoding::Utf8, process)?;
+        Some(converted.into_term(process, |chars| charlist(chars, process)))
+    })
+}
+
+/// Runs `fun` with the current process, raising `badarg` if it returns `None`
+fn with_current_process<F>(fun: F) -> ErlangResult
+where
+    F: FnOnce(&Process) -> Option<Term>,
+{
+    let result = scheduler::with_current(|scheduler| {
+        let arc_proc = scheduler.current_process();
+        fun(arc_proc.deref())
+    });
+    match result {
+        Some(term) => ErlangResult::Ok(term.into()),
+        None => badarg(Trace::capture()),
+    }
+}
+
+/// The encodings the `unicode` functions convert characters between
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum CharEncoding {
+    Latin1,
+    Utf8,
+    Utf16(Endianness),
+    Utf32(Endianness),
+}
+impl CharEncoding {
+    /// Parses an encoding, where `unicode` is the same as `utf8`, and `utf16` and `utf32` are
+    /// big-endian unless given as `{utf16, little}` or `{utf32, little}`
+    fn from_term(term: OpaqueTerm) -> Option<Self> {
+        match term.into() {
+            Term::Atom(a) if a == atoms::Latin1 => Some(Self::Latin1),
+            Term::Atom(a) if a == atoms::Unicode || a == atoms::Utf8 => Some(Self::Utf8),
+            Term::Atom(a) if a == atoms::Utf16 => Some(Self::Utf16(Endianness::Big)),
+            Term::Atom(a) if a == atoms::Utf32 => Some(Self::Utf32(Endianness::Big)),
+            Term::Tuple(ptr) => {
+                let [encoding, endianness] = unsafe { ptr.as_ref() }.as_slice() else { return None; };
+                let endianness = match (*endianness).into() {
+                    Term::Atom(a) if a == atoms::Big => Endianness::Big,
+                    Term::Atom(a) if a == atoms::Little => Endianness::Little,
+                    _ => return None,
+                };
+                match (*encoding).into() {
+                    Term::Atom(a) if a == atoms::Utf16 => Some(Self::Utf16(endianness)),
+                    Term::Atom(a) if a == atoms::Utf32 => Some(Self::Utf32(endianness)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if `c` can be represented in this encoding
+    fn can_encode(self, c: char) -> bool {
+        match self {
+            Self::Latin1 => (c as u32) <= 0xff,
+            _ => true,
+        }
+    }
+
+    /// Decodes the character `bytes` begins with, which must not be empty
+    fn decode(self, bytes: &[u8]) -> Decoded {
+        match self {
+            Self::Latin1 => Decoded::Char(bytes[0] as char, 1),
+            Self::Utf8 => {
+                let len = match bytes[0] {
+                    0x00..=0x7f => 1,
+                    0xc2..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf4 => 4,
+                    _ => return Decoded::Invalid,
+                };
+                match std::str::from_utf8(&bytes[..len.min(bytes.len())]) {
+                    Ok(s) => Decoded::Char(s.chars().next().unwrap(), len),
+                    // The bytes are valid as far as they go, but there aren't enough of them
+                    Err(err) if err.error_len().is_none() => Decoded::Incomplete,
+                    Err(_) => Decoded::Invalid,
+                }
+            }
+            Self::Utf16(endianness) => {
+                let Some(unit) = read_unit(bytes, 2, endianness) else { return Decoded::Incomplete; };
+                match unit {
+                    0xd800..=0xdbff => {
+                        let Some(low) = read_unit(&bytes[2..], 2, endianness) else { return Decoded::Incomplete; };
+                        if !(0xdc00..=0xdfff).contains(&low) {
+                            return Decoded::Invalid;
+                        }
+                        let c = 0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00);
+                        Decoded::Char(char::from_u32(c).unwrap(), 4)
+                    }
+                    0xdc00..=0xdfff => Decoded::Invalid,
+                    unit => Decoded::Char(char::from_u32(unit).unwrap(), 2),
+                }
+            }
+            Self::Utf32(endianness) => {
+                let Some(unit) = read_unit(bytes, 4, endianness) else { return Decoded::Incomplete; };
+                match char::from_u32(unit) {
+                    Some(c) => Decoded::Char(c, 4),
+                    None => Decoded::Invalid,
+                }
+            }
+        }
+    }
+
+    /// Encodes `chars`, each of which must be representable in this encoding
+    fn encode(self, chars: &[char]) -> Vec<u8> {
+        match self {
+            Self::Latin1 => chars.iter().map(|c| *c as u8).collect(),
+            Self::Utf8 => chars.iter().collect::<String>().into_bytes(),
+            Self::Utf16(endianness) => {
+                let mut bytes = Vec::with_capacity(chars.len() * 2);
+                let mut units = [0; 2];
+                for c in chars {
+                    for unit in c.encode_utf16(&mut units) {
+                        match endianness {
+                            Endianness::Little => bytes.extend_from_slice(&unit.to_le_bytes()),
+                            _ => bytes.extend_from_slice(&unit.to_be_bytes()),
+                        }
+                    }
+                }
+                bytes
+            }
+            Self::Utf32(endianness) => {
+                let mut bytes = Vec::with_capacity(chars.len() * 4);
+                for c in chars {
+                    match endianness {
+                        Endianness::Little => bytes.extend_from_slice(&(*c as u32).to_le_bytes()),
+                        _ => bytes.extend_from_slice(&(*c as u32).to_be_bytes()),
+                    }
+                }
+                bytes
+            }
+        }
+    }
+}
+
+/// Reads a code unit of `size` bytes from the start of `bytes`, if there are enough of them
+fn read_unit(bytes: &[u8], size: usize, endianness: Endianness) -> Option<u32> {
+    let bytes = bytes.get(..size)?;
+    let push = |unit: u32, byte: &u8| (unit << 8) | *byte as u32;
+    match endianness {
+        Endianness::Little => Some(bytes.iter().rev().fold(0, push)),
+        _ => Some(bytes.iter().fold(0, push)),
+    }
+}
+
+/// The result of decoding a character from the start of some bytes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Decoded {
+    /// A character, and the number of bytes it was encoded in
+    Char(char, usize),
+    /// The bytes begin a character which continues past their end
+    Incomplete,
+    /// The bytes do not begin with a character
+    Invalid,
+}
+
+/// The result of converting chardata, following the convention of the `unicode` module of
+/// returning errors rather than raising them
+#[derive(Debug)]
+enum Converted {
+    Ok(Vec<char>),
+    /// `{error, Converted, Rest}`, where `Rest` begins with the first character which is invalid
+    /// in the input encoding, or can't be represented in the output encoding, or the first term
+    /// which isn't chardata at all
+    Error(Vec<char>, Term),
+    /// `{incomplete, Converted, Rest}`, where `Rest` is a binary of the bytes at the end of the
+    /// input which begin a character, but not all of it
+    Incomplete(Vec<char>, Term),
+}
+impl Converted {
+    /// Builds the result of a conversion, with `output` building the converted characters
+    fn into_term<F>(self, process: &Process, output: F) -> Term
+    where
+        F: Fn(&[char]) -> Term,
+    {
+        let (tag, chars, rest) = match self {
+            Self::Ok(chars) => return output(&chars),
+            Self::Error(chars, rest) => (atoms::Error, chars, rest),
+            Self::Incomplete(chars, rest) => (atoms::Incomplete, chars, rest),
+        };
+        let elements = [tag.into(), output(&chars).into(), rest.into()];
+        Term::Tuple(Tuple::from_slice(&elements, process).unwrap())
+    }
+}
+
+/// The characters converted so far, as chardata is folded over
+#[derive(Default)]
+struct Conversion {
+    chars: Vec<char>,
+    /// The bytes at the end of the last binary, which begin a character that was incomplete
+    incomplete: Vec<u8>,
+}
+
+/// Decodes the characters of `data` from `in_encoding`, checking that they can be encoded in
+/// `out_encoding`, or returns `None` if `data` is neither a list nor a binary.
+///
+/// A character may be split across consecutive binaries, but not across a binary and a list
+/// element, which is an error.
+fn convert(
+    data: Term,
+    in_encoding: CharEncoding,
+    out_encoding: CharEncoding,
+    process: &Process,
+) -> Option<Converted> {
+    match data {
+        Term::Nil | Term::Cons(_) => (),
+        _ if data.as_bitstring().map_or(false, |bits| bits.is_binary()) => (),
+        _ => return None,
+    }
+
+    let result = data.chardata_fold(Conversion::default(), |mut conversion, chunk| match chunk {
+        // Characters in lists are codepoints whatever the input encoding, but latin1 has fewer
+        // of them, and they can't complete a character left incomplete by a binary
+        CharChunk::Char(i) => match u32::try_from(i).ok().and_then(char::from_u32) {
+            Some(c)
+                if conversion.incomplete.is_empty()
+                    && in_encoding.can_encode(c)
+                    && out_encoding.can_encode(c) =>
+            {
+                conversion.chars.push(c);
+                ControlFlow::Continue(conversion)
+            }
+            _ => ControlFlow::Break((conversion, Term::Int(i))),
+        },
+        CharChunk::Binary(bits) => {
+            let mut bytes = mem::take(&mut conversion.incomplete);
+            bytes.extend(bits.bytes());
+            let mut offset = 0;
+            while offset < bytes.len() {
+                match in_encoding.decode(&bytes[offset..]) {
+                    Decoded::Char(c, len) if out_encoding.can_encode(c) => {
+                        conversion.chars.push(c);
+                        offset += len;
+                    }
+                    Decoded::Incomplete => {
+                        conversion.incomplete = bytes[offset..].to_vec();
+                        break;
+                    }
+                    Decoded::Char(..) | Decoded::Invalid => {
+                        let rest = binary_from_bytes(&bytes[offset..], process);
+                        return ControlFlow::Break((conversion, rest));
+                    }
+                }
+            }
+            ControlFlow::Continue(conversion)
+        }
+    });
+
+    let converted = match result {
+        Ok(conversion) if conversion.incomplete.is_empty() => Converted::Ok(conversion.chars),
+        Ok(conversion) => {
+            let rest = binary_from_bytes(&conversion.incomplete, process);
+            Converted::Incomplete(conversion.chars, rest)
+        }
+        Err((conversion, rest)) => {
+            let mut rest = rest.to_term(process).unwrap();
+            // The bytes of an incomplete character are followed by something which can't
+            // complete it, so they are the start of the rest
+            if !conversion.incomplete.is_empty() {
+                let incomplete = binary_from_bytes(&conversion.incomplete, process);
+                rest = list(&[incomplete, rest], process);
+            }
+            Converted::Error(conversion.chars, rest)
+        }
+    };
+    Some(converted)
+}
+
+fn binary_from_bytes(bytes: &[u8], process: &Process) -> Term {
+    if bytes.len() <= BinaryData::MAX_HEAP_BYTES {
+        let mut bin = BinaryData::with_capacity_small(bytes.len(), process).unwrap();
+        bin.copy_from_slice(bytes);
+        bin.into()
+    } else {
+        let bin: OpaqueTerm = BinaryData::from_bytes(bytes).into();
+        bin.into()
+    }
+}
+
+fn charlist(chars: &[char], process: &Process) -> Term {
+    let elements = chars
+        .iter()
+        .map(|c| Term::Int(*c as i64))
+        .collect::<Vec<_>>();
+    list(&elements, process)
+}
+
+fn list(elements: &[Term], process: &Process) -> Term {
+    let elements = elements
+        .iter()
+        .map(|t| (*t).into())
+        .collect::<Vec<OpaqueTerm>>();
+    match Cons::from_slice(&elements, process).unwrap() {
+        None => Term::Nil,
+        Some(cons) => Term::Cons(cons),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use firefly_rt::process::ProcessId;
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(Atom::try_from(name).unwrap())
+    }
+
+    fn tuple(elements: &[Term], process: &Process) -> Term {
+        let elements = elements.iter().map(|t| (*t).into()).collect::<Vec<_>>();
+        Term::Tuple(Tuple::from_slice(&elements, process).unwrap())
+    }
+
+    fn to_binary(data: Term, from: CharEncoding, to: CharEncoding, process: &Process) -> Term {
+        let converted = convert(data, from, to, process).unwrap();
+        converted.into_term(process, |chars| {
+            binary_from_bytes(&to.encode(chars), process)
+        })
+    }
+
+    fn to_list(data: Term, from: CharEncoding, process: &Process) -> Term {
+        let converted = convert(data, from, CharEncoding::Utf8, process).unwrap();
+        converted.into_term(process, |chars| charlist(chars, process))
+    }
+
+    fn utf8_to_binary(data: Term, process: &Process) -> Term {
+        to_binary(data, CharEncoding::Utf8, CharEncoding::Utf8, process)
+    }
+
+    #[test]
+    fn encodings_are_parsed_from_atoms_and_tuples() {
+        let process = process();
+        let encoding = |term: Term| CharEncoding::from_term(term.into());
+
+        assert_eq!(encoding(atom("unicode")), Some(CharEncoding::Utf8));
+        assert_eq!(encoding(atom("latin1")), Some(CharEncoding::Latin1));
+        assert_eq!(
+            encoding(atom("utf16")),
+            Some(CharEncoding::Utf16(Endianness::Big))
+        );
+        let little = tuple(&[atom("utf32"), atom("little")], &process);
+        assert_eq!(
+            encoding(little),
+            Some(CharEncoding::Utf32(Endianness::Little))
+        );
+        let native = tuple(&[atom("utf32"), atom("native")], &process);
+        assert_eq!(encoding(native), None);
+        assert_eq!(encoding(atom("ascii")), None);
+    }
+
+    #[test]
+    fn mixed_deep_lists_are_converted() {
+        let process = process();
+        let inner = list(
+            &[
+                binary_from_bytes("é".as_bytes(), &process),
+                Term::Int('€' as i64),
+            ],
+            &process,
+        );
+        let data = list(
+            &[
+                Term::Int('a' as i64),
+                inner,
+                binary_from_bytes(b"bc", &process),
+            ],
+            &process,
+        );
+
+        let expected = binary_from_bytes("aé€bc".as_bytes(), &process);
+        assert_eq!(utf8_to_binary(data, &process), expected);
+        assert_eq!(
+            to_list(data, CharEncoding::Utf8, &process),
+            charlist(&['a', 'é', '€', 'b', 'c'], &process)
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_in_the_middle_is_an_error() {
+        let process = process();
+        let data = binary_from_bytes(b"abc\xffdef", &process);
+
+        let expected = tuple(
+            &[
+                atom("error"),
+                binary_from_bytes(b"abc", &process),
+                binary_from_bytes(b"\xffdef", &process),
+            ],
+            &process,
+        );
+        assert_eq!(utf8_to_binary(data, &process), expected);
+    }
+
+    #[test]
+    fn the_rest_of_an_error_includes_the_elements_not_yet_visited() {
+        let process = process();
+        let data = list(
+            &[
+                binary_from_bytes(b"ab", &process),
+                atom("c"),
+                binary_from_bytes(b"d", &process),
+            ],
+            &process,
+        );
+
+        let expected = tuple(
+            &[
+                atom("error"),
+                charlist(&['a', 'b'], &process),
+                list(&[atom("c"), binary_from_bytes(b"d", &process)], &process),
+            ],
+            &process,
+        );
+        assert_eq!(to_list(data, CharEncoding::Utf8, &process), expected);
+    }
+
+    #[test]
+    fn incomplete_trailing_sequences_are_returned() {
+        let process = process();
+        // The first two bytes of the encoding of '€'
+        let data = binary_from_bytes(b"abc\xe2\x82", &process);
+
+        let expected = tuple(
+            &[
+                atom("incomplete"),
+                binary_from_bytes(b"abc", &process),
+                binary_from_bytes(b"\xe2\x82", &process),
+            ],
+            &process,
+        );
+        assert_eq!(utf8_to_binary(data, &process), expected);
+    }
+
+    #[test]
+    fn sequences_may_be_split_across_binaries() {
+        let process = process();
+        let data = list(
+            &[
+                binary_from_bytes(b"a\xe2", &process),
+                binary_from_bytes(b"\x82", &process),
+                binary_from_bytes(b"\xacb", &process),
+            ],
+            &process,
+        );
+
+        let expected = binary_from_bytes("a€b".as_bytes(), &process);
+        assert_eq!(utf8_to_binary(data, &process), expected);
+    }
+
+    #[test]
+    fn incomplete_sequences_followed_by_characters_are_an_error() {
+        let process = process();
+        let data = list(
+            &[binary_from_bytes(b"a\xe2", &process), Term::Int('b' as i64)],
+            &process,
+        );
+
+        let expected = tuple(
+            &[
+                atom("error"),
+                binary_from_bytes(b"a", &process),
+                list(
+                    &[
+                        binary_from_bytes(b"\xe2", &process),
+                        list(&[Term::Int('b' as i64)], &process),
+                    ],
+                    &process,
+                ),
+            ],
+            &process,
+        );
+        assert_eq!(utf8_to_binary(data, &process), expected);
+    }
+
+    #[test]
+    fn characters_outside_latin1_are_an_error_when_encoding_latin1() {
+        let process = process();
+        let data = list(&[Term::Int('é' as i64), Term::Int('€' as i64)], &process);
+
+        let expected = tuple(
+            &[
+                atom("error"),
+                binary_from_bytes(&[0xe9], &process),
+                list(&[Term::Int('€' as i64)], &process),
+            ],
+            &process,
+        );
+        let actual = to_binary(data, CharEncoding::Utf8, CharEncoding::Latin1, &process);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn latin1_bytes_are_converted_to_utf8() {
+        let process = process();
+        let data = binary_from_bytes(&[b'a', 0xe9], &process);
+
+        let expected = binary_from_bytes("aé".as_bytes(), &process);
+        let actual = to_binary(data, CharEncoding::Latin1, CharEncoding::Utf8, &process);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn utf16_and_utf32_round_trip_in_either_byte_order() {
+        let process = process();
+        let text = "a€😀";
+        let utf8 = binary_from_bytes(text.as_bytes(), &process);
+
+        let encodings = [
+            CharEncoding::Utf16(Endianness::Big),
+            CharEncoding::Utf16(Endianness::Little),
+            CharEncoding::Utf32(Endianness::Big),
+            CharEncoding::Utf32(Endianness::Little),
+        ];
+        for encoding in encodings {
+            let encoded = to_binary(utf8, CharEncoding::Utf8, encoding, &process);
+            let decoded = to_binary(encoded, encoding, CharEncoding::Utf8, &process);
+            assert_eq!(decoded, utf8, "round trip through {:?}", encoding);
+        }
+
+        let utf16_be = to_binary(
+            utf8,
+            CharEncoding::Utf8,
+            CharEncoding::Utf16(Endianness::Big),
+            &process,
+        );
+        let expected =
+            binary_from_bytes(&[0x00, 0x61, 0x20, 0xac, 0xd8, 0x3d, 0xde, 0x00], &process);
+        assert_eq!(utf16_be, expected);
+        let utf32_le = to_binary(
+            utf8,
+            CharEncoding::Utf8,
+            CharEncoding::Utf32(Endianness::Little),
+            &process,
+        );
+        let expected = binary_from_bytes(
+            &[
+                0x61, 0x00, 0x00, 0x00, 0xac, 0x20, 0x00, 0x00, 0x00, 0xf6, 0x01, 0x00,
+            ],
+            &process,
+        );
+        assert_eq!(utf32_le, expected);
+    }
+
+    #[test]
+    fn unpaired_surrogates_are_invalid_utf16() {
+        let process = process();
+        let data = binary_from_bytes(&[0x00, 0x61, 0xdc, 0x00], &process);
+
+        let expected = tuple(
+            &[
+                atom("error"),
+                charlist(&['a'], &process),
+                binary_from_bytes(&[0xdc, 0x00], &process),
+            ],
+            &process,
+        );
+        let actual = to_list(data, CharEncoding::Utf16(Endianness::Big), &process);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn terms_which_are_not_chardata_are_rejected() {
+        let process = process();
+
+        assert!(convert(atom("a"), CharEncoding::Utf8, CharEncoding::Utf8, &process).is_none());
+        assert!(convert(
+            Term::Int(1),
+            CharEncoding::Utf8,
+            CharEncoding::Utf8,
+            &process
+        )
+        .is_none());
+    }
 }
@@ -0,0 +1,694 @@
+//! A minimal implementation of ETS tables, shared by all processes in the system
+//!
+//! Only the `set` and `ordered_set` table types are supported. Objects are deep-copied into a
+//! heap fragment owned by the table when inserted, and back onto the heap of the calling process
+//! when looked up, so that they survive the process which inserted them, and so that processes
+//! never share terms. Tables are deleted when their owner exits, see `delete_owned_by`.
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::ptr::NonNull;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use lazy_static::lazy_static;
+
+use firefly_alloc::fragment::HeapFragment;
+use firefly_alloc::heap::Heap;
+use firefly_rt::cmp::ExactEq;
+use firefly_rt::memory::Category;
+use firefly_rt::term::{phash2, Atom, ProcessId, ReferenceId, Term, Tuple};
+
+lazy_static! {
+    static ref TABLES: RwLock<Tables> = RwLock::new(Tables::default());
+}
+
+/// Acquires shared access to the tables of the system
+pub fn read() -> RwLockReadGuard<'static, Tables> {
+    TABLES.read().unwrap()
+}
+
+/// Acquires exclusive access to the tables of the system
+pub fn write() -> RwLockWriteGuard<'static, Tables> {
+    TABLES.write().unwrap()
+}
+
+/// Deletes all of the tables owned by `owner`, which must be called when it exits
+pub fn delete_owned_by(owner: ProcessId) -> usize {
+    write().delete_owned_by(owner)
+}
+
+/// The errors which can occur operating on a table, all of which are raised as `badarg`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EtsError {
+    /// A named table was created with a name that is already registered
+    NameAlreadyExists(Atom),
+    /// The table does not exist, or has been deleted
+    NotFound,
+    /// The calling process does not have the access to the table the operation requires
+    AccessDenied,
+    /// An object to insert was not a tuple with an element at the key position
+    BadObject,
+    /// An object could not be copied into, or out of, the table
+    AllocError,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TableType {
+    /// Objects are unique by key, with keys matched exactly, i.e. `1` and `1.0` are different keys
+    Set,
+    /// Objects are unique by key, and kept in term order, with keys matched by comparison, i.e.
+    /// `1` and `1.0` are the same key
+    OrderedSet,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Access {
+    /// Any process may read or write the table
+    Public,
+    /// Any process may read the table, but only the owner may write to it
+    Protected,
+    /// Only the owner may read or write the table
+    Private,
+}
+
+/// The options a table is created with by `ets:new/2`
+#[derive(Debug, Copy, Clone)]
+pub struct TableOptions {
+    pub table_type: TableType,
+    pub access: Access,
+    /// Whether the table can be referred to by its name
+    pub named: bool,
+    /// The zero-based index of the key in each object
+    pub keypos: usize,
+}
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            table_type: TableType::Set,
+            access: Access::Protected,
+            named: false,
+            keypos: 0,
+        }
+    }
+}
+
+/// How a table is referred to by the `ets` BIFs
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TableRef {
+    /// The reference returned by `ets:new/2`
+    Id(ReferenceId),
+    /// The name of a named table
+    Name(Atom),
+}
+
+/// All of the tables in the system
+#[derive(Default)]
+pub struct Tables {
+    tables: HashMap<ReferenceId, Table>,
+    names: HashMap<Atom, ReferenceId>,
+}
+impl Tables {
+    /// Creates a new, empty table identified by `id`
+    pub fn new_table(
+        &mut self,
+        id: ReferenceId,
+        name: Atom,
+        owner: ProcessId,
+        options: TableOptions,
+    ) -> Result<(), EtsError> {
+        if options.named {
+            if self.names.contains_key(&name) {
+                return Err(EtsError::NameAlreadyExists(name));
+            }
+            self.names.insert(name, id);
+        }
+
+        let objects = match options.table_type {
+            TableType::Set => Objects::Set(HashMap::new()),
+            TableType::OrderedSet => Objects::OrderedSet(BTreeMap::new()),
+        };
+        self.tables.insert(
+            id,
+            Table {
+                name,
+                owner,
+                options,
+                objects,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Inserts copies of `objects` into `table`, replacing any objects with the same keys.
+    ///
+    /// Either all of the objects are inserted, or none are.
+    pub fn insert(
+        &mut self,
+        table: TableRef,
+        caller: ProcessId,
+        objects: &[NonNull<Tuple>],
+    ) -> Result<(), EtsError> {
+        let table = self.get_mut(table)?;
+        table.check_write(caller)?;
+
+        let keypos = table.options.keypos;
+        if objects
+            .iter()
+            .any(|tuple| unsafe { tuple.as_ref() }.len() <= keypos)
+        {
+            return Err(EtsError::BadObject);
+        }
+
+        let copies = objects
+            .iter()
+            .map(|tuple| Object::new(*tuple))
+            .collect::<Result<Vec<_>, _>>()?;
+        for object in copies {
+            // Replacing an object must also replace its key, as the key is part of the object, but
+            // `insert` keeps the existing key, so the object must be removed first
+            let key = object.key(keypos);
+            match &mut table.objects {
+                Objects::Set(objects) => {
+                    objects.remove(&SetKey(key));
+                    objects.insert(SetKey(key), object);
+                }
+                Objects::OrderedSet(objects) => {
+                    objects.remove(&OrderedKey(key));
+                    objects.insert(OrderedKey(key), object);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns copies, allocated on `heap`, of the objects in `table` with `key`
+    pub fn lookup<H: Heap>(
+        &self,
+        table: TableRef,
+        caller: ProcessId,
+        key: Term,
+        heap: H,
+    ) -> Result<Vec<Term>, EtsError> {
+        let table = self.get(table)?;
+        table.check_read(caller)?;
+
+        let found = match &table.objects {
+            Objects::Set(objects) => objects.get(&SetKey(key)),
+            Objects::OrderedSet(objects) => objects.get(&OrderedKey(key)),
+        };
+        match found {
+            None => Ok(vec![]),
+            Some(object) => {
+                let copy = Term::Tuple(object.tuple)
+                    .clone_to_heap(heap)
+                    .map_err(|_| EtsError::AllocError)?;
+                Ok(vec![copy])
+            }
+        }
+    }
+
+    /// Deletes `table` and all of its objects
+    pub fn delete(&mut self, table: TableRef, caller: ProcessId) -> Result<(), EtsError> {
+        let id = self.resolve(table)?;
+        self.get(TableRef::Id(id))?.check_write(caller)?;
+        self.remove(id);
+        Ok(())
+    }
+
+    /// Deletes the objects with `key` from `table`
+    pub fn delete_key(
+        &mut self,
+        table: TableRef,
+        caller: ProcessId,
+        key: Term,
+    ) -> Result<(), EtsError> {
+        let table = self.get_mut(table)?;
+        table.check_write(caller)?;
+
+        match &mut table.objects {
+            Objects::Set(objects) => {
+                objects.remove(&SetKey(key));
+            }
+            Objects::OrderedSet(objects) => {
+                objects.remove(&OrderedKey(key));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deletes all of the tables owned by `owner`, returning how many there were
+    pub fn delete_owned_by(&mut self, owner: ProcessId) -> usize {
+        let owned = self
+            .tables
+            .iter()
+            .filter(|(_, table)| table.owner == owner)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in owned.iter() {
+            self.remove(*id);
+        }
+        owned.len()
+    }
+
+    /// Returns the id of the table referred to by `table`
+    pub fn resolve(&self, table: TableRef) -> Result<ReferenceId, EtsError> {
+        match table {
+            TableRef::Id(id) if self.tables.contains_key(&id) => Ok(id),
+            TableRef::Id(_) => Err(EtsError::NotFound),
+            TableRef::Name(name) => self.names.get(&name).copied().ok_or(EtsError::NotFound),
+        }
+    }
+
+    fn get(&self, table: TableRef) -> Result<&Table, EtsError> {
+        let id = self.resolve(table)?;
+        Ok(&self.tables[&id])
+    }
+
+    fn get_mut(&mut self, table: TableRef) -> Result<&mut Table, EtsError> {
+        let id = self.resolve(table)?;
+        Ok(self.tables.get_mut(&id).unwrap())
+    }
+
+    fn remove(&mut self, id: ReferenceId) {
+        if let Some(table) = self.tables.remove(&id) {
+            if table.options.named {
+                self.names.remove(&table.name);
+            }
+        }
+    }
+}
+
+struct Table {
+    name: Atom,
+    owner: ProcessId,
+    options: TableOptions,
+    objects: Objects,
+}
+impl Table {
+    fn check_read(&self, caller: ProcessId) -> Result<(), EtsError> {
+        match self.options.access {
+            Access::Private if caller != self.owner => Err(EtsError::AccessDenied),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_write(&self, caller: ProcessId) -> Result<(), EtsError> {
+        match self.options.access {
+            Access::Public => Ok(()),
+            _ if caller == self.owner => Ok(()),
+            _ => Err(EtsError::AccessDenied),
+        }
+    }
+}
+
+enum Objects {
+    Set(HashMap<SetKey, Object>),
+    OrderedSet(BTreeMap<OrderedKey, Object>),
+}
+
+/// A copy of an object owned by a table, in its own heap fragment
+struct Object {
+    tuple: NonNull<Tuple>,
+    fragment: NonNull<HeapFragment>,
+}
+// The fragment is only ever read once the object is constructed, and is only freed with the
+// object, which requires exclusive access to the table
+unsafe impl Send for Object {}
+unsafe impl Sync for Object {}
+impl Object {
+    fn new(tuple: NonNull<Tuple>) -> Result<Self, EtsError> {
+        let (copy, fragment) = Term::Tuple(tuple)
+            .clone_to_fragment()
+            .map_err(|_| EtsError::AllocError)?;
+        let Term::Tuple(tuple) = copy else {
+            unreachable!()
+        };
+        Category::Ets.allocated(unsafe { fragment.as_ref() }.heap_size());
+        Ok(Self { tuple, fragment })
+    }
+
+    /// Returns the key of this object, which is only valid as long as the object is
+    fn key(&self, keypos: usize) -> Term {
+        unsafe { self.tuple.as_ref().get_unchecked(keypos) }
+    }
+}
+impl Drop for Object {
+    fn drop(&mut self) {
+        Category::Ets.deallocated(unsafe { self.fragment.as_ref() }.heap_size());
+        unsafe {
+            self.fragment.as_ptr().drop_in_place();
+        }
+    }
+}
+
+/// A key in a `set`, matched exactly
+///
+/// This is also used for the keys of persistent terms, which are matched the same way.
+pub(crate) struct SetKey(pub(crate) Term);
+// Keys are only ever read, and are owned by the table that holds them, e.g. as part of an `Object`
+unsafe impl Send for SetKey {}
+unsafe impl Sync for SetKey {}
+impl Hash for SetKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Unlike the derived hash, this hashes boxed terms by their contents
+        state.write_u32(phash2(self.0));
+    }
+}
+impl PartialEq for SetKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.exact_eq(&other.0)
+    }
+}
+impl Eq for SetKey {}
+
+/// A key in an `ordered_set`, in term order, but matched by comparison rather than exactly
+struct OrderedKey(Term);
+// Keys in a table are part of an `Object`, see above
+unsafe impl Send for OrderedKey {}
+unsafe impl Sync for OrderedKey {}
+impl Ord for OrderedKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The term order puts floats before integers they compare equal to, so it is enough to
+        // treat those as equal
+        if self.0 == other.0 {
+            Ordering::Equal
+        } else {
+            self.0.cmp(&other.0)
+        }
+    }
+}
+impl PartialOrd for OrderedKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for OrderedKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for OrderedKey {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use firefly_rt::process::Process;
+    use firefly_rt::term::OpaqueTerm;
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(Atom::try_from(name).unwrap())
+    }
+
+    fn tuple(elements: &[Term], process: &Process) -> NonNull<Tuple> {
+        let elements = elements
+            .iter()
+            .map(|t| (*t).into())
+            .collect::<Vec<OpaqueTerm>>();
+        Tuple::from_slice(&elements, process).unwrap()
+    }
+
+    fn id(number: u64) -> TableRef {
+        TableRef::Id(ReferenceId::new(0, number))
+    }
+
+    fn new_table(
+        tables: &mut Tables,
+        number: u64,
+        name: &str,
+        owner: ProcessId,
+        options: TableOptions,
+    ) -> Result<(), EtsError> {
+        let Term::Atom(name) = atom(name) else {
+            unreachable!()
+        };
+        tables.new_table(ReferenceId::new(0, number), name, owner, options)
+    }
+
+    fn named() -> TableOptions {
+        TableOptions {
+            named: true,
+            ..Default::default()
+        }
+    }
+
+    fn assert_objects(found: Vec<Term>, expected: &[Term]) {
+        assert_eq!(found.len(), expected.len(), "{:?} != {:?}", found, expected);
+        for (f, e) in found.iter().zip(expected) {
+            assert!(f.exact_eq(e), "{:?} != {:?}", found, expected);
+        }
+    }
+
+    #[test]
+    fn objects_are_copied_into_and_out_of_the_table() {
+        let owner = process();
+        let mut tables = Tables::default();
+        new_table(&mut tables, 1, "copies", owner.pid(), Default::default()).unwrap();
+
+        let inner = tuple(&[Term::Int(1), Term::Int(2)], &owner);
+        let mut original = tuple(&[atom("key"), Term::Tuple(inner)], &owner);
+        tables.insert(id(1), owner.pid(), &[original]).unwrap();
+
+        // Mutating the original, inside and out, does not affect the object in the table
+        let expected = Term::Tuple(tuple(
+            &[
+                atom("key"),
+                Term::Tuple(tuple(&[Term::Int(1), Term::Int(2)], &owner)),
+            ],
+            &owner,
+        ));
+        unsafe {
+            original
+                .as_mut()
+                .set_element_mut(1, atom("mutated"))
+                .unwrap();
+        }
+        let mut inner = inner;
+        unsafe {
+            inner.as_mut().set_element_mut(0, atom("mutated")).unwrap();
+        }
+        let found = tables
+            .lookup(id(1), owner.pid(), atom("key"), &owner)
+            .unwrap();
+        assert_objects(found, &[expected]);
+
+        // Neither does mutating a copy looked up from the table
+        let reader = process();
+        let found = tables
+            .lookup(id(1), reader.pid(), atom("key"), &reader)
+            .unwrap();
+        let Term::Tuple(mut copy) = found[0] else {
+            panic!("expected a tuple")
+        };
+        assert!(reader.contains(copy.as_ptr()));
+        unsafe {
+            copy.as_mut().set_element_mut(1, atom("mutated")).unwrap();
+        }
+        let found = tables
+            .lookup(id(1), owner.pid(), atom("key"), &owner)
+            .unwrap();
+        assert_objects(found, &[expected]);
+    }
+
+    #[test]
+    fn objects_survive_the_heap_they_were_inserted_from() {
+        let owner = process();
+        let mut tables = Tables::default();
+        new_table(&mut tables, 1, "survivors", owner.pid(), Default::default()).unwrap();
+
+        {
+            let inserter = process();
+            let object = tuple(&[Term::Int(1), atom("value")], &inserter);
+            tables.insert(id(1), owner.pid(), &[object]).unwrap();
+        }
+
+        let found = tables
+            .lookup(id(1), owner.pid(), Term::Int(1), &owner)
+            .unwrap();
+        assert_objects(
+            found,
+            &[Term::Tuple(tuple(&[Term::Int(1), atom("value")], &owner))],
+        );
+    }
+
+    #[test]
+    fn set_matches_keys_exactly_and_ordered_set_by_comparison() {
+        let owner = process();
+        let mut tables = Tables::default();
+        let ordered = TableOptions {
+            table_type: TableType::OrderedSet,
+            ..Default::default()
+        };
+        new_table(&mut tables, 1, "set", owner.pid(), Default::default()).unwrap();
+        new_table(&mut tables, 2, "ordered_set", owner.pid(), ordered).unwrap();
+
+        for table in [id(1), id(2)] {
+            let int = tuple(&[Term::Int(1), atom("int")], &owner);
+            let float = tuple(&[1.0f64.into(), atom("float")], &owner);
+            tables.insert(table, owner.pid(), &[int, float]).unwrap();
+        }
+
+        let lookup = |table, key| tables.lookup(table, owner.pid(), key, &owner).unwrap();
+        assert_objects(
+            lookup(id(1), Term::Int(1)),
+            &[Term::Tuple(tuple(&[Term::Int(1), atom("int")], &owner))],
+        );
+        assert_objects(
+            lookup(id(1), 1.0f64.into()),
+            &[Term::Tuple(tuple(&[1.0f64.into(), atom("float")], &owner))],
+        );
+        // In an ordered_set, the second insert replaced the first
+        let float = Term::Tuple(tuple(&[1.0f64.into(), atom("float")], &owner));
+        assert_objects(lookup(id(2), Term::Int(1)), &[float]);
+        assert_objects(lookup(id(2), 1.0f64.into()), &[float]);
+    }
+
+    #[test]
+    fn objects_without_a_key_are_not_inserted() {
+        let owner = process();
+        let mut tables = Tables::default();
+        let options = TableOptions {
+            keypos: 1,
+            ..Default::default()
+        };
+        new_table(&mut tables, 1, "keypos", owner.pid(), options).unwrap();
+
+        let valid = tuple(&[atom("value"), Term::Int(1)], &owner);
+        let invalid = tuple(&[atom("value")], &owner);
+        assert_eq!(
+            tables.insert(id(1), owner.pid(), &[valid, invalid]),
+            Err(EtsError::BadObject)
+        );
+        assert_objects(
+            tables
+                .lookup(id(1), owner.pid(), Term::Int(1), &owner)
+                .unwrap(),
+            &[],
+        );
+    }
+
+    #[test]
+    fn named_tables_cannot_share_a_name() {
+        let owner = process();
+        let mut tables = Tables::default();
+        let Term::Atom(name) = atom("registered") else {
+            unreachable!()
+        };
+
+        new_table(&mut tables, 1, "registered", owner.pid(), named()).unwrap();
+        assert_eq!(
+            new_table(&mut tables, 2, "registered", owner.pid(), named()),
+            Err(EtsError::NameAlreadyExists(name))
+        );
+        // Unnamed tables may share a name with any other table
+        new_table(
+            &mut tables,
+            3,
+            "registered",
+            owner.pid(),
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            tables.resolve(TableRef::Name(name)),
+            Ok(ReferenceId::new(0, 1))
+        );
+
+        // Deleting the unnamed table leaves the name registered, deleting the named one frees it
+        tables.delete(id(3), owner.pid()).unwrap();
+        assert_eq!(
+            tables.resolve(TableRef::Name(name)),
+            Ok(ReferenceId::new(0, 1))
+        );
+        tables.delete(TableRef::Name(name), owner.pid()).unwrap();
+        assert_eq!(
+            tables.resolve(TableRef::Name(name)),
+            Err(EtsError::NotFound)
+        );
+        new_table(&mut tables, 4, "registered", owner.pid(), named()).unwrap();
+        assert_eq!(
+            tables.resolve(TableRef::Name(name)),
+            Ok(ReferenceId::new(0, 4))
+        );
+    }
+
+    #[test]
+    fn tables_are_deleted_when_their_owner_dies() {
+        let owner = process();
+        let other = process();
+        let mut tables = Tables::default();
+        let Term::Atom(name) = atom("owned") else {
+            unreachable!()
+        };
+
+        new_table(&mut tables, 1, "owned", owner.pid(), named()).unwrap();
+        new_table(&mut tables, 2, "unnamed", owner.pid(), Default::default()).unwrap();
+        new_table(&mut tables, 3, "other", other.pid(), Default::default()).unwrap();
+        let object = tuple(&[atom("key"), atom("value")], &owner);
+        tables.insert(id(1), owner.pid(), &[object]).unwrap();
+
+        assert_eq!(tables.delete_owned_by(owner.pid()), 2);
+        assert_eq!(tables.resolve(id(1)), Err(EtsError::NotFound));
+        assert_eq!(tables.resolve(id(2)), Err(EtsError::NotFound));
+        assert_eq!(
+            tables.resolve(TableRef::Name(name)),
+            Err(EtsError::NotFound)
+        );
+        assert_eq!(tables.resolve(id(3)), Ok(ReferenceId::new(0, 3)));
+
+        // The name can be registered again by another process
+        new_table(&mut tables, 4, "owned", other.pid(), named()).unwrap();
+        assert_eq!(tables.delete_owned_by(owner.pid()), 0);
+    }
+
+    #[test]
+    fn access_is_checked_against_the_owner() {
+        let owner = process();
+        let other = process();
+        let mut tables = Tables::default();
+        for (number, access) in [
+            (1, Access::Public),
+            (2, Access::Protected),
+            (3, Access::Private),
+        ] {
+            let options = TableOptions {
+                access,
+                ..Default::default()
+            };
+            new_table(&mut tables, number, "access", owner.pid(), options).unwrap();
+        }
+
+        let object = tuple(&[atom("key")], &other);
+        let read = |tables: &Tables, number| {
+            tables
+                .lookup(id(number), other.pid(), atom("key"), &other)
+                .map(|_| ())
+        };
+        assert_eq!(tables.insert(id(1), other.pid(), &[object]), Ok(()));
+        assert_eq!(read(&tables, 1), Ok(()));
+        assert_eq!(
+            tables.insert(id(2), other.pid(), &[object]),
+            Err(EtsError::AccessDenied)
+        );
+        assert_eq!(read(&tables, 2), Ok(()));
+        assert_eq!(
+            tables.insert(id(3), other.pid(), &[object]),
+            Err(EtsError::AccessDenied)
+        );
+        assert_eq!(read(&tables, 3), Err(EtsError::AccessDenied));
+
+        assert_eq!(
+            tables.delete(id(2), other.pid()),
+            Err(EtsError::AccessDenied)
+        );
+        assert_eq!(tables.delete(id(1), other.pid()), Ok(()));
+        assert_eq!(tables.insert(id(3), owner.pid(), &[object]), Ok(()));
+    }
+}
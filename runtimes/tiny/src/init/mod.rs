@@ -1,5 +1,9 @@
+use std::ptr::NonNull;
+
+use firefly_rt::backtrace::Trace;
+use firefly_rt::error::ErlangException;
 use firefly_rt::function::ErlangResult;
-use firefly_rt::term::{ListBuilder, OpaqueTerm};
+use firefly_rt::term::{atoms, ListBuilder, OpaqueTerm, Term};
 
 use crate::env;
 use crate::scheduler;
@@ -35,3 +39,53 @@ pub(crate) extern "C-unwind" fn start() -> ErlangResult {
         unsafe { boot(args) }
     })
 }
+
+/// Stops the system with exit status 0, once every process has been terminated
+#[allow(improper_ctypes_definitions)]
+#[export_name = "init:stop/0"]
+pub extern "C-unwind" fn stop0() -> ErlangResult {
+    scheduler::with_current(|scheduler| scheduler.stop(0));
+
+    ErlangResult::Ok(atoms::Ok.into())
+}
+
+/// Stops the system with the given status, once every process has been terminated
+///
+/// An integer status is the exit status of the executable, truncated to 8 bits as by the OS. A
+/// charlist or atom status is printed to stderr, and the exit status is 1.
+#[allow(improper_ctypes_definitions)]
+#[export_name = "init:stop/1"]
+pub extern "C-unwind" fn stop1(status: OpaqueTerm) -> ErlangResult {
+    let status = match status.into() {
+        Term::Int(code) if code >= 0 => code as u8,
+        Term::Atom(slogan) => {
+            eprintln!("{}", slogan.as_str());
+            1
+        }
+        Term::Nil => {
+            eprintln!();
+            1
+        }
+        Term::Cons(ptr) => match unsafe { ptr.as_ref() }.to_string() {
+            Some(slogan) => {
+                eprintln!("{}", slogan);
+                1
+            }
+            None => return badarg(),
+        },
+        _ => return badarg(),
+    };
+
+    scheduler::with_current(|scheduler| scheduler.stop(status));
+
+    ErlangResult::Ok(atoms::Ok.into())
+}
+
+fn badarg() -> ErlangResult {
+    let exception = Box::into_raw(ErlangException::new(
+        atoms::Error,
+        atoms::Badarg.into(),
+        Trace::capture(),
+    ));
+    ErlangResult::Err(unsafe { NonNull::new_unchecked(exception) })
+}
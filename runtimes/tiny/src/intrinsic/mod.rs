@@ -226,8 +226,8 @@ pub extern "C-unwind" fn map_fetch(
 }
 
 #[export_name = "__firefly_build_stacktrace"]
-pub unsafe extern "C-unwind" fn build_stacktrace(mut trace: NonNull<Trace>) -> OpaqueTerm {
-    let term = trace.as_mut().as_term().unwrap();
+pub unsafe extern "C-unwind" fn build_stacktrace(trace: NonNull<Trace>) -> OpaqueTerm {
+    let term = trace.as_ref().as_term().unwrap();
     term.into()
 }
 
@@ -1,3 +1,4 @@
+#![feature(allocator_api)]
 #![feature(c_unwind)]
 #![feature(once_cell)]
 #![feature(ptr_metadata)]
@@ -10,8 +11,10 @@ extern crate firefly_crt;
 
 mod env;
 mod erlang;
+mod ets;
 mod init;
 mod intrinsic;
+mod persistent_term;
 mod scheduler;
 mod sys;
 
@@ -38,6 +41,7 @@ fn main_internal(_name: &str, _version: &str, _argv: Vec<String>) -> ExitCode {
     let mut rx1 = bus.add_rx();
     // Initialize the break handler with the bus, which will broadcast on it
     break_handler::init(bus);
+    sys::dump::install_panic_hook();
 
     scheduler::init();
     scheduler::with_current(|scheduler| scheduler.spawn_init()).unwrap();
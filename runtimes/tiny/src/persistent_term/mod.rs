@@ -0,0 +1,213 @@
+//! The storage of `persistent_term`, shared by all processes in the system
+//!
+//! Keys and values are deep-copied into heap fragments owned by the storage when put, but unlike
+//! ETS, `get` returns the stored value itself rather than a copy. As any process may refer to a
+//! value it has read, the fragments are never freed, even when the term is erased or replaced, so
+//! like literals, stored terms live until the system exits. Putting terms should therefore be
+//! rare compared to reading them, just like in OTP.
+use std::collections::HashMap;
+use std::ptr::NonNull;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use lazy_static::lazy_static;
+
+use firefly_alloc::fragment::HeapFragment;
+use firefly_rt::cmp::ExactEq;
+use firefly_rt::term::Term;
+
+use crate::ets::SetKey;
+
+lazy_static! {
+    static ref TERMS: RwLock<PersistentTerms> = RwLock::new(PersistentTerms::default());
+}
+
+/// Acquires shared access to the persistent terms of the system
+pub fn read() -> RwLockReadGuard<'static, PersistentTerms> {
+    TERMS.read().unwrap()
+}
+
+/// Acquires exclusive access to the persistent terms of the system
+pub fn write() -> RwLockWriteGuard<'static, PersistentTerms> {
+    TERMS.write().unwrap()
+}
+
+/// All of the persistent terms in the system
+#[derive(Default)]
+pub struct PersistentTerms {
+    terms: HashMap<SetKey, Term>,
+    /// The fragments that the keys and values of `terms`, and of all terms ever erased or
+    /// replaced, are allocated in
+    immortal: Vec<NonNull<HeapFragment>>,
+}
+// Stored terms are never mutated or freed, so they can be read from any thread
+unsafe impl Send for PersistentTerms {}
+unsafe impl Sync for PersistentTerms {}
+impl PersistentTerms {
+    /// Stores a copy of `value` under `key`, replacing any value already stored under it.
+    ///
+    /// If the value stored under `key` is already exactly equal to `value`, nothing is copied.
+    pub fn put(&mut self, key: Term, value: Term) {
+        if let Some(stored) = self.terms.get(&SetKey(key)) {
+            if stored.exact_eq(&value) {
+                return;
+            }
+        }
+
+        let value = self.copy(value);
+        // The key is kept along with the value that replaces it, as `insert` keeps the existing key
+        match self.terms.get_mut(&SetKey(key)) {
+            Some(stored) => *stored = value,
+            None => {
+                let key = self.copy(key);
+                self.terms.insert(SetKey(key), value);
+            }
+        }
+    }
+
+    /// Returns the value stored under `key`, which lives as long as the system does
+    pub fn get(&self, key: Term) -> Option<Term> {
+        self.terms.get(&SetKey(key)).copied()
+    }
+
+    /// Erases the value stored under `key`, returning whether there was one
+    pub fn erase(&mut self, key: Term) -> bool {
+        self.terms.remove(&SetKey(key)).is_some()
+    }
+
+    fn copy(&mut self, term: Term) -> Term {
+        let (copy, fragment) = term
+            .clone_to_fragment()
+            .expect("unable to allocate persistent term");
+        self.immortal.push(fragment);
+        copy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    use firefly_rt::process::Process;
+    use firefly_rt::term::{Atom, OpaqueTerm, ProcessId, Tuple};
+
+    fn process() -> Process {
+        Process::new(None, ProcessId::next(), "root:init/0".parse().unwrap())
+    }
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(Atom::try_from(name).unwrap())
+    }
+
+    fn tuple(elements: &[Term], process: &Process) -> Term {
+        let elements = elements
+            .iter()
+            .map(|t| (*t).into())
+            .collect::<Vec<OpaqueTerm>>();
+        Term::Tuple(Tuple::from_slice(&elements, process).unwrap())
+    }
+
+    fn assert_exact_eq(actual: Option<Term>, expected: Term) {
+        match actual {
+            Some(actual) => assert!(actual.exact_eq(&expected), "{:?} != {:?}", actual, expected),
+            None => panic!("expected {:?}, but nothing was stored", expected),
+        }
+    }
+
+    #[test]
+    fn terms_are_observable_after_the_writer_exits() {
+        let mut terms = PersistentTerms::default();
+
+        {
+            let writer = process();
+            let key = tuple(&[atom("config"), Term::Int(1)], &writer);
+            let value = tuple(&[atom("value"), tuple(&[Term::Int(2)], &writer)], &writer);
+            terms.put(key, value);
+        }
+
+        let reader = process();
+        let key = tuple(&[atom("config"), Term::Int(1)], &reader);
+        let expected = tuple(&[atom("value"), tuple(&[Term::Int(2)], &reader)], &reader);
+        assert_exact_eq(terms.get(key), expected);
+    }
+
+    #[test]
+    fn get_returns_the_stored_term_without_copying() {
+        let mut terms = PersistentTerms::default();
+        let writer = process();
+        let value = tuple(&[atom("value")], &writer);
+        terms.put(atom("key"), value);
+
+        let Some(Term::Tuple(first)) = terms.get(atom("key")) else {
+            panic!("expected a tuple")
+        };
+        let Some(Term::Tuple(second)) = terms.get(atom("key")) else {
+            panic!("expected a tuple")
+        };
+        assert_eq!(first, second);
+        assert!(!writer.contains(first.as_ptr()));
+        assert!(!process().contains(first.as_ptr()));
+    }
+
+    #[test]
+    fn put_replaces_and_erase_removes_without_freeing() {
+        let mut terms = PersistentTerms::default();
+        let writer = process();
+        terms.put(atom("key"), tuple(&[Term::Int(1)], &writer));
+        let replaced = terms.get(atom("key")).unwrap();
+
+        terms.put(atom("key"), tuple(&[Term::Int(2)], &writer));
+        assert_exact_eq(terms.get(atom("key")), tuple(&[Term::Int(2)], &writer));
+
+        // Putting an equal value does not copy it again
+        let fragments = terms.immortal.len();
+        terms.put(atom("key"), tuple(&[Term::Int(2)], &writer));
+        assert_eq!(terms.immortal.len(), fragments);
+
+        let erased = terms.get(atom("key")).unwrap();
+        assert!(terms.erase(atom("key")));
+        assert!(terms.get(atom("key")).is_none());
+        assert!(!terms.erase(atom("key")));
+
+        // Processes may still refer to terms that have been replaced or erased
+        assert!(replaced.exact_eq(&tuple(&[Term::Int(1)], &writer)));
+        assert!(erased.exact_eq(&tuple(&[Term::Int(2)], &writer)));
+    }
+
+    #[test]
+    fn keys_are_matched_exactly() {
+        let mut terms = PersistentTerms::default();
+        terms.put(Term::Int(1), atom("int"));
+
+        assert!(terms.get(Term::from(1.0f64)).is_none());
+        assert_exact_eq(terms.get(Term::Int(1)), atom("int"));
+    }
+
+    #[test]
+    fn put_is_safe_from_multiple_threads() {
+        let terms = RwLock::new(PersistentTerms::default());
+
+        thread::scope(|scope| {
+            for i in 0..4 {
+                let terms = &terms;
+                scope.spawn(move || {
+                    let writer = process();
+                    for j in 0..100 {
+                        let value = tuple(&[Term::Int(i), Term::Int(j)], &writer);
+                        terms.write().unwrap().put(Term::Int(i * 100 + j), value);
+                    }
+                });
+            }
+        });
+
+        let reader = process();
+        let terms = terms.read().unwrap();
+        for i in 0..4 {
+            for j in 0..100 {
+                let expected = tuple(&[Term::Int(i), Term::Int(j)], &reader);
+                assert_exact_eq(terms.get(Term::Int(i * 100 + j)), expected);
+            }
+        }
+    }
+}
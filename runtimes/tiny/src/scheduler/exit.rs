@@ -4,6 +4,8 @@ use firefly_rt::error::{self, ErlangException};
 use firefly_rt::process::Process;
 use firefly_rt::term::{atoms, Term};
 
+use crate::ets;
+
 pub fn log_exit(process: &Process, ptr: NonNull<ErlangException>) -> bool {
     let exception = unsafe { ptr.as_ref() };
     let reason = exception.reason();
@@ -16,6 +18,13 @@ pub fn log_exit(process: &Process, ptr: NonNull<ErlangException>) -> bool {
     }
 }
 
+/// Cleans up after `process` has exited, whatever the reason.
+///
+/// As there are no links or monitors in this runtime, this only deletes the ETS tables it owned.
+pub fn propagate_exit(process: &Process) {
+    ets::delete_owned_by(process.pid());
+}
+
 fn is_expected_exit_reason(reason: Term) -> bool {
     match reason {
         Term::Atom(a) if a == atoms::Normal => true,
@@ -1,25 +1,32 @@
 mod exit;
 mod queue;
+mod registers;
 
 use std::arch::global_asm;
 use std::cell::{OnceCell, UnsafeCell};
 use std::mem;
-use std::ptr;
+use std::ptr::{self, NonNull};
 use std::sync::{
-    atomic::{AtomicI32, AtomicU64, Ordering},
+    atomic::{AtomicBool, AtomicI32, AtomicU16, Ordering},
     Arc,
 };
 use std::thread::{self, ThreadId};
 
+use firefly_rt::backtrace::Trace;
+use firefly_rt::error::ErlangException;
 use firefly_rt::function::{DynamicCallee, ModuleFunctionArity};
 use firefly_rt::process::{Process, ProcessStatus};
-use firefly_rt::term::{OpaqueTerm, Pid, ProcessId};
+use firefly_rt::term::{atoms, OpaqueTerm, Pid, ProcessId, ReferenceId, ReferenceIdGenerator};
 
 use self::queue::RunQueue;
+use self::registers::CalleeSavedRegisters;
 
 #[thread_local]
 pub static CURRENT_PROCESS: UnsafeCell<Option<Arc<Process>>> = UnsafeCell::new(None);
 
+/// The id of the next scheduler to be created, which distinguishes the references it creates
+static NEXT_SCHEDULER_ID: AtomicU16 = AtomicU16::new(0);
+
 #[thread_local]
 pub static CURRENT_SCHEDULER: OnceCell<Scheduler> = OnceCell::new();
 
@@ -34,10 +41,22 @@ where
 /// Initializes the scheduler for the current thread, if not already initialized,
 /// returning a reference to it
 pub fn init<'a>() -> bool {
-    CURRENT_SCHEDULER.get_or_init(|| Scheduler::new().unwrap());
+    CURRENT_SCHEDULER.get_or_init(|| {
+        firefly_rt::time::init();
+        Scheduler::new().unwrap()
+    });
     true
 }
 
+/// Returns the processes of the scheduler for the current thread, if it has been initialized, for
+/// crash dumps
+pub fn processes() -> Vec<Arc<Process>> {
+    CURRENT_SCHEDULER
+        .get()
+        .map(|scheduler| scheduler.processes())
+        .unwrap_or_default()
+}
+
 /// Applies the currently executing process to the given function
 pub fn with_current_process<F, R>(fun: F) -> R
 where
@@ -80,14 +99,17 @@ unsafe impl Sync for SchedulerData {}
 pub struct Scheduler {
     pub id: ThreadId,
     // References are always 64-bits even on 32-bit platforms
-    #[allow(dead_code)]
-    next_reference_id: AtomicU64,
+    reference_ids: ReferenceIdGenerator,
     // In this runtime, we aren't doing work-stealing, so the run queue
     // is never accessed by any other thread
     run_queue: UnsafeCell<RunQueue>,
     prev: UnsafeCell<Option<Arc<SchedulerData>>>,
     current: UnsafeCell<Arc<SchedulerData>>,
     halt_code: AtomicI32,
+    // Set by `init:stop`, after which `halt_code` holds the requested status
+    stopping: AtomicBool,
+    // The process spawned by `spawn_init`, which the system can't run without
+    init: OnceCell<ProcessId>,
 }
 // This guarantee holds as long as `init` and `current` are only
 // ever accessed by the scheduler when scheduling
@@ -122,11 +144,15 @@ impl Scheduler {
         // The scheduler starts with the root process running
         Ok(Self {
             id,
-            next_reference_id: AtomicU64::new(0),
+            reference_ids: ReferenceIdGenerator::new(
+                NEXT_SCHEDULER_ID.fetch_add(1, Ordering::Relaxed),
+            ),
             run_queue: UnsafeCell::new(RunQueue::default()),
             prev: UnsafeCell::new(None),
             current: UnsafeCell::new(root),
             halt_code: AtomicI32::new(0),
+            stopping: AtomicBool::new(false),
+            init: OnceCell::new(),
         })
     }
 
@@ -163,6 +189,22 @@ impl Scheduler {
         self.current().process.clone()
     }
 
+    /// Adds `reductions` to those done by the current process, yielding to the scheduler if it
+    /// has used up its budget
+    ///
+    /// This is how natives which do work proportional to the size of their arguments make sure
+    /// they don't starve other processes.
+    pub fn reduce(&self, reductions: usize) {
+        if unsafe { self.current().process.reduce(reductions) } {
+            self.process_yield();
+        }
+    }
+
+    /// Returns a reference id that is unique across all schedulers
+    pub fn next_reference_id(&self) -> ReferenceId {
+        self.reference_ids.next()
+    }
+
     /// Swaps the prev and current scheduler data in-place and updates CURRENT_PROCESS
     ///
     /// This is intended for use when yielding to the scheduler
@@ -193,11 +235,24 @@ impl Scheduler {
     }
 
     /// Returns true if the root process (scheduler) is running
-    #[allow(dead_code)]
     fn is_root(&self) -> bool {
         unsafe { (&*self.prev.get()).is_none() }
     }
 
+    /// Returns the process currently executing, if any, followed by those in the run queue
+    ///
+    /// This is only for crash dumps, which may be written by a panic hook while the scheduler is
+    /// part way through scheduling, in which case a process being moved may be missing.
+    fn processes(&self) -> Vec<Arc<Process>> {
+        let mut processes = Vec::new();
+        if !self.is_root() {
+            processes.push(self.current_process());
+        }
+        let rq = unsafe { &*self.run_queue.get() };
+        processes.extend(rq.iter().map(|data| data.process.clone()));
+        processes
+    }
+
     pub(super) fn spawn_init(&self) -> anyhow::Result<Arc<Process>> {
         // The init process is the actual "root" Erlang process, it acts
         // as the entry point for the program from Erlang's perspective,
@@ -208,6 +263,9 @@ impl Scheduler {
         //let init_fn = function::find_symbol(&mfa).expect("unable to locate init:start/0 function!");
         let init_fn = crate::init::start as DynamicCallee;
         let process = Arc::new(Process::new(Some(self.parent()), ProcessId::next(), mfa));
+        self.init
+            .set(process.pid())
+            .expect("init has already been spawned");
 
         let data = Arc::new(SchedulerData::new(process));
 
@@ -293,10 +351,35 @@ impl Scheduler {
     pub(super) fn shutdown(&self) -> std::process::ExitCode {
         use std::process::ExitCode;
 
-        if self.halt_code.load(Ordering::Relaxed) == 0 {
-            ExitCode::SUCCESS
-        } else {
-            ExitCode::FAILURE
+        ExitCode::from(self.halt_code.load(Ordering::Relaxed) as u8)
+    }
+
+    /// Requests that the system stops with the given exit status, as done by `init:stop/1`
+    ///
+    /// The caller keeps running until it next yields, at which point every process is terminated
+    /// with reason `shutdown` and the scheduler loop ends.
+    pub fn stop(&self, status: u8) {
+        self.halt_code.store(status as i32, Ordering::Relaxed);
+        self.stopping.store(true, Ordering::Relaxed);
+    }
+
+    /// Terminates every remaining process with reason `shutdown`, leaving init until last
+    fn terminate_all(&self) {
+        let rq = unsafe { &mut *self.run_queue.get() };
+        let (init, mut processes): (Vec<_>, Vec<_>) = rq
+            .drain()
+            .into_iter()
+            .partition(|data| self.init.get() == Some(&data.process.pid()));
+        processes.extend(init);
+
+        for data in processes {
+            let exception = Box::into_raw(ErlangException::new(
+                atoms::Exit,
+                atoms::Shutdown.into(),
+                Trace::capture(),
+            ));
+            data.process.exit_error(unsafe { NonNull::new_unchecked(exception) });
+            exit::propagate_exit(&data.process);
         }
     }
 
@@ -353,22 +436,41 @@ impl Scheduler {
                     self.swap_current();
                     // At this point, `prev` is the process which just yielded
                     let prev = self.take_prev();
+                    let stopping = self.stopping.load(Ordering::Relaxed);
                     match prev.process.status() {
-                        ProcessStatus::Running => {
+                        // `swap_current` marks a process that yielded while running as runnable
+                        ProcessStatus::Running | ProcessStatus::Runnable => {
                             let rq = unsafe { &mut *self.run_queue.get() };
                             rq.reschedule(prev);
                         }
                         ProcessStatus::Exiting => {
-                            self.halt_code.store(0, Ordering::Relaxed);
+                            if !stopping {
+                                self.halt_code.store(0, Ordering::Relaxed);
+                            }
                             // Process has exited normally, we're done with it
+                            exit::propagate_exit(&prev.process);
                         }
                         ProcessStatus::Errored(exception) => {
                             exit::log_exit(&prev.process, exception);
-                            self.halt_code.store(1, Ordering::Relaxed);
+                            if !stopping && self.init.get() == Some(&prev.process.pid()) {
+                                let reason = unsafe { exception.as_ref() }.reason();
+                                crate::sys::dump::init_exited(&prev.process, reason);
+                            }
+                            exit::propagate_exit(&prev.process);
+                            if !stopping {
+                                self.halt_code.store(1, Ordering::Relaxed);
+                            }
                         }
                         other => assert_eq!(other, ProcessStatus::Running),
                     }
 
+                    // `init:stop` was called, so rather than scheduling anything else, bring the
+                    // system down with the requested status
+                    if stopping {
+                        self.terminate_all();
+                        break false;
+                    }
+
                     // When reached, either the process scheduled is the root process,
                     // or the process is exiting and we called .reduce(); either way we're
                     // returning to the main scheduler loop to check for signals, etc.
@@ -394,8 +496,9 @@ impl Scheduler {
     /// at which point execution resumes where the newly scheduled process left
     /// off previously, or in its init function.
     unsafe fn swap_process(&self, new: Arc<SchedulerData>) {
-        // Mark the new process as Running
+        // Mark the new process as Running, with a fresh budget of reductions
         new.process.set_status(ProcessStatus::Running);
+        new.process.reset_reductions();
 
         self.swap_with(new);
         let prev = self.prev();
@@ -421,75 +524,6 @@ impl Scheduler {
     }
 }
 
-#[derive(Default, Debug)]
-#[repr(C)]
-#[cfg(all(unix, target_arch = "x86_64"))]
-struct CalleeSavedRegisters {
-    pub rsp: u64,
-    pub r15: u64,
-    pub r14: u64,
-    pub r13: u64,
-    pub r12: u64,
-    pub rbx: u64,
-    pub rbp: u64,
-}
-#[cfg(target_arch = "x86_64")]
-impl CalleeSavedRegisters {
-    #[inline(always)]
-    unsafe fn set<T: Copy>(&mut self, index: isize, value: T) {
-        let base = std::ptr::addr_of!(self.rbp);
-        let base = base.offset((-index) - 2) as *mut T;
-        base.write(value);
-    }
-
-    #[inline(always)]
-    unsafe fn set_stack_pointer(&mut self, value: u64) {
-        self.rsp = value;
-    }
-
-    #[inline(always)]
-    unsafe fn set_frame_pointer(&mut self, value: u64) {
-        self.rbp = value;
-    }
-}
-
-#[derive(Debug, Default)]
-#[repr(C)]
-#[cfg(all(unix, target_arch = "aarch64"))]
-struct CalleeSavedRegisters {
-    pub sp: u64,
-    pub x29: u64,
-    pub x28: u64,
-    pub x27: u64,
-    pub x26: u64,
-    pub x25: u64,
-    pub x24: u64,
-    pub x23: u64,
-    pub x22: u64,
-    pub x21: u64,
-    pub x20: u64,
-    pub x19: u64,
-}
-#[cfg(target_arch = "aarch64")]
-impl CalleeSavedRegisters {
-    #[inline(always)]
-    unsafe fn set<T: Copy>(&mut self, index: isize, value: T) {
-        let base = std::ptr::addr_of!(self.x19);
-        let base = base.offset(-index) as *mut T;
-        base.write(value);
-    }
-
-    #[inline(always)]
-    unsafe fn set_stack_pointer(&mut self, value: u64) {
-        self.sp = value;
-    }
-
-    #[inline(always)]
-    unsafe fn set_frame_pointer(&mut self, value: u64) {
-        self.x29 = value;
-    }
-}
-
 const FIRST_SWAP: u64 = 0xdeadbeef;
 
 extern "C-unwind" {
@@ -507,3 +541,22 @@ global_asm!(include_str!("swap_stack/swap_stack_linux_x86_64.s"));
 global_asm!(include_str!("swap_stack/swap_stack_macos_x86_64.s"));
 #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
 global_asm!(include_str!("swap_stack/swap_stack_macos_aarch64.s"));
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+global_asm!(include_str!("swap_stack/swap_stack_linux_aarch64.s"));
+#[cfg(all(target_os = "linux", target_arch = "riscv64"))]
+global_asm!(include_str!("swap_stack/swap_stack_linux_riscv64.s"));
+#[cfg(not(any(
+    all(
+        target_os = "linux",
+        any(
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            target_arch = "riscv64"
+        )
+    ),
+    all(
+        target_os = "macos",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )
+)))]
+compile_error!("swap_stack has not been implemented for this target!");
@@ -53,4 +53,17 @@ impl RunQueue {
     pub fn reschedule(&mut self, process: Arc<SchedulerData>) {
         self.visited.push_back(process);
     }
+
+    /// Returns every process in the queue, whether or not it has executed this cycle
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<SchedulerData>> {
+        self.scheduled.iter().chain(self.visited.iter())
+    }
+
+    /// Removes every process from the queue, in the order they would have been scheduled
+    pub fn drain(&mut self) -> Vec<Arc<SchedulerData>> {
+        self.scheduled
+            .drain(..)
+            .chain(self.visited.drain(..))
+            .collect()
+    }
 }
@@ -0,0 +1,36 @@
+/// The registers saved by `swap_stack` on aarch64, in the order they are stored
+#[derive(Debug, Default)]
+#[repr(C)]
+pub struct CalleeSavedRegisters {
+    pub sp: u64,
+    pub x29: u64,
+    pub x28: u64,
+    pub x27: u64,
+    pub x26: u64,
+    pub x25: u64,
+    pub x24: u64,
+    pub x23: u64,
+    pub x22: u64,
+    pub x21: u64,
+    pub x20: u64,
+    pub x19: u64,
+}
+impl CalleeSavedRegisters {
+    /// Sets the `index`th register used by the first swap to a process, i.e. x19, x20 and x21
+    #[inline(always)]
+    pub unsafe fn set<T: Copy>(&mut self, index: isize, value: T) {
+        let base = std::ptr::addr_of!(self.x19);
+        let base = base.offset(-index) as *mut T;
+        base.write(value);
+    }
+
+    #[inline(always)]
+    pub unsafe fn set_stack_pointer(&mut self, value: u64) {
+        self.sp = value;
+    }
+
+    #[inline(always)]
+    pub unsafe fn set_frame_pointer(&mut self, value: u64) {
+        self.x29 = value;
+    }
+}
@@ -0,0 +1,281 @@
+//! The registers saved and restored by `swap_stack` when switching between process stacks
+//!
+//! Each layout must match the offsets used by the `swap_stack` implementations for its
+//! architecture, and `set` must write the registers which `swap_stack` reads on the first swap to
+//! a process: the closure environment (index 0), `FIRST_SWAP` (index 1) and the entry point
+//! (index 2). All of the layouts are compiled for tests, so that the assembly for every target is
+//! checked against them whatever the host.
+#[cfg(any(test, target_arch = "aarch64"))]
+mod aarch64;
+#[cfg(any(test, target_arch = "riscv64"))]
+mod riscv64;
+#[cfg(any(test, target_arch = "x86_64"))]
+mod x86_64;
+
+#[cfg(all(unix, target_arch = "aarch64"))]
+pub use self::aarch64::CalleeSavedRegisters;
+#[cfg(all(unix, target_arch = "riscv64"))]
+pub use self::riscv64::CalleeSavedRegisters;
+#[cfg(all(unix, target_arch = "x86_64"))]
+pub use self::x86_64::CalleeSavedRegisters;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINUX_X86_64: &str = include_str!("../swap_stack/swap_stack_linux_x86_64.s");
+    const MACOS_X86_64: &str = include_str!("../swap_stack/swap_stack_macos_x86_64.s");
+    const LINUX_AARCH64: &str = include_str!("../swap_stack/swap_stack_linux_aarch64.s");
+    const MACOS_AARCH64: &str = include_str!("../swap_stack/swap_stack_macos_aarch64.s");
+    const LINUX_RISCV64: &str = include_str!("../swap_stack/swap_stack_linux_riscv64.s");
+
+    /// Returns the name and offset of each of the given fields of `$registers`, sorted by offset
+    macro_rules! layout {
+        ($registers:ident, [$($field:ident),+]) => {{
+            let base = std::ptr::addr_of!($registers) as usize;
+            let mut layout = vec![
+                $((stringify!($field).to_string(), std::ptr::addr_of!($registers.$field) as usize - base)),+
+            ];
+            layout.sort_by_key(|(_, offset)| *offset);
+            layout
+        }};
+    }
+
+    /// The offset of each register in `CalleeSavedRegisters`, sorted by offset
+    type Layout = Vec<(String, usize)>;
+
+    /// A register stored to, or loaded from, memory at an offset from `base`
+    #[derive(Debug, PartialEq, Eq)]
+    struct Access {
+        store: bool,
+        register: String,
+        base: String,
+        offset: usize,
+    }
+
+    /// Returns the instructions in `asm`, without comments, directives or labels, and with their
+    /// operands separated by a single space
+    fn instructions<'a>(asm: &'a str, comment: &'a str) -> impl Iterator<Item = String> + 'a {
+        asm.lines()
+            .map(move |line| line.split(comment).next().unwrap().trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('.') && !line.ends_with(':'))
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+
+    /// Returns every memory access by a `mov` (x86_64), `stp`/`ldp` (aarch64) or `sd`/`ld`
+    /// (riscv64) in `asm`, in order
+    fn accesses(asm: &str, comment: &str) -> Vec<Access> {
+        let mut accesses = vec![];
+
+        for instruction in instructions(asm, comment) {
+            let (mnemonic, operands) = instruction.split_once(' ').unwrap_or((&instruction, ""));
+            let operands = operands.replace(' ', "");
+
+            match mnemonic {
+                "mov" => {
+                    let (dest, src) = operands.split_once(',').unwrap();
+                    let (store, register, memory) = if dest.starts_with('[') {
+                        (true, src, dest)
+                    } else if src.starts_with('[') {
+                        (false, dest, src)
+                    } else {
+                        continue;
+                    };
+                    let memory = memory.trim_start_matches('[').trim_end_matches(']');
+                    let (base, offset) = memory.split_once('+').unwrap_or((memory, "0"));
+                    accesses.push(Access {
+                        store,
+                        register: register.to_string(),
+                        base: base.to_string(),
+                        offset: offset.parse().unwrap(),
+                    });
+                }
+                "stp" | "ldp" => {
+                    let (registers, memory) = operands.split_once(",[").unwrap();
+                    // Pre- and post-indexed accesses are pushes and pops of the stack
+                    if memory.ends_with('!') || !memory.ends_with(']') {
+                        continue;
+                    }
+                    let memory = memory.trim_end_matches(']');
+                    let (base, offset) = memory.split_once(",#").unwrap_or((memory, "0"));
+                    let offset: usize = offset.parse().unwrap();
+                    for (i, register) in registers.split(',').enumerate() {
+                        accesses.push(Access {
+                            store: mnemonic == "stp",
+                            register: register.to_string(),
+                            base: base.to_string(),
+                            offset: offset + 8 * i,
+                        });
+                    }
+                }
+                "sd" | "ld" => {
+                    let (register, memory) = operands.split_once(',').unwrap();
+                    let (offset, base) = memory.trim_end_matches(')').split_once('(').unwrap();
+                    accesses.push(Access {
+                        store: mnemonic == "sd",
+                        register: register.to_string(),
+                        base: base.to_string(),
+                        offset: offset.parse().unwrap(),
+                    });
+                }
+                _ => (),
+            }
+        }
+
+        accesses
+    }
+
+    /// Returns the register stored at each offset of `prev`, and the register loaded from each
+    /// offset of `new`, sorted by offset, renaming registers by `aliases`
+    fn saved_and_restored(
+        asm: &str,
+        comment: &str,
+        prev: &str,
+        new: &str,
+        aliases: &[(&str, &str)],
+    ) -> (Layout, Layout) {
+        let alias = |register: String| {
+            aliases
+                .iter()
+                .find(|(from, _)| *from == register)
+                .map(|(_, to)| to.to_string())
+                .unwrap_or(register)
+        };
+        let mut saved = vec![];
+        let mut restored = vec![];
+        for access in accesses(asm, comment) {
+            if access.store && access.base == prev {
+                saved.push((alias(access.register), access.offset));
+            } else if !access.store && access.base == new {
+                restored.push((alias(access.register), access.offset));
+            }
+        }
+        saved.sort_by_key(|(_, offset)| *offset);
+        restored.sort_by_key(|(_, offset)| *offset);
+
+        (saved, restored)
+    }
+
+    fn assert_instruction(asm: &str, comment: &str, expected: &str) {
+        assert!(
+            instructions(asm, comment).any(|instruction| instruction == expected),
+            "swap_stack does not contain `{}`",
+            expected
+        );
+    }
+
+    #[test]
+    fn x86_64_layout_matches_swap_stack() {
+        let registers = x86_64::CalleeSavedRegisters::default();
+        let layout = layout!(registers, [rsp, r15, r14, r13, r12, rbx, rbp]);
+        assert_eq!(std::mem::size_of_val(&registers), 7 * 8);
+
+        for asm in [LINUX_X86_64, MACOS_X86_64] {
+            let (saved, restored) = saved_and_restored(asm, "#", "rdi", "rsi", &[]);
+            assert_eq!(saved, layout);
+            assert_eq!(restored, layout);
+        }
+    }
+
+    #[test]
+    fn x86_64_first_swap_registers_match_swap_stack() {
+        let mut registers = x86_64::CalleeSavedRegisters::default();
+        unsafe {
+            registers.set(0, 1u64);
+            registers.set(1, 2u64);
+            registers.set(2, 3u64);
+            registers.set_stack_pointer(4);
+            registers.set_frame_pointer(5);
+        }
+        assert_eq!((registers.r12, registers.r13, registers.r14), (1, 2, 3));
+        assert_eq!((registers.rsp, registers.rbp), (4, 5));
+        assert_eq!((registers.r15, registers.rbx), (0, 0));
+
+        for asm in [LINUX_X86_64, MACOS_X86_64] {
+            assert_instruction(asm, "#", "mov rdi, r12");
+            assert_instruction(asm, "#", "cmp rdx, r13");
+            assert_instruction(asm, "#", "call r14");
+        }
+    }
+
+    #[test]
+    fn aarch64_layout_matches_swap_stack() {
+        let registers = aarch64::CalleeSavedRegisters::default();
+        let layout = layout!(
+            registers,
+            [sp, x29, x28, x27, x26, x25, x24, x23, x22, x21, x20, x19]
+        );
+        assert_eq!(std::mem::size_of_val(&registers), 12 * 8);
+
+        // The stack pointer can't be stored directly, so it is moved through x11
+        for (asm, comment) in [(LINUX_AARCH64, "//"), (MACOS_AARCH64, ";")] {
+            let (saved, restored) = saved_and_restored(asm, comment, "x0", "x1", &[("x11", "sp")]);
+            // The first swap to a process clears `FIRST_SWAP` in its registers
+            let saved = saved
+                .into_iter()
+                .filter(|(register, _)| register != "xzr")
+                .collect::<Vec<_>>();
+            assert_eq!(saved, layout);
+            assert_eq!(restored, layout);
+        }
+    }
+
+    #[test]
+    fn aarch64_first_swap_registers_match_swap_stack() {
+        let mut registers = aarch64::CalleeSavedRegisters::default();
+        unsafe {
+            registers.set(0, 1u64);
+            registers.set(1, 2u64);
+            registers.set(2, 3u64);
+            registers.set_stack_pointer(4);
+            registers.set_frame_pointer(5);
+        }
+        assert_eq!((registers.x19, registers.x20, registers.x21), (1, 2, 3));
+        assert_eq!((registers.sp, registers.x29), (4, 5));
+        assert_eq!((registers.x22, registers.x28), (0, 0));
+
+        for (asm, comment) in [(LINUX_AARCH64, "//"), (MACOS_AARCH64, ";")] {
+            assert_instruction(asm, comment, "mov x0, x19");
+            assert_instruction(asm, comment, "cmp x20, x2");
+            assert_instruction(asm, comment, "blr x21");
+        }
+    }
+
+    #[test]
+    fn riscv64_layout_matches_swap_stack() {
+        let registers = riscv64::CalleeSavedRegisters::default();
+        let layout = layout!(
+            registers,
+            [sp, s0, s11, s10, s9, s8, s7, s6, s5, s4, s3, s2, s1]
+        );
+        assert_eq!(std::mem::size_of_val(&registers), 13 * 8);
+
+        let (saved, restored) = saved_and_restored(LINUX_RISCV64, "#", "a0", "a1", &[]);
+        assert_eq!(saved, layout);
+        // The first swap to a process clears `FIRST_SWAP` in its registers
+        let restored = restored
+            .into_iter()
+            .filter(|(register, _)| register != "zero")
+            .collect::<Vec<_>>();
+        assert_eq!(restored, layout);
+    }
+
+    #[test]
+    fn riscv64_first_swap_registers_match_swap_stack() {
+        let mut registers = riscv64::CalleeSavedRegisters::default();
+        unsafe {
+            registers.set(0, 1u64);
+            registers.set(1, 2u64);
+            registers.set(2, 3u64);
+            registers.set_stack_pointer(4);
+            registers.set_frame_pointer(5);
+        }
+        assert_eq!((registers.s1, registers.s2, registers.s3), (1, 2, 3));
+        assert_eq!((registers.sp, registers.s0), (4, 5));
+        assert_eq!((registers.s4, registers.s11), (0, 0));
+
+        assert_instruction(LINUX_RISCV64, "#", "mv a0, s1");
+        assert_instruction(LINUX_RISCV64, "#", "bne s2, a2, .L_resume");
+        assert_instruction(LINUX_RISCV64, "#", "jalr s3");
+    }
+}
@@ -0,0 +1,41 @@
+/// The registers saved by `swap_stack` on riscv64, in the order they are stored
+///
+/// The return address is not callee-saved, so like x30 on aarch64, `swap_stack` saves `ra` on the
+/// stack of the process being swapped out, along with the frame pointer, rather than here.
+#[derive(Debug, Default)]
+#[repr(C)]
+pub struct CalleeSavedRegisters {
+    pub sp: u64,
+    /// The frame pointer
+    pub s0: u64,
+    pub s11: u64,
+    pub s10: u64,
+    pub s9: u64,
+    pub s8: u64,
+    pub s7: u64,
+    pub s6: u64,
+    pub s5: u64,
+    pub s4: u64,
+    pub s3: u64,
+    pub s2: u64,
+    pub s1: u64,
+}
+impl CalleeSavedRegisters {
+    /// Sets the `index`th register used by the first swap to a process, i.e. s1, s2 and s3
+    #[inline(always)]
+    pub unsafe fn set<T: Copy>(&mut self, index: isize, value: T) {
+        let base = std::ptr::addr_of!(self.s1);
+        let base = base.offset(-index) as *mut T;
+        base.write(value);
+    }
+
+    #[inline(always)]
+    pub unsafe fn set_stack_pointer(&mut self, value: u64) {
+        self.sp = value;
+    }
+
+    #[inline(always)]
+    pub unsafe fn set_frame_pointer(&mut self, value: u64) {
+        self.s0 = value;
+    }
+}
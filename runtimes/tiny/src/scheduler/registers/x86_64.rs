@@ -0,0 +1,31 @@
+/// The registers saved by `swap_stack` on x86_64, in the order they are stored
+#[derive(Default, Debug)]
+#[repr(C)]
+pub struct CalleeSavedRegisters {
+    pub rsp: u64,
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub rbx: u64,
+    pub rbp: u64,
+}
+impl CalleeSavedRegisters {
+    /// Sets the `index`th register used by the first swap to a process, i.e. r12, r13 and r14
+    #[inline(always)]
+    pub unsafe fn set<T: Copy>(&mut self, index: isize, value: T) {
+        let base = std::ptr::addr_of!(self.rbp);
+        let base = base.offset((-index) - 2) as *mut T;
+        base.write(value);
+    }
+
+    #[inline(always)]
+    pub unsafe fn set_stack_pointer(&mut self, value: u64) {
+        self.rsp = value;
+    }
+
+    #[inline(always)]
+    pub unsafe fn set_frame_pointer(&mut self, value: u64) {
+        self.rbp = value;
+    }
+}
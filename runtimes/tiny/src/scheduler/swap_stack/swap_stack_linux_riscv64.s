@@ -0,0 +1,141 @@
+    .section .text.__firefly_swap_stack,"ax",@progbits
+    .globl __firefly_swap_stack
+    .p2align 2
+    .type __firefly_swap_stack,@function
+__firefly_swap_stack:
+    .cfi_startproc
+    .cfi_personality 155, DW.ref.rust_eh_personality
+    .cfi_lsda 255
+    # At this point the following registers are bound:
+    #
+    #   a0 <- prev: *mut CalleeSavedRegisters
+    #   a1 <- new: *const CalleeSavedRegisters
+    #   a2 <- FIRST_SWAP (in a register for symmetry with the other targets, comparisons on
+    #         riscv64 are always between two registers)
+    #
+
+    # Save the frame pointer and return address for when control returns to this call frame.
+    # `ra` is not callee-saved, so like x30 on aarch64 it is kept on the stack of `prev`,
+    # alongside the frame pointer, rather than in `CalleeSavedRegisters`
+    addi sp, sp, -16
+    sd   s0, 0(sp)
+    sd   ra, 8(sp)
+
+    # We save the frame pointer and return address registers to scratch
+    # so that we can setup CFA directives if this is the first swap of the
+    # target process
+    mv   t0, s0
+    mv   t1, ra
+
+    # Save the stack pointer, and callee-saved registers of `prev`
+    sd   sp, 0(a0)
+    sd   s0, 8(a0)
+    sd   s11, 16(a0)
+    sd   s10, 24(a0)
+    sd   s9, 32(a0)
+    sd   s8, 40(a0)
+    sd   s7, 48(a0)
+    sd   s6, 56(a0)
+    sd   s5, 64(a0)
+    sd   s4, 72(a0)
+    sd   s3, 80(a0)
+    sd   s2, 88(a0)
+    sd   s1, 96(a0)
+
+    # Restore the stack pointer, and callee-saved registers of `new`
+    ld   sp, 0(a1)
+    ld   s0, 8(a1)
+    ld   s11, 16(a1)
+    ld   s10, 24(a1)
+    ld   s9, 32(a1)
+    ld   s8, 40(a1)
+    ld   s7, 48(a1)
+    ld   s6, 56(a1)
+    ld   s5, 64(a1)
+    ld   s4, 72(a1)
+    ld   s3, 80(a1)
+    ld   s2, 88(a1)
+    ld   s1, 96(a1)
+
+    # The value of all the callee-saved registers has changed, so we
+    # need to inform the unwinder of that fact before proceeding
+    .cfi_restore sp
+    .cfi_restore s0
+    .cfi_restore s11
+    .cfi_restore s10
+    .cfi_restore s9
+    .cfi_restore s8
+    .cfi_restore s7
+    .cfi_restore s6
+    .cfi_restore s5
+    .cfi_restore s4
+    .cfi_restore s3
+    .cfi_restore s2
+    .cfi_restore s1
+
+    # If this is the first time swapping to this process,
+    # we need to to perform some one-time initialization to
+    # link the stack to the original parent stack (i.e. the scheduler),
+    # which is important for the unwinder
+    bne  s2, a2, .L_resume
+
+    # Ensure we never perform initialization twice
+    mv   s2, zero
+    sd   zero, 88(a1)
+    # Make sure we initialize the return address register from t1
+    # This address returns to the point in the scheduler where it invoked swap_stack
+    mv   ra, t1
+    # Store the following from the top of the stack down:
+    # * pointer to parent's frame pointer for the unwinder to restore (8 bytes above sp)
+    # * return address
+    # * original frame pointer
+    sd   t0, 0(sp)
+    addi sp, sp, -16
+    sd   s0, 0(sp)
+    sd   ra, 8(sp)
+
+    # These CFI directives inform the unwinder of where it can expect
+    # to find the CFA relative to the frame pointer. This matches how we've laid out the stack.
+    #
+    # The first directive tells the unwinder that it can expect to find the
+    # CFA (call frame address) 16 bytes above s0. The second and third directives
+    # tell the unwinder that the return address and frame pointer can be found 8 and 16
+    # bytes (respectively) below the CFA. The unwinder will restore these registers from
+    # those slots and will expect to find the previous CFA 16 bytes above the restored
+    # frame pointer, allowing it to walk back to the parent frame.
+    .cfi_def_cfa s0, 16
+    .cfi_offset ra, -8
+    .cfi_offset s0, -16
+
+    # Now that the frames are linked, we can call the entry point.
+    # The only argument is the value of the closure environment (or Term::NONE if not a closure)
+    mv   a0, s1
+    jalr s3
+
+    # When we return to this point, the process has fully unwound and should exit, returning
+    # back to the scheduler. We handle this by calling __firefly_builtin_exit, which sets up the
+    # process status, and then yields to the scheduler. Control never returns here, so we hint
+    # as such by which branch instruction we use
+    #
+    # NOTE: The ErlangResult struct is returned in a0/a1, which are also the registers used to
+    # pass it by value as the sole argument to the __firefly_builtin_exit intrinsic
+    tail __firefly_builtin_exit
+
+.L_resume:
+    # We land here only on a context switch, and since the last switch _away_ from
+    # this process pushed the frame pointer/return address on to the stack, we restore
+    # those values and proceed
+    ld   s0, 0(sp)
+    ld   ra, 8(sp)
+    addi sp, sp, 16
+
+.L_ret:
+    # At this point we will return back to where execution left off:
+    # For the 'root' (scheduler) process, this returns back into `swap_process`;
+    # for all other processes, this returns to the code which was executing when
+    # it yielded, the address of which we've just restored into the return address
+    # register so we can simply jump to that address
+    jr   ra
+
+    .size __firefly_swap_stack, .-__firefly_swap_stack
+    .cfi_endproc
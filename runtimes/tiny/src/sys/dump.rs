@@ -0,0 +1,204 @@
+//! Crash dumps of the system state, in a subset of the BEAM `erl_crash.dump` format.
+//!
+//! Processes in this runtime have neither links, monitors nor mailboxes, so each process is
+//! dumped with only its state, the function it was spawned as, and its stack.
+use std::env;
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::panic;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use firefly_rt::memory::{self, Category};
+use firefly_rt::process::{Process, ProcessStatus};
+use firefly_rt::term::{try_atom_count, Term};
+
+use crate::scheduler;
+
+/// The environment variable naming the file crash dumps are written to, like BEAM's
+/// `ERL_CRASH_DUMP`
+pub const CRASH_DUMP_ENV: &str = "FIREFLY_CRASH_DUMP";
+/// The file crash dumps are written to when `FIREFLY_CRASH_DUMP` isn't set, relative to the
+/// working directory
+pub const DEFAULT_CRASH_DUMP: &str = "erl_crash.dump";
+/// The most bytes of the exit reason rendered in the slogan when init exits
+const SLOGAN_REASON_BYTES: usize = 256;
+
+/// Set once a crash dump file has been written, so that only the first cause of the system going
+/// down is dumped
+static CRASH_DUMPED: AtomicBool = AtomicBool::new(false);
+
+/// Writes a crash dump with the given slogan, i.e. the reason the system is going down, and the
+/// given processes
+pub fn write_crash_dump(
+    writer: &mut dyn Write,
+    slogan: &str,
+    processes: &[Arc<Process>],
+) -> io::Result<()> {
+    writeln!(writer, "=erl_crash_dump:0.5")?;
+    writeln!(writer, "Slogan: {}", slogan)?;
+    writeln!(
+        writer,
+        "System version: {} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+
+    writeln!(writer, "=memory")?;
+    writeln!(writer, "total: {}", memory::total())?;
+    for category in Category::ALL {
+        writeln!(writer, "{}: {}", category.as_atom(), category.bytes())?;
+    }
+
+    writeln!(writer, "=index_table:atom_tab")?;
+    match try_atom_count() {
+        Some(atom_count) => writeln!(writer, "entries: {}", atom_count)?,
+        None => writeln!(writer, "entries: locked")?,
+    }
+
+    for process in processes {
+        let pid = process.pid();
+        let state = match process.status() {
+            ProcessStatus::Running => "Running",
+            ProcessStatus::Runnable => "Scheduled",
+            ProcessStatus::Waiting => "Waiting",
+            ProcessStatus::Exiting | ProcessStatus::Errored(_) => "Exiting",
+        };
+
+        writeln!(writer, "=proc:<0.{}.{}>", pid.number(), pid.serial())?;
+        writeln!(writer, "State: {}", state)?;
+        writeln!(writer, "Spawned as: {}", process.mfa())?;
+        writeln!(writer, "Stack size: {}", process.stack().size)?;
+    }
+
+    writer.flush()
+}
+
+/// The file crash dumps are written to: `FIREFLY_CRASH_DUMP` if set, otherwise
+/// `DEFAULT_CRASH_DUMP`
+pub fn crash_dump_path() -> PathBuf {
+    env::var_os(CRASH_DUMP_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CRASH_DUMP))
+}
+
+/// Writes a crash dump with the given slogan and processes to the file at `crash_dump_path`,
+/// reporting where to stderr.
+///
+/// Only the first crash dump is written, so that a panic while the system goes down doesn't
+/// replace it with one that doesn't show the original cause.
+pub fn write_crash_dump_file(slogan: &str, processes: &[Arc<Process>]) {
+    if CRASH_DUMPED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let path = crash_dump_path();
+    let written = File::create(&path)
+        .and_then(|file| write_crash_dump(&mut BufWriter::new(file), slogan, processes));
+
+    match written {
+        Ok(()) => eprintln!("Crash dump was written to: {}", path.display()),
+        Err(err) => eprintln!(
+            "Crash dump could not be written to {}: {}",
+            path.display(),
+            err
+        ),
+    }
+}
+
+/// Installs a panic hook that writes a crash dump file of the processes on the panicking thread's
+/// scheduler, once the previous hook, by default the one printing the panic message, has
+/// reported the panic
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+        write_crash_dump_file(&info.to_string(), &scheduler::processes());
+    }));
+}
+
+/// Writes a crash dump for the `init` process exiting with `reason`, and halts, as the system
+/// can't run without init
+pub fn init_exited(init: &Arc<Process>, reason: Term) -> ! {
+    let mut rendered = BoundedString::default();
+    let _ = write!(rendered, "{}", reason);
+    let slogan = format!("Init terminating ({})", rendered.0);
+
+    let mut processes = vec![init.clone()];
+    processes.extend(scheduler::processes());
+    write_crash_dump_file(&slogan, &processes);
+
+    std::process::exit(1)
+}
+
+/// A string which stops the rendering of a term once it holds `SLOGAN_REASON_BYTES`, ending with
+/// `...` if the term was longer
+#[derive(Default)]
+struct BoundedString(String);
+impl fmt::Write for BoundedString {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // Already truncated
+        if SLOGAN_REASON_BYTES < self.0.len() {
+            return Err(fmt::Error);
+        }
+        let available = SLOGAN_REASON_BYTES - self.0.len();
+
+        if s.len() <= available {
+            self.0.push_str(s);
+
+            Ok(())
+        } else {
+            let fits = (0..=available)
+                .rev()
+                .find(|&index| s.is_char_boundary(index))
+                .unwrap();
+            self.0.push_str(&s[..fits]);
+            self.0.push_str("...");
+
+            Err(fmt::Error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use firefly_rt::process::ProcessId;
+
+    #[test]
+    fn crash_dump_includes_memory_atoms_and_processes() {
+        let process = Arc::new(Process::new(
+            None,
+            ProcessId::next(),
+            "init:start/0".parse().unwrap(),
+        ));
+        let pid = process.pid();
+
+        let mut dump = Vec::new();
+        write_crash_dump(&mut dump, "simulated crash", &[process]).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+
+        assert!(dump.starts_with("=erl_crash_dump:0.5\nSlogan: simulated crash\n"));
+        assert!(dump.contains("=memory\ntotal: "));
+        assert!(dump.contains("=index_table:atom_tab\nentries: "));
+        assert!(dump.contains(&format!(
+            "=proc:<0.{}.{}>\nState: Waiting\nSpawned as: init:start/0\n",
+            pid.number(),
+            pid.serial()
+        )));
+    }
+
+    #[test]
+    fn bounded_string_stops_rendering_once_full() {
+        let mut bounded = BoundedString::default();
+        let long = "a".repeat(SLOGAN_REASON_BYTES + 1);
+
+        assert!(write!(bounded, "{}", long).is_err());
+        assert_eq!(bounded.0.len(), SLOGAN_REASON_BYTES + "...".len());
+        assert!(bounded.0.ends_with("a..."));
+    }
+}
@@ -1 +1,2 @@
 pub mod break_handler;
+pub mod dump;
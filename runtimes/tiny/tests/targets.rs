@@ -0,0 +1,131 @@
+//! Checks that the runtime builds for each of the targets it supports, not just the host
+//!
+//! Targets whose standard library is not installed (see `rustup target add`) are skipped. The
+//! `cross_run_*` tests are ignored by default, as they also require `qemu-aarch64`/`qemu-riscv64`
+//! and a cross linker on the path; run them with `cargo test -p firefly_rt_tiny -- --ignored`.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PACKAGE: &str = env!("CARGO_PKG_NAME");
+
+fn cargo() -> Command {
+    let mut cmd = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR"));
+    cmd
+}
+
+/// Returns a target directory separate from the one running this test, so that the nested cargo
+/// invocation doesn't block on the build directory lock
+fn target_dir(target: &str) -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(target)
+}
+
+fn is_target_installed(target: &str) -> bool {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let Ok(output) = Command::new(rustc).args(["--print", "sysroot"]).output() else { return false; };
+    let sysroot = String::from_utf8_lossy(&output.stdout);
+    Path::new(sysroot.trim())
+        .join("lib/rustlib")
+        .join(target)
+        .join("lib")
+        .is_dir()
+}
+
+fn is_on_path(program: &str) -> bool {
+    Command::new(program).arg("--version").output().is_ok()
+}
+
+fn check(target: &str) {
+    if !is_target_installed(target) {
+        eprintln!("skipping {}: its standard library is not installed", target);
+        return;
+    }
+
+    let status = cargo()
+        .args(["check", "-p", PACKAGE, "--target", target, "--target-dir"])
+        .arg(target_dir(target))
+        .status()
+        .unwrap();
+    assert!(status.success(), "cargo check failed for {}", target);
+}
+
+fn cross_run(target: &str, qemu: &str, linker: &str) {
+    if !is_target_installed(target) || !is_on_path(qemu) || !is_on_path(linker) {
+        eprintln!(
+            "skipping {}: requires its standard library, {} and {}",
+            target, qemu, linker
+        );
+        return;
+    }
+
+    let triple = target.to_uppercase().replace('-', "_");
+    // The cross toolchain's sysroot, e.g. /usr/aarch64-linux-gnu, holds the dynamic loader
+    let sysroot = Path::new("/usr").join(linker.trim_end_matches("-gcc"));
+    let status = cargo()
+        .args([
+            "test",
+            "-p",
+            PACKAGE,
+            "--lib",
+            "--target",
+            target,
+            "--target-dir",
+        ])
+        .arg(target_dir(target))
+        .env(format!("CARGO_TARGET_{}_LINKER", triple), linker)
+        .env(format!("CARGO_TARGET_{}_RUNNER", triple), qemu)
+        .env("QEMU_LD_PREFIX", sysroot)
+        .status()
+        .unwrap();
+    assert!(
+        status.success(),
+        "cargo test failed for {} under {}",
+        target,
+        qemu
+    );
+}
+
+#[test]
+fn check_x86_64_linux() {
+    check("x86_64-unknown-linux-gnu");
+}
+
+#[test]
+fn check_aarch64_linux() {
+    check("aarch64-unknown-linux-gnu");
+}
+
+#[test]
+fn check_riscv64_linux() {
+    check("riscv64gc-unknown-linux-gnu");
+}
+
+#[test]
+fn check_x86_64_macos() {
+    check("x86_64-apple-darwin");
+}
+
+#[test]
+fn check_aarch64_macos() {
+    check("aarch64-apple-darwin");
+}
+
+#[test]
+#[ignore]
+fn cross_run_aarch64_linux() {
+    cross_run(
+        "aarch64-unknown-linux-gnu",
+        "qemu-aarch64",
+        "aarch64-linux-gnu-gcc",
+    );
+}
+
+#[test]
+#[ignore]
+fn cross_run_riscv64_linux() {
+    cross_run(
+        "riscv64gc-unknown-linux-gnu",
+        "qemu-riscv64",
+        "riscv64-linux-gnu-gcc",
+    );
+}
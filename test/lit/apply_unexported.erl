@@ -0,0 +1,30 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% CHECK: {apply_exported, public}
+%% CHECK: {apply_unexported, undef}
+%% CHECK: {remote_unexported, undef}
+%% CHECK: {local_unexported, private}
+-module(init).
+
+-export([boot/1, public/0]).
+
+-import(erlang, [display/1]).
+
+boot(_) ->
+  display({apply_exported, apply(init, public, [])}),
+  display({apply_unexported, undef_or_result(fun () -> apply(init, private, []) end)}),
+  display({remote_unexported, undef_or_result(fun () -> init:private() end)}),
+  display({local_unexported, private()}).
+
+undef_or_result(Fun) ->
+  try
+    Fun()
+  catch
+    error:undef -> undef
+  end.
+
+public() ->
+  public.
+
+private() ->
+  private.
@@ -0,0 +1,38 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% CHECK: {exported, true}
+%% CHECK: {not_exported, false}
+%% CHECK: {unknown_module, false}
+%% CHECK: {builtin, true}
+%% CHECK: {not_builtin, false}
+%% CHECK: {negative_arity, badarg}
+%% CHECK: {big_arity, badarg}
+%% CHECK: {private, private}
+-module(init).
+
+-export([boot/1, public/0]).
+
+-import(erlang, [display/1]).
+
+boot(_) ->
+  display({exported, erlang:function_exported(init, public, 0)}),
+  display({not_exported, erlang:function_exported(init, private, 0)}),
+  display({unknown_module, erlang:function_exported(no_such_module, public, 0)}),
+  display({builtin, erlang:is_builtin(erlang, length, 1)}),
+  display({not_builtin, erlang:is_builtin(init, public, 0)}),
+  display({negative_arity, badarg_or_result(fun () -> erlang:function_exported(init, public, -1) end)}),
+  display({big_arity, badarg_or_result(fun () -> erlang:is_builtin(erlang, length, 1 bsl 64) end)}),
+  display({private, private()}).
+
+badarg_or_result(Fun) ->
+  try
+    Fun()
+  catch
+    error:badarg -> badarg
+  end.
+
+public() ->
+  public.
+
+private() ->
+  private.
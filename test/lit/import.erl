@@ -0,0 +1,12 @@
+%% RUN: @firefly compile --emit=ssa --output-dir @tempfile @file && grep -c "call lists:map/2" @tempfile/import.ssa
+
+%% Unqualified calls to an imported function are lowered as remote calls to the module it is imported from
+%% CHECK: 1
+-module(init).
+
+-export([boot/1]).
+
+-import(lists, [map/2]).
+
+boot(Args) ->
+    erlang:display(map(fun (Arg) -> {Arg} end, Args)).
@@ -0,0 +1,13 @@
+%% RUN: @firefly compile -Z analyze_only @file 2>&1
+
+%% CHECK: import overrides auto-imported BIF
+%% CHECK: this import of length/1 overrides the auto-imported BIF erlang:length/1
+%% CHECK: use -compile({no_auto_import, [length/1]}) to resolve the name clash
+-module(init).
+
+-export([boot/1]).
+
+-import(mylists, [length/1]).
+
+boot(Args) ->
+    length(Args).
@@ -0,0 +1,11 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile; echo "exit status: $?"
+
+%% CHECK: stopping
+%% CHECK: exit status: 3
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    ok = init:stop(3),
+    erlang:display(stopping).
@@ -0,0 +1,10 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile 2>&1; echo "exit status: $?"
+
+%% CHECK: invalid configuration
+%% CHECK: exit status: 1
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    init:stop("invalid configuration").
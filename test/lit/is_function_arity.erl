@@ -0,0 +1,26 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% CHECK: {capture_2, true}
+%% CHECK: {capture_3, false}
+%% CHECK: {closure_2, true}
+%% CHECK: {closure_3, false}
+%% CHECK: {not_a_fun, false}
+-module(init).
+
+-export([boot/1]).
+
+boot(_Args) ->
+    Capture = fun (A, B) -> {A, B} end,
+    erlang:display({capture_2, has_arity_2(Capture)}),
+    erlang:display({capture_3, has_arity_3(Capture)}),
+    Msg = {ok, captured},
+    Closure = fun (A, B) -> {Msg, A, B} end,
+    erlang:display({closure_2, has_arity_2(Closure)}),
+    erlang:display({closure_3, has_arity_3(Closure)}),
+    erlang:display({not_a_fun, has_arity_2(not_a_fun)}).
+
+has_arity_2(Fun) when is_function(Fun, 2) -> true;
+has_arity_2(_) -> false.
+
+has_arity_3(Fun) when is_function(Fun, 3) -> true;
+has_arity_3(_) -> false.
@@ -0,0 +1,20 @@
+%% RUN: @firefly compile --emit=ssa --output-dir @tempfile @file && grep -c "fun.arity" @tempfile/is_function_arity_lowering.ssa && grep -c "call erlang:is_function/2" @tempfile/is_function_arity_lowering.ssa
+
+%% A literal arity is checked against the closure header, without calling the builtin,
+%% while a dynamic arity still calls erlang:is_function/2
+%% CHECK: 1
+%% CHECK: 1
+-module(init).
+
+-export([boot/1]).
+
+boot(Args) ->
+    Fun = fun (A, B) -> {A, B} end,
+    erlang:display(literal_arity(Fun)),
+    erlang:display(dynamic_arity(Fun, length(Args))).
+
+literal_arity(Fun) when is_function(Fun, 2) -> true;
+literal_arity(_) -> false.
+
+dynamic_arity(Fun, Arity) when is_function(Fun, Arity) -> true;
+dynamic_arity(_, _) -> false.
@@ -0,0 +1,13 @@
+%% RUN: @firefly compile -o @tempfile @file && @tempfile
+
+%% A local function shadows the auto-imported BIF of the same name
+%% CHECK: {size, 3}
+-module(init).
+
+-export([boot/1]).
+
+boot(_) ->
+    erlang:display({size, size({a, b})}).
+
+size(Tuple) ->
+    tuple_size(Tuple) + 1.